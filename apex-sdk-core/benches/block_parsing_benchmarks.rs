@@ -0,0 +1,123 @@
+use apex_sdk_core::{BlockInfo, RawBlockInfo};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn tx_hashes(count: usize) -> Vec<[u8; 32]> {
+    (0..count)
+        .map(|i| {
+            let mut hash = [0u8; 32];
+            hash[..8].copy_from_slice(&(i as u64).to_le_bytes());
+            hash
+        })
+        .collect()
+}
+
+/// Mirrors the allocation pattern of the string-based parsing path
+fn parse_owned(hash: &[u8; 32], parent: &[u8; 32], txs: &[[u8; 32]]) -> BlockInfo {
+    BlockInfo {
+        number: 12345678,
+        hash: format!("0x{}", hex::encode(hash)),
+        parent_hash: format!("0x{}", hex::encode(parent)),
+        timestamp: 1704067200,
+        transactions: txs
+            .iter()
+            .map(|tx| format!("0x{}", hex::encode(tx)))
+            .collect(),
+        state_root: Some(format!("0x{}", hex::encode(hash))),
+        extrinsics_root: Some(format!("0x{}", hex::encode(parent))),
+        extrinsic_count: txs.len() as u32,
+        event_count: Some(30),
+        is_finalized: true,
+    }
+}
+
+fn parse_raw(hash: &[u8; 32], parent: &[u8; 32], txs: &[[u8; 32]]) -> RawBlockInfo {
+    RawBlockInfo {
+        number: 12345678,
+        hash: *hash,
+        parent_hash: *parent,
+        timestamp: 1704067200,
+        transactions: txs.to_vec(),
+        state_root: Some(*hash),
+        extrinsics_root: Some(*parent),
+        event_count: Some(30),
+        is_finalized: true,
+    }
+}
+
+// ============================================================================
+// Block Parsing Benchmarks
+// ============================================================================
+
+fn benchmark_block_parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("block_parsing");
+    let hash = [0xab; 32];
+    let parent = [0xcd; 32];
+
+    for tx_count in [0usize, 10, 100, 1000] {
+        let txs = tx_hashes(tx_count);
+
+        group.bench_with_input(
+            BenchmarkId::new("owned_strings", tx_count),
+            &txs,
+            |b, txs| b.iter(|| black_box(parse_owned(&hash, &parent, txs))),
+        );
+
+        group.bench_with_input(BenchmarkId::new("raw_bytes", tx_count), &txs, |b, txs| {
+            b.iter(|| black_box(parse_raw(&hash, &parent, txs)))
+        });
+    }
+
+    group.finish();
+}
+
+fn benchmark_transaction_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("transaction_lookup");
+    let txs = tx_hashes(1000);
+    let target = txs[999];
+
+    let owned = parse_owned(&[0xab; 32], &[0xcd; 32], &txs);
+    let target_hex = format!("0x{}", hex::encode(target));
+    group.bench_function("owned_strings", |b| {
+        b.iter(|| black_box(owned.transactions.contains(&target_hex)))
+    });
+
+    let raw = parse_raw(&[0xab; 32], &[0xcd; 32], &txs);
+    group.bench_function("block_ref", |b| {
+        b.iter(|| black_box(raw.as_block_ref().contains_transaction(&target)))
+    });
+
+    group.finish();
+}
+
+fn benchmark_lazy_hex(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lazy_hex");
+    let raw = parse_raw(&[0xab; 32], &[0xcd; 32], &tx_hashes(100));
+
+    group.bench_function("hash_hex_only", |b| {
+        b.iter(|| black_box(raw.as_block_ref().hash_hex()))
+    });
+
+    group.bench_function("full_conversion", |b| {
+        b.iter(|| black_box(raw.to_block_info()))
+    });
+
+    group.finish();
+}
+
+// ============================================================================
+// Benchmark Groups
+// ============================================================================
+
+criterion_group!(
+    benches,
+    benchmark_block_parsing,
+    benchmark_transaction_lookup,
+    benchmark_lazy_hex,
+);
+
+criterion_main!(benches);
@@ -0,0 +1,469 @@
+//! # Amounts
+//!
+//! Chains store amounts as integers in their smallest unit (planck on
+//! Substrate, wei on EVM chains), while users think in tokens with a fixed
+//! number of decimals. Mixing the two is the most common source of
+//! off-by-10^n bugs, so they are kept apart in the type system:
+//!
+//! - [`Balance`] is a raw on-chain amount. It only offers checked and
+//!   saturating arithmetic; there are no panicking or wrapping operators.
+//! - [`DisplayAmount`] is a formatted view for humans. It has no arithmetic.
+//! - [`Denomination`] converts between them: [`Denomination::parse`] turns
+//!   user input into a [`Balance`] and [`Balance::to_display`] goes back.
+//!
+//! Percentages use [`Perbill`], the parts-per-billion type runtimes use for
+//! commissions, slashes and inflation.
+//!
+//! ```rust
+//! use apex_sdk_core::balance::{Balance, Denomination, Perbill};
+//!
+//! let dot = Denomination::new("DOT", 10);
+//! let amount = dot.parse("1.5").unwrap();
+//! assert_eq!(amount, Balance::from_planck(15_000_000_000));
+//!
+//! let fee = amount.mul_perbill(Perbill::from_percent(2));
+//! let total = amount.checked_add(fee).unwrap();
+//! assert_eq!(total.to_display(&dot).to_string(), "1.53 DOT");
+//!
+//! assert!(Balance::MAX.checked_add(Balance::from_planck(1)).is_none());
+//! ```
+
+use crate::SdkError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::iter::Sum;
+use thiserror::Error;
+
+/// Largest number of decimals whose unit still fits in a `u128`
+pub const MAX_DECIMALS: u8 = 38;
+
+/// Errors raised by amount arithmetic and parsing
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AmountError {
+    #[error("Amount overflow")]
+    Overflow,
+    #[error("Amount underflow")]
+    Underflow,
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("Invalid amount: {0}")]
+    Invalid(String),
+    #[error("Amount has {found} decimals, {symbol} supports {max}")]
+    TooManyDecimals {
+        symbol: String,
+        found: usize,
+        max: u8,
+    },
+}
+
+impl From<AmountError> for SdkError {
+    fn from(err: AmountError) -> Self {
+        SdkError::TransactionError(err.to_string())
+    }
+}
+
+/// An amount in the chain's smallest unit (planck, wei)
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Balance(u128);
+
+impl Balance {
+    /// Zero
+    pub const ZERO: Balance = Balance(0);
+    /// Largest representable amount
+    pub const MAX: Balance = Balance(u128::MAX);
+
+    /// Amount from raw units
+    pub const fn from_planck(planck: u128) -> Self {
+        Balance(planck)
+    }
+
+    /// Raw units
+    pub const fn planck(self) -> u128 {
+        self.0
+    }
+
+    /// Check whether the amount is zero
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Sum, or `None` on overflow
+    pub fn checked_add(self, other: Balance) -> Option<Balance> {
+        self.0.checked_add(other.0).map(Balance)
+    }
+
+    /// Difference, or `None` if `other` is larger
+    pub fn checked_sub(self, other: Balance) -> Option<Balance> {
+        self.0.checked_sub(other.0).map(Balance)
+    }
+
+    /// Product with a scalar, or `None` on overflow
+    pub fn checked_mul(self, factor: u128) -> Option<Balance> {
+        self.0.checked_mul(factor).map(Balance)
+    }
+
+    /// Quotient by a scalar, rounding down, or `None` for a zero divisor
+    pub fn checked_div(self, divisor: u128) -> Option<Balance> {
+        self.0.checked_div(divisor).map(Balance)
+    }
+
+    /// Sum, clamped at [`Balance::MAX`]
+    pub fn saturating_add(self, other: Balance) -> Balance {
+        Balance(self.0.saturating_add(other.0))
+    }
+
+    /// Difference, clamped at zero
+    pub fn saturating_sub(self, other: Balance) -> Balance {
+        Balance(self.0.saturating_sub(other.0))
+    }
+
+    /// Product with a scalar, clamped at [`Balance::MAX`]
+    pub fn saturating_mul(self, factor: u128) -> Balance {
+        Balance(self.0.saturating_mul(factor))
+    }
+
+    /// Sum, failing with [`AmountError::Overflow`]
+    pub fn try_add(self, other: Balance) -> Result<Balance, AmountError> {
+        self.checked_add(other).ok_or(AmountError::Overflow)
+    }
+
+    /// Difference, failing with [`AmountError::Underflow`]
+    pub fn try_sub(self, other: Balance) -> Result<Balance, AmountError> {
+        self.checked_sub(other).ok_or(AmountError::Underflow)
+    }
+
+    /// Product with a scalar, failing with [`AmountError::Overflow`]
+    pub fn try_mul(self, factor: u128) -> Result<Balance, AmountError> {
+        self.checked_mul(factor).ok_or(AmountError::Overflow)
+    }
+
+    /// Quotient by a scalar, failing with [`AmountError::DivisionByZero`]
+    pub fn try_div(self, divisor: u128) -> Result<Balance, AmountError> {
+        self.checked_div(divisor).ok_or(AmountError::DivisionByZero)
+    }
+
+    /// Sum of all amounts, or `None` on overflow
+    pub fn checked_sum<I: IntoIterator<Item = Balance>>(amounts: I) -> Option<Balance> {
+        amounts
+            .into_iter()
+            .try_fold(Balance::ZERO, Balance::checked_add)
+    }
+
+    /// Fraction of this amount, rounding down
+    pub fn mul_perbill(self, ratio: Perbill) -> Balance {
+        ratio.mul_floor(self)
+    }
+
+    /// Human-readable view in `denomination`
+    pub fn to_display(self, denomination: &Denomination) -> DisplayAmount {
+        DisplayAmount {
+            amount: self,
+            decimals: denomination.decimals,
+            symbol: denomination.symbol.clone(),
+        }
+    }
+}
+
+/// Saturates at [`Balance::MAX`]; use [`Balance::checked_sum`] to detect overflow
+impl Sum for Balance {
+    fn sum<I: Iterator<Item = Balance>>(iter: I) -> Self {
+        iter.fold(Balance::ZERO, Balance::saturating_add)
+    }
+}
+
+impl fmt::Display for Balance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Parts per billion, clamped to `0..=100%`
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Perbill(u32);
+
+impl Perbill {
+    /// Parts making up 100%
+    pub const ACCURACY: u32 = 1_000_000_000;
+
+    /// 0%
+    pub const fn zero() -> Self {
+        Perbill(0)
+    }
+
+    /// 100%
+    pub const fn one() -> Self {
+        Perbill(Self::ACCURACY)
+    }
+
+    /// Ratio from parts per billion, clamped to 100%
+    pub fn from_parts(parts: u32) -> Self {
+        Perbill(parts.min(Self::ACCURACY))
+    }
+
+    /// Ratio from a whole percentage, clamped to 100%
+    pub fn from_percent(percent: u32) -> Self {
+        Perbill(percent.min(100) * (Self::ACCURACY / 100))
+    }
+
+    /// Ratio `numerator / denominator`, rounding down and clamped to 100%
+    ///
+    /// A zero denominator yields 100%, as in the runtime.
+    pub fn from_rational(numerator: u128, denominator: u128) -> Self {
+        if denominator == 0 || numerator >= denominator {
+            return Self::one();
+        }
+        let (mut numerator, mut denominator) = (numerator, denominator);
+        // drop low bits until the scaled numerator fits; precision stays far below one part
+        while numerator.checked_mul(Self::ACCURACY as u128).is_none() {
+            numerator >>= 1;
+            denominator >>= 1;
+        }
+        Perbill((numerator * Self::ACCURACY as u128 / denominator) as u32)
+    }
+
+    /// Parts per billion
+    pub fn deconstruct(self) -> u32 {
+        self.0
+    }
+
+    /// This fraction of `amount`, rounding down
+    pub fn mul_floor(self, amount: Balance) -> Balance {
+        Balance(mul_div_floor(
+            amount.0,
+            self.0 as u128,
+            Self::ACCURACY as u128,
+        ))
+    }
+
+    /// This fraction of `amount`, rounding up
+    pub fn mul_ceil(self, amount: Balance) -> Balance {
+        let accuracy = Self::ACCURACY as u128;
+        let inexact = !((amount.0 % accuracy) * self.0 as u128).is_multiple_of(accuracy);
+        Balance(self.mul_floor(amount).0 + inexact as u128)
+    }
+
+    /// Complement, `100% - self`
+    pub fn left_from_one(self) -> Self {
+        Perbill(Self::ACCURACY - self.0)
+    }
+}
+
+impl fmt::Display for Perbill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let amount = DisplayAmount {
+            amount: Balance(self.0 as u128),
+            decimals: 7,
+            symbol: String::new(),
+        };
+        write!(f, "{}%", amount.value())
+    }
+}
+
+/// `value * numerator / denominator` without intermediate overflow
+///
+/// `numerator` must not exceed `denominator`.
+fn mul_div_floor(value: u128, numerator: u128, denominator: u128) -> u128 {
+    let quotient = value / denominator;
+    let remainder = value % denominator;
+    quotient * numerator + remainder * numerator / denominator
+}
+
+/// Symbol and decimals of a token
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Denomination {
+    /// Ticker symbol, e.g. `DOT`
+    pub symbol: String,
+    /// Number of decimals in one token
+    pub decimals: u8,
+}
+
+impl Denomination {
+    /// Token with the given symbol and decimals
+    pub fn new(symbol: impl Into<String>, decimals: u8) -> Self {
+        Self {
+            symbol: symbol.into(),
+            decimals: decimals.min(MAX_DECIMALS),
+        }
+    }
+
+    /// One whole token in raw units
+    pub fn unit(&self) -> Balance {
+        Balance(10u128.pow(self.decimals as u32))
+    }
+
+    /// Amount of whole tokens, failing with [`AmountError::Overflow`]
+    pub fn tokens(&self, whole: u128) -> Result<Balance, AmountError> {
+        self.unit().try_mul(whole)
+    }
+
+    /// Parse a decimal string such as `"1.25"` into raw units
+    ///
+    /// Underscores are accepted as digit separators. Input with more
+    /// fractional digits than the token has is rejected rather than rounded.
+    pub fn parse(&self, input: &str) -> Result<Balance, AmountError> {
+        let cleaned: String = input.trim().chars().filter(|c| *c != '_').collect();
+        let (whole, fraction) = cleaned.split_once('.').unwrap_or((&cleaned, ""));
+
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+            return Err(AmountError::Invalid(input.to_string()));
+        }
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > self.decimals as usize {
+            return Err(AmountError::TooManyDecimals {
+                symbol: self.symbol.clone(),
+                found: fraction.len(),
+                max: self.decimals,
+            });
+        }
+
+        let whole = if whole.is_empty() {
+            0
+        } else {
+            whole.parse::<u128>().map_err(|_| AmountError::Overflow)?
+        };
+        let fraction = if fraction.is_empty() {
+            0
+        } else {
+            let scale = 10u128.pow((self.decimals as usize - fraction.len()) as u32);
+            fraction
+                .parse::<u128>()
+                .map_err(|_| AmountError::Overflow)?
+                * scale
+        };
+
+        self.tokens(whole)?.try_add(Balance(fraction))
+    }
+}
+
+/// An amount formatted in whole tokens, for display only
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayAmount {
+    amount: Balance,
+    decimals: u8,
+    symbol: String,
+}
+
+impl DisplayAmount {
+    /// Underlying raw amount
+    pub fn amount(&self) -> Balance {
+        self.amount
+    }
+
+    /// Token symbol
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Exact decimal value without the symbol, trailing zeros removed
+    pub fn value(&self) -> String {
+        self.with_precision(self.decimals)
+    }
+
+    /// Decimal value truncated to at most `precision` fractional digits
+    pub fn with_precision(&self, precision: u8) -> String {
+        let unit = 10u128.pow(self.decimals as u32);
+        let whole = self.amount.0 / unit;
+        let fraction = format!(
+            "{:0width$}",
+            self.amount.0 % unit,
+            width = self.decimals as usize
+        );
+        let fraction = fraction[..precision.min(self.decimals) as usize].trim_end_matches('0');
+        if fraction.is_empty() {
+            whole.to_string()
+        } else {
+            format!("{}.{}", whole, fraction)
+        }
+    }
+}
+
+impl fmt::Display for DisplayAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(precision) => write!(f, "{}", self.with_precision(precision as u8))?,
+            None => write!(f, "{}", self.value())?,
+        }
+        if !self.symbol.is_empty() {
+            write!(f, " {}", self.symbol)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let one = Balance::from_planck(1);
+        assert_eq!(Balance::MAX.checked_add(one), None);
+        assert_eq!(Balance::ZERO.checked_sub(one), None);
+        assert_eq!(one.checked_div(0), None);
+        assert_eq!(Balance::MAX.saturating_add(one), Balance::MAX);
+        assert_eq!(Balance::ZERO.saturating_sub(one), Balance::ZERO);
+        assert_eq!(Balance::ZERO.try_sub(one), Err(AmountError::Underflow));
+        assert_eq!(Balance::checked_sum([Balance::MAX, one]), None);
+        assert_eq!(
+            [one, one].into_iter().sum::<Balance>(),
+            Balance::from_planck(2)
+        );
+    }
+
+    #[test]
+    fn test_perbill() {
+        let amount = Balance::from_planck(1_000);
+        assert_eq!(Perbill::from_percent(150), Perbill::one());
+        assert_eq!(Perbill::from_percent(5).mul_floor(amount).planck(), 50);
+        assert_eq!(Perbill::from_rational(1, 3).deconstruct(), 333_333_333);
+        assert_eq!(Perbill::from_rational(1, 3).mul_floor(amount).planck(), 333);
+        assert_eq!(Perbill::from_rational(1, 3).mul_ceil(amount).planck(), 334);
+        assert_eq!(Perbill::from_percent(5).mul_ceil(amount).planck(), 50);
+        assert_eq!(Perbill::from_rational(5, 0), Perbill::one());
+        assert_eq!(
+            Perbill::from_percent(30).left_from_one(),
+            Perbill::from_percent(70)
+        );
+        assert_eq!(Perbill::from_parts(125_000_000).to_string(), "12.5%");
+
+        // no intermediate overflow near the top of the range
+        assert_eq!(
+            Perbill::from_percent(50).mul_floor(Balance::MAX).planck(),
+            u128::MAX / 2
+        );
+        assert_eq!(Perbill::one().mul_ceil(Balance::MAX), Balance::MAX);
+        let half = Perbill::from_rational(u128::MAX / 2, u128::MAX).deconstruct();
+        assert!((499_999_999..=500_000_000).contains(&half));
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let dot = Denomination::new("DOT", 10);
+        assert_eq!(dot.parse("1").unwrap().planck(), 10_000_000_000);
+        assert_eq!(dot.parse("0.0000000001").unwrap().planck(), 1);
+        assert_eq!(dot.parse(".5").unwrap().planck(), 5_000_000_000);
+        assert_eq!(dot.parse("1_000.50").unwrap().planck(), 10_005_000_000_000);
+        assert!(matches!(
+            dot.parse("0.00000000001"),
+            Err(AmountError::TooManyDecimals { found: 11, .. })
+        ));
+        assert!(matches!(dot.parse("-1"), Err(AmountError::Invalid(_))));
+        assert!(matches!(dot.parse("."), Err(AmountError::Invalid(_))));
+        assert_eq!(
+            Denomination::new("ETH", 18).parse("1000000000000000000000"),
+            Err(AmountError::Overflow)
+        );
+
+        let amount = Balance::from_planck(12_345_678_900);
+        assert_eq!(amount.to_display(&dot).to_string(), "1.23456789 DOT");
+        assert_eq!(format!("{:.2}", amount.to_display(&dot)), "1.23 DOT");
+        assert_eq!(dot.unit().to_display(&dot).value(), "1");
+    }
+}
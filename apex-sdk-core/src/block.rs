@@ -0,0 +1,341 @@
+//! # Zero-Copy Block Data
+//!
+//! Byte-backed block representations for high-throughput paths such as backfills.
+//!
+//! [`BlockInfo`] stores every hash as an owned `0x`-prefixed hex `String`, which costs
+//! one allocation per hash and per transaction. [`RawBlockInfo`] keeps the same data
+//! as fixed-size byte arrays, and [`BlockInfoRef`] is a borrowed view over it that
+//! only hex-encodes a hash when it is actually asked for.
+//!
+//! Block numbers are always carried as `u64`. Chains store them as `u32` or
+//! `u64` depending on their runtime; [`BlockNumberWidth`] converts to and from
+//! the on-chain width and reports numbers that do not fit instead of
+//! truncating them.
+
+use crate::{BlockInfo, SdkError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const HEX_CHARS: &[u8; 16] = b"0123456789abcdef";
+
+/// Hex-encode bytes with a `0x` prefix using a single allocation
+pub fn hex_prefixed(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 + bytes.len() * 2);
+    out.push_str("0x");
+    for byte in bytes {
+        out.push(HEX_CHARS[(byte >> 4) as usize] as char);
+        out.push(HEX_CHARS[(byte & 0x0f) as usize] as char);
+    }
+    out
+}
+
+/// Errors converting block numbers between widths and representations
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BlockNumberError {
+    #[error("Block number {number} does not fit in {width}")]
+    Overflow {
+        number: u64,
+        width: BlockNumberWidth,
+    },
+    #[error("Expected a {expected}-byte block number, found {found} bytes")]
+    Length { expected: usize, found: usize },
+    #[error("Expected a 4-byte (u32) or 8-byte (u64) block number, found {found} bytes")]
+    Width { found: usize },
+    #[error("Invalid block number: {0}")]
+    Invalid(String),
+}
+
+impl From<BlockNumberError> for SdkError {
+    fn from(err: BlockNumberError) -> Self {
+        SdkError::ProviderError(err.to_string())
+    }
+}
+
+/// Integer width of a runtime's `BlockNumber`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BlockNumberWidth {
+    /// `u32`, used by Polkadot, Kusama and most parachains
+    #[default]
+    U32,
+    /// `u64`
+    U64,
+}
+
+impl BlockNumberWidth {
+    /// Width of a SCALE-encoded block number of `len` bytes
+    pub fn from_encoded_len(len: usize) -> Result<Self, BlockNumberError> {
+        match len {
+            4 => Ok(Self::U32),
+            8 => Ok(Self::U64),
+            found => Err(BlockNumberError::Width { found }),
+        }
+    }
+
+    /// Encoded size in bytes
+    pub fn bytes(&self) -> usize {
+        match self {
+            Self::U32 => 4,
+            Self::U64 => 8,
+        }
+    }
+
+    /// Largest representable block number
+    pub fn max(&self) -> u64 {
+        match self {
+            Self::U32 => u32::MAX as u64,
+            Self::U64 => u64::MAX,
+        }
+    }
+
+    /// Check that `number` fits this width
+    pub fn check(&self, number: u64) -> Result<u64, BlockNumberError> {
+        if number > self.max() {
+            return Err(BlockNumberError::Overflow {
+                number,
+                width: *self,
+            });
+        }
+        Ok(number)
+    }
+
+    /// SCALE (little-endian) encoding of `number`
+    pub fn encode(&self, number: u64) -> Result<Vec<u8>, BlockNumberError> {
+        let number = self.check(number)?;
+        Ok(number.to_le_bytes()[..self.bytes()].to_vec())
+    }
+
+    /// Decode a block number from the front of `input`, advancing it
+    pub fn decode(&self, input: &mut &[u8]) -> Result<u64, BlockNumberError> {
+        if input.len() < self.bytes() {
+            return Err(BlockNumberError::Length {
+                expected: self.bytes(),
+                found: input.len(),
+            });
+        }
+        let (number, rest) = input.split_at(self.bytes());
+        let mut bytes = [0u8; 8];
+        bytes[..number.len()].copy_from_slice(number);
+        *input = rest;
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+impl std::fmt::Display for BlockNumberWidth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::U32 => f.write_str("u32"),
+            Self::U64 => f.write_str("u64"),
+        }
+    }
+}
+
+/// `number` as a `u32`, for APIs that take 32-bit block numbers
+pub fn block_number_u32(number: u64) -> Result<u32, BlockNumberError> {
+    BlockNumberWidth::U32
+        .check(number)
+        .map(|number| number as u32)
+}
+
+/// Parse a block number given as `0x`-prefixed hex (as in RPC headers) or decimal
+pub fn parse_block_number(number: &str) -> Result<u64, BlockNumberError> {
+    let parsed = match number.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => number.parse(),
+    };
+    parsed.map_err(|e| BlockNumberError::Invalid(format!("{}: {}", number, e)))
+}
+
+/// Owned block data with hashes kept as raw bytes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawBlockInfo {
+    pub number: u64,
+    pub hash: [u8; 32],
+    pub parent_hash: [u8; 32],
+    pub timestamp: u64,
+    pub transactions: Vec<[u8; 32]>,
+    pub state_root: Option<[u8; 32]>,
+    pub extrinsics_root: Option<[u8; 32]>,
+    pub event_count: Option<u32>,
+    pub is_finalized: bool,
+}
+
+impl RawBlockInfo {
+    /// Borrow this block as a [`BlockInfoRef`]
+    pub fn as_block_ref(&self) -> BlockInfoRef<'_> {
+        BlockInfoRef {
+            number: self.number,
+            hash: &self.hash,
+            parent_hash: &self.parent_hash,
+            timestamp: self.timestamp,
+            transactions: &self.transactions,
+            state_root: self.state_root.as_ref(),
+            extrinsics_root: self.extrinsics_root.as_ref(),
+            event_count: self.event_count,
+            is_finalized: self.is_finalized,
+        }
+    }
+
+    /// Convert into the owned, hex-encoded [`BlockInfo`]
+    pub fn to_block_info(&self) -> BlockInfo {
+        self.as_block_ref().to_block_info()
+    }
+}
+
+impl From<RawBlockInfo> for BlockInfo {
+    fn from(raw: RawBlockInfo) -> Self {
+        raw.to_block_info()
+    }
+}
+
+impl From<&RawBlockInfo> for BlockInfo {
+    fn from(raw: &RawBlockInfo) -> Self {
+        raw.to_block_info()
+    }
+}
+
+/// Borrowed view over block data
+///
+/// Hash accessors encode lazily; comparisons such as
+/// [`contains_transaction`](Self::contains_transaction) never allocate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockInfoRef<'a> {
+    pub number: u64,
+    pub hash: &'a [u8; 32],
+    pub parent_hash: &'a [u8; 32],
+    pub timestamp: u64,
+    pub transactions: &'a [[u8; 32]],
+    pub state_root: Option<&'a [u8; 32]>,
+    pub extrinsics_root: Option<&'a [u8; 32]>,
+    pub event_count: Option<u32>,
+    pub is_finalized: bool,
+}
+
+impl<'a> BlockInfoRef<'a> {
+    /// Block hash as `0x`-prefixed hex
+    pub fn hash_hex(&self) -> String {
+        hex_prefixed(self.hash)
+    }
+
+    /// Parent hash as `0x`-prefixed hex
+    pub fn parent_hash_hex(&self) -> String {
+        hex_prefixed(self.parent_hash)
+    }
+
+    /// Number of extrinsics in the block
+    pub fn extrinsic_count(&self) -> u32 {
+        self.transactions.len() as u32
+    }
+
+    /// Iterate over transaction hashes, encoding each one on demand
+    pub fn transaction_hashes(&self) -> impl Iterator<Item = String> + 'a {
+        self.transactions.iter().map(|hash| hex_prefixed(hash))
+    }
+
+    /// Check whether the block contains a transaction without encoding any hashes
+    pub fn contains_transaction(&self, tx_hash: &[u8; 32]) -> bool {
+        self.transactions.iter().any(|hash| hash == tx_hash)
+    }
+
+    /// Materialize an owned [`BlockInfo`]
+    pub fn to_block_info(&self) -> BlockInfo {
+        BlockInfo {
+            number: self.number,
+            hash: self.hash_hex(),
+            parent_hash: self.parent_hash_hex(),
+            timestamp: self.timestamp,
+            transactions: self.transaction_hashes().collect(),
+            state_root: self.state_root.map(|root| hex_prefixed(root)),
+            extrinsics_root: self.extrinsics_root.map(|root| hex_prefixed(root)),
+            extrinsic_count: self.extrinsic_count(),
+            event_count: self.event_count,
+            is_finalized: self.is_finalized,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_raw() -> RawBlockInfo {
+        RawBlockInfo {
+            number: 100,
+            hash: [0xab; 32],
+            parent_hash: [0x01; 32],
+            timestamp: 1_700_000_000,
+            transactions: vec![[0x11; 32], [0x22; 32]],
+            state_root: Some([0xcd; 32]),
+            extrinsics_root: None,
+            event_count: Some(4),
+            is_finalized: true,
+        }
+    }
+
+    #[test]
+    fn test_hex_prefixed_matches_hex_crate() {
+        let bytes = [0x00, 0x0f, 0xf0, 0xff, 0x7a];
+        assert_eq!(hex_prefixed(&bytes), format!("0x{}", hex::encode(bytes)));
+        assert_eq!(hex_prefixed(&[]), "0x");
+    }
+
+    #[test]
+    fn test_block_ref_lazy_accessors() {
+        let raw = sample_raw();
+        let block = raw.as_block_ref();
+
+        assert_eq!(block.hash_hex(), format!("0x{}", "ab".repeat(32)));
+        assert_eq!(block.extrinsic_count(), 2);
+        assert!(block.contains_transaction(&[0x22; 32]));
+        assert!(!block.contains_transaction(&[0x33; 32]));
+    }
+
+    #[test]
+    fn test_block_number_widths() {
+        let beyond_u32 = u32::MAX as u64 + 1;
+        assert_eq!(
+            BlockNumberWidth::U32.encode(beyond_u32),
+            Err(BlockNumberError::Overflow {
+                number: beyond_u32,
+                width: BlockNumberWidth::U32
+            })
+        );
+        assert!(block_number_u32(beyond_u32).is_err());
+        assert_eq!(block_number_u32(7).unwrap(), 7);
+
+        for (width, number) in [
+            (BlockNumberWidth::U32, u32::MAX as u64),
+            (BlockNumberWidth::U64, beyond_u32),
+        ] {
+            let encoded = width.encode(number).unwrap();
+            assert_eq!(BlockNumberWidth::from_encoded_len(encoded.len()), Ok(width));
+            let mut input = &encoded[..];
+            assert_eq!(width.decode(&mut input), Ok(number));
+            assert!(input.is_empty());
+        }
+        assert!(BlockNumberWidth::U64
+            .decode(&mut &[1u8, 0, 0, 0][..])
+            .is_err());
+        assert_eq!(
+            BlockNumberWidth::from_encoded_len(2),
+            Err(BlockNumberError::Width { found: 2 })
+        );
+
+        assert_eq!(parse_block_number("0x1a"), Ok(26));
+        assert_eq!(parse_block_number("26"), Ok(26));
+        assert_eq!(parse_block_number("0x100000000"), Ok(beyond_u32));
+        assert!(parse_block_number("0xzz").is_err());
+    }
+
+    #[test]
+    fn test_raw_block_converts_to_block_info() {
+        let info: BlockInfo = sample_raw().into();
+
+        assert_eq!(info.number, 100);
+        assert_eq!(info.parent_hash, format!("0x{}", "01".repeat(32)));
+        assert_eq!(info.transactions[1], format!("0x{}", "22".repeat(32)));
+        assert_eq!(info.state_root, Some(format!("0x{}", "cd".repeat(32))));
+        assert_eq!(info.extrinsics_root, None);
+        assert_eq!(info.extrinsic_count, 2);
+        assert!(info.is_finalized);
+    }
+}
@@ -0,0 +1,276 @@
+//! Subscription checkpoints
+//!
+//! A [`Checkpoint`] records the last finalized block a subscription has fully
+//! processed. Subscriptions save it through a pluggable [`CheckpointStore`] and,
+//! after a restart, resume from the block that follows it: the gap up to the
+//! current finalized head is backfilled before switching to live blocks.
+//!
+//! Two stores are provided:
+//! - [`MemoryCheckpointStore`] for tests and short-lived processes
+//! - [`FileCheckpointStore`] which keeps one versioned JSON file per subscription
+//!
+//! ```rust
+//! use apex_sdk_core::checkpoint::{Checkpoint, CheckpointStore, MemoryCheckpointStore};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), apex_sdk_core::SdkError> {
+//! let store = MemoryCheckpointStore::new();
+//! store.save("transfers", &Checkpoint::new(100, "0x64")).await?;
+//!
+//! let checkpoint = store.load("transfers").await?.unwrap();
+//! assert_eq!(checkpoint.next_block(), 101);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::schema::{from_versioned_json, to_versioned_json};
+use crate::SdkError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Last block fully processed by a subscription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Block number
+    pub block_number: u64,
+    /// Block hash (hex)
+    pub block_hash: String,
+    /// Unix timestamp (seconds) at which the checkpoint was taken
+    pub updated_at: i64,
+}
+
+impl Checkpoint {
+    /// Create a checkpoint for a processed block
+    pub fn new(block_number: u64, block_hash: impl Into<String>) -> Self {
+        Self {
+            block_number,
+            block_hash: block_hash.into(),
+            updated_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    /// First block a resumed subscription has to process
+    pub fn next_block(&self) -> u64 {
+        self.block_number.saturating_add(1)
+    }
+}
+
+/// Blocks to backfill before following live blocks
+///
+/// Starts after the checkpoint if there is one, otherwise at `start`. Returns
+/// `None` when there is nothing between that point and `finalized`.
+pub fn backfill_range(
+    checkpoint: Option<&Checkpoint>,
+    start: u64,
+    finalized: u64,
+) -> Option<RangeInclusive<u64>> {
+    let from = checkpoint.map(Checkpoint::next_block).unwrap_or(start);
+    (from <= finalized).then_some(from..=finalized)
+}
+
+/// Persistent storage for subscription checkpoints
+///
+/// Subscriptions are identified by a caller-chosen id, so several subscriptions
+/// can share one store.
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    /// Load the checkpoint of a subscription, if one was saved
+    async fn load(&self, subscription_id: &str) -> Result<Option<Checkpoint>, SdkError>;
+
+    /// Save the checkpoint of a subscription, replacing the previous one
+    async fn save(&self, subscription_id: &str, checkpoint: &Checkpoint) -> Result<(), SdkError>;
+
+    /// Forget a subscription's checkpoint so it starts over
+    async fn clear(&self, subscription_id: &str) -> Result<(), SdkError>;
+}
+
+/// In-memory checkpoint store; checkpoints are lost when the process exits
+#[derive(Debug, Default)]
+pub struct MemoryCheckpointStore {
+    checkpoints: Mutex<HashMap<String, Checkpoint>>,
+}
+
+impl MemoryCheckpointStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for MemoryCheckpointStore {
+    async fn load(&self, subscription_id: &str) -> Result<Option<Checkpoint>, SdkError> {
+        Ok(self
+            .checkpoints
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(subscription_id)
+            .cloned())
+    }
+
+    async fn save(&self, subscription_id: &str, checkpoint: &Checkpoint) -> Result<(), SdkError> {
+        self.checkpoints
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(subscription_id.to_string(), checkpoint.clone());
+        Ok(())
+    }
+
+    async fn clear(&self, subscription_id: &str) -> Result<(), SdkError> {
+        self.checkpoints
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(subscription_id);
+        Ok(())
+    }
+}
+
+/// Checkpoint store keeping one JSON file per subscription in a directory
+///
+/// Files are replaced atomically (written to a temporary file, then renamed), so
+/// a crash while saving leaves the previous checkpoint intact.
+#[derive(Debug, Clone)]
+pub struct FileCheckpointStore {
+    dir: PathBuf,
+}
+
+impl FileCheckpointStore {
+    /// Create a store in `dir`; the directory is created on first save
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Directory holding the checkpoint files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, subscription_id: &str) -> PathBuf {
+        let name: String = subscription_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.checkpoint.json", name))
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for FileCheckpointStore {
+    async fn load(&self, subscription_id: &str) -> Result<Option<Checkpoint>, SdkError> {
+        let path = self.path(subscription_id);
+        match tokio::fs::read_to_string(&path).await {
+            Ok(json) => from_versioned_json(&json).map(Some).map_err(|e| {
+                SdkError::ConfigError(format!("Invalid checkpoint file {}: {}", path.display(), e))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(SdkError::ConfigError(format!(
+                "Failed to read checkpoint {}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    async fn save(&self, subscription_id: &str, checkpoint: &Checkpoint) -> Result<(), SdkError> {
+        let path = self.path(subscription_id);
+        let json = to_versioned_json(checkpoint)
+            .map_err(|e| SdkError::ConfigError(format!("Failed to encode checkpoint: {}", e)))?;
+
+        let write = async {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            let temp = path.with_extension("json.tmp");
+            tokio::fs::write(&temp, json).await?;
+            tokio::fs::rename(&temp, &path).await
+        };
+        write.await.map_err(|e| {
+            SdkError::ConfigError(format!(
+                "Failed to write checkpoint {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    async fn clear(&self, subscription_id: &str) -> Result<(), SdkError> {
+        let path = self.path(subscription_id);
+        match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(SdkError::ConfigError(
+                format!("Failed to remove checkpoint {}: {}", path.display(), e),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backfill_range() {
+        let checkpoint = Checkpoint::new(100, "0x64");
+
+        assert_eq!(backfill_range(Some(&checkpoint), 0, 105), Some(101..=105));
+        assert_eq!(backfill_range(Some(&checkpoint), 0, 100), None);
+        assert_eq!(backfill_range(None, 50, 52), Some(50..=52));
+        assert_eq!(backfill_range(None, 53, 52), None);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        let store = MemoryCheckpointStore::new();
+        assert_eq!(store.load("blocks").await.unwrap(), None);
+
+        store
+            .save("blocks", &Checkpoint::new(1, "0x01"))
+            .await
+            .unwrap();
+        store
+            .save("blocks", &Checkpoint::new(2, "0x02"))
+            .await
+            .unwrap();
+        assert_eq!(store.load("blocks").await.unwrap().unwrap().block_number, 2);
+        assert_eq!(store.load("events").await.unwrap(), None);
+
+        store.clear("blocks").await.unwrap();
+        assert_eq!(store.load("blocks").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_survives_reopen() {
+        let dir = std::env::temp_dir().join(format!(
+            "apex-checkpoints-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let checkpoint = Checkpoint::new(42, "0x2a");
+
+        FileCheckpointStore::new(&dir)
+            .save("polkadot/transfers", &checkpoint)
+            .await
+            .unwrap();
+
+        let reopened = FileCheckpointStore::new(&dir);
+        assert_eq!(
+            reopened.load("polkadot/transfers").await.unwrap(),
+            Some(checkpoint)
+        );
+        assert_eq!(reopened.load("other").await.unwrap(), None);
+
+        reopened.clear("polkadot/transfers").await.unwrap();
+        reopened.clear("polkadot/transfers").await.unwrap();
+        assert_eq!(reopened.load("polkadot/transfers").await.unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,472 @@
+//! GraphQL data source for indexer backends
+//!
+//! [`GraphQLDataSource`] answers [`EventHistory`] queries from a Subsquid or
+//! SubQuery GraphQL endpoint. Combine it with an RPC-backed source in a
+//! [`HybridHistory`](crate::history::HybridHistory) to serve old blocks from the
+//! indexer and blocks it has not processed yet from the node.
+//!
+//! Expected schemas:
+//! - [`IndexerDialect::Subsquid`]: `events` entities with `name`
+//!   (`"Pallet.Event"`), `indexInBlock`, `args`, `block { height hash }` and
+//!   `extrinsic { indexInBlock }`; progress from `squidStatus { height }`
+//! - [`IndexerDialect::SubQuery`]: dictionary-style `events` entities with
+//!   `id` (`"<height>-<index>"`), `blockHeight`, `module` and `event`; progress
+//!   from `_metadata { lastProcessedHeight }`. These carry no block hash or
+//!   event arguments, so the returned records have an empty hash and null fields.
+//!
+//! ```rust,no_run
+//! use apex_sdk_core::graphql::{GraphQLDataSource, IndexerDialect};
+//! use apex_sdk_core::history::{EventFilter, EventHistory};
+//!
+//! # async fn example() -> Result<(), apex_sdk_core::SdkError> {
+//! let indexer = GraphQLDataSource::new(
+//!     "https://polkadot.explorer.subsquid.io/graphql",
+//!     IndexerDialect::Subsquid,
+//! );
+//! let transfers = indexer
+//!     .events(&EventFilter::new(19_000_000, 19_100_000).with_pallet("Balances"))
+//!     .await?;
+//! println!("{} transfers", transfers.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::ClientConfig;
+use crate::history::{EventFilter, EventHistory};
+use crate::sink::EventRecord;
+use crate::SdkError;
+use async_trait::async_trait;
+use serde_json::{json, Value as JsonValue};
+use tracing::debug;
+
+/// Default number of events requested per page
+pub const DEFAULT_PAGE_SIZE: usize = 1000;
+
+/// GraphQL schema flavour of an indexer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexerDialect {
+    /// Subsquid (OpenReader) schema
+    Subsquid,
+    /// SubQuery (PostGraphile) schema
+    SubQuery,
+}
+
+impl IndexerDialect {
+    /// Query and variables fetching one page of events
+    pub fn events_query(
+        &self,
+        filter: &EventFilter,
+        first: usize,
+        offset: usize,
+    ) -> (String, JsonValue) {
+        match self {
+            IndexerDialect::Subsquid => {
+                let (name_filter, name) = match (&filter.pallet, &filter.event) {
+                    (Some(pallet), Some(event)) => {
+                        (", name_eq: $name", Some(format!("{}.{}", pallet, event)))
+                    }
+                    (Some(pallet), None) => {
+                        (", name_startsWith: $name", Some(format!("{}.", pallet)))
+                    }
+                    (None, Some(event)) => (", name_endsWith: $name", Some(format!(".{}", event))),
+                    (None, None) => ("", None),
+                };
+                let name_variable = if name.is_some() {
+                    ", $name: String!"
+                } else {
+                    ""
+                };
+                let query = format!(
+                    "query($from: Int!, $to: Int!, $limit: Int!, $offset: Int!{name_variable}) {{ \
+                     events(where: {{block: {{height_gte: $from, height_lte: $to}}{name_filter}}}, \
+                     orderBy: [block_height_ASC, indexInBlock_ASC], limit: $limit, offset: $offset) {{ \
+                     name indexInBlock args block {{ height hash }} extrinsic {{ indexInBlock }} }} }}"
+                );
+                let mut variables = json!({
+                    "from": filter.from_block,
+                    "to": filter.to_block,
+                    "limit": first,
+                    "offset": offset,
+                });
+                if let Some(name) = name {
+                    variables["name"] = json!(name);
+                }
+                (query, variables)
+            }
+            IndexerDialect::SubQuery => {
+                let mut conditions = vec![
+                    "blockHeight: {greaterThanOrEqualTo: $from, lessThanOrEqualTo: $to}"
+                        .to_string(),
+                ];
+                let mut declarations = String::new();
+                let mut variables = json!({
+                    "from": filter.from_block.to_string(),
+                    "to": filter.to_block.to_string(),
+                    "first": first,
+                    "offset": offset,
+                });
+                if let Some(pallet) = &filter.pallet {
+                    conditions.push("module: {equalToInsensitive: $module}".to_string());
+                    declarations.push_str(", $module: String!");
+                    variables["module"] = json!(pallet);
+                }
+                if let Some(event) = &filter.event {
+                    conditions.push("event: {equalTo: $event}".to_string());
+                    declarations.push_str(", $event: String!");
+                    variables["event"] = json!(event);
+                }
+                let query = format!(
+                    "query($from: BigFloat!, $to: BigFloat!, $first: Int!, $offset: Int!{}) {{ \
+                     events(filter: {{{}}}, orderBy: [BLOCK_HEIGHT_ASC, ID_ASC], first: $first, offset: $offset) {{ \
+                     nodes {{ id blockHeight module event }} }} }}",
+                    declarations,
+                    conditions.join(", ")
+                );
+                (query, variables)
+            }
+        }
+    }
+
+    /// Query returning the indexer's processed height
+    pub fn height_query(&self) -> &'static str {
+        match self {
+            IndexerDialect::Subsquid => "query { squidStatus { height } }",
+            IndexerDialect::SubQuery => "query { _metadata { lastProcessedHeight } }",
+        }
+    }
+
+    /// Parse the `data` of an events response
+    pub fn parse_events(&self, data: &JsonValue) -> Result<Vec<EventRecord>, SdkError> {
+        let nodes = match self {
+            IndexerDialect::Subsquid => data.get("events"),
+            IndexerDialect::SubQuery => data.pointer("/events/nodes"),
+        }
+        .and_then(JsonValue::as_array)
+        .ok_or_else(|| invalid_response("missing events"))?;
+
+        nodes
+            .iter()
+            .map(|node| match self {
+                IndexerDialect::Subsquid => parse_subsquid_event(node),
+                IndexerDialect::SubQuery => parse_subquery_event(node),
+            })
+            .collect()
+    }
+
+    /// Parse the `data` of a height response
+    pub fn parse_height(&self, data: &JsonValue) -> Result<u64, SdkError> {
+        let height = match self {
+            IndexerDialect::Subsquid => data.pointer("/squidStatus/height"),
+            IndexerDialect::SubQuery => data.pointer("/_metadata/lastProcessedHeight"),
+        };
+        height
+            .and_then(as_u64)
+            .ok_or_else(|| invalid_response("missing indexer height"))
+    }
+}
+
+fn parse_subsquid_event(node: &JsonValue) -> Result<EventRecord, SdkError> {
+    let name = node
+        .get("name")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| invalid_response("event without name"))?;
+    let (pallet, event) = name
+        .split_once('.')
+        .ok_or_else(|| invalid_response(&format!("malformed event name {}", name)))?;
+
+    Ok(EventRecord {
+        block_number: node
+            .pointer("/block/height")
+            .and_then(as_u64)
+            .ok_or_else(|| invalid_response("event without block height"))?,
+        block_hash: node
+            .pointer("/block/hash")
+            .and_then(JsonValue::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        index: node
+            .get("indexInBlock")
+            .and_then(as_u64)
+            .ok_or_else(|| invalid_response("event without index"))? as u32,
+        extrinsic_index: node
+            .pointer("/extrinsic/indexInBlock")
+            .and_then(as_u64)
+            .map(|index| index as u32),
+        pallet: pallet.to_string(),
+        event: event.to_string(),
+        fields: node.get("args").cloned().unwrap_or(JsonValue::Null),
+    })
+}
+
+fn parse_subquery_event(node: &JsonValue) -> Result<EventRecord, SdkError> {
+    let id = node
+        .get("id")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| invalid_response("event without id"))?;
+    let index = id
+        .rsplit_once('-')
+        .and_then(|(_, index)| index.parse().ok())
+        .ok_or_else(|| invalid_response(&format!("malformed event id {}", id)))?;
+    let module = node
+        .get("module")
+        .and_then(JsonValue::as_str)
+        .unwrap_or_default();
+
+    Ok(EventRecord {
+        block_number: node
+            .get("blockHeight")
+            .and_then(as_u64)
+            .ok_or_else(|| invalid_response("event without block height"))?,
+        block_hash: String::new(),
+        index,
+        extrinsic_index: None,
+        pallet: pallet_name(module),
+        event: node
+            .get("event")
+            .and_then(JsonValue::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        fields: JsonValue::Null,
+    })
+}
+
+/// SubQuery stores pallets in camel case (`convictionVoting`); restore the
+/// metadata name (`ConvictionVoting`)
+fn pallet_name(module: &str) -> String {
+    let mut chars = module.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+/// Numbers come back as JSON numbers or, for big number types, strings
+fn as_u64(value: &JsonValue) -> Option<u64> {
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+}
+
+fn invalid_response(reason: &str) -> SdkError {
+    SdkError::ProviderError(format!("Invalid indexer response: {}", reason))
+}
+
+/// Event history served by a Subsquid or SubQuery GraphQL endpoint
+#[derive(Debug, Clone)]
+pub struct GraphQLDataSource {
+    endpoint: String,
+    dialect: IndexerDialect,
+    client: reqwest::Client,
+    headers: Vec<(String, String)>,
+    page_size: usize,
+}
+
+impl GraphQLDataSource {
+    /// Create a source for a GraphQL endpoint
+    pub fn new(endpoint: impl Into<String>, dialect: IndexerDialect) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            dialect,
+            client: reqwest::Client::new(),
+            headers: Vec::new(),
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    /// Send the credentials and headers of `config` with every request
+    pub fn with_client_config(mut self, config: &ClientConfig) -> Self {
+        self.headers = config.headers();
+        self
+    }
+
+    /// Use an existing HTTP client
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Set how many events are requested per page
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// GraphQL endpoint URL
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Schema flavour of the endpoint
+    pub fn dialect(&self) -> IndexerDialect {
+        self.dialect
+    }
+
+    /// Run a query and return its `data`
+    pub async fn query(&self, query: &str, variables: JsonValue) -> Result<JsonValue, SdkError> {
+        let mut request = self
+            .client
+            .post(&self.endpoint)
+            .json(&json!({ "query": query, "variables": variables }));
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| SdkError::NetworkError(format!("Indexer request failed: {}", e)))?
+            .error_for_status()
+            .map_err(|e| SdkError::ProviderError(format!("Indexer returned an error: {}", e)))?;
+        let body: JsonValue = response
+            .json()
+            .await
+            .map_err(|e| invalid_response(&e.to_string()))?;
+
+        if let Some(errors) = body.get("errors").and_then(JsonValue::as_array) {
+            let messages: Vec<&str> = errors
+                .iter()
+                .filter_map(|error| error.get("message").and_then(JsonValue::as_str))
+                .collect();
+            return Err(SdkError::ProviderError(format!(
+                "Indexer query failed: {}",
+                messages.join("; ")
+            )));
+        }
+        body.get("data")
+            .cloned()
+            .ok_or_else(|| invalid_response("missing data"))
+    }
+}
+
+#[async_trait]
+impl EventHistory for GraphQLDataSource {
+    async fn events(&self, filter: &EventFilter) -> Result<Vec<EventRecord>, SdkError> {
+        filter.validate()?;
+        debug!(
+            "Querying indexer events in blocks {}..={}",
+            filter.from_block, filter.to_block
+        );
+
+        let mut records = Vec::new();
+        loop {
+            let first = match filter.limit {
+                Some(limit) => self.page_size.min(limit - records.len()),
+                None => self.page_size,
+            };
+            let (query, variables) = self.dialect.events_query(filter, first, records.len());
+            let page = self
+                .dialect
+                .parse_events(&self.query(&query, variables).await?)?;
+
+            let done = page.len() < first;
+            records.extend(page);
+            if done || filter.limit.is_some_and(|limit| records.len() >= limit) {
+                return Ok(records);
+            }
+        }
+    }
+
+    async fn indexed_height(&self) -> Result<Option<u64>, SdkError> {
+        let data = self
+            .query(self.dialect.height_query(), JsonValue::Null)
+            .await?;
+        self.dialect.parse_height(&data).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subsquid_query() {
+        let filter = EventFilter::new(10, 20).with_pallet("Balances");
+        let (query, variables) = IndexerDialect::Subsquid.events_query(&filter, 100, 200);
+
+        assert!(query.contains("name_startsWith: $name"));
+        assert!(query.contains("$name: String!"));
+        assert_eq!(variables["name"], "Balances.");
+        assert_eq!(variables["from"], 10);
+        assert_eq!(variables["offset"], 200);
+
+        let (query, variables) =
+            IndexerDialect::Subsquid.events_query(&EventFilter::new(1, 2), 100, 0);
+        assert!(!query.contains("$name"));
+        assert!(variables.get("name").is_none());
+    }
+
+    #[test]
+    fn test_subquery_query() {
+        let filter = EventFilter::new(10, 20)
+            .with_pallet("Balances")
+            .with_event("Transfer");
+        let (query, variables) = IndexerDialect::SubQuery.events_query(&filter, 50, 0);
+
+        assert!(query.contains("module: {equalToInsensitive: $module}"));
+        assert!(query.contains("event: {equalTo: $event}"));
+        assert_eq!(variables["from"], "10");
+        assert_eq!(variables["module"], "Balances");
+    }
+
+    #[test]
+    fn test_parse_subsquid_events() {
+        let data = json!({
+            "events": [{
+                "name": "Balances.Transfer",
+                "indexInBlock": 3,
+                "args": {"from": "0x01", "to": "0x02", "amount": "1000"},
+                "block": {"height": 15, "hash": "0xabc"},
+                "extrinsic": {"indexInBlock": 2}
+            }, {
+                "name": "System.NewAccount",
+                "indexInBlock": 0,
+                "block": {"height": "16", "hash": "0xdef"},
+                "extrinsic": null
+            }]
+        });
+
+        let records = IndexerDialect::Subsquid.parse_events(&data).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].pallet, "Balances");
+        assert_eq!(records[0].event, "Transfer");
+        assert_eq!(records[0].extrinsic_index, Some(2));
+        assert_eq!(records[0].fields["amount"], "1000");
+        assert_eq!(records[1].block_number, 16);
+        assert_eq!(records[1].extrinsic_index, None);
+    }
+
+    #[test]
+    fn test_parse_subquery_events() {
+        let data = json!({
+            "events": {"nodes": [
+                {"id": "100-4", "blockHeight": "100", "module": "convictionVoting", "event": "Voted"}
+            ]}
+        });
+
+        let records = IndexerDialect::SubQuery.parse_events(&data).unwrap();
+        assert_eq!(records[0].block_number, 100);
+        assert_eq!(records[0].index, 4);
+        assert_eq!(records[0].pallet, "ConvictionVoting");
+        assert!(records[0].block_hash.is_empty());
+
+        assert!(IndexerDialect::SubQuery
+            .parse_events(&json!({"events": {}}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_height() {
+        assert_eq!(
+            IndexerDialect::Subsquid
+                .parse_height(&json!({"squidStatus": {"height": 42}}))
+                .unwrap(),
+            42
+        );
+        assert_eq!(
+            IndexerDialect::SubQuery
+                .parse_height(&json!({"_metadata": {"lastProcessedHeight": "43"}}))
+                .unwrap(),
+            43
+        );
+    }
+}
@@ -0,0 +1,346 @@
+//! Historical event queries
+//!
+//! [`EventHistory`] is implemented by anything that can answer "which events
+//! happened in these blocks?": an RPC node decoding blocks on demand, or an
+//! indexer such as Subsquid or SubQuery (see `GraphQLDataSource` behind the
+//! `graphql` feature).
+//!
+//! Indexers answer large historical ranges far faster than a node, but lag
+//! behind the chain head. [`HybridHistory`] combines both: blocks up to the
+//! indexer's [`indexed_height`](EventHistory::indexed_height) are read from the
+//! indexer, newer blocks from the live source, and the results are merged in
+//! block order — callers see a single source.
+//!
+//! ```rust
+//! use apex_sdk_core::history::{split_range, EventFilter};
+//!
+//! let filter = EventFilter::new(100, 200)
+//!     .with_pallet("Balances")
+//!     .with_event("Transfer");
+//! assert!(filter.covers(150));
+//!
+//! // indexer has processed up to block 180
+//! let split = split_range(100, 200, Some(180));
+//! assert_eq!(split.indexed, Some(100..=180));
+//! assert_eq!(split.live, Some(181..=200));
+//! ```
+
+use crate::sink::EventRecord;
+use crate::SdkError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use tracing::warn;
+
+/// Block range and event selector for a history query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventFilter {
+    /// First block to search
+    pub from_block: u64,
+    /// Last block to search (inclusive)
+    pub to_block: u64,
+    /// Only match events from this pallet
+    pub pallet: Option<String>,
+    /// Only match events with this variant name
+    pub event: Option<String>,
+    /// Stop after this many matches
+    pub limit: Option<usize>,
+}
+
+impl EventFilter {
+    /// Match every event in blocks `from_block..=to_block`
+    pub fn new(from_block: u64, to_block: u64) -> Self {
+        Self {
+            from_block,
+            to_block,
+            pallet: None,
+            event: None,
+            limit: None,
+        }
+    }
+
+    /// Only match events from this pallet
+    pub fn with_pallet(mut self, pallet: impl Into<String>) -> Self {
+        self.pallet = Some(pallet.into());
+        self
+    }
+
+    /// Only match events with this variant name
+    pub fn with_event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Stop after this many matches
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Same selector over another block range
+    pub fn with_range(mut self, from_block: u64, to_block: u64) -> Self {
+        self.from_block = from_block;
+        self.to_block = to_block;
+        self
+    }
+
+    /// Check whether `block` lies in the filter's range
+    pub fn covers(&self, block: u64) -> bool {
+        (self.from_block..=self.to_block).contains(&block)
+    }
+
+    /// Check whether a record matches the filter
+    pub fn matches(&self, record: &EventRecord) -> bool {
+        self.covers(record.block_number)
+            && self
+                .pallet
+                .as_ref()
+                .is_none_or(|pallet| pallet.eq_ignore_ascii_case(&record.pallet))
+            && self
+                .event
+                .as_ref()
+                .is_none_or(|event| *event == record.event)
+    }
+
+    /// Reject empty ranges
+    pub fn validate(&self) -> Result<(), SdkError> {
+        if self.from_block > self.to_block {
+            return Err(SdkError::ConfigError(format!(
+                "Invalid block range: {} is after {}",
+                self.from_block, self.to_block
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A source of historical events
+#[async_trait]
+pub trait EventHistory: Send + Sync {
+    /// Events matching `filter`, in block and event order
+    async fn events(&self, filter: &EventFilter) -> Result<Vec<EventRecord>, SdkError>;
+
+    /// Highest block this source has data for
+    ///
+    /// `None` means the source follows the chain head, as an RPC node does.
+    async fn indexed_height(&self) -> Result<Option<u64>, SdkError> {
+        Ok(None)
+    }
+}
+
+/// A block range split between an indexer and a live source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeSplit {
+    /// Blocks the indexer has processed
+    pub indexed: Option<RangeInclusive<u64>>,
+    /// Blocks newer than the indexer's height
+    pub live: Option<RangeInclusive<u64>>,
+}
+
+/// Split `from..=to` at an indexer's height
+///
+/// An indexer height of `None` covers the whole range.
+pub fn split_range(from: u64, to: u64, indexed_height: Option<u64>) -> RangeSplit {
+    let Some(height) = indexed_height else {
+        return RangeSplit {
+            indexed: Some(from..=to),
+            live: None,
+        };
+    };
+    RangeSplit {
+        indexed: (from <= height).then(|| from..=to.min(height)),
+        live: (to > height).then(|| from.max(height.saturating_add(1))..=to),
+    }
+}
+
+/// Answers historical ranges from an indexer and recent blocks from a live source
+///
+/// If the indexer is unreachable, the whole range is served by the live source.
+#[derive(Debug, Clone)]
+pub struct HybridHistory<I, L> {
+    indexer: I,
+    live: L,
+}
+
+impl<I, L> HybridHistory<I, L> {
+    /// Combine an indexer with a live source
+    pub fn new(indexer: I, live: L) -> Self {
+        Self { indexer, live }
+    }
+
+    /// The indexer source
+    pub fn indexer(&self) -> &I {
+        &self.indexer
+    }
+
+    /// The live source
+    pub fn live(&self) -> &L {
+        &self.live
+    }
+}
+
+#[async_trait]
+impl<I: EventHistory, L: EventHistory> EventHistory for HybridHistory<I, L> {
+    async fn events(&self, filter: &EventFilter) -> Result<Vec<EventRecord>, SdkError> {
+        filter.validate()?;
+
+        let height = match self.indexer.indexed_height().await {
+            Ok(height) => height,
+            Err(e) => {
+                warn!("Indexer unavailable, querying live source: {}", e);
+                return self.live.events(filter).await;
+            }
+        };
+        let split = split_range(filter.from_block, filter.to_block, height);
+
+        let mut records = Vec::new();
+        if let Some(range) = split.indexed {
+            let part = filter.clone().with_range(*range.start(), *range.end());
+            match self.indexer.events(&part).await {
+                Ok(found) => records = found,
+                Err(e) => {
+                    warn!("Indexer query failed, querying live source: {}", e);
+                    records = self.live.events(&part).await?;
+                }
+            }
+        }
+
+        if let Some(range) = split.live {
+            let remaining = filter
+                .limit
+                .map(|limit| limit.saturating_sub(records.len()));
+            if remaining != Some(0) {
+                let mut part = filter.clone().with_range(*range.start(), *range.end());
+                part.limit = remaining;
+                records.extend(self.live.events(&part).await?);
+            }
+        }
+
+        if let Some(limit) = filter.limit {
+            records.truncate(limit);
+        }
+        Ok(records)
+    }
+
+    async fn indexed_height(&self) -> Result<Option<u64>, SdkError> {
+        self.live.indexed_height().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedSource {
+        height: Option<u64>,
+        blocks: Vec<u64>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl EventHistory for FixedSource {
+        async fn events(&self, filter: &EventFilter) -> Result<Vec<EventRecord>, SdkError> {
+            if self.fail {
+                return Err(SdkError::NetworkError("down".to_string()));
+            }
+            Ok(self
+                .blocks
+                .iter()
+                .map(|&number| record(number))
+                .filter(|record| filter.matches(record))
+                .take(filter.limit.unwrap_or(usize::MAX))
+                .collect())
+        }
+
+        async fn indexed_height(&self) -> Result<Option<u64>, SdkError> {
+            if self.fail {
+                return Err(SdkError::NetworkError("down".to_string()));
+            }
+            Ok(self.height)
+        }
+    }
+
+    fn record(block_number: u64) -> EventRecord {
+        EventRecord {
+            block_number,
+            block_hash: format!("0x{:02x}", block_number),
+            index: 0,
+            extrinsic_index: None,
+            pallet: "Balances".to_string(),
+            event: "Transfer".to_string(),
+            fields: serde_json::Value::Null,
+        }
+    }
+
+    fn numbers(records: &[EventRecord]) -> Vec<u64> {
+        records.iter().map(|record| record.block_number).collect()
+    }
+
+    #[test]
+    fn test_split_range() {
+        let split = |height| {
+            let split = split_range(10, 20, height);
+            (split.indexed, split.live)
+        };
+        assert_eq!(split(None), (Some(10..=20), None));
+        assert_eq!(split(Some(15)), (Some(10..=15), Some(16..=20)));
+        assert_eq!(split(Some(25)), (Some(10..=20), None));
+        assert_eq!(split(Some(5)), (None, Some(10..=20)));
+        assert_eq!(split(Some(20)), (Some(10..=20), None));
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let filter = EventFilter::new(1, 10).with_pallet("balances");
+        assert!(filter.matches(&record(5)));
+        assert!(!filter.matches(&record(11)));
+        assert!(!EventFilter::new(1, 10)
+            .with_event("Deposit")
+            .matches(&record(5)));
+        assert!(EventFilter::new(2, 1).validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_routes_by_indexed_height() {
+        let history = HybridHistory::new(
+            FixedSource {
+                height: Some(15),
+                blocks: vec![11, 14, 18],
+                fail: false,
+            },
+            FixedSource {
+                height: None,
+                blocks: vec![12, 16, 19],
+                fail: false,
+            },
+        );
+
+        let all = history.events(&EventFilter::new(10, 20)).await.unwrap();
+        assert_eq!(numbers(&all), vec![11, 14, 16, 19]);
+
+        let limited = history
+            .events(&EventFilter::new(10, 20).with_limit(3))
+            .await
+            .unwrap();
+        assert_eq!(numbers(&limited), vec![11, 14, 16]);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_falls_back_to_live() {
+        let history = HybridHistory::new(
+            FixedSource {
+                height: Some(100),
+                blocks: vec![11],
+                fail: true,
+            },
+            FixedSource {
+                height: None,
+                blocks: vec![12, 16],
+                fail: false,
+            },
+        );
+
+        let events = history.events(&EventFilter::new(10, 20)).await.unwrap();
+        assert_eq!(numbers(&events), vec![12, 16]);
+    }
+}
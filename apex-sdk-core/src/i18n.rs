@@ -0,0 +1,309 @@
+//! Localizable user-facing messages
+//!
+//! Errors and summaries shown to end users are described by a [`Message`]: a
+//! stable `code` for programmatic handling, named arguments, and the English
+//! text used when no translation exists. Wallets embedding the SDK register
+//! [`MessageCatalog`]s on a [`Localizer`] to render messages in their own
+//! language; codes never change between releases, wording may.
+//!
+//! Templates reference arguments as `{name}`:
+//!
+//! ```rust
+//! use apex_sdk_core::i18n::{Catalog, Localized, Localizer};
+//! use apex_sdk_core::SdkError;
+//!
+//! let german = Catalog::new("de")
+//!     .with_template("sdk.network_error", "Netzwerkfehler: {detail}");
+//! let localizer = Localizer::new().with_catalog(german);
+//!
+//! let error = SdkError::NetworkError("timeout".to_string());
+//! assert_eq!(error.code(), "sdk.network_error");
+//! assert_eq!(localizer.render(&error.message()), "Netzwerkfehler: timeout");
+//!
+//! // codes without a translation fall back to English
+//! let error = SdkError::SignerError("locked".to_string());
+//! assert_eq!(localizer.render(&error.message()), "Signer error: locked");
+//! ```
+
+use crate::balance::AmountError;
+use crate::SdkError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A user-facing message with a stable code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Message {
+    code: String,
+    args: Vec<(String, String)>,
+    fallback: String,
+}
+
+impl Message {
+    /// Message with a stable code and its English text
+    pub fn new(code: impl Into<String>, fallback: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            args: Vec::new(),
+            fallback: fallback.into(),
+        }
+    }
+
+    /// Message whose English text is `template` filled with `args`
+    pub fn from_template<V: ToString>(
+        code: impl Into<String>,
+        template: &str,
+        args: &[(&str, V)],
+    ) -> Self {
+        let mut message = Self::new(code, "");
+        for (name, value) in args {
+            message = message.with_arg(*name, value.to_string());
+        }
+        message.fallback = message.format(template);
+        message
+    }
+
+    /// Add a named argument for templates
+    pub fn with_arg(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.args.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Stable message code
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Value of a named argument
+    pub fn arg(&self, name: &str) -> Option<&str> {
+        self.args
+            .iter()
+            .find(|(arg, _)| arg == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Named arguments in insertion order
+    pub fn args(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.args
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// English text
+    pub fn fallback(&self) -> &str {
+        &self.fallback
+    }
+
+    /// Fill `{name}` placeholders in `template` with this message's arguments
+    pub fn format(&self, template: &str) -> String {
+        self.args
+            .iter()
+            .fold(template.to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{}}}", name), value)
+            })
+    }
+}
+
+impl std::fmt::Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.fallback)
+    }
+}
+
+/// Source of translated templates
+pub trait MessageCatalog: Send + Sync {
+    /// Locale served by this catalog, e.g. `de` or `pt-BR`
+    fn locale(&self) -> &str;
+
+    /// Template for a message code
+    fn template(&self, code: &str) -> Option<&str>;
+}
+
+/// In-memory catalog of templates keyed by message code
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Catalog {
+    locale: String,
+    templates: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Empty catalog for a locale
+    pub fn new(locale: impl Into<String>) -> Self {
+        Self {
+            locale: locale.into(),
+            templates: HashMap::new(),
+        }
+    }
+
+    /// Catalog from a JSON object mapping codes to templates
+    pub fn from_json(locale: impl Into<String>, json: &str) -> Result<Self, SdkError> {
+        let templates = serde_json::from_str(json)
+            .map_err(|e| SdkError::ConfigError(format!("Invalid message catalog: {}", e)))?;
+        Ok(Self {
+            locale: locale.into(),
+            templates,
+        })
+    }
+
+    /// Add or replace a template
+    pub fn with_template(mut self, code: impl Into<String>, template: impl Into<String>) -> Self {
+        self.templates.insert(code.into(), template.into());
+        self
+    }
+
+    /// Number of templates
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Check whether the catalog has no templates
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+}
+
+impl MessageCatalog for Catalog {
+    fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    fn template(&self, code: &str) -> Option<&str> {
+        self.templates.get(code).map(String::as_str)
+    }
+}
+
+/// Renders messages through registered catalogs, falling back to English
+#[derive(Clone, Default)]
+pub struct Localizer {
+    catalogs: Vec<Arc<dyn MessageCatalog>>,
+}
+
+impl Localizer {
+    /// Localizer rendering English text
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a catalog; catalogs added later take precedence
+    pub fn with_catalog(mut self, catalog: impl MessageCatalog + 'static) -> Self {
+        self.catalogs.insert(0, Arc::new(catalog));
+        self
+    }
+
+    /// Locales of the registered catalogs, most preferred first
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        self.catalogs.iter().map(|catalog| catalog.locale())
+    }
+
+    /// Text of `message` from the first catalog that has its code
+    pub fn render(&self, message: &Message) -> String {
+        self.catalogs
+            .iter()
+            .find_map(|catalog| catalog.template(message.code()))
+            .map(|template| message.format(template))
+            .unwrap_or_else(|| message.fallback().to_string())
+    }
+}
+
+impl std::fmt::Debug for Localizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Localizer")
+            .field("locales", &self.locales().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Types with a localizable user-facing message
+pub trait Localized {
+    /// Message describing this value
+    fn message(&self) -> Message;
+
+    /// Stable code of the message
+    fn code(&self) -> String {
+        self.message().code().to_string()
+    }
+}
+
+impl Localized for SdkError {
+    fn message(&self) -> Message {
+        let (code, detail) = match self {
+            SdkError::ProviderError(detail) => ("sdk.provider_error", detail),
+            SdkError::SignerError(detail) => ("sdk.signer_error", detail),
+            SdkError::TransactionError(detail) => ("sdk.transaction_error", detail),
+            SdkError::NetworkError(detail) => ("sdk.network_error", detail),
+            SdkError::ConfigError(detail) => ("sdk.config_error", detail),
+            SdkError::NotImplemented(detail) => ("sdk.not_implemented", detail),
+        };
+        Message::new(code, self.to_string()).with_arg("detail", detail)
+    }
+}
+
+impl Localized for AmountError {
+    fn message(&self) -> Message {
+        let message = |code| Message::new(code, self.to_string());
+        match self {
+            AmountError::Overflow => message("amount.overflow"),
+            AmountError::Underflow => message("amount.underflow"),
+            AmountError::DivisionByZero => message("amount.division_by_zero"),
+            AmountError::Invalid(input) => message("amount.invalid").with_arg("input", input),
+            AmountError::TooManyDecimals { symbol, found, max } => {
+                message("amount.too_many_decimals")
+                    .with_arg("symbol", symbol)
+                    .with_arg("found", found)
+                    .with_arg("max", max)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_precedence() {
+        let spanish = Catalog::from_json(
+            "es",
+            r#"{"amount.too_many_decimals": "{symbol} admite {max} decimales, no {found}"}"#,
+        )
+        .unwrap();
+        let override_catalog =
+            Catalog::new("es-MX").with_template("amount.overflow", "Monto demasiado grande");
+        let localizer = Localizer::new()
+            .with_catalog(spanish)
+            .with_catalog(override_catalog);
+
+        assert_eq!(localizer.locales().collect::<Vec<_>>(), vec!["es-MX", "es"]);
+        let error = AmountError::TooManyDecimals {
+            symbol: "DOT".to_string(),
+            found: 12,
+            max: 10,
+        };
+        assert_eq!(
+            localizer.render(&error.message()),
+            "DOT admite 10 decimales, no 12"
+        );
+        assert_eq!(
+            localizer.render(&AmountError::Overflow.message()),
+            "Monto demasiado grande"
+        );
+        assert_eq!(
+            localizer.render(&AmountError::Underflow.message()),
+            "Amount underflow"
+        );
+        assert!(Catalog::from_json("xx", "[]").is_err());
+    }
+
+    #[test]
+    fn test_fallback_matches_display() {
+        let error = SdkError::ConfigError("missing endpoint".to_string());
+        let message = error.message();
+        assert_eq!(message.code(), "sdk.config_error");
+        assert_eq!(message.arg("detail"), Some("missing endpoint"));
+        assert_eq!(Localizer::new().render(&message), error.to_string());
+
+        let message = Message::from_template("test.greeting", "Hello {name}", &[("name", "Ada")]);
+        assert_eq!(message.fallback(), "Hello Ada");
+        assert_eq!(message.arg("name"), Some("Ada"));
+    }
+}
@@ -48,11 +48,78 @@ pub mod metrics;
 /// Golden vectors for encoding verification
 pub mod golden_vectors;
 
+/// Schema versioning for persisted core types
+pub mod schema;
+
+/// Zero-copy block data views
+pub mod block;
+
+/// RPC client credentials and headers
+pub mod client;
+
+/// Transport-level cache for immutable RPC responses
+pub mod response_cache;
+
+/// Subscription checkpoints for resuming after restarts
+pub mod checkpoint;
+
+/// Indexer sinks publishing decoded blocks and events
+pub mod sink;
+
+/// Overflow-checked amounts, percentages and token denominations
+pub mod balance;
+
+/// Localizable messages for errors and summaries
+pub mod i18n;
+
+/// Historical event queries across indexers and live sources
+pub mod history;
+
+/// Screening of transfer addresses against block lists
+pub mod screening;
+
+/// Bounded stream channels with configurable backpressure
+pub mod stream;
+
+/// Opt-in, anonymous usage statistics
+pub mod telemetry;
+
+/// GraphQL data source for Subsquid and SubQuery indexers
+#[cfg(feature = "graphql")]
+pub mod graphql;
+
+/// Apache Parquet export of indexed blocks, extrinsics and events
+#[cfg(feature = "parquet")]
+pub mod parquet;
+
+/// Embedded SQLite store for local analytics over indexed data
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use balance::{AmountError, Balance, Denomination, DisplayAmount, Perbill};
+pub use block::{
+    block_number_u32, hex_prefixed, parse_block_number, BlockInfoRef, BlockNumberError,
+    BlockNumberWidth, RawBlockInfo,
+};
+pub use checkpoint::{
+    backfill_range, Checkpoint, CheckpointStore, FileCheckpointStore, MemoryCheckpointStore,
+};
+pub use client::{
+    ipc_path, ClientConfig, Credentials, EndpointConfig, ProxyConfig, ProxyScheme, TlsConfig,
+};
 pub use golden_vectors::{
     load_default_golden_vectors, verify_golden_vector, ChainType, GoldenVector, GoldenVectorSet,
 };
+pub use history::{EventFilter, EventHistory, HybridHistory, RangeSplit};
+pub use i18n::{Catalog, Localized, Localizer, Message, MessageCatalog};
 pub use metrics::{MetricType, MetricsCollector};
 pub use pipeline::{TransactionPipeline, TransactionResult};
+pub use response_cache::{ResponseCache, ResponseCacheConfig, ResponseCacheStats};
+pub use schema::{from_versioned_json, to_versioned_json, SchemaError, Versioned};
+pub use screening::{AddressScreener, ScreeningResult, StaticListScreener};
+pub use sink::{BlockRecord, EventRecord, IndexerSink, Serialization, SinkConfig, SinkRecord};
+pub use stream::{OverflowPolicy, StreamConfig, StreamSendError};
+pub use telemetry::{Telemetry, TelemetryReporter, UsageReport};
 
 /// Unified error taxonomy for the SDK
 #[derive(Error, Debug)]
@@ -0,0 +1,387 @@
+//! Apache Parquet export of decoded chain data
+//!
+//! [`ParquetSink`] is an [`IndexerSink`] writing the records it receives to
+//! one Parquet file per record kind, ready to load into a DataFrame:
+//!
+//! | File | Schema |
+//! |------|--------|
+//! | `blocks.parquet` | [`block_schema`] |
+//! | `extrinsics.parquet` | [`extrinsic_schema`] |
+//! | `events.parquet` | [`event_schema`] |
+//!
+//! Each [`write`](IndexerSink::write) appends the records of that call, and
+//! [`flush`](IndexerSink::flush) closes the current row group. The files are
+//! only readable once [`ParquetSink::finish`] has written their footers;
+//! dropping the sink finishes them too.
+//!
+//! ```rust,no_run
+//! use apex_sdk_core::parquet::ParquetSink;
+//! use apex_sdk_core::sink::{BlockRecord, IndexerSink, SinkRecord};
+//!
+//! # async fn example() -> Result<(), apex_sdk_core::SdkError> {
+//! let sink = ParquetSink::create("./export")?;
+//! sink.write(&[SinkRecord::Block(BlockRecord::new(1, "0x01"))]).await?;
+//! sink.finish()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Columns are only ever added at the end of a schema, so readers selecting
+//! columns by name keep working across releases. Event fields are stored as
+//! JSON text in the `fields` column.
+
+use crate::sink::{BlockRecord, EventRecord, ExtrinsicRecord, IndexerSink, SinkRecord};
+use crate::SdkError;
+use arrow_array::{ArrayRef, BooleanArray, RecordBatch, StringArray, UInt32Array, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use async_trait::async_trait;
+use parquet::arrow::ArrowWriter;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// File holding block records
+pub const BLOCKS_FILE: &str = "blocks.parquet";
+
+/// File holding extrinsic records
+pub const EXTRINSICS_FILE: &str = "extrinsics.parquet";
+
+/// File holding event records
+pub const EVENTS_FILE: &str = "events.parquet";
+
+/// Schema of `blocks.parquet`
+pub fn block_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("number", DataType::UInt64, false),
+        Field::new("hash", DataType::Utf8, false),
+        Field::new("parent_hash", DataType::Utf8, true),
+        Field::new("timestamp", DataType::UInt64, true),
+        Field::new("extrinsic_count", DataType::UInt32, true),
+        Field::new("event_count", DataType::UInt32, true),
+    ])
+}
+
+/// Schema of `extrinsics.parquet`
+pub fn extrinsic_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("block_number", DataType::UInt64, false),
+        Field::new("block_hash", DataType::Utf8, false),
+        Field::new("index", DataType::UInt32, false),
+        Field::new("hash", DataType::Utf8, false),
+        Field::new("signer", DataType::Utf8, true),
+        Field::new("pallet", DataType::Utf8, false),
+        Field::new("call", DataType::Utf8, false),
+        Field::new("success", DataType::Boolean, false),
+    ])
+}
+
+/// Schema of `events.parquet`
+pub fn event_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("block_number", DataType::UInt64, false),
+        Field::new("block_hash", DataType::Utf8, false),
+        Field::new("index", DataType::UInt32, false),
+        Field::new("extrinsic_index", DataType::UInt32, true),
+        Field::new("pallet", DataType::Utf8, false),
+        Field::new("event", DataType::Utf8, false),
+        Field::new("fields", DataType::Utf8, false),
+    ])
+}
+
+/// Writes records to Parquet files in a directory
+pub struct ParquetSink {
+    dir: PathBuf,
+    writers: Mutex<Option<Writers>>,
+}
+
+struct Writers {
+    blocks: ArrowWriter<File>,
+    extrinsics: ArrowWriter<File>,
+    events: ArrowWriter<File>,
+}
+
+impl ParquetSink {
+    /// Create `dir` if needed and start new files in it, replacing old ones
+    pub fn create(dir: impl AsRef<Path>) -> Result<Self, SdkError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            SdkError::ConfigError(format!("Failed to create {}: {}", dir.display(), e))
+        })?;
+        let writers = Writers {
+            blocks: writer(&dir, BLOCKS_FILE, block_schema())?,
+            extrinsics: writer(&dir, EXTRINSICS_FILE, extrinsic_schema())?,
+            events: writer(&dir, EVENTS_FILE, event_schema())?,
+        };
+        Ok(Self {
+            dir,
+            writers: Mutex::new(Some(writers)),
+        })
+    }
+
+    /// Directory the files are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Write the file footers; later writes fail
+    pub fn finish(&self) -> Result<(), SdkError> {
+        let Some(writers) = self.lock().take() else {
+            return Ok(());
+        };
+        for writer in [writers.blocks, writers.extrinsics, writers.events] {
+            writer.close().map_err(write_error)?;
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Writers>> {
+        self.writers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl IndexerSink for ParquetSink {
+    async fn write(&self, records: &[SinkRecord]) -> Result<(), SdkError> {
+        let mut blocks = Vec::new();
+        let mut extrinsics = Vec::new();
+        let mut events = Vec::new();
+        for record in records {
+            match record {
+                SinkRecord::Block(block) => blocks.push(block),
+                SinkRecord::Extrinsic(extrinsic) => extrinsics.push(extrinsic),
+                SinkRecord::Event(event) => events.push(event),
+            }
+        }
+
+        let mut guard = self.lock();
+        let writers = guard
+            .as_mut()
+            .ok_or_else(|| SdkError::ConfigError("Parquet sink is finished".to_string()))?;
+        if !blocks.is_empty() {
+            writers
+                .blocks
+                .write(&block_batch(&blocks)?)
+                .map_err(write_error)?;
+        }
+        if !extrinsics.is_empty() {
+            writers
+                .extrinsics
+                .write(&extrinsic_batch(&extrinsics)?)
+                .map_err(write_error)?;
+        }
+        if !events.is_empty() {
+            writers
+                .events
+                .write(&event_batch(&events)?)
+                .map_err(write_error)?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), SdkError> {
+        let mut guard = self.lock();
+        let Some(writers) = guard.as_mut() else {
+            return Ok(());
+        };
+        writers.blocks.flush().map_err(write_error)?;
+        writers.extrinsics.flush().map_err(write_error)?;
+        writers.events.flush().map_err(write_error)
+    }
+}
+
+impl Drop for ParquetSink {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            warn!(
+                "Failed to finish Parquet files in {}: {}",
+                self.dir.display(),
+                e
+            );
+        }
+    }
+}
+
+impl std::fmt::Debug for ParquetSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParquetSink")
+            .field("dir", &self.dir)
+            .field("finished", &self.lock().is_none())
+            .finish()
+    }
+}
+
+fn writer(dir: &Path, name: &str, schema: Schema) -> Result<ArrowWriter<File>, SdkError> {
+    let path = dir.join(name);
+    let file = File::create(&path).map_err(|e| {
+        SdkError::ConfigError(format!("Failed to create {}: {}", path.display(), e))
+    })?;
+    ArrowWriter::try_new(file, Arc::new(schema), None).map_err(write_error)
+}
+
+fn write_error(e: impl std::fmt::Display) -> SdkError {
+    SdkError::ProviderError(format!("Failed to write Parquet: {}", e))
+}
+
+fn batch(schema: Schema, columns: Vec<ArrayRef>) -> Result<RecordBatch, SdkError> {
+    RecordBatch::try_new(SchemaRef::new(schema), columns).map_err(write_error)
+}
+
+fn block_batch(blocks: &[&BlockRecord]) -> Result<RecordBatch, SdkError> {
+    batch(
+        block_schema(),
+        vec![
+            Arc::new(UInt64Array::from_iter_values(
+                blocks.iter().map(|b| b.number),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                blocks.iter().map(|b| b.hash.as_str()),
+            )),
+            Arc::new(StringArray::from_iter(
+                blocks.iter().map(|b| b.parent_hash.as_deref()),
+            )),
+            Arc::new(UInt64Array::from_iter(blocks.iter().map(|b| b.timestamp))),
+            Arc::new(UInt32Array::from_iter(
+                blocks.iter().map(|b| b.extrinsic_count),
+            )),
+            Arc::new(UInt32Array::from_iter(blocks.iter().map(|b| b.event_count))),
+        ],
+    )
+}
+
+fn extrinsic_batch(extrinsics: &[&ExtrinsicRecord]) -> Result<RecordBatch, SdkError> {
+    batch(
+        extrinsic_schema(),
+        vec![
+            Arc::new(UInt64Array::from_iter_values(
+                extrinsics.iter().map(|x| x.block_number),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                extrinsics.iter().map(|x| x.block_hash.as_str()),
+            )),
+            Arc::new(UInt32Array::from_iter_values(
+                extrinsics.iter().map(|x| x.index),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                extrinsics.iter().map(|x| x.hash.as_str()),
+            )),
+            Arc::new(StringArray::from_iter(
+                extrinsics.iter().map(|x| x.signer.as_deref()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                extrinsics.iter().map(|x| x.pallet.as_str()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                extrinsics.iter().map(|x| x.call.as_str()),
+            )),
+            Arc::new(BooleanArray::from_iter(
+                extrinsics.iter().map(|x| Some(x.success)),
+            )),
+        ],
+    )
+}
+
+fn event_batch(events: &[&EventRecord]) -> Result<RecordBatch, SdkError> {
+    batch(
+        event_schema(),
+        vec![
+            Arc::new(UInt64Array::from_iter_values(
+                events.iter().map(|e| e.block_number),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                events.iter().map(|e| e.block_hash.as_str()),
+            )),
+            Arc::new(UInt32Array::from_iter_values(
+                events.iter().map(|e| e.index),
+            )),
+            Arc::new(UInt32Array::from_iter(
+                events.iter().map(|e| e.extrinsic_index),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                events.iter().map(|e| e.pallet.as_str()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                events.iter().map(|e| e.event.as_str()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                events.iter().map(|e| e.fields.to_string()),
+            )),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn read(path: PathBuf) -> RecordBatch {
+        let file = File::open(path).unwrap();
+        let mut reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        reader.next().unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_writes_records_per_kind() {
+        let dir = std::env::temp_dir().join(format!(
+            "apex-parquet-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let sink = ParquetSink::create(&dir).unwrap();
+
+        let mut block = BlockRecord::new(7, "0x07");
+        block.event_count = Some(1);
+        let records = [
+            SinkRecord::Block(block),
+            SinkRecord::Extrinsic(ExtrinsicRecord {
+                block_number: 7,
+                block_hash: "0x07".to_string(),
+                index: 1,
+                hash: "0xaa".to_string(),
+                signer: None,
+                pallet: "Timestamp".to_string(),
+                call: "set".to_string(),
+                success: true,
+            }),
+            SinkRecord::Event(EventRecord {
+                block_number: 7,
+                block_hash: "0x07".to_string(),
+                index: 0,
+                extrinsic_index: Some(1),
+                pallet: "Balances".to_string(),
+                event: "Transfer".to_string(),
+                fields: serde_json::json!({ "amount": "100" }),
+            }),
+        ];
+        sink.write(&records).await.unwrap();
+        sink.flush().await.unwrap();
+        sink.finish().unwrap();
+        assert!(sink.write(&records).await.is_err());
+
+        let blocks = read(dir.join(BLOCKS_FILE));
+        assert_eq!(blocks.schema().as_ref(), &block_schema());
+        assert_eq!(blocks.num_rows(), 1);
+        assert!(blocks.column(2).is_null(0));
+
+        let extrinsics = read(dir.join(EXTRINSICS_FILE));
+        assert_eq!(extrinsics.num_rows(), 1);
+
+        let events = read(dir.join(EVENTS_FILE));
+        let fields = events
+            .column_by_name("fields")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(fields.value(0), r#"{"amount":"100"}"#);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
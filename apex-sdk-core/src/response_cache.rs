@@ -0,0 +1,569 @@
+//! # Response Cache
+//!
+//! Transport-level cache for RPC calls whose answer can never change.
+//!
+//! A request that names its block by hash (a block body, a header, metadata or a
+//! runtime call at that block) always returns the same bytes, so, much like a
+//! strong ETag, the request itself identifies the response and a cached entry
+//! never has to be revalidated. Entries are only evicted to stay within the
+//! configured size limits.
+//!
+//! Unlike the domain caches in the adapters, this cache sits below every query
+//! API, so any code path that repeats an identical immutable call benefits.
+//! Concurrent identical calls are coalesced into a single request.
+//!
+//! ```rust
+//! use apex_sdk_core::response_cache::{is_immutable_request, ResponseCache};
+//!
+//! let hash = format!("\"0x{}\"", "ab".repeat(32));
+//! assert!(is_immutable_request("chain_getBlock", Some(&format!("[{}]", hash))));
+//! // Without a block hash the node answers for its current best block
+//! assert!(!is_immutable_request("chain_getBlock", Some("[]")));
+//!
+//! let cache = ResponseCache::default();
+//! cache.insert("chain_getBlock", Some(&format!("[{}]", hash)), "{\"block\":{}}");
+//! assert_eq!(cache.stats().entries, 1);
+//! ```
+
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Default maximum number of cached responses
+pub const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+/// Default maximum total size of cached responses in bytes
+pub const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// Methods whose result is fixed once the block hash at the given parameter
+/// index is known
+const HASH_PINNED_METHODS: &[(&str, usize)] = &[
+    ("chain_getBlock", 0),
+    ("chain_getHeader", 0),
+    ("state_getMetadata", 0),
+    ("state_getRuntimeVersion", 0),
+    ("state_call", 2),
+    ("state_getStorage", 1),
+    ("state_getStorageHash", 1),
+    ("state_getStorageSize", 1),
+    ("state_getKeysPaged", 3),
+    ("state_queryStorageAt", 1),
+    ("archive_v1_body", 0),
+    ("archive_v1_header", 0),
+    ("archive_v1_call", 0),
+    ("eth_getBlockByHash", 0),
+];
+
+/// Methods whose result is fixed for the lifetime of the chain
+const CHAIN_CONSTANT_METHODS: &[&str] = &[
+    "chainSpec_v1_genesisHash",
+    "chainSpec_v1_chainName",
+    "chainSpec_v1_properties",
+];
+
+/// Whether the response to `method` with `params` (raw JSON) can never change
+pub fn is_immutable_request(method: &str, params: Option<&str>) -> bool {
+    if CHAIN_CONSTANT_METHODS.contains(&method) {
+        return true;
+    }
+
+    let Some(&(_, index)) = HASH_PINNED_METHODS.iter().find(|(name, _)| *name == method) else {
+        return false;
+    };
+
+    let Some(Ok(Value::Array(params))) = params.map(serde_json::from_str::<Value>) else {
+        return false;
+    };
+
+    params
+        .get(index)
+        .and_then(Value::as_str)
+        .is_some_and(is_block_hash)
+}
+
+fn is_block_hash(value: &str) -> bool {
+    value.len() == 66
+        && value.starts_with("0x")
+        && value[2..].bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Cache key: method name and canonicalized parameters
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RequestKey {
+    method: String,
+    params: String,
+}
+
+impl RequestKey {
+    fn new(method: &str, params: Option<&str>) -> Self {
+        // Re-serialize so whitespace differences map to the same entry
+        let params = match params.map(serde_json::from_str::<Value>) {
+            Some(Ok(value)) => value.to_string(),
+            Some(Err(_)) => params.unwrap_or_default().trim().to_string(),
+            None => String::new(),
+        };
+
+        Self {
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.method.len() + self.params.len()
+    }
+}
+
+/// Size limits for a [`ResponseCache`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseCacheConfig {
+    /// Maximum number of cached responses
+    pub max_entries: usize,
+    /// Maximum total size of keys and responses in bytes
+    pub max_bytes: usize,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_bytes: DEFAULT_MAX_BYTES,
+        }
+    }
+}
+
+impl ResponseCacheConfig {
+    /// Create a configuration with default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of cached responses
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Set the maximum total size in bytes
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+}
+
+/// Response cache statistics
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseCacheStats {
+    /// Requests answered from the cache
+    pub hits: u64,
+    /// Cacheable requests that went to the node
+    pub misses: u64,
+    /// Number of cached responses
+    pub entries: usize,
+    /// Total size of cached keys and responses in bytes
+    pub bytes: usize,
+}
+
+impl ResponseCacheStats {
+    /// Fraction of cacheable requests answered from the cache
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+struct Entry {
+    body: Arc<str>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Store {
+    entries: HashMap<RequestKey, Entry>,
+    recency: BTreeMap<u64, RequestKey>,
+    bytes: usize,
+    tick: u64,
+}
+
+impl Store {
+    fn touch(&mut self, key: &RequestKey) -> Option<Arc<str>> {
+        self.tick += 1;
+        let tick = self.tick;
+        let entry = self.entries.get_mut(key)?;
+        self.recency.remove(&entry.last_used);
+        entry.last_used = tick;
+        self.recency.insert(tick, key.clone());
+        Some(entry.body.clone())
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some((_, key)) = self.recency.pop_first() {
+            if let Some(entry) = self.entries.remove(&key) {
+                self.bytes -= key.size() + entry.body.len();
+            }
+        }
+    }
+}
+
+struct Shared {
+    config: ResponseCacheConfig,
+    store: Mutex<Store>,
+    in_flight: Mutex<HashMap<RequestKey, Arc<tokio::sync::Mutex<()>>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Cache of immutable RPC responses
+///
+/// Cloning yields another handle to the same cache, so one cache can be shared
+/// by every connection to a chain. Two handles compare equal when they refer
+/// to the same cache.
+#[derive(Clone)]
+pub struct ResponseCache {
+    shared: Arc<Shared>,
+}
+
+impl ResponseCache {
+    /// Create an empty cache with the given limits
+    pub fn new(config: ResponseCacheConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                config,
+                store: Mutex::new(Store::default()),
+                in_flight: Mutex::new(HashMap::new()),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Get the cache limits
+    pub fn config(&self) -> &ResponseCacheConfig {
+        &self.shared.config
+    }
+
+    /// Look up a cached response
+    pub fn get(&self, method: &str, params: Option<&str>) -> Option<Arc<str>> {
+        self.lookup(&RequestKey::new(method, params))
+    }
+
+    /// Store a response if the request is immutable
+    ///
+    /// `null` results are never stored, since they usually mean the node does
+    /// not know the block yet.
+    pub fn insert(&self, method: &str, params: Option<&str>, body: impl Into<Arc<str>>) {
+        if is_immutable_request(method, params) {
+            self.store(RequestKey::new(method, params), body.into());
+        }
+    }
+
+    /// Answer an immutable request from the cache, or run `fetch` and cache its
+    /// result
+    ///
+    /// Requests that are not immutable always run `fetch`. Concurrent calls for
+    /// the same immutable request wait for the first one instead of issuing
+    /// their own.
+    pub async fn get_or_fetch<F, Fut, E>(
+        &self,
+        method: &str,
+        params: Option<&str>,
+        fetch: F,
+    ) -> Result<Arc<str>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String, E>>,
+    {
+        if !is_immutable_request(method, params) {
+            return fetch().await.map(Arc::from);
+        }
+
+        let key = RequestKey::new(method, params);
+        if let Some(body) = self.lookup(&key) {
+            return Ok(body);
+        }
+
+        let gate = self
+            .shared
+            .in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let guard = gate.lock().await;
+
+        // Another caller may have fetched the response while we waited
+        let result = match self.peek(&key) {
+            Some(body) => {
+                self.shared.hits.fetch_add(1, Ordering::Relaxed);
+                Ok(body)
+            }
+            None => {
+                self.shared.misses.fetch_add(1, Ordering::Relaxed);
+                let result = fetch().await.map(Arc::<str>::from);
+                if let Ok(body) = &result {
+                    self.store(key.clone(), body.clone());
+                }
+                result
+            }
+        };
+
+        drop(guard);
+        let mut in_flight = self
+            .shared
+            .in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if Arc::strong_count(&gate) == 2 {
+            in_flight.remove(&key);
+        }
+
+        result
+    }
+
+    /// Remove every cached response
+    pub fn clear(&self) {
+        *self.shared.store.lock().unwrap_or_else(|e| e.into_inner()) = Store::default();
+    }
+
+    /// Get cache statistics
+    pub fn stats(&self) -> ResponseCacheStats {
+        let store = self.shared.store.lock().unwrap_or_else(|e| e.into_inner());
+        ResponseCacheStats {
+            hits: self.shared.hits.load(Ordering::Relaxed),
+            misses: self.shared.misses.load(Ordering::Relaxed),
+            entries: store.entries.len(),
+            bytes: store.bytes,
+        }
+    }
+
+    fn lookup(&self, key: &RequestKey) -> Option<Arc<str>> {
+        let body = self.peek(key);
+        if body.is_some() {
+            self.shared.hits.fetch_add(1, Ordering::Relaxed);
+        }
+        body
+    }
+
+    fn peek(&self, key: &RequestKey) -> Option<Arc<str>> {
+        self.shared
+            .store
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .touch(key)
+    }
+
+    fn store(&self, key: RequestKey, body: Arc<str>) {
+        let size = key.size() + body.len();
+        let config = &self.shared.config;
+        if body.trim() == "null" || size > config.max_bytes || config.max_entries == 0 {
+            return;
+        }
+
+        let mut store = self.shared.store.lock().unwrap_or_else(|e| e.into_inner());
+        if store.entries.contains_key(&key) {
+            return;
+        }
+        while store.entries.len() >= config.max_entries || store.bytes + size > config.max_bytes {
+            store.evict_oldest();
+        }
+
+        store.tick += 1;
+        let tick = store.tick;
+        store.recency.insert(tick, key.clone());
+        store.entries.insert(
+            key,
+            Entry {
+                body,
+                last_used: tick,
+            },
+        );
+        store.bytes += size;
+    }
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(ResponseCacheConfig::default())
+    }
+}
+
+impl PartialEq for ResponseCache {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+impl Eq for ResponseCache {}
+
+impl std::fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("config", &self.shared.config)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    fn hash_params(byte: &str) -> String {
+        format!("[\"0x{}\"]", byte.repeat(32))
+    }
+
+    #[test]
+    fn test_immutable_request_classification() {
+        let params = hash_params("ab");
+        assert!(is_immutable_request("chain_getBlock", Some(&params)));
+        assert!(is_immutable_request("chainSpec_v1_genesisHash", None));
+
+        assert!(!is_immutable_request("chain_getBlock", None));
+        assert!(!is_immutable_request("chain_getBlock", Some("[null]")));
+        assert!(!is_immutable_request("chain_getBlockHash", Some("[100]")));
+        assert!(!is_immutable_request("system_health", Some(&params)));
+
+        // state_call takes the block hash as its third parameter
+        let hash = format!("\"0x{}\"", "cd".repeat(32));
+        let call = format!(
+            "[\"Metadata_metadata_at_version\", \"0x0f000000\", {}]",
+            hash
+        );
+        assert!(is_immutable_request("state_call", Some(&call)));
+        assert!(!is_immutable_request(
+            "state_call",
+            Some("[\"Metadata_metadata_at_version\", \"0x0f000000\"]")
+        ));
+    }
+
+    #[test]
+    fn test_insert_and_get_ignore_whitespace() {
+        let cache = ResponseCache::default();
+        let hash = "0x".to_string() + &"ab".repeat(32);
+
+        cache.insert(
+            "chain_getHeader",
+            Some(&format!("[\"{}\"]", hash)),
+            "{\"number\":\"0x1\"}",
+        );
+        let hit = cache.get("chain_getHeader", Some(&format!("[ \"{}\" ]", hash)));
+
+        assert_eq!(hit.as_deref(), Some("{\"number\":\"0x1\"}"));
+        assert_eq!(cache.stats().hits, 1);
+    }
+
+    #[test]
+    fn test_mutable_and_null_responses_not_stored() {
+        let cache = ResponseCache::default();
+        cache.insert("chain_getHeader", None, "{}");
+        cache.insert("chain_getHeader", Some(&hash_params("ab")), "null");
+
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_least_recently_used_evicted() {
+        let cache = ResponseCache::new(ResponseCacheConfig::new().with_max_entries(2));
+        cache.insert("chain_getBlock", Some(&hash_params("01")), "a");
+        cache.insert("chain_getBlock", Some(&hash_params("02")), "b");
+        cache.get("chain_getBlock", Some(&hash_params("01")));
+        cache.insert("chain_getBlock", Some(&hash_params("03")), "c");
+
+        assert!(cache
+            .get("chain_getBlock", Some(&hash_params("01")))
+            .is_some());
+        assert!(cache
+            .get("chain_getBlock", Some(&hash_params("02")))
+            .is_none());
+        assert!(cache
+            .get("chain_getBlock", Some(&hash_params("03")))
+            .is_some());
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 2);
+        cache.clear();
+        assert_eq!(cache.stats().bytes, 0);
+    }
+
+    #[test]
+    fn test_byte_limit() {
+        let cache = ResponseCache::new(ResponseCacheConfig::new().with_max_bytes(200));
+        cache.insert("chain_getBlock", Some(&hash_params("01")), "x".repeat(500));
+        assert_eq!(cache.stats().entries, 0);
+
+        cache.insert("chain_getBlock", Some(&hash_params("01")), "x".repeat(50));
+        cache.insert("chain_getBlock", Some(&hash_params("02")), "x".repeat(50));
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert!(stats.bytes <= 200);
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch_coalesces_identical_requests() {
+        let cache = ResponseCache::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let params = hash_params("ab");
+
+        let fetch = || {
+            let calls = calls.clone();
+            async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                Ok::<_, String>("{\"block\":{}}".to_string())
+            }
+        };
+
+        let (a, b) = tokio::join!(
+            cache.get_or_fetch("chain_getBlock", Some(&params), fetch),
+            cache.get_or_fetch("chain_getBlock", Some(&params), fetch)
+        );
+        assert_eq!(a.unwrap(), b.unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        assert!(cache.shared.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch_passes_through_mutable_requests_and_errors() {
+        let cache = ResponseCache::default();
+
+        let result = cache
+            .get_or_fetch("system_health", None, || async {
+                Ok::<_, String>("{}".to_string())
+            })
+            .await;
+        assert!(result.is_ok());
+
+        let params = hash_params("ab");
+        let result = cache
+            .get_or_fetch("chain_getBlock", Some(&params), || async {
+                Err::<String, _>("unavailable".to_string())
+            })
+            .await;
+        assert_eq!(result, Err("unavailable".to_string()));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (0, 1, 0));
+    }
+
+    #[test]
+    fn test_clones_share_state() {
+        let cache = ResponseCache::default();
+        let handle = cache.clone();
+        handle.insert("chainSpec_v1_genesisHash", None, "\"0x00\"");
+
+        assert_eq!(cache, handle);
+        assert_ne!(cache, ResponseCache::default());
+        assert_eq!(cache.stats().entries, 1);
+    }
+}
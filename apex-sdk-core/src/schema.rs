@@ -0,0 +1,351 @@
+//! # Schema Versioning
+//!
+//! Versioned serialization for core types that are persisted outside the process
+//! (caches, archives, indexer sinks).
+//!
+//! Every persisted value is wrapped in a [`SchemaEnvelope`] that records the schema
+//! name and version it was written with. When reading, older versions are upgraded
+//! step by step through the type's compatibility adapter, so data written by a
+//! previous SDK release keeps decoding after an upgrade. Payloads written before
+//! envelopes existed are treated as version `0`.
+//!
+//! ```rust
+//! use apex_sdk_core::schema::{from_versioned_json, to_versioned_json};
+//! use apex_sdk_core::BlockInfo;
+//!
+//! // A block persisted by an SDK release that predates the enhanced block fields
+//! let legacy = r#"{"number":7,"hash":"0x07","parent_hash":"0x06","timestamp":0,"transactions":["0xaa"]}"#;
+//! let block: BlockInfo = from_versioned_json(legacy).unwrap();
+//! assert_eq!(block.extrinsic_count, 1);
+//!
+//! let stored = to_versioned_json(&block).unwrap();
+//! let restored: BlockInfo = from_versioned_json(&stored).unwrap();
+//! assert_eq!(restored.number, 7);
+//! ```
+
+use crate::checkpoint::Checkpoint;
+use crate::{BlockEvent, BlockInfo, DetailedBlockInfo, ExtrinsicInfo};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+/// Version assigned to payloads persisted without a [`SchemaEnvelope`]
+pub const LEGACY_SCHEMA_VERSION: u32 = 0;
+
+/// Errors raised while encoding or decoding versioned data
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+    #[error("Schema mismatch: expected {expected}, found {found}")]
+    SchemaMismatch { expected: String, found: String },
+    #[error("Unsupported {schema} schema version {found} (current: {current})")]
+    UnsupportedVersion {
+        schema: String,
+        found: u32,
+        current: u32,
+    },
+    #[error("Migration error: {0}")]
+    Migration(String),
+}
+
+impl From<serde_json::Error> for SchemaError {
+    fn from(err: serde_json::Error) -> Self {
+        SchemaError::Serialization(err.to_string())
+    }
+}
+
+/// A type with an explicit, versioned persisted representation
+///
+/// Bump [`SCHEMA_VERSION`](Versioned::SCHEMA_VERSION) whenever the serialized shape
+/// changes and teach [`upgrade`](Versioned::upgrade) how to move a payload from the
+/// previous version to the next one.
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// Stable schema name recorded in the envelope
+    const SCHEMA_NAME: &'static str;
+
+    /// Current schema version written by this SDK release
+    const SCHEMA_VERSION: u32;
+
+    /// Upgrade a payload from `from_version` to `from_version + 1`
+    ///
+    /// The default implementation treats every older version as shape-compatible.
+    fn upgrade(from_version: u32, value: Value) -> Result<Value, SchemaError> {
+        let _ = from_version;
+        Ok(value)
+    }
+}
+
+/// Envelope stored alongside every versioned payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaEnvelope {
+    /// Schema name (see [`Versioned::SCHEMA_NAME`])
+    pub schema: String,
+    /// Schema version the payload was written with
+    pub version: u32,
+    /// The serialized payload
+    pub data: Value,
+}
+
+impl SchemaEnvelope {
+    /// Try to interpret a JSON value as an envelope
+    ///
+    /// Returns `None` for bare (pre-versioning) payloads.
+    fn detect(value: &Value) -> Option<Self> {
+        let object = value.as_object()?;
+        if object.len() != 3 || !object.contains_key("data") {
+            return None;
+        }
+        serde_json::from_value(value.clone()).ok()
+    }
+}
+
+/// Description of a registered schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaDescriptor {
+    /// Schema name
+    pub name: String,
+    /// Current version written by this SDK release
+    pub version: u32,
+}
+
+impl SchemaDescriptor {
+    fn of<T: Versioned>() -> Self {
+        Self {
+            name: T::SCHEMA_NAME.to_string(),
+            version: T::SCHEMA_VERSION,
+        }
+    }
+
+    /// Whether data written with `version` of this schema can be read
+    pub fn can_read(&self, version: u32) -> bool {
+        version <= self.version
+    }
+}
+
+/// List all schemas known to this SDK release with their current versions
+pub fn schema_registry() -> Vec<SchemaDescriptor> {
+    vec![
+        SchemaDescriptor::of::<BlockInfo>(),
+        SchemaDescriptor::of::<ExtrinsicInfo>(),
+        SchemaDescriptor::of::<BlockEvent>(),
+        SchemaDescriptor::of::<DetailedBlockInfo>(),
+        SchemaDescriptor::of::<Checkpoint>(),
+    ]
+}
+
+/// Wrap a value in a [`SchemaEnvelope`] at its current schema version
+pub fn to_versioned_value<T: Versioned>(value: &T) -> Result<Value, SchemaError> {
+    let envelope = SchemaEnvelope {
+        schema: T::SCHEMA_NAME.to_string(),
+        version: T::SCHEMA_VERSION,
+        data: serde_json::to_value(value)?,
+    };
+    Ok(serde_json::to_value(envelope)?)
+}
+
+/// Serialize a value as versioned JSON
+pub fn to_versioned_json<T: Versioned>(value: &T) -> Result<String, SchemaError> {
+    Ok(serde_json::to_string(&to_versioned_value(value)?)?)
+}
+
+/// Decode a versioned (or legacy bare) JSON value, upgrading it if needed
+pub fn from_versioned_value<T: Versioned>(value: Value) -> Result<T, SchemaError> {
+    let (version, data) = match SchemaEnvelope::detect(&value) {
+        Some(envelope) => {
+            if envelope.schema != T::SCHEMA_NAME {
+                return Err(SchemaError::SchemaMismatch {
+                    expected: T::SCHEMA_NAME.to_string(),
+                    found: envelope.schema,
+                });
+            }
+            (envelope.version, envelope.data)
+        }
+        None => (LEGACY_SCHEMA_VERSION, value),
+    };
+
+    let data = upgrade_to_current::<T>(version, data)?;
+    Ok(serde_json::from_value(data)?)
+}
+
+/// Decode versioned (or legacy bare) JSON, upgrading it if needed
+pub fn from_versioned_json<T: Versioned>(json: &str) -> Result<T, SchemaError> {
+    from_versioned_value(serde_json::from_str(json)?)
+}
+
+/// Run the compatibility adapters from `version` up to the current version
+pub fn upgrade_to_current<T: Versioned>(
+    mut version: u32,
+    mut data: Value,
+) -> Result<Value, SchemaError> {
+    if version > T::SCHEMA_VERSION {
+        return Err(SchemaError::UnsupportedVersion {
+            schema: T::SCHEMA_NAME.to_string(),
+            found: version,
+            current: T::SCHEMA_VERSION,
+        });
+    }
+
+    while version < T::SCHEMA_VERSION {
+        data = T::upgrade(version, data)?;
+        version += 1;
+    }
+
+    Ok(data)
+}
+
+impl Versioned for BlockInfo {
+    const SCHEMA_NAME: &'static str = "block_info";
+    const SCHEMA_VERSION: u32 = 1;
+
+    /// v0 -> v1 adds roots, counts and finality; `extrinsic_count` is derived
+    /// from the transaction list rather than left at zero.
+    fn upgrade(from_version: u32, mut value: Value) -> Result<Value, SchemaError> {
+        if from_version != 0 {
+            return Ok(value);
+        }
+
+        let object = value
+            .as_object_mut()
+            .ok_or_else(|| SchemaError::Migration("block_info v0 must be an object".into()))?;
+
+        let transaction_count = object
+            .get("transactions")
+            .and_then(Value::as_array)
+            .map(|txs| txs.len())
+            .unwrap_or(0);
+
+        object.entry("state_root").or_insert(Value::Null);
+        object.entry("extrinsics_root").or_insert(Value::Null);
+        object
+            .entry("extrinsic_count")
+            .or_insert_with(|| Value::from(transaction_count as u32));
+        object.entry("event_count").or_insert(Value::Null);
+        object.entry("is_finalized").or_insert(Value::Bool(false));
+
+        Ok(value)
+    }
+}
+
+impl Versioned for ExtrinsicInfo {
+    const SCHEMA_NAME: &'static str = "extrinsic_info";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl Versioned for BlockEvent {
+    const SCHEMA_NAME: &'static str = "block_event";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl Versioned for DetailedBlockInfo {
+    const SCHEMA_NAME: &'static str = "detailed_block_info";
+    const SCHEMA_VERSION: u32 = 1;
+
+    /// v0 -> v1 upgrades the embedded basic block info.
+    fn upgrade(from_version: u32, mut value: Value) -> Result<Value, SchemaError> {
+        if from_version != 0 {
+            return Ok(value);
+        }
+
+        if let Some(basic) = value.get_mut("basic") {
+            *basic = BlockInfo::upgrade(0, basic.take())?;
+        }
+
+        Ok(value)
+    }
+}
+
+impl Versioned for Checkpoint {
+    const SCHEMA_NAME: &'static str = "checkpoint";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_block() -> BlockInfo {
+        BlockInfo {
+            number: 42,
+            hash: "0x2a".to_string(),
+            parent_hash: "0x29".to_string(),
+            timestamp: 1_700_000_000,
+            transactions: vec!["0x01".to_string(), "0x02".to_string()],
+            state_root: Some("0xaa".to_string()),
+            extrinsics_root: Some("0xbb".to_string()),
+            extrinsic_count: 2,
+            event_count: Some(5),
+            is_finalized: true,
+        }
+    }
+
+    #[test]
+    fn test_block_info_round_trip() {
+        let block = sample_block();
+        let json = to_versioned_json(&block).unwrap();
+        let decoded: BlockInfo = from_versioned_json(&json).unwrap();
+
+        assert_eq!(decoded.number, block.number);
+        assert_eq!(decoded.state_root, block.state_root);
+        assert_eq!(decoded.event_count, block.event_count);
+        assert!(decoded.is_finalized);
+    }
+
+    #[test]
+    fn test_envelope_records_schema_and_version() {
+        let value = to_versioned_value(&sample_block()).unwrap();
+        assert_eq!(value["schema"], "block_info");
+        assert_eq!(value["version"], BlockInfo::SCHEMA_VERSION);
+        assert_eq!(value["data"]["number"], 42);
+    }
+
+    #[test]
+    fn test_legacy_block_info_is_upgraded() {
+        let legacy = r#"{
+            "number": 1,
+            "hash": "0x01",
+            "parent_hash": "0x00",
+            "timestamp": 10,
+            "transactions": ["0xa", "0xb", "0xc"]
+        }"#;
+
+        let block: BlockInfo = from_versioned_json(legacy).unwrap();
+        assert_eq!(block.extrinsic_count, 3);
+        assert_eq!(block.state_root, None);
+        assert!(!block.is_finalized);
+    }
+
+    #[test]
+    fn test_schema_mismatch_is_rejected() {
+        let json = to_versioned_json(&sample_block()).unwrap();
+        let result: Result<ExtrinsicInfo, _> = from_versioned_json(&json);
+
+        assert!(matches!(result, Err(SchemaError::SchemaMismatch { .. })));
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let json = serde_json::json!({
+            "schema": "block_info",
+            "version": BlockInfo::SCHEMA_VERSION + 1,
+            "data": {}
+        });
+        let result: Result<BlockInfo, _> = from_versioned_value(json);
+
+        assert!(matches!(
+            result,
+            Err(SchemaError::UnsupportedVersion { found: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_schema_registry() {
+        let registry = schema_registry();
+        let block = registry.iter().find(|s| s.name == "block_info").unwrap();
+
+        assert_eq!(block.version, BlockInfo::SCHEMA_VERSION);
+        assert!(block.can_read(0));
+        assert!(!block.can_read(block.version + 1));
+    }
+}
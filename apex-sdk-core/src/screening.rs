@@ -0,0 +1,202 @@
+//! Screening of transfer addresses against sanctions or block lists
+//!
+//! High-level transfer APIs call an [`AddressScreener`] with the source and
+//! destination of every transfer before it is signed, so integrators can plug
+//! in their compliance provider. A [`ScreeningResult::Blocked`] result stops
+//! the transfer. [`StaticListScreener`] is a reference implementation over a
+//! fixed list of addresses:
+//!
+//! ```rust
+//! use apex_sdk_core::screening::{AddressScreener, ScreeningResult, StaticListScreener};
+//!
+//! # async fn example() -> Result<(), apex_sdk_core::SdkError> {
+//! let screener = StaticListScreener::from_list(
+//!     "# OFAC SDN, 2026-10-01\n0x8589427373D6D84E98730D7795D8f6f8731FDA16\n",
+//! );
+//! let result = screener
+//!     .screen(
+//!         "0x742d35Cc6634C0532925a3b844Bc454e4438f44e",
+//!         "0x8589427373d6d84e98730d7795d8f6f8731fda16",
+//!     )
+//!     .await?;
+//! assert!(result.is_blocked());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::SdkError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Verdict on a transfer's addresses
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScreeningResult {
+    /// Neither address is listed
+    Clear,
+    /// An address is listed; the transfer must not go ahead
+    Blocked {
+        /// The listed address
+        address: String,
+        /// Why it is listed
+        reason: String,
+    },
+}
+
+impl ScreeningResult {
+    /// Whether the transfer must not go ahead
+    pub fn is_blocked(&self) -> bool {
+        matches!(self, ScreeningResult::Blocked { .. })
+    }
+}
+
+/// Compliance check of transfer addresses
+#[async_trait]
+pub trait AddressScreener: Send + Sync + std::fmt::Debug {
+    /// Screen a transfer from `source` to `destination`
+    ///
+    /// Errors mean the addresses could not be screened; callers treat them
+    /// as blocking.
+    async fn screen(&self, source: &str, destination: &str) -> Result<ScreeningResult, SdkError>;
+}
+
+/// Blocks transfers from or to a fixed set of addresses
+///
+/// Hex addresses match case-insensitively, and SS58 addresses match the hex
+/// of their account id, whatever their network prefix. Other formats match
+/// exactly.
+#[derive(Debug, Clone)]
+pub struct StaticListScreener {
+    listed: HashSet<String>,
+    reason: String,
+}
+
+impl StaticListScreener {
+    /// Screener blocking `addresses`
+    pub fn new<I, S>(addresses: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            listed: addresses
+                .into_iter()
+                .map(|address| normalize(address.as_ref()))
+                .collect(),
+            reason: "Address is on the block list".to_string(),
+        }
+    }
+
+    /// Screener blocking the addresses of a list with one address per line
+    ///
+    /// Blank lines and lines starting with `#` are skipped.
+    pub fn from_list(list: &str) -> Self {
+        Self::new(
+            list.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#')),
+        )
+    }
+
+    /// Reason reported for blocked transfers
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = reason.into();
+        self
+    }
+
+    /// Add an address to the list
+    pub fn insert(&mut self, address: &str) {
+        self.listed.insert(normalize(address));
+    }
+
+    /// Whether `address` is listed
+    pub fn contains(&self, address: &str) -> bool {
+        self.listed.contains(&normalize(address))
+    }
+
+    /// Number of listed addresses
+    pub fn len(&self) -> usize {
+        self.listed.len()
+    }
+
+    /// Whether no address is listed
+    pub fn is_empty(&self) -> bool {
+        self.listed.is_empty()
+    }
+}
+
+impl Default for StaticListScreener {
+    fn default() -> Self {
+        Self::new(std::iter::empty::<&str>())
+    }
+}
+
+#[async_trait]
+impl AddressScreener for StaticListScreener {
+    async fn screen(&self, source: &str, destination: &str) -> Result<ScreeningResult, SdkError> {
+        Ok([source, destination]
+            .into_iter()
+            .find(|address| self.contains(address))
+            .map_or(ScreeningResult::Clear, |address| ScreeningResult::Blocked {
+                address: address.to_string(),
+                reason: self.reason.clone(),
+            }))
+    }
+}
+
+fn normalize(address: &str) -> String {
+    let address = address.trim();
+    if address.starts_with("0x") || address.starts_with("0X") {
+        address.to_ascii_lowercase()
+    } else if let Some(account) = apex_sdk_types::ss58_account_id(address) {
+        format!("0x{}", hex::encode(account))
+    } else {
+        address.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_static_list() {
+        let screener = StaticListScreener::from_list(
+            "# test list\n\n0xABCDEF0000000000000000000000000000000001\n5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY\n",
+        )
+        .with_reason("sanctioned");
+        assert_eq!(screener.len(), 2);
+        assert!(screener.contains("0xabcdef0000000000000000000000000000000001"));
+        assert!(!screener.contains("5grwvaef5zxb26fz9rcqpdws57cterhpnehxcpcnohgkutqy"));
+
+        let blocked = screener
+            .screen(
+                "0x0000000000000000000000000000000000000002",
+                "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            blocked,
+            ScreeningResult::Blocked {
+                address: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string(),
+                reason: "sanctioned".to_string(),
+            }
+        );
+        // the same account with the Polkadot prefix, and as hex
+        assert!(screener.contains("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5"));
+        assert!(
+            screener.contains("0xD43593C715FDD31C61141ABD04A99FD6822C8558854CCDE39A5684E7A56DA27D")
+        );
+        assert_eq!(
+            screener
+                .screen(
+                    "0x0000000000000000000000000000000000000002",
+                    "0x0000000000000000000000000000000000000003"
+                )
+                .await
+                .unwrap(),
+            ScreeningResult::Clear
+        );
+    }
+}
@@ -0,0 +1,724 @@
+//! Indexer sinks for decoded chain data
+//!
+//! An [`IndexerSink`] receives decoded blocks and events as [`SinkRecord`]s and
+//! forwards them to external infrastructure. Records are encoded according to
+//! the sink's [`SinkConfig`] (JSON, or protobuf with the `protobuf` feature) and
+//! routed to the configured block or event topic.
+//!
+//! Publishers are behind optional features:
+//! - `kafka`: `KafkaSink` built on `rdkafka`
+//! - `nats`: `NatsSink` built on `async-nats`
+//!
+//! ```rust
+//! use apex_sdk_core::sink::{BlockRecord, SinkConfig, SinkRecord};
+//!
+//! let config = SinkConfig::new()
+//!     .with_block_topic("polkadot.blocks")
+//!     .with_event_topic("polkadot.events")
+//!     .with_topic_per_pallet(true);
+//!
+//! let record = SinkRecord::Block(BlockRecord::new(1, "0x01"));
+//! assert_eq!(config.topic_for(&record), "polkadot.blocks");
+//! assert_eq!(record.key(), "1");
+//! ```
+
+use crate::{BlockInfo, DetailedBlockInfo, ExtrinsicInfo, SdkError};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Default topic for block records
+pub const DEFAULT_BLOCK_TOPIC: &str = "apex.blocks";
+
+/// Default topic for event records
+pub const DEFAULT_EVENT_TOPIC: &str = "apex.events";
+
+/// Default topic for extrinsic records
+pub const DEFAULT_EXTRINSIC_TOPIC: &str = "apex.extrinsics";
+
+/// A decoded block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockRecord {
+    /// Block number
+    pub number: u64,
+    /// Block hash (hex)
+    pub hash: String,
+    /// Parent block hash (hex), if known
+    pub parent_hash: Option<String>,
+    /// Block timestamp, if known
+    pub timestamp: Option<u64>,
+    /// Number of extrinsics or transactions, if known
+    pub extrinsic_count: Option<u32>,
+    /// Number of events, if known
+    pub event_count: Option<u32>,
+}
+
+impl BlockRecord {
+    /// Create a record with only the block number and hash
+    pub fn new(number: u64, hash: impl Into<String>) -> Self {
+        Self {
+            number,
+            hash: hash.into(),
+            parent_hash: None,
+            timestamp: None,
+            extrinsic_count: None,
+            event_count: None,
+        }
+    }
+}
+
+impl From<&BlockInfo> for BlockRecord {
+    fn from(block: &BlockInfo) -> Self {
+        Self {
+            number: block.number,
+            hash: block.hash.clone(),
+            parent_hash: Some(block.parent_hash.clone()),
+            timestamp: Some(block.timestamp),
+            extrinsic_count: Some(block.extrinsic_count),
+            event_count: block.event_count,
+        }
+    }
+}
+
+/// A decoded event with its fields
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    /// Block the event was emitted in
+    pub block_number: u64,
+    /// Hash of that block (hex)
+    pub block_hash: String,
+    /// Index of the event within the block
+    pub index: u32,
+    /// Index of the extrinsic that emitted the event, if any
+    pub extrinsic_index: Option<u32>,
+    /// Pallet (or contract) name
+    pub pallet: String,
+    /// Event name
+    pub event: String,
+    /// Decoded event fields
+    pub fields: serde_json::Value,
+}
+
+/// A decoded extrinsic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtrinsicRecord {
+    /// Block including the extrinsic
+    pub block_number: u64,
+    /// Hash of that block (hex)
+    pub block_hash: String,
+    /// Index of the extrinsic within the block
+    pub index: u32,
+    /// Extrinsic hash (hex)
+    pub hash: String,
+    /// Signer address, if signed
+    pub signer: Option<String>,
+    /// Pallet name
+    pub pallet: String,
+    /// Call name
+    pub call: String,
+    /// Whether the extrinsic dispatched successfully
+    pub success: bool,
+}
+
+impl ExtrinsicRecord {
+    /// Record of an extrinsic in block `block_number`
+    pub fn new(block_number: u64, block_hash: impl Into<String>, info: &ExtrinsicInfo) -> Self {
+        Self {
+            block_number,
+            block_hash: block_hash.into(),
+            index: info.index,
+            hash: info.hash.clone(),
+            signer: info.signer.clone(),
+            pallet: info.pallet.clone(),
+            call: info.call.clone(),
+            success: info.success,
+        }
+    }
+}
+
+/// A record published by an [`IndexerSink`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SinkRecord {
+    /// A block
+    Block(BlockRecord),
+    /// An event
+    Event(EventRecord),
+    /// An extrinsic
+    Extrinsic(ExtrinsicRecord),
+}
+
+impl SinkRecord {
+    /// Block number the record belongs to
+    pub fn block_number(&self) -> u64 {
+        match self {
+            SinkRecord::Block(block) => block.number,
+            SinkRecord::Event(event) => event.block_number,
+            SinkRecord::Extrinsic(extrinsic) => extrinsic.block_number,
+        }
+    }
+
+    /// Block, extrinsic and event records of a detailed block
+    ///
+    /// Events of a [`DetailedBlockInfo`] carry no fields, so theirs are null.
+    pub fn from_detailed_block(block: &DetailedBlockInfo) -> Vec<SinkRecord> {
+        let basic = &block.basic;
+        let mut record = BlockRecord::from(basic);
+        record.event_count = Some(block.events.len() as u32);
+
+        std::iter::once(SinkRecord::Block(record))
+            .chain(block.extrinsics.iter().map(|extrinsic| {
+                SinkRecord::Extrinsic(ExtrinsicRecord::new(basic.number, &basic.hash, extrinsic))
+            }))
+            .chain(block.events.iter().map(|event| {
+                SinkRecord::Event(EventRecord {
+                    block_number: basic.number,
+                    block_hash: basic.hash.clone(),
+                    index: event.index,
+                    extrinsic_index: event.extrinsic_index,
+                    pallet: event.pallet.clone(),
+                    event: event.event.clone(),
+                    fields: serde_json::Value::Null,
+                })
+            }))
+            .collect()
+    }
+
+    /// Partitioning key; records of one block share a key so they stay ordered
+    pub fn key(&self) -> String {
+        self.block_number().to_string()
+    }
+}
+
+/// Wire format of published records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Serialization {
+    /// JSON (`SinkRecord` serialized with serde)
+    #[default]
+    Json,
+    /// Protobuf (see the `proto` module for the message definitions)
+    #[cfg(feature = "protobuf")]
+    Protobuf,
+}
+
+/// Topic routing and encoding for a sink
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SinkConfig {
+    /// Topic (Kafka) or subject (NATS) for block records
+    pub block_topic: String,
+    /// Topic (Kafka) or subject (NATS) for event records
+    pub event_topic: String,
+    /// Topic (Kafka) or subject (NATS) for extrinsic records
+    pub extrinsic_topic: String,
+    /// Publish events to `<event_topic>.<pallet>` instead of a single topic
+    pub topic_per_pallet: bool,
+    /// Wire format
+    pub serialization: Serialization,
+}
+
+impl Default for SinkConfig {
+    fn default() -> Self {
+        Self {
+            block_topic: DEFAULT_BLOCK_TOPIC.to_string(),
+            event_topic: DEFAULT_EVENT_TOPIC.to_string(),
+            extrinsic_topic: DEFAULT_EXTRINSIC_TOPIC.to_string(),
+            topic_per_pallet: false,
+            serialization: Serialization::default(),
+        }
+    }
+}
+
+impl SinkConfig {
+    /// Create a configuration with default topics and JSON encoding
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the block topic
+    pub fn with_block_topic(mut self, topic: impl Into<String>) -> Self {
+        self.block_topic = topic.into();
+        self
+    }
+
+    /// Set the event topic
+    pub fn with_event_topic(mut self, topic: impl Into<String>) -> Self {
+        self.event_topic = topic.into();
+        self
+    }
+
+    /// Set the extrinsic topic
+    pub fn with_extrinsic_topic(mut self, topic: impl Into<String>) -> Self {
+        self.extrinsic_topic = topic.into();
+        self
+    }
+
+    /// Route events to one topic per pallet
+    pub fn with_topic_per_pallet(mut self, enabled: bool) -> Self {
+        self.topic_per_pallet = enabled;
+        self
+    }
+
+    /// Set the wire format
+    pub fn with_serialization(mut self, serialization: Serialization) -> Self {
+        self.serialization = serialization;
+        self
+    }
+
+    /// Topic a record is published to
+    pub fn topic_for(&self, record: &SinkRecord) -> String {
+        match record {
+            SinkRecord::Block(_) => self.block_topic.clone(),
+            SinkRecord::Event(event) if self.topic_per_pallet => {
+                format!("{}.{}", self.event_topic, event.pallet)
+            }
+            SinkRecord::Event(_) => self.event_topic.clone(),
+            SinkRecord::Extrinsic(_) => self.extrinsic_topic.clone(),
+        }
+    }
+
+    /// Encode a record in the configured wire format
+    pub fn encode(&self, record: &SinkRecord) -> Result<Vec<u8>, SdkError> {
+        match self.serialization {
+            Serialization::Json => serde_json::to_vec(record)
+                .map_err(|e| SdkError::ProviderError(format!("Failed to encode record: {}", e))),
+            #[cfg(feature = "protobuf")]
+            Serialization::Protobuf => Ok(proto::encode(record)),
+        }
+    }
+}
+
+/// Destination for decoded blocks and events
+#[async_trait]
+pub trait IndexerSink: Send + Sync {
+    /// Publish records in order
+    async fn write(&self, records: &[SinkRecord]) -> Result<(), SdkError>;
+
+    /// Wait until previously written records are delivered
+    async fn flush(&self) -> Result<(), SdkError> {
+        Ok(())
+    }
+}
+
+/// Protobuf messages for published records
+///
+/// Equivalent `.proto` definition:
+///
+/// ```text
+/// message BlockRecord {
+///   uint64 number = 1;
+///   string hash = 2;
+///   optional string parent_hash = 3;
+///   optional uint64 timestamp = 4;
+///   optional uint32 extrinsic_count = 5;
+///   optional uint32 event_count = 6;
+/// }
+///
+/// message EventRecord {
+///   uint64 block_number = 1;
+///   string block_hash = 2;
+///   uint32 index = 3;
+///   optional uint32 extrinsic_index = 4;
+///   string pallet = 5;
+///   string event = 6;
+///   string fields_json = 7;
+/// }
+///
+/// message ExtrinsicRecord {
+///   uint64 block_number = 1;
+///   string block_hash = 2;
+///   uint32 index = 3;
+///   string hash = 4;
+///   optional string signer = 5;
+///   string pallet = 6;
+///   string call = 7;
+///   bool success = 8;
+/// }
+///
+/// message SinkRecord {
+///   oneof record {
+///     BlockRecord block = 1;
+///     EventRecord event = 2;
+///     ExtrinsicRecord extrinsic = 3;
+///   }
+/// }
+/// ```
+#[cfg(feature = "protobuf")]
+pub mod proto {
+    use prost::Message;
+
+    /// Protobuf form of [`super::BlockRecord`]
+    #[derive(Clone, PartialEq, Message)]
+    pub struct BlockRecord {
+        #[prost(uint64, tag = "1")]
+        pub number: u64,
+        #[prost(string, tag = "2")]
+        pub hash: String,
+        #[prost(string, optional, tag = "3")]
+        pub parent_hash: Option<String>,
+        #[prost(uint64, optional, tag = "4")]
+        pub timestamp: Option<u64>,
+        #[prost(uint32, optional, tag = "5")]
+        pub extrinsic_count: Option<u32>,
+        #[prost(uint32, optional, tag = "6")]
+        pub event_count: Option<u32>,
+    }
+
+    /// Protobuf form of [`super::EventRecord`]; fields are carried as JSON
+    #[derive(Clone, PartialEq, Message)]
+    pub struct EventRecord {
+        #[prost(uint64, tag = "1")]
+        pub block_number: u64,
+        #[prost(string, tag = "2")]
+        pub block_hash: String,
+        #[prost(uint32, tag = "3")]
+        pub index: u32,
+        #[prost(uint32, optional, tag = "4")]
+        pub extrinsic_index: Option<u32>,
+        #[prost(string, tag = "5")]
+        pub pallet: String,
+        #[prost(string, tag = "6")]
+        pub event: String,
+        #[prost(string, tag = "7")]
+        pub fields_json: String,
+    }
+
+    /// Protobuf form of [`super::ExtrinsicRecord`]
+    #[derive(Clone, PartialEq, Message)]
+    pub struct ExtrinsicRecord {
+        #[prost(uint64, tag = "1")]
+        pub block_number: u64,
+        #[prost(string, tag = "2")]
+        pub block_hash: String,
+        #[prost(uint32, tag = "3")]
+        pub index: u32,
+        #[prost(string, tag = "4")]
+        pub hash: String,
+        #[prost(string, optional, tag = "5")]
+        pub signer: Option<String>,
+        #[prost(string, tag = "6")]
+        pub pallet: String,
+        #[prost(string, tag = "7")]
+        pub call: String,
+        #[prost(bool, tag = "8")]
+        pub success: bool,
+    }
+
+    /// Protobuf form of [`super::SinkRecord`]
+    #[derive(Clone, PartialEq, Message)]
+    pub struct SinkRecord {
+        #[prost(oneof = "Record", tags = "1, 2, 3")]
+        pub record: Option<Record>,
+    }
+
+    /// Record variants
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Record {
+        #[prost(message, tag = "1")]
+        Block(BlockRecord),
+        #[prost(message, tag = "2")]
+        Event(EventRecord),
+        #[prost(message, tag = "3")]
+        Extrinsic(ExtrinsicRecord),
+    }
+
+    impl From<&super::SinkRecord> for SinkRecord {
+        fn from(record: &super::SinkRecord) -> Self {
+            let record = match record {
+                super::SinkRecord::Block(block) => Record::Block(BlockRecord {
+                    number: block.number,
+                    hash: block.hash.clone(),
+                    parent_hash: block.parent_hash.clone(),
+                    timestamp: block.timestamp,
+                    extrinsic_count: block.extrinsic_count,
+                    event_count: block.event_count,
+                }),
+                super::SinkRecord::Event(event) => Record::Event(EventRecord {
+                    block_number: event.block_number,
+                    block_hash: event.block_hash.clone(),
+                    index: event.index,
+                    extrinsic_index: event.extrinsic_index,
+                    pallet: event.pallet.clone(),
+                    event: event.event.clone(),
+                    fields_json: event.fields.to_string(),
+                }),
+                super::SinkRecord::Extrinsic(extrinsic) => Record::Extrinsic(ExtrinsicRecord {
+                    block_number: extrinsic.block_number,
+                    block_hash: extrinsic.block_hash.clone(),
+                    index: extrinsic.index,
+                    hash: extrinsic.hash.clone(),
+                    signer: extrinsic.signer.clone(),
+                    pallet: extrinsic.pallet.clone(),
+                    call: extrinsic.call.clone(),
+                    success: extrinsic.success,
+                }),
+            };
+            SinkRecord {
+                record: Some(record),
+            }
+        }
+    }
+
+    /// Encode a record as a protobuf `SinkRecord` message
+    pub fn encode(record: &super::SinkRecord) -> Vec<u8> {
+        SinkRecord::from(record).encode_to_vec()
+    }
+}
+
+/// Publishes records to Kafka
+#[cfg(feature = "kafka")]
+pub struct KafkaSink {
+    producer: rdkafka::producer::FutureProducer,
+    config: SinkConfig,
+    timeout: std::time::Duration,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaSink {
+    /// Connect a producer to `brokers` (comma-separated `host:port` list)
+    pub fn new(brokers: &str, config: SinkConfig) -> Result<Self, SdkError> {
+        let mut client_config = rdkafka::ClientConfig::new();
+        client_config.set("bootstrap.servers", brokers);
+        Self::from_client_config(&client_config, config)
+    }
+
+    /// Create a producer from a full `rdkafka` client configuration
+    pub fn from_client_config(
+        client_config: &rdkafka::ClientConfig,
+        config: SinkConfig,
+    ) -> Result<Self, SdkError> {
+        let producer = client_config.create().map_err(|e| {
+            SdkError::ConfigError(format!("Failed to create Kafka producer: {}", e))
+        })?;
+        Ok(Self {
+            producer,
+            config,
+            timeout: std::time::Duration::from_secs(30),
+        })
+    }
+
+    /// Set how long a send may wait for space in the producer queue
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[cfg(feature = "kafka")]
+#[async_trait]
+impl IndexerSink for KafkaSink {
+    async fn write(&self, records: &[SinkRecord]) -> Result<(), SdkError> {
+        for record in records {
+            let topic = self.config.topic_for(record);
+            let key = record.key();
+            let payload = self.config.encode(record)?;
+            let message = rdkafka::producer::FutureRecord::to(&topic)
+                .key(&key)
+                .payload(&payload);
+
+            self.producer
+                .send(message, self.timeout)
+                .await
+                .map_err(|(e, _)| {
+                    SdkError::NetworkError(format!("Failed to publish to {}: {}", topic, e))
+                })?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), SdkError> {
+        use rdkafka::producer::Producer;
+
+        let producer = self.producer.clone();
+        let timeout = self.timeout;
+        tokio::task::spawn_blocking(move || producer.flush(timeout))
+            .await
+            .map_err(|e| SdkError::NetworkError(format!("Kafka flush task failed: {}", e)))?
+            .map_err(|e| SdkError::NetworkError(format!("Failed to flush Kafka producer: {}", e)))
+    }
+}
+
+/// Publishes records to NATS subjects
+#[cfg(feature = "nats")]
+pub struct NatsSink {
+    client: async_nats::Client,
+    config: SinkConfig,
+}
+
+#[cfg(feature = "nats")]
+impl NatsSink {
+    /// Connect to a NATS server
+    pub async fn connect(url: &str, config: SinkConfig) -> Result<Self, SdkError> {
+        let client = async_nats::connect(url)
+            .await
+            .map_err(|e| SdkError::NetworkError(format!("Failed to connect to NATS: {}", e)))?;
+        Ok(Self::from_client(client, config))
+    }
+
+    /// Use an existing NATS client
+    pub fn from_client(client: async_nats::Client, config: SinkConfig) -> Self {
+        Self { client, config }
+    }
+}
+
+#[cfg(feature = "nats")]
+#[async_trait]
+impl IndexerSink for NatsSink {
+    async fn write(&self, records: &[SinkRecord]) -> Result<(), SdkError> {
+        for record in records {
+            let subject = self.config.topic_for(record);
+            let payload = self.config.encode(record)?;
+            self.client
+                .publish(subject.clone(), payload.into())
+                .await
+                .map_err(|e| {
+                    SdkError::NetworkError(format!("Failed to publish to {}: {}", subject, e))
+                })?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), SdkError> {
+        self.client
+            .flush()
+            .await
+            .map_err(|e| SdkError::NetworkError(format!("Failed to flush NATS client: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(pallet: &str) -> SinkRecord {
+        SinkRecord::Event(EventRecord {
+            block_number: 7,
+            block_hash: "0x07".to_string(),
+            index: 2,
+            extrinsic_index: Some(1),
+            pallet: pallet.to_string(),
+            event: "Transfer".to_string(),
+            fields: serde_json::json!({ "amount": "100" }),
+        })
+    }
+
+    #[test]
+    fn test_topic_routing() {
+        let config = SinkConfig::new();
+        assert_eq!(config.topic_for(&event("Balances")), DEFAULT_EVENT_TOPIC);
+
+        let config = config
+            .with_event_topic("dot.events")
+            .with_topic_per_pallet(true);
+        assert_eq!(config.topic_for(&event("Balances")), "dot.events.Balances");
+        assert_eq!(
+            config.topic_for(&SinkRecord::Block(BlockRecord::new(7, "0x07"))),
+            DEFAULT_BLOCK_TOPIC
+        );
+        assert_eq!(event("Balances").key(), "7");
+    }
+
+    #[test]
+    fn test_json_encoding() {
+        let bytes = SinkConfig::new().encode(&event("Balances")).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(value["kind"], "event");
+        assert_eq!(value["pallet"], "Balances");
+        assert_eq!(value["fields"]["amount"], "100");
+
+        let decoded: SinkRecord = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, event("Balances"));
+    }
+
+    #[test]
+    fn test_block_record_from_block_info() {
+        let info = BlockInfo {
+            number: 3,
+            hash: "0x03".to_string(),
+            parent_hash: "0x02".to_string(),
+            timestamp: 1_700_000_000,
+            transactions: vec![],
+            state_root: None,
+            extrinsics_root: None,
+            extrinsic_count: 4,
+            event_count: Some(9),
+            is_finalized: true,
+        };
+        let record = BlockRecord::from(&info);
+
+        assert_eq!(record.parent_hash.as_deref(), Some("0x02"));
+        assert_eq!(record.extrinsic_count, Some(4));
+        assert_eq!(record.event_count, Some(9));
+    }
+
+    #[test]
+    fn test_records_from_detailed_block() {
+        let block = DetailedBlockInfo {
+            basic: BlockInfo {
+                number: 3,
+                hash: "0x03".to_string(),
+                parent_hash: "0x02".to_string(),
+                timestamp: 1_700_000_000,
+                transactions: vec![],
+                state_root: None,
+                extrinsics_root: None,
+                extrinsic_count: 1,
+                event_count: None,
+                is_finalized: true,
+            },
+            extrinsics: vec![ExtrinsicInfo {
+                index: 0,
+                hash: "0xaa".to_string(),
+                signed: false,
+                signer: None,
+                pallet: "Timestamp".to_string(),
+                call: "set".to_string(),
+                success: true,
+            }],
+            events: vec![crate::BlockEvent {
+                index: 0,
+                extrinsic_index: Some(0),
+                pallet: "System".to_string(),
+                event: "ExtrinsicSuccess".to_string(),
+            }],
+        };
+        let records = SinkRecord::from_detailed_block(&block);
+        assert_eq!(records.len(), 3);
+
+        let config = SinkConfig::new();
+        let topics: Vec<_> = records.iter().map(|r| config.topic_for(r)).collect();
+        assert_eq!(
+            topics,
+            vec![
+                DEFAULT_BLOCK_TOPIC,
+                DEFAULT_EXTRINSIC_TOPIC,
+                DEFAULT_EVENT_TOPIC
+            ]
+        );
+        match &records[1] {
+            SinkRecord::Extrinsic(extrinsic) => {
+                assert_eq!(extrinsic.block_hash, "0x03");
+                assert_eq!(extrinsic.call, "set");
+            }
+            other => panic!("unexpected record: {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "protobuf")]
+    #[test]
+    fn test_protobuf_encoding() {
+        use prost::Message;
+
+        let config = SinkConfig::new().with_serialization(Serialization::Protobuf);
+        let bytes = config.encode(&event("Balances")).unwrap();
+        let decoded = proto::SinkRecord::decode(bytes.as_slice()).unwrap();
+
+        match decoded.record {
+            Some(proto::Record::Event(event)) => {
+                assert_eq!(event.block_number, 7);
+                assert_eq!(event.extrinsic_index, Some(1));
+                assert_eq!(event.fields_json, r#"{"amount":"100"}"#);
+            }
+            other => panic!("unexpected record: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! Embedded SQLite store for indexed chain data
+//!
+//! [`SqliteStore`] is an [`IndexerSink`] keeping the records it receives in
+//! three tables of a local SQLite database, so indexed data can be analysed
+//! with plain SQL and no infrastructure:
+//!
+//! | Table | Columns |
+//! |-------|---------|
+//! | `blocks` | `number`, `hash`, `parent_hash`, `timestamp`, `extrinsic_count`, `event_count` |
+//! | `extrinsics` | `block_number`, `block_hash`, `extrinsic_index`, `hash`, `signer`, `pallet`, `call`, `success` |
+//! | `events` | `block_number`, `block_hash`, `event_index`, `extrinsic_index`, `pallet`, `event`, `fields` |
+//!
+//! Event fields are stored as JSON text, so SQLite's JSON functions reach into
+//! them. Rows are keyed by block number and index; writing a record again
+//! replaces it. [`SqliteStore::query_sql`] runs any query and returns its rows
+//! as JSON objects:
+//!
+//! ```rust,no_run
+//! use apex_sdk_core::sqlite::SqliteStore;
+//!
+//! # fn example() -> Result<(), apex_sdk_core::SdkError> {
+//! let store = SqliteStore::open("./chain.db")?;
+//! let rows = store.query_sql(
+//!     "SELECT e.pallet, x.signer, json_extract(e.fields, '$.amount') AS amount
+//!      FROM events e JOIN extrinsics x USING (block_number, extrinsic_index)
+//!      WHERE e.event = 'Transfer'",
+//! )?;
+//! for row in rows {
+//!     println!("{}", row);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The store also answers [`EventHistory`] queries up to the highest block it
+//! holds, so it can serve as the indexed half of a
+//! [`HybridHistory`](crate::history::HybridHistory).
+
+use crate::history::{EventFilter, EventHistory};
+use crate::sink::{EventRecord, IndexerSink, SinkRecord};
+use crate::SdkError;
+use async_trait::async_trait;
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value as JsonValue;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS blocks (
+    number INTEGER PRIMARY KEY,
+    hash TEXT NOT NULL,
+    parent_hash TEXT,
+    timestamp INTEGER,
+    extrinsic_count INTEGER,
+    event_count INTEGER
+);
+CREATE TABLE IF NOT EXISTS extrinsics (
+    block_number INTEGER NOT NULL,
+    block_hash TEXT NOT NULL,
+    extrinsic_index INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+    signer TEXT,
+    pallet TEXT NOT NULL,
+    call TEXT NOT NULL,
+    success INTEGER NOT NULL,
+    PRIMARY KEY (block_number, extrinsic_index)
+);
+CREATE TABLE IF NOT EXISTS events (
+    block_number INTEGER NOT NULL,
+    block_hash TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    extrinsic_index INTEGER,
+    pallet TEXT NOT NULL,
+    event TEXT NOT NULL,
+    fields TEXT NOT NULL,
+    PRIMARY KEY (block_number, event_index)
+);
+CREATE INDEX IF NOT EXISTS events_by_name ON events (pallet, event);
+";
+
+/// Blocks, extrinsics and events in a SQLite database
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Open or create the database at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SdkError> {
+        let path = path.as_ref();
+        let connection = Connection::open(path).map_err(|e| {
+            SdkError::ConfigError(format!("Failed to open {}: {}", path.display(), e))
+        })?;
+        Self::with_connection(connection)
+    }
+
+    /// Database that lives only as long as the store
+    pub fn in_memory() -> Result<Self, SdkError> {
+        let connection = Connection::open_in_memory()
+            .map_err(|e| SdkError::ConfigError(format!("Failed to open database: {}", e)))?;
+        Self::with_connection(connection)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, SdkError> {
+        connection.execute_batch(SCHEMA).map_err(sql_error)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Run `sql` and return its rows as JSON objects keyed by column name
+    ///
+    /// Blobs are returned as hex strings.
+    pub fn query_sql(&self, sql: &str) -> Result<Vec<JsonValue>, SdkError> {
+        let connection = self.lock();
+        let mut statement = connection.prepare(sql).map_err(sql_error)?;
+        let columns: Vec<String> = statement
+            .column_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let mut rows = statement.query([]).map_err(sql_error)?;
+
+        let mut result = Vec::new();
+        while let Some(row) = rows.next().map_err(sql_error)? {
+            let mut object = serde_json::Map::new();
+            for (index, column) in columns.iter().enumerate() {
+                let value = row.get_ref(index).map_err(sql_error)?;
+                object.insert(column.clone(), json_value(value));
+            }
+            result.push(JsonValue::Object(object));
+        }
+        Ok(result)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl IndexerSink for SqliteStore {
+    async fn write(&self, records: &[SinkRecord]) -> Result<(), SdkError> {
+        let mut connection = self.lock();
+        let transaction = connection.transaction().map_err(sql_error)?;
+        for record in records {
+            match record {
+                SinkRecord::Block(block) => transaction.execute(
+                    "INSERT OR REPLACE INTO blocks VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        block.number as i64,
+                        block.hash,
+                        block.parent_hash,
+                        block.timestamp.map(|timestamp| timestamp as i64),
+                        block.extrinsic_count,
+                        block.event_count,
+                    ],
+                ),
+                SinkRecord::Extrinsic(extrinsic) => transaction.execute(
+                    "INSERT OR REPLACE INTO extrinsics VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        extrinsic.block_number as i64,
+                        extrinsic.block_hash,
+                        extrinsic.index,
+                        extrinsic.hash,
+                        extrinsic.signer,
+                        extrinsic.pallet,
+                        extrinsic.call,
+                        extrinsic.success,
+                    ],
+                ),
+                SinkRecord::Event(event) => transaction.execute(
+                    "INSERT OR REPLACE INTO events VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        event.block_number as i64,
+                        event.block_hash,
+                        event.index,
+                        event.extrinsic_index,
+                        event.pallet,
+                        event.event,
+                        event.fields.to_string(),
+                    ],
+                ),
+            }
+            .map_err(sql_error)?;
+        }
+        transaction.commit().map_err(sql_error)
+    }
+}
+
+#[async_trait]
+impl EventHistory for SqliteStore {
+    async fn events(&self, filter: &EventFilter) -> Result<Vec<EventRecord>, SdkError> {
+        filter.validate()?;
+        let connection = self.lock();
+        let mut statement = connection
+            .prepare(
+                "SELECT block_number, block_hash, event_index, extrinsic_index, pallet, event, fields
+                 FROM events
+                 WHERE block_number BETWEEN ?1 AND ?2
+                   AND (?3 IS NULL OR pallet = ?3 COLLATE NOCASE)
+                   AND (?4 IS NULL OR event = ?4)
+                 ORDER BY block_number, event_index
+                 LIMIT ?5",
+            )
+            .map_err(sql_error)?;
+        let limit = filter
+            .limit
+            .map_or(-1, |limit| i64::try_from(limit).unwrap_or(i64::MAX));
+        let rows = statement
+            .query_map(
+                params![
+                    filter.from_block as i64,
+                    filter.to_block.min(i64::MAX as u64) as i64,
+                    filter.pallet,
+                    filter.event,
+                    limit,
+                ],
+                |row| {
+                    let fields: String = row.get(6)?;
+                    Ok(EventRecord {
+                        block_number: row.get::<_, i64>(0)? as u64,
+                        block_hash: row.get(1)?,
+                        index: row.get(2)?,
+                        extrinsic_index: row.get(3)?,
+                        pallet: row.get(4)?,
+                        event: row.get(5)?,
+                        fields: serde_json::from_str(&fields).unwrap_or(JsonValue::Null),
+                    })
+                },
+            )
+            .map_err(sql_error)?;
+        rows.collect::<Result<_, _>>().map_err(sql_error)
+    }
+
+    async fn indexed_height(&self) -> Result<Option<u64>, SdkError> {
+        self.lock()
+            .query_row("SELECT MAX(number) FROM blocks", [], |row| {
+                row.get::<_, Option<i64>>(0)
+            })
+            .optional()
+            .map(|height| height.flatten().map(|height| height as u64))
+            .map_err(sql_error)
+    }
+}
+
+impl std::fmt::Debug for SqliteStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteStore").finish_non_exhaustive()
+    }
+}
+
+fn sql_error(e: rusqlite::Error) -> SdkError {
+    SdkError::ProviderError(format!("SQLite error: {}", e))
+}
+
+fn json_value(value: ValueRef<'_>) -> JsonValue {
+    match value {
+        ValueRef::Null => JsonValue::Null,
+        ValueRef::Integer(n) => JsonValue::from(n),
+        ValueRef::Real(n) => JsonValue::from(n),
+        ValueRef::Text(text) => JsonValue::String(String::from_utf8_lossy(text).into_owned()),
+        ValueRef::Blob(bytes) => JsonValue::String(format!("0x{}", hex::encode(bytes))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::{BlockRecord, ExtrinsicRecord};
+    use serde_json::json;
+
+    fn records(number: u64) -> Vec<SinkRecord> {
+        let hash = format!("0x{:02x}", number);
+        vec![
+            SinkRecord::Block(BlockRecord::new(number, hash.clone())),
+            SinkRecord::Extrinsic(ExtrinsicRecord {
+                block_number: number,
+                block_hash: hash.clone(),
+                index: 1,
+                hash: "0xaa".to_string(),
+                signer: Some("0x01".to_string()),
+                pallet: "Balances".to_string(),
+                call: "transfer_keep_alive".to_string(),
+                success: true,
+            }),
+            SinkRecord::Event(EventRecord {
+                block_number: number,
+                block_hash: hash,
+                index: 0,
+                extrinsic_index: Some(1),
+                pallet: "Balances".to_string(),
+                event: "Transfer".to_string(),
+                fields: json!({ "amount": number * 100 }),
+            }),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_query_sql_joins_tables() {
+        let store = SqliteStore::in_memory().unwrap();
+        store.write(&records(1)).await.unwrap();
+        store.write(&records(2)).await.unwrap();
+        // writing a block again replaces its rows
+        store.write(&records(2)).await.unwrap();
+
+        let rows = store
+            .query_sql(
+                "SELECT e.block_number, x.signer, json_extract(e.fields, '$.amount') AS amount
+                 FROM events e JOIN extrinsics x USING (block_number, extrinsic_index)
+                 ORDER BY e.block_number",
+            )
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                json!({"block_number": 1, "signer": "0x01", "amount": 100}),
+                json!({"block_number": 2, "signer": "0x01", "amount": 200}),
+            ]
+        );
+        assert!(store.query_sql("SELECT * FROM nowhere").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_event_history() {
+        let store = SqliteStore::in_memory().unwrap();
+        assert_eq!(store.indexed_height().await.unwrap(), None);
+        for number in 1..=3 {
+            store.write(&records(number)).await.unwrap();
+        }
+        assert_eq!(store.indexed_height().await.unwrap(), Some(3));
+
+        let filter = EventFilter::new(2, 3).with_pallet("balances");
+        let events = store.events(&filter).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].block_number, 2);
+        assert_eq!(events[0].fields, json!({ "amount": 200 }));
+
+        let limited = store.events(&filter.with_limit(1)).await.unwrap();
+        assert_eq!(limited.len(), 1);
+        let none = store
+            .events(&EventFilter::new(1, 3).with_event("Deposit"))
+            .await
+            .unwrap();
+        assert!(none.is_empty());
+    }
+}
@@ -0,0 +1,403 @@
+//! Bounded stream channels with configurable backpressure.
+//!
+//! Every subscription stream is backed by a bounded queue so a slow consumer can
+//! never cause unbounded memory growth. What happens when the queue is full is
+//! decided by the [`OverflowPolicy`] in the stream's [`StreamConfig`]. By
+//! default the producer waits for the consumer, so no item is lost; dropped
+//! items are only possible when a lossy policy is chosen explicitly, and are
+//! counted in [`StreamReceiver::dropped_count`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use apex_sdk_core::stream::{bounded_stream, OverflowPolicy, StreamConfig};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let config = StreamConfig::new()
+//!     .with_capacity(2)
+//!     .with_overflow_policy(OverflowPolicy::DropOldest);
+//! let (sender, mut receiver) = bounded_stream(config);
+//!
+//! for i in 0..3 {
+//!     sender.send(i).await.unwrap();
+//! }
+//!
+//! // The oldest item was evicted to make room for the newest one
+//! assert_eq!(receiver.recv().await, Some(1));
+//! assert_eq!(receiver.dropped_count(), 1);
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::sync::Notify;
+
+/// Default number of items buffered per stream
+pub const DEFAULT_STREAM_CAPACITY: usize = 100;
+
+/// What a stream does when its buffer is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Evict the oldest buffered item to make room for the new one
+    DropOldest,
+    /// Wait until the consumer frees up space
+    #[default]
+    Block,
+    /// Reject the new item with [`StreamSendError::Full`]
+    Error,
+}
+
+/// Configuration for subscription streams
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamConfig {
+    /// Maximum number of buffered items
+    pub capacity: usize,
+    /// Behavior when the buffer is full
+    pub overflow_policy: OverflowPolicy,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_STREAM_CAPACITY,
+            overflow_policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+impl StreamConfig {
+    /// Create a new stream configuration with default values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the buffer capacity (a capacity of zero is treated as one)
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Set the overflow policy
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+}
+
+/// Error returned when an item cannot be pushed into a stream
+///
+/// The rejected item is handed back to the caller.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum StreamSendError<T> {
+    #[error("Stream closed: receiver dropped")]
+    Closed(T),
+    #[error("Stream full: consumer is not keeping up")]
+    Full(T),
+}
+
+impl<T> StreamSendError<T> {
+    /// Recover the item that could not be sent
+    pub fn into_inner(self) -> T {
+        match self {
+            StreamSendError::Closed(item) | StreamSendError::Full(item) => item,
+        }
+    }
+}
+
+struct Shared<T> {
+    queue: Mutex<VecDeque<T>>,
+    config: StreamConfig,
+    item_ready: Notify,
+    space_ready: Notify,
+    senders: AtomicUsize,
+    receiver_alive: AtomicBool,
+    dropped: AtomicU64,
+}
+
+/// Create a bounded stream channel
+pub fn bounded_stream<T>(config: StreamConfig) -> (StreamSender<T>, StreamReceiver<T>) {
+    let capacity = config.capacity.max(1);
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(VecDeque::with_capacity(capacity)),
+        config: StreamConfig { capacity, ..config },
+        item_ready: Notify::new(),
+        space_ready: Notify::new(),
+        senders: AtomicUsize::new(1),
+        receiver_alive: AtomicBool::new(true),
+        dropped: AtomicU64::new(0),
+    });
+
+    (
+        StreamSender {
+            shared: shared.clone(),
+        },
+        StreamReceiver { shared },
+    )
+}
+
+/// Producing half of a bounded stream
+pub struct StreamSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> StreamSender<T> {
+    /// Push an item, applying the configured overflow policy
+    ///
+    /// With [`OverflowPolicy::Block`] this waits until the consumer frees up space.
+    pub async fn send(&self, mut item: T) -> Result<(), StreamSendError<T>> {
+        loop {
+            let space_ready = self.shared.space_ready.notified();
+            tokio::pin!(space_ready);
+            space_ready.as_mut().enable();
+
+            match self.try_send(item) {
+                Err(StreamSendError::Full(rejected))
+                    if self.shared.config.overflow_policy == OverflowPolicy::Block =>
+                {
+                    item = rejected;
+                    space_ready.await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Push an item without waiting
+    ///
+    /// Under [`OverflowPolicy::Block`] a full buffer returns
+    /// [`StreamSendError::Full`] instead of waiting.
+    pub fn try_send(&self, item: T) -> Result<(), StreamSendError<T>> {
+        if !self.shared.receiver_alive.load(Ordering::SeqCst) {
+            return Err(StreamSendError::Closed(item));
+        }
+
+        {
+            let mut queue = self.shared.queue.lock().unwrap_or_else(|e| e.into_inner());
+            if queue.len() >= self.shared.config.capacity {
+                match self.shared.config.overflow_policy {
+                    OverflowPolicy::DropOldest => {
+                        queue.pop_front();
+                        self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                        tracing::debug!("Stream buffer full, dropped oldest item");
+                    }
+                    OverflowPolicy::Block | OverflowPolicy::Error => {
+                        return Err(StreamSendError::Full(item));
+                    }
+                }
+            }
+            queue.push_back(item);
+        }
+
+        self.shared.item_ready.notify_one();
+        Ok(())
+    }
+
+    /// Check whether the receiver has been dropped
+    pub fn is_closed(&self) -> bool {
+        !self.shared.receiver_alive.load(Ordering::SeqCst)
+    }
+
+    /// Get the stream configuration
+    pub fn config(&self) -> &StreamConfig {
+        &self.shared.config
+    }
+}
+
+impl<T> Clone for StreamSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::SeqCst);
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for StreamSender<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.shared.item_ready.notify_one();
+        }
+    }
+}
+
+/// Consuming half of a bounded stream
+pub struct StreamReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> StreamReceiver<T> {
+    /// Receive the next item
+    ///
+    /// Returns `None` once every sender has been dropped and the buffer is drained.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            // Read the sender count before the queue so an item pushed right before
+            // the last sender drops is never missed.
+            let closed = self.shared.senders.load(Ordering::SeqCst) == 0;
+
+            if let Some(item) = self.try_recv() {
+                return Some(item);
+            }
+            if closed {
+                return None;
+            }
+
+            self.shared.item_ready.notified().await;
+        }
+    }
+
+    /// Receive the next item if one is buffered
+    pub fn try_recv(&mut self) -> Option<T> {
+        let item = self
+            .shared
+            .queue
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop_front();
+
+        if item.is_some() {
+            self.shared.space_ready.notify_one();
+        }
+        item
+    }
+
+    /// Number of items currently buffered
+    pub fn len(&self) -> usize {
+        self.shared
+            .queue
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+
+    /// Check whether the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of items evicted under [`OverflowPolicy::DropOldest`]
+    pub fn dropped_count(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Get the stream configuration
+    pub fn config(&self) -> &StreamConfig {
+        &self.shared.config
+    }
+}
+
+impl<T> Drop for StreamReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.receiver_alive.store(false, Ordering::SeqCst);
+        self.shared.space_ready.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_stream_config_defaults() {
+        let config = StreamConfig::default();
+        assert_eq!(config.capacity, DEFAULT_STREAM_CAPACITY);
+        assert_eq!(config.overflow_policy, OverflowPolicy::Block);
+
+        let config = StreamConfig::new().with_capacity(0);
+        assert_eq!(config.capacity, 1);
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_policy() {
+        let config = StreamConfig::new()
+            .with_capacity(2)
+            .with_overflow_policy(OverflowPolicy::DropOldest);
+        let (sender, mut receiver) = bounded_stream(config);
+
+        for i in 0..5 {
+            sender.send(i).await.unwrap();
+        }
+
+        assert_eq!(receiver.len(), 2);
+        assert_eq!(receiver.dropped_count(), 3);
+        assert_eq!(receiver.recv().await, Some(3));
+        assert_eq!(receiver.recv().await, Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_error_policy() {
+        let config = StreamConfig::new()
+            .with_capacity(1)
+            .with_overflow_policy(OverflowPolicy::Error);
+        let (sender, mut receiver) = bounded_stream(config);
+
+        sender.send(1).await.unwrap();
+        let result = sender.send(2).await;
+        assert_eq!(result, Err(StreamSendError::Full(2)));
+
+        assert_eq!(receiver.recv().await, Some(1));
+        assert!(sender.send(3).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_block_policy_waits_for_space() {
+        let config = StreamConfig::new()
+            .with_capacity(1)
+            .with_overflow_policy(OverflowPolicy::Block);
+        let (sender, mut receiver) = bounded_stream(config);
+
+        sender.send(1).await.unwrap();
+        assert_eq!(sender.try_send(2), Err(StreamSendError::Full(2)));
+
+        let blocked = tokio::time::timeout(Duration::from_millis(50), sender.send(2)).await;
+        assert!(blocked.is_err());
+
+        let producer = tokio::spawn(async move {
+            sender.send(2).await.unwrap();
+            sender.send(3).await.unwrap();
+        });
+
+        assert_eq!(receiver.recv().await, Some(1));
+        assert_eq!(receiver.recv().await, Some(2));
+        assert_eq!(receiver.recv().await, Some(3));
+        assert_eq!(receiver.recv().await, None);
+        assert_eq!(receiver.dropped_count(), 0);
+
+        producer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_blocked_sender_released_when_receiver_dropped() {
+        let config = StreamConfig::new()
+            .with_capacity(1)
+            .with_overflow_policy(OverflowPolicy::Block);
+        let (sender, receiver) = bounded_stream(config);
+        sender.send("first").await.unwrap();
+
+        let producer = tokio::spawn(async move { sender.send("second").await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(receiver);
+
+        let result = producer.await.unwrap();
+        assert_eq!(result, Err(StreamSendError::Closed("second")));
+    }
+
+    #[tokio::test]
+    async fn test_buffer_drained_after_senders_dropped() {
+        let (sender, mut receiver) = bounded_stream(StreamConfig::default());
+        let second = sender.clone();
+
+        sender.send(1).await.unwrap();
+        second.send(2).await.unwrap();
+        drop(sender);
+        drop(second);
+
+        assert_eq!(receiver.recv().await, Some(1));
+        assert_eq!(receiver.recv().await, Some(2));
+        assert_eq!(receiver.recv().await, None);
+    }
+}
@@ -0,0 +1,275 @@
+//! Opt-in, anonymous usage statistics
+//!
+//! [`Telemetry`] counts which SDK features are used and on which chains, and
+//! hands the aggregate as a [`UsageReport`] to a [`TelemetryReporter`]. It is
+//! off unless a reporter is configured: [`Telemetry::default`] collects and
+//! sends nothing. Reports only hold the SDK version, chain genesis hashes and
+//! feature counters; never addresses, keys, endpoints or transaction data.
+//! Setting the `DO_NOT_TRACK` environment variable disables reporting even
+//! when a reporter is configured.
+//!
+//! With the `telemetry` feature, `HttpReporter` posts reports as JSON to an
+//! endpoint of your choice. There is no built-in endpoint.
+//!
+//! ```rust
+//! use apex_sdk_core::telemetry::{Telemetry, TelemetryReporter, UsageReport};
+//! use apex_sdk_core::SdkError;
+//!
+//! struct LogReporter;
+//!
+//! #[async_trait::async_trait]
+//! impl TelemetryReporter for LogReporter {
+//!     async fn report(&self, report: &UsageReport) -> Result<(), SdkError> {
+//!         println!("{}", serde_json::to_string(report).unwrap());
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let telemetry = Telemetry::new(LogReporter);
+//! telemetry.record_chain("0x91b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c3");
+//! telemetry.record_feature("execute.substrate");
+//! assert_eq!(telemetry.snapshot().features["execute.substrate"], 1);
+//!
+//! assert!(!Telemetry::default().is_enabled());
+//! ```
+
+use crate::SdkError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+/// Version of the SDK reported by default
+pub const SDK_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Environment variable that disables reporting when set
+pub const DO_NOT_TRACK_VAR: &str = "DO_NOT_TRACK";
+
+/// Aggregated, anonymous usage since the last report
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// SDK version
+    pub sdk_version: String,
+    /// Genesis hashes (or EVM chain ids) of the chains used
+    pub chains: BTreeSet<String>,
+    /// Uses per feature
+    pub features: BTreeMap<String, u64>,
+}
+
+impl UsageReport {
+    /// Whether nothing was used since the last report
+    pub fn is_empty(&self) -> bool {
+        self.chains.is_empty() && self.features.is_empty()
+    }
+
+    fn merge(&mut self, other: UsageReport) {
+        self.chains.extend(other.chains);
+        for (feature, count) in other.features {
+            *self.features.entry(feature).or_default() += count;
+        }
+    }
+}
+
+/// Destination of usage reports
+#[async_trait]
+pub trait TelemetryReporter: Send + Sync {
+    /// Deliver one report
+    async fn report(&self, report: &UsageReport) -> Result<(), SdkError>;
+}
+
+/// Usage counters with an optional reporter; disabled by default
+#[derive(Clone, Default)]
+pub struct Telemetry {
+    reporter: Option<Arc<dyn TelemetryReporter>>,
+    sdk_version: Option<String>,
+    usage: Arc<Mutex<UsageReport>>,
+}
+
+impl Telemetry {
+    /// Telemetry that reports to `reporter`
+    pub fn new(reporter: impl TelemetryReporter + 'static) -> Self {
+        Self {
+            reporter: Some(Arc::new(reporter)),
+            ..Self::default()
+        }
+    }
+
+    /// Telemetry that records and sends nothing
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Report this version instead of [`SDK_VERSION`]
+    pub fn with_sdk_version(mut self, version: impl Into<String>) -> Self {
+        self.sdk_version = Some(version.into());
+        self
+    }
+
+    /// Whether usage is recorded and reported
+    ///
+    /// False without a reporter or when `DO_NOT_TRACK` is set.
+    pub fn is_enabled(&self) -> bool {
+        self.reporter.is_some() && std::env::var_os(DO_NOT_TRACK_VAR).is_none()
+    }
+
+    /// Record use of the chain with this genesis hash or chain id
+    pub fn record_chain(&self, genesis: &str) {
+        if self.is_enabled() {
+            self.lock().chains.insert(genesis.to_ascii_lowercase());
+        }
+    }
+
+    /// Record one use of a feature
+    pub fn record_feature(&self, feature: &str) {
+        if self.is_enabled() {
+            *self.lock().features.entry(feature.to_string()).or_default() += 1;
+        }
+    }
+
+    /// Usage recorded since the last successful flush
+    pub fn snapshot(&self) -> UsageReport {
+        UsageReport {
+            sdk_version: self.version(),
+            ..self.lock().clone()
+        }
+    }
+
+    /// Send the recorded usage and reset the counters
+    ///
+    /// Nothing is sent when disabled or when nothing was recorded. If the
+    /// reporter fails, the usage is kept for the next flush.
+    pub async fn flush(&self) -> Result<(), SdkError> {
+        let Some(reporter) = self.reporter.as_ref().filter(|_| self.is_enabled()) else {
+            return Ok(());
+        };
+        let mut report = std::mem::take(&mut *self.lock());
+        if report.is_empty() {
+            return Ok(());
+        }
+        report.sdk_version = self.version();
+
+        let result = reporter.report(&report).await;
+        if result.is_err() {
+            self.lock().merge(report);
+        }
+        result
+    }
+
+    fn version(&self) -> String {
+        self.sdk_version
+            .clone()
+            .unwrap_or_else(|| SDK_VERSION.to_string())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, UsageReport> {
+        self.usage.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl std::fmt::Debug for Telemetry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Telemetry")
+            .field("enabled", &self.is_enabled())
+            .field("sdk_version", &self.version())
+            .finish()
+    }
+}
+
+/// Posts reports as JSON to an HTTP endpoint
+#[cfg(feature = "telemetry")]
+#[derive(Debug, Clone)]
+pub struct HttpReporter {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "telemetry")]
+impl HttpReporter {
+    /// Reporter posting to `endpoint`
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Use an existing HTTP client
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+}
+
+#[cfg(feature = "telemetry")]
+#[async_trait]
+impl TelemetryReporter for HttpReporter {
+    async fn report(&self, report: &UsageReport) -> Result<(), SdkError> {
+        self.client
+            .post(&self.endpoint)
+            .json(report)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| SdkError::NetworkError(format!("Failed to send telemetry: {}", e)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        reports: Mutex<Vec<UsageReport>>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl TelemetryReporter for Arc<Recorder> {
+        async fn report(&self, report: &UsageReport) -> Result<(), SdkError> {
+            if self.fail {
+                return Err(SdkError::NetworkError("offline".to_string()));
+            }
+            self.reports.lock().unwrap().push(report.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disabled_by_default() {
+        let telemetry = Telemetry::default();
+        telemetry.record_feature("execute.evm");
+        assert!(telemetry.snapshot().is_empty());
+        assert!(telemetry.flush().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_flush_reports_and_resets() {
+        if std::env::var_os(DO_NOT_TRACK_VAR).is_some() {
+            return;
+        }
+        let recorder = Arc::new(Recorder::default());
+        let telemetry = Telemetry::new(recorder.clone()).with_sdk_version("1.2.3");
+        telemetry.record_chain("0xABCD");
+        telemetry.record_feature("execute.substrate");
+        telemetry.record_feature("execute.substrate");
+        telemetry.flush().await.unwrap();
+
+        let reports = recorder.reports.lock().unwrap().clone();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].sdk_version, "1.2.3");
+        assert!(reports[0].chains.contains("0xabcd"));
+        assert_eq!(reports[0].features["execute.substrate"], 2);
+        assert!(telemetry.snapshot().is_empty());
+
+        // failed reports are kept for the next flush
+        let failing = Telemetry::new(Arc::new(Recorder {
+            fail: true,
+            ..Recorder::default()
+        }));
+        failing.record_feature("execute.evm");
+        assert!(failing.flush().await.is_err());
+        assert_eq!(failing.snapshot().features["execute.evm"], 1);
+    }
+}
@@ -0,0 +1,153 @@
+//! Compatibility tests for persisted core types
+//!
+//! The fixtures below are frozen copies of data written by earlier SDK releases.
+//! They must keep decoding after every upgrade; never edit an existing fixture,
+//! add a new one alongside it when a schema version is bumped.
+
+use apex_sdk_core::schema::{
+    from_versioned_json, schema_registry, to_versioned_json, SchemaError, Versioned,
+};
+use apex_sdk_core::{BlockEvent, BlockInfo, DetailedBlockInfo, ExtrinsicInfo};
+
+/// `BlockInfo` as cached by 0.1.x releases before schema envelopes existed
+const BLOCK_INFO_V0: &str = r#"{
+    "number": 18000000,
+    "hash": "0x91b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c3",
+    "parent_hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+    "timestamp": 1700000000,
+    "transactions": ["0x01", "0x02"]
+}"#;
+
+/// `BlockInfo` written with schema version 1
+const BLOCK_INFO_V1: &str = r#"{
+    "schema": "block_info",
+    "version": 1,
+    "data": {
+        "number": 18000000,
+        "hash": "0x91b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c3",
+        "parent_hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+        "timestamp": 1700000000,
+        "transactions": ["0x01", "0x02"],
+        "state_root": "0xaa",
+        "extrinsics_root": "0xbb",
+        "extrinsic_count": 2,
+        "event_count": 4,
+        "is_finalized": true
+    }
+}"#;
+
+/// `ExtrinsicInfo` as cached before schema envelopes existed
+const EXTRINSIC_INFO_V0: &str = r#"{
+    "index": 1,
+    "hash": "0x02",
+    "signed": true,
+    "signer": "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d",
+    "pallet": "Balances",
+    "call": "transfer_keep_alive",
+    "success": true
+}"#;
+
+/// `DetailedBlockInfo` as cached before schema envelopes existed
+const DETAILED_BLOCK_INFO_V0: &str = r#"{
+    "basic": {
+        "number": 5,
+        "hash": "0x05",
+        "parent_hash": "0x04",
+        "timestamp": 0,
+        "transactions": ["0x01"]
+    },
+    "extrinsics": [],
+    "events": [
+        { "index": 0, "extrinsic_index": 0, "pallet": "System", "event": "ExtrinsicSuccess" }
+    ]
+}"#;
+
+#[test]
+fn test_block_info_v0_fixture_decodes() {
+    let block: BlockInfo = from_versioned_json(BLOCK_INFO_V0).unwrap();
+
+    assert_eq!(block.number, 18_000_000);
+    assert_eq!(block.transactions.len(), 2);
+    assert_eq!(block.extrinsic_count, 2);
+    assert_eq!(block.event_count, None);
+    assert!(!block.is_finalized);
+}
+
+#[test]
+fn test_block_info_v1_fixture_decodes() {
+    let block: BlockInfo = from_versioned_json(BLOCK_INFO_V1).unwrap();
+
+    assert_eq!(block.number, 18_000_000);
+    assert_eq!(block.state_root.as_deref(), Some("0xaa"));
+    assert_eq!(block.event_count, Some(4));
+    assert!(block.is_finalized);
+}
+
+#[test]
+fn test_extrinsic_info_v0_fixture_decodes() {
+    let extrinsic: ExtrinsicInfo = from_versioned_json(EXTRINSIC_INFO_V0).unwrap();
+
+    assert_eq!(extrinsic.pallet, "Balances");
+    assert_eq!(extrinsic.call, "transfer_keep_alive");
+    assert!(extrinsic.signed);
+}
+
+#[test]
+fn test_detailed_block_info_v0_fixture_decodes() {
+    let detailed: DetailedBlockInfo = from_versioned_json(DETAILED_BLOCK_INFO_V0).unwrap();
+
+    assert_eq!(detailed.basic.number, 5);
+    assert_eq!(detailed.basic.extrinsic_count, 1);
+    assert_eq!(detailed.events.len(), 1);
+}
+
+#[test]
+fn test_upgraded_data_round_trips_at_current_version() {
+    let block: BlockInfo = from_versioned_json(BLOCK_INFO_V0).unwrap();
+    let stored = to_versioned_json(&block).unwrap();
+
+    let value: serde_json::Value = serde_json::from_str(&stored).unwrap();
+    assert_eq!(value["version"], BlockInfo::SCHEMA_VERSION);
+
+    let restored: BlockInfo = from_versioned_json(&stored).unwrap();
+    assert_eq!(restored.number, block.number);
+    assert_eq!(restored.extrinsic_count, block.extrinsic_count);
+}
+
+#[test]
+fn test_block_event_round_trip() {
+    let event = BlockEvent {
+        index: 3,
+        extrinsic_index: None,
+        pallet: "Staking".to_string(),
+        event: "Rewarded".to_string(),
+    };
+
+    let stored = to_versioned_json(&event).unwrap();
+    let restored: BlockEvent = from_versioned_json(&stored).unwrap();
+
+    assert_eq!(restored.index, 3);
+    assert_eq!(restored.extrinsic_index, None);
+    assert_eq!(restored.event, "Rewarded");
+}
+
+#[test]
+fn test_wrong_schema_is_rejected() {
+    let result: Result<ExtrinsicInfo, _> = from_versioned_json(BLOCK_INFO_V1);
+    assert!(matches!(result, Err(SchemaError::SchemaMismatch { .. })));
+}
+
+#[test]
+fn test_registry_covers_persisted_types() {
+    let names: Vec<String> = schema_registry().into_iter().map(|s| s.name).collect();
+
+    for expected in [
+        "block_info",
+        "extrinsic_info",
+        "block_event",
+        "detailed_block_info",
+        "checkpoint",
+    ] {
+        assert!(names.iter().any(|n| n == expected), "missing {}", expected);
+    }
+}
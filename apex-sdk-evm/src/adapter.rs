@@ -6,8 +6,8 @@ use crate::{
 };
 use alloy::providers::Provider as AlloyProvider;
 use apex_sdk_core::{
-    ChainAdapter, ConfirmationStrategy, ReceiptWatcher, RetryConfig, SdkError, TimeoutConfig,
-    TransactionPipeline, TransactionResult,
+    ChainAdapter, ClientConfig, ConfirmationStrategy, ReceiptWatcher, RetryConfig, SdkError,
+    TimeoutConfig, TransactionPipeline, TransactionResult,
 };
 use apex_sdk_types::{Address, TransactionStatus};
 use async_trait::async_trait;
@@ -33,7 +33,16 @@ pub struct EvmAdapter {
 impl EvmAdapter {
     /// Create a new EVM adapter
     pub async fn new(rpc_url: &str, chain_name: &str) -> Result<Self, Error> {
-        let provider = EvmProvider::new(rpc_url).await?;
+        Self::new_with_client_config(rpc_url, chain_name, &ClientConfig::default()).await
+    }
+
+    /// Create a new EVM adapter that authenticates with the given client configuration
+    pub async fn new_with_client_config(
+        rpc_url: &str,
+        chain_name: &str,
+        client_config: &ClientConfig,
+    ) -> Result<Self, Error> {
+        let provider = EvmProvider::new_with_client_config(rpc_url, client_config).await?;
 
         Ok(Self {
             provider,
@@ -48,6 +57,14 @@ impl EvmAdapter {
         Self::new(rpc_url, "EVM").await
     }
 
+    /// Connect to an EVM chain with credentials and custom headers
+    pub async fn connect_with_client_config(
+        rpc_url: &str,
+        client_config: &ClientConfig,
+    ) -> Result<Self, Error> {
+        Self::new_with_client_config(rpc_url, "EVM", client_config).await
+    }
+
     /// Configure the adapter with a signer and create the transaction pipeline
     pub fn with_signer(mut self, signer: EvmSigner) -> Self {
         let provider_clone = self.provider.clone();
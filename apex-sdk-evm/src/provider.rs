@@ -2,8 +2,13 @@
 
 use crate::{AlloyHttpProvider, Error};
 use alloy::primitives::Address as EthAddress;
-use alloy::providers::{Provider, ProviderBuilder};
-use apex_sdk_core::{BlockInfo, Provider as CoreProvider, SdkError};
+use alloy::providers::{IpcConnect, Provider, ProviderBuilder};
+use alloy::rpc::client::RpcClient;
+use alloy::transports::http::reqwest::{
+    self,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use apex_sdk_core::{ipc_path, BlockInfo, ClientConfig, Provider as CoreProvider, SdkError};
 use apex_sdk_types::Address;
 use async_trait::async_trait;
 use std::str::FromStr;
@@ -19,11 +24,22 @@ pub struct EvmProvider {
 impl EvmProvider {
     /// Create a new EVM provider
     pub async fn new(rpc_url: &str) -> Result<Self, Error> {
-        let provider = ProviderBuilder::new().connect_http(
-            rpc_url
-                .parse()
-                .map_err(|e| Error::Connection(format!("Invalid URL: {}", e)))?,
-        );
+        Self::new_with_client_config(rpc_url, &ClientConfig::default()).await
+    }
+
+    /// Create a new EVM provider that sends credentials and custom headers
+    /// with every request
+    ///
+    /// IPC endpoints (`ipc://` URLs, `.ipc` socket paths and named pipes) connect
+    /// over the local socket; the client configuration does not apply to them.
+    pub async fn new_with_client_config(
+        rpc_url: &str,
+        client_config: &ClientConfig,
+    ) -> Result<Self, Error> {
+        let provider = match ipc_path(rpc_url) {
+            Some(path) => connect_ipc_provider(path).await?,
+            None => connect_http_provider(rpc_url, client_config)?,
+        };
 
         let chain_id = provider
             .get_chain_id()
@@ -55,6 +71,73 @@ impl EvmProvider {
     }
 }
 
+/// Build a provider over a unix domain socket or Windows named pipe
+async fn connect_ipc_provider(path: &str) -> Result<AlloyHttpProvider, Error> {
+    ProviderBuilder::new()
+        .connect_ipc(IpcConnect::new(path.to_string()))
+        .await
+        .map_err(|e| Error::Connection(format!("Failed to connect to IPC socket {}: {}", path, e)))
+}
+
+/// Build an HTTP provider, applying headers, proxy and TLS settings when configured
+fn connect_http_provider(
+    rpc_url: &str,
+    client_config: &ClientConfig,
+) -> Result<AlloyHttpProvider, Error> {
+    let url = rpc_url
+        .parse()
+        .map_err(|e| Error::Connection(format!("Invalid URL: {}", e)))?;
+
+    if client_config.is_empty() {
+        return Ok(ProviderBuilder::new().connect_http(url));
+    }
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in client_config.headers() {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| Error::Connection(format!("Invalid header name {}: {}", name, e)))?;
+        let mut header_value = HeaderValue::from_str(&value)
+            .map_err(|e| Error::Connection(format!("Invalid value for header {}: {}", name, e)))?;
+        header_value.set_sensitive(true);
+        headers.insert(header_name, header_value);
+    }
+
+    let mut builder = reqwest::Client::builder().default_headers(headers);
+
+    if let Some(proxy_config) = &client_config.proxy {
+        proxy_config
+            .scheme()
+            .map_err(|e| Error::Connection(e.to_string()))?;
+        let mut proxy = reqwest::Proxy::all(&proxy_config.url)
+            .map_err(|e| Error::Connection(format!("Invalid proxy {}: {}", proxy_config.url, e)))?;
+        if let Some((username, password)) = &proxy_config.credentials {
+            proxy = proxy.basic_auth(username, password);
+        }
+        builder = builder.proxy(proxy);
+    }
+
+    if !client_config.tls.is_default() {
+        let mut certificates = Vec::new();
+        for pem in client_config.tls.trusted_certificates() {
+            let bundle = reqwest::Certificate::from_pem_bundle(pem)
+                .map_err(|e| Error::Connection(format!("Invalid certificate: {}", e)))?;
+            certificates.extend(bundle);
+        }
+
+        builder = if client_config.tls.is_pinned() {
+            builder.tls_certs_only(certificates)
+        } else {
+            builder.tls_certs_merge(certificates)
+        };
+    }
+
+    let http_client = builder
+        .build()
+        .map_err(|e| Error::Connection(format!("Failed to build HTTP client: {}", e)))?;
+
+    Ok(ProviderBuilder::new().connect_client(RpcClient::new_http_with_client(http_client, url)))
+}
+
 #[async_trait]
 impl CoreProvider for EvmProvider {
     async fn get_block_number(&self) -> Result<u64, SdkError> {
@@ -165,4 +248,134 @@ mod tests {
             assert!(provider.is_ok());
         }
     }
+
+    #[tokio::test]
+    async fn test_provider_sends_configured_headers() {
+        use wiremock::matchers::{header, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("x-api-key", "secret-key"))
+            .and(header("x-client", "apex"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "result": "0x1"
+            })))
+            .mount(&server)
+            .await;
+
+        let client_config = ClientConfig::new()
+            .with_api_key("x-api-key", "secret-key")
+            .with_header("x-client", "apex");
+        let provider = EvmProvider::new_with_client_config(&server.uri(), &client_config)
+            .await
+            .unwrap();
+
+        assert_eq!(provider.chain_id(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_provider_connects_through_http_proxy() {
+        use apex_sdk_core::ProxyConfig;
+        use wiremock::matchers::{header, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // A plain HTTP proxy receives the request with the target in absolute form
+        let proxy = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("host", "rpc.invalid"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "result": "0x5"
+            })))
+            .mount(&proxy)
+            .await;
+
+        let client_config = ClientConfig::new().with_proxy(ProxyConfig::new(proxy.uri()));
+        let provider = EvmProvider::new_with_client_config("http://rpc.invalid", &client_config)
+            .await
+            .unwrap();
+
+        assert_eq!(provider.chain_id(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_provider_rejects_invalid_proxy_and_certificates() {
+        use apex_sdk_core::{ProxyConfig, TlsConfig};
+
+        let client_config = ClientConfig::new().with_proxy(ProxyConfig::new("ftp://proxy:21"));
+        let result =
+            EvmProvider::new_with_client_config("http://localhost:8545", &client_config).await;
+        assert!(matches!(result, Err(Error::Connection(_))));
+
+        let client_config =
+            ClientConfig::new().with_tls(TlsConfig::new().with_pinned_certificate("not a pem"));
+        let result =
+            EvmProvider::new_with_client_config("https://localhost:8545", &client_config).await;
+        assert!(matches!(result, Err(Error::Connection(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_provider_connects_over_ipc_socket() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let path = std::env::temp_dir().join(format!("apex-evm-{}.ipc", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+        // Answer every request with chain id 0x2a, echoing the request id
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = Vec::new();
+            let mut chunk = [0u8; 1024];
+            loop {
+                let read = socket.read(&mut chunk).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                buffer.extend_from_slice(&chunk[..read]);
+                let mut requests =
+                    serde_json::Deserializer::from_slice(&buffer).into_iter::<serde_json::Value>();
+                let mut consumed = 0;
+                while let Some(Ok(request)) = requests.next() {
+                    consumed = requests.byte_offset();
+                    let response = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "result": "0x2a"
+                    });
+                    socket
+                        .write_all(response.to_string().as_bytes())
+                        .await
+                        .unwrap();
+                }
+                buffer.drain(..consumed);
+            }
+        });
+
+        let endpoint = format!("ipc://{}", path.display());
+        let provider = EvmProvider::new(&endpoint).await.unwrap();
+        assert_eq!(provider.chain_id(), 42);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_provider_reports_missing_ipc_socket() {
+        let result = EvmProvider::new("ipc:///nonexistent/apex/node.ipc").await;
+        assert!(matches!(result, Err(Error::Connection(msg)) if msg.contains("IPC")));
+    }
+
+    #[tokio::test]
+    async fn test_provider_rejects_invalid_header() {
+        let client_config = ClientConfig::new().with_header("bad header", "value");
+        let result =
+            EvmProvider::new_with_client_config("http://localhost:8545", &client_config).await;
+
+        assert!(matches!(result, Err(Error::Connection(_))));
+    }
 }
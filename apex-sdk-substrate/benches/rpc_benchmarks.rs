@@ -0,0 +1,191 @@
+//! End-to-end benchmarks of the RPC query paths against a live node
+//!
+//! Start a local dev node first, e.g. `docker compose up substrate-node`, then
+//! `cargo bench --bench rpc_benchmarks`. `SUBSTRATE_RPC_URL` selects another
+//! node and `APEX_BENCH_BLOCK` pins the measured block; by default it is ten
+//! blocks behind the finalized head when the run starts. Without a reachable
+//! node the benchmarks are skipped.
+//!
+//! Each path is measured twice: over a plain connection, and over one with a
+//! warm response cache, which leaves only decoding and the requests that are
+//! not pinned to a block hash. The difference between the two is time spent
+//! on the wire.
+
+use apex_sdk_core::{ClientConfig, ResponseCache, ResponseCacheConfig};
+use apex_sdk_substrate::{ChainConfig, EventQuery, SubstrateAdapter};
+use criterion::{criterion_group, criterion_main, Bencher, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+/// Blocks behind the finalized head measured when no block is pinned
+const DEFAULT_DEPTH: u64 = 10;
+
+/// Connection to the benchmarked node
+struct Node {
+    runtime: Runtime,
+    plain: SubstrateAdapter,
+    cached: SubstrateAdapter,
+    block: u64,
+}
+
+impl Node {
+    /// Connect to the node, or `None` when it is not running
+    fn connect() -> Option<Self> {
+        let endpoint = std::env::var("SUBSTRATE_RPC_URL")
+            .unwrap_or_else(|_| "ws://localhost:9944".to_string());
+        let runtime = Runtime::new().expect("Failed to start tokio runtime");
+
+        let connected = runtime.block_on(async {
+            let plain = SubstrateAdapter::connect(&endpoint).await?;
+            let cache = ResponseCache::new(ResponseCacheConfig::new());
+            let cached = SubstrateAdapter::connect_with_config(
+                ChainConfig::custom("Substrate", &endpoint, 42)
+                    .with_client_config(ClientConfig::new().with_response_cache(cache)),
+            )
+            .await?;
+            let head = plain
+                .client()
+                .blocks()
+                .at_latest()
+                .await
+                .map_err(|e| apex_sdk_substrate::Error::Connection(e.to_string()))?
+                .number() as u64;
+            Ok::<_, apex_sdk_substrate::Error>((plain, cached, head))
+        });
+        let (plain, cached, head) = match connected {
+            Ok(connected) => connected,
+            Err(e) => {
+                eprintln!("Skipping RPC benchmarks, no node at {}: {}", endpoint, e);
+                return None;
+            }
+        };
+
+        let block = std::env::var("APEX_BENCH_BLOCK")
+            .ok()
+            .and_then(|block| block.parse().ok())
+            .unwrap_or_else(|| head.saturating_sub(DEFAULT_DEPTH));
+        Some(Self {
+            runtime,
+            plain,
+            cached,
+            block,
+        })
+    }
+
+    /// Both connections with the label they are reported under
+    fn adapters(&self) -> [(&'static str, &SubstrateAdapter); 2] {
+        [("plain", &self.plain), ("warm_cache", &self.cached)]
+    }
+}
+
+// ============================================================================
+// Block Query Benchmarks
+// ============================================================================
+
+fn benchmark_block_queries(c: &mut Criterion, node: &Node) {
+    let mut group = c.benchmark_group("rpc_block_queries");
+    group.measurement_time(Duration::from_secs(10));
+
+    for (label, adapter) in node.adapters() {
+        let block_query = adapter.block_query();
+
+        group.bench_with_input(
+            BenchmarkId::new("get_block_by_number", label),
+            &node.block,
+            |b: &mut Bencher, &block| {
+                b.iter(|| {
+                    black_box(
+                        node.runtime
+                            .block_on(block_query.get_block_by_number(block))
+                            .expect("Failed to fetch block"),
+                    )
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("get_block_detailed", label),
+            &node.block,
+            |b: &mut Bencher, &block| {
+                b.iter(|| {
+                    black_box(
+                        node.runtime
+                            .block_on(adapter.get_block_detailed(block))
+                            .expect("Failed to fetch detailed block"),
+                    )
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+// ============================================================================
+// Event Scanning Benchmarks
+// ============================================================================
+
+fn benchmark_event_scanning(c: &mut Criterion, node: &Node) {
+    let mut group = c.benchmark_group("rpc_event_scanning");
+    group.measurement_time(Duration::from_secs(15));
+    group.sample_size(20);
+
+    for (label, adapter) in node.adapters() {
+        for range in [1u64, 10] {
+            let from = node.block.saturating_sub(range - 1);
+            group.throughput(Throughput::Elements(range));
+
+            group.bench_with_input(
+                BenchmarkId::new(format!("all_events_{}", label), range),
+                &from,
+                |b: &mut Bencher, &from| {
+                    let query = EventQuery::new().between(from, node.block);
+                    b.iter(|| {
+                        black_box(
+                            node.runtime
+                                .block_on(query.run(adapter))
+                                .expect("Failed to scan events"),
+                        )
+                    })
+                },
+            );
+
+            group.bench_with_input(
+                BenchmarkId::new(format!("system_events_{}", label), range),
+                &from,
+                |b: &mut Bencher, &from| {
+                    let query = EventQuery::new()
+                        .pallet("System")
+                        .variant("ExtrinsicSuccess")
+                        .between(from, node.block);
+                    b.iter(|| {
+                        black_box(
+                            node.runtime
+                                .block_on(query.run(adapter))
+                                .expect("Failed to scan events"),
+                        )
+                    })
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+fn benchmark_rpc_paths(c: &mut Criterion) {
+    let Some(node) = Node::connect() else {
+        return;
+    };
+    // warm the cached connection so both variants measure steady state
+    for block in node.block.saturating_sub(9)..=node.block {
+        let _ = node.runtime.block_on(node.cached.get_block_detailed(block));
+    }
+
+    benchmark_block_queries(c, &node);
+    benchmark_event_scanning(c, &node);
+}
+
+criterion_group!(benches, benchmark_rpc_paths);
+criterion_main!(benches);
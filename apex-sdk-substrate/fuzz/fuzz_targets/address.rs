@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(address) = std::str::from_utf8(data) {
+        apex_sdk_substrate::fuzzing::address(address);
+    }
+});
@@ -0,0 +1,22 @@
+//! Decodes extrinsics against the metadata file named by `APEX_FUZZ_METADATA`,
+//! e.g. one recorded with the golden fixtures in `tests/golden`.
+
+#![no_main]
+
+use apex_sdk_substrate::golden::load_metadata;
+use libfuzzer_sys::fuzz_target;
+use std::sync::OnceLock;
+use subxt::Metadata;
+
+fn metadata() -> &'static Metadata {
+    static METADATA: OnceLock<Metadata> = OnceLock::new();
+    METADATA.get_or_init(|| {
+        let path = std::env::var("APEX_FUZZ_METADATA")
+            .expect("APEX_FUZZ_METADATA must name a SCALE-encoded metadata file");
+        load_metadata(path.as_ref()).expect("Failed to load metadata")
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    apex_sdk_substrate::fuzzing::extrinsic(data, metadata());
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    apex_sdk_substrate::fuzzing::uos_frames(data);
+    if let Some((size, payload)) = data.split_first() {
+        apex_sdk_substrate::fuzzing::frames_roundtrip(payload, *size as usize);
+    }
+});
@@ -0,0 +1,343 @@
+//! Account existence and activity classification
+//!
+//! [`AccountQuery::classify`] combines `System::Account` with derivation
+//! heuristics to tell what kind of account an address is:
+//!
+//! - **Existence**: an account exists while it has providers or sufficients;
+//!   balances under the existential deposit are reaped.
+//! - **Sovereign accounts** are recognised from their layout alone: `para` or
+//!   `sibl` followed by the little-endian parachain id and zero padding.
+//! - **Pure proxies** have proxies but a zero nonce. A pure proxy has no key,
+//!   so it never signs, while a regular account must sign `add_proxy`.
+//! - **Multisig accounts** also never sign; one with a pending operation in
+//!   `Multisig::Multisigs` is reported as a multisig. An idle multisig cannot
+//!   be told apart from an unused key without knowing its signatories.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{AccountKind, AccountQuery, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let class = AccountQuery::new(adapter)
+//!     .classify("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5")
+//!     .await?;
+//!
+//! if let AccountKind::Sovereign(sovereign) = class.kind() {
+//!     println!("sovereign account of parachain {}", sovereign.para_id);
+//! }
+//! println!("exists: {}, below ED: {}", class.exists, class.below_existential_deposit);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::account_id;
+use crate::storage::AccountInfo;
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::{Compact, Decode};
+use serde::{Deserialize, Serialize};
+use sp_core::crypto::{AccountId32, Ss58Codec};
+use subxt::dynamic::Value;
+
+/// Prefix of a parachain's sovereign account on its relay chain
+const CHILD_PREFIX: &[u8; 4] = b"para";
+
+/// Prefix of a parachain's sovereign account on a sibling parachain
+const SIBLING_PREFIX: &[u8; 4] = b"sibl";
+
+/// Where a sovereign account is held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SovereignKind {
+    /// On the relay chain (`para` prefix)
+    Child,
+    /// On a sibling parachain (`sibl` prefix)
+    Sibling,
+}
+
+/// Sovereign account of a parachain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SovereignAccount {
+    /// Parachain owning the account
+    pub para_id: u32,
+    /// Relay or sibling account
+    pub kind: SovereignKind,
+}
+
+impl SovereignAccount {
+    /// Sovereign account of `para_id` on the relay chain
+    pub fn child(para_id: u32) -> Self {
+        Self {
+            para_id,
+            kind: SovereignKind::Child,
+        }
+    }
+
+    /// Sovereign account of `para_id` on a sibling parachain
+    pub fn sibling(para_id: u32) -> Self {
+        Self {
+            para_id,
+            kind: SovereignKind::Sibling,
+        }
+    }
+
+    /// Recognise a sovereign account from its bytes
+    pub fn from_account_id(account: &[u8; 32]) -> Option<Self> {
+        let kind = match &account[..4] {
+            prefix if prefix == CHILD_PREFIX => SovereignKind::Child,
+            prefix if prefix == SIBLING_PREFIX => SovereignKind::Sibling,
+            _ => return None,
+        };
+        if account[8..].iter().any(|byte| *byte != 0) {
+            return None;
+        }
+        let para_id = u32::from_le_bytes(account[4..8].try_into().ok()?);
+        Some(Self { para_id, kind })
+    }
+
+    /// Account id of this sovereign account
+    pub fn account_id(&self) -> [u8; 32] {
+        let prefix = match self.kind {
+            SovereignKind::Child => CHILD_PREFIX,
+            SovereignKind::Sibling => SIBLING_PREFIX,
+        };
+        let mut account = [0u8; 32];
+        account[..4].copy_from_slice(prefix);
+        account[4..8].copy_from_slice(&self.para_id.to_le_bytes());
+        account
+    }
+}
+
+/// Most specific kind of an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccountKind {
+    /// Parachain sovereign account
+    Sovereign(SovereignAccount),
+    /// Keyless account created by `Proxy::create_pure`
+    PureProxy,
+    /// Multisig account with pending operations
+    Multisig,
+    /// Account controlled by a key
+    Regular,
+    /// No on-chain state
+    Unused,
+}
+
+/// What is known about an account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountClassification {
+    /// Account id as hex
+    pub account: String,
+    /// Whether `System::Account` holds providers or sufficients
+    pub exists: bool,
+    /// Whether the total balance is under the existential deposit
+    pub below_existential_deposit: bool,
+    /// Transactions signed by the account
+    pub nonce: u64,
+    /// Free plus reserved balance
+    pub total_balance: u128,
+    /// Entries in `Proxy::Proxies`
+    pub proxy_count: u32,
+    /// Whether `Multisig::Multisigs` holds a pending operation
+    pub pending_multisig: bool,
+    /// Sovereign account layout, if any
+    pub sovereign: Option<SovereignAccount>,
+}
+
+impl AccountClassification {
+    /// Classify from already fetched state
+    pub fn from_state(
+        account: &[u8; 32],
+        info: &AccountInfo,
+        existential_deposit: u128,
+        proxy_count: u32,
+        pending_multisig: bool,
+    ) -> Self {
+        Self {
+            account: format!("0x{}", hex::encode(account)),
+            exists: info.providers > 0 || info.sufficients > 0,
+            below_existential_deposit: info.total() < existential_deposit,
+            nonce: info.nonce,
+            total_balance: info.total(),
+            proxy_count,
+            pending_multisig,
+            sovereign: SovereignAccount::from_account_id(account),
+        }
+    }
+
+    /// Whether the account is a keyless pure proxy
+    pub fn is_pure_proxy(&self) -> bool {
+        self.proxy_count > 0 && self.nonce == 0
+    }
+
+    /// Whether the account is a multisig with pending operations
+    pub fn is_multisig(&self) -> bool {
+        self.pending_multisig && self.nonce == 0
+    }
+
+    /// Most specific kind; sovereign layout wins over activity heuristics
+    pub fn kind(&self) -> AccountKind {
+        if let Some(sovereign) = self.sovereign {
+            AccountKind::Sovereign(sovereign)
+        } else if self.is_pure_proxy() {
+            AccountKind::PureProxy
+        } else if self.is_multisig() {
+            AccountKind::Multisig
+        } else if self.exists || self.nonce > 0 {
+            AccountKind::Regular
+        } else {
+            AccountKind::Unused
+        }
+    }
+}
+
+/// Account lookups for a connected chain
+pub struct AccountQuery<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> AccountQuery<'a> {
+    /// Query accounts through the adapter's connection
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Classify an SS58 or hex address
+    ///
+    /// Proxy and multisig checks are skipped on chains without those pallets.
+    pub async fn classify(&self, address: &str) -> Result<AccountClassification> {
+        let account = account_id(address)?;
+        let storage = self.adapter.storage();
+        let pallets = self.adapter.pallets();
+
+        let info = storage
+            .get_account_info(&AccountId32::from(account).to_ss58check())
+            .await?;
+        let existential_deposit = storage.get_existential_deposit().unwrap_or_default();
+
+        let proxy_count = if pallets.has("Proxy") {
+            storage
+                .query_storage("Proxy", "Proxies", vec![Value::from_bytes(account)])
+                .await?
+                .map(|bytes| proxy_count(&bytes))
+                .transpose()?
+                .unwrap_or_default()
+        } else {
+            0
+        };
+        let pending_multisig = pallets.has("Multisig") && self.has_multisig(&account).await?;
+
+        Ok(AccountClassification::from_state(
+            &account,
+            &info,
+            existential_deposit,
+            proxy_count,
+            pending_multisig,
+        ))
+    }
+
+    /// Check for any `Multisig::Multisigs` entry under `account`
+    async fn has_multisig(&self, account: &[u8; 32]) -> Result<bool> {
+        let query =
+            subxt::dynamic::storage("Multisig", "Multisigs", vec![Value::from_bytes(account)]);
+        let storage = self
+            .adapter
+            .client()
+            .storage()
+            .at_latest()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to fetch latest block: {}", e)))?;
+        let mut entries = storage
+            .iter(query)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to iterate Multisig::Multisigs: {}", e)))?;
+        match entries.next().await {
+            Some(entry) => entry
+                .map(|_| true)
+                .map_err(|e| Error::Storage(format!("Failed to fetch multisig entry: {}", e))),
+            None => Ok(false),
+        }
+    }
+}
+
+/// Number of proxies in an encoded `(Vec<ProxyDefinition>, Balance)`
+///
+/// Only the length prefix is read, so chain-specific proxy types don't matter.
+fn proxy_count(bytes: &[u8]) -> Result<u32> {
+    Compact::<u32>::decode(&mut &bytes[..])
+        .map(|count| count.0)
+        .map_err(|e| Error::Encoding(format!("Failed to decode Proxy::Proxies: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_scale_codec::Encode;
+
+    fn info(nonce: u64, providers: u32, free: u128) -> AccountInfo {
+        AccountInfo {
+            nonce,
+            providers,
+            free,
+            ..AccountInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_sovereign_accounts() {
+        let child = SovereignAccount::child(2000);
+        let account = child.account_id();
+        assert_eq!(&account[..8], b"para\xd0\x07\x00\x00");
+        assert_eq!(SovereignAccount::from_account_id(&account), Some(child));
+
+        let sibling = SovereignAccount::sibling(1000);
+        assert_eq!(
+            SovereignAccount::from_account_id(&sibling.account_id()),
+            Some(sibling)
+        );
+
+        let mut not_padded = account;
+        not_padded[31] = 1;
+        assert_eq!(SovereignAccount::from_account_id(&not_padded), None);
+        assert_eq!(SovereignAccount::from_account_id(&[7u8; 32]), None);
+    }
+
+    #[test]
+    fn test_classification() {
+        let account = [7u8; 32];
+        let ed = 10_000_000_000;
+
+        let regular = AccountClassification::from_state(&account, &info(3, 1, ed), ed, 1, false);
+        assert_eq!(regular.kind(), AccountKind::Regular);
+        assert!(!regular.below_existential_deposit);
+
+        let pure = AccountClassification::from_state(&account, &info(0, 1, ed), ed, 1, false);
+        assert_eq!(pure.kind(), AccountKind::PureProxy);
+
+        let multisig = AccountClassification::from_state(&account, &info(0, 1, ed), ed, 0, true);
+        assert_eq!(multisig.kind(), AccountKind::Multisig);
+
+        let unused = AccountClassification::from_state(&account, &info(0, 0, 0), ed, 0, false);
+        assert_eq!(unused.kind(), AccountKind::Unused);
+        assert!(!unused.exists);
+        assert!(unused.below_existential_deposit);
+
+        let sovereign = SovereignAccount::child(2000);
+        let class = AccountClassification::from_state(
+            &sovereign.account_id(),
+            &info(0, 1, ed),
+            ed,
+            1,
+            false,
+        );
+        assert_eq!(class.kind(), AccountKind::Sovereign(sovereign));
+    }
+
+    #[test]
+    fn test_proxy_count() {
+        let proxies = (
+            vec![([1u8; 32], 0u8, 0u32), ([2u8; 32], 3u8, 10u32)],
+            100u128,
+        )
+            .encode();
+        assert_eq!(proxy_count(&proxies).unwrap(), 2);
+        assert!(proxy_count(&[]).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+//! 20-byte Ethereum-style accounts
+//!
+//! EVM-compatible parachains such as Moonbeam use `AccountId20` instead of
+//! `AccountId32`: accounts are the last 20 bytes of the keccak-256 hash of an
+//! uncompressed secp256k1 public key, addresses are EIP-55 checksummed hex and
+//! extrinsics carry ECDSA signatures over the keccak-256 hash of the payload.
+//!
+//! [`AccountIdKind`] tells the two layouts apart, so balance and account
+//! queries accept either form of address; [`SubstrateAdapter::account_id_kind`]
+//! reads it from the connected chain's metadata and
+//! [`AccountIdKind::key_type`] picks the matching [`KeyPairType`].
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{SubstrateAdapter, Wallet};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let key_type = adapter.account_id_kind()?.key_type();
+//! let wallet = Wallet::from_mnemonic(
+//!     "test test test test test test test test test test test junk",
+//!     key_type,
+//! )?;
+//!
+//! let balance = adapter.storage().get_balance(&wallet.address()).await?;
+//! println!("{} holds {}", wallet.address(), balance);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`SubstrateAdapter::account_id_kind`]: crate::SubstrateAdapter::account_id_kind
+
+use crate::event_query::account_hex;
+use crate::wallet::KeyPairType;
+use crate::{Error, Result};
+use apex_sdk_types::Address;
+use hmac::{Hmac, Mac};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::elliptic_curve::PrimeField;
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::Sha512;
+use sp_core::crypto::{AccountId32, Ss58AddressFormat, Ss58Codec};
+use sp_core::ecdsa;
+use std::fmt;
+use std::str::FromStr;
+
+/// BIP-44 path of the first Ethereum account, as used by MetaMask
+pub const DEFAULT_ETHEREUM_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// Offset marking a hardened BIP-32 index
+const HARDENED: u32 = 0x8000_0000;
+
+/// 20-byte account id of an EVM-compatible chain
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Encode, Decode)]
+pub struct AccountId20(pub [u8; 20]);
+
+impl AccountId20 {
+    /// Account of an ECDSA public key
+    pub fn from_ecdsa_public(public: &ecdsa::Public) -> Result<Self> {
+        let key = k256::PublicKey::from_sec1_bytes(public.as_ref())
+            .map_err(|e| Error::Wallet(format!("Invalid ECDSA public key: {}", e)))?;
+        let uncompressed = key.to_encoded_point(false);
+        let hash = sp_core::keccak_256(&uncompressed.as_bytes()[1..]);
+        let mut account = [0u8; 20];
+        account.copy_from_slice(&hash[12..]);
+        Ok(Self(account))
+    }
+
+    /// EIP-55 checksummed hex address
+    pub fn to_checksum(&self) -> String {
+        Address::evm(format!("0x{}", hex::encode(self.0))).to_checksum()
+    }
+}
+
+impl FromStr for AccountId20 {
+    type Err = Error;
+
+    /// Parse hex; mixed-case input must carry a valid EIP-55 checksum
+    fn from_str(address: &str) -> Result<Self> {
+        Address::evm_checked(address).map_err(|e| Error::Other(e.to_string()))?;
+        let mut account = [0u8; 20];
+        hex::decode_to_slice(&address[2..], &mut account)
+            .map_err(|e| Error::Other(format!("Invalid account {}: {}", address, e)))?;
+        Ok(Self(account))
+    }
+}
+
+impl fmt::Display for AccountId20 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_checksum())
+    }
+}
+
+impl fmt::Debug for AccountId20 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AccountId20({})", self.to_checksum())
+    }
+}
+
+impl Serialize for AccountId20 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_checksum())
+    }
+}
+
+impl<'de> Deserialize<'de> for AccountId20 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let address = String::deserialize(deserializer)?;
+        address.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Account id layout of a chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum AccountIdKind {
+    /// 32-byte ids with SS58 addresses (relay chains and most parachains)
+    #[default]
+    Id32,
+    /// 20-byte ids with checksummed hex addresses (Moonbeam, Astar EVM)
+    Id20,
+}
+
+impl AccountIdKind {
+    /// Layout of an encoded account id of `len` bytes
+    pub fn from_len(len: usize) -> Option<Self> {
+        match len {
+            32 => Some(Self::Id32),
+            20 => Some(Self::Id20),
+            _ => None,
+        }
+    }
+
+    /// Encoded id length in bytes
+    pub fn byte_len(&self) -> usize {
+        match self {
+            Self::Id32 => 32,
+            Self::Id20 => 20,
+        }
+    }
+
+    /// Key type whose public keys map to this layout
+    pub fn key_type(&self) -> KeyPairType {
+        match self {
+            Self::Id32 => KeyPairType::Sr25519,
+            Self::Id20 => KeyPairType::Ethereum,
+        }
+    }
+
+    /// Account id bytes of an SS58 or hex address in this layout
+    pub fn parse(&self, address: &str) -> Result<Vec<u8>> {
+        let bytes = account_bytes(address)?;
+        if bytes.len() != self.byte_len() {
+            return Err(Error::Other(format!(
+                "{} is not a {} byte account",
+                address,
+                self.byte_len()
+            )));
+        }
+        Ok(bytes)
+    }
+
+    /// Display address of account id bytes; `ss58_prefix` applies to 32-byte ids
+    pub fn format(&self, account: &[u8], ss58_prefix: u16) -> Result<String> {
+        match self {
+            Self::Id32 => {
+                let account: [u8; 32] = account.try_into().map_err(|_| {
+                    Error::Other(format!("Expected 32 byte account, got {}", account.len()))
+                })?;
+                Ok(AccountId32::from(account)
+                    .to_ss58check_with_version(Ss58AddressFormat::custom(ss58_prefix)))
+            }
+            Self::Id20 => {
+                let account: [u8; 20] = account.try_into().map_err(|_| {
+                    Error::Other(format!("Expected 20 byte account, got {}", account.len()))
+                })?;
+                Ok(AccountId20(account).to_checksum())
+            }
+        }
+    }
+}
+
+/// Account id bytes of an SS58, 32-byte hex or 20-byte hex address
+pub fn account_bytes(address: &str) -> Result<Vec<u8>> {
+    let hex_account = account_hex(address)?;
+    if hex_account.len() == 42 {
+        // reject mistyped mixed-case addresses instead of silently accepting them
+        AccountId20::from_str(address)?;
+    }
+    hex::decode(&hex_account[2..]).map_err(|e| Error::Other(e.to_string()))
+}
+
+/// Secret key of a BIP-39 mnemonic at a BIP-32 path such as [`DEFAULT_ETHEREUM_PATH`]
+pub(crate) fn ethereum_secret(mnemonic: &str, path: &str) -> Result<[u8; 32]> {
+    let mnemonic = bip39::Mnemonic::parse(mnemonic)
+        .map_err(|e| Error::Wallet(format!("Invalid mnemonic: {}", e)))?;
+    let (mut key, mut chain_code) = hmac_split(b"Bitcoin seed", &mnemonic.to_seed(""));
+
+    let mut indexes = path.split('/');
+    if indexes.next() != Some("m") {
+        return Err(Error::Wallet(format!(
+            "Derivation path {} must start with m",
+            path
+        )));
+    }
+    for index in indexes {
+        let (number, hardened) = match index.strip_suffix('\'') {
+            Some(number) => (number, true),
+            None => (index, false),
+        };
+        let number: u32 = number
+            .parse()
+            .ok()
+            .filter(|number| *number < HARDENED)
+            .ok_or_else(|| Error::Wallet(format!("Invalid path index {} in {}", index, path)))?;
+
+        let parent = k256::SecretKey::from_slice(&key)
+            .map_err(|e| Error::Wallet(format!("Invalid derived key: {}", e)))?;
+        let mut data = Vec::with_capacity(37);
+        if hardened {
+            data.push(0);
+            data.extend_from_slice(&key);
+            data.extend_from_slice(&(number | HARDENED).to_be_bytes());
+        } else {
+            data.extend_from_slice(parent.public_key().to_encoded_point(true).as_bytes());
+            data.extend_from_slice(&number.to_be_bytes());
+        }
+
+        let (tweak, child_chain_code) = hmac_split(&chain_code, &data);
+        let tweak = Option::<k256::Scalar>::from(k256::Scalar::from_repr(tweak.into()))
+            .ok_or_else(|| Error::Wallet(format!("Path {} derives an invalid key", path)))?;
+        let child = tweak + parent.to_nonzero_scalar().as_ref();
+        key = child.to_bytes().into();
+        chain_code = child_chain_code;
+    }
+    Ok(key)
+}
+
+/// HMAC-SHA512 split into key and chain code halves
+fn hmac_split(key: &[u8], data: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    let output = mac.finalize().into_bytes();
+    let (mut left, mut right) = ([0u8; 32], [0u8; 32]);
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    (left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::Pair;
+
+    #[test]
+    fn test_ethereum_account_derivation() {
+        let mnemonic = "test test test test test test test test test test test junk";
+        let secret = ethereum_secret(mnemonic, DEFAULT_ETHEREUM_PATH).unwrap();
+        assert_eq!(
+            hex::encode(secret),
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        );
+
+        let pair = ecdsa::Pair::from_seed(&secret);
+        let account = AccountId20::from_ecdsa_public(&pair.public()).unwrap();
+        assert_eq!(
+            account.to_string(),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+
+        let second = ethereum_secret(mnemonic, "m/44'/60'/0'/0/1").unwrap();
+        let pair = ecdsa::Pair::from_seed(&second);
+        assert_eq!(
+            AccountId20::from_ecdsa_public(&pair.public())
+                .unwrap()
+                .to_string(),
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+        );
+        assert!(ethereum_secret(mnemonic, "44'/60'").is_err());
+    }
+
+    #[test]
+    fn test_account_id_kinds() {
+        let account: AccountId20 = "0xf24ff3a9cf04c71dbc94d0b566f7a27b94566cac"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            account.to_string(),
+            "0xf24FF3a9CF04c71Dbc94D0b566f7A27B94566cac"
+        );
+        assert_eq!(
+            serde_json::to_string(&account).unwrap(),
+            "\"0xf24FF3a9CF04c71Dbc94D0b566f7A27B94566cac\""
+        );
+        assert_eq!(
+            AccountId20::decode(&mut &account.encode()[..]).unwrap(),
+            account
+        );
+        assert!("0xf24FF3a9CF04c71Dbc94D0b566f7A27B94566caC"
+            .parse::<AccountId20>()
+            .is_err());
+
+        let kind = AccountIdKind::Id20;
+        let bytes = kind.parse(&account.to_string()).unwrap();
+        assert_eq!(bytes, account.0);
+        assert_eq!(kind.format(&bytes, 1284).unwrap(), account.to_string());
+        assert!(AccountIdKind::Id32.parse(&account.to_string()).is_err());
+        assert_eq!(AccountIdKind::from_len(20), Some(AccountIdKind::Id20));
+        assert_eq!(kind.key_type(), KeyPairType::Ethereum);
+    }
+}
@@ -0,0 +1,243 @@
+//! Recovering assets trapped by failed XCM execution
+//!
+//! When an XCM message fails after withdrawing assets, the XCM pallet keeps
+//! them in its asset trap and emits `AssetsTrapped` with the origin that may
+//! claim them. Claiming takes a `claim_assets` call naming exactly the
+//! trapped assets in the version they were trapped in, which is easy to get
+//! wrong by hand. [`AssetTrap`] finds the traps belonging to an account and
+//! builds the claim from the event itself:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{AssetTrap, SubstrateAdapter, Wallet};
+//!
+//! # async fn example(adapter: &SubstrateAdapter, wallet: &Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! let trap = AssetTrap::new(adapter);
+//! for trapped in trap.find(&wallet.address(), 8_000_000, 8_001_000).await? {
+//!     if trap.is_claimable(&trapped).await? {
+//!         for asset in trapped.amounts() {
+//!             println!("claiming {}", asset);
+//!         }
+//!         trap.claim(wallet, &trapped).await?;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only the trapping origin can claim, so traps of sovereign or pallet
+//! origins are found but can only be claimed through governance or XCM.
+
+use crate::event_query::{account_hex, json_contains_hex, EventQuery, MatchedEvent};
+use crate::receipt::json_u128;
+use crate::template::json_to_value;
+use crate::xcm_decode::{assets, AssetAmount};
+use crate::{Error, Result, SubstrateAdapter, Wallet};
+use serde_json::{json, Value as JsonValue};
+use subxt::dynamic::Value;
+use tracing::info;
+
+/// Assets trapped by one failed XCM execution
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrappedAssets {
+    /// Block the assets were trapped in
+    pub block_number: u64,
+    /// Hash of the trapped origin and assets (hex)
+    pub hash: String,
+    /// Origin allowed to claim, as decoded from the event
+    pub origin: JsonValue,
+    /// Trapped `VersionedAssets`, as decoded from the event
+    pub assets: JsonValue,
+}
+
+impl TrappedAssets {
+    /// Decode an `AssetsTrapped` event; `None` for any other event
+    pub fn from_event(event: &MatchedEvent) -> Option<Self> {
+        if !matches!(event.pallet.as_str(), "PolkadotXcm" | "XcmPallet")
+            || event.variant != "AssetsTrapped"
+        {
+            return None;
+        }
+        Some(Self {
+            block_number: event.block_number,
+            hash: event.fields["hash"].as_str()?.to_string(),
+            origin: event.fields["origin"].clone(),
+            assets: event.fields["assets"].clone(),
+        })
+    }
+
+    /// XCM version the assets were trapped in, e.g. `V4`
+    pub fn version(&self) -> Option<&str> {
+        self.assets.as_object()?.keys().next().map(String::as_str)
+    }
+
+    /// Trapped assets with their amounts
+    pub fn amounts(&self) -> Vec<AssetAmount> {
+        match self
+            .assets
+            .as_object()
+            .and_then(|versioned| versioned.values().next())
+        {
+            Some(inner) => assets(inner),
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether `address` is the trapping origin or part of it
+    pub fn involves(&self, address: &str) -> bool {
+        account_hex(address).is_ok_and(|hex| json_contains_hex(&self.origin, &hex))
+    }
+
+    /// Whether `address`, an account on this chain, is the trapping origin
+    pub fn claimable_by(&self, address: &str) -> bool {
+        self.origin["parents"] == 0 && self.involves(address)
+    }
+
+    /// Location of `account` on this chain, in the version of the trapped
+    /// assets
+    fn beneficiary(&self, account: &str) -> Option<JsonValue> {
+        let location = json!({
+            "parents": 0,
+            "interior": { "X1": { "AccountId32": { "network": "None", "id": account } } },
+        });
+        let mut versioned = serde_json::Map::new();
+        versioned.insert(self.version()?.to_string(), location);
+        Some(JsonValue::Object(versioned))
+    }
+}
+
+/// Finds and claims trapped assets on a chain with the XCM pallet
+pub struct AssetTrap<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> AssetTrap<'a> {
+    /// Asset trap of the adapter's chain
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Assets trapped for `address` in blocks `from..=to`
+    pub async fn find(&self, address: &str, from: u64, to: u64) -> Result<Vec<TrappedAssets>> {
+        let events = EventQuery::new()
+            .pallet(self.xcm_pallet()?)
+            .variant("AssetsTrapped")
+            .involving(address)
+            .between(from, to)
+            .run(self.adapter)
+            .await?;
+        Ok(events
+            .iter()
+            .filter_map(TrappedAssets::from_event)
+            .filter(|trapped| trapped.involves(address))
+            .collect())
+    }
+
+    /// Whether the trap still holds the assets
+    pub async fn is_claimable(&self, trapped: &TrappedAssets) -> Result<bool> {
+        let hash = hex::decode(trapped.hash.trim_start_matches("0x"))
+            .map_err(|e| Error::Encoding(format!("Invalid trap hash {}: {}", trapped.hash, e)))?;
+        let count = self
+            .adapter
+            .storage()
+            .query_storage_json(
+                self.xcm_pallet()?,
+                "AssetTraps",
+                vec![Value::from_bytes(hash)],
+            )
+            .await?;
+        Ok(count.as_ref().and_then(json_u128).unwrap_or(0) > 0)
+    }
+
+    /// `claim_assets` arguments crediting `beneficiary` on this chain
+    pub fn claim_args(&self, trapped: &TrappedAssets, beneficiary: &str) -> Result<Vec<Value>> {
+        let pallet = self.xcm_pallet()?;
+        let metadata = self.adapter.client().metadata();
+        let fields = metadata
+            .pallet_by_name(pallet)
+            .and_then(|pallet| pallet.call_variant_by_name("claim_assets"))
+            .map(|call| {
+                call.fields
+                    .iter()
+                    .map(|field| field.ty.id)
+                    .collect::<Vec<_>>()
+            })
+            .ok_or_else(|| Error::Metadata(format!("Runtime has no {}::claim_assets", pallet)))?;
+        let [assets_ty, beneficiary_ty] = fields[..] else {
+            return Err(Error::Metadata(format!(
+                "Unexpected {}::claim_assets arguments",
+                pallet
+            )));
+        };
+        let beneficiary = trapped
+            .beneficiary(&account_hex(beneficiary)?)
+            .ok_or_else(|| {
+                Error::Encoding(format!("Unversioned trapped assets: {}", trapped.assets))
+            })?;
+
+        Ok(vec![
+            json_to_value(&trapped.assets, assets_ty, metadata.types())?,
+            json_to_value(&beneficiary, beneficiary_ty, metadata.types())?,
+        ])
+    }
+
+    /// Claim `trapped` with `wallet`, crediting the wallet itself
+    ///
+    /// Fails unless the wallet is the trapping origin; returns the
+    /// transaction hash.
+    pub async fn claim(&self, wallet: &Wallet, trapped: &TrappedAssets) -> Result<String> {
+        let address = wallet.address();
+        if !trapped.claimable_by(&address) {
+            return Err(Error::Transaction(format!(
+                "Assets trapped for {} cannot be claimed by {}",
+                trapped.origin, address
+            )));
+        }
+        info!("Claiming trapped assets {} for {}", trapped.hash, address);
+        let args = self.claim_args(trapped, &address)?;
+        self.adapter
+            .transaction_executor()
+            .submit_call(wallet, self.xcm_pallet()?, "claim_assets", args)
+            .await
+    }
+
+    fn xcm_pallet(&self) -> Result<&'static str> {
+        let pallets = self.adapter.pallets();
+        match pallets.first_of(&["PolkadotXcm", "XcmPallet"]) {
+            Some(pallet) => Ok(pallet),
+            None => pallets.require("PolkadotXcm").map(|_| "PolkadotXcm"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trapped_assets_from_event() {
+        let alice = format!("0x{}", "d4".repeat(32));
+        let event = MatchedEvent {
+            block_number: 8_000_123,
+            block_hash: format!("0x{}", "00".repeat(32)),
+            event_index: 5,
+            extrinsic_index: Some(2),
+            pallet: "PolkadotXcm".to_string(),
+            variant: "AssetsTrapped".to_string(),
+            fields: json!({
+                "hash": format!("0x{}", "ab".repeat(32)),
+                "origin": { "parents": 0, "interior": { "X1": { "AccountId32": { "network": "None", "id": alice } } } },
+                "assets": { "V4": { "id": { "parents": 1, "interior": "Here" }, "fun": { "Fungible": 1_500_000_000 } } },
+            }),
+        };
+
+        let trapped = TrappedAssets::from_event(&event).unwrap();
+        assert_eq!(trapped.version(), Some("V4"));
+        assert!(trapped.claimable_by(&alice));
+        assert!(!trapped.involves(&format!("0x{}", "11".repeat(32))));
+        assert_eq!(trapped.amounts()[0].amount, Some(1_500_000_000));
+        assert_eq!(
+            trapped.beneficiary(&alice).unwrap()["V4"]["interior"]["X1"]["AccountId32"]["id"],
+            json!(alice)
+        );
+    }
+}
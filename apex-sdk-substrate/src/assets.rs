@@ -0,0 +1,324 @@
+//! Asset identifiers across native, pallet-assets and XCM-located assets
+//!
+//! An [`AssetId`] names a fungible asset from the point of view of the chain
+//! holding it: the chain's native token, an id in `pallet-assets`, or an XCM
+//! location registered in `pallet-foreign-assets`. It converts to and from the
+//! [`MultiLocation`] used in XCM messages; [`xcm::AssetId`] is the wire-level
+//! identifier built from that location.
+//!
+//! [`AssetRegistry::well_known`] lists common assets such as DOT and USDT on
+//! Polkadot AssetHub, with their home chain and decimals, so callers can look
+//! them up by symbol and locate them from any other chain of the network.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::assets::AssetRegistry;
+//! use apex_sdk_substrate::SubstrateAdapter;
+//!
+//! # async fn example(adapter: &SubstrateAdapter, alice: &str) -> Result<(), apex_sdk_substrate::Error> {
+//! let registry = AssetRegistry::well_known();
+//! let usdt = registry.find("polkadot", "USDT").expect("registered");
+//!
+//! // connected to AssetHub, the asset's home chain
+//! let balance = adapter.storage().get_asset_balance(alice, &usdt.id).await?;
+//! println!("{} {}", balance, usdt.symbol);
+//!
+//! // how a sibling parachain refers to it in XCM
+//! let location = usdt.location_from(Some(2034));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`xcm::AssetId`]: crate::xcm::AssetId
+
+use crate::xcm::{self, Junction, MultiLocation, XcmAsset};
+use crate::{Error, Result};
+use subxt::dynamic::Value;
+
+/// Index of `pallet-assets` on the Polkadot and Kusama AssetHubs
+pub const ASSET_HUB_ASSETS_PALLET: u8 = 50;
+
+/// Parachain id of the Polkadot and Kusama AssetHubs
+pub const ASSET_HUB_PARA_ID: u32 = 1000;
+
+/// A fungible asset on the chain holding it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetId {
+    /// Native token managed by `pallet-balances`
+    Native,
+    /// Asset id in `pallet-assets`
+    Local(u32),
+    /// Asset registered by location in `pallet-foreign-assets`
+    Foreign(MultiLocation),
+}
+
+impl AssetId {
+    /// Location of the asset relative to its chain
+    ///
+    /// `assets_pallet` is the index of `pallet-assets` on that chain, e.g.
+    /// [`ASSET_HUB_ASSETS_PALLET`].
+    pub fn to_location(&self, assets_pallet: u8) -> MultiLocation {
+        match self {
+            AssetId::Native => MultiLocation::new(0, vec![]),
+            AssetId::Local(id) => MultiLocation::new(
+                0,
+                vec![
+                    Junction::PalletInstance(assets_pallet),
+                    Junction::GeneralIndex(*id as u128),
+                ],
+            ),
+            AssetId::Foreign(location) => location.clone(),
+        }
+    }
+
+    /// Asset at a location relative to the chain holding it
+    ///
+    /// Locations that are neither the native token nor an id in
+    /// `pallet-assets` at index `assets_pallet` are foreign assets.
+    pub fn from_location(location: &MultiLocation, assets_pallet: u8) -> Self {
+        match (location.parents, location.interior.as_slice()) {
+            (0, []) => AssetId::Native,
+            (0, [Junction::PalletInstance(pallet), Junction::GeneralIndex(id)])
+                if *pallet == assets_pallet =>
+            {
+                u32::try_from(*id)
+                    .map(AssetId::Local)
+                    .unwrap_or_else(|_| AssetId::Foreign(location.clone()))
+            }
+            _ => AssetId::Foreign(location.clone()),
+        }
+    }
+
+    /// XCM asset of `amount` units, as seen from the holding chain
+    pub fn to_xcm_asset(&self, amount: u128, assets_pallet: u8) -> XcmAsset {
+        XcmAsset::fungible(
+            xcm::AssetId::Concrete(self.to_location(assets_pallet)),
+            amount,
+        )
+    }
+
+    /// Whether the asset is the chain's native token
+    pub fn is_native(&self) -> bool {
+        matches!(self, AssetId::Native)
+    }
+
+    /// Pallet storing balances of this asset
+    pub fn pallet(&self) -> &'static str {
+        match self {
+            AssetId::Native => "Balances",
+            AssetId::Local(_) => "Assets",
+            AssetId::Foreign(_) => "ForeignAssets",
+        }
+    }
+
+    /// Dynamic value of the id in its pallet; `None` for the native token
+    pub(crate) fn pallet_key(&self) -> Result<Option<Value>> {
+        match self {
+            AssetId::Native => Ok(None),
+            AssetId::Local(id) => Ok(Some(Value::u128(*id as u128))),
+            AssetId::Foreign(location) => location.to_value().map(Some),
+        }
+    }
+}
+
+/// A registered asset with its home chain and display metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownAsset {
+    /// Ticker symbol, e.g. `USDT`
+    pub symbol: String,
+    /// Decimal places of the smallest unit
+    pub decimals: u8,
+    /// Relay network the asset lives in, e.g. `polkadot`
+    pub network: String,
+    /// Parachain holding the asset, or `None` for the relay chain
+    pub home: Option<u32>,
+    /// Id on the home chain
+    pub id: AssetId,
+    /// Index of `pallet-assets` on the home chain
+    pub assets_pallet: u8,
+}
+
+impl KnownAsset {
+    /// Native token of a relay chain
+    pub fn relay_native(network: &str, symbol: &str, decimals: u8) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            decimals,
+            network: network.to_string(),
+            home: None,
+            id: AssetId::Native,
+            assets_pallet: ASSET_HUB_ASSETS_PALLET,
+        }
+    }
+
+    /// `pallet-assets` asset on a network's AssetHub
+    pub fn asset_hub(network: &str, symbol: &str, decimals: u8, id: u32) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            decimals,
+            network: network.to_string(),
+            home: Some(ASSET_HUB_PARA_ID),
+            id: AssetId::Local(id),
+            assets_pallet: ASSET_HUB_ASSETS_PALLET,
+        }
+    }
+
+    /// Location of the asset seen from the relay chain (`None`) or a parachain
+    ///
+    /// Foreign assets are only located from their home chain, since their
+    /// locations are relative to it.
+    pub fn location_from(&self, here: Option<u32>) -> Option<MultiLocation> {
+        let local = self.id.to_location(self.assets_pallet);
+        if here == self.home {
+            return Some(local);
+        }
+        if matches!(self.id, AssetId::Foreign(_)) {
+            return None;
+        }
+
+        let parents = if here.is_some() { 1 } else { 0 };
+        let mut interior = Vec::with_capacity(local.interior.len() + 1);
+        if let Some(para_id) = self.home {
+            interior.push(Junction::Parachain(para_id));
+        }
+        interior.extend(local.interior);
+        Some(MultiLocation::new(parents, interior))
+    }
+}
+
+/// Lookup table of known assets
+#[derive(Debug, Clone, Default)]
+pub struct AssetRegistry {
+    assets: Vec<KnownAsset>,
+}
+
+impl AssetRegistry {
+    /// Empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Relay tokens and the main stablecoins on Polkadot, Kusama and Westend
+    pub fn well_known() -> Self {
+        Self::new()
+            .with_asset(KnownAsset::relay_native("polkadot", "DOT", 10))
+            .with_asset(KnownAsset::asset_hub("polkadot", "USDT", 6, 1984))
+            .with_asset(KnownAsset::asset_hub("polkadot", "USDC", 6, 1337))
+            .with_asset(KnownAsset::relay_native("kusama", "KSM", 12))
+            .with_asset(KnownAsset::asset_hub("kusama", "USDT", 6, 1984))
+            .with_asset(KnownAsset::relay_native("westend", "WND", 12))
+    }
+
+    /// Add an asset; a later asset with the same network and symbol replaces it
+    pub fn with_asset(mut self, asset: KnownAsset) -> Self {
+        self.assets
+            .retain(|known| !(known.network == asset.network && known.symbol == asset.symbol));
+        self.assets.push(asset);
+        self
+    }
+
+    /// Asset by network and symbol, ignoring case
+    pub fn find(&self, network: &str, symbol: &str) -> Option<&KnownAsset> {
+        self.assets.iter().find(|asset| {
+            asset.network.eq_ignore_ascii_case(network) && asset.symbol.eq_ignore_ascii_case(symbol)
+        })
+    }
+
+    /// Asset held by `home` under `id`
+    pub fn by_id(&self, network: &str, home: Option<u32>, id: &AssetId) -> Option<&KnownAsset> {
+        self.assets.iter().find(|asset| {
+            asset.network.eq_ignore_ascii_case(network) && asset.home == home && asset.id == *id
+        })
+    }
+
+    /// Asset a location refers to, seen from the relay chain (`None`) or a parachain
+    pub fn by_location(
+        &self,
+        network: &str,
+        here: Option<u32>,
+        location: &MultiLocation,
+    ) -> Option<&KnownAsset> {
+        self.assets.iter().find(|asset| {
+            asset.network.eq_ignore_ascii_case(network)
+                && asset.location_from(here).as_ref() == Some(location)
+        })
+    }
+
+    /// All registered assets
+    pub fn assets(&self) -> &[KnownAsset] {
+        &self.assets
+    }
+}
+
+/// Decode the balance at the start of an `Assets::Account` value
+pub(crate) fn asset_account_balance(bytes: &[u8]) -> Result<u128> {
+    use parity_scale_codec::Decode;
+    u128::decode(&mut &bytes[..])
+        .map_err(|e| Error::Storage(format!("Failed to decode asset balance: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_locations() {
+        let usdt = AssetId::Local(1984);
+        let location = usdt.to_location(ASSET_HUB_ASSETS_PALLET);
+        assert_eq!(
+            location.interior,
+            vec![Junction::PalletInstance(50), Junction::GeneralIndex(1984)]
+        );
+        assert_eq!(AssetId::from_location(&location, 50), usdt);
+        assert_eq!(
+            AssetId::from_location(&MultiLocation::new(0, vec![]), 50),
+            AssetId::Native
+        );
+        assert!(matches!(
+            AssetId::from_location(&MultiLocation::parent(), 50),
+            AssetId::Foreign(_)
+        ));
+        assert!(matches!(
+            AssetId::from_location(&location, 8),
+            AssetId::Foreign(_)
+        ));
+    }
+
+    #[test]
+    fn test_registry() {
+        let registry = AssetRegistry::well_known();
+        let usdt = registry.find("Polkadot", "usdt").unwrap();
+        assert_eq!(usdt.decimals, 6);
+
+        // from a sibling parachain
+        let location = usdt.location_from(Some(2034)).unwrap();
+        assert_eq!(location.parents, 1);
+        assert_eq!(
+            location.interior,
+            vec![
+                Junction::Parachain(1000),
+                Junction::PalletInstance(50),
+                Junction::GeneralIndex(1984)
+            ]
+        );
+        assert_eq!(
+            registry.by_location("polkadot", Some(2034), &location),
+            Some(usdt)
+        );
+
+        // DOT from AssetHub and from the relay chain
+        let dot = registry.find("polkadot", "DOT").unwrap();
+        assert_eq!(dot.location_from(Some(1000)), Some(MultiLocation::parent()));
+        assert_eq!(dot.location_from(None), Some(MultiLocation::new(0, vec![])));
+        assert_eq!(
+            registry.by_id("kusama", Some(1000), &AssetId::Local(1984)),
+            registry.find("kusama", "USDT")
+        );
+
+        let registry = registry.with_asset(KnownAsset::asset_hub("polkadot", "USDT", 6, 9999));
+        assert_eq!(
+            registry.find("polkadot", "USDT").unwrap().id,
+            AssetId::Local(9999)
+        );
+        assert_eq!(registry.assets().len(), 6);
+    }
+}
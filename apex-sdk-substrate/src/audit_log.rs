@@ -0,0 +1,355 @@
+//! Tamper-evident audit log of signing and submission
+//!
+//! [`AuditLog`] records every signing request and submission made by a
+//! [`TransactionExecutor`](crate::TransactionExecutor) as an [`AuditEntry`]
+//! with the payload hash, signer, outcome and operator-supplied context.
+//! Entries are hash-chained: each one commits to the hash of the previous
+//! entry, so [`verify_chain`] detects edited, removed or reordered entries.
+//! Where entries go is up to the [`AuditWriter`]:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{AuditLog, JsonLinesWriter, SubstrateAdapter, Wallet};
+//!
+//! # async fn example(adapter: SubstrateAdapter, wallet: &Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! let log = AuditLog::new(JsonLinesWriter::new("audit.jsonl"))
+//!     .with_context("operator", "treasury-bot");
+//! let adapter = adapter.with_audit_log(log.with_context("ticket", "OPS-1024"));
+//! adapter
+//!     .transaction_executor()
+//!     .transfer(wallet, "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty", 1_000_000_000_000)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A signing request is recorded before the payload is signed, and a
+//! submission before the extrinsic is broadcast; if either record cannot be
+//! written, the transaction goes no further. Once a submission's outcome is
+//! known, it is recorded as a second `Submit` entry.
+
+use crate::{Error, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// `prev_hash` of the first entry of a log
+pub const GENESIS_HASH: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Operation an entry records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditOperation {
+    /// A payload is about to be signed
+    Sign,
+    /// A signed extrinsic is submitted
+    Submit,
+}
+
+/// Outcome of an audited operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditOutcome {
+    /// Signing or submission was requested
+    Requested,
+    /// The extrinsic was included in a finalized block
+    Included {
+        /// Extrinsic hash
+        tx_hash: String,
+    },
+    /// The operation failed
+    Failed {
+        /// Error message
+        error: String,
+    },
+}
+
+/// One hash-chained audit record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, from 0
+    pub sequence: u64,
+    /// Unix time in milliseconds
+    pub timestamp: u64,
+    /// Operation recorded
+    pub operation: AuditOperation,
+    /// Signer address
+    pub signer: String,
+    /// Blake2-256 of the call data (sign) or the signed extrinsic (submit)
+    pub payload_hash: String,
+    /// Outcome of the operation
+    pub outcome: AuditOutcome,
+    /// Operator-supplied context
+    pub context: BTreeMap<String, String>,
+    /// Hash of the previous entry
+    pub prev_hash: String,
+    /// Hash of this entry
+    pub hash: String,
+}
+
+impl AuditEntry {
+    /// Hash of the entry's contents, excluding `hash` itself
+    pub fn compute_hash(&self) -> String {
+        let unhashed = AuditEntry {
+            hash: String::new(),
+            ..self.clone()
+        };
+        let encoded = serde_json::to_vec(&unhashed).expect("audit entries serialize");
+        format!("0x{}", hex::encode(sp_core::blake2_256(&encoded)))
+    }
+}
+
+/// Destination of audit entries
+#[async_trait]
+pub trait AuditWriter: Send + Sync {
+    /// Durably append one entry
+    async fn append(&self, entry: &AuditEntry) -> Result<()>;
+}
+
+/// Keeps entries in memory, e.g. for tests
+#[derive(Debug, Clone, Default)]
+pub struct MemoryAuditWriter {
+    entries: Arc<parking_lot::Mutex<Vec<AuditEntry>>>,
+}
+
+impl MemoryAuditWriter {
+    /// Entries appended so far
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.lock().clone()
+    }
+}
+
+#[async_trait]
+impl AuditWriter for MemoryAuditWriter {
+    async fn append(&self, entry: &AuditEntry) -> Result<()> {
+        self.entries.lock().push(entry.clone());
+        Ok(())
+    }
+}
+
+/// Appends entries to a file, one JSON object per line
+#[derive(Debug, Clone)]
+pub struct JsonLinesWriter {
+    path: PathBuf,
+}
+
+impl JsonLinesWriter {
+    /// Append to the file at `path`, creating it if needed
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl AuditWriter for JsonLinesWriter {
+    async fn append(&self, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)
+            .map_err(|e| Error::Encoding(format!("Failed to encode audit entry: {}", e)))?;
+        line.push(b'\n');
+        let io = |e: std::io::Error| {
+            Error::Storage(format!(
+                "Failed to write audit log {}: {}",
+                self.path.display(),
+                e
+            ))
+        };
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(io)?;
+        file.write_all(&line).await.map_err(io)?;
+        file.sync_data().await.map_err(io)
+    }
+}
+
+/// Position of the last written entry
+#[derive(Debug)]
+struct ChainHead {
+    next_sequence: u64,
+    last_hash: String,
+}
+
+/// Append-only, hash-chained log of signing and submission
+///
+/// Clones share the writer and the chain; [`with_context`](Self::with_context)
+/// gives a clone its own context.
+#[derive(Clone)]
+pub struct AuditLog {
+    writer: Arc<dyn AuditWriter>,
+    head: Arc<Mutex<ChainHead>>,
+    context: BTreeMap<String, String>,
+}
+
+impl AuditLog {
+    /// Start a new log
+    pub fn new(writer: impl AuditWriter + 'static) -> Self {
+        Self::starting_at(writer, 0, GENESIS_HASH.to_string())
+    }
+
+    /// Continue an existing log after its last entry
+    pub fn resume(writer: impl AuditWriter + 'static, last: &AuditEntry) -> Self {
+        Self::starting_at(writer, last.sequence + 1, last.hash.clone())
+    }
+
+    fn starting_at(
+        writer: impl AuditWriter + 'static,
+        next_sequence: u64,
+        last_hash: String,
+    ) -> Self {
+        Self {
+            writer: Arc::new(writer),
+            head: Arc::new(Mutex::new(ChainHead {
+                next_sequence,
+                last_hash,
+            })),
+            context: BTreeMap::new(),
+        }
+    }
+
+    /// Add operator context to every entry recorded through this clone
+    pub fn with_context(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.context.insert(key.into(), value.into());
+        self
+    }
+
+    /// Append an entry and return it
+    pub async fn record(
+        &self,
+        operation: AuditOperation,
+        signer: &str,
+        payload_hash: &str,
+        outcome: AuditOutcome,
+    ) -> Result<AuditEntry> {
+        let mut head = self.head.lock().await;
+        let mut entry = AuditEntry {
+            sequence: head.next_sequence,
+            timestamp: chrono::Utc::now().timestamp_millis() as u64,
+            operation,
+            signer: signer.to_string(),
+            payload_hash: payload_hash.to_string(),
+            outcome,
+            context: self.context.clone(),
+            prev_hash: head.last_hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        self.writer.append(&entry).await?;
+
+        head.next_sequence += 1;
+        head.last_hash = entry.hash.clone();
+        Ok(entry)
+    }
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("context", &self.context)
+            .finish()
+    }
+}
+
+/// Blake2-256 of a payload, as recorded in [`AuditEntry::payload_hash`]
+pub fn payload_hash(payload: &[u8]) -> String {
+    format!("0x{}", hex::encode(sp_core::blake2_256(payload)))
+}
+
+/// Check that entries form an unbroken chain from `prev_hash`
+///
+/// Pass [`GENESIS_HASH`] for a complete log. Fails at the first entry that
+/// was altered or does not follow its predecessor.
+pub fn verify_chain(entries: &[AuditEntry], prev_hash: &str) -> Result<()> {
+    let mut expected_hash = prev_hash;
+    let mut expected_sequence = entries.first().map(|entry| entry.sequence);
+    for entry in entries {
+        let broken = |why: &str| {
+            Err(Error::Storage(format!(
+                "Audit log broken at entry {}: {}",
+                entry.sequence, why
+            )))
+        };
+        if Some(entry.sequence) != expected_sequence {
+            return broken("sequence gap");
+        }
+        if entry.prev_hash != expected_hash {
+            return broken("previous hash mismatch");
+        }
+        if entry.compute_hash() != entry.hash {
+            return broken("contents altered");
+        }
+        expected_hash = &entry.hash;
+        expected_sequence = Some(entry.sequence + 1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[tokio::test]
+    async fn test_entries_are_chained() {
+        let writer = MemoryAuditWriter::default();
+        let log = AuditLog::new(writer.clone()).with_context("operator", "ops");
+        let hash = payload_hash(b"call");
+        log.record(AuditOperation::Sign, ALICE, &hash, AuditOutcome::Requested)
+            .await
+            .unwrap();
+        log.with_context("ticket", "OPS-1")
+            .record(
+                AuditOperation::Submit,
+                ALICE,
+                &payload_hash(b"extrinsic"),
+                AuditOutcome::Included {
+                    tx_hash: "0x01".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let entries = writer.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].prev_hash, entries[0].hash);
+        assert_eq!(entries[1].context["ticket"], "OPS-1");
+        assert_eq!(entries[1].context["operator"], "ops");
+        verify_chain(&entries, GENESIS_HASH).unwrap();
+        verify_chain(&entries[1..], &entries[0].hash).unwrap();
+
+        // a resumed log continues the chain
+        let resumed = AuditLog::resume(writer.clone(), &entries[1]);
+        resumed
+            .record(AuditOperation::Sign, ALICE, &hash, AuditOutcome::Requested)
+            .await
+            .unwrap();
+        verify_chain(&writer.entries(), GENESIS_HASH).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tampering_is_detected() {
+        let writer = MemoryAuditWriter::default();
+        let log = AuditLog::new(writer.clone());
+        for _ in 0..3 {
+            log.record(
+                AuditOperation::Sign,
+                ALICE,
+                &payload_hash(b"call"),
+                AuditOutcome::Requested,
+            )
+            .await
+            .unwrap();
+        }
+
+        let mut altered = writer.entries();
+        altered[1].signer = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty".to_string();
+        assert!(verify_chain(&altered, GENESIS_HASH).is_err());
+
+        let mut removed = writer.entries();
+        removed.remove(1);
+        assert!(verify_chain(&removed, GENESIS_HASH).is_err());
+    }
+}
@@ -0,0 +1,543 @@
+//! Auto-compounding of staking rewards
+//!
+//! [`AutoCompounder`] claims the stash's unclaimed era rewards with
+//! `Staking::payout_stakers` and bonds what they paid with
+//! `Staking::bond_extra`, on a cron schedule. Every step is recorded as a
+//! [`CompoundAction`], including the ones it skipped and why, so operators
+//! can audit what the bot did. Transactions go through the adapter's
+//! executor, so its policy and audit log apply.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{AutoCompounder, CompoundAction, CompoundConfig, CronSchedule};
+//! # use apex_sdk_substrate::{SubstrateAdapter, Wallet};
+//!
+//! # async fn example(adapter: &SubstrateAdapter, stash: Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! let config = CompoundConfig::default()
+//!     .with_min_rebond(10_000_000_000) // 1 DOT
+//!     .with_max_fee(200_000_000)
+//!     .with_keep_free(5_000_000_000);
+//!
+//! AutoCompounder::new(adapter, stash)
+//!     .with_config(config)
+//!     .with_schedule(CronSchedule::parse("15 0 * * *")?)
+//!     .on_action(|action: &CompoundAction| {
+//!         println!("{}", serde_json::to_string(action).unwrap())
+//!     })
+//!     .run()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Rewards are bonded only when they are paid to the stash as free balance;
+//! with a `Staked` payee the runtime bonds them itself and only claims are
+//! made.
+
+use crate::event_query::{account_hex, account_id};
+use crate::scheduler::CronSchedule;
+use crate::{Error, Result, SubstrateAdapter, TransactionExecutor, Wallet};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+use subxt::dynamic::Value;
+use tracing::{info, warn};
+
+/// Default most payouts claimed per run
+pub const DEFAULT_MAX_PAYOUTS: usize = 8;
+
+/// Limits of an [`AutoCompounder`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompoundConfig {
+    /// Smallest amount worth bonding
+    pub min_rebond: u128,
+    /// Highest fee paid for one transaction
+    pub max_fee: Option<u128>,
+    /// Transferable balance never bonded, kept for fees
+    pub keep_free: u128,
+    /// Most payouts claimed per run
+    pub max_payouts: usize,
+}
+
+impl Default for CompoundConfig {
+    fn default() -> Self {
+        Self {
+            min_rebond: 0,
+            max_fee: None,
+            keep_free: 0,
+            max_payouts: DEFAULT_MAX_PAYOUTS,
+        }
+    }
+}
+
+impl CompoundConfig {
+    /// Skip bonding rewards smaller than `amount`
+    pub fn with_min_rebond(mut self, amount: u128) -> Self {
+        self.min_rebond = amount;
+        self
+    }
+
+    /// Skip transactions whose estimated fee is above `fee`
+    pub fn with_max_fee(mut self, fee: u128) -> Self {
+        self.max_fee = Some(fee);
+        self
+    }
+
+    /// Keep `amount` of transferable balance unbonded
+    pub fn with_keep_free(mut self, amount: u128) -> Self {
+        self.keep_free = amount;
+        self
+    }
+
+    /// Claim at most `count` payouts per run
+    pub fn with_max_payouts(mut self, count: usize) -> Self {
+        self.max_payouts = count;
+        self
+    }
+
+    /// Amount to bond after `reward` was paid, with `transferable` balance
+    ///
+    /// Errors give the reason nothing is bonded.
+    pub fn rebond_amount(
+        &self,
+        reward: u128,
+        transferable: u128,
+    ) -> std::result::Result<u128, String> {
+        let amount = reward.min(transferable.saturating_sub(self.keep_free));
+        if amount == 0 {
+            return Err("no reward to bond".to_string());
+        }
+        if amount < self.min_rebond {
+            return Err(format!(
+                "reward {} is below the minimum {}",
+                amount, self.min_rebond
+            ));
+        }
+        Ok(amount)
+    }
+
+    /// Reason a transaction with `fee` is not made, if any
+    pub fn fee_exceeded(&self, fee: u128) -> Option<String> {
+        self.max_fee
+            .filter(|max| fee > *max)
+            .map(|max| format!("fee {} is above the ceiling {}", fee, max))
+    }
+}
+
+/// A step of a compounding run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CompoundStep {
+    /// Claim the rewards of `validator` for `era`
+    Claim {
+        /// Validator stash
+        validator: String,
+        /// Era claimed
+        era: u32,
+    },
+    /// Bond `amount` of the claimed rewards
+    Rebond {
+        /// Amount bonded
+        amount: u128,
+    },
+}
+
+/// What happened to a step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum StepOutcome {
+    /// The transaction was included
+    Submitted {
+        /// Extrinsic hash
+        tx_hash: String,
+        /// Estimated fee
+        fee: u128,
+    },
+    /// Dry run: the transaction would have been submitted
+    Planned {
+        /// Estimated fee
+        fee: u128,
+    },
+    /// The step was not made
+    Skipped {
+        /// Why
+        reason: String,
+    },
+    /// The transaction failed
+    Failed {
+        /// Error message
+        error: String,
+    },
+}
+
+/// One entry of the action log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompoundAction {
+    /// Unix time in milliseconds
+    pub timestamp: u64,
+    /// Stash compounded
+    pub stash: String,
+    /// Step taken
+    pub step: CompoundStep,
+    /// What happened
+    pub outcome: StepOutcome,
+}
+
+/// Receiver of compounding actions
+#[async_trait]
+pub trait CompoundHandler: Send + Sync {
+    /// Called once for every action, in order
+    async fn on_action(&self, action: &CompoundAction);
+}
+
+#[async_trait]
+impl<F> CompoundHandler for F
+where
+    F: Fn(&CompoundAction) + Send + Sync,
+{
+    async fn on_action(&self, action: &CompoundAction) {
+        self(action)
+    }
+}
+
+/// Where a stash's rewards are paid, from `Staking::Payee`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Payee {
+    Staked,
+    Stash,
+    Elsewhere,
+}
+
+/// Claims and bonds a stash's staking rewards
+pub struct AutoCompounder {
+    adapter: SubstrateAdapter,
+    executor: TransactionExecutor,
+    wallet: Wallet,
+    config: CompoundConfig,
+    schedule: CronSchedule,
+    validators: Option<Vec<String>>,
+    handlers: Vec<Arc<dyn CompoundHandler>>,
+    dry_run: bool,
+}
+
+impl AutoCompounder {
+    /// Compound the rewards of the stash `wallet`, daily by default
+    pub fn new(adapter: &SubstrateAdapter, wallet: Wallet) -> Self {
+        Self {
+            adapter: adapter.clone(),
+            executor: adapter.transaction_executor(),
+            wallet,
+            config: CompoundConfig::default(),
+            schedule: CronSchedule::parse("@daily").expect("valid cron expression"),
+            validators: None,
+            handlers: Vec::new(),
+            dry_run: false,
+        }
+    }
+
+    /// Thresholds and limits
+    pub fn with_config(mut self, config: CompoundConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// When [`run`](Self::run) compounds
+    pub fn with_schedule(mut self, schedule: CronSchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// Claim for these validators instead of the stash's nominations
+    pub fn with_validators(mut self, validators: Vec<String>) -> Self {
+        self.validators = Some(validators);
+        self
+    }
+
+    /// Log the actions with fee estimates without submitting anything
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Register an action handler
+    pub fn on_action(mut self, handler: impl CompoundHandler + 'static) -> Self {
+        self.handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Compound on the schedule until the chain cannot be read
+    pub async fn run(&self) -> Result<()> {
+        info!(
+            "Compounding rewards of {} on {}",
+            self.wallet.address(),
+            self.schedule.expression()
+        );
+        loop {
+            let now = chrono::Utc::now().timestamp_millis() as u64;
+            let next = self.schedule.next_after(now).ok_or_else(|| {
+                Error::Other(format!(
+                    "Schedule {} never fires",
+                    self.schedule.expression()
+                ))
+            })?;
+            tokio::time::sleep(std::time::Duration::from_millis(next - now)).await;
+            for action in self.compound_once().await? {
+                for handler in &self.handlers {
+                    handler.on_action(&action).await;
+                }
+            }
+        }
+    }
+
+    /// Claim unclaimed rewards and bond them now
+    ///
+    /// Failed or skipped steps are logged in the returned actions; errors
+    /// are from reading the chain.
+    pub async fn compound_once(&self) -> Result<Vec<CompoundAction>> {
+        let stash = self.wallet.address();
+        let mut actions = Vec::new();
+        let before = self.transferable(&stash).await?;
+
+        let mut claimed = 0;
+        for (validator, era) in self.unclaimed().await? {
+            if claimed == self.config.max_payouts {
+                break;
+            }
+            let args = vec![
+                Value::from_bytes(account_id(&validator)?),
+                Value::u128(era as u128),
+            ];
+            let outcome = self.step("payout_stakers", args).await;
+            claimed += 1;
+            actions.push(self.action(CompoundStep::Claim { validator, era }, outcome));
+        }
+        if claimed == 0 {
+            return Ok(actions);
+        }
+
+        let after = self.transferable(&stash).await?;
+        let reward = after.saturating_sub(before);
+        let outcome = match self.payee(&stash).await? {
+            Payee::Staked => StepOutcome::Skipped {
+                reason: "rewards are bonded by the runtime".to_string(),
+            },
+            Payee::Elsewhere => StepOutcome::Skipped {
+                reason: "rewards are paid to another account".to_string(),
+            },
+            // in a dry run nothing was claimed, so plan with the transferable balance
+            Payee::Stash => match self
+                .config
+                .rebond_amount(if self.dry_run { after } else { reward }, after)
+            {
+                Ok(amount) => {
+                    let outcome = self.step("bond_extra", vec![Value::u128(amount)]).await;
+                    actions.push(self.action(CompoundStep::Rebond { amount }, outcome));
+                    return Ok(actions);
+                }
+                Err(reason) => StepOutcome::Skipped { reason },
+            },
+        };
+        actions.push(self.action(CompoundStep::Rebond { amount: reward }, outcome));
+        Ok(actions)
+    }
+
+    /// Unclaimed `(validator, era)` payouts within the history depth, oldest first
+    async fn unclaimed(&self) -> Result<Vec<(String, u32)>> {
+        let storage = self.adapter.storage();
+        let active_era = storage
+            .query_storage_json("Staking", "ActiveEra", vec![])
+            .await?
+            .and_then(|era| era.get("index").and_then(JsonValue::as_u64))
+            .ok_or_else(|| Error::Storage("Staking::ActiveEra is not set".to_string()))?
+            as u32;
+        let history_depth = storage
+            .get_constant_json("Staking", "HistoryDepth")?
+            .as_u64()
+            .unwrap_or(84) as u32;
+
+        let validators = self.validators().await?;
+        let mut unclaimed = Vec::new();
+        for era in active_era.saturating_sub(history_depth)..active_era {
+            for validator in &validators {
+                let keys = || -> Result<Vec<Value>> {
+                    Ok(vec![
+                        Value::u128(era as u128),
+                        Value::from_bytes(account_id(validator)?),
+                    ])
+                };
+                let exposed = storage
+                    .query_storage_json("Staking", "ErasStakersOverview", keys()?)
+                    .await?
+                    .is_some();
+                let claimed = storage
+                    .query_storage_json("Staking", "ClaimedRewards", keys()?)
+                    .await?
+                    .and_then(|pages| pages.as_array().map(|pages| !pages.is_empty()))
+                    .unwrap_or(false);
+                if exposed && !claimed {
+                    unclaimed.push((validator.clone(), era));
+                }
+            }
+        }
+        Ok(unclaimed)
+    }
+
+    /// Validators whose payouts include the stash
+    async fn validators(&self) -> Result<Vec<String>> {
+        if let Some(validators) = &self.validators {
+            return Ok(validators.clone());
+        }
+        let stash = self.wallet.address();
+        let key = || -> Result<Vec<Value>> { Ok(vec![Value::from_bytes(account_id(&stash)?)]) };
+        let storage = self.adapter.storage();
+
+        let mut validators = Vec::new();
+        if storage
+            .query_storage_json("Staking", "Validators", key()?)
+            .await?
+            .is_some()
+        {
+            validators.push(account_hex(&stash)?);
+        }
+        if let Some(nominations) = storage
+            .query_storage_json("Staking", "Nominators", key()?)
+            .await?
+        {
+            validators.extend(
+                nominations["targets"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|target| target.as_str().map(str::to_string)),
+            );
+        }
+        Ok(validators)
+    }
+
+    async fn payee(&self, stash: &str) -> Result<Payee> {
+        let payee = self
+            .adapter
+            .storage()
+            .query_storage_json(
+                "Staking",
+                "Payee",
+                vec![Value::from_bytes(account_id(stash)?)],
+            )
+            .await?;
+        Ok(match payee.as_ref().and_then(JsonValue::as_str) {
+            Some("Staked") => Payee::Staked,
+            Some("Stash") => Payee::Stash,
+            _ => Payee::Elsewhere,
+        })
+    }
+
+    async fn transferable(&self, stash: &str) -> Result<u128> {
+        Ok(self
+            .adapter
+            .storage()
+            .get_account_info(stash)
+            .await?
+            .transferable())
+    }
+
+    /// Estimate, check against the fee ceiling and submit a staking call
+    async fn step(&self, call: &str, args: Vec<Value>) -> StepOutcome {
+        let fee = match self
+            .executor
+            .estimate_fee("Staking", call, args.clone(), &self.wallet)
+            .await
+        {
+            Ok(fee) => fee,
+            Err(e) => {
+                return StepOutcome::Failed {
+                    error: e.to_string(),
+                }
+            }
+        };
+        if let Some(reason) = self.config.fee_exceeded(fee) {
+            return StepOutcome::Skipped { reason };
+        }
+        if self.dry_run {
+            return StepOutcome::Planned { fee };
+        }
+        match self
+            .executor
+            .submit_call(&self.wallet, "Staking", call, args)
+            .await
+        {
+            Ok(tx_hash) => StepOutcome::Submitted { tx_hash, fee },
+            Err(e) => {
+                warn!("Staking::{} failed: {}", call, e);
+                StepOutcome::Failed {
+                    error: e.to_string(),
+                }
+            }
+        }
+    }
+
+    fn action(&self, step: CompoundStep, outcome: StepOutcome) -> CompoundAction {
+        CompoundAction {
+            timestamp: chrono::Utc::now().timestamp_millis() as u64,
+            stash: self.wallet.address(),
+            step,
+            outcome,
+        }
+    }
+}
+
+impl std::fmt::Debug for AutoCompounder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AutoCompounder")
+            .field("stash", &self.wallet.address())
+            .field("config", &self.config)
+            .field("schedule", &self.schedule.expression())
+            .field("dry_run", &self.dry_run)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebond_amount() {
+        let config = CompoundConfig::default()
+            .with_min_rebond(1_000)
+            .with_keep_free(500)
+            .with_max_fee(100);
+
+        assert_eq!(config.rebond_amount(2_000, 10_000), Ok(2_000));
+        // the reserve for fees is never bonded
+        assert_eq!(config.rebond_amount(2_000, 2_200), Ok(1_700));
+        assert!(config.rebond_amount(2_000, 1_200).is_err());
+        assert!(config.rebond_amount(999, 10_000).is_err());
+        assert!(config.rebond_amount(0, 10_000).is_err());
+
+        assert_eq!(config.fee_exceeded(100), None);
+        assert!(config.fee_exceeded(101).is_some());
+        assert_eq!(CompoundConfig::default().fee_exceeded(u128::MAX), None);
+    }
+
+    #[test]
+    fn test_action_log_format() {
+        let action = CompoundAction {
+            timestamp: 1,
+            stash: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string(),
+            step: CompoundStep::Claim {
+                validator: "0x01".to_string(),
+                era: 1_500,
+            },
+            outcome: StepOutcome::Skipped {
+                reason: "fee 10 is above the ceiling 5".to_string(),
+            },
+        };
+        let json = serde_json::to_value(&action).unwrap();
+        assert_eq!(json["step"]["type"], "claim");
+        assert_eq!(json["outcome"]["status"], "skipped");
+        assert_eq!(
+            serde_json::from_value::<CompoundAction>(json).unwrap(),
+            action
+        );
+    }
+}
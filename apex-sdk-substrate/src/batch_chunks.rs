@@ -0,0 +1,202 @@
+//! Automatic chunking of oversized batches
+//!
+//! A batch too long or too heavy for one block is split into consecutive
+//! chunks that are submitted one after another, each as its own `Utility`
+//! batch. [`TransactionExecutor::execute_batch_chunked`] does the planning
+//! and reports the outcome of every chunk in a [`ChunkedBatch`]:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{BatchCall, BatchMode, SubstrateAdapter, Wallet};
+//!
+//! # async fn example(adapter: &SubstrateAdapter, wallet: &Wallet, calls: Vec<BatchCall>) -> Result<(), apex_sdk_substrate::Error> {
+//! let report = adapter
+//!     .transaction_executor()
+//!     .execute_batch_chunked(calls, wallet, BatchMode::AllOrNothing)
+//!     .await?;
+//!
+//! for chunk in &report.chunks {
+//!     println!("calls {:?}: {:?}", chunk.calls, chunk.outcome);
+//! }
+//! for calls in report.rollback() {
+//!     println!("calls {:?} went through and must be reverted by hand", calls);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Atomicity only holds within a chunk. With [`BatchMode::AllOrNothing`] the
+//! remaining chunks are skipped after the first failure, and
+//! [`ChunkedBatch::rollback`] lists the calls that were already finalized.
+//!
+//! [`TransactionExecutor::execute_batch_chunked`]: crate::TransactionExecutor::execute_batch_chunked
+
+use crate::transaction::{BatchCall, BatchMode};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Bytes reserved for the signature, extensions and `Utility` call header
+pub const BATCH_OVERHEAD_BYTES: u64 = 256;
+
+/// What happened to one chunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ChunkOutcome {
+    /// Finalized successfully
+    Finalized {
+        /// Hash of the chunk's batch extrinsic
+        tx_hash: String,
+    },
+    /// Submission or execution failed
+    Failed {
+        /// Error message
+        error: String,
+    },
+    /// Not submitted because an earlier chunk failed
+    Skipped,
+}
+
+/// One batch extrinsic of a chunked batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchChunk {
+    /// Position of the chunk
+    pub index: usize,
+    /// Indices of the original calls in the chunk
+    pub calls: Range<usize>,
+    /// Outcome of the chunk
+    pub outcome: ChunkOutcome,
+}
+
+/// Outcome of a batch submitted in chunks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkedBatch {
+    /// Mode every chunk was submitted with
+    pub mode: BatchMode,
+    /// Chunks in submission order
+    pub chunks: Vec<BatchChunk>,
+}
+
+impl ChunkedBatch {
+    /// Whether every chunk was finalized
+    pub fn is_success(&self) -> bool {
+        self.chunks
+            .iter()
+            .all(|chunk| matches!(chunk.outcome, ChunkOutcome::Finalized { .. }))
+    }
+
+    /// Hashes of the finalized chunk extrinsics, in order
+    pub fn tx_hashes(&self) -> Vec<&str> {
+        self.chunks
+            .iter()
+            .filter_map(|chunk| match &chunk.outcome {
+                ChunkOutcome::Finalized { tx_hash } => Some(tx_hash.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// First chunk that failed
+    pub fn failed(&self) -> Option<&BatchChunk> {
+        self.chunks
+            .iter()
+            .find(|chunk| matches!(chunk.outcome, ChunkOutcome::Failed { .. }))
+    }
+
+    /// Calls to revert by hand to restore all-or-nothing semantics
+    ///
+    /// Empty unless the batch was submitted with [`BatchMode::AllOrNothing`]
+    /// and a chunk failed after others were finalized.
+    pub fn rollback(&self) -> Vec<Range<usize>> {
+        if self.mode != BatchMode::AllOrNothing || self.failed().is_none() {
+            return Vec::new();
+        }
+        self.chunks
+            .iter()
+            .filter(|chunk| matches!(chunk.outcome, ChunkOutcome::Finalized { .. }))
+            .map(|chunk| chunk.calls.clone())
+            .collect()
+    }
+}
+
+/// Encoded length of a call inside a batch
+pub fn call_length(call: &BatchCall) -> u64 {
+    2 + call.args_encoded.len() as u64
+}
+
+/// Split calls into consecutive ranges whose batch fits in `max_length` bytes
+///
+/// A call too long to fit even on its own gets a chunk of its own, which the
+/// node will reject.
+pub fn split_by_length(calls: &[BatchCall], max_length: u64) -> Vec<Range<usize>> {
+    let budget = max_length.saturating_sub(BATCH_OVERHEAD_BYTES);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut length = 0;
+    for (index, call) in calls.iter().enumerate() {
+        let call_length = call_length(call);
+        if index > start && length + call_length > budget {
+            chunks.push(start..index);
+            start = index;
+            length = 0;
+        }
+        length += call_length;
+    }
+    if start < calls.len() {
+        chunks.push(start..calls.len());
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_by_length() {
+        let calls: Vec<BatchCall> = (0..10).map(|_| BatchCall::new(5, 3, vec![0; 98])).collect();
+        assert_eq!(split_by_length(&calls, 10_000), vec![0..10]);
+        assert_eq!(
+            split_by_length(&calls, BATCH_OVERHEAD_BYTES + 350),
+            vec![0..3, 3..6, 6..9, 9..10]
+        );
+        // an oversized call still gets its own chunk
+        let oversized = vec![BatchCall::new(0, 0, vec![0; 1_000]), calls[0].clone()];
+        assert_eq!(
+            split_by_length(&oversized, BATCH_OVERHEAD_BYTES + 500),
+            vec![0..1, 1..2]
+        );
+        assert!(split_by_length(&[], 10_000).is_empty());
+    }
+
+    #[test]
+    fn test_rollback_guidance() {
+        let chunk = |index: usize, outcome| BatchChunk {
+            index,
+            calls: index * 100..(index + 1) * 100,
+            outcome,
+        };
+        let finalized = |hash: &str| ChunkOutcome::Finalized {
+            tx_hash: hash.to_string(),
+        };
+        let mut report = ChunkedBatch {
+            mode: BatchMode::AllOrNothing,
+            chunks: vec![
+                chunk(0, finalized("0x01")),
+                chunk(1, finalized("0x02")),
+                chunk(
+                    2,
+                    ChunkOutcome::Failed {
+                        error: "Batch transaction failed".to_string(),
+                    },
+                ),
+                chunk(3, ChunkOutcome::Skipped),
+            ],
+        };
+        assert!(!report.is_success());
+        assert_eq!(report.tx_hashes(), vec!["0x01", "0x02"]);
+        assert_eq!(report.failed().map(|chunk| chunk.index), Some(2));
+        assert_eq!(report.rollback(), vec![0..100, 100..200]);
+
+        report.mode = BatchMode::Optimistic;
+        assert!(report.rollback().is_empty());
+    }
+}
@@ -7,31 +7,119 @@
 //! - Detect block finality
 //! - Parse extrinsics and compute hashes
 
+use crate::event_query::decode_events;
+use crate::receipt::ExtrinsicReceipt;
+use crate::rpc_spec::SpecClient;
 use crate::Error;
-use apex_sdk_core::{BlockEvent, BlockInfo, DetailedBlockInfo, ExtrinsicInfo};
+use apex_sdk_core::{BlockEvent, BlockInfo, DetailedBlockInfo, ExtrinsicInfo, RawBlockInfo};
 use subxt::{OnlineClient, PolkadotConfig};
 use tracing::debug;
 
+/// Block type returned by the subxt online client
+type SubxtBlock = subxt::blocks::Block<PolkadotConfig, OnlineClient<PolkadotConfig>>;
+
 /// Block query client for retrieving and parsing block information
+///
+/// Clones share the underlying connection, so a `BlockQuery` can be cloned into
+/// each task that needs one.
+#[derive(Clone)]
 pub struct BlockQuery {
     client: OnlineClient<PolkadotConfig>,
+    spec: Option<SpecClient>,
 }
 
 impl BlockQuery {
     /// Create a new BlockQuery instance
     pub fn new(client: OnlineClient<PolkadotConfig>) -> Self {
-        Self { client }
+        Self { client, spec: None }
+    }
+
+    /// Resolve block numbers to hashes through RPC instead of walking back
+    /// from the latest block
+    ///
+    /// With `archive_*_hashByHeight` (or `chain_getBlockHash`) any block can be
+    /// fetched by number in one lookup, however far it is from the head.
+    pub fn with_spec_client(mut self, spec: SpecClient) -> Self {
+        self.spec = Some(spec);
+        self
     }
 
     /// Get block information by block number
     ///
-    /// This method queries the latest finalized block and traverses backwards
-    /// to find the requested block number. For recent blocks, this is efficient.
-    /// For historical blocks far from the current height, consider using get_block_by_hash
-    /// if you have the block hash.
+    /// With a [`SpecClient`] attached the hash is looked up directly. Otherwise
+    /// this walks back from the latest block, which only reaches the last 100
+    /// blocks; use get_block_by_hash for older blocks.
     pub async fn get_block_by_number(&self, block_number: u64) -> Result<BlockInfo, Error> {
+        Ok(self.get_raw_block_by_number(block_number).await?.into())
+    }
+
+    /// Get block information by block hash
+    ///
+    /// This is the most efficient way to query a specific block if you have its hash.
+    pub async fn get_block_by_hash(&self, hash_hex: &str) -> Result<BlockInfo, Error> {
+        Ok(self.get_raw_block_by_hash(hash_hex).await?.into())
+    }
+
+    /// Get byte-backed block data by block number
+    ///
+    /// Unlike [`get_block_by_number`](Self::get_block_by_number), hashes are kept as
+    /// raw bytes and only hex-encoded on demand through
+    /// [`RawBlockInfo::as_block_ref`]. Prefer this during high-throughput backfills.
+    pub async fn get_raw_block_by_number(&self, block_number: u64) -> Result<RawBlockInfo, Error> {
         debug!("Fetching block by number: {}", block_number);
 
+        let block = self.find_block_by_number(block_number).await?;
+        self.parse_raw_block_info(&block).await
+    }
+
+    /// Get byte-backed block data by block hash
+    pub async fn get_raw_block_by_hash(&self, hash_hex: &str) -> Result<RawBlockInfo, Error> {
+        debug!("Fetching block by hash: {}", hash_hex);
+
+        // Parse the hex string to H256
+        let hash_hex = hash_hex.trim_start_matches("0x");
+        let hash_bytes = hex::decode(hash_hex)
+            .map_err(|e| Error::Transaction(format!("Invalid block hash: {}", e)))?;
+
+        if hash_bytes.len() != 32 {
+            return Err(Error::Transaction(
+                "Block hash must be 32 bytes".to_string(),
+            ));
+        }
+
+        let mut hash_array = [0u8; 32];
+        hash_array.copy_from_slice(&hash_bytes);
+        let block_hash: subxt::utils::H256 = hash_array.into();
+
+        // Query the block
+        let block = self
+            .client
+            .blocks()
+            .at(block_hash)
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get block: {}", e)))?;
+
+        self.parse_raw_block_info(&block).await
+    }
+
+    /// Locate a block by number
+    pub(crate) async fn find_block_by_number(
+        &self,
+        block_number: u64,
+    ) -> Result<SubxtBlock, Error> {
+        if let Some(spec) = &self.spec {
+            let hash = spec
+                .block_hash(block_number)
+                .await?
+                .ok_or_else(|| Error::Transaction(format!("Block {} not found", block_number)))?;
+            return self
+                .client
+                .blocks()
+                .at(hash)
+                .await
+                .map_err(|e| Error::Connection(format!("Failed to get block: {}", e)));
+        }
+
         // Get the latest finalized block
         let latest_block = self
             .client
@@ -52,7 +140,7 @@ impl BlockQuery {
 
         // If requesting the latest block, return it directly
         if block_number == latest_number {
-            return self.parse_block_info(latest_block).await;
+            return Ok(latest_block);
         }
 
         // For historical blocks, we need to traverse backwards or query by hash
@@ -68,7 +156,7 @@ impl BlockQuery {
                 match self.client.blocks().at(parent_hash).await {
                     Ok(parent) => {
                         if parent.number() as u64 == block_number {
-                            return self.parse_block_info(parent).await;
+                            return Ok(parent);
                         }
                         current_block = parent;
                     }
@@ -90,91 +178,14 @@ impl BlockQuery {
         )))
     }
 
-    /// Get block information by block hash
-    ///
-    /// This is the most efficient way to query a specific block if you have its hash.
-    pub async fn get_block_by_hash(&self, hash_hex: &str) -> Result<BlockInfo, Error> {
-        debug!("Fetching block by hash: {}", hash_hex);
-
-        // Parse the hex string to H256
-        let hash_hex = hash_hex.trim_start_matches("0x");
-        let hash_bytes = hex::decode(hash_hex)
-            .map_err(|e| Error::Transaction(format!("Invalid block hash: {}", e)))?;
-
-        if hash_bytes.len() != 32 {
-            return Err(Error::Transaction(
-                "Block hash must be 32 bytes".to_string(),
-            ));
-        }
-
-        let mut hash_array = [0u8; 32];
-        hash_array.copy_from_slice(&hash_bytes);
-        let block_hash: subxt::utils::H256 = hash_array.into();
-
-        // Query the block
-        let block = self
-            .client
-            .blocks()
-            .at(block_hash)
-            .await
-            .map_err(|e| Error::Connection(format!("Failed to get block: {}", e)))?;
-
-        self.parse_block_info(block).await
-    }
-
     /// Get detailed block information including extrinsics and events
     pub async fn get_detailed_block(&self, block_number: u64) -> Result<DetailedBlockInfo, Error> {
         debug!("Fetching detailed block info for block: {}", block_number);
 
-        // First get the block
-        let latest_block = self
-            .client
-            .blocks()
-            .at_latest()
-            .await
-            .map_err(|e| Error::Connection(format!("Failed to get latest block: {}", e)))?;
-
-        let latest_number = latest_block.number() as u64;
-
-        if block_number > latest_number {
-            return Err(Error::Transaction(format!(
-                "Block {} not found (latest: {})",
-                block_number, latest_number
-            )));
-        }
-
-        // Get the block
-        let block = if block_number == latest_number {
-            latest_block
-        } else {
-            // Traverse backwards for recent blocks
-            let search_depth = latest_number.saturating_sub(block_number);
-            const MAX_TRAVERSE_DEPTH: u64 = 100;
-
-            if search_depth > MAX_TRAVERSE_DEPTH {
-                return Err(Error::Transaction(format!(
-                    "Block {} is too far from current height {}",
-                    block_number, latest_number
-                )));
-            }
-
-            let mut current_block = latest_block;
-            for _ in 0..search_depth {
-                let parent_hash = current_block.header().parent_hash;
-                current_block =
-                    self.client.blocks().at(parent_hash).await.map_err(|e| {
-                        Error::Connection(format!("Failed to traverse blocks: {}", e))
-                    })?;
-
-                if current_block.number() as u64 == block_number {
-                    break;
-                }
-            }
-            current_block
-        };
+        let block = self.find_block_by_number(block_number).await?;
 
         // Parse basic block info
-        let basic_info = self.parse_block_info(block.clone()).await?;
+        let basic_info = self.parse_block_info(&block).await?;
 
         // Parse extrinsics
         let extrinsics = self.extract_extrinsics(&block).await?;
@@ -189,17 +200,39 @@ impl BlockQuery {
         })
     }
 
+    /// Receipts of every extrinsic in a block, with the events each emitted
+    pub async fn get_receipts(&self, block_number: u64) -> Result<Vec<ExtrinsicReceipt>, Error> {
+        let block = self.find_block_by_number(block_number).await?;
+        let hash = format!("0x{}", hex::encode(block.hash().0));
+
+        let extrinsics = self.extract_extrinsics(&block).await?;
+        let events = block.events().await.map_err(|e| {
+            Error::Connection(format!(
+                "Failed to get events of block {}: {}",
+                block_number, e
+            ))
+        })?;
+        let events = decode_events(&events, block_number, &hash)?;
+
+        Ok(extrinsics
+            .into_iter()
+            .map(|extrinsic| ExtrinsicReceipt::new(block_number, hash.clone(), extrinsic, &events))
+            .collect())
+    }
+
     /// Parse block information from a subxt Block
-    async fn parse_block_info(
-        &self,
-        block: subxt::blocks::Block<PolkadotConfig, OnlineClient<PolkadotConfig>>,
-    ) -> Result<BlockInfo, Error> {
-        let number = block.number() as u64;
-        let hash = format!("0x{}", hex::encode(block.hash()));
-        let parent_hash = format!("0x{}", hex::encode(block.header().parent_hash));
+    async fn parse_block_info(&self, block: &SubxtBlock) -> Result<BlockInfo, Error> {
+        Ok(self.parse_raw_block_info(block).await?.into())
+    }
+
+    /// Parse byte-backed block information from a subxt Block
+    ///
+    /// Hashes are copied as fixed-size arrays; no hex strings are allocated here.
+    async fn parse_raw_block_info(&self, block: &SubxtBlock) -> Result<RawBlockInfo, Error> {
+        let header = block.header();
 
         // Extract timestamp
-        let timestamp = self.extract_timestamp(&block).await?;
+        let timestamp = self.extract_timestamp(block).await?;
 
         // Get extrinsics and compute hashes
         let extrinsics = block
@@ -207,34 +240,25 @@ impl BlockQuery {
             .await
             .map_err(|e| Error::Transaction(format!("Failed to get extrinsics: {}", e)))?;
 
-        let mut transactions = Vec::new();
-        let extrinsic_count = extrinsics.len() as u32;
-
-        for ext_details in extrinsics.iter() {
-            let ext_bytes = ext_details.bytes();
-            let hash = sp_core::blake2_256(ext_bytes);
-            transactions.push(format!("0x{}", hex::encode(hash)));
-        }
+        let transactions = extrinsics
+            .iter()
+            .map(|ext_details| sp_core::blake2_256(ext_details.bytes()))
+            .collect();
 
         // Check finality
         let is_finalized = self.check_finality(block.hash()).await?;
 
-        // Get state root and extrinsics root from header
-        let state_root = Some(format!("0x{}", hex::encode(block.header().state_root)));
-        let extrinsics_root = Some(format!("0x{}", hex::encode(block.header().extrinsics_root)));
-
         // Count events (we'll do a quick count without full parsing for basic info)
-        let event_count = self.count_block_events(&block).await.ok();
+        let event_count = self.count_block_events(block).await.ok();
 
-        Ok(BlockInfo {
-            number,
-            hash,
-            parent_hash,
+        Ok(RawBlockInfo {
+            number: block.number() as u64,
+            hash: block.hash().0,
+            parent_hash: header.parent_hash.0,
             timestamp,
             transactions,
-            state_root,
-            extrinsics_root,
-            extrinsic_count,
+            state_root: Some(header.state_root.0),
+            extrinsics_root: Some(header.extrinsics_root.0),
             event_count,
             is_finalized,
         })
@@ -246,10 +270,7 @@ impl BlockQuery {
     /// 1. Query Timestamp pallet storage at block hash
     /// 2. Scan for Timestamp::set extrinsic
     /// 3. Use current time as last resort (with warning)
-    async fn extract_timestamp(
-        &self,
-        block: &subxt::blocks::Block<PolkadotConfig, OnlineClient<PolkadotConfig>>,
-    ) -> Result<u64, Error> {
+    async fn extract_timestamp(&self, block: &SubxtBlock) -> Result<u64, Error> {
         // For now, extract timestamp from block header's inherent data
         // Most Substrate chains include timestamp as an inherent extrinsic
         // We'll scan for the Timestamp::set call
@@ -320,10 +341,7 @@ impl BlockQuery {
     }
 
     /// Extract extrinsic information from a block
-    async fn extract_extrinsics(
-        &self,
-        block: &subxt::blocks::Block<PolkadotConfig, OnlineClient<PolkadotConfig>>,
-    ) -> Result<Vec<ExtrinsicInfo>, Error> {
+    async fn extract_extrinsics(&self, block: &SubxtBlock) -> Result<Vec<ExtrinsicInfo>, Error> {
         let extrinsics = block
             .extrinsics()
             .await
@@ -377,10 +395,7 @@ impl BlockQuery {
     }
 
     /// Extract all events from a block
-    async fn extract_block_events(
-        &self,
-        block: &subxt::blocks::Block<PolkadotConfig, OnlineClient<PolkadotConfig>>,
-    ) -> Result<Vec<BlockEvent>, Error> {
+    async fn extract_block_events(&self, block: &SubxtBlock) -> Result<Vec<BlockEvent>, Error> {
         let extrinsics = block
             .extrinsics()
             .await
@@ -409,10 +424,7 @@ impl BlockQuery {
     }
 
     /// Count events in a block (lightweight, no full parsing)
-    async fn count_block_events(
-        &self,
-        block: &subxt::blocks::Block<PolkadotConfig, OnlineClient<PolkadotConfig>>,
-    ) -> Result<u32, Error> {
+    async fn count_block_events(&self, block: &SubxtBlock) -> Result<u32, Error> {
         let extrinsics = block
             .extrinsics()
             .await
@@ -431,6 +443,13 @@ impl BlockQuery {
 
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn test_block_query_is_shareable() {
+        fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+        assert_shareable::<super::BlockQuery>();
+        assert_shareable::<crate::SubstrateAdapter>();
+    }
+
     #[test]
     fn test_block_hash_parsing() {
         // Test with 0x prefix
@@ -0,0 +1,296 @@
+//! Extrinsic size and weight pre-validation
+//!
+//! [`BlockLimits`] reads `System::BlockLength` and `System::BlockWeights` from
+//! the runtime constants and checks an extrinsic against the limits of its
+//! dispatch class before it is submitted. An extrinsic that could never fit
+//! in a block fails with [`Error::ExhaustsResources`] instead of being
+//! rejected by the node:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{BlockLimits, DispatchClass, SubstrateAdapter};
+//!
+//! # fn example(adapter: &SubstrateAdapter, extrinsic: &[u8]) -> Result<(), apex_sdk_substrate::Error> {
+//! let limits = BlockLimits::fetch(adapter.client(), DispatchClass::Normal)?;
+//! limits.check_length(extrinsic.len())?;
+//! println!("up to {} bytes per extrinsic", limits.max_length);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`TransactionExecutor`](crate::TransactionExecutor) runs these checks on
+//! every extrinsic it signs.
+
+use crate::event_query::value_to_json;
+use crate::{Error, Result};
+use parity_scale_codec::{Compact, Decode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::dynamic::Value;
+use subxt::{OnlineClient, PolkadotConfig};
+
+/// Dispatch class an extrinsic is accounted under
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DispatchClass {
+    /// Ordinary extrinsics
+    #[default]
+    Normal,
+    /// Operational extrinsics, which may use the reserved part of a block
+    Operational,
+    /// Inherents, always included
+    Mandatory,
+}
+
+impl DispatchClass {
+    /// Field name of the class in the runtime's per-class structures
+    pub fn name(self) -> &'static str {
+        match self {
+            DispatchClass::Normal => "normal",
+            DispatchClass::Operational => "operational",
+            DispatchClass::Mandatory => "mandatory",
+        }
+    }
+}
+
+/// Two-dimensional weight of an extrinsic
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Weight {
+    /// Computation time, in picoseconds
+    pub ref_time: u64,
+    /// Proof size, in bytes
+    pub proof_size: u64,
+}
+
+impl Weight {
+    pub(crate) fn from_json(weight: &JsonValue) -> Option<Self> {
+        let field = |name: &str| match &weight[name] {
+            JsonValue::Number(n) => n.as_u64(),
+            JsonValue::String(s) => s.parse().ok(),
+            _ => None,
+        };
+        Some(Self {
+            ref_time: field("ref_time")?,
+            proof_size: field("proof_size")?,
+        })
+    }
+
+    /// The weight as a call argument
+    pub(crate) fn to_value(self) -> Value {
+        Value::named_composite([
+            ("ref_time", Value::u128(self.ref_time.into())),
+            ("proof_size", Value::u128(self.proof_size.into())),
+        ])
+    }
+}
+
+/// Block resource an extrinsic can exhaust
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockResource {
+    /// Encoded length
+    Length,
+    /// Weight `ref_time`
+    RefTime,
+    /// Weight `proof_size`
+    ProofSize,
+}
+
+impl std::fmt::Display for BlockResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BlockResource::Length => "length",
+            BlockResource::RefTime => "ref_time",
+            BlockResource::ProofSize => "proof_size",
+        })
+    }
+}
+
+/// Largest extrinsic a block accepts for one dispatch class
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockLimits {
+    /// Dispatch class the limits apply to
+    pub class: DispatchClass,
+    /// Maximum encoded length, in bytes
+    pub max_length: u64,
+    /// Maximum weight of a single extrinsic
+    pub max_weight: Weight,
+}
+
+impl BlockLimits {
+    /// Read the limits of `class` from the runtime constants
+    pub fn fetch(client: &OnlineClient<PolkadotConfig>, class: DispatchClass) -> Result<Self> {
+        let constant = |name: &str| {
+            client
+                .constants()
+                .at(&subxt::dynamic::constant("System", name))
+                .map_err(|e| Error::Metadata(format!("Failed to get System::{}: {}", name, e)))?
+                .to_value()
+                .map(|value| value_to_json(&value))
+                .map_err(|e| Error::Metadata(format!("Failed to decode System::{}: {}", name, e)))
+        };
+        Self::from_constants(&constant("BlockLength")?, &constant("BlockWeights")?, class)
+    }
+
+    /// Limits of `class` from the JSON form of `BlockLength` and `BlockWeights`
+    ///
+    /// A class without an extrinsic weight limit is bounded by its total, and
+    /// failing that by the whole block.
+    pub fn from_constants(
+        length: &JsonValue,
+        weights: &JsonValue,
+        class: DispatchClass,
+    ) -> Result<Self> {
+        let max_length = length["max"][class.name()]
+            .as_u64()
+            .ok_or_else(|| Error::Metadata("Unexpected System::BlockLength layout".to_string()))?;
+        let per_class = &weights["per_class"][class.name()];
+        let max_weight = [&per_class["max_extrinsic"], &per_class["max_total"]]
+            .into_iter()
+            .find_map(|limit| Weight::from_json(&limit["Some"]))
+            .or_else(|| Weight::from_json(&weights["max_block"]))
+            .ok_or_else(|| Error::Metadata("Unexpected System::BlockWeights layout".to_string()))?;
+        Ok(Self {
+            class,
+            max_length,
+            max_weight,
+        })
+    }
+
+    /// Fail if an extrinsic of `length` bytes cannot fit in a block
+    pub fn check_length(&self, length: usize) -> Result<()> {
+        exceeds(BlockResource::Length, length as u64, self.max_length)
+    }
+
+    /// Fail if an extrinsic of `weight` cannot fit in a block
+    pub fn check_weight(&self, weight: Weight) -> Result<()> {
+        exceeds(
+            BlockResource::RefTime,
+            weight.ref_time,
+            self.max_weight.ref_time,
+        )?;
+        exceeds(
+            BlockResource::ProofSize,
+            weight.proof_size,
+            self.max_weight.proof_size,
+        )
+    }
+}
+
+fn exceeds(resource: BlockResource, required: u64, limit: u64) -> Result<()> {
+    if required > limit {
+        return Err(Error::ExhaustsResources {
+            resource,
+            required,
+            limit,
+        });
+    }
+    Ok(())
+}
+
+/// Weight and class from an encoded `RuntimeDispatchInfo`
+///
+/// Returns `None` for runtimes still using one-dimensional weights.
+pub fn decode_dispatch_info(info: &[u8]) -> Option<(Weight, DispatchClass)> {
+    let mut input = info;
+    let ref_time = Compact::<u64>::decode(&mut input).ok()?.0;
+    let proof_size = Compact::<u64>::decode(&mut input).ok()?.0;
+    let class = match u8::decode(&mut input).ok()? {
+        0 => DispatchClass::Normal,
+        1 => DispatchClass::Operational,
+        2 => DispatchClass::Mandatory,
+        _ => return None,
+    };
+    // only the partial fee should follow
+    if input.len() != 16 {
+        return None;
+    }
+    Some((
+        Weight {
+            ref_time,
+            proof_size,
+        },
+        class,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_scale_codec::Encode;
+
+    #[test]
+    fn test_limits_from_constants() {
+        let length = serde_json::json!({
+            "max": { "normal": 3_932_160, "operational": 5_242_880, "mandatory": 5_242_880 }
+        });
+        let weights = serde_json::json!({
+            "max_block": { "ref_time": 2_000_000_000_000u64, "proof_size": 18_446_744_073_709_551_615u64 },
+            "per_class": {
+                "normal": {
+                    "max_extrinsic": { "Some": { "ref_time": 1_479_000_000_000u64, "proof_size": 3_932_160 } },
+                    "max_total": { "Some": { "ref_time": 1_500_000_000_000u64, "proof_size": 3_932_160 } }
+                },
+                "mandatory": { "max_extrinsic": "None", "max_total": "None" }
+            }
+        });
+
+        let normal = BlockLimits::from_constants(&length, &weights, DispatchClass::Normal).unwrap();
+        assert_eq!(normal.max_length, 3_932_160);
+        assert_eq!(normal.max_weight.ref_time, 1_479_000_000_000);
+        assert!(normal.check_length(3_932_160).is_ok());
+        assert!(matches!(
+            normal.check_length(3_932_161),
+            Err(Error::ExhaustsResources {
+                resource: BlockResource::Length,
+                ..
+            })
+        ));
+        assert!(matches!(
+            normal.check_weight(Weight {
+                ref_time: 1,
+                proof_size: 4_000_000
+            }),
+            Err(Error::ExhaustsResources {
+                resource: BlockResource::ProofSize,
+                required: 4_000_000,
+                limit: 3_932_160
+            })
+        ));
+
+        let mandatory =
+            BlockLimits::from_constants(&length, &weights, DispatchClass::Mandatory).unwrap();
+        assert_eq!(mandatory.max_weight.ref_time, 2_000_000_000_000);
+        assert!(BlockLimits::from_constants(
+            &serde_json::json!({}),
+            &weights,
+            DispatchClass::Normal
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_decode_dispatch_info() {
+        let info = (
+            Compact(250_000_000u64),
+            Compact(3_593u64),
+            1u8,
+            15_000_000_000u128,
+        )
+            .encode();
+        assert_eq!(
+            decode_dispatch_info(&info),
+            Some((
+                Weight {
+                    ref_time: 250_000_000,
+                    proof_size: 3_593
+                },
+                DispatchClass::Operational
+            ))
+        );
+        // pre-weight-v2 layout: plain u64 weight
+        assert_eq!(
+            decode_dispatch_info(&(195_000_000u64, 0u8, 1u128).encode()),
+            None
+        );
+    }
+}
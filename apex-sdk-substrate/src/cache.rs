@@ -84,6 +84,21 @@ impl CacheConfig {
         Self::default()
     }
 
+    /// Defaults with block-dependent TTLs scaled to the chain's block time
+    ///
+    /// Recent blocks live for two block times. Storage and balances never
+    /// outlive five and two block times, so fast chains don't serve state
+    /// many blocks old.
+    pub fn for_block_time(block_time: Duration) -> Self {
+        let defaults = Self::default();
+        Self {
+            storage_ttl: defaults.storage_ttl.min(block_time * 5),
+            balance_ttl: defaults.balance_ttl.min(block_time * 2),
+            block_ttl_recent: block_time * 2,
+            ..defaults
+        }
+    }
+
     /// Set maximum entries
     pub fn with_max_entries(mut self, max_entries: usize) -> Self {
         self.max_entries = max_entries;
@@ -281,6 +296,11 @@ impl Cache {
     ///
     /// Finalized blocks are cached with a longer TTL since they are immutable.
     /// Recent blocks use a shorter TTL as they might be affected by chain reorganizations.
+    ///
+    /// A recent block that is a sibling of the cached block at its height, as
+    /// parachains with elastic scaling produce, or whose parent is not the
+    /// cached block below it, drops the non-finalized blocks of the other fork
+    /// from the by-number lookup. Lookups by hash are unaffected.
     pub fn put_block(&self, block_info: apex_sdk_core::BlockInfo) {
         let ttl = if block_info.is_finalized {
             self.config.block_ttl_finalized
@@ -295,10 +315,54 @@ impl Cache {
         let hash_key = format!("block:hash:{}", block_info.hash);
 
         let mut cache = self.block_cache.write();
+        if !block_info.is_finalized {
+            Self::drop_fork(&mut cache, &block_info);
+        }
         cache.put(num_key, entry.clone());
         cache.put(hash_key, entry);
     }
 
+    /// Remove non-finalized blocks by number that `block` shows to be on another fork
+    fn drop_fork(
+        cache: &mut LruCache<String, CacheEntry<apex_sdk_core::BlockInfo>>,
+        block: &apex_sdk_core::BlockInfo,
+    ) {
+        fn forked(
+            cache: &LruCache<String, CacheEntry<apex_sdk_core::BlockInfo>>,
+            key: &str,
+            hash: &str,
+        ) -> bool {
+            cache
+                .peek(key)
+                .is_some_and(|entry| !entry.value.is_finalized && entry.value.hash != hash)
+        }
+
+        if let Some(parent) = block.number.checked_sub(1) {
+            let parent_key = format!("block:num:{}", parent);
+            if forked(cache, &parent_key, &block.parent_hash) {
+                cache.pop(&parent_key);
+            }
+        }
+
+        let num_key = format!("block:num:{}", block.number);
+        if !forked(cache, &num_key, &block.hash) {
+            return;
+        }
+        // descendants of the replaced sibling are on the abandoned fork too
+        let stale: Vec<String> = cache
+            .iter()
+            .filter(|(key, entry)| {
+                !entry.value.is_finalized
+                    && entry.value.number >= block.number
+                    && key.starts_with("block:num:")
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in stale {
+            cache.pop(&key);
+        }
+    }
+
     /// Clear all caches
     pub fn clear(&self) {
         self.storage_cache.write().clear();
@@ -599,6 +663,53 @@ mod tests {
         assert_eq!(cache.get_storage("key1"), None);
     }
 
+    fn block(number: u64, hash: &str, parent_hash: &str) -> apex_sdk_core::BlockInfo {
+        apex_sdk_core::BlockInfo {
+            number,
+            hash: hash.to_string(),
+            parent_hash: parent_hash.to_string(),
+            timestamp: 0,
+            transactions: vec![],
+            state_root: None,
+            extrinsics_root: None,
+            extrinsic_count: 0,
+            event_count: None,
+            is_finalized: false,
+        }
+    }
+
+    #[test]
+    fn test_sibling_block_drops_fork() {
+        let cache = Cache::new();
+        cache.put_block(block(10, "0xa10", "0xa9"));
+        cache.put_block(block(11, "0xa11", "0xa10"));
+        cache.put_block(block(12, "0xa12", "0xa11"));
+
+        // a sibling of #11 replaces the fork above it
+        cache.put_block(block(11, "0xb11", "0xa10"));
+        assert_eq!(cache.get_block_by_number(11).unwrap().hash, "0xb11");
+        assert!(cache.get_block_by_number(12).is_none());
+        assert_eq!(cache.get_block_by_number(10).unwrap().hash, "0xa10");
+        assert!(cache.get_block_by_hash("0xa12").is_some());
+
+        // a child on another fork drops its mismatched parent
+        cache.put_block(block(12, "0xc12", "0xc11"));
+        assert!(cache.get_block_by_number(11).is_none());
+    }
+
+    #[test]
+    fn test_block_time_ttls() {
+        let fast = CacheConfig::for_block_time(Duration::from_millis(500));
+        assert_eq!(fast.block_ttl_recent, Duration::from_secs(1));
+        assert_eq!(fast.balance_ttl, Duration::from_secs(1));
+        assert_eq!(fast.storage_ttl, Duration::from_millis(2500));
+
+        let slow = CacheConfig::for_block_time(Duration::from_secs(12));
+        assert_eq!(slow.block_ttl_recent, Duration::from_secs(24));
+        assert_eq!(slow.balance_ttl, CacheConfig::default().balance_ttl);
+        assert_eq!(slow.storage_ttl, CacheConfig::default().storage_ttl);
+    }
+
     #[test]
     fn test_lru_eviction() {
         let config = CacheConfig::new().with_max_entries(2);
@@ -0,0 +1,297 @@
+//! Endpoint capability detection
+//!
+//! Nodes differ in what they expose: some prune legacy RPC methods, some
+//! disable `payment_*`, and older runtimes lack newer runtime APIs. Instead of
+//! failing at call time, [`Capabilities`] is probed once on connect and
+//! high-level APIs pick a strategy from it:
+//!
+//! | Feature | Preferred | Fallbacks |
+//! |---------|-----------|-----------|
+//! | Fee estimation | `TransactionPaymentApi` runtime call | `payment_queryInfo`, size-based estimate |
+//! | Block lookup | `archive_*_hashByHeight` | `chain_getBlockHash` |
+//! | Finalized heads | `chainHead_*_follow` | `chain_subscribeFinalizedHeads` |
+//! | Submission | `transaction_*_broadcast` | `author_submitExtrinsic` |
+//! | Storage reads | `archive_*_storage` | `state_getStorage` |
+//!
+//! [`Capabilities::matrix`] reports the choice made for each feature.
+
+use crate::rpc_spec::RpcCapabilities;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use subxt::backend::rpc::RpcClient;
+use subxt::ext::subxt_rpcs::client::RpcParams;
+use tracing::{debug, warn};
+
+/// Runtime APIs whose versions are tracked
+pub const KNOWN_RUNTIME_APIS: [&str; 9] = [
+    "Core",
+    "Metadata",
+    "BlockBuilder",
+    "TaggedTransactionQueue",
+    "AccountNonceApi",
+    "TransactionPaymentApi",
+    "TransactionPaymentCallApi",
+    "DryRunApi",
+    "XcmPaymentApi",
+];
+
+/// How transaction fees are estimated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FeeStrategy {
+    /// `TransactionPaymentApi_query_info` runtime call
+    #[default]
+    RuntimeApi,
+    /// `payment_queryInfo` RPC method
+    PaymentRpc,
+    /// Estimate from the extrinsic size; used when neither is available
+    SizeBased,
+}
+
+impl FeeStrategy {
+    /// Method used by this strategy
+    pub fn method(&self) -> &'static str {
+        match self {
+            FeeStrategy::RuntimeApi => "TransactionPaymentApi_query_info",
+            FeeStrategy::PaymentRpc => "payment_queryInfo",
+            FeeStrategy::SizeBased => "size-based estimate",
+        }
+    }
+}
+
+/// Strategy selected for one feature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyChoice {
+    /// Feature name
+    pub feature: &'static str,
+    /// Method or strategy used
+    pub method: &'static str,
+    /// Whether a fallback was chosen over the preferred method
+    pub degraded: bool,
+}
+
+impl fmt::Display for StrategyChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.feature, self.method)?;
+        if self.degraded {
+            write!(f, " (fallback)")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuntimeVersionResponse {
+    spec_version: u32,
+    apis: Vec<(String, u32)>,
+}
+
+/// RPC methods and runtime APIs supported by an endpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    rpc: RpcCapabilities,
+    runtime_apis: BTreeMap<String, u32>,
+    spec_version: Option<u32>,
+}
+
+impl Capabilities {
+    /// Capabilities from known RPC methods and runtime API versions
+    pub fn new(rpc: RpcCapabilities, runtime_apis: BTreeMap<String, u32>) -> Self {
+        Self {
+            rpc,
+            runtime_apis,
+            spec_version: None,
+        }
+    }
+
+    /// Probe `rpc_methods` and `state_getRuntimeVersion`
+    ///
+    /// A probe that fails leaves its capabilities empty; unknown capabilities
+    /// are assumed to match a legacy node.
+    pub async fn probe(rpc: &RpcClient) -> Self {
+        let mut capabilities = Self {
+            rpc: RpcCapabilities::detect(rpc).await,
+            ..Self::default()
+        };
+
+        match rpc
+            .request::<RuntimeVersionResponse>("state_getRuntimeVersion", RpcParams::new())
+            .await
+        {
+            Ok(version) => {
+                capabilities.spec_version = Some(version.spec_version);
+                capabilities.runtime_apis = runtime_api_versions(&version.apis);
+            }
+            Err(e) => warn!("Failed to read runtime APIs: {}", e),
+        }
+
+        for choice in capabilities.matrix() {
+            debug!("{}", choice);
+        }
+        capabilities
+    }
+
+    /// RPC methods advertised by the node
+    pub fn rpc(&self) -> &RpcCapabilities {
+        &self.rpc
+    }
+
+    /// Runtime spec version at probe time
+    pub fn spec_version(&self) -> Option<u32> {
+        self.spec_version
+    }
+
+    /// Version of a runtime API, if the runtime implements it
+    pub fn runtime_api_version(&self, name: &str) -> Option<u32> {
+        self.runtime_apis.get(name).copied()
+    }
+
+    /// Check whether the runtime implements an API
+    pub fn has_runtime_api(&self, name: &str) -> bool {
+        self.runtime_apis.contains_key(name)
+    }
+
+    /// Fee estimation strategy for this endpoint
+    pub fn fee_strategy(&self) -> FeeStrategy {
+        let runtime_api =
+            self.runtime_apis.is_empty() || self.has_runtime_api("TransactionPaymentApi");
+        if runtime_api && self.legacy_method("state_call") {
+            FeeStrategy::RuntimeApi
+        } else if self.legacy_method("payment_queryInfo") {
+            FeeStrategy::PaymentRpc
+        } else {
+            FeeStrategy::SizeBased
+        }
+    }
+
+    /// Legacy methods are assumed present when the node did not list its methods
+    fn legacy_method(&self, method: &str) -> bool {
+        self.rpc.methods().next().is_none() || self.rpc.supports(method)
+    }
+
+    /// Strategy chosen for every feature
+    pub fn matrix(&self) -> Vec<StrategyChoice> {
+        let fee = self.fee_strategy();
+        let choice = |feature, preferred: Option<&'static str>, fallback| StrategyChoice {
+            feature,
+            method: preferred.unwrap_or(fallback),
+            degraded: preferred.is_none(),
+        };
+
+        vec![
+            StrategyChoice {
+                feature: "fee estimation",
+                method: fee.method(),
+                degraded: fee != FeeStrategy::RuntimeApi,
+            },
+            choice(
+                "block lookup",
+                self.rpc.hash_by_height(),
+                "chain_getBlockHash",
+            ),
+            choice(
+                "finalized heads",
+                self.rpc.chain_head().map(|_| "chainHead_follow"),
+                "chain_subscribeFinalizedHeads",
+            ),
+            choice(
+                "submission",
+                self.rpc.transaction().map(|_| "transaction_broadcast"),
+                "author_submitExtrinsic",
+            ),
+            choice(
+                "storage reads",
+                self.rpc.archive().map(|_| "archive_storage"),
+                "state_getStorage",
+            ),
+        ]
+    }
+}
+
+/// Map the `apis` of a runtime version to the names in [`KNOWN_RUNTIME_APIS`]
+///
+/// Runtime versions list APIs by the blake2-64 hash of their name.
+fn runtime_api_versions(apis: &[(String, u32)]) -> BTreeMap<String, u32> {
+    KNOWN_RUNTIME_APIS
+        .iter()
+        .filter_map(|name| {
+            let id = format!("0x{}", hex::encode(sp_core::blake2_64(name.as_bytes())));
+            apis.iter()
+                .find(|(api, _)| api.eq_ignore_ascii_case(&id))
+                .map(|(_, version)| (name.to_string(), *version))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_api_versions() {
+        let apis = vec![
+            ("0xdf6acb689907609b".to_string(), 5),
+            ("0x0123456789abcdef".to_string(), 1),
+        ];
+        let versions = runtime_api_versions(&apis);
+
+        assert_eq!(versions.get("Core"), Some(&5));
+        assert_eq!(versions.len(), 1);
+    }
+
+    #[test]
+    fn test_fee_strategy_degrades() {
+        let apis = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| (name.to_string(), 1))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let full = Capabilities::new(
+            RpcCapabilities::new(["state_call", "payment_queryInfo"]),
+            apis(&["Core", "TransactionPaymentApi"]),
+        );
+        assert_eq!(full.fee_strategy(), FeeStrategy::RuntimeApi);
+
+        let rpc_only = Capabilities::new(
+            RpcCapabilities::new(["state_call", "payment_queryInfo"]),
+            apis(&["Core"]),
+        );
+        assert_eq!(rpc_only.fee_strategy(), FeeStrategy::PaymentRpc);
+
+        let neither = Capabilities::new(RpcCapabilities::new(["state_call"]), apis(&["Core"]));
+        assert_eq!(neither.fee_strategy(), FeeStrategy::SizeBased);
+
+        // nothing probed: behave like a legacy node
+        assert_eq!(
+            Capabilities::default().fee_strategy(),
+            FeeStrategy::RuntimeApi
+        );
+    }
+
+    #[test]
+    fn test_matrix() {
+        let capabilities = Capabilities::new(
+            RpcCapabilities::new(["chainHead_v1_follow", "archive_unstable_hashByHeight"]),
+            BTreeMap::new(),
+        );
+        let matrix = capabilities.matrix();
+
+        let method = |feature: &str| {
+            matrix
+                .iter()
+                .find(|choice| choice.feature == feature)
+                .map(|choice| (choice.method, choice.degraded))
+                .unwrap()
+        };
+        assert_eq!(method("finalized heads"), ("chainHead_follow", false));
+        assert_eq!(
+            method("block lookup"),
+            ("archive_unstable_hashByHeight", false)
+        );
+        assert_eq!(method("submission"), ("author_submitExtrinsic", true));
+        assert_eq!(method("fee estimation"), ("size-based estimate", true));
+    }
+}
@@ -0,0 +1,503 @@
+//! Tracing events back to the call that caused them
+//!
+//! [`CausalityTracer`] takes an event and finds where it came from: the
+//! extrinsic that emitted it and, when that extrinsic wraps other calls, the
+//! path of nested calls down to the one that actually emitted the event.
+//!
+//! Nested calls are reconstructed by walking the extrinsic's events in order
+//! against its decoded call tree. `Utility` batches mark the end of every item
+//! with `ItemCompleted`/`ItemFailed` and the end of the batch with
+//! `BatchCompleted*`/`BatchInterrupted`, which is enough to attribute each
+//! event to a batch item. These wrappers are understood:
+//!
+//! | Pallet | Calls |
+//! |--------|-------|
+//! | `Utility` | `batch`, `batch_all`, `force_batch`, `as_derivative`, `dispatch_as`, `with_weight` |
+//! | `Proxy` | `proxy`, `proxy_announced` |
+//! | `Multisig` | `as_multi`, `as_multi_threshold_1` |
+//! | `Sudo` | `sudo`, `sudo_as`, `sudo_unchecked_weight` |
+//!
+//! Events emitted outside extrinsics are attributed to a scheduled task when
+//! a `Scheduler::Dispatched` event follows them, otherwise to runtime hooks.
+//! Scheduled calls are not stored in the block, so their path stays empty.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{CausalityTracer, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let trace = CausalityTracer::new(adapter).trace(19_000_000, 42).await?;
+//! println!("{:?}", trace.origin);
+//! for frame in &trace.path {
+//!     println!("  {}.{} (item {:?})", frame.pallet, frame.call, frame.item);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::{composite_to_json, decode_events, MatchedEvent};
+use crate::{Error, Result, SubstrateAdapter};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Where an event originated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EventOrigin {
+    /// Emitted while applying the extrinsic at this index
+    Extrinsic(u32),
+    /// Emitted by a task dispatched by the `Scheduler`
+    Scheduled {
+        /// `(block, index)` of the task as decoded from `Scheduler::Dispatched`
+        task: JsonValue,
+        /// Named task id, if any
+        id: Option<String>,
+    },
+    /// Emitted by runtime hooks outside any extrinsic
+    Hook,
+}
+
+/// One call on the path from the extrinsic to the emitting call
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallFrame {
+    /// Pallet name
+    pub pallet: String,
+    /// Call name
+    pub call: String,
+    /// Position within the parent batch, if the parent is one
+    pub item: Option<u32>,
+}
+
+/// An event together with the calls that caused it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallTrace {
+    /// The traced event
+    pub event: MatchedEvent,
+    /// Extrinsic, scheduled task or hook that emitted the event
+    pub origin: EventOrigin,
+    /// Calls from the extrinsic's root call down to the emitting call
+    pub path: Vec<CallFrame>,
+}
+
+impl CallTrace {
+    /// Innermost call that emitted the event
+    pub fn emitter(&self) -> Option<&CallFrame> {
+        self.path.last()
+    }
+
+    /// Whether the event came from a call nested inside another
+    pub fn is_nested(&self) -> bool {
+        self.path.len() > 1
+    }
+}
+
+/// Finds the origin and call path of events
+pub struct CausalityTracer<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> CausalityTracer<'a> {
+    /// Tracer using the adapter's connection
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Trace a previously decoded event
+    pub async fn trace_event(&self, event: &MatchedEvent) -> Result<CallTrace> {
+        self.trace(event.block_number, event.event_index).await
+    }
+
+    /// Trace the event at `event_index` in `block_number`
+    pub async fn trace(&self, block_number: u64, event_index: u32) -> Result<CallTrace> {
+        let block = self
+            .adapter
+            .block_query()
+            .find_block_by_number(block_number)
+            .await?;
+        let hash = format!("0x{}", hex::encode(block.hash().0));
+        let events = block.events().await.map_err(|e| {
+            Error::Connection(format!(
+                "Failed to get events of block {}: {}",
+                block_number, e
+            ))
+        })?;
+        let events = decode_events(&events, block_number, &hash)?;
+        let event = events
+            .iter()
+            .find(|event| event.event_index == event_index)
+            .cloned()
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "Block {} has no event {}",
+                    block_number, event_index
+                ))
+            })?;
+
+        let Some(extrinsic_index) = event.extrinsic_index else {
+            return Ok(CallTrace {
+                origin: hook_origin(&events, event_index),
+                event,
+                path: Vec::new(),
+            });
+        };
+
+        let extrinsics = block
+            .extrinsics()
+            .await
+            .map_err(|e| Error::Transaction(format!("Failed to get extrinsics: {}", e)))?;
+        let extrinsic = extrinsics
+            .iter()
+            .find(|extrinsic| extrinsic.index() == extrinsic_index)
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "Block {} has no extrinsic {}",
+                    block_number, extrinsic_index
+                ))
+            })?;
+        let root = CallNode {
+            pallet: extrinsic
+                .pallet_name()
+                .map_err(|e| Error::Encoding(format!("Failed to decode extrinsic: {}", e)))?
+                .to_string(),
+            call: extrinsic
+                .variant_name()
+                .map_err(|e| Error::Encoding(format!("Failed to decode extrinsic: {}", e)))?
+                .to_string(),
+            fields: extrinsic
+                .field_values()
+                .map(|fields| composite_to_json(&fields))
+                .unwrap_or(JsonValue::Null),
+        };
+
+        let extrinsic_events: Vec<MatchedEvent> = events
+            .into_iter()
+            .filter(|event| event.extrinsic_index == Some(extrinsic_index))
+            .collect();
+        Ok(CallTrace {
+            path: call_path(&root, &extrinsic_events, event_index),
+            origin: EventOrigin::Extrinsic(extrinsic_index),
+            event,
+        })
+    }
+}
+
+/// A decoded call with its fields as JSON
+#[derive(Debug, Clone)]
+struct CallNode {
+    pallet: String,
+    call: String,
+    fields: JsonValue,
+}
+
+/// How a call dispatches other calls
+enum Dispatch {
+    Batch(Vec<CallNode>),
+    Wrapper(CallNode),
+    Leaf,
+}
+
+impl CallNode {
+    /// Parse a decoded `RuntimeCall`, e.g. `{"Balances": {"transfer_keep_alive": {..}}}`
+    fn from_runtime_call(value: &JsonValue) -> Option<Self> {
+        let (pallet, inner) = value.as_object()?.iter().next()?;
+        let (call, fields) = match inner {
+            JsonValue::String(call) => (call.clone(), JsonValue::Null),
+            JsonValue::Object(call) => {
+                let (name, fields) = call.iter().next()?;
+                (name.clone(), fields.clone())
+            }
+            _ => return None,
+        };
+        Some(Self {
+            pallet: pallet.clone(),
+            call,
+            fields,
+        })
+    }
+
+    fn frame(&self, item: Option<u32>) -> CallFrame {
+        CallFrame {
+            pallet: self.pallet.clone(),
+            call: self.call.clone(),
+            item,
+        }
+    }
+
+    fn dispatch(&self) -> Dispatch {
+        let nested = |field: &str| CallNode::from_runtime_call(&self.fields[field]);
+        match (self.pallet.as_str(), self.call.as_str()) {
+            ("Utility", "batch" | "batch_all" | "force_batch") => {
+                // a one-element vector decodes to the element itself
+                let calls = match &self.fields["calls"] {
+                    JsonValue::Array(calls) => calls.iter().collect(),
+                    call @ JsonValue::Object(_) => vec![call],
+                    _ => Vec::new(),
+                };
+                Dispatch::Batch(
+                    calls
+                        .into_iter()
+                        .filter_map(Self::from_runtime_call)
+                        .collect(),
+                )
+            }
+            ("Utility", "as_derivative" | "dispatch_as" | "with_weight")
+            | ("Proxy", "proxy" | "proxy_announced")
+            | ("Multisig", "as_multi" | "as_multi_threshold_1")
+            | ("Sudo", "sudo" | "sudo_as" | "sudo_unchecked_weight") => {
+                nested("call").map_or(Dispatch::Leaf, Dispatch::Wrapper)
+            }
+            _ => Dispatch::Leaf,
+        }
+    }
+}
+
+fn is_utility(event: &MatchedEvent, variants: &[&str]) -> bool {
+    event.pallet == "Utility" && variants.contains(&event.variant.as_str())
+}
+
+/// Ends a batch item
+fn is_item_end(event: &MatchedEvent) -> bool {
+    is_utility(event, &["ItemCompleted", "ItemFailed"])
+}
+
+/// Ends a whole batch
+fn is_batch_end(event: &MatchedEvent) -> bool {
+    is_utility(
+        event,
+        &[
+            "BatchCompleted",
+            "BatchCompletedWithErrors",
+            "BatchInterrupted",
+        ],
+    )
+}
+
+/// Walks an extrinsic's events in emission order against its call tree
+struct Walker<'e> {
+    events: &'e [MatchedEvent],
+    position: usize,
+    target: u32,
+}
+
+impl Walker<'_> {
+    fn peek(&self) -> Option<&MatchedEvent> {
+        self.events.get(self.position)
+    }
+
+    /// Consume the next event; true if it is the target
+    fn advance(&mut self) -> bool {
+        let found = self.peek().is_some_and(|e| e.event_index == self.target);
+        self.position += 1;
+        found
+    }
+
+    /// Consume the events of `node`, returning the path below it if the target is among them
+    ///
+    /// Inside a batch, a call's events end where the batch marks the item done.
+    fn walk(
+        &mut self,
+        node: &CallNode,
+        item: Option<u32>,
+        in_batch: bool,
+    ) -> Option<Vec<CallFrame>> {
+        let frame = node.frame(item);
+        match node.dispatch() {
+            Dispatch::Batch(calls) => {
+                for (index, call) in calls.iter().enumerate() {
+                    if let Some(mut path) = self.walk(call, Some(index as u32), true) {
+                        path.insert(0, frame);
+                        return Some(path);
+                    }
+                    match self.peek() {
+                        Some(event) if is_item_end(event) => {
+                            if self.advance() {
+                                return Some(vec![frame]);
+                            }
+                        }
+                        _ => break,
+                    }
+                }
+                if self.peek().is_some_and(is_batch_end) && self.advance() {
+                    return Some(vec![frame]);
+                }
+                None
+            }
+            Dispatch::Wrapper(call) => {
+                if let Some(mut path) = self.walk(&call, None, in_batch) {
+                    path.insert(0, frame);
+                    return Some(path);
+                }
+                self.consume_own(frame, in_batch)
+            }
+            Dispatch::Leaf => self.consume_own(frame, in_batch),
+        }
+    }
+
+    /// Consume events emitted by the call itself
+    fn consume_own(&mut self, frame: CallFrame, in_batch: bool) -> Option<Vec<CallFrame>> {
+        while let Some(event) = self.peek() {
+            if in_batch && (is_item_end(event) || is_batch_end(event)) {
+                break;
+            }
+            if self.advance() {
+                return Some(vec![frame]);
+            }
+        }
+        None
+    }
+}
+
+/// Calls from `root` down to the one that emitted event `target`
+///
+/// Fee withdrawal before dispatch and fee handling after it are attributed to
+/// the root call.
+fn call_path(root: &CallNode, events: &[MatchedEvent], target: u32) -> Vec<CallFrame> {
+    let skip = events
+        .first()
+        .filter(|event| event.pallet == "Balances" && event.variant == "Withdraw")
+        .map_or(0, |_| 1);
+    let is_fee_event = events[..skip]
+        .iter()
+        .any(|event| event.event_index == target)
+        || events.iter().any(|event| {
+            event.event_index == target
+                && matches!(event.pallet.as_str(), "System" | "TransactionPayment")
+        });
+    if is_fee_event {
+        return vec![root.frame(None)];
+    }
+
+    let mut walker = Walker {
+        events: &events[skip..],
+        position: 0,
+        target,
+    };
+    walker
+        .walk(root, None, false)
+        .unwrap_or_else(|| vec![root.frame(None)])
+}
+
+/// Origin of an event emitted outside extrinsics
+///
+/// A scheduled task's events precede its `Scheduler::Dispatched` event.
+fn hook_origin(events: &[MatchedEvent], event_index: u32) -> EventOrigin {
+    events
+        .iter()
+        .skip_while(|event| event.event_index < event_index)
+        .take_while(|event| event.extrinsic_index.is_none())
+        .find(|event| event.pallet == "Scheduler" && event.variant == "Dispatched")
+        .map(|dispatched| EventOrigin::Scheduled {
+            task: dispatched.fields["task"].clone(),
+            id: dispatched.fields["id"].as_str().map(str::to_string),
+        })
+        .unwrap_or(EventOrigin::Hook)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(index: u32, pallet: &str, variant: &str) -> MatchedEvent {
+        MatchedEvent {
+            block_number: 1,
+            block_hash: "0x00".to_string(),
+            event_index: index,
+            extrinsic_index: Some(2),
+            pallet: pallet.to_string(),
+            variant: variant.to_string(),
+            fields: JsonValue::Null,
+        }
+    }
+
+    fn path(trace: &[CallFrame]) -> Vec<String> {
+        trace
+            .iter()
+            .map(|frame| match frame.item {
+                Some(item) => format!("{}.{}[{}]", frame.pallet, frame.call, item),
+                None => format!("{}.{}", frame.pallet, frame.call),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_call_path() {
+        // proxy(batch_all([transfer, batch([remark, transfer])]))
+        let root = CallNode {
+            pallet: "Proxy".to_string(),
+            call: "proxy".to_string(),
+            fields: json!({
+                "real": "0x01",
+                "force_proxy_type": null,
+                "call": {"Utility": {"batch_all": {"calls": [
+                    {"Balances": {"transfer_keep_alive": {"dest": "0x02", "value": 1}}},
+                    {"Utility": {"batch": {"calls": [
+                        {"System": {"remark": {"remark": "0x00"}}},
+                        {"Balances": {"transfer_keep_alive": {"dest": "0x03", "value": 2}}},
+                    ]}}},
+                ]}}},
+            }),
+        };
+        let events = vec![
+            event(10, "Balances", "Withdraw"),
+            event(11, "Balances", "Transfer"),
+            event(12, "Utility", "ItemCompleted"),
+            event(13, "Utility", "ItemCompleted"),
+            event(14, "Balances", "Transfer"),
+            event(15, "Utility", "ItemCompleted"),
+            event(16, "Utility", "BatchCompleted"),
+            event(17, "Utility", "ItemCompleted"),
+            event(18, "Utility", "BatchCompleted"),
+            event(19, "Proxy", "ProxyExecuted"),
+            event(20, "TransactionPayment", "TransactionFeePaid"),
+            event(21, "System", "ExtrinsicSuccess"),
+        ];
+
+        let trace = |target| path(&call_path(&root, &events, target));
+        assert_eq!(
+            trace(11),
+            vec![
+                "Proxy.proxy",
+                "Utility.batch_all",
+                "Balances.transfer_keep_alive[0]"
+            ]
+        );
+        assert_eq!(
+            trace(14),
+            vec![
+                "Proxy.proxy",
+                "Utility.batch_all",
+                "Utility.batch[1]",
+                "Balances.transfer_keep_alive[1]"
+            ]
+        );
+        assert_eq!(
+            trace(16),
+            vec!["Proxy.proxy", "Utility.batch_all", "Utility.batch[1]"]
+        );
+        assert_eq!(trace(19), vec!["Proxy.proxy"]);
+        assert_eq!(trace(10), vec!["Proxy.proxy"]);
+        assert_eq!(trace(21), vec!["Proxy.proxy"]);
+    }
+
+    #[test]
+    fn test_hook_origin() {
+        let mut events = vec![
+            event(0, "Balances", "Transfer"),
+            event(1, "Scheduler", "Dispatched"),
+            event(2, "Staking", "EraPaid"),
+            event(3, "System", "ExtrinsicSuccess"),
+        ];
+        events[1].fields = json!({"task": [100, 0], "id": null, "result": {"Ok": null}});
+        for event in &mut events[..3] {
+            event.extrinsic_index = None;
+        }
+
+        assert_eq!(
+            hook_origin(&events, 0),
+            EventOrigin::Scheduled {
+                task: json!([100, 0]),
+                id: None
+            }
+        );
+        assert_eq!(hook_origin(&events, 2), EventOrigin::Hook);
+    }
+}
@@ -0,0 +1,581 @@
+//! Finality and block production health alerts
+//!
+//! [`ChainMonitor`] polls the best and finalized heads and raises a
+//! [`ChainAlert`] when:
+//! - the finalized head has not advanced for a configured time
+//! - the best block is too far ahead of the finalized head
+//! - blocks arrive much slower than the chain's target block time
+//!
+//! Each condition is reported once when it starts and once more with
+//! [`ChainAlert::Recovered`] when it clears. Polling rather than following a
+//! subscription means a node that silently stops sending heads is still
+//! noticed. Thresholds default to values scaled to the chain's block time
+//! (see [`HealthThresholds::for_block_time`]), so sub-second chains and slow
+//! parachains get sensible alerts without tuning.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{ChainAlert, ChainMonitor, SubstrateAdapter};
+//! use std::time::Duration;
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! ChainMonitor::new(adapter)
+//!     .with_finality_stall(Duration::from_secs(120))
+//!     .with_max_finality_gap(50)
+//!     .on_alert(|alert: &ChainAlert| eprintln!("{:?}", alert))
+//!     .run()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::chain_time::DEFAULT_BLOCK_TIME_MS;
+use crate::rpc_spec::SpecClient;
+use crate::{Result, SubstrateAdapter};
+use apex_sdk_core::stream::{bounded_stream, StreamConfig, StreamReceiver, StreamSendError};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Default time between head polls
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Default time without a new finalized block before alerting
+pub const DEFAULT_FINALITY_STALL: Duration = Duration::from_secs(60);
+
+/// Default largest tolerated gap between the best and finalized heads
+pub const DEFAULT_MAX_FINALITY_GAP: u64 = 30;
+
+/// Default window over which the block production rate is measured
+pub const DEFAULT_RATE_WINDOW: Duration = Duration::from_secs(120);
+
+/// Default multiple of the target block time that counts as slow
+pub const DEFAULT_SLOW_BLOCK_FACTOR: f64 = 2.0;
+
+/// Kind of chain health problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainCondition {
+    /// The finalized head stopped advancing
+    FinalityStall,
+    /// The best head is too far ahead of the finalized head
+    FinalityLag,
+    /// Blocks are produced slower than expected
+    SlowBlocks,
+}
+
+/// A change in chain health
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChainAlert {
+    /// The finalized head has not advanced for `stalled_ms`
+    FinalityStalled {
+        /// Last finalized block
+        finalized: u64,
+        /// Time since it was finalized
+        stalled_ms: u64,
+    },
+    /// The best head is `gap` blocks ahead of the finalized head
+    FinalityLag {
+        /// Best block
+        best: u64,
+        /// Finalized block
+        finalized: u64,
+        /// Blocks awaiting finality
+        gap: u64,
+    },
+    /// Blocks arrived every `average_block_ms` over the rate window
+    SlowBlockProduction {
+        /// Best block
+        best: u64,
+        /// Measured time per block
+        average_block_ms: u64,
+        /// Target block time of the chain
+        expected_block_ms: u64,
+    },
+    /// A previously reported condition cleared
+    Recovered {
+        /// Condition that cleared
+        condition: ChainCondition,
+        /// Best block
+        best: u64,
+        /// Finalized block
+        finalized: u64,
+    },
+}
+
+impl ChainAlert {
+    /// Condition the alert starts or ends
+    pub fn condition(&self) -> ChainCondition {
+        match self {
+            ChainAlert::FinalityStalled { .. } => ChainCondition::FinalityStall,
+            ChainAlert::FinalityLag { .. } => ChainCondition::FinalityLag,
+            ChainAlert::SlowBlockProduction { .. } => ChainCondition::SlowBlocks,
+            ChainAlert::Recovered { condition, .. } => *condition,
+        }
+    }
+
+    /// Whether the alert reports a condition clearing
+    pub fn is_recovery(&self) -> bool {
+        matches!(self, ChainAlert::Recovered { .. })
+    }
+}
+
+/// Limits beyond which [`HealthTracker`] raises alerts
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthThresholds {
+    /// Time without a new finalized block
+    pub finality_stall: Duration,
+    /// Blocks between the best and finalized heads
+    pub max_finality_gap: u64,
+    /// Window over which the block rate is measured
+    pub rate_window: Duration,
+    /// Target block time of the chain
+    pub expected_block_time: Duration,
+    /// Multiple of the target block time that counts as slow
+    pub slow_block_factor: f64,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            finality_stall: DEFAULT_FINALITY_STALL,
+            max_finality_gap: DEFAULT_MAX_FINALITY_GAP,
+            rate_window: DEFAULT_RATE_WINDOW,
+            expected_block_time: Duration::from_millis(DEFAULT_BLOCK_TIME_MS),
+            slow_block_factor: DEFAULT_SLOW_BLOCK_FACTOR,
+        }
+    }
+}
+
+impl HealthThresholds {
+    /// Defaults scaled from a 6 second chain to `block_time`
+    ///
+    /// The finality gap covers the same time as the default, since finality
+    /// (GRANDPA, or the relay chain for parachains) does not speed up with
+    /// block production. The stall and rate window never drop below their
+    /// defaults, but span at least 10 and 20 blocks on slow chains.
+    pub fn for_block_time(block_time: Duration) -> Self {
+        let block_ms = (block_time.as_millis() as u64).max(1);
+        Self {
+            finality_stall: DEFAULT_FINALITY_STALL.max(block_time * 10),
+            max_finality_gap: (DEFAULT_MAX_FINALITY_GAP * DEFAULT_BLOCK_TIME_MS / block_ms).max(1),
+            rate_window: DEFAULT_RATE_WINDOW.max(block_time * 20),
+            expected_block_time: block_time,
+            slow_block_factor: DEFAULT_SLOW_BLOCK_FACTOR,
+        }
+    }
+}
+
+/// Turns head observations into alerts
+///
+/// Feed it the best and finalized block numbers with the time they were
+/// observed; it returns the alerts for conditions that started or cleared.
+#[derive(Debug, Clone)]
+pub struct HealthTracker {
+    thresholds: HealthThresholds,
+    started_ms: Option<u64>,
+    finalized_since: Option<(u64, u64)>,
+    samples: VecDeque<(u64, u64)>,
+    active: BTreeSet<ChainCondition>,
+}
+
+impl HealthTracker {
+    /// Tracker with the given thresholds
+    pub fn new(thresholds: HealthThresholds) -> Self {
+        Self {
+            thresholds,
+            started_ms: None,
+            finalized_since: None,
+            samples: VecDeque::new(),
+            active: BTreeSet::new(),
+        }
+    }
+
+    /// Thresholds in use
+    pub fn thresholds(&self) -> &HealthThresholds {
+        &self.thresholds
+    }
+
+    /// Conditions currently reported
+    pub fn active(&self) -> impl Iterator<Item = ChainCondition> + '_ {
+        self.active.iter().copied()
+    }
+
+    /// Record the heads seen at `now_ms` and return new alerts
+    pub fn observe(&mut self, now_ms: u64, best: u64, finalized: u64) -> Vec<ChainAlert> {
+        let started_ms = *self.started_ms.get_or_insert(now_ms);
+        let mut alerts = Vec::new();
+
+        let (last_finalized, since_ms) = match self.finalized_since {
+            Some((last, since)) if finalized <= last => (last, since),
+            _ => (finalized, now_ms),
+        };
+        self.finalized_since = Some((last_finalized, since_ms));
+        let stalled_ms = now_ms.saturating_sub(since_ms);
+        self.update(
+            ChainCondition::FinalityStall,
+            stalled_ms >= self.thresholds.finality_stall.as_millis() as u64,
+            || ChainAlert::FinalityStalled {
+                finalized: last_finalized,
+                stalled_ms,
+            },
+            (best, finalized),
+            &mut alerts,
+        );
+
+        let gap = best.saturating_sub(finalized);
+        self.update(
+            ChainCondition::FinalityLag,
+            gap > self.thresholds.max_finality_gap,
+            || ChainAlert::FinalityLag {
+                best,
+                finalized,
+                gap,
+            },
+            (best, finalized),
+            &mut alerts,
+        );
+
+        let window_ms = self.thresholds.rate_window.as_millis() as u64;
+        self.samples.push_back((now_ms, best));
+        while self.samples.len() > 1 && self.samples[1].0 + window_ms <= now_ms {
+            self.samples.pop_front();
+        }
+        if now_ms.saturating_sub(started_ms) >= window_ms {
+            let (oldest_ms, oldest_best) = self.samples[0];
+            let elapsed = now_ms.saturating_sub(oldest_ms);
+            let produced = best.saturating_sub(oldest_best);
+            // without a single new block the whole window counts as one block time
+            let average_block_ms = elapsed.checked_div(produced).unwrap_or(elapsed);
+            let expected_block_ms = self.thresholds.expected_block_time.as_millis() as u64;
+            let slow = average_block_ms as f64
+                > expected_block_ms as f64 * self.thresholds.slow_block_factor;
+            self.update(
+                ChainCondition::SlowBlocks,
+                slow,
+                || ChainAlert::SlowBlockProduction {
+                    best,
+                    average_block_ms,
+                    expected_block_ms,
+                },
+                (best, finalized),
+                &mut alerts,
+            );
+        }
+
+        alerts
+    }
+
+    /// Emit an alert when `condition` starts or clears
+    fn update(
+        &mut self,
+        condition: ChainCondition,
+        holds: bool,
+        alert: impl FnOnce() -> ChainAlert,
+        (best, finalized): (u64, u64),
+        alerts: &mut Vec<ChainAlert>,
+    ) {
+        if holds && self.active.insert(condition) {
+            alerts.push(alert());
+        } else if !holds && self.active.remove(&condition) {
+            alerts.push(ChainAlert::Recovered {
+                condition,
+                best,
+                finalized,
+            });
+        }
+    }
+}
+
+/// Receiver of chain health alerts
+#[async_trait]
+pub trait ChainAlertHandler: Send + Sync {
+    /// Called once for every alert, in the order they were raised
+    async fn on_alert(&self, alert: &ChainAlert);
+}
+
+#[async_trait]
+impl<F> ChainAlertHandler for F
+where
+    F: Fn(&ChainAlert) + Send + Sync,
+{
+    async fn on_alert(&self, alert: &ChainAlert) {
+        self(alert)
+    }
+}
+
+/// Polls a chain's heads and reports finality and block production problems
+#[derive(Clone)]
+pub struct ChainMonitor {
+    spec: SpecClient,
+    thresholds: HealthThresholds,
+    poll_interval: Duration,
+    stream: StreamConfig,
+    handlers: Vec<Arc<dyn ChainAlertHandler>>,
+}
+
+impl ChainMonitor {
+    /// Monitor the adapter's chain, with thresholds scaled to its block time
+    ///
+    /// Heads are polled at least twice per block.
+    pub fn new(adapter: &SubstrateAdapter) -> Self {
+        let block_time = adapter.block_time();
+        Self {
+            spec: adapter.spec_client(),
+            thresholds: HealthThresholds::for_block_time(block_time),
+            poll_interval: DEFAULT_POLL_INTERVAL.min(block_time / 2),
+            stream: adapter.config().stream.clone(),
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Time between head polls
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Alert when no block is finalized for this long
+    pub fn with_finality_stall(mut self, stall: Duration) -> Self {
+        self.thresholds.finality_stall = stall;
+        self
+    }
+
+    /// Alert when more than `gap` blocks await finality
+    pub fn with_max_finality_gap(mut self, gap: u64) -> Self {
+        self.thresholds.max_finality_gap = gap;
+        self
+    }
+
+    /// Alert when blocks take `factor` times the target block time on average
+    /// over `window`
+    pub fn with_slow_blocks(mut self, factor: f64, window: Duration) -> Self {
+        self.thresholds.slow_block_factor = factor;
+        self.thresholds.rate_window = window;
+        self
+    }
+
+    /// Override the target block time read from the runtime
+    pub fn with_expected_block_time(mut self, block_time: Duration) -> Self {
+        self.thresholds.expected_block_time = block_time;
+        self
+    }
+
+    /// Buffer the alerts of [`stream`](Self::stream) as `config` says instead
+    /// of the adapter's [`ChainConfig::stream`](crate::ChainConfig::stream)
+    pub fn with_stream_config(mut self, config: StreamConfig) -> Self {
+        self.stream = config;
+        self
+    }
+
+    /// Register an alert handler
+    pub fn on_alert(mut self, handler: impl ChainAlertHandler + 'static) -> Self {
+        self.handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Thresholds alerts are raised at
+    pub fn thresholds(&self) -> &HealthThresholds {
+        &self.thresholds
+    }
+
+    /// Poll until the heads cannot be read, passing alerts to the handlers
+    pub async fn run(&self) -> Result<()> {
+        info!("Monitoring chain health every {:?}", self.poll_interval);
+        self.poll(|alert| async move {
+            for handler in &self.handlers {
+                handler.on_alert(&alert).await;
+            }
+            true
+        })
+        .await
+    }
+
+    /// Poll in a background task, delivering alerts through a stream
+    ///
+    /// Registered handlers are not called. The stream ends after yielding the
+    /// error that stopped polling, and polling stops when the stream is dropped.
+    /// Alerts are buffered as the monitor's stream configuration says; with
+    /// the default policy, polling waits while the buffer is full.
+    pub fn stream(&self) -> ChainAlertStream {
+        let (sender, receiver) = bounded_stream(self.stream.clone());
+        let monitor = self.clone();
+        let task = tokio::spawn(async move {
+            let result = monitor
+                .poll(|alert| {
+                    let sender = sender.clone();
+                    async move {
+                        match sender.send(Ok(alert)).await {
+                            Ok(()) => true,
+                            Err(StreamSendError::Full(_)) => {
+                                warn!("Chain alert buffer full, dropped an alert");
+                                true
+                            }
+                            Err(StreamSendError::Closed(_)) => false,
+                        }
+                    }
+                })
+                .await;
+            if let Err(e) = result {
+                let _ = sender.send(Err(e)).await;
+            }
+        });
+        ChainAlertStream { receiver, task }
+    }
+
+    /// Poll the heads, passing alerts to `deliver` until it returns false
+    async fn poll<F, Fut>(&self, mut deliver: F) -> Result<()>
+    where
+        F: FnMut(ChainAlert) -> Fut,
+        Fut: std::future::Future<Output = bool>,
+    {
+        let mut tracker = HealthTracker::new(self.thresholds.clone());
+        let started = Instant::now();
+        let mut interval = tokio::time::interval(self.poll_interval);
+
+        loop {
+            interval.tick().await;
+            let best = self.spec.best_number().await?;
+            let finalized = self.spec.finalized_number().await?;
+            let now_ms = started.elapsed().as_millis() as u64;
+            debug!("Chain heads: best {} finalized {}", best, finalized);
+
+            for alert in tracker.observe(now_ms, best, finalized) {
+                if !deliver(alert).await {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ChainMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChainMonitor")
+            .field("thresholds", &self.thresholds)
+            .field("poll_interval", &self.poll_interval)
+            .field("handlers", &self.handlers.len())
+            .finish()
+    }
+}
+
+/// Alerts from a [`ChainMonitor`] polling in the background
+pub struct ChainAlertStream {
+    receiver: StreamReceiver<Result<ChainAlert>>,
+    task: JoinHandle<()>,
+}
+
+impl ChainAlertStream {
+    /// Next alert, or `None` once polling has stopped
+    pub async fn next(&mut self) -> Option<Result<ChainAlert>> {
+        self.receiver.recv().await
+    }
+
+    /// Number of alerts evicted under
+    /// [`OverflowPolicy::DropOldest`](apex_sdk_core::stream::OverflowPolicy::DropOldest)
+    pub fn dropped_count(&self) -> u64 {
+        self.receiver.dropped_count()
+    }
+}
+
+impl Drop for ChainAlertStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> HealthTracker {
+        HealthTracker::new(HealthThresholds {
+            finality_stall: Duration::from_secs(30),
+            max_finality_gap: 5,
+            rate_window: Duration::from_secs(60),
+            expected_block_time: Duration::from_secs(6),
+            slow_block_factor: 2.0,
+        })
+    }
+
+    #[test]
+    fn test_finality_stall_and_lag_alert_once_then_recover() {
+        let mut tracker = tracker();
+        assert!(tracker.observe(0, 100, 98).is_empty());
+        assert!(tracker.observe(24_000, 103, 98).is_empty());
+
+        let alerts = tracker.observe(30_000, 103, 98);
+        assert_eq!(
+            alerts,
+            vec![ChainAlert::FinalityStalled {
+                finalized: 98,
+                stalled_ms: 30_000
+            }]
+        );
+
+        let alerts = tracker.observe(36_000, 106, 98);
+        assert_eq!(
+            alerts,
+            vec![ChainAlert::FinalityLag {
+                best: 106,
+                finalized: 98,
+                gap: 8
+            }]
+        );
+
+        let alerts = tracker.observe(42_000, 107, 105);
+        assert_eq!(alerts.len(), 2);
+        assert!(alerts.iter().all(ChainAlert::is_recovery));
+        assert_eq!(tracker.active().count(), 0);
+    }
+
+    #[test]
+    fn test_slow_block_production() {
+        let mut tracker = tracker();
+        let mut alerts = Vec::new();
+        // one block every 18 seconds, three times the target
+        for step in 0..=10u64 {
+            alerts.extend(tracker.observe(step * 6_000, 100 + step / 3, 100 + step / 3));
+        }
+        assert_eq!(
+            alerts,
+            vec![ChainAlert::SlowBlockProduction {
+                best: 103,
+                average_block_ms: 20_000,
+                expected_block_ms: 6_000
+            }]
+        );
+
+        // back to normal speed: recovers once the slow samples leave the window
+        for step in 11..=30u64 {
+            alerts.extend(tracker.observe(step * 6_000, 93 + step, 93 + step));
+        }
+        assert_eq!(
+            alerts.last().map(ChainAlert::condition),
+            Some(ChainCondition::SlowBlocks)
+        );
+        assert!(alerts.last().is_some_and(ChainAlert::is_recovery));
+    }
+
+    #[test]
+    fn test_thresholds_scale_with_block_time() {
+        assert_eq!(
+            HealthThresholds::for_block_time(Duration::from_secs(6)),
+            HealthThresholds::default()
+        );
+
+        let fast = HealthThresholds::for_block_time(Duration::from_millis(500));
+        assert_eq!(fast.max_finality_gap, 360);
+        assert_eq!(fast.finality_stall, DEFAULT_FINALITY_STALL);
+        assert_eq!(fast.rate_window, DEFAULT_RATE_WINDOW);
+
+        let slow = HealthThresholds::for_block_time(Duration::from_secs(12));
+        assert_eq!(slow.max_finality_gap, 15);
+        assert_eq!(slow.finality_stall, Duration::from_secs(120));
+        assert_eq!(slow.rate_window, Duration::from_secs(240));
+    }
+}
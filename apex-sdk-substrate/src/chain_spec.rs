@@ -0,0 +1,585 @@
+//! Chain-spec parsing and genesis inspection
+//!
+//! [`ChainSpec`] loads the JSON chain specs nodes are started with, in plain
+//! form (the runtime's genesis config) or raw form (genesis storage), and
+//! pulls out what operators usually check before a launch: endowed
+//! accounts, initial authorities and the parachain id. For raw specs it
+//! recomputes the genesis hash, so a spec can be checked against a running
+//! node:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{ChainSpec, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let spec = ChainSpec::from_file("raw-spec.json")?;
+//! for account in spec.endowed_accounts()? {
+//!     println!("{}: {}", account.account, account.free);
+//! }
+//! println!("Para id: {:?}", spec.parachain_id());
+//! spec.check_node(adapter)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Genesis hashes can only be computed from raw specs; a plain spec's
+//! storage is built by running the runtime.
+
+use crate::event_query::{account_hex, hex_bytes};
+use crate::receipt::json_u128;
+use crate::runtime_upgrade::WasmRuntime;
+use crate::state_diff::storage_prefix;
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::Decode;
+use serde_json::Value as JsonValue;
+use sp_runtime::traits::{BlakeTwo256, Hash, Header as _};
+use sp_runtime::StateVersion;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Storage key of the runtime code
+const CODE_KEY: &[u8] = b":code";
+
+/// Prefix of the keys holding default child trie roots
+const CHILD_STORAGE_PREFIX: &[u8] = b":child_storage:default:";
+
+/// Well-known key of GRANDPA authorities on older runtimes
+const GRANDPA_AUTHORITIES_KEY: &[u8] = b":grandpa_authorities";
+
+/// Key-value pairs of one trie
+type Trie = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Genesis state of a chain spec
+#[derive(Debug, Clone, PartialEq)]
+pub enum Genesis {
+    /// Genesis config of the runtime, built into storage by the node
+    Plain(JsonValue),
+    /// Genesis storage
+    Raw {
+        /// Main trie
+        top: Trie,
+        /// Default child tries, by child storage key without prefix
+        children_default: BTreeMap<Vec<u8>, Trie>,
+    },
+}
+
+/// An account endowed at genesis
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenesisAccount {
+    /// Account id (hex)
+    pub account: String,
+    /// Free balance
+    ///
+    /// Plain specs writing balances beyond 2^64 as JSON numbers are read
+    /// with floating point precision.
+    pub free: u128,
+}
+
+/// Initial authorities found in a genesis
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenesisAuthorities {
+    /// Validators of the first session (hex)
+    pub validators: Vec<String>,
+    /// Aura block authors (hex)
+    pub aura: Vec<String>,
+    /// BABE block authors (hex)
+    pub babe: Vec<String>,
+    /// GRANDPA finality voters (hex)
+    pub grandpa: Vec<String>,
+}
+
+impl GenesisAuthorities {
+    /// Whether no authorities were found
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+            && self.aura.is_empty()
+            && self.babe.is_empty()
+            && self.grandpa.is_empty()
+    }
+}
+
+/// A parsed chain spec
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainSpec {
+    /// Human-readable chain name
+    pub name: String,
+    /// Chain id, e.g. `rococo_local_testnet`
+    pub id: String,
+    /// `Development`, `Local` or `Live`, if given
+    pub chain_type: Option<String>,
+    /// Boot node multiaddresses
+    pub boot_nodes: Vec<String>,
+    /// Chain properties such as `tokenSymbol` and `ss58Format`
+    pub properties: JsonValue,
+    /// Relay chain of a parachain spec
+    pub relay_chain: Option<String>,
+    /// Parachain id given outside the genesis
+    pub para_id: Option<u32>,
+    /// Genesis state
+    pub genesis: Genesis,
+}
+
+impl ChainSpec {
+    /// Load a chain spec from a JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            Error::Other(format!(
+                "Failed to read chain spec {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::from_json(&json)
+    }
+
+    /// Parse a plain or raw chain spec
+    pub fn from_json(json: &str) -> Result<Self> {
+        let spec: JsonValue = serde_json::from_str(json)
+            .map_err(|e| Error::Encoding(format!("Invalid chain spec: {}", e)))?;
+        let text = |field: &str| spec[field].as_str().map(str::to_string);
+
+        let genesis = &spec["genesis"];
+        let genesis = if let Some(raw) = genesis.get("raw") {
+            let children_default = match raw.get("childrenDefault") {
+                Some(JsonValue::Object(children)) => children
+                    .iter()
+                    .map(|(key, trie)| {
+                        Ok((hex_bytes(key, "hex in chain spec")?, parse_trie(trie)?))
+                    })
+                    .collect::<Result<_>>()?,
+                _ => BTreeMap::new(),
+            };
+            Genesis::Raw {
+                top: parse_trie(&raw["top"])?,
+                children_default,
+            }
+        } else if genesis.is_object() {
+            Genesis::Plain(genesis.clone())
+        } else {
+            return Err(Error::Encoding("Chain spec has no genesis".to_string()));
+        };
+
+        Ok(Self {
+            name: text("name").unwrap_or_default(),
+            id: text("id").unwrap_or_default(),
+            chain_type: text("chainType"),
+            boot_nodes: spec["bootNodes"]
+                .as_array()
+                .map(|nodes| {
+                    nodes
+                        .iter()
+                        .filter_map(|node| node.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            properties: spec["properties"].clone(),
+            relay_chain: text("relay_chain").or_else(|| text("relayChain")),
+            para_id: ["para_id", "paraId"]
+                .iter()
+                .find_map(|field| spec[*field].as_u64())
+                .and_then(|id| u32::try_from(id).ok()),
+            genesis,
+        })
+    }
+
+    /// Whether the genesis is raw storage
+    pub fn is_raw(&self) -> bool {
+        matches!(self.genesis, Genesis::Raw { .. })
+    }
+
+    /// Runtime code of the genesis block
+    pub fn code(&self) -> Result<Option<Vec<u8>>> {
+        match &self.genesis {
+            Genesis::Raw { top, .. } => Ok(top.get(CODE_KEY).cloned()),
+            Genesis::Plain(_) => {
+                let code = self
+                    .genesis_json("code")
+                    .or_else(|| self.config().map(|config| &config["system"]["code"]));
+                match code.and_then(JsonValue::as_str) {
+                    Some(code) => hex_bytes(code, "hex in chain spec").map(Some),
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+
+    /// Parachain id, from the spec or the genesis `ParachainInfo`
+    pub fn parachain_id(&self) -> Option<u32> {
+        let genesis_id = match &self.genesis {
+            Genesis::Raw { top, .. } => top
+                .get(&storage_prefix("ParachainInfo", "ParachainId"))
+                .and_then(|value| u32::decode(&mut value.as_slice()).ok()),
+            Genesis::Plain(_) => self
+                .config_section("parachainInfo")
+                .and_then(|info| info.get("parachainId").or_else(|| info.get("parachain_id")))
+                .and_then(JsonValue::as_u64)
+                .and_then(|id| u32::try_from(id).ok()),
+        };
+        genesis_id.or(self.para_id)
+    }
+
+    /// Accounts with a balance at genesis
+    pub fn endowed_accounts(&self) -> Result<Vec<GenesisAccount>> {
+        match &self.genesis {
+            Genesis::Raw { top, .. } => {
+                let prefix = storage_prefix("System", "Account");
+                top.iter()
+                    .filter(|(key, _)| key.starts_with(&prefix))
+                    .map(|(key, value)| {
+                        // Blake2_128Concat: 16 byte hash, then the account id
+                        let account = key.get(prefix.len() + 16..).ok_or_else(|| {
+                            Error::Encoding("Invalid System::Account key".to_string())
+                        })?;
+                        Ok(GenesisAccount {
+                            account: format!("0x{}", hex::encode(account)),
+                            free: account_free(value)?,
+                        })
+                    })
+                    .collect()
+            }
+            Genesis::Plain(_) => {
+                let Some(balances) = self
+                    .config_section("balances")
+                    .and_then(|balances| balances["balances"].as_array())
+                else {
+                    return Ok(Vec::new());
+                };
+                balances
+                    .iter()
+                    .map(|entry| {
+                        let address = entry[0].as_str().ok_or_else(|| {
+                            Error::Encoding(format!("Invalid genesis balance: {}", entry))
+                        })?;
+                        let free = json_u128(&entry[1])
+                            .or_else(|| entry[1].as_f64().map(|amount| amount as u128))
+                            .ok_or_else(|| {
+                                Error::Encoding(format!("Invalid genesis balance: {}", entry))
+                            })?;
+                        Ok(GenesisAccount {
+                            account: account_hex(address)?,
+                            free,
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Validators and consensus authorities at genesis
+    pub fn authorities(&self) -> Result<GenesisAuthorities> {
+        match &self.genesis {
+            Genesis::Raw { top, .. } => {
+                let read = |pallet: &str, item: &str| top.get(&storage_prefix(pallet, item));
+                let mut authorities = GenesisAuthorities::default();
+                if let Some(value) = read("Session", "Validators") {
+                    authorities.validators = decode_keys::<[u8; 32]>(value, hex_key)?;
+                }
+                if let Some(value) = read("Aura", "Authorities") {
+                    authorities.aura = decode_keys::<[u8; 32]>(value, hex_key)?;
+                }
+                if let Some(value) = read("Babe", "Authorities") {
+                    authorities.babe =
+                        decode_keys::<([u8; 32], u64)>(value, |(key, _)| hex_key(key))?;
+                }
+                if let Some(value) = read("Grandpa", "Authorities") {
+                    authorities.grandpa =
+                        decode_keys::<([u8; 32], u64)>(value, |(key, _)| hex_key(key))?;
+                } else if let Some(value) = top.get(GRANDPA_AUTHORITIES_KEY) {
+                    // Prefixed with a version byte
+                    authorities.grandpa = decode_keys::<([u8; 32], u64)>(
+                        value.get(1..).unwrap_or_default(),
+                        |(key, _)| hex_key(key),
+                    )?;
+                }
+                Ok(authorities)
+            }
+            Genesis::Plain(_) => {
+                let keys = |section: &str, field: &str| -> Result<Vec<String>> {
+                    self.config_section(section)
+                        .and_then(|section| section[field].as_array())
+                        .map(|entries| entries.iter().map(authority_key).collect())
+                        .unwrap_or_else(|| Ok(Vec::new()))
+                };
+                let mut authorities = GenesisAuthorities {
+                    validators: Vec::new(),
+                    aura: keys("aura", "authorities")?,
+                    babe: keys("babe", "authorities")?,
+                    grandpa: keys("grandpa", "authorities")?,
+                };
+                // Session keys: [account, validator, {aura, babe, grandpa, ..}]
+                let session = self
+                    .config_section("session")
+                    .and_then(|session| session["keys"].as_array());
+                for entry in session.into_iter().flatten() {
+                    authorities.validators.push(authority_key(&entry[1])?);
+                    for (name, list) in [
+                        ("aura", &mut authorities.aura),
+                        ("babe", &mut authorities.babe),
+                        ("grandpa", &mut authorities.grandpa),
+                    ] {
+                        if let Some(key) = entry[2].get(name) {
+                            list.push(authority_key(key)?);
+                        }
+                    }
+                }
+                Ok(authorities)
+            }
+        }
+    }
+
+    /// Hash of the genesis block this spec produces
+    ///
+    /// Needs a raw spec with the runtime code, whose state version decides
+    /// the trie layout.
+    pub fn genesis_hash(&self) -> Result<String> {
+        let Genesis::Raw {
+            top,
+            children_default,
+        } = &self.genesis
+        else {
+            return Err(Error::Other(
+                "Genesis hash needs a raw chain spec".to_string(),
+            ));
+        };
+        let code = self
+            .code()?
+            .ok_or_else(|| Error::Other("Chain spec has no runtime code".to_string()))?;
+        let state_version =
+            StateVersion::try_from(WasmRuntime::from_bytes(code)?.version().state_version)
+                .map_err(|_| Error::Encoding("Unknown runtime state version".to_string()))?;
+
+        let mut top = top.clone();
+        for (key, child) in children_default {
+            let root = BlakeTwo256::trie_root(
+                child.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+                state_version,
+            );
+            top.insert(
+                [CHILD_STORAGE_PREFIX, key.as_slice()].concat(),
+                root.as_bytes().to_vec(),
+            );
+        }
+        let state_root = BlakeTwo256::trie_root(top.into_iter().collect(), state_version);
+        let extrinsics_root = BlakeTwo256::ordered_trie_root(Vec::new(), state_version);
+        let header = sp_runtime::generic::Header::<u32, BlakeTwo256>::new(
+            0,
+            extrinsics_root,
+            state_root,
+            Default::default(),
+            Default::default(),
+        );
+        Ok(format!("0x{}", hex::encode(header.hash())))
+    }
+
+    /// Check that the adapter's node runs the chain this spec describes
+    pub fn check_node(&self, adapter: &SubstrateAdapter) -> Result<()> {
+        let expected = self.genesis_hash()?;
+        let actual = format!("0x{}", hex::encode(adapter.client().genesis_hash()));
+        if expected != actual {
+            return Err(Error::Other(format!(
+                "Chain spec {} has genesis {}, but the node runs {}",
+                self.id, expected, actual
+            )));
+        }
+        Ok(())
+    }
+
+    /// Field of a plain genesis, in current or older layouts
+    fn genesis_json(&self, field: &str) -> Option<&JsonValue> {
+        let Genesis::Plain(genesis) = &self.genesis else {
+            return None;
+        };
+        genesis
+            .get("runtimeGenesis")
+            .and_then(|runtime| runtime.get(field))
+    }
+
+    /// Runtime genesis config of a plain spec
+    fn config(&self) -> Option<&JsonValue> {
+        let Genesis::Plain(genesis) = &self.genesis else {
+            return None;
+        };
+        self.genesis_json("config")
+            .or_else(|| self.genesis_json("patch"))
+            .or_else(|| genesis.get("runtime"))
+            .map(|runtime| runtime.get("runtime_genesis_config").unwrap_or(runtime))
+    }
+
+    /// Pallet section of a plain genesis config, in camel or snake case
+    fn config_section(&self, name: &str) -> Option<&JsonValue> {
+        let config = self.config()?;
+        config
+            .get(name)
+            .or_else(|| config.get(camel_to_snake(name).as_str()))
+    }
+}
+
+fn camel_to_snake(name: &str) -> String {
+    name.chars()
+        .flat_map(|c| {
+            let lower = c.to_ascii_lowercase();
+            (c.is_ascii_uppercase().then_some('_').into_iter()).chain([lower])
+        })
+        .collect()
+}
+
+fn parse_trie(trie: &JsonValue) -> Result<Trie> {
+    let JsonValue::Object(entries) = trie else {
+        return Err(Error::Encoding(
+            "Raw genesis trie is not an object".to_string(),
+        ));
+    };
+    entries
+        .iter()
+        .map(|(key, value)| {
+            let value = value
+                .as_str()
+                .ok_or_else(|| Error::Encoding(format!("Invalid raw value for {}", key)))?;
+            Ok((
+                hex_bytes(key, "hex in chain spec")?,
+                hex_bytes(value, "hex in chain spec")?,
+            ))
+        })
+        .collect()
+}
+
+fn hex_key(key: [u8; 32]) -> String {
+    format!("0x{}", hex::encode(key))
+}
+
+/// Decode a SCALE `Vec` of keys, rendering each one
+fn decode_keys<T: Decode>(mut value: &[u8], render: impl Fn(T) -> String) -> Result<Vec<String>> {
+    Vec::<T>::decode(&mut value)
+        .map(|keys| keys.into_iter().map(render).collect())
+        .map_err(|e| Error::Encoding(format!("Invalid genesis authorities: {}", e)))
+}
+
+/// Free balance of an encoded `AccountInfo`: nonce, consumers, providers and
+/// sufficients, then the account data starting with `free`
+fn account_free(value: &[u8]) -> Result<u128> {
+    let mut data = value
+        .get(16..)
+        .ok_or_else(|| Error::Encoding("Invalid System::Account value".to_string()))?;
+    u128::decode(&mut data).map_err(|e| Error::Encoding(format!("Invalid account data: {}", e)))
+}
+
+/// An authority in a plain genesis: an address, or `[address, weight]`
+fn authority_key(entry: &JsonValue) -> Result<String> {
+    let address = match entry {
+        JsonValue::Array(pair) => pair.first(),
+        other => Some(other),
+    }
+    .and_then(JsonValue::as_str)
+    .ok_or_else(|| Error::Encoding(format!("Invalid genesis authority: {}", entry)))?;
+    account_hex(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_scale_codec::Encode;
+    use serde_json::json;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const ALICE_HEX: &str = "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    #[test]
+    fn test_plain_spec() {
+        let spec = json!({
+            "name": "Local Testnet",
+            "id": "local_testnet",
+            "chainType": "Local",
+            "bootNodes": [],
+            "relay_chain": "rococo-local",
+            "para_id": 2000,
+            "properties": { "tokenSymbol": "UNIT" },
+            "genesis": { "runtimeGenesis": {
+                "code": "0x00",
+                "patch": {
+                    "balances": { "balances": [[ALICE, 1_000_000_000_000u64], [BOB, "2000000000000000000000"]] },
+                    "parachainInfo": { "parachainId": 2000 },
+                    "aura": { "authorities": [ALICE] },
+                    "session": { "keys": [[ALICE, ALICE, { "aura": ALICE, "grandpa": BOB }]] }
+                }
+            }}
+        });
+        let spec = ChainSpec::from_json(&spec.to_string()).unwrap();
+        assert!(!spec.is_raw());
+        assert_eq!(spec.chain_type.as_deref(), Some("Local"));
+        assert_eq!(spec.relay_chain.as_deref(), Some("rococo-local"));
+        assert_eq!(spec.parachain_id(), Some(2000));
+        assert_eq!(spec.code().unwrap(), Some(vec![0]));
+
+        let accounts = spec.endowed_accounts().unwrap();
+        assert_eq!(accounts[0].account, ALICE_HEX);
+        assert_eq!(accounts[1].free, 2_000_000_000_000_000_000_000);
+
+        let authorities = spec.authorities().unwrap();
+        assert_eq!(authorities.validators, vec![ALICE_HEX]);
+        assert_eq!(authorities.aura, vec![ALICE_HEX, ALICE_HEX]);
+        assert_eq!(authorities.grandpa.len(), 1);
+        assert!(spec.genesis_hash().is_err());
+    }
+
+    #[test]
+    fn test_raw_spec() {
+        let alice = [0xd4u8; 32];
+        let mut account_key = storage_prefix("System", "Account");
+        account_key.extend([0u8; 16]);
+        account_key.extend(alice);
+        let account_info = (0u32, 0u32, 1u32, 0u32, 5_000u128, 0u128, 0u128, 0u128).encode();
+
+        let raw = |key: &[u8], value: &[u8]| {
+            (
+                format!("0x{}", hex::encode(key)),
+                json!(format!("0x{}", hex::encode(value))),
+            )
+        };
+        let top: serde_json::Map<_, _> = [
+            raw(&account_key, &account_info),
+            raw(
+                &storage_prefix("ParachainInfo", "ParachainId"),
+                &1000u32.encode(),
+            ),
+            raw(
+                &storage_prefix("Aura", "Authorities"),
+                &vec![alice].encode(),
+            ),
+            raw(
+                &storage_prefix("Grandpa", "Authorities"),
+                &vec![(alice, 1u64)].encode(),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let spec = json!({
+            "name": "Raw",
+            "id": "raw",
+            "genesis": { "raw": { "top": top, "childrenDefault": {} } }
+        });
+
+        let spec = ChainSpec::from_json(&spec.to_string()).unwrap();
+        assert!(spec.is_raw());
+        assert_eq!(spec.parachain_id(), Some(1000));
+        assert_eq!(
+            spec.endowed_accounts().unwrap(),
+            vec![GenesisAccount {
+                account: hex_key(alice),
+                free: 5_000
+            }]
+        );
+        let authorities = spec.authorities().unwrap();
+        assert_eq!(authorities.aura, vec![hex_key(alice)]);
+        assert_eq!(authorities.grandpa, vec![hex_key(alice)]);
+        assert!(authorities.validators.is_empty());
+        // No runtime code to read the state version from
+        assert!(spec.genesis_hash().is_err());
+    }
+
+    #[test]
+    fn test_camel_to_snake() {
+        assert_eq!(camel_to_snake("parachainInfo"), "parachain_info");
+        assert_eq!(camel_to_snake("balances"), "balances");
+    }
+}
@@ -0,0 +1,346 @@
+//! Conversion between timestamps and block numbers
+//!
+//! [`ChainTime`] answers "which block was live at this time?" and "when was
+//! (or will be) this block produced?" in two modes:
+//!
+//! - **Estimate**: a [`BlockClock`] anchored at the finalized head. The block
+//!   time comes from the runtime constants (`Babe::ExpectedBlockTime`,
+//!   `Aura::SlotDuration`, or twice `Timestamp::MinimumPeriod`) and is refined
+//!   by sampling real `Timestamp::Now` values, so missed slots are accounted
+//!   for. Costs a few storage reads and also works for future blocks.
+//! - **Exact**: a binary search over `Timestamp::Now` in historical state.
+//!   Needs an archive node and about `log2(head)` storage reads.
+//!
+//! All timestamps are Unix milliseconds, as stored by the Timestamp pallet.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{ChainTime, SubstrateAdapter};
+//! use chrono::{TimeZone, Utc};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let new_year = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+//! let time = ChainTime::new(adapter);
+//!
+//! let estimate = time.block_at(new_year.timestamp_millis() as u64).await?;
+//! let exact = time.block_at_exact(new_year.timestamp_millis() as u64).await?;
+//! println!("~{} (exactly {:?})", estimate, exact);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::Decode;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tracing::debug;
+
+/// Block time assumed when the runtime exposes no slot constants
+pub const DEFAULT_BLOCK_TIME_MS: u64 = 6_000;
+
+/// Blocks between the head and the older sample used to measure block time
+pub const DEFAULT_SAMPLE_DISTANCE: u64 = 10_000;
+
+/// Linear mapping between block numbers and timestamps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockClock {
+    /// Block with a known timestamp
+    pub anchor_block: u64,
+    /// Timestamp of `anchor_block` in milliseconds
+    pub anchor_ms: u64,
+    /// Average milliseconds per block
+    pub block_time_ms: u64,
+}
+
+impl BlockClock {
+    /// Clock anchored at a known block
+    pub fn new(anchor_block: u64, anchor_ms: u64, block_time_ms: u64) -> Self {
+        Self {
+            anchor_block,
+            anchor_ms,
+            block_time_ms: block_time_ms.max(1),
+        }
+    }
+
+    /// Clock fitted to sampled `(block, timestamp)` pairs
+    ///
+    /// Anchored at the newest sample; the block time is the average between
+    /// the oldest and newest sample, or `block_time_ms` with a single sample.
+    /// Parachains with elastic scaling author several blocks per slot, which
+    /// can share a timestamp; samples that show no time passing also fall
+    /// back to `block_time_ms`.
+    pub fn from_samples(samples: &[(u64, u64)], block_time_ms: u64) -> Option<Self> {
+        let oldest = samples.iter().min_by_key(|(block, _)| *block)?;
+        let newest = samples.iter().max_by_key(|(block, _)| *block)?;
+
+        let blocks = newest.0 - oldest.0;
+        let measured = newest
+            .1
+            .checked_sub(oldest.1)
+            .filter(|_| blocks > 0)
+            .map(|elapsed| elapsed / blocks)
+            .filter(|ms| *ms > 0);
+        Some(Self::new(
+            newest.0,
+            newest.1,
+            measured.unwrap_or(block_time_ms),
+        ))
+    }
+
+    /// Estimated timestamp of `block`
+    pub fn timestamp_of(&self, block: u64) -> u64 {
+        if block >= self.anchor_block {
+            let elapsed = (block - self.anchor_block).saturating_mul(self.block_time_ms);
+            self.anchor_ms.saturating_add(elapsed)
+        } else {
+            let elapsed = (self.anchor_block - block).saturating_mul(self.block_time_ms);
+            self.anchor_ms.saturating_sub(elapsed)
+        }
+    }
+
+    /// Estimated block that was live at `timestamp_ms`
+    ///
+    /// That is the last block produced at or before the timestamp.
+    pub fn block_at(&self, timestamp_ms: u64) -> u64 {
+        if timestamp_ms >= self.anchor_ms {
+            self.anchor_block
+                .saturating_add((timestamp_ms - self.anchor_ms) / self.block_time_ms)
+        } else {
+            let blocks = (self.anchor_ms - timestamp_ms).div_ceil(self.block_time_ms);
+            self.anchor_block.saturating_sub(blocks)
+        }
+    }
+}
+
+/// Timestamp and block number conversions for a connected chain
+pub struct ChainTime<'a> {
+    adapter: &'a SubstrateAdapter,
+    block_time: Option<Duration>,
+    sample_distance: u64,
+}
+
+impl<'a> ChainTime<'a> {
+    /// Converter using the adapter's connection
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self {
+            adapter,
+            block_time: None,
+            sample_distance: DEFAULT_SAMPLE_DISTANCE,
+        }
+    }
+
+    /// Use a fixed block time instead of reading runtime constants
+    pub fn with_block_time(mut self, block_time: Duration) -> Self {
+        self.block_time = Some(block_time);
+        self
+    }
+
+    /// Distance in blocks between the samples used to measure block time
+    ///
+    /// `0` disables sampling and uses the constant block time.
+    pub fn with_sample_distance(mut self, blocks: u64) -> Self {
+        self.sample_distance = blocks;
+        self
+    }
+
+    /// Target block time in milliseconds
+    pub fn expected_block_time_ms(&self) -> u64 {
+        if let Some(block_time) = self.block_time {
+            return block_time.as_millis() as u64;
+        }
+
+        let storage = self.adapter.storage();
+        let constant = |pallet, name| {
+            storage
+                .get_constant(pallet, name)
+                .ok()
+                .and_then(|bytes| u64::decode(&mut &bytes[..]).ok())
+                .filter(|ms| *ms > 0)
+        };
+        constant("Babe", "ExpectedBlockTime")
+            .or_else(|| constant("Aura", "SlotDuration"))
+            .or_else(|| constant("Timestamp", "MinimumPeriod").map(|ms| ms * 2))
+            .unwrap_or(DEFAULT_BLOCK_TIME_MS)
+    }
+
+    /// Exact `Timestamp::Now` of a block; requires its state to be available
+    pub async fn timestamp_at(&self, block: u64) -> Result<u64> {
+        let spec = self.adapter.spec_client();
+        let hash = spec
+            .block_hash(block)
+            .await?
+            .ok_or_else(|| Error::Storage(format!("Block {} not found", block)))?;
+        let bytes = spec
+            .storage(&timestamp_key(), hash)
+            .await?
+            .ok_or_else(|| Error::Storage(format!("No timestamp at block {}", block)))?;
+        u64::decode(&mut &bytes[..])
+            .map_err(|e| Error::Encoding(format!("Failed to decode Timestamp::Now: {}", e)))
+    }
+
+    /// Clock anchored at the finalized head and fitted to sampled timestamps
+    pub async fn clock(&self) -> Result<BlockClock> {
+        let head = self.adapter.spec_client().finalized_number().await?;
+        let block_time_ms = self.expected_block_time_ms();
+
+        let mut samples = vec![(head, self.timestamp_at(head).await?)];
+        let older = head.saturating_sub(self.sample_distance).max(1);
+        if older < head {
+            // pruned nodes may not have older state; fall back to the constant
+            match self.timestamp_at(older).await {
+                Ok(timestamp) => samples.push((older, timestamp)),
+                Err(e) => debug!("Block time sample at {} unavailable: {}", older, e),
+            }
+        }
+
+        let clock = BlockClock::from_samples(&samples, block_time_ms)
+            .unwrap_or_else(|| BlockClock::new(head, samples[0].1, block_time_ms));
+        debug!(
+            "Block clock: {} ms/block (constant {} ms), anchored at {}",
+            clock.block_time_ms, block_time_ms, clock.anchor_block
+        );
+        Ok(clock)
+    }
+
+    /// Estimated block live at `timestamp_ms`; may lie in the future
+    pub async fn block_at(&self, timestamp_ms: u64) -> Result<u64> {
+        Ok(self.clock().await?.block_at(timestamp_ms))
+    }
+
+    /// Estimated timestamp of `block`; may lie in the future
+    pub async fn timestamp_of(&self, block: u64) -> Result<u64> {
+        Ok(self.clock().await?.timestamp_of(block))
+    }
+
+    /// Last finalized block produced at or before `timestamp_ms`
+    ///
+    /// Binary search over historical `Timestamp::Now`. Returns `None` for
+    /// timestamps before block 1; timestamps after the finalized head resolve
+    /// to the head.
+    pub async fn block_at_exact(&self, timestamp_ms: u64) -> Result<Option<u64>> {
+        let head = self.adapter.spec_client().finalized_number().await?;
+        search_block(1, head, timestamp_ms, |block| self.timestamp_at(block)).await
+    }
+}
+
+/// Storage key of `Timestamp::Now`
+fn timestamp_key() -> Vec<u8> {
+    [sp_core::twox_128(b"Timestamp"), sp_core::twox_128(b"Now")].concat()
+}
+
+/// Last block in `low..=high` whose timestamp is at most `target`
+///
+/// Timestamps must not decrease with the block number. Blocks sharing a
+/// timestamp, as in a slot with several elastic-scaling blocks, resolve to the
+/// last of them.
+async fn search_block<F, Fut>(
+    mut low: u64,
+    mut high: u64,
+    target: u64,
+    mut timestamp_of: F,
+) -> Result<Option<u64>>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<u64>>,
+{
+    if low > high || timestamp_of(low).await? > target {
+        return Ok(None);
+    }
+    if timestamp_of(high).await? <= target {
+        return Ok(Some(high));
+    }
+
+    // invariant: timestamp(low) <= target < timestamp(high)
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if timestamp_of(mid).await? <= target {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(Some(low))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_clock() {
+        let clock = BlockClock::new(1_000, 60_000, 6_000);
+        assert_eq!(clock.timestamp_of(1_010), 120_000);
+        assert_eq!(clock.timestamp_of(990), 0);
+        assert_eq!(clock.block_at(65_999), 1_000);
+        assert_eq!(clock.block_at(66_000), 1_001);
+        assert_eq!(clock.block_at(59_999), 999);
+        assert_eq!(clock.block_at(54_000), 999);
+        assert_eq!(clock.block_at(0), 990);
+
+        // 100 blocks took 700s because of missed slots
+        let fitted = BlockClock::from_samples(&[(1_100, 800_000), (1_000, 100_000)], 6_000);
+        assert_eq!(fitted, Some(BlockClock::new(1_100, 800_000, 7_000)));
+        assert_eq!(
+            BlockClock::from_samples(&[(5, 30_000)], 6_000),
+            Some(BlockClock::new(5, 30_000, 6_000))
+        );
+        assert_eq!(BlockClock::from_samples(&[], 6_000), None);
+    }
+
+    #[tokio::test]
+    async fn test_search_block() {
+        // block n was produced at 1000 + 6000 * n, with a gap after block 5
+        let timestamps: Vec<u64> = (0..20)
+            .map(|n| 1_000 + 6_000 * n + if n > 5 { 60_000 } else { 0 })
+            .collect();
+        let search = |target| {
+            let timestamps = timestamps.clone();
+            async move {
+                search_block(1, 19, target, |block| {
+                    let timestamp = timestamps[block as usize];
+                    async move { Ok(timestamp) }
+                })
+                .await
+                .unwrap()
+            }
+        };
+
+        assert_eq!(search(0).await, None);
+        assert_eq!(search(7_000).await, Some(1));
+        assert_eq!(search(12_999).await, Some(1));
+        assert_eq!(search(31_000).await, Some(5));
+        assert_eq!(search(90_000).await, Some(5));
+        assert_eq!(search(97_000).await, Some(6));
+        assert_eq!(search(u64::MAX).await, Some(19));
+    }
+
+    #[tokio::test]
+    async fn test_blocks_sharing_a_slot() {
+        // three blocks per 6s slot, each slot's blocks stamped with its start
+        let timestamps: Vec<u64> = (0..30).map(|n| 6_000 * (n / 3)).collect();
+        let search = |target| {
+            let timestamps = timestamps.clone();
+            async move {
+                search_block(1, 29, target, |block| {
+                    let timestamp = timestamps[block as usize];
+                    async move { Ok(timestamp) }
+                })
+                .await
+                .unwrap()
+            }
+        };
+        assert_eq!(search(6_000).await, Some(5));
+        assert_eq!(search(11_999).await, Some(5));
+        assert_eq!(search(54_000).await, Some(29));
+
+        // samples within one slot measure nothing
+        assert_eq!(
+            BlockClock::from_samples(&[(4, 6_000), (5, 6_000)], 2_000),
+            Some(BlockClock::new(5, 6_000, 2_000))
+        );
+        assert_eq!(
+            BlockClock::from_samples(&[(3, 6_000), (30, 60_000)], 6_000),
+            Some(BlockClock::new(30, 60_000, 2_000))
+        );
+    }
+}
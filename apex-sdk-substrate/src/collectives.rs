@@ -0,0 +1,347 @@
+//! Membership and voting records of collectives
+//!
+//! Three kinds of membership bodies are read here:
+//!
+//! - ranked collectives such as the Technical Fellowship
+//!   ([`FELLOWSHIP_COLLECTIVE`]), whose members hold a rank and vote on polls
+//!   of their referenda pallet with a weight depending on that rank
+//! - plain collectives (`pallet-collective`) such as an alliance or council,
+//!   whose members vote aye or nay on motions
+//! - the Kusama Society
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::collectives::{fellowship_rank_name, Collectives, FELLOWSHIP_COLLECTIVE};
+//! use apex_sdk_substrate::SubstrateAdapter;
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let collectives = Collectives::new(adapter);
+//! for member in collectives.ranked_members(FELLOWSHIP_COLLECTIVE).await? {
+//!     println!("{} {}", member.account, fellowship_rank_name(member.rank).unwrap_or("?"));
+//! }
+//!
+//! for vote in collectives.ranked_votes(FELLOWSHIP_COLLECTIVE, 312).await? {
+//!     println!("{} voted {} with {} votes", vote.voter, if vote.aye { "aye" } else { "nay" }, vote.votes);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::account_id;
+use crate::receipt::json_some;
+use crate::{Result, SubstrateAdapter};
+use parity_scale_codec::Decode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::dynamic::Value;
+
+/// Ranked collective of the Polkadot Technical Fellowship
+pub const FELLOWSHIP_COLLECTIVE: &str = "FellowshipCollective";
+
+/// Ranked collective of the Polkadot Ambassador Program
+pub const AMBASSADOR_COLLECTIVE: &str = "AmbassadorCollective";
+
+/// Length of a storage prefix followed by a `Twox64Concat` hash
+const TWOX64_KEY_PREFIX_LEN: usize = 32 + 8;
+
+/// Title of a Fellowship rank
+pub fn fellowship_rank_name(rank: u16) -> Option<&'static str> {
+    const NAMES: [&str; 10] = [
+        "Candidate",
+        "Member",
+        "Proficient",
+        "Fellow",
+        "Architect",
+        "Architect Adept",
+        "Grand Architect",
+        "Free Master",
+        "Master Constant",
+        "Grand Master",
+    ];
+    NAMES.get(rank as usize).copied()
+}
+
+/// A member of a ranked collective
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankedMember {
+    /// Member account as hex
+    pub account: String,
+    /// Rank, starting at 0 for candidates
+    pub rank: u16,
+}
+
+/// A member's vote on a poll of a ranked collective
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankedVote {
+    /// Poll (referendum) index
+    pub poll: u32,
+    /// Voter account as hex
+    pub voter: String,
+    /// Whether the vote is aye
+    pub aye: bool,
+    /// Vote weight from the voter's rank
+    pub votes: u32,
+}
+
+impl RankedVote {
+    /// Vote from a `Voting` storage key and decoded `VoteRecord`
+    ///
+    /// The key holds the poll index and voter after their hashes.
+    pub fn from_entry(key: &[u8], record: &JsonValue) -> Option<Self> {
+        let poll = u32::decode(&mut key.get(TWOX64_KEY_PREFIX_LEN..)?).ok()?;
+        let voter = key.get(TWOX64_KEY_PREFIX_LEN + 4 + 8..)?;
+        let (aye, votes) = match record.as_object()?.iter().next()? {
+            (variant, votes) if variant == "Aye" => (true, votes),
+            (variant, votes) if variant == "Nay" => (false, votes),
+            _ => return None,
+        };
+        Some(Self {
+            poll,
+            voter: format!("0x{}", hex::encode(voter)),
+            aye,
+            votes: u32::try_from(votes.as_u64()?).ok()?,
+        })
+    }
+}
+
+/// Votes on a motion of a plain collective
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MotionVotes {
+    /// Motion hash as hex
+    pub proposal: String,
+    /// Motion index
+    pub index: u32,
+    /// Ayes needed to approve
+    pub threshold: u32,
+    /// Accounts voting aye, as hex
+    pub ayes: Vec<String>,
+    /// Accounts voting nay, as hex
+    pub nays: Vec<String>,
+    /// Block voting closes at
+    pub end: u32,
+}
+
+impl MotionVotes {
+    /// Votes from the motion hash and decoded `Votes` record
+    pub fn from_json(proposal: String, value: &JsonValue) -> Option<Self> {
+        let number = |name: &str| u32::try_from(value.get(name)?.as_u64()?).ok();
+        Some(Self {
+            proposal,
+            index: number("index")?,
+            threshold: number("threshold")?,
+            ayes: accounts(value.get("ayes")?),
+            nays: accounts(value.get("nays")?),
+            end: number("end")?,
+        })
+    }
+}
+
+/// A member of the Society
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SocietyMember {
+    /// Member account as hex
+    pub account: String,
+    /// Rank within the society
+    pub rank: u32,
+    /// Strikes accumulated for failing to vote or being rejected
+    pub strikes: u32,
+    /// Vouching status, e.g. `Vouching` or `Banned`
+    pub vouching: Option<String>,
+    /// Index in the member list
+    pub index: u32,
+}
+
+impl SocietyMember {
+    /// Member from its account and decoded `MemberRecord`
+    pub fn from_json(account: String, value: &JsonValue) -> Option<Self> {
+        let number = |name: &str| u32::try_from(value.get(name)?.as_u64()?).ok();
+        Some(Self {
+            account,
+            rank: number("rank")?,
+            strikes: number("strikes")?,
+            vouching: value
+                .get("vouching")
+                .and_then(json_some)
+                .and_then(JsonValue::as_str)
+                .map(str::to_string),
+            index: number("index")?,
+        })
+    }
+}
+
+/// Accounts of a decoded `Vec<AccountId>`; a single account decodes without the list
+fn accounts(value: &JsonValue) -> Vec<String> {
+    match value {
+        JsonValue::String(account) => vec![account.clone()],
+        JsonValue::Array(accounts) => accounts
+            .iter()
+            .filter_map(|account| account.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Hex of the account or hash ending a map key
+fn trailing_hex(key: &[u8]) -> Option<String> {
+    let tail = key.get(key.len().checked_sub(32)?..)?;
+    Some(format!("0x{}", hex::encode(tail)))
+}
+
+/// Collective membership and voting queries for a connected chain
+pub struct Collectives<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> Collectives<'a> {
+    /// Query through the adapter's connection
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Members of a ranked collective, highest rank first
+    pub async fn ranked_members(&self, collective: &str) -> Result<Vec<RankedMember>> {
+        let entries = self
+            .adapter
+            .storage()
+            .iter_storage_json(collective, "Members", vec![])
+            .await?;
+        let mut members: Vec<RankedMember> = entries
+            .iter()
+            .filter_map(|(key, record)| {
+                Some(RankedMember {
+                    account: trailing_hex(key)?,
+                    rank: u16::try_from(record.get("rank")?.as_u64()?).ok()?,
+                })
+            })
+            .collect();
+        members.sort_by(|a, b| b.rank.cmp(&a.rank).then_with(|| a.account.cmp(&b.account)));
+        Ok(members)
+    }
+
+    /// Rank of an account in a ranked collective, if a member
+    pub async fn rank_of(&self, collective: &str, address: &str) -> Result<Option<u16>> {
+        let account = account_id(address)?;
+        let record = self
+            .adapter
+            .storage()
+            .query_storage_json(collective, "Members", vec![Value::from_bytes(account)])
+            .await?;
+        Ok(record
+            .and_then(|record| record.get("rank")?.as_u64())
+            .and_then(|rank| u16::try_from(rank).ok()))
+    }
+
+    /// Number of members at `rank` or above
+    pub async fn member_count(&self, collective: &str, rank: u16) -> Result<u32> {
+        let count = self
+            .adapter
+            .storage()
+            .query_storage_json(collective, "MemberCount", vec![Value::u128(rank as u128)])
+            .await?;
+        Ok(count
+            .and_then(|count| count.as_u64())
+            .and_then(|count| u32::try_from(count).ok())
+            .unwrap_or_default())
+    }
+
+    /// Votes cast on a poll of a ranked collective
+    pub async fn ranked_votes(&self, collective: &str, poll: u32) -> Result<Vec<RankedVote>> {
+        let entries = self
+            .adapter
+            .storage()
+            .iter_storage_json(collective, "Voting", vec![Value::u128(poll as u128)])
+            .await?;
+        Ok(entries
+            .iter()
+            .filter_map(|(key, record)| RankedVote::from_entry(key, record))
+            .collect())
+    }
+
+    /// Members of a plain collective such as `AllianceMotion`
+    pub async fn members(&self, collective: &str) -> Result<Vec<String>> {
+        let members = self
+            .adapter
+            .storage()
+            .query_storage_json(collective, "Members", vec![])
+            .await?;
+        Ok(members.as_ref().map(accounts).unwrap_or_default())
+    }
+
+    /// Prime member of a plain collective
+    pub async fn prime(&self, collective: &str) -> Result<Option<String>> {
+        let prime = self
+            .adapter
+            .storage()
+            .query_storage_json(collective, "Prime", vec![])
+            .await?;
+        Ok(prime.and_then(|prime| prime.as_str().map(str::to_string)))
+    }
+
+    /// Votes on the open motions of a plain collective
+    pub async fn motion_votes(&self, collective: &str) -> Result<Vec<MotionVotes>> {
+        let entries = self
+            .adapter
+            .storage()
+            .iter_storage_json(collective, "Voting", vec![])
+            .await?;
+        Ok(entries
+            .iter()
+            .filter_map(|(key, votes)| MotionVotes::from_json(trailing_hex(key)?, votes))
+            .collect())
+    }
+
+    /// Members of the Society
+    pub async fn society_members(&self) -> Result<Vec<SocietyMember>> {
+        let entries = self
+            .adapter
+            .storage()
+            .iter_storage_json("Society", "Members", vec![])
+            .await?;
+        Ok(entries
+            .iter()
+            .filter_map(|(key, record)| SocietyMember::from_json(trailing_hex(key)?, record))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_ranked_vote_entry() {
+        let mut key = vec![0u8; TWOX64_KEY_PREFIX_LEN];
+        key.extend(312u32.to_le_bytes());
+        key.extend([0u8; 8]);
+        key.extend([0xcd; 32]);
+
+        let vote = RankedVote::from_entry(&key, &json!({"Nay": 3})).unwrap();
+        assert_eq!(vote.poll, 312);
+        assert_eq!(vote.voter, format!("0x{}", "cd".repeat(32)));
+        assert!(!vote.aye);
+        assert_eq!(vote.votes, 3);
+        assert!(RankedVote::from_entry(&key, &json!("Abstain")).is_none());
+
+        assert_eq!(fellowship_rank_name(3), Some("Fellow"));
+        assert_eq!(fellowship_rank_name(10), None);
+    }
+
+    #[test]
+    fn test_motion_votes() {
+        let votes = MotionVotes::from_json(
+            "0x01".to_string(),
+            &json!({"index": 7, "threshold": 4, "ayes": "0xaa", "nays": [], "end": 100}),
+        )
+        .unwrap();
+        assert_eq!(votes.ayes, vec!["0xaa".to_string()]);
+        assert!(votes.nays.is_empty());
+
+        let member = SocietyMember::from_json(
+            "0xbb".to_string(),
+            &json!({"rank": 0, "strikes": 2, "vouching": {"Some": "Banned"}, "index": 5}),
+        )
+        .unwrap();
+        assert_eq!(member.vouching.as_deref(), Some("Banned"));
+        assert_eq!(member.strikes, 2);
+    }
+}
@@ -32,6 +32,7 @@
 //!     .await?;
 //! ```
 
+use crate::pallets::require_pallet;
 use crate::{Error, Result, Sr25519Signer, Wallet};
 use serde::{Deserialize, Serialize};
 use subxt::{OnlineClient, PolkadotConfig};
@@ -360,6 +361,7 @@ impl ContractClient {
         salt: Option<Vec<u8>>,
     ) -> Result<Self> {
         info!("Deploying contract with constructor: {}", constructor_name);
+        require_pallet(&client.metadata(), "Contracts")?;
 
         // Find the constructor
         let constructor = metadata
@@ -489,6 +491,7 @@ impl ContractClient {
     /// Transaction hash of the call
     pub async fn call(&self, method_name: &str, args: &[u8], wallet: &Wallet) -> Result<String> {
         info!("Calling contract method: {}", method_name);
+        require_pallet(&self.client.metadata(), "Contracts")?;
 
         // Find the message in metadata
         let message = if let Some(ref metadata) = self.metadata {
@@ -0,0 +1,478 @@
+//! Coretime sales and regions from the broker pallet
+//!
+//! Coretime chains sell cores in bulk through `pallet-broker`. Each sale
+//! period starts with a lead-in during which the price falls towards the end
+//! price; a purchase yields a [`RegionId`] naming a core, a start timeslice and
+//! the 80-part [`CoreMask`] of the core it covers. Regions can be split in time
+//! with [`Coretime::partition`] or by core share with [`Coretime::interlace`]
+//! before being assigned to a task.
+//!
+//! [`Region::partition_at`] and [`Region::interlace_with`] compute the
+//! resulting regions locally, so callers know their ids without waiting for
+//! the events.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::coretime::{CoreMask, Coretime, PriceAdapter};
+//! use apex_sdk_substrate::{SubstrateAdapter, Wallet};
+//!
+//! # async fn example(adapter: &SubstrateAdapter, wallet: &Wallet, now: u32) -> Result<(), apex_sdk_substrate::Error> {
+//! let coretime = Coretime::new(adapter);
+//! let sale = coretime.sale_info().await?.expect("sales started");
+//! let price = sale.price_at(now, PriceAdapter::CenterTarget);
+//! println!("{} cores left at {}", sale.cores_available(), price);
+//!
+//! coretime.purchase(wallet, price).await?;
+//! for region in coretime.regions_of(&wallet.address()).await? {
+//!     // give half of the core to another task
+//!     coretime.interlace(wallet, &region.id, CoreMask::from_chunk(0, 40)).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::account_id;
+use crate::receipt::{json_some, json_u128};
+use crate::wallet::Wallet;
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::dynamic::Value;
+
+/// Period of 80 relay chain blocks in which coretime is accounted
+pub type Timeslice = u32;
+
+/// Index of a core
+pub type CoreIndex = u16;
+
+/// Number of parts a core is divided into
+pub const CORE_MASK_BITS: u32 = 80;
+
+/// Bytes of a region key before the encoded [`RegionId`]: storage prefix and hash
+const REGION_KEY_PREFIX_LEN: usize = 32 + 16;
+
+/// Share of a core as a bitmap of 80 parts, most significant bit first
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Encode, Decode, Serialize, Deserialize,
+)]
+pub struct CoreMask(pub [u8; 10]);
+
+impl CoreMask {
+    /// The whole core
+    pub fn complete() -> Self {
+        Self([0xff; 10])
+    }
+
+    /// No part of the core
+    pub fn void() -> Self {
+        Self([0; 10])
+    }
+
+    /// Parts `from..to` of the core
+    pub fn from_chunk(from: u32, to: u32) -> Self {
+        let mut bytes = [0u8; 10];
+        for part in from.min(CORE_MASK_BITS)..to.min(CORE_MASK_BITS) {
+            bytes[part as usize / 8] |= 0x80 >> (part % 8);
+        }
+        Self(bytes)
+    }
+
+    /// Number of parts set
+    pub fn count_ones(&self) -> u32 {
+        self.0.iter().map(|byte| byte.count_ones()).sum()
+    }
+
+    /// Whether no part is set
+    pub fn is_void(&self) -> bool {
+        self.0 == [0; 10]
+    }
+
+    /// Whether every part is set
+    pub fn is_complete(&self) -> bool {
+        self.0 == [0xff; 10]
+    }
+
+    /// Whether every part of `self` is also in `other`
+    pub fn is_subset_of(&self, other: &CoreMask) -> bool {
+        self.0.iter().zip(other.0).all(|(a, b)| a & !b == 0)
+    }
+
+    fn xor(&self, other: &CoreMask) -> Self {
+        let mut bytes = self.0;
+        for (byte, other) in bytes.iter_mut().zip(other.0) {
+            *byte ^= other;
+        }
+        Self(bytes)
+    }
+}
+
+/// Identifier of a region of coretime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Encode, Decode, Serialize, Deserialize)]
+pub struct RegionId {
+    /// First timeslice of the region
+    pub begin: Timeslice,
+    /// Core the region is on
+    pub core: CoreIndex,
+    /// Share of the core
+    pub mask: CoreMask,
+}
+
+impl RegionId {
+    /// Region id from a `Broker::Regions` storage key
+    pub fn from_storage_key(key: &[u8]) -> Option<Self> {
+        RegionId::decode(&mut key.get(REGION_KEY_PREFIX_LEN..)?).ok()
+    }
+
+    fn to_value(self) -> Value {
+        Value::named_composite([
+            ("begin", Value::u128(self.begin as u128)),
+            ("core", Value::u128(self.core as u128)),
+            (
+                "mask",
+                Value::unnamed_composite([Value::from_bytes(self.mask.0)]),
+            ),
+        ])
+    }
+}
+
+/// A region and its record in `Broker::Regions`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    /// Region id
+    pub id: RegionId,
+    /// Timeslice the region ends before
+    pub end: Timeslice,
+    /// Owner account as hex, if owned
+    pub owner: Option<String>,
+    /// Price paid, for regions bought in a sale
+    pub paid: Option<u128>,
+}
+
+impl Region {
+    /// Region from its id and decoded `RegionRecord`
+    pub fn from_record(id: RegionId, record: &JsonValue) -> Option<Self> {
+        let end = u32::try_from(record.get("end")?.as_u64()?).ok()?;
+        // older runtimes stored the owner without an `Option`
+        let owner = record
+            .get("owner")
+            .and_then(|owner| match json_some(owner)? {
+                JsonValue::String(owner) => Some(owner.clone()),
+                _ => None,
+            });
+        let paid = record.get("paid").and_then(json_some).and_then(json_u128);
+        Some(Self {
+            id,
+            end,
+            owner,
+            paid,
+        })
+    }
+
+    /// Number of timeslices the region lasts
+    pub fn duration(&self) -> Timeslice {
+        self.end.saturating_sub(self.id.begin)
+    }
+
+    /// The two regions `partition` at `pivot` produces
+    pub fn partition_at(&self, pivot: Timeslice) -> Result<(Region, Region)> {
+        if pivot <= self.id.begin || pivot >= self.end {
+            return Err(Error::Transaction(format!(
+                "Pivot {} is outside region {}..{}",
+                pivot, self.id.begin, self.end
+            )));
+        }
+        let first = Region {
+            end: pivot,
+            ..self.clone()
+        };
+        let second = Region {
+            id: RegionId {
+                begin: pivot,
+                ..self.id
+            },
+            ..self.clone()
+        };
+        Ok((first, second))
+    }
+
+    /// The two regions `interlace` with `pivot` produces: `pivot` and the rest
+    pub fn interlace_with(&self, pivot: CoreMask) -> Result<(Region, Region)> {
+        if pivot.is_void() || pivot == self.id.mask || !pivot.is_subset_of(&self.id.mask) {
+            return Err(Error::Transaction(
+                "Interlace pivot must be a non-empty, strict part of the region's mask".to_string(),
+            ));
+        }
+        let with_mask = |mask| Region {
+            id: RegionId { mask, ..self.id },
+            ..self.clone()
+        };
+        Ok((with_mask(pivot), with_mask(self.id.mask.xor(&pivot))))
+    }
+}
+
+/// How the sale price falls during the lead-in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PriceAdapter {
+    /// From twice the end price down to the end price
+    Linear,
+    /// From 100 times the end price, reaching 10 times halfway through
+    #[default]
+    CenterTarget,
+}
+
+impl PriceAdapter {
+    /// Price multiplier `progress` of the way through the lead-in
+    pub fn leadin_factor(&self, progress: f64) -> f64 {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            PriceAdapter::Linear => 2.0 - progress,
+            PriceAdapter::CenterTarget if progress <= 0.5 => 100.0 - 180.0 * progress,
+            PriceAdapter::CenterTarget => 19.0 - 18.0 * progress,
+        }
+    }
+}
+
+/// The current sale from `Broker::SaleInfo`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaleInfo {
+    /// Block the lead-in starts at
+    pub sale_start: u32,
+    /// Length of the lead-in in blocks
+    pub leadin_length: u32,
+    /// Price once the lead-in is over
+    pub end_price: u128,
+    /// First timeslice of the regions sold
+    pub region_begin: Timeslice,
+    /// Timeslice the regions sold end before
+    pub region_end: Timeslice,
+    /// Cores the sale aims to sell
+    pub ideal_cores_sold: u16,
+    /// Cores offered
+    pub cores_offered: u16,
+    /// Index of the first core offered
+    pub first_core: CoreIndex,
+    /// Price of the last core sold once the ideal was reached
+    pub sellout_price: Option<u128>,
+    /// Cores sold so far
+    pub cores_sold: u16,
+}
+
+impl SaleInfo {
+    /// Sale from a decoded `SaleInfoRecord`
+    pub fn from_json(value: &JsonValue) -> Option<Self> {
+        let number = |name: &str| u32::try_from(value.get(name)?.as_u64()?).ok();
+        let small = |name: &str| u16::try_from(value.get(name)?.as_u64()?).ok();
+        // renamed from `price` when the lead-in started above the end price
+        let end_price = value.get("end_price").or_else(|| value.get("price"));
+        Some(Self {
+            sale_start: number("sale_start")?,
+            leadin_length: number("leadin_length")?,
+            end_price: json_u128(end_price?)?,
+            region_begin: number("region_begin")?,
+            region_end: number("region_end")?,
+            ideal_cores_sold: small("ideal_cores_sold")?,
+            cores_offered: small("cores_offered")?,
+            first_core: small("first_core")?,
+            sellout_price: value
+                .get("sellout_price")
+                .and_then(json_some)
+                .and_then(json_u128),
+            cores_sold: small("cores_sold")?,
+        })
+    }
+
+    /// Cores still for sale
+    pub fn cores_available(&self) -> u16 {
+        self.cores_offered.saturating_sub(self.cores_sold)
+    }
+
+    /// Price of a core at block `now`, counted as the broker pallet counts blocks
+    pub fn price_at(&self, now: u32, adapter: PriceAdapter) -> u128 {
+        let elapsed = now.saturating_sub(self.sale_start);
+        let progress = if self.leadin_length == 0 {
+            1.0
+        } else {
+            elapsed as f64 / self.leadin_length as f64
+        };
+        (self.end_price as f64 * adapter.leadin_factor(progress)) as u128
+    }
+}
+
+/// Coretime sales and regions on a connected coretime chain
+pub struct Coretime<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> Coretime<'a> {
+    /// Use the adapter's connection
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// The current sale, or `None` before sales start
+    pub async fn sale_info(&self) -> Result<Option<SaleInfo>> {
+        let value = self
+            .adapter
+            .storage()
+            .query_storage_json("Broker", "SaleInfo", vec![])
+            .await?;
+        value
+            .map(|value| {
+                SaleInfo::from_json(&value)
+                    .ok_or_else(|| Error::Storage("Unexpected SaleInfo layout".to_string()))
+            })
+            .transpose()
+    }
+
+    /// A region by id
+    pub async fn region(&self, id: &RegionId) -> Result<Option<Region>> {
+        let value = self
+            .adapter
+            .storage()
+            .query_storage_json("Broker", "Regions", vec![id.to_value()])
+            .await?;
+        Ok(value.and_then(|record| Region::from_record(*id, &record)))
+    }
+
+    /// All regions
+    pub async fn regions(&self) -> Result<Vec<Region>> {
+        let entries = self
+            .adapter
+            .storage()
+            .iter_storage_json("Broker", "Regions", vec![])
+            .await?;
+        Ok(entries
+            .iter()
+            .filter_map(|(key, record)| {
+                Region::from_record(RegionId::from_storage_key(key)?, record)
+            })
+            .collect())
+    }
+
+    /// Regions owned by an SS58 or hex address
+    pub async fn regions_of(&self, owner: &str) -> Result<Vec<Region>> {
+        let owner = format!("0x{}", hex::encode(account_id(owner)?));
+        let mut regions = self.regions().await?;
+        regions.retain(|region| {
+            region
+                .owner
+                .as_deref()
+                .is_some_and(|o| o.eq_ignore_ascii_case(&owner))
+        });
+        Ok(regions)
+    }
+
+    /// Buy a core in the current sale, paying at most `price_limit`
+    ///
+    /// The chain picks the core; the new region is in the `Purchased` event.
+    pub async fn purchase(&self, wallet: &Wallet, price_limit: u128) -> Result<String> {
+        self.submit(wallet, "purchase", vec![Value::u128(price_limit)])
+            .await
+    }
+
+    /// Renew the bulk coretime of a core for the next sale period
+    pub async fn renew(&self, wallet: &Wallet, core: CoreIndex) -> Result<String> {
+        self.submit(wallet, "renew", vec![Value::u128(core as u128)])
+            .await
+    }
+
+    /// Split a region in time at `pivot`; see [`Region::partition_at`]
+    pub async fn partition(
+        &self,
+        wallet: &Wallet,
+        region: &RegionId,
+        pivot: Timeslice,
+    ) -> Result<String> {
+        let args = vec![region.to_value(), Value::u128(pivot as u128)];
+        self.submit(wallet, "partition", args).await
+    }
+
+    /// Split a region's core share off into `pivot`; see [`Region::interlace_with`]
+    pub async fn interlace(
+        &self,
+        wallet: &Wallet,
+        region: &RegionId,
+        pivot: CoreMask,
+    ) -> Result<String> {
+        let pivot = Value::unnamed_composite([Value::from_bytes(pivot.0)]);
+        self.submit(wallet, "interlace", vec![region.to_value(), pivot])
+            .await
+    }
+
+    async fn submit(&self, wallet: &Wallet, call: &str, args: Vec<Value>) -> Result<String> {
+        self.adapter.require_pallet("Broker")?;
+        self.adapter
+            .transaction_executor()
+            .submit_call(wallet, "Broker", call, args)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sale_info() {
+        let sale = SaleInfo::from_json(&json!({
+            "sale_start": 1_000,
+            "leadin_length": 100,
+            "end_price": 10_000_000_000u64,
+            "region_begin": 300,
+            "region_end": 306,
+            "ideal_cores_sold": 40,
+            "cores_offered": 50,
+            "first_core": 10,
+            "sellout_price": "None",
+            "cores_sold": 12,
+        }))
+        .unwrap();
+        assert_eq!(sale.cores_available(), 38);
+        assert_eq!(sale.sellout_price, None);
+        assert_eq!(
+            sale.price_at(1_000, PriceAdapter::CenterTarget),
+            1_000_000_000_000
+        );
+        assert_eq!(
+            sale.price_at(1_050, PriceAdapter::CenterTarget),
+            100_000_000_000
+        );
+        assert_eq!(
+            sale.price_at(5_000, PriceAdapter::CenterTarget),
+            10_000_000_000
+        );
+        assert_eq!(sale.price_at(1_050, PriceAdapter::Linear), 15_000_000_000);
+    }
+
+    #[test]
+    fn test_region_splits() {
+        let id = RegionId {
+            begin: 300,
+            core: 4,
+            mask: CoreMask::complete(),
+        };
+        let mut key = vec![0u8; REGION_KEY_PREFIX_LEN];
+        key.extend(id.encode());
+        assert_eq!(RegionId::from_storage_key(&key), Some(id));
+
+        let region = Region::from_record(
+            id,
+            &json!({"end": 306, "owner": {"Some": "0x01"}, "paid": {"Some": 5}}),
+        )
+        .unwrap();
+        assert_eq!(region.owner.as_deref(), Some("0x01"));
+        assert_eq!(region.paid, Some(5));
+
+        let (first, second) = region.partition_at(303).unwrap();
+        assert_eq!((first.id.begin, first.end), (300, 303));
+        assert_eq!((second.id.begin, second.end), (303, 306));
+        assert!(region.partition_at(306).is_err());
+
+        let (half, rest) = region.interlace_with(CoreMask::from_chunk(0, 40)).unwrap();
+        assert_eq!(half.id.mask.count_ones(), 40);
+        assert_eq!(rest.id.mask, CoreMask::from_chunk(40, 80));
+        assert!(region.interlace_with(CoreMask::complete()).is_err());
+        assert!(half.interlace_with(rest.id.mask).is_err());
+    }
+}
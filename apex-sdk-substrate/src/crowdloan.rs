@@ -0,0 +1,280 @@
+//! Crowdloan contributions and parachain auction outcomes
+//!
+//! Crowdloan contributions are kept in one child trie per fund, keyed by
+//! contributor, so they are not visible through regular storage iteration.
+//! [`CrowdloanHistory::contributions`] reads a fund's trie at any block an
+//! archive node still has state for, which also covers funds dissolved since
+//! and chains that no longer run the crowdloan pallet. Auction outcomes are
+//! read from the `Slots::Leased` events emitted when a lease is won.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::crowdloan::{total_contributed, CrowdloanHistory};
+//! use apex_sdk_substrate::SubstrateAdapter;
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let history = CrowdloanHistory::new(adapter);
+//!
+//! // Acala's crowdloan, read at a block before the fund was dissolved
+//! let contributions = history.contributions(2000, 14, 8_400_000).await?;
+//! println!("{} DOT units from {} contributors", total_contributed(&contributions), contributions.len());
+//!
+//! for lease in history.leases_won(7_800_000, 8_200_000).await? {
+//!     println!("para {} won {} periods from {}", lease.para_id, lease.period_count, lease.period_begin);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::EventQuery;
+use crate::receipt::json_u128;
+use crate::rpc_spec::default_child_key;
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::Decode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Keys requested per `childstate_getKeysPaged` call
+const CONTRIBUTION_PAGE_SIZE: u32 = 1_000;
+
+/// Bytes of a `Crowdloan::Funds` key before the para id: storage prefix and hash
+const FUNDS_KEY_PREFIX_LEN: usize = 32 + 8;
+
+/// Trie id of a fund's contributions: `blake2_256("crowdloan" ++ fund_index)`
+pub fn fund_trie_id(fund_index: u32) -> [u8; 32] {
+    let mut seed = b"crowdloan".to_vec();
+    seed.extend_from_slice(&fund_index.to_le_bytes());
+    sp_core::blake2_256(&seed)
+}
+
+/// A crowdloan fund from `Crowdloan::Funds`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FundInfo {
+    /// Parachain the fund bids for
+    pub para_id: u32,
+    /// Account that created the fund, as hex
+    pub depositor: String,
+    /// Amount raised so far
+    pub raised: u128,
+    /// Most the fund accepts
+    pub cap: u128,
+    /// Block contributions close at
+    pub end: u32,
+    /// First lease period bid for
+    pub first_period: u32,
+    /// Last lease period bid for
+    pub last_period: u32,
+    /// Index of the fund's contribution trie
+    pub fund_index: u32,
+}
+
+impl FundInfo {
+    /// Fund from its para id and decoded value
+    pub fn from_json(para_id: u32, value: &JsonValue) -> Option<Self> {
+        let number = |name: &str| u32::try_from(value.get(name)?.as_u64()?).ok();
+        Some(Self {
+            para_id,
+            depositor: value.get("depositor")?.as_str()?.to_string(),
+            raised: json_u128(value.get("raised")?)?,
+            cap: json_u128(value.get("cap")?)?,
+            end: number("end")?,
+            first_period: number("first_period")?,
+            last_period: number("last_period")?,
+            // called `trie_index` before funds were re-indexed
+            fund_index: number("fund_index").or_else(|| number("trie_index"))?,
+        })
+    }
+}
+
+/// One contributor's total to a fund
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contribution {
+    /// Parachain the fund bids for
+    pub para_id: u32,
+    /// Contributor account as hex
+    pub contributor: String,
+    /// Amount contributed
+    pub amount: u128,
+    /// Memo attached by the contributor, e.g. a reward address
+    pub memo: Vec<u8>,
+}
+
+impl Contribution {
+    /// Contribution from a trie entry: the contributor's account and `(amount, memo)`
+    pub fn from_entry(para_id: u32, key: &[u8], value: &[u8]) -> Result<Self> {
+        let (amount, memo) = <(u128, Vec<u8>)>::decode(&mut &value[..])
+            .map_err(|e| Error::Storage(format!("Failed to decode contribution: {}", e)))?;
+        Ok(Self {
+            para_id,
+            contributor: format!("0x{}", hex::encode(key)),
+            amount,
+            memo,
+        })
+    }
+}
+
+/// Sum of contributed amounts
+pub fn total_contributed(contributions: &[Contribution]) -> u128 {
+    contributions
+        .iter()
+        .map(|contribution| contribution.amount)
+        .sum()
+}
+
+/// A lease won in an auction, from a `Slots::Leased` event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaseWon {
+    /// Block the lease was won in
+    pub block_number: u64,
+    /// Winning parachain
+    pub para_id: u32,
+    /// Account holding the deposit, as hex; a crowdloan fund account for crowdloans
+    pub leaser: String,
+    /// First lease period won
+    pub period_begin: u32,
+    /// Number of lease periods won
+    pub period_count: u32,
+    /// Total amount locked for the lease
+    pub total_amount: u128,
+}
+
+impl LeaseWon {
+    /// Lease from the fields of a `Slots::Leased` event
+    pub fn from_event(block_number: u64, fields: &JsonValue) -> Option<Self> {
+        let number = |name: &str| u32::try_from(fields.get(name)?.as_u64()?).ok();
+        Some(Self {
+            block_number,
+            para_id: number("para_id")?,
+            leaser: fields.get("leaser")?.as_str()?.to_string(),
+            period_begin: number("period_begin")?,
+            period_count: number("period_count")?,
+            total_amount: json_u128(fields.get("total_amount")?)?,
+        })
+    }
+}
+
+/// Crowdloan and auction history of a relay chain
+pub struct CrowdloanHistory<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> CrowdloanHistory<'a> {
+    /// Read history through the adapter's connection
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Funds currently open or awaiting dissolution
+    pub async fn funds(&self) -> Result<Vec<FundInfo>> {
+        let entries = self
+            .adapter
+            .storage()
+            .iter_storage_json("Crowdloan", "Funds", vec![])
+            .await?;
+        Ok(entries
+            .iter()
+            .filter_map(|(key, value)| {
+                let para_id = key.get(FUNDS_KEY_PREFIX_LEN..)?;
+                let para_id = u32::decode(&mut &para_id[..]).ok()?;
+                FundInfo::from_json(para_id, value)
+            })
+            .collect())
+    }
+
+    /// Every contribution to a fund as of `block`
+    ///
+    /// Needs the state of `block`, so older blocks need an archive node.
+    pub async fn contributions(
+        &self,
+        para_id: u32,
+        fund_index: u32,
+        block: u64,
+    ) -> Result<Vec<Contribution>> {
+        let spec = self.adapter.spec_client();
+        let hash = spec
+            .block_hash(block)
+            .await?
+            .ok_or_else(|| Error::Storage(format!("Block {} not found", block)))?;
+        let child_key = default_child_key(&fund_trie_id(fund_index));
+
+        let mut contributions = Vec::new();
+        let mut start_key: Option<Vec<u8>> = None;
+        loop {
+            let keys = spec
+                .child_storage_keys(
+                    &child_key,
+                    &[],
+                    CONTRIBUTION_PAGE_SIZE,
+                    start_key.as_deref(),
+                    hash,
+                )
+                .await?;
+            for key in &keys {
+                if let Some(value) = spec.child_storage(&child_key, key, hash).await? {
+                    contributions.push(Contribution::from_entry(para_id, key, &value)?);
+                }
+            }
+            if keys.len() < CONTRIBUTION_PAGE_SIZE as usize {
+                break;
+            }
+            start_key = keys.last().cloned();
+        }
+        Ok(contributions)
+    }
+
+    /// Leases won between two blocks, inclusive
+    pub async fn leases_won(&self, from: u64, to: u64) -> Result<Vec<LeaseWon>> {
+        let events = EventQuery::new()
+            .pallet("Slots")
+            .variant("Leased")
+            .between(from, to)
+            .run(self.adapter)
+            .await?;
+        Ok(events
+            .iter()
+            .filter_map(|event| LeaseWon::from_event(event.block_number, &event.fields))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_scale_codec::Encode;
+    use serde_json::json;
+
+    #[test]
+    fn test_contribution_entries() {
+        let value = (5_000_000_000u128, b"memo".to_vec()).encode();
+        let contribution = Contribution::from_entry(2000, &[0xab; 32], &value).unwrap();
+        assert_eq!(contribution.amount, 5_000_000_000);
+        assert_eq!(contribution.memo, b"memo");
+        assert_eq!(contribution.contributor, format!("0x{}", "ab".repeat(32)));
+        assert!(Contribution::from_entry(2000, &[0; 32], &[1, 2]).is_err());
+        assert_eq!(
+            total_contributed(&[contribution.clone(), contribution]),
+            10_000_000_000
+        );
+
+        assert_ne!(fund_trie_id(0), fund_trie_id(1));
+        assert!(default_child_key(&fund_trie_id(0)).starts_with(b":child_storage:default:"));
+
+        let fund = FundInfo::from_json(
+            2000,
+            &json!({
+                "depositor": "0x01",
+                "verifier": "None",
+                "deposit": 100,
+                "raised": "1000000000000000000000",
+                "end": 8_179_200,
+                "cap": "2000000000000000000000",
+                "last_contribution": {"Ending": 8_100_000},
+                "first_period": 6,
+                "last_period": 13,
+                "trie_index": 14,
+            }),
+        )
+        .unwrap();
+        assert_eq!(fund.fund_index, 14);
+        assert_eq!(fund.raised, 1_000_000_000_000_000_000_000);
+    }
+}
@@ -0,0 +1,294 @@
+//! Routing blocks to the metadata of their runtime during backfills
+//!
+//! Decoding a range of blocks with one metadata silently corrupts every
+//! block on the other side of a runtime upgrade: events still decode, just
+//! into the wrong fields. [`DecodeRouter`] keeps track of which block ranges
+//! each spec version produced and decodes every block with the metadata of
+//! its own range. Upgrade boundaries inside a range are found by bisection,
+//! so planning a backfill costs a few runtime version lookups per upgrade
+//! rather than one per block:
+//!
+//! ```rust,no_run
+//! use apex_sdk_core::FileCheckpointStore;
+//! use apex_sdk_substrate::{DecodeRouter, ResumableSubscription, SubstrateAdapter};
+//! use std::sync::Arc;
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let router = DecodeRouter::new();
+//! for range in router.plan(adapter, 18_000_000, 19_000_000).await? {
+//!     println!("{}..={} spec {}", range.from, range.to, range.spec_version);
+//! }
+//!
+//! let store = Arc::new(FileCheckpointStore::new("./checkpoints"));
+//! let mut blocks = ResumableSubscription::new(adapter, "indexer", store)
+//!     .start_at(18_000_000)
+//!     .with_router(router);
+//! while let Some(block) = blocks.next().await? {
+//!     println!("#{}: {} events", block.number, block.events.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Spec versions only increase, so two blocks of the same spec version
+//! bound a range of that version.
+
+use crate::event_query::MatchedEvent;
+use crate::historical::{events_at, spec_version_at, MetadataHistory};
+use crate::rpc_spec::SpecClient;
+use crate::{Error, Result, SubstrateAdapter};
+use parking_lot::RwLock;
+use std::sync::Arc;
+use subxt::utils::H256;
+use subxt::Metadata;
+use tracing::debug;
+
+/// Blocks `from..=to` produced by runtime `spec_version`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpecRange {
+    /// First block known to be of this version
+    pub from: u64,
+    /// Last block known to be of this version
+    pub to: u64,
+    /// Runtime spec version
+    pub spec_version: u32,
+}
+
+impl SpecRange {
+    /// Whether block `number` falls in the range
+    pub fn contains(&self, number: u64) -> bool {
+        (self.from..=self.to).contains(&number)
+    }
+}
+
+/// Picks the metadata to decode each block with
+///
+/// Clones share the known ranges and the metadata cache.
+#[derive(Clone, Default)]
+pub struct DecodeRouter {
+    history: MetadataHistory,
+    ranges: Arc<RwLock<Vec<SpecRange>>>,
+}
+
+impl DecodeRouter {
+    /// Router without known ranges or metadata
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Share `history` as the metadata cache
+    pub fn with_history(mut self, history: MetadataHistory) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// The metadata cache
+    pub fn history(&self) -> &MetadataHistory {
+        &self.history
+    }
+
+    /// Known ranges, in block order
+    pub fn ranges(&self) -> Vec<SpecRange> {
+        self.ranges.read().clone()
+    }
+
+    /// Spec version of block `number`, if it falls in a known range
+    pub fn spec_version_of(&self, number: u64) -> Option<u32> {
+        self.ranges
+            .read()
+            .iter()
+            .find(|range| range.contains(number))
+            .map(|range| range.spec_version)
+    }
+
+    /// Record that block `number` was produced by `spec_version`
+    ///
+    /// Fails if that contradicts the known ranges, which only happens when
+    /// the node reports versions out of order.
+    pub fn record(&self, number: u64, spec_version: u32) -> Result<()> {
+        let mut ranges = self.ranges.write();
+        let conflict = ranges.iter().any(|range| {
+            (range.spec_version < spec_version && range.to >= number)
+                || (range.spec_version > spec_version && range.from <= number)
+        });
+        if conflict {
+            return Err(Error::Metadata(format!(
+                "Block {} reported spec version {}, which contradicts known ranges {:?}",
+                number, spec_version, ranges
+            )));
+        }
+        match ranges
+            .iter_mut()
+            .find(|range| range.spec_version == spec_version)
+        {
+            Some(range) => {
+                range.from = range.from.min(number);
+                range.to = range.to.max(number);
+            }
+            None => {
+                ranges.push(SpecRange {
+                    from: number,
+                    to: number,
+                    spec_version,
+                });
+                ranges.sort_by_key(|range| range.from);
+            }
+        }
+        Ok(())
+    }
+
+    /// Ranges of spec versions covering blocks `from..=to`
+    ///
+    /// Resolves every upgrade boundary in between, so decoding the blocks
+    /// afterwards needs no further version lookups.
+    pub async fn plan(
+        &self,
+        adapter: &SubstrateAdapter,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<SpecRange>> {
+        if from > to {
+            return Err(Error::Other(format!(
+                "Invalid block range: {} is after {}",
+                from, to
+            )));
+        }
+        let spec = adapter.spec_client();
+        let mut pending = vec![(from, to)];
+        while let Some((start, end)) = pending.pop() {
+            let first = self.spec_version_for(&spec, start).await?;
+            let last = self.spec_version_for(&spec, end).await?;
+            if first != last && end - start > 1 {
+                let middle = start + (end - start) / 2;
+                pending.push((middle, end));
+                pending.push((start, middle));
+            }
+        }
+
+        Ok(self
+            .ranges()
+            .into_iter()
+            .filter(|range| range.to >= from && range.from <= to)
+            .map(|range| SpecRange {
+                from: range.from.max(from),
+                to: range.to.min(to),
+                spec_version: range.spec_version,
+            })
+            .collect())
+    }
+
+    /// Spec version and metadata to decode block `number` with
+    pub async fn metadata_for(
+        &self,
+        adapter: &SubstrateAdapter,
+        number: u64,
+    ) -> Result<(u32, Metadata)> {
+        let spec = adapter.spec_client();
+        let hash = block_hash(&spec, number).await?;
+        self.metadata_at(&spec, number, hash).await
+    }
+
+    /// Events of block `number`, decoded with the metadata of its runtime
+    pub async fn events(
+        &self,
+        adapter: &SubstrateAdapter,
+        number: u64,
+    ) -> Result<Vec<MatchedEvent>> {
+        let spec = adapter.spec_client();
+        let hash = block_hash(&spec, number).await?;
+        self.events_at(&spec, number, hash).await
+    }
+
+    /// Events of block `number` with hash `hash`
+    pub(crate) async fn events_at(
+        &self,
+        spec: &SpecClient,
+        number: u64,
+        hash: H256,
+    ) -> Result<Vec<MatchedEvent>> {
+        let (_, metadata) = self.metadata_at(spec, number, hash).await?;
+        events_at(spec, number, hash, metadata).await
+    }
+
+    async fn metadata_at(
+        &self,
+        spec: &SpecClient,
+        number: u64,
+        hash: H256,
+    ) -> Result<(u32, Metadata)> {
+        let spec_version = match self.spec_version_of(number) {
+            Some(spec_version) => spec_version,
+            None => {
+                let spec_version = spec_version_at(spec, hash).await?;
+                self.record(number, spec_version)?;
+                spec_version
+            }
+        };
+        let metadata = self.history.metadata_of(spec, spec_version, hash).await?;
+        Ok((spec_version, metadata))
+    }
+
+    async fn spec_version_for(&self, spec: &SpecClient, number: u64) -> Result<u32> {
+        if let Some(spec_version) = self.spec_version_of(number) {
+            return Ok(spec_version);
+        }
+        let spec_version = spec_version_at(spec, block_hash(spec, number).await?).await?;
+        debug!("Block {} has spec version {}", number, spec_version);
+        self.record(number, spec_version)?;
+        Ok(spec_version)
+    }
+}
+
+impl std::fmt::Debug for DecodeRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DecodeRouter")
+            .field("ranges", &self.ranges())
+            .field("history", &self.history)
+            .finish()
+    }
+}
+
+async fn block_hash(spec: &SpecClient, number: u64) -> Result<H256> {
+    spec.block_hash(number)
+        .await?
+        .ok_or_else(|| Error::Storage(format!("Block {} not found", number)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_merges_ranges() {
+        let router = DecodeRouter::new();
+        router.record(100, 9_420).unwrap();
+        router.record(300, 9_430).unwrap();
+        router.record(150, 9_420).unwrap();
+        router.record(200, 9_430).unwrap();
+
+        assert_eq!(
+            router.ranges(),
+            vec![
+                SpecRange {
+                    from: 100,
+                    to: 150,
+                    spec_version: 9_420
+                },
+                SpecRange {
+                    from: 200,
+                    to: 300,
+                    spec_version: 9_430
+                },
+            ]
+        );
+        assert_eq!(router.spec_version_of(120), Some(9_420));
+        assert_eq!(router.spec_version_of(250), Some(9_430));
+        // the upgrade happened somewhere in between
+        assert_eq!(router.spec_version_of(175), None);
+
+        // versions never go back
+        assert!(router.record(250, 9_420).is_err());
+        assert!(router.record(120, 9_430).is_err());
+        assert_eq!(router.ranges().len(), 2);
+    }
+}
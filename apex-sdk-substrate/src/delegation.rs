@@ -0,0 +1,257 @@
+//! Conviction voting delegation graphs
+//!
+//! In OpenGov an account delegates its votes per track to a single target,
+//! which votes with its own balance plus everything delegated to it.
+//! Delegation is one level deep: votes delegated to an account that is itself
+//! delegating are not passed on, and count for nothing until it votes
+//! directly again. [`DelegationGraph`] rebuilds who delegates to whom on a
+//! track from `ConvictionVoting::VotingFor`, and reports the delegated voting
+//! power per delegate, including such stranded delegations.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::delegation::DelegationExplorer;
+//! use apex_sdk_substrate::SubstrateAdapter;
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let graph = DelegationExplorer::new(adapter).graph(0).await?;
+//! for delegate in graph.delegates().iter().take(10) {
+//!     println!("{}: {} votes from {} delegators", delegate.account, delegate.votes, delegate.delegators);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::receipt::json_u128;
+use crate::referenda::Conviction;
+use crate::{Result, SubstrateAdapter};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+
+/// Offset of the account in a `VotingFor` key, after the prefix and its hash
+const ACCOUNT_OFFSET: usize = 32 + 8;
+
+/// Offset of the track in a `VotingFor` key, after the account and its hash
+const TRACK_OFFSET: usize = ACCOUNT_OFFSET + 32 + 8;
+
+/// One account delegating its votes on a track
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delegation {
+    /// Delegating account as hex
+    pub delegator: String,
+    /// Account delegated to, as hex
+    pub target: String,
+    /// Balance delegated
+    pub balance: u128,
+    /// Conviction of the delegation
+    pub conviction: Conviction,
+}
+
+impl Delegation {
+    /// Votes the delegation carries
+    pub fn votes(&self) -> u128 {
+        self.conviction.votes(self.balance)
+    }
+
+    /// Delegation from a `VotingFor` key and decoded `Voting`; `None` when casting
+    ///
+    /// Returns the track with the delegation.
+    pub fn from_entry(key: &[u8], voting: &JsonValue) -> Option<(u16, Self)> {
+        let delegator = key.get(ACCOUNT_OFFSET..ACCOUNT_OFFSET + 32)?;
+        let track = key.get(TRACK_OFFSET..TRACK_OFFSET + 2)?;
+        let delegating = voting.get("Delegating")?;
+        let delegation = Self {
+            delegator: format!("0x{}", hex::encode(delegator)),
+            target: delegating.get("target")?.as_str()?.to_string(),
+            balance: json_u128(delegating.get("balance")?)?,
+            conviction: Conviction::from_name(delegating.get("conviction")?.as_str()?)?,
+        };
+        Some((u16::from_le_bytes([track[0], track[1]]), delegation))
+    }
+}
+
+/// Delegated voting power received by one account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delegate {
+    /// Delegate account as hex
+    pub account: String,
+    /// Number of accounts delegating to it
+    pub delegators: usize,
+    /// Conviction-weighted votes delegated
+    pub votes: u128,
+    /// Balance delegated, without conviction
+    pub capital: u128,
+    /// Whether the delegate is itself delegating, stranding these votes
+    pub stranded: bool,
+}
+
+/// Who delegates to whom on one track
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelegationGraph {
+    /// Track id
+    pub track: u16,
+    /// Every delegation on the track
+    pub delegations: Vec<Delegation>,
+}
+
+impl DelegationGraph {
+    /// Delegates ordered by delegated votes, largest first
+    pub fn delegates(&self) -> Vec<Delegate> {
+        let mut delegates: BTreeMap<&str, Delegate> = BTreeMap::new();
+        for delegation in &self.delegations {
+            let delegate = delegates
+                .entry(delegation.target.as_str())
+                .or_insert_with(|| Delegate {
+                    account: delegation.target.clone(),
+                    delegators: 0,
+                    votes: 0,
+                    capital: 0,
+                    stranded: self.delegate_of(&delegation.target).is_some(),
+                });
+            delegate.delegators += 1;
+            delegate.votes = delegate.votes.saturating_add(delegation.votes());
+            delegate.capital = delegate.capital.saturating_add(delegation.balance);
+        }
+        let mut delegates: Vec<Delegate> = delegates.into_values().collect();
+        delegates.sort_by(|a, b| {
+            b.votes
+                .cmp(&a.votes)
+                .then_with(|| a.account.cmp(&b.account))
+        });
+        delegates
+    }
+
+    /// Delegations received by an account (hex)
+    pub fn delegators_of<'b>(&'b self, account: &'b str) -> impl Iterator<Item = &'b Delegation> {
+        self.delegations
+            .iter()
+            .filter(move |delegation| delegation.target.eq_ignore_ascii_case(account))
+    }
+
+    /// The delegation an account (hex) made, if any
+    pub fn delegate_of(&self, account: &str) -> Option<&Delegation> {
+        self.delegations
+            .iter()
+            .find(|delegation| delegation.delegator.eq_ignore_ascii_case(account))
+    }
+
+    /// Delegations to accounts that are themselves delegating
+    pub fn stranded(&self) -> impl Iterator<Item = &Delegation> {
+        self.delegations
+            .iter()
+            .filter(|delegation| self.delegate_of(&delegation.target).is_some())
+    }
+
+    /// Total delegated votes on the track
+    pub fn total_votes(&self) -> u128 {
+        self.delegations.iter().map(Delegation::votes).sum()
+    }
+}
+
+/// Builds delegation graphs for a connected chain
+pub struct DelegationExplorer<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> DelegationExplorer<'a> {
+    /// Query through the adapter's connection
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Delegation graph of one track
+    pub async fn graph(&self, track: u16) -> Result<DelegationGraph> {
+        let mut graphs = self.graphs().await?;
+        Ok(graphs.remove(&track).unwrap_or(DelegationGraph {
+            track,
+            delegations: Vec::new(),
+        }))
+    }
+
+    /// Delegation graphs of every track with delegations
+    ///
+    /// Reads all of `ConvictionVoting::VotingFor`, since it is keyed by
+    /// account before track.
+    pub async fn graphs(&self) -> Result<BTreeMap<u16, DelegationGraph>> {
+        let entries = self
+            .adapter
+            .storage()
+            .iter_storage_json("ConvictionVoting", "VotingFor", vec![])
+            .await?;
+        let mut graphs: BTreeMap<u16, DelegationGraph> = BTreeMap::new();
+        for (track, delegation) in entries
+            .iter()
+            .filter_map(|(key, voting)| Delegation::from_entry(key, voting))
+        {
+            graphs
+                .entry(track)
+                .or_insert_with(|| DelegationGraph {
+                    track,
+                    delegations: Vec::new(),
+                })
+                .delegations
+                .push(delegation);
+        }
+        Ok(graphs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key(account: u8, track: u16) -> Vec<u8> {
+        let mut key = vec![0u8; ACCOUNT_OFFSET];
+        key.extend([account; 32]);
+        key.extend([0u8; 8]);
+        key.extend(track.to_le_bytes());
+        key
+    }
+
+    fn delegating(target: u8, balance: u64, conviction: &str) -> JsonValue {
+        json!({"Delegating": {
+            "balance": balance,
+            "target": format!("0x{}", hex::encode([target; 32])),
+            "conviction": conviction,
+            "delegations": {"votes": 0, "capital": 0},
+            "prior": [0, 0],
+        }})
+    }
+
+    #[test]
+    fn test_delegation_graph() {
+        let casting = json!({"Casting": {"votes": [], "delegations": {"votes": 0, "capital": 0}}});
+        assert!(Delegation::from_entry(&key(1, 0), &casting).is_none());
+
+        let entries = [
+            (key(1, 2), delegating(9, 100, "Locked2x")),
+            (key(2, 2), delegating(9, 50, "None")),
+            (key(3, 2), delegating(1, 10, "Locked1x")),
+        ];
+        let delegations: Vec<Delegation> = entries
+            .iter()
+            .map(|(key, voting)| {
+                let (track, delegation) = Delegation::from_entry(key, voting).unwrap();
+                assert_eq!(track, 2);
+                delegation
+            })
+            .collect();
+        let graph = DelegationGraph {
+            track: 2,
+            delegations,
+        };
+
+        let delegates = graph.delegates();
+        assert_eq!(delegates.len(), 2);
+        assert_eq!(delegates[0].account, format!("0x{}", "09".repeat(32)));
+        assert_eq!((delegates[0].delegators, delegates[0].votes), (2, 205));
+        assert_eq!(delegates[0].capital, 150);
+        assert!(!delegates[0].stranded);
+        assert!(delegates[1].stranded);
+
+        assert_eq!(graph.stranded().count(), 1);
+        assert_eq!(graph.delegators_of(&delegates[0].account).count(), 2);
+        assert_eq!(graph.total_votes(), 215);
+    }
+}
@@ -0,0 +1,254 @@
+//! Deterministic derivation of keyless accounts
+//!
+//! Several pallets control accounts that have no private key. Their ids are
+//! derived from public inputs, so custody tooling can compute deposit
+//! addresses before anything happens on chain:
+//!
+//! | Account | Inputs |
+//! |---------|--------|
+//! | [`multisig_account`] | sorted signatories and threshold |
+//! | [`derivative_account`] | owner and `Utility::as_derivative` index |
+//! | [`pure_proxy_account`] | spawner, proxy type, index and creation position |
+//! | [`pallet_account`] | 8-byte `PalletId`, e.g. `py/trsry` |
+//! | [`SovereignAccount`] | parachain id, relay or sibling |
+//!
+//! The derivations match the FRAME pallets: a BLAKE2-256 hash of the SCALE
+//! encoded inputs behind a fixed prefix.
+//!
+//! ```rust
+//! use apex_sdk_substrate::derivation::{derivative_account, multisig_account, ss58};
+//!
+//! let alice = [1u8; 32];
+//! let bob = [2u8; 32];
+//!
+//! let multisig = multisig_account(&[bob, alice], 2).unwrap();
+//! assert_eq!(multisig, multisig_account(&[alice, bob], 2).unwrap());
+//!
+//! let deposit = derivative_account(&multisig, 7);
+//! println!("deposit address: {}", ss58(&deposit, 0));
+//! ```
+
+use crate::event_query::{account_id, MatchedEvent};
+use crate::{Error, Result};
+use apex_sdk_core::block_number_u32;
+use parity_scale_codec::Encode;
+use serde::{Deserialize, Serialize};
+use sp_core::crypto::{AccountId32, Ss58AddressFormat, Ss58Codec};
+
+pub use crate::account::{SovereignAccount, SovereignKind};
+
+/// Entropy prefix of `Multisig` and `Utility::as_derivative` accounts
+const UTILITY_PREFIX: &[u8; 16] = b"modlpy/utilisuba";
+
+/// Entropy prefix of pure proxy accounts
+const PROXY_PREFIX: &[u8; 16] = b"modlpy/proxy____";
+
+/// Prefix of accounts owned by a pallet
+const PALLET_PREFIX: &[u8; 4] = b"modl";
+
+/// Account of a multisig with these signatories and threshold
+///
+/// Signatories are sorted and deduplicated first, as the pallet requires.
+pub fn multisig_account(signatories: &[[u8; 32]], threshold: u16) -> Result<[u8; 32]> {
+    let mut who = signatories.to_vec();
+    who.sort();
+    who.dedup();
+
+    if threshold == 0 || threshold as usize > who.len() {
+        return Err(Error::Other(format!(
+            "Invalid multisig threshold {} for {} signatories",
+            threshold,
+            who.len()
+        )));
+    }
+    Ok((UTILITY_PREFIX, who, threshold).using_encoded(sp_core::blake2_256))
+}
+
+/// Account dispatched from by `Utility::as_derivative(index, ..)` signed by `owner`
+pub fn derivative_account(owner: &[u8; 32], index: u16) -> [u8; 32] {
+    (UTILITY_PREFIX, owner, index).using_encoded(sp_core::blake2_256)
+}
+
+/// Account created by `Proxy::create_pure`
+///
+/// `proxy_type` is the variant index of the runtime's `ProxyType`; `height`
+/// and `extrinsic_index` locate the creating extrinsic.
+pub fn pure_proxy_account(
+    spawner: &[u8; 32],
+    proxy_type: u8,
+    index: u16,
+    height: u32,
+    extrinsic_index: u32,
+) -> [u8; 32] {
+    (
+        PROXY_PREFIX,
+        spawner,
+        height,
+        extrinsic_index,
+        proxy_type,
+        index,
+    )
+        .using_encoded(sp_core::blake2_256)
+}
+
+/// Account of a `PalletId`, e.g. `*b"py/trsry"` for the treasury
+pub fn pallet_account(pallet_id: &[u8; 8]) -> [u8; 32] {
+    let mut account = [0u8; 32];
+    account[..4].copy_from_slice(PALLET_PREFIX);
+    account[4..12].copy_from_slice(pallet_id);
+    account
+}
+
+/// SS58 address of an account id for a network prefix
+pub fn ss58(account: &[u8; 32], prefix: u16) -> String {
+    AccountId32::from(*account).to_ss58check_with_version(Ss58AddressFormat::custom(prefix))
+}
+
+/// A pure proxy as reported by its `Proxy::PureCreated` event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PureProxyCreation {
+    /// The new pure account
+    pub pure: [u8; 32],
+    /// Account that called `create_pure`
+    pub spawner: [u8; 32],
+    /// Proxy type name, e.g. `Any`
+    pub proxy_type: String,
+    /// Index passed to `create_pure`
+    pub disambiguation_index: u16,
+    /// Block containing the creation
+    pub block_number: u64,
+    /// Position of the creating extrinsic
+    pub extrinsic_index: Option<u32>,
+}
+
+impl PureProxyCreation {
+    /// Read a `PureCreated` (or legacy `AnonymousCreated`) event
+    pub fn from_event(event: &MatchedEvent) -> Result<Self> {
+        let invalid = |reason: &str| {
+            Error::Other(format!(
+                "{}.{} is not a pure proxy creation: {}",
+                event.pallet, event.variant, reason
+            ))
+        };
+        if event.pallet != "Proxy"
+            || !matches!(event.variant.as_str(), "PureCreated" | "AnonymousCreated")
+        {
+            return Err(invalid("unexpected event"));
+        }
+
+        let fields = &event.fields;
+        let account = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| fields[*name].as_str())
+                .ok_or_else(|| invalid(&format!("missing {}", names[0])))
+                .and_then(account_id)
+        };
+        let proxy_type = match &fields["proxy_type"] {
+            serde_json::Value::String(name) => name.clone(),
+            serde_json::Value::Object(variant) => {
+                variant.keys().next().cloned().unwrap_or_default()
+            }
+            _ => return Err(invalid("missing proxy_type")),
+        };
+        let disambiguation_index = fields["disambiguation_index"]
+            .as_u64()
+            .and_then(|index| u16::try_from(index).ok())
+            .ok_or_else(|| invalid("missing disambiguation_index"))?;
+
+        Ok(Self {
+            pure: account(&["pure", "anonymous"])?,
+            spawner: account(&["who"])?,
+            proxy_type,
+            disambiguation_index,
+            block_number: event.block_number,
+            extrinsic_index: event.extrinsic_index,
+        })
+    }
+
+    /// Recompute the pure account given the variant index of `proxy_type`
+    ///
+    /// Matches [`pure`](Self::pure) when the index is right, which lets the
+    /// caller confirm the runtime's `ProxyType` encoding.
+    pub fn derive(&self, proxy_type_index: u8) -> Result<[u8; 32]> {
+        let extrinsic_index = self
+            .extrinsic_index
+            .ok_or_else(|| Error::Other("Pure proxy created outside an extrinsic".to_string()))?;
+        let height = block_number_u32(self.block_number)?;
+        Ok(pure_proxy_account(
+            &self.spawner,
+            proxy_type_index,
+            self.disambiguation_index,
+            height,
+            extrinsic_index,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_multisig_account() {
+        let (alice, bob) = ([1u8; 32], [2u8; 32]);
+        let account = multisig_account(&[bob, alice, bob], 2).unwrap();
+        assert_eq!(
+            hex::encode(account),
+            "6fd5d80fd10c6bdf947ee5c83d64c6d6dc84ea591b44d1ea5ecafe6f1c505b0d"
+        );
+        assert_ne!(account, multisig_account(&[alice, bob], 1).unwrap());
+        assert!(multisig_account(&[alice, bob], 0).is_err());
+        assert!(multisig_account(&[alice, bob], 3).is_err());
+    }
+
+    #[test]
+    fn test_derived_accounts() {
+        let owner = [1u8; 32];
+        assert_eq!(
+            hex::encode(derivative_account(&owner, 0)),
+            "1a4dfb39d9805df468da8e070dad6bcc4b4d697339a072f7a1a03c0e12eb6c9d"
+        );
+        assert_eq!(
+            hex::encode(pure_proxy_account(&owner, 0, 0, 100, 2)),
+            "54ae8da8e75f30c79b3b99816e57be77216ad87eded28af200a00a3df92eb48e"
+        );
+
+        let treasury = pallet_account(b"py/trsry");
+        assert_eq!(&treasury[..12], b"modlpy/trsry");
+        assert!(treasury[12..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn test_pure_proxy_from_event() {
+        let spawner = [1u8; 32];
+        let pure = pure_proxy_account(&spawner, 0, 0, 100, 2);
+        let event = MatchedEvent {
+            block_number: 100,
+            block_hash: "0x00".to_string(),
+            event_index: 5,
+            extrinsic_index: Some(2),
+            pallet: "Proxy".to_string(),
+            variant: "PureCreated".to_string(),
+            fields: json!({
+                "pure": format!("0x{}", hex::encode(pure)),
+                "who": format!("0x{}", hex::encode(spawner)),
+                "proxy_type": "Any",
+                "disambiguation_index": 0,
+            }),
+        };
+
+        let creation = PureProxyCreation::from_event(&event).unwrap();
+        assert_eq!(creation.proxy_type, "Any");
+        assert_eq!(creation.derive(0).unwrap(), creation.pure);
+        assert_ne!(creation.derive(1).unwrap(), creation.pure);
+
+        let transfer = MatchedEvent {
+            pallet: "Balances".to_string(),
+            variant: "Transfer".to_string(),
+            ..event
+        };
+        assert!(PureProxyCreation::from_event(&transfer).is_err());
+    }
+}
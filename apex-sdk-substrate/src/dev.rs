@@ -0,0 +1,249 @@
+//! Developer mode for local and private chains
+//!
+//! Dev chains (`--dev` nodes, local testnets, zombienet networks) fund the
+//! well-known dev accounts derived from the public dev phrase and usually
+//! make Alice the sudo key. [`DevMode`] checks that the adapter is connected
+//! to such a chain, hands out the dev accounts as signers and mints tokens
+//! through sudo:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{DevAccount, DevMode, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let dev = DevMode::detect(adapter).await?;
+//! let bob = dev.account(DevAccount::Bob)?;
+//! dev.faucet(&bob.address(), 1_000_000_000_000).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The dev phrase is public; never fund dev accounts on a live network.
+
+use crate::event_query::account_id;
+use crate::sudo::{confirm_root_action, Sudo};
+use crate::wallet::{KeyPairType, Wallet};
+use crate::{Error, Result, SubstrateAdapter};
+use serde_json::Value as JsonValue;
+use subxt::dynamic::Value;
+use subxt::ext::subxt_rpcs::client::RpcParams;
+use tracing::{info, warn};
+
+/// Chain types reported by `system_chainType` that mark a dev chain
+const DEV_CHAIN_TYPES: &[&str] = &["Development", "Local"];
+
+/// Well-known accounts derived from the dev phrase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DevAccount {
+    Alice,
+    Bob,
+    Charlie,
+    Dave,
+    Eve,
+    Ferdie,
+}
+
+impl DevAccount {
+    /// All dev accounts, Alice first
+    pub const ALL: [DevAccount; 6] = [
+        DevAccount::Alice,
+        DevAccount::Bob,
+        DevAccount::Charlie,
+        DevAccount::Dave,
+        DevAccount::Eve,
+        DevAccount::Ferdie,
+    ];
+
+    /// Name used in the derivation path, e.g. `Alice`
+    pub fn name(&self) -> &'static str {
+        match self {
+            DevAccount::Alice => "Alice",
+            DevAccount::Bob => "Bob",
+            DevAccount::Charlie => "Charlie",
+            DevAccount::Dave => "Dave",
+            DevAccount::Eve => "Eve",
+            DevAccount::Ferdie => "Ferdie",
+        }
+    }
+
+    /// Look up a dev account by name, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|account| account.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Sr25519 wallet of this account, derived as `//Name` from the dev phrase
+    pub fn wallet(&self) -> Result<Wallet> {
+        Wallet::from_mnemonic_with_path(
+            sp_core::crypto::DEV_PHRASE,
+            Some(self.name()),
+            KeyPairType::Sr25519,
+        )
+    }
+}
+
+/// Whether a `system_chainType` response names a dev chain
+fn is_dev_chain_type(chain_type: &JsonValue) -> bool {
+    chain_type
+        .as_str()
+        .is_some_and(|chain_type| DEV_CHAIN_TYPES.contains(&chain_type))
+}
+
+/// Whether a chain name looks like a dev chain, for nodes without
+/// `system_chainType`
+fn is_dev_chain_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["development", "local", "dev"].iter().any(|marker| {
+        name.split(|c: char| !c.is_alphanumeric())
+            .any(|word| word == *marker)
+    })
+}
+
+/// Helpers for local development against a dev chain
+pub struct DevMode<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> DevMode<'a> {
+    /// Enable dev mode if the adapter is connected to a dev chain
+    ///
+    /// Fails on live chains, so dev helpers can't run against them by
+    /// accident.
+    pub async fn detect(adapter: &'a SubstrateAdapter) -> Result<Self> {
+        if !Self::is_dev_chain(adapter).await? {
+            return Err(Error::Other(
+                "Connected chain is not a development chain".to_string(),
+            ));
+        }
+        Ok(Self { adapter })
+    }
+
+    /// Whether the adapter is connected to a development or local chain
+    ///
+    /// Uses the node's chain type, falling back to the chain name for nodes
+    /// that don't report one.
+    pub async fn is_dev_chain(adapter: &SubstrateAdapter) -> Result<bool> {
+        let chain_type: std::result::Result<JsonValue, _> = adapter
+            .rpc_client()
+            .request("system_chainType", RpcParams::new())
+            .await;
+        match chain_type {
+            Ok(chain_type) => Ok(is_dev_chain_type(&chain_type)),
+            Err(e) => {
+                warn!(
+                    "system_chainType unavailable, checking the chain name: {}",
+                    e
+                );
+                let name: String = adapter
+                    .rpc_client()
+                    .request("system_chain", RpcParams::new())
+                    .await
+                    .map_err(|e| Error::Connection(format!("Failed to fetch chain name: {}", e)))?;
+                Ok(is_dev_chain_name(&name))
+            }
+        }
+    }
+
+    /// Wallet of one dev account
+    pub fn account(&self, account: DevAccount) -> Result<Wallet> {
+        account.wallet()
+    }
+
+    /// Wallets of all dev accounts, Alice first
+    pub fn accounts(&self) -> Result<Vec<Wallet>> {
+        DevAccount::ALL.iter().map(DevAccount::wallet).collect()
+    }
+
+    /// Dev account holding the chain's sudo key
+    pub async fn sudo_account(&self) -> Result<DevAccount> {
+        let key = Sudo::new(self.adapter)
+            .key()
+            .await?
+            .ok_or_else(|| Error::Other("Chain has no sudo key".to_string()))?;
+        for account in DevAccount::ALL {
+            if format!("0x{}", hex::encode(account.wallet()?.public_key())) == key {
+                return Ok(account);
+            }
+        }
+        Err(Error::Other(format!(
+            "Sudo key {} is not a dev account",
+            key
+        )))
+    }
+
+    /// Dispatch `call` with root origin, signed by the dev account holding
+    /// the sudo key
+    ///
+    /// Dev chains need no [`confirm_root_action`]; see [`Sudo::sudo`].
+    /// Returns the extrinsic hash; the call's own outcome is reported by the
+    /// `Sudo::Sudid` event.
+    pub async fn sudo(&self, call: Value) -> Result<String> {
+        self.adapter.require_pallet("Sudo")?;
+        let signer = self.sudo_account().await?.wallet()?;
+        Sudo::new(self.adapter)
+            .sudo(&signer, call, confirm_root_action("dev mode"))
+            .await
+    }
+
+    /// Mint `amount` to `address` by raising its free balance through sudo
+    ///
+    /// Uses `Balances::force_set_balance`, so no dev account's balance is
+    /// spent. Returns the extrinsic hash.
+    pub async fn faucet(&self, address: &str, amount: u128) -> Result<String> {
+        let who = account_id(address)?;
+        let free = self.adapter.storage().get_account_info(address).await?.free;
+        let new_free = free
+            .checked_add(amount)
+            .ok_or_else(|| Error::Other("Faucet amount overflows the balance".to_string()))?;
+        info!("Funding {} with {} units", address, amount);
+
+        let call = Value::unnamed_variant(
+            "Balances",
+            [Value::named_variant(
+                "force_set_balance",
+                [
+                    (
+                        "who",
+                        Value::unnamed_variant("Id", vec![Value::from_bytes(who)]),
+                    ),
+                    ("new_free", Value::u128(new_free)),
+                ],
+            )],
+        );
+        self.sudo(call).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_dev_account_addresses() {
+        assert_eq!(
+            DevAccount::Alice.wallet().unwrap().address(),
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+        );
+        assert_eq!(
+            DevAccount::Bob.wallet().unwrap().address(),
+            "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty"
+        );
+        assert_eq!(DevAccount::from_name("ferdie"), Some(DevAccount::Ferdie));
+        assert_eq!(DevAccount::from_name("Mallory"), None);
+    }
+
+    #[test]
+    fn test_dev_chain_detection() {
+        assert!(is_dev_chain_type(&json!("Development")));
+        assert!(is_dev_chain_type(&json!("Local")));
+        assert!(!is_dev_chain_type(&json!("Live")));
+        assert!(!is_dev_chain_type(&json!({ "Custom": "Development" })));
+
+        assert!(is_dev_chain_name("Development"));
+        assert!(is_dev_chain_name("Rococo Local Testnet"));
+        assert!(is_dev_chain_name("asset-hub-dev"));
+        assert!(!is_dev_chain_name("Polkadot"));
+        assert!(!is_dev_chain_name("Devnet Prime"));
+    }
+}
@@ -0,0 +1,743 @@
+//! Token swaps through the asset-conversion pallet
+//!
+//! [`Dex`] quotes prices with the runtime's `AssetConversionApi`, applies a
+//! slippage tolerance to the quote and submits
+//! `AssetConversion::swap_exact_tokens_for_tokens` or
+//! `swap_tokens_for_exact_tokens` through the adapter's executor, so its
+//! policy and audit log apply. [`SwapExecution`] decodes the
+//! `SwapExecuted` event of a finished swap.
+//!
+//! For analytics, [`Dex::reserves`] reads a pool's reserves,
+//! [`Dex::lp_position`] values an account's LP tokens against them and
+//! [`Dex::volume`] totals the swaps executed over a block range.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::assets::AssetId;
+//! use apex_sdk_substrate::Dex;
+//! # use apex_sdk_substrate::{SubstrateAdapter, Wallet};
+//!
+//! # async fn example(asset_hub: &SubstrateAdapter, wallet: &Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! let dex = Dex::new(asset_hub);
+//!
+//! // 10 DOT for USDT, accepting 0.5% less than quoted
+//! let quote = dex
+//!     .quote_exact_in(&AssetId::Native, &AssetId::Local(1984), 100_000_000_000)
+//!     .await?;
+//! println!("{} USDT at least", quote.min_out(50));
+//!
+//! let tx_hash = dex.swap_exact_in(wallet, &quote, 50).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Quotes include the pool fee and go through a single pool; routes over
+//! several pools are built with [`Dex::swap_exact_in_call`].
+
+use crate::assets::{AssetId, ASSET_HUB_ASSETS_PALLET};
+use crate::event_query::{account_id, value_to_json, EventQuery, MatchedEvent};
+use crate::receipt::{json_some, json_u128, ExtrinsicReceipt};
+use crate::xcm::MultiLocation;
+use crate::{Error, Result, SubstrateAdapter, Wallet};
+use serde_json::Value as JsonValue;
+use sp_core::U256;
+use subxt::dynamic::Value;
+use tracing::debug;
+
+/// Default slippage tolerance in basis points (0.5%)
+pub const DEFAULT_SLIPPAGE_BPS: u32 = 50;
+
+const BPS: u128 = 10_000;
+
+/// Smallest amount accepted when `amount` may slip by `bps` basis points
+pub fn min_with_slippage(amount: u128, bps: u32) -> u128 {
+    let keep = BPS.saturating_sub(bps as u128);
+    // split to avoid overflowing on large amounts
+    (amount / BPS) * keep + (amount % BPS) * keep / BPS
+}
+
+/// Largest amount paid when `amount` may slip by `bps` basis points
+///
+/// Rounds up, saturating at `u128::MAX`.
+pub fn max_with_slippage(amount: u128, bps: u32) -> u128 {
+    let extra = (amount / BPS) * bps as u128 + ((amount % BPS) * bps as u128).div_ceil(BPS);
+    amount.saturating_add(extra)
+}
+
+/// Price of a swap through one pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quote {
+    /// Asset paid
+    pub asset_in: AssetId,
+    /// Asset received
+    pub asset_out: AssetId,
+    /// Amount paid, in the smallest unit of `asset_in`
+    pub amount_in: u128,
+    /// Amount received, in the smallest unit of `asset_out`
+    pub amount_out: u128,
+}
+
+impl Quote {
+    /// Smallest output accepted with `bps` basis points of slippage
+    pub fn min_out(&self, bps: u32) -> u128 {
+        min_with_slippage(self.amount_out, bps)
+    }
+
+    /// Largest input paid with `bps` basis points of slippage
+    pub fn max_in(&self, bps: u32) -> u128 {
+        max_with_slippage(self.amount_in, bps)
+    }
+
+    /// Units of `asset_out` per unit of `asset_in`, ignoring decimals
+    pub fn price(&self) -> f64 {
+        if self.amount_in == 0 {
+            return 0.0;
+        }
+        self.amount_out as f64 / self.amount_in as f64
+    }
+}
+
+/// Result of a swap, decoded from `AssetConversion::SwapExecuted`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapExecution {
+    /// Account that paid (hex)
+    pub who: String,
+    /// Account credited with the output (hex)
+    pub send_to: String,
+    /// Amount paid
+    pub amount_in: u128,
+    /// Amount received
+    pub amount_out: u128,
+    /// Assets swapped through, from input to output
+    pub path: Vec<MultiLocation>,
+}
+
+impl SwapExecution {
+    /// Decode a `SwapExecuted` event; `None` for any other event
+    ///
+    /// Accepts both the `Vec<(Location, Balance)>` path of current runtimes
+    /// and the plain `Vec<Location>` of older ones.
+    pub fn from_event(event: &MatchedEvent) -> Option<Self> {
+        if event.pallet != "AssetConversion" || event.variant != "SwapExecuted" {
+            return None;
+        }
+        let fields = &event.fields;
+        let path = fields["path"]
+            .as_array()?
+            .iter()
+            .map(|step| match step {
+                JsonValue::Array(hop) => hop.first().and_then(MultiLocation::from_json),
+                location => MultiLocation::from_json(location),
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            who: fields["who"].as_str()?.to_string(),
+            send_to: fields["send_to"].as_str()?.to_string(),
+            amount_in: json_u128(&fields["amount_in"])?,
+            amount_out: json_u128(&fields["amount_out"])?,
+            path,
+        })
+    }
+
+    /// First swap executed by an extrinsic
+    pub fn from_receipt(receipt: &ExtrinsicReceipt) -> Option<Self> {
+        receipt.events.iter().find_map(Self::from_event)
+    }
+}
+
+/// Reserves of one pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolReserves {
+    /// First asset of the pair
+    pub asset1: AssetId,
+    /// Second asset of the pair
+    pub asset2: AssetId,
+    /// Pool balance of `asset1`
+    pub reserve1: u128,
+    /// Pool balance of `asset2`
+    pub reserve2: u128,
+}
+
+impl PoolReserves {
+    /// Spot price: units of `asset2` per unit of `asset1`, ignoring decimals
+    pub fn price(&self) -> f64 {
+        if self.reserve1 == 0 {
+            return 0.0;
+        }
+        self.reserve2 as f64 / self.reserve1 as f64
+    }
+
+    /// `amount` of `asset2` expressed in `asset1` at the spot price
+    pub fn in_asset1(&self, amount: u128) -> u128 {
+        mul_div(amount, self.reserve1, self.reserve2)
+    }
+}
+
+/// LP tokens held in one pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LpPosition {
+    /// Id of the LP token in `pallet-pool-assets`
+    pub lp_token: u32,
+    /// LP tokens held
+    pub balance: u128,
+    /// LP tokens issued
+    pub total_supply: u128,
+    /// Reserves the position is valued at
+    pub reserves: PoolReserves,
+}
+
+impl LpPosition {
+    /// Fraction of the pool owned, from 0 to 1
+    pub fn share(&self) -> f64 {
+        if self.total_supply == 0 {
+            return 0.0;
+        }
+        self.balance as f64 / self.total_supply as f64
+    }
+
+    /// Amounts of `asset1` and `asset2` the position would withdraw
+    pub fn amounts(&self) -> (u128, u128) {
+        (
+            mul_div(self.reserves.reserve1, self.balance, self.total_supply),
+            mul_div(self.reserves.reserve2, self.balance, self.total_supply),
+        )
+    }
+
+    /// Value of the position in `asset1` at the spot price
+    pub fn value_in_asset1(&self) -> u128 {
+        let (amount1, amount2) = self.amounts();
+        amount1.saturating_add(self.reserves.in_asset1(amount2))
+    }
+}
+
+/// Swaps between two assets over a block range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteVolume {
+    /// Asset paid
+    pub asset_in: MultiLocation,
+    /// Asset received
+    pub asset_out: MultiLocation,
+    /// Number of swaps
+    pub swaps: usize,
+    /// Total paid
+    pub amount_in: u128,
+    /// Total received
+    pub amount_out: u128,
+}
+
+/// Swap volume over a block range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapVolume {
+    /// First block scanned
+    pub from_block: u64,
+    /// Last block scanned
+    pub to_block: u64,
+    /// Totals per route, by first and last asset of the swap path, in
+    /// order of first appearance
+    pub routes: Vec<RouteVolume>,
+}
+
+impl SwapVolume {
+    /// Totals of `swaps` executed in blocks `from..=to`
+    pub fn from_swaps(from: u64, to: u64, swaps: &[SwapExecution]) -> Self {
+        let mut routes: Vec<RouteVolume> = Vec::new();
+        for swap in swaps {
+            let (Some(asset_in), Some(asset_out)) = (swap.path.first(), swap.path.last()) else {
+                continue;
+            };
+            let index = match routes
+                .iter()
+                .position(|route| route.asset_in == *asset_in && route.asset_out == *asset_out)
+            {
+                Some(index) => index,
+                None => {
+                    routes.push(RouteVolume {
+                        asset_in: asset_in.clone(),
+                        asset_out: asset_out.clone(),
+                        swaps: 0,
+                        amount_in: 0,
+                        amount_out: 0,
+                    });
+                    routes.len() - 1
+                }
+            };
+            let route = &mut routes[index];
+            route.swaps += 1;
+            route.amount_in = route.amount_in.saturating_add(swap.amount_in);
+            route.amount_out = route.amount_out.saturating_add(swap.amount_out);
+        }
+        Self {
+            from_block: from,
+            to_block: to,
+            routes,
+        }
+    }
+
+    /// Number of swaps over all routes
+    pub fn swaps(&self) -> usize {
+        self.routes.iter().map(|route| route.swaps).sum()
+    }
+
+    /// Total paid in `asset` over all routes starting with it
+    pub fn volume_in(&self, asset: &MultiLocation) -> u128 {
+        self.routes
+            .iter()
+            .filter(|route| route.asset_in == *asset)
+            .fold(0u128, |total, route| total.saturating_add(route.amount_in))
+    }
+}
+
+/// `a * b / c` without intermediate overflow; 0 when `c` is 0
+fn mul_div(a: u128, b: u128, c: u128) -> u128 {
+    if c == 0 {
+        return 0;
+    }
+    let result = U256::from(a) * U256::from(b) / U256::from(c);
+    u128::try_from(result).unwrap_or(u128::MAX)
+}
+
+/// Quotes and swaps on a chain with the asset-conversion pallet
+pub struct Dex<'a> {
+    adapter: &'a SubstrateAdapter,
+    assets_pallet: u8,
+    native: MultiLocation,
+}
+
+impl<'a> Dex<'a> {
+    /// Dex on an AssetHub-like chain whose native token is the relay token
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self {
+            adapter,
+            assets_pallet: ASSET_HUB_ASSETS_PALLET,
+            native: MultiLocation::parent(),
+        }
+    }
+
+    /// Index of `pallet-assets`, used to locate [`AssetId::Local`] assets
+    pub fn with_assets_pallet(mut self, index: u8) -> Self {
+        self.assets_pallet = index;
+        self
+    }
+
+    /// Location the pallet uses for the native token
+    ///
+    /// Defaults to the parent, as on system chains.
+    pub fn with_native_location(mut self, location: MultiLocation) -> Self {
+        self.native = location;
+        self
+    }
+
+    /// Location of `asset` as the pallet expects it
+    pub fn location(&self, asset: &AssetId) -> MultiLocation {
+        match asset {
+            AssetId::Native => self.native.clone(),
+            asset => asset.to_location(self.assets_pallet),
+        }
+    }
+
+    /// Asset at a location reported by the pallet
+    pub fn asset(&self, location: &MultiLocation) -> AssetId {
+        if *location == self.native {
+            AssetId::Native
+        } else {
+            AssetId::from_location(location, self.assets_pallet)
+        }
+    }
+
+    /// Output of swapping exactly `amount_in` of `asset_in`
+    pub async fn quote_exact_in(
+        &self,
+        asset_in: &AssetId,
+        asset_out: &AssetId,
+        amount_in: u128,
+    ) -> Result<Quote> {
+        let amount_out = self
+            .quote(
+                "quote_price_exact_tokens_for_tokens",
+                asset_in,
+                asset_out,
+                amount_in,
+            )
+            .await?;
+        Ok(Quote {
+            asset_in: asset_in.clone(),
+            asset_out: asset_out.clone(),
+            amount_in,
+            amount_out,
+        })
+    }
+
+    /// Input needed to receive exactly `amount_out` of `asset_out`
+    pub async fn quote_exact_out(
+        &self,
+        asset_in: &AssetId,
+        asset_out: &AssetId,
+        amount_out: u128,
+    ) -> Result<Quote> {
+        let amount_in = self
+            .quote(
+                "quote_price_tokens_for_exact_tokens",
+                asset_in,
+                asset_out,
+                amount_out,
+            )
+            .await?;
+        Ok(Quote {
+            asset_in: asset_in.clone(),
+            asset_out: asset_out.clone(),
+            amount_in,
+            amount_out,
+        })
+    }
+
+    async fn quote(
+        &self,
+        method: &str,
+        asset_in: &AssetId,
+        asset_out: &AssetId,
+        amount: u128,
+    ) -> Result<u128> {
+        debug!("Quoting {} of {:?} for {:?}", amount, asset_in, asset_out);
+        let args = vec![
+            self.location(asset_in).to_value()?,
+            self.location(asset_out).to_value()?,
+            Value::u128(amount),
+            Value::bool(true),
+        ];
+        let quote = self.runtime_api(method, args).await?;
+        json_some(&quote).and_then(json_u128).ok_or_else(|| {
+            Error::Transaction(format!(
+                "No pool for {:?} and {:?} or not enough liquidity",
+                asset_in, asset_out
+            ))
+        })
+    }
+
+    /// Reserves of the pool of `asset1` and `asset2`
+    pub async fn reserves(&self, asset1: &AssetId, asset2: &AssetId) -> Result<PoolReserves> {
+        let args = vec![
+            self.location(asset1).to_value()?,
+            self.location(asset2).to_value()?,
+        ];
+        let reserves = self.runtime_api("get_reserves", args).await?;
+        let (reserve1, reserve2) = json_some(&reserves)
+            .and_then(|reserves| Some((json_u128(&reserves[0])?, json_u128(&reserves[1])?)))
+            .ok_or_else(|| Error::Storage(format!("No pool for {:?} and {:?}", asset1, asset2)))?;
+        Ok(PoolReserves {
+            asset1: asset1.clone(),
+            asset2: asset2.clone(),
+            reserve1,
+            reserve2,
+        })
+    }
+
+    /// Id of the LP token of the pool of `asset1` and `asset2`
+    pub async fn lp_token(&self, asset1: &AssetId, asset2: &AssetId) -> Result<u32> {
+        let storage = self.adapter.storage();
+        // pool ids are ordered by the runtime; try both
+        for (first, second) in [(asset1, asset2), (asset2, asset1)] {
+            let key = Value::unnamed_composite([
+                self.location(first).to_value()?,
+                self.location(second).to_value()?,
+            ]);
+            if let Some(pool) = storage
+                .query_storage_json("AssetConversion", "Pools", vec![key])
+                .await?
+            {
+                return json_u128(&pool["lp_token"])
+                    .and_then(|id| u32::try_from(id).ok())
+                    .ok_or_else(|| Error::Storage(format!("Unexpected pool info: {}", pool)));
+            }
+        }
+        Err(Error::Storage(format!(
+            "No pool for {:?} and {:?}",
+            asset1, asset2
+        )))
+    }
+
+    /// `address`'s LP tokens in the pool of `asset1` and `asset2`, valued
+    /// at the current reserves
+    pub async fn lp_position(
+        &self,
+        address: &str,
+        asset1: &AssetId,
+        asset2: &AssetId,
+    ) -> Result<LpPosition> {
+        let lp_token = self.lp_token(asset1, asset2).await?;
+        let reserves = self.reserves(asset1, asset2).await?;
+        let storage = self.adapter.storage();
+
+        let balance = storage
+            .query_storage_json(
+                "PoolAssets",
+                "Account",
+                vec![
+                    Value::u128(lp_token as u128),
+                    Value::from_bytes(account_id(address)?),
+                ],
+            )
+            .await?
+            .and_then(|account| json_u128(&account["balance"]))
+            .unwrap_or(0);
+        let total_supply = storage
+            .query_storage_json("PoolAssets", "Asset", vec![Value::u128(lp_token as u128)])
+            .await?
+            .and_then(|details| json_u128(&details["supply"]))
+            .unwrap_or(0);
+
+        Ok(LpPosition {
+            lp_token,
+            balance,
+            total_supply,
+            reserves,
+        })
+    }
+
+    /// Swaps executed in blocks `from..=to`, totalled per route
+    pub async fn volume(&self, from: u64, to: u64) -> Result<SwapVolume> {
+        let events = EventQuery::new()
+            .pallet("AssetConversion")
+            .variant("SwapExecuted")
+            .between(from, to)
+            .run(self.adapter)
+            .await?;
+        let swaps = events
+            .iter()
+            .filter_map(SwapExecution::from_event)
+            .collect::<Vec<_>>();
+        Ok(SwapVolume::from_swaps(from, to, &swaps))
+    }
+
+    async fn runtime_api(&self, method: &str, args: Vec<Value>) -> Result<JsonValue> {
+        let value = self
+            .adapter
+            .client()
+            .runtime_api()
+            .at_latest()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get latest block: {}", e)))?
+            .call(subxt::dynamic::runtime_api_call(
+                "AssetConversionApi",
+                method,
+                args,
+            ))
+            .await
+            .map_err(|e| {
+                Error::Transaction(format!(
+                    "Failed to call AssetConversionApi_{}: {}",
+                    method, e
+                ))
+            })?
+            .to_value()
+            .map_err(|e| {
+                Error::Transaction(format!("Failed to decode {} result: {}", method, e))
+            })?;
+        Ok(value_to_json(&value))
+    }
+
+    /// `swap_exact_tokens_for_tokens` along `path`, crediting `send_to`
+    pub fn swap_exact_in_call(
+        &self,
+        path: &[AssetId],
+        amount_in: u128,
+        amount_out_min: u128,
+        send_to: &str,
+    ) -> Result<subxt::tx::DynamicPayload> {
+        let args = self.swap_args(path, amount_in, amount_out_min, send_to)?;
+        Ok(subxt::dynamic::tx(
+            "AssetConversion",
+            "swap_exact_tokens_for_tokens",
+            args,
+        ))
+    }
+
+    /// `swap_tokens_for_exact_tokens` along `path`, crediting `send_to`
+    pub fn swap_exact_out_call(
+        &self,
+        path: &[AssetId],
+        amount_out: u128,
+        amount_in_max: u128,
+        send_to: &str,
+    ) -> Result<subxt::tx::DynamicPayload> {
+        let args = self.swap_args(path, amount_out, amount_in_max, send_to)?;
+        Ok(subxt::dynamic::tx(
+            "AssetConversion",
+            "swap_tokens_for_exact_tokens",
+            args,
+        ))
+    }
+
+    /// Arguments shared by both swap calls: path, exact amount, bound,
+    /// recipient and `keep_alive`
+    fn swap_args(
+        &self,
+        path: &[AssetId],
+        amount: u128,
+        bound: u128,
+        send_to: &str,
+    ) -> Result<Vec<Value>> {
+        Ok(vec![
+            self.path_value(path)?,
+            Value::u128(amount),
+            Value::u128(bound),
+            Value::from_bytes(account_id(send_to)?),
+            Value::bool(true),
+        ])
+    }
+
+    fn path_value(&self, path: &[AssetId]) -> Result<Value> {
+        if path.len() < 2 {
+            return Err(Error::Transaction(
+                "Swap path needs at least two assets".to_string(),
+            ));
+        }
+        let locations = path
+            .iter()
+            .map(|asset| self.location(asset).to_value())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Value::unnamed_composite(locations))
+    }
+
+    /// Swap exactly the quoted input for at least the quoted output less `bps`
+    ///
+    /// Keeps `wallet` alive; returns the transaction hash.
+    pub async fn swap_exact_in(&self, wallet: &Wallet, quote: &Quote, bps: u32) -> Result<String> {
+        let args = self.swap_args(
+            &[quote.asset_in.clone(), quote.asset_out.clone()],
+            quote.amount_in,
+            quote.min_out(bps),
+            &wallet.address(),
+        )?;
+        self.adapter
+            .transaction_executor()
+            .submit_call(
+                wallet,
+                "AssetConversion",
+                "swap_exact_tokens_for_tokens",
+                args,
+            )
+            .await
+    }
+
+    /// Swap at most the quoted input plus `bps` for exactly the quoted output
+    ///
+    /// Keeps `wallet` alive; returns the transaction hash.
+    pub async fn swap_exact_out(&self, wallet: &Wallet, quote: &Quote, bps: u32) -> Result<String> {
+        let args = self.swap_args(
+            &[quote.asset_in.clone(), quote.asset_out.clone()],
+            quote.amount_out,
+            quote.max_in(bps),
+            &wallet.address(),
+        )?;
+        self.adapter
+            .transaction_executor()
+            .submit_call(
+                wallet,
+                "AssetConversion",
+                "swap_tokens_for_exact_tokens",
+                args,
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xcm::Junction;
+
+    #[test]
+    fn test_slippage_bounds() {
+        assert_eq!(min_with_slippage(1_000_000, 50), 995_000);
+        assert_eq!(max_with_slippage(1_000_000, 50), 1_005_000);
+        assert_eq!(max_with_slippage(1, 50), 2);
+        assert_eq!(min_with_slippage(u128::MAX, 0), u128::MAX);
+        assert_eq!(max_with_slippage(u128::MAX, 50), u128::MAX);
+        assert_eq!(min_with_slippage(1_000, 20_000), 0);
+    }
+
+    #[test]
+    fn test_swap_execution_from_event() {
+        let who = format!("0x{}", "11".repeat(32));
+        let event = MatchedEvent {
+            block_number: 7,
+            block_hash: format!("0x{}", "00".repeat(32)),
+            event_index: 3,
+            extrinsic_index: Some(2),
+            pallet: "AssetConversion".to_string(),
+            variant: "SwapExecuted".to_string(),
+            fields: serde_json::json!({
+                "who": who,
+                "send_to": who,
+                "amount_in": "100000000000",
+                "amount_out": 51_230_000,
+                "path": [
+                    [{ "parents": 1, "interior": "Here" }, "100000000000"],
+                    [
+                        { "parents": 0, "interior": { "X2": [{ "PalletInstance": 50 }, { "GeneralIndex": 1984 }] } },
+                        51_230_000
+                    ]
+                ]
+            }),
+        };
+
+        let swap = SwapExecution::from_event(&event).unwrap();
+        assert_eq!(swap.amount_in, 100_000_000_000);
+        assert_eq!(swap.amount_out, 51_230_000);
+        assert_eq!(swap.path[0], MultiLocation::parent());
+        assert_eq!(
+            swap.path[1],
+            MultiLocation::new(
+                0,
+                vec![Junction::PalletInstance(50), Junction::GeneralIndex(1984)]
+            )
+        );
+        assert_eq!(
+            AssetId::from_location(&swap.path[1], ASSET_HUB_ASSETS_PALLET),
+            AssetId::Local(1984)
+        );
+    }
+
+    #[test]
+    fn test_lp_position_and_volume() {
+        let position = LpPosition {
+            lp_token: 4,
+            balance: 250,
+            total_supply: 1_000,
+            reserves: PoolReserves {
+                asset1: AssetId::Native,
+                asset2: AssetId::Local(1984),
+                reserve1: 4_000_000_000_000,
+                reserve2: 2_000_000_000,
+            },
+        };
+        assert_eq!(position.share(), 0.25);
+        assert_eq!(position.amounts(), (1_000_000_000_000, 500_000_000));
+        assert_eq!(position.value_in_asset1(), 2_000_000_000_000);
+        assert_eq!(mul_div(u128::MAX, 2, 4), u128::MAX / 2);
+
+        let usdt = MultiLocation::new(
+            0,
+            vec![Junction::PalletInstance(50), Junction::GeneralIndex(1984)],
+        );
+        let swap = |path: Vec<MultiLocation>, amount_in, amount_out| SwapExecution {
+            who: String::new(),
+            send_to: String::new(),
+            amount_in,
+            amount_out,
+            path,
+        };
+        let volume = SwapVolume::from_swaps(
+            1,
+            10,
+            &[
+                swap(vec![MultiLocation::parent(), usdt.clone()], 10, 5),
+                swap(vec![usdt.clone(), MultiLocation::parent()], 5, 9),
+                swap(vec![MultiLocation::parent(), usdt.clone()], 20, 10),
+            ],
+        );
+        assert_eq!(volume.swaps(), 3);
+        assert_eq!(volume.routes.len(), 2);
+        assert_eq!(volume.routes[0].amount_out, 15);
+        assert_eq!(volume.volume_in(&MultiLocation::parent()), 30);
+    }
+}
@@ -0,0 +1,263 @@
+//! GRANDPA and BABE equivocation reports
+//!
+//! An equivocation report pairs the SCALE-encoded equivocation proof, as
+//! gossiped by the offending authority, with a proof that the offending
+//! session key belongs to a validator. [`EquivocationReporter`] fetches the
+//! key ownership proof from the runtime API and submits the report, either
+//! unsigned or signed by a wallet:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{Consensus, EquivocationReporter, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter, proof: Vec<u8>) -> Result<(), apex_sdk_substrate::Error> {
+//! let reporter = EquivocationReporter::new(adapter);
+//! let report = reporter.prepare(Consensus::Babe, proof).await?;
+//! let tx_hash = reporter.submit_unsigned(&report).await?;
+//! println!("reported in {}", tx_hash);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Key ownership proofs can only be generated while the offending session
+//! (BABE) or authority set (GRANDPA) is current, so reports have to be made
+//! promptly.
+
+use crate::slash_monitor::Consensus;
+use crate::transaction::wait_for_finalized;
+use crate::wallet::Wallet;
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use subxt::dynamic::Value;
+use subxt::ext::scale_value;
+use subxt::Metadata;
+use tracing::info;
+
+/// An equivocation proof with the offender's key ownership proof
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EquivocationReport {
+    /// Consensus engine the equivocation happened in
+    pub consensus: Consensus,
+    /// GRANDPA authority set id or BABE slot of the equivocation
+    pub set_id_or_slot: u64,
+    /// Session key of the offending authority
+    pub offender: [u8; 32],
+    /// SCALE-encoded equivocation proof
+    pub equivocation_proof: Vec<u8>,
+    /// SCALE-encoded key ownership proof
+    pub key_owner_proof: Vec<u8>,
+}
+
+/// Builds and submits equivocation reports
+pub struct EquivocationReporter<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> EquivocationReporter<'a> {
+    /// Report through the adapter's connection
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Report for an encoded equivocation proof, with its key ownership proof
+    pub async fn prepare(
+        &self,
+        consensus: Consensus,
+        equivocation_proof: Vec<u8>,
+    ) -> Result<EquivocationReport> {
+        let (set_id_or_slot, offender) = proof_target(consensus, &equivocation_proof)?;
+        let key_owner_proof = self
+            .key_ownership_proof(consensus, set_id_or_slot, offender)
+            .await?
+            .ok_or_else(|| {
+                Error::Transaction(format!(
+                    "No key ownership proof for 0x{} at {}; the session has ended",
+                    hex::encode(offender),
+                    set_id_or_slot
+                ))
+            })?;
+        Ok(EquivocationReport {
+            consensus,
+            set_id_or_slot,
+            offender,
+            equivocation_proof,
+            key_owner_proof,
+        })
+    }
+
+    /// Proof that `authority` was a session key at `set_id_or_slot`
+    ///
+    /// `None` when the runtime can no longer prove it.
+    pub async fn key_ownership_proof(
+        &self,
+        consensus: Consensus,
+        set_id_or_slot: u64,
+        authority: [u8; 32],
+    ) -> Result<Option<Vec<u8>>> {
+        let method = match consensus {
+            Consensus::Grandpa => "GrandpaApi_generate_key_ownership_proof",
+            Consensus::Babe => "BabeApi_generate_key_ownership_proof",
+        };
+        let bytes = self
+            .adapter
+            .client()
+            .runtime_api()
+            .at_latest()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get latest block: {}", e)))?
+            .call_raw(method, Some(&(set_id_or_slot, authority).encode()))
+            .await
+            .map_err(|e| Error::Transaction(format!("Failed to call {}: {}", method, e)))?;
+        Option::<Vec<u8>>::decode(&mut &bytes[..])
+            .map_err(|e| Error::Encoding(format!("Invalid key ownership proof: {}", e)))
+    }
+
+    /// `report_equivocation` call, or `report_equivocation_unsigned` if `unsigned`
+    pub fn report_call(
+        &self,
+        report: &EquivocationReport,
+        unsigned: bool,
+    ) -> Result<subxt::tx::DynamicPayload> {
+        let call = if unsigned {
+            "report_equivocation_unsigned"
+        } else {
+            "report_equivocation"
+        };
+        let args = self.report_args(report, call)?;
+        Ok(subxt::dynamic::tx(pallet(report.consensus), call, args))
+    }
+
+    /// Submit the report as an unsigned extrinsic, which costs nothing
+    ///
+    /// The runtime only accepts it from the transaction pool while the report
+    /// is valid and not yet known.
+    pub async fn submit_unsigned(&self, report: &EquivocationReport) -> Result<String> {
+        let progress = self
+            .adapter
+            .client()
+            .tx()
+            .create_unsigned(&self.report_call(report, true)?)
+            .map_err(|e| Error::Transaction(format!("Failed to create unsigned tx: {}", e)))?
+            .submit_and_watch()
+            .await
+            .map_err(|e| Error::Transaction(format!("Failed to submit report: {}", e)))?;
+        info!(
+            "Submitted {:?} equivocation report for 0x{}",
+            report.consensus,
+            hex::encode(report.offender)
+        );
+        wait_for_finalized(progress).await
+    }
+
+    /// Submit the report signed by `wallet`
+    ///
+    /// Fees are refunded for valid reports.
+    pub async fn submit(&self, report: &EquivocationReport, wallet: &Wallet) -> Result<String> {
+        let args = self.report_args(report, "report_equivocation")?;
+        self.adapter
+            .transaction_executor()
+            .submit_call(
+                wallet,
+                pallet(report.consensus),
+                "report_equivocation",
+                args,
+            )
+            .await
+    }
+
+    fn report_args(&self, report: &EquivocationReport, call: &str) -> Result<Vec<Value>> {
+        let pallet = pallet(report.consensus);
+        self.adapter.require_pallet(pallet)?;
+        let metadata = self.adapter.client().metadata();
+        Ok(vec![
+            call_arg(&metadata, pallet, call, 0, &report.equivocation_proof)?,
+            call_arg(&metadata, pallet, call, 1, &report.key_owner_proof)?,
+        ])
+    }
+}
+
+fn pallet(consensus: Consensus) -> &'static str {
+    match consensus {
+        Consensus::Grandpa => "Grandpa",
+        Consensus::Babe => "Babe",
+    }
+}
+
+/// Authority set id or slot, and offender, from an encoded equivocation proof
+///
+/// BABE proofs start with the offender and the slot. GRANDPA proofs start
+/// with the set id, followed by the prevote or precommit equivocation with
+/// its round number and offender.
+pub fn proof_target(consensus: Consensus, proof: &[u8]) -> Result<(u64, [u8; 32])> {
+    let invalid = |e: parity_scale_codec::Error| {
+        Error::Encoding(format!("Invalid {:?} equivocation proof: {}", consensus, e))
+    };
+    let mut input = proof;
+    match consensus {
+        Consensus::Babe => {
+            let offender = <[u8; 32]>::decode(&mut input).map_err(invalid)?;
+            let slot = u64::decode(&mut input).map_err(invalid)?;
+            Ok((slot, offender))
+        }
+        Consensus::Grandpa => {
+            let set_id = u64::decode(&mut input).map_err(invalid)?;
+            let _kind = u8::decode(&mut input).map_err(invalid)?;
+            let _round = u64::decode(&mut input).map_err(invalid)?;
+            let offender = <[u8; 32]>::decode(&mut input).map_err(invalid)?;
+            Ok((set_id, offender))
+        }
+    }
+}
+
+/// Encoded argument as a dynamic value of the call's argument type
+fn call_arg(
+    metadata: &Metadata,
+    pallet: &str,
+    call: &str,
+    position: usize,
+    bytes: &[u8],
+) -> Result<Value> {
+    let ty = metadata
+        .pallet_by_name(pallet)
+        .and_then(|pallet| pallet.call_variant_by_name(call))
+        .and_then(|variant| variant.fields.get(position))
+        .map(|field| field.ty.id)
+        .ok_or_else(|| Error::Metadata(format!("Runtime has no {}::{}", pallet, call)))?;
+    let mut input = bytes;
+    let value = scale_value::scale::decode_as_type(&mut input, ty, metadata.types())
+        .map_err(|e| Error::Encoding(format!("Invalid {}::{} argument: {}", pallet, call, e)))?;
+    if !input.is_empty() {
+        return Err(Error::Encoding(format!(
+            "Invalid {}::{} argument: {} trailing bytes",
+            pallet,
+            call,
+            input.len()
+        )));
+    }
+    Ok(value.remove_context())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proof_target() {
+        let offender = [0x88; 32];
+        let mut babe = (offender, 290_000_123u64).encode();
+        babe.extend([0u8; 64]);
+        assert_eq!(
+            proof_target(Consensus::Babe, &babe).unwrap(),
+            (290_000_123, offender)
+        );
+
+        // set id, precommit variant, round, identity, then the two votes
+        let mut grandpa = (3_140u64, 1u8, 27_500u64, offender).encode();
+        grandpa.extend([0u8; 200]);
+        assert_eq!(
+            proof_target(Consensus::Grandpa, &grandpa).unwrap(),
+            (3_140, offender)
+        );
+        assert!(proof_target(Consensus::Grandpa, &[0u8; 20]).is_err());
+    }
+}
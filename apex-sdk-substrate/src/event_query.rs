@@ -0,0 +1,655 @@
+//! Structured event queries across block ranges
+//!
+//! [`EventQuery`] answers the most common analytic question — "which events of
+//! this kind happened in these blocks, optionally involving this account?" —
+//! in one call:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{EventQuery, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let transfers = EventQuery::new()
+//!     .pallet("Balances")
+//!     .variant("Transfer")
+//!     .involving("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5")
+//!     .between(19_000_000, 19_000_100)
+//!     .run(adapter)
+//!     .await?;
+//!
+//! for event in transfers {
+//!     println!("#{} {}::{} {}", event.block_number, event.pallet, event.variant, event.fields);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Blocks are resolved through `chain_getBlockHash` and fetched concurrently.
+//! Decoded events are keyed by block hash, so attaching a [`Cache`] makes
+//! repeated or overlapping queries skip blocks that were already decoded.
+//!
+//! For long ranges, attach an indexer with [`EventQuery::with_history`]: blocks
+//! the indexer has processed are answered by it, newer blocks still come from
+//! the node. [`SubstrateAdapter`] itself implements [`EventHistory`], so it can
+//! also serve as the live half of a [`HybridHistory`](apex_sdk_core::HybridHistory).
+
+use crate::cache::Cache;
+use crate::{Error, Result, SubstrateAdapter};
+use apex_sdk_core::history::{split_range, EventFilter, EventHistory};
+use apex_sdk_core::sink::EventRecord;
+use apex_sdk_core::SdkError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+use subxt::backend::rpc::RpcClient;
+use subxt::events::{Events, Phase};
+use subxt::ext::scale_value::{Composite, Primitive, Value, ValueDef};
+use subxt::ext::subxt_rpcs::client::RpcParams;
+use subxt::utils::H256;
+use subxt::{OnlineClient, PolkadotConfig};
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+
+/// Default number of blocks fetched concurrently
+pub const DEFAULT_QUERY_CONCURRENCY: usize = 8;
+
+/// A decoded event together with the block it was emitted in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchedEvent {
+    /// Number of the block containing the event
+    pub block_number: u64,
+    /// Hash of the block containing the event
+    pub block_hash: String,
+    /// Position of the event within the block
+    pub event_index: u32,
+    /// Index of the extrinsic that emitted the event, if any
+    pub extrinsic_index: Option<u32>,
+    /// Pallet name
+    pub pallet: String,
+    /// Event variant name
+    pub variant: String,
+    /// Decoded event fields; byte arrays and account ids are rendered as hex
+    pub fields: JsonValue,
+}
+
+impl From<&MatchedEvent> for EventRecord {
+    fn from(event: &MatchedEvent) -> Self {
+        Self {
+            block_number: event.block_number,
+            block_hash: event.block_hash.clone(),
+            index: event.event_index,
+            extrinsic_index: event.extrinsic_index,
+            pallet: event.pallet.clone(),
+            event: event.variant.clone(),
+            fields: event.fields.clone(),
+        }
+    }
+}
+
+impl From<EventRecord> for MatchedEvent {
+    fn from(record: EventRecord) -> Self {
+        Self {
+            block_number: record.block_number,
+            block_hash: record.block_hash,
+            event_index: record.index,
+            extrinsic_index: record.extrinsic_index,
+            pallet: record.pallet,
+            variant: record.event,
+            fields: record.fields,
+        }
+    }
+}
+
+impl MatchedEvent {
+    /// Check whether any field holds the given account id (lowercase hex)
+    fn involves(&self, account_hex: &str) -> bool {
+        json_contains_hex(&self.fields, account_hex)
+    }
+}
+
+/// Builder for event queries over a block range
+#[derive(Clone, Default)]
+pub struct EventQuery {
+    pallet: Option<String>,
+    variant: Option<String>,
+    involving: Vec<String>,
+    range: Option<(u64, u64)>,
+    limit: Option<usize>,
+    concurrency: Option<usize>,
+    cache: Option<Arc<Cache>>,
+    history: Option<Arc<dyn EventHistory>>,
+}
+
+impl EventQuery {
+    /// Create a query matching every event
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match events from this pallet
+    pub fn pallet(mut self, pallet: impl Into<String>) -> Self {
+        self.pallet = Some(pallet.into());
+        self
+    }
+
+    /// Only match events with this variant name
+    pub fn variant(mut self, variant: impl Into<String>) -> Self {
+        self.variant = Some(variant.into());
+        self
+    }
+
+    /// Only match events with a field holding this account
+    ///
+    /// Accepts SS58 addresses and hex encoded 20 or 32 byte account ids. When
+    /// called several times, events involving any of the accounts match.
+    pub fn involving(mut self, address: impl Into<String>) -> Self {
+        self.involving.push(address.into());
+        self
+    }
+
+    /// Search blocks `from..=to`
+    pub fn between(mut self, from: u64, to: u64) -> Self {
+        self.range = Some((from, to));
+        self
+    }
+
+    /// Stop after this many matches
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set how many blocks are fetched at once
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency.max(1));
+        self
+    }
+
+    /// Reuse decoded block events from this cache
+    pub fn with_cache(mut self, cache: Arc<Cache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Answer blocks already processed by this indexer from it
+    ///
+    /// Blocks past the indexer's height, or the whole range if the indexer is
+    /// unreachable, are fetched from the node as usual. Account filters are
+    /// matched against the indexer's event arguments, which must render
+    /// account ids as hex.
+    pub fn with_history(mut self, history: Arc<dyn EventHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Run the query, returning matches in block and event order
+    pub async fn run(&self, adapter: &SubstrateAdapter) -> Result<Vec<MatchedEvent>> {
+        let (from, to) = self.range.ok_or_else(|| {
+            Error::Other("EventQuery needs a block range; call between()".to_string())
+        })?;
+        if from > to {
+            return Err(Error::Other(format!(
+                "Invalid block range: {} is after {}",
+                from, to
+            )));
+        }
+
+        let accounts = self
+            .involving
+            .iter()
+            .map(|address| account_hex(address))
+            .collect::<Result<Vec<_>>>()?;
+
+        let Some(history) = &self.history else {
+            return self.run_rpc(adapter, from, to, &accounts, self.limit).await;
+        };
+        let height = match history.indexed_height().await {
+            Ok(height) => height,
+            Err(e) => {
+                warn!("Indexer unavailable, querying the node: {}", e);
+                return self.run_rpc(adapter, from, to, &accounts, self.limit).await;
+            }
+        };
+        let split = split_range(from, to, height);
+
+        let mut matches = Vec::new();
+        if let Some(range) = split.indexed {
+            let (start, end) = (*range.start(), *range.end());
+            match history
+                .events(&self.history_filter(start, end, &accounts))
+                .await
+            {
+                Ok(records) => matches.extend(
+                    records
+                        .into_iter()
+                        .map(MatchedEvent::from)
+                        .filter(|event| self.matches(event, &accounts))
+                        .take(self.limit.unwrap_or(usize::MAX)),
+                ),
+                Err(e) => {
+                    warn!("Indexer query failed, querying the node: {}", e);
+                    matches = self
+                        .run_rpc(adapter, start, end, &accounts, self.limit)
+                        .await?;
+                }
+            }
+        }
+
+        if let Some(range) = split.live {
+            let remaining = self.limit.map(|limit| limit.saturating_sub(matches.len()));
+            if remaining != Some(0) {
+                matches.extend(
+                    self.run_rpc(adapter, *range.start(), *range.end(), &accounts, remaining)
+                        .await?,
+                );
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Filter sent to the indexer; the limit only applies when no account
+    /// filter has to be matched locally
+    fn history_filter(&self, from: u64, to: u64, accounts: &[String]) -> EventFilter {
+        let mut filter = EventFilter::new(from, to);
+        filter.pallet = self.pallet.clone();
+        filter.event = self.variant.clone();
+        if accounts.is_empty() {
+            filter.limit = self.limit;
+        }
+        filter
+    }
+
+    /// Fetch and match blocks `from..=to` from the node
+    async fn run_rpc(
+        &self,
+        adapter: &SubstrateAdapter,
+        from: u64,
+        to: u64,
+        accounts: &[String],
+        limit: Option<usize>,
+    ) -> Result<Vec<MatchedEvent>> {
+        let concurrency = self.concurrency.unwrap_or(DEFAULT_QUERY_CONCURRENCY);
+
+        debug!("Querying events in blocks {}..={}", from, to);
+
+        let mut matches = Vec::new();
+        let mut next = from;
+        while next <= to {
+            let chunk_end = to.min(next.saturating_add(concurrency as u64 - 1));
+
+            let mut tasks = JoinSet::new();
+            for number in next..=chunk_end {
+                let client = adapter.client().clone();
+                let rpc = adapter.rpc_client().clone();
+                let cache = self.cache.clone();
+                tasks.spawn(
+                    async move { block_events(&client, &rpc, cache.as_deref(), number).await },
+                );
+            }
+
+            let mut chunk = Vec::new();
+            while let Some(joined) = tasks.join_next().await {
+                let events = joined
+                    .map_err(|e| Error::Other(format!("Event query task failed: {}", e)))??;
+                chunk.extend(
+                    events
+                        .into_iter()
+                        .filter(|event| self.matches(event, accounts)),
+                );
+            }
+            chunk.sort_by_key(|event| (event.block_number, event.event_index));
+
+            for event in chunk {
+                if limit.is_some_and(|limit| matches.len() >= limit) {
+                    return Ok(matches);
+                }
+                matches.push(event);
+            }
+
+            if chunk_end == u64::MAX {
+                break;
+            }
+            next = chunk_end + 1;
+        }
+
+        Ok(matches)
+    }
+
+    fn matches(&self, event: &MatchedEvent, accounts: &[String]) -> bool {
+        self.pallet
+            .as_ref()
+            .is_none_or(|pallet| *pallet == event.pallet)
+            && self
+                .variant
+                .as_ref()
+                .is_none_or(|variant| *variant == event.variant)
+            && (accounts.is_empty() || accounts.iter().any(|account| event.involves(account)))
+    }
+}
+
+impl std::fmt::Debug for EventQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventQuery")
+            .field("pallet", &self.pallet)
+            .field("variant", &self.variant)
+            .field("involving", &self.involving)
+            .field("range", &self.range)
+            .field("limit", &self.limit)
+            .field("concurrency", &self.concurrency)
+            .field("cached", &self.cache.is_some())
+            .field("indexed", &self.history.is_some())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl EventHistory for SubstrateAdapter {
+    async fn events(
+        &self,
+        filter: &EventFilter,
+    ) -> std::result::Result<Vec<EventRecord>, SdkError> {
+        let mut query = EventQuery::new().between(filter.from_block, filter.to_block);
+        if let Some(pallet) = &filter.pallet {
+            query = query.pallet(pallet);
+        }
+        if let Some(event) = &filter.event {
+            query = query.variant(event);
+        }
+        if let Some(limit) = filter.limit {
+            query = query.limit(limit);
+        }
+
+        let events = query.run(self).await?;
+        Ok(events.iter().map(EventRecord::from).collect())
+    }
+}
+
+/// Resolve the hash of block `number` through `chain_getBlockHash`
+pub(crate) async fn block_hash_at(rpc: &RpcClient, number: u64) -> Result<H256> {
+    let mut params = RpcParams::new();
+    params
+        .push(number)
+        .map_err(|e| Error::Encoding(format!("Failed to encode block number: {}", e)))?;
+    let hash: Option<H256> = rpc
+        .request("chain_getBlockHash", params)
+        .await
+        .map_err(|e| Error::Connection(format!("Failed to get hash of block {}: {}", number, e)))?;
+    hash.ok_or_else(|| Error::Transaction(format!("Block {} not found", number)))
+}
+
+/// Decode every event of block `number`
+async fn block_events(
+    client: &OnlineClient<PolkadotConfig>,
+    rpc: &RpcClient,
+    cache: Option<&Cache>,
+    number: u64,
+) -> Result<Vec<MatchedEvent>> {
+    let hash = block_hash_at(rpc, number).await?;
+    let block_hash = format!("0x{}", hex::encode(hash.0));
+    let cache_key = format!("events:{}", block_hash);
+    if let Some(cached) = cache.and_then(|cache| cache.get_rpc(&cache_key)) {
+        if let Ok(events) = serde_json::from_str(&cached) {
+            return Ok(events);
+        }
+    }
+
+    let events = client
+        .blocks()
+        .at(hash)
+        .await
+        .map_err(|e| Error::Connection(format!("Failed to get block {}: {}", number, e)))?
+        .events()
+        .await
+        .map_err(|e| {
+            Error::Connection(format!("Failed to get events of block {}: {}", number, e))
+        })?;
+
+    let decoded = decode_events(&events, number, &block_hash)?;
+
+    if let (Some(cache), Ok(serialized)) = (cache, serde_json::to_string(&decoded)) {
+        cache.put_rpc(cache_key, serialized);
+    }
+
+    Ok(decoded)
+}
+
+/// Convert the events of one block into [`MatchedEvent`]s
+pub(crate) fn decode_events(
+    events: &Events<PolkadotConfig>,
+    number: u64,
+    block_hash: &str,
+) -> Result<Vec<MatchedEvent>> {
+    let mut decoded = Vec::new();
+    for event in events.iter() {
+        let event = event.map_err(|e| {
+            Error::Encoding(format!("Failed to decode event in block {}: {}", number, e))
+        })?;
+        let fields = event
+            .field_values()
+            .map(|fields| composite_to_json(&fields))
+            .unwrap_or(JsonValue::Null);
+
+        decoded.push(MatchedEvent {
+            block_number: number,
+            block_hash: block_hash.to_string(),
+            event_index: event.index(),
+            extrinsic_index: match event.phase() {
+                Phase::ApplyExtrinsic(index) => Some(index),
+                _ => None,
+            },
+            pallet: event.pallet_name().to_string(),
+            variant: event.variant_name().to_string(),
+            fields,
+        });
+    }
+    Ok(decoded)
+}
+
+/// Lowercase hex account id for an SS58 or hex address
+pub(crate) fn account_hex(address: &str) -> Result<String> {
+    if let Some(hex_part) = address.strip_prefix("0x") {
+        let bytes = hex::decode(hex_part)
+            .map_err(|e| Error::Other(format!("Invalid account {}: {}", address, e)))?;
+        if bytes.len() != 20 && bytes.len() != 32 {
+            return Err(Error::Other(format!(
+                "Invalid account {}: expected 20 or 32 bytes",
+                address
+            )));
+        }
+        return Ok(format!("0x{}", hex::encode(bytes)));
+    }
+
+    use sp_core::crypto::{AccountId32, Ss58Codec};
+    let account_id = AccountId32::from_ss58check(address)
+        .map_err(|e| Error::Other(format!("Invalid SS58 address {}: {:?}", address, e)))?;
+    let bytes: &[u8] = account_id.as_ref();
+    Ok(format!("0x{}", hex::encode(bytes)))
+}
+
+/// 32 byte account id for an SS58 or hex address
+pub(crate) fn account_id(address: &str) -> Result<[u8; 32]> {
+    let hex_account = account_hex(address)?;
+    hex::decode(&hex_account[2..])
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Error::Other(format!("{} is not a 32 byte account", address)))
+}
+
+/// Decode hex with or without `0x`, naming `what` was decoded on failure
+pub(crate) fn hex_bytes(value: &str, what: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim_start_matches("0x"))
+        .map_err(|e| Error::Encoding(format!("Invalid {}: {}", what, e)))
+}
+
+/// Convert decoded event fields to JSON
+///
+/// Single-field tuples (newtypes such as `AccountId32`) are unwrapped, 20 and 32
+/// byte arrays become hex strings and integers beyond 64 bits become strings.
+pub(crate) fn composite_to_json<T>(composite: &Composite<T>) -> JsonValue {
+    match composite {
+        Composite::Named(fields) => JsonValue::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value_to_json(value)))
+                .collect(),
+        ),
+        Composite::Unnamed(values) => {
+            if let Some(bytes) = as_fixed_bytes(values) {
+                return JsonValue::String(format!("0x{}", hex::encode(bytes)));
+            }
+            if let [value] = values.as_slice() {
+                return value_to_json(value);
+            }
+            JsonValue::Array(values.iter().map(value_to_json).collect())
+        }
+    }
+}
+
+pub(crate) fn value_to_json<T>(value: &Value<T>) -> JsonValue {
+    match &value.value {
+        ValueDef::Composite(composite) => composite_to_json(composite),
+        ValueDef::Variant(variant) => {
+            if variant.values.is_empty() {
+                JsonValue::String(variant.name.clone())
+            } else {
+                let mut object = serde_json::Map::new();
+                object.insert(variant.name.clone(), composite_to_json(&variant.values));
+                JsonValue::Object(object)
+            }
+        }
+        ValueDef::BitSequence(bits) => JsonValue::Array(bits.iter().map(JsonValue::Bool).collect()),
+        ValueDef::Primitive(primitive) => match primitive {
+            Primitive::Bool(b) => JsonValue::Bool(*b),
+            Primitive::Char(c) => JsonValue::String(c.to_string()),
+            Primitive::String(s) => JsonValue::String(s.clone()),
+            Primitive::U128(n) => u64::try_from(*n)
+                .map(JsonValue::from)
+                .unwrap_or_else(|_| JsonValue::String(n.to_string())),
+            Primitive::I128(n) => i64::try_from(*n)
+                .map(JsonValue::from)
+                .unwrap_or_else(|_| JsonValue::String(n.to_string())),
+            Primitive::U256(bytes) | Primitive::I256(bytes) => {
+                JsonValue::String(format!("0x{}", hex::encode(bytes)))
+            }
+        },
+    }
+}
+
+/// Bytes of an unnamed composite that looks like an account id or hash
+fn as_fixed_bytes<T>(values: &[Value<T>]) -> Option<Vec<u8>> {
+    if values.len() != 20 && values.len() != 32 {
+        return None;
+    }
+    values
+        .iter()
+        .map(|value| match value.value {
+            ValueDef::Primitive(Primitive::U128(n)) => u8::try_from(n).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+pub(crate) fn json_contains_hex(value: &JsonValue, hex: &str) -> bool {
+    match value {
+        JsonValue::String(s) => s.eq_ignore_ascii_case(hex),
+        JsonValue::Array(values) => values.iter().any(|v| json_contains_hex(v, hex)),
+        JsonValue::Object(fields) => fields.values().any(|v| json_contains_hex(v, hex)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str) -> MatchedEvent {
+        MatchedEvent {
+            block_number: 1,
+            block_hash: format!("0x{}", "00".repeat(32)),
+            event_index: 2,
+            extrinsic_index: Some(1),
+            pallet: "Balances".to_string(),
+            variant: "Transfer".to_string(),
+            fields: serde_json::json!({ "from": from, "to": to, "amount": "1000" }),
+        }
+    }
+
+    #[test]
+    fn test_history_filter_and_record_round_trip() {
+        let query = EventQuery::new()
+            .pallet("Balances")
+            .variant("Transfer")
+            .limit(5);
+
+        let filter = query.history_filter(10, 20, &[]);
+        assert_eq!(filter.pallet.as_deref(), Some("Balances"));
+        assert_eq!(filter.event.as_deref(), Some("Transfer"));
+        assert_eq!(filter.limit, Some(5));
+        assert_eq!(
+            query.history_filter(10, 20, &["0xaa".to_string()]).limit,
+            None
+        );
+
+        let event = transfer("0x01", "0x02");
+        assert_eq!(MatchedEvent::from(EventRecord::from(&event)), event);
+    }
+
+    #[test]
+    fn test_filters_by_pallet_variant_and_account() {
+        let alice = format!("0x{}", "aa".repeat(32));
+        let bob = format!("0x{}", "bb".repeat(32));
+        let event = transfer(&alice, &bob);
+
+        assert!(EventQuery::new().matches(&event, &[]));
+        assert!(EventQuery::new()
+            .pallet("Balances")
+            .variant("Transfer")
+            .matches(&event, &[]));
+        assert!(!EventQuery::new().variant("Deposit").matches(&event, &[]));
+        assert!(EventQuery::new().matches(&event, &[bob.to_uppercase().replace("0X", "0x")]));
+        assert!(!EventQuery::new().matches(&event, &[format!("0x{}", "cc".repeat(32))]));
+    }
+
+    #[test]
+    fn test_account_hex() {
+        let alice = account_hex("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").unwrap();
+        assert_eq!(
+            alice,
+            "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d"
+        );
+        assert_eq!(
+            account_hex(&alice.to_uppercase().replacen("0X", "0x", 1)).unwrap(),
+            alice
+        );
+        assert!(account_hex("0x1234").is_err());
+        assert!(account_hex("not-an-address").is_err());
+
+        assert!(account_id(&alice).is_ok());
+        assert!(account_id(&format!("0x{}", "11".repeat(20))).is_err());
+    }
+
+    #[test]
+    fn test_composite_to_json() {
+        let account = Value::unnamed_composite(vec![Value::unnamed_composite(
+            (0..32u8)
+                .map(|b| Value::u128(b as u128))
+                .collect::<Vec<_>>(),
+        )]);
+        let fields = Composite::named(vec![
+            ("who", account),
+            ("amount", Value::u128(u128::MAX)),
+            ("small", Value::u128(5)),
+            ("status", Value::unnamed_variant("Free", vec![])),
+        ]);
+
+        let json = composite_to_json(&fields);
+        assert_eq!(
+            json["who"],
+            format!("0x{}", hex::encode((0..32u8).collect::<Vec<_>>()))
+        );
+        assert_eq!(json["amount"], u128::MAX.to_string());
+        assert_eq!(json["small"], 5);
+        assert_eq!(json["status"], "Free");
+    }
+}
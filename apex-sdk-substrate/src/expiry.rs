@@ -0,0 +1,209 @@
+//! Expiry notifications for submitted transactions
+//!
+//! Transactions are signed mortal: once their era has passed they can no
+//! longer be included, and the node drops them. While a submitted
+//! transaction waits for inclusion, the executor follows best blocks and
+//! notifies every [`ExpiryHandler`] with [`ExpiryStatus::AboutToExpire`] when
+//! only a few blocks of its era are left, and with [`ExpiryStatus::Expired`]
+//! once it has lapsed. An expired submission fails with [`Error::Expired`],
+//! which the executor's retry policy answers by signing the call again, with
+//! a fresh era, right away:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{ExpiryNotice, ExpiryStatus, SubstrateAdapter};
+//!
+//! # async fn example() -> Result<(), apex_sdk_substrate::Error> {
+//! let adapter = SubstrateAdapter::connect("wss://westend-rpc.polkadot.io")
+//!     .await?
+//!     .on_expiry(|notice: &ExpiryNotice| match notice.status {
+//!         ExpiryStatus::AboutToExpire { remaining } => {
+//!             eprintln!("{} expires in {} blocks", notice.extrinsic_hash, remaining)
+//!         }
+//!         ExpiryStatus::Expired { resubmitting } => {
+//!             eprintln!("{} expired, resubmitting: {}", notice.extrinsic_hash, resubmitting)
+//!         }
+//!     });
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Immortal transactions never expire and are not tracked.
+
+use crate::mortality::Era;
+use crate::Error;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use subxt::{OnlineClient, PolkadotConfig};
+use tracing::debug;
+
+/// Blocks before the end of its era at which a pending transaction is
+/// reported as about to expire
+pub const DEFAULT_EXPIRY_WARNING_BLOCKS: u64 = 8;
+
+/// How close a pending transaction is to the end of its era
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExpiryStatus {
+    /// Still valid for `remaining` more blocks
+    AboutToExpire {
+        /// Blocks after the head it can still be included in
+        remaining: u64,
+    },
+    /// Its era has passed without it being included
+    Expired {
+        /// Whether the executor signs and submits the call again
+        resubmitting: bool,
+    },
+}
+
+/// Expiry notification for one submitted transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpiryNotice {
+    /// Hash of the submitted extrinsic (hex)
+    pub extrinsic_hash: String,
+    /// Best block when the notice was raised
+    pub head: u64,
+    /// Last block the extrinsic could be included in
+    pub valid_until: u64,
+    /// What happened
+    pub status: ExpiryStatus,
+}
+
+/// Receiver of expiry notifications
+#[async_trait]
+pub trait ExpiryHandler: Send + Sync {
+    /// Called for every notice, in block order
+    async fn on_expiry(&self, notice: &ExpiryNotice);
+}
+
+#[async_trait]
+impl<F> ExpiryHandler for F
+where
+    F: Fn(&ExpiryNotice) + Send + Sync,
+{
+    async fn on_expiry(&self, notice: &ExpiryNotice) {
+        self(notice)
+    }
+}
+
+/// Follows the era of one pending transaction as blocks pass
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpiryTracker {
+    valid_until: u64,
+    warning_blocks: u64,
+    warned: bool,
+}
+
+impl ExpiryTracker {
+    /// Tracker for a transaction signed with `era` at block `signed_at`;
+    /// `None` if immortal
+    pub fn new(era: Era, signed_at: u64, warning_blocks: u64) -> Option<Self> {
+        Some(Self {
+            valid_until: era.death(signed_at)? - 1,
+            warning_blocks,
+            warned: false,
+        })
+    }
+
+    /// Last block the transaction can be included in
+    pub fn valid_until(&self) -> u64 {
+        self.valid_until
+    }
+
+    /// Whether block `head` was the last chance to include it
+    pub fn is_expired(&self, head: u64) -> bool {
+        head >= self.valid_until
+    }
+
+    /// Blocks left, the first time block `head` leaves no more than the
+    /// warning threshold
+    pub fn warning(&mut self, head: u64) -> Option<u64> {
+        let remaining = self.valid_until.checked_sub(head)?;
+        if self.warned || remaining == 0 || remaining > self.warning_blocks {
+            return None;
+        }
+        self.warned = true;
+        Some(remaining)
+    }
+}
+
+/// Notify every handler of `notice`
+pub(crate) async fn notify(handlers: &[Arc<dyn ExpiryHandler>], notice: &ExpiryNotice) {
+    for handler in handlers {
+        handler.on_expiry(notice).await;
+    }
+}
+
+/// Expiry tracking of one submission
+pub(crate) struct ExpiryWatch {
+    pub(crate) extrinsic_hash: String,
+    pub(crate) tracker: ExpiryTracker,
+    pub(crate) handlers: Vec<Arc<dyn ExpiryHandler>>,
+}
+
+impl ExpiryWatch {
+    /// Follow best blocks until the transaction's era has passed without
+    /// `included` being set, warning handlers on the way
+    ///
+    /// Returns [`Error::Expired`] then; never returns if the transaction is
+    /// included first or blocks cannot be followed.
+    pub(crate) async fn run(
+        mut self,
+        client: &OnlineClient<PolkadotConfig>,
+        included: &AtomicBool,
+    ) -> Error {
+        match client.blocks().subscribe_best().await {
+            Ok(mut blocks) => {
+                while let Some(Ok(block)) = blocks.next().await {
+                    if included.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let head = block.number() as u64;
+                    if self.tracker.is_expired(head) {
+                        return Error::Expired {
+                            extrinsic_hash: self.extrinsic_hash,
+                            valid_until: self.tracker.valid_until(),
+                        };
+                    }
+                    if let Some(remaining) = self.tracker.warning(head) {
+                        let notice = ExpiryNotice {
+                            extrinsic_hash: self.extrinsic_hash.clone(),
+                            head,
+                            valid_until: self.tracker.valid_until(),
+                            status: ExpiryStatus::AboutToExpire { remaining },
+                        };
+                        notify(&self.handlers, &notice).await;
+                    }
+                }
+            }
+            Err(e) => debug!("Not tracking expiry of {}: {}", self.extrinsic_hash, e),
+        }
+        std::future::pending().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry_tracker() {
+        let era = Era::Mortal {
+            period: 64,
+            phase: 40,
+        };
+        let mut tracker = ExpiryTracker::new(era, 1000, DEFAULT_EXPIRY_WARNING_BLOCKS).unwrap();
+        assert_eq!(tracker.valid_until(), 1063);
+
+        assert_eq!(tracker.warning(1050), None);
+        assert_eq!(tracker.warning(1056), Some(7));
+        // warned once only
+        assert_eq!(tracker.warning(1057), None);
+        assert!(!tracker.is_expired(1062));
+        assert!(tracker.is_expired(1063));
+
+        assert!(ExpiryTracker::new(Era::Immortal, 1000, 8).is_none());
+    }
+}
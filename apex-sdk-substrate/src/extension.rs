@@ -0,0 +1,251 @@
+//! Signing with polkadot-js compatible browser extensions
+//!
+//! In the browser, keys stay in an extension such as polkadot-js, Talisman or
+//! SubWallet, which inject themselves into `window.injectedWeb3`. An
+//! [`ExtensionSigner`] enables one of them and asks it to sign transactions
+//! built by a [`WatchOnlyAccount`], so the usual prepare, sign and submit flow
+//! works with keys the dApp never sees.
+//!
+//! Requires the `web` feature. The signer talks to the page, so it only works
+//! in a browser on a `wasm32` target; elsewhere its calls into JavaScript panic.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::extension::ExtensionSigner;
+//! use apex_sdk_substrate::SubstrateAdapter;
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let signer = ExtensionSigner::enable("polkadot-js", "My dApp").await?;
+//! let account = &signer.accounts().await?[0];
+//!
+//! let tx_hash = signer
+//!     .transfer(adapter, &account.address, "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty", 1_000_000_000)
+//!     .await?;
+//! println!("submitted {}", tx_hash);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::wallet::KeyPairType;
+use crate::watch_only::{PendingTransaction, TransactionPayload, WatchOnlyAccount};
+use crate::{Error, Result, SubstrateAdapter};
+use js_sys::{Array, Function, Object, Promise, Reflect, JSON};
+use serde::{Deserialize, Serialize};
+use subxt::dynamic::Value;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+/// An account exposed by an extension
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InjectedAccount {
+    /// SS58 address
+    pub address: String,
+    /// Name given by the user
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Key type: `sr25519`, `ed25519`, `ecdsa` or `ethereum`
+    #[serde(default, rename = "type")]
+    pub key_type: Option<String>,
+    /// Chain the account is restricted to, if any
+    #[serde(default)]
+    pub genesis_hash: Option<String>,
+}
+
+impl InjectedAccount {
+    /// Key pair type of the account; extensions omitting it use SR25519
+    pub fn key_pair_type(&self) -> Option<KeyPairType> {
+        match self.key_type.as_deref() {
+            None | Some("sr25519") => Some(KeyPairType::Sr25519),
+            Some("ed25519") => Some(KeyPairType::Ed25519),
+            Some("ethereum") => Some(KeyPairType::Ethereum),
+            Some(_) => None,
+        }
+    }
+}
+
+/// Names of the extensions injected into the page
+pub fn available_extensions() -> Vec<String> {
+    Reflect::get(&js_sys::global(), &"injectedWeb3".into())
+        .ok()
+        .filter(|injected| injected.is_object())
+        .map(|injected| {
+            Object::keys(injected.unchecked_ref::<Object>())
+                .iter()
+                .filter_map(|name| name.as_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Signer backed by an enabled browser extension
+pub struct ExtensionSigner {
+    source: String,
+    injected: JsValue,
+}
+
+impl ExtensionSigner {
+    /// Ask the user to authorize `app_name` with the extension `source`
+    pub async fn enable(source: &str, app_name: &str) -> Result<Self> {
+        let extension = Reflect::get(&js_sys::global(), &"injectedWeb3".into())
+            .and_then(|injected| Reflect::get(&injected, &source.into()))
+            .ok()
+            .filter(|extension| extension.is_object())
+            .ok_or_else(|| Error::Wallet(format!("Extension {} is not installed", source)))?;
+        let injected = call(&extension, "enable", &[app_name.into()]).await?;
+        Ok(Self {
+            source: source.to_string(),
+            injected,
+        })
+    }
+
+    /// Name the extension injected itself under
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Accounts the user shared with the dApp
+    pub async fn accounts(&self) -> Result<Vec<InjectedAccount>> {
+        let accounts = field(&self.injected, "accounts")?;
+        let list = call(&accounts, "get", &[]).await?;
+        from_js(&list)
+    }
+
+    /// Ask the extension to sign a transaction payload; returns the hex signature
+    pub async fn sign_payload(&self, payload: &TransactionPayload) -> Result<String> {
+        let signer = field(&self.injected, "signer")?;
+        let result = call(&signer, "signPayload", &[to_js(payload)?]).await?;
+        field(&result, "signature")?
+            .as_string()
+            .ok_or_else(|| Error::Signature("Extension returned no signature".to_string()))
+    }
+
+    /// Sign a prepared transaction with the extension, submit it and wait for finality
+    pub async fn sign_and_submit(&self, pending: PendingTransaction) -> Result<String> {
+        let signature = self.sign_payload(pending.payload()).await?;
+        pending.submit_signature(&signature).await
+    }
+
+    /// Build, sign and submit a call from one of the extension's accounts
+    pub async fn submit_call(
+        &self,
+        adapter: &SubstrateAdapter,
+        address: &str,
+        pallet: &str,
+        call: &str,
+        args: Vec<Value>,
+    ) -> Result<String> {
+        let pending = self
+            .account(address)
+            .await?
+            .prepare(adapter, pallet, call, args)
+            .await?;
+        self.sign_and_submit(pending).await
+    }
+
+    /// Transfer from one of the extension's accounts
+    pub async fn transfer(
+        &self,
+        adapter: &SubstrateAdapter,
+        from: &str,
+        to: &str,
+        amount: u128,
+    ) -> Result<String> {
+        let pending = self
+            .account(from)
+            .await?
+            .prepare_transfer(adapter, to, amount)
+            .await?;
+        self.sign_and_submit(pending).await
+    }
+
+    /// Watch-only view of a shared account
+    async fn account(&self, address: &str) -> Result<WatchOnlyAccount> {
+        let account = self
+            .accounts()
+            .await?
+            .into_iter()
+            .find(|account| account.address == address)
+            .ok_or_else(|| {
+                Error::Wallet(format!("{} is not shared by {}", address, self.source))
+            })?;
+        let key_type = account
+            .key_pair_type()
+            .ok_or_else(|| Error::Wallet(format!("Unsupported key type for {}", address)))?;
+        WatchOnlyAccount::new(address, key_type)
+    }
+}
+
+/// Property of a JS object
+fn field(object: &JsValue, name: &str) -> Result<JsValue> {
+    Reflect::get(object, &name.into())
+        .ok()
+        .filter(|value| !value.is_undefined())
+        .ok_or_else(|| Error::Wallet(format!("Extension object has no {}", name)))
+}
+
+/// Call a method returning a promise and await it
+async fn call(object: &JsValue, method: &str, args: &[JsValue]) -> Result<JsValue> {
+    let function: Function = field(object, method)?
+        .dyn_into()
+        .map_err(|_| Error::Wallet(format!("Extension {} is not a function", method)))?;
+    let returned = function
+        .apply(object, &args.iter().collect::<Array>())
+        .map_err(|e| Error::Wallet(format!("Extension {} failed: {}", method, js_error(&e))))?;
+    let promise = Promise::resolve(&returned);
+    JsFuture::from(promise)
+        .await
+        .map_err(|e| Error::Wallet(format!("Extension {} rejected: {}", method, js_error(&e))))
+}
+
+/// Convert through JSON, which extensions accept as plain objects
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue> {
+    let json = serde_json::to_string(value).map_err(|e| Error::Encoding(e.to_string()))?;
+    JSON::parse(&json).map_err(|e| Error::Encoding(js_error(&e)))
+}
+
+fn from_js<T: for<'de> Deserialize<'de>>(value: &JsValue) -> Result<T> {
+    let json = JSON::stringify(value)
+        .map_err(|e| Error::Encoding(js_error(&e)))?
+        .as_string()
+        .unwrap_or_default();
+    serde_json::from_str(&json).map_err(|e| Error::Encoding(e.to_string()))
+}
+
+/// Message of a thrown JS value
+fn js_error(error: &JsValue) -> String {
+    error
+        .as_string()
+        .or_else(|| {
+            Reflect::get(error, &"message".into())
+                .ok()
+                .and_then(|message| message.as_string())
+        })
+        .unwrap_or_else(|| format!("{:?}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_injected_accounts() {
+        let accounts: Vec<InjectedAccount> = serde_json::from_value(json!([
+            {
+                "address": "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+                "name": "Alice",
+                "type": "sr25519",
+                "genesisHash": "0x91b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c3"
+            },
+            { "address": "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty" },
+            { "address": "0x8eaf04151687736326c9fea17e25fc5287613693", "type": "ecdsa" }
+        ]))
+        .unwrap();
+
+        assert_eq!(accounts[0].name.as_deref(), Some("Alice"));
+        assert!(accounts[0].genesis_hash.is_some());
+        assert_eq!(accounts[0].key_pair_type(), Some(KeyPairType::Sr25519));
+        assert_eq!(accounts[1].key_pair_type(), Some(KeyPairType::Sr25519));
+        assert_eq!(accounts[2].key_pair_type(), None);
+    }
+}
@@ -0,0 +1,313 @@
+//! Tip suggestions from recent inclusion data
+//!
+//! [`FeeAdvisor`] samples the most recent blocks, records how full each one
+//! was and the tips of the signed extrinsics that made it in, and suggests the
+//! smallest tip likely to get a transaction included within a given number of
+//! blocks, much like an EVM gas oracle:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{FeeAdvisor, FeeConfig, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let suggestion = FeeAdvisor::new(adapter).suggest_tip(3).await?;
+//! println!(
+//!     "tip {} planck, {:.0}% likely within 3 blocks",
+//!     suggestion.tip,
+//!     suggestion.probability * 100.0
+//! );
+//! let fee_config = FeeConfig::new().with_tip(suggestion.tip);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A block that was not full took everything offered to it, so any tip would
+//! have made it in. A full block only took extrinsics at least as well paying
+//! as the cheapest one it included. The pool also orders by weight and length,
+//! so the suggestion is a guide, not a guarantee.
+
+use crate::event_query::{block_hash_at, value_to_json};
+use crate::{Error, Result, SubstrateAdapter};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::dynamic::Value;
+use subxt::utils::H256;
+use tracing::debug;
+
+/// Default number of recent blocks sampled
+pub const DEFAULT_SAMPLE_BLOCKS: u32 = 20;
+
+/// Default probability the suggested tip should reach
+pub const DEFAULT_CONFIDENCE: f64 = 0.9;
+
+/// Share of the normal dispatch class capacity from which a block counts as full
+pub const FULL_BLOCK_THRESHOLD: f64 = 0.9;
+
+/// Inclusion data of one sampled block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockSample {
+    /// Block number
+    pub number: u64,
+    /// Used share of the normal dispatch class capacity, from 0 to 1
+    pub fullness: f64,
+    /// Tips of the signed extrinsics in the block, in planck
+    pub tips: Vec<u128>,
+}
+
+impl BlockSample {
+    /// Whether the block had no room left for more extrinsics
+    pub fn is_full(&self) -> bool {
+        self.fullness >= FULL_BLOCK_THRESHOLD
+    }
+
+    /// Lowest tip that would have been included in this block
+    pub fn min_tip(&self) -> u128 {
+        if self.is_full() {
+            self.tips.iter().copied().min().unwrap_or_default()
+        } else {
+            0
+        }
+    }
+}
+
+/// Suggested tip for a target inclusion delay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TipSuggestion {
+    /// Suggested tip in planck
+    pub tip: u128,
+    /// Blocks within which inclusion is targeted
+    pub target_blocks: u32,
+    /// Estimated probability of inclusion within the target
+    pub probability: f64,
+    /// Number of blocks sampled
+    pub sampled_blocks: usize,
+    /// Number of sampled blocks that were full
+    pub full_blocks: usize,
+    /// Average fullness of the sampled blocks, from 0 to 1
+    pub average_fullness: f64,
+}
+
+/// Tip oracle over recent blocks
+pub struct FeeAdvisor<'a> {
+    adapter: &'a SubstrateAdapter,
+    sample_blocks: u32,
+    confidence: f64,
+}
+
+impl<'a> FeeAdvisor<'a> {
+    /// Advise from the chain behind the adapter
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self {
+            adapter,
+            sample_blocks: DEFAULT_SAMPLE_BLOCKS,
+            confidence: DEFAULT_CONFIDENCE,
+        }
+    }
+
+    /// Number of recent blocks to sample
+    pub fn with_sample_blocks(mut self, blocks: u32) -> Self {
+        self.sample_blocks = blocks.max(1);
+        self
+    }
+
+    /// Probability of inclusion the suggested tip should reach, from 0 to 1
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Suggest a tip likely to be included within `target_blocks` blocks
+    pub async fn suggest_tip(&self, target_blocks: u32) -> Result<TipSuggestion> {
+        let samples = self.samples().await?;
+        Ok(suggest(&samples, target_blocks, self.confidence))
+    }
+
+    /// Inclusion data of the most recent blocks, oldest first
+    pub async fn samples(&self) -> Result<Vec<BlockSample>> {
+        let client = self.adapter.client();
+        let head = client
+            .blocks()
+            .at_latest()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get latest block: {}", e)))?
+            .number() as u64;
+        let capacity = self.normal_capacity()?;
+
+        let from = head.saturating_sub(u64::from(self.sample_blocks) - 1);
+        let mut samples = Vec::with_capacity(self.sample_blocks as usize);
+        for number in from..=head {
+            let hash = block_hash_at(self.adapter.rpc_client(), number).await?;
+            samples.push(BlockSample {
+                number,
+                fullness: self.normal_weight(hash).await? as f64 / capacity as f64,
+                tips: self.tips(hash).await?,
+            });
+        }
+        debug!("Sampled {} blocks up to #{}", samples.len(), head);
+        Ok(samples)
+    }
+
+    async fn tips(&self, hash: H256) -> Result<Vec<u128>> {
+        let block = self
+            .adapter
+            .client()
+            .blocks()
+            .at(hash)
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get block: {}", e)))?;
+        let extrinsics = block
+            .extrinsics()
+            .await
+            .map_err(|e| Error::Transaction(format!("Failed to get extrinsics: {}", e)))?;
+        Ok(extrinsics
+            .iter()
+            .filter_map(|extrinsic| extrinsic.transaction_extensions()?.tip())
+            .collect())
+    }
+
+    /// `ref_time` consumed by normal extrinsics in the block
+    async fn normal_weight(&self, hash: H256) -> Result<u64> {
+        let query = subxt::dynamic::storage("System", "BlockWeight", Vec::<Value>::new());
+        let value = self
+            .adapter
+            .client()
+            .storage()
+            .at(hash)
+            .fetch(&query)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to query System::BlockWeight: {}", e)))?
+            .ok_or_else(|| Error::Storage("System::BlockWeight not found".to_string()))?
+            .to_value()
+            .map_err(|e| Error::Storage(format!("Failed to decode System::BlockWeight: {}", e)))?;
+        ref_time(&value_to_json(&value)["normal"])
+            .ok_or_else(|| Error::Storage("Unexpected System::BlockWeight layout".to_string()))
+    }
+
+    /// Maximum `ref_time` of normal extrinsics per block
+    fn normal_capacity(&self) -> Result<u64> {
+        let weights = self
+            .adapter
+            .storage()
+            .get_constant_json("System", "BlockWeights")?;
+        normal_capacity(&weights)
+            .filter(|capacity| *capacity > 0)
+            .ok_or_else(|| Error::Storage("Unexpected System::BlockWeights layout".to_string()))
+    }
+}
+
+/// Smallest tip reaching `confidence` of inclusion within `target_blocks`
+///
+/// Treats each sampled block as an independent draw: a tip gets into a block
+/// when it is at least that block's [`min_tip`](BlockSample::min_tip). When
+/// no observed tip reaches the confidence, the highest one is suggested.
+pub fn suggest(samples: &[BlockSample], target_blocks: u32, confidence: f64) -> TipSuggestion {
+    let target_blocks = target_blocks.max(1);
+    let mut thresholds: Vec<u128> = samples.iter().map(BlockSample::min_tip).collect();
+    thresholds.sort_unstable();
+
+    let inclusion = |tip: u128| {
+        if thresholds.is_empty() {
+            return 1.0;
+        }
+        let per_block =
+            thresholds.partition_point(|min| *min <= tip) as f64 / thresholds.len() as f64;
+        1.0 - (1.0 - per_block).powi(target_blocks.min(i32::MAX as u32) as i32)
+    };
+
+    let mut candidates = Vec::with_capacity(thresholds.len() + 1);
+    candidates.push(0);
+    candidates.extend(&thresholds);
+    candidates.dedup();
+    let tip = candidates
+        .iter()
+        .copied()
+        .find(|tip| inclusion(*tip) >= confidence)
+        .unwrap_or_else(|| candidates.last().copied().unwrap_or_default());
+
+    let average_fullness = if samples.is_empty() {
+        0.0
+    } else {
+        samples.iter().map(|sample| sample.fullness).sum::<f64>() / samples.len() as f64
+    };
+    TipSuggestion {
+        tip,
+        target_blocks,
+        probability: inclusion(tip),
+        sampled_blocks: samples.len(),
+        full_blocks: samples.iter().filter(|sample| sample.is_full()).count(),
+        average_fullness,
+    }
+}
+
+/// Normal class `max_total`, falling back to `max_block`, from `BlockWeights`
+fn normal_capacity(weights: &JsonValue) -> Option<u64> {
+    let max_total = &weights["per_class"]["normal"]["max_total"];
+    ref_time(&max_total["Some"])
+        .or_else(|| ref_time(max_total))
+        .or_else(|| ref_time(&weights["max_block"]))
+}
+
+fn ref_time(weight: &JsonValue) -> Option<u64> {
+    match &weight["ref_time"] {
+        JsonValue::Number(n) => n.as_u64(),
+        JsonValue::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(number: u64, fullness: f64, tips: &[u128]) -> BlockSample {
+        BlockSample {
+            number,
+            fullness,
+            tips: tips.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_suggest_tip() {
+        // quiet chain: any tip is included
+        let quiet = [block(1, 0.2, &[5]), block(2, 0.4, &[])];
+        assert_eq!(suggest(&quiet, 1, 0.9).tip, 0);
+
+        // half the blocks full, the cheapest included tips being 10 and 20
+        let busy = [
+            block(1, 0.95, &[10, 50]),
+            block(2, 0.3, &[1]),
+            block(3, 1.0, &[20, 30]),
+            block(4, 0.5, &[]),
+        ];
+        let next_block = suggest(&busy, 1, 0.9);
+        assert_eq!(next_block.tip, 20);
+        assert_eq!(next_block.full_blocks, 2);
+        assert!((next_block.probability - 1.0).abs() < 1e-9);
+
+        // 75% per block for a tip of 10 gives 1 - 0.25^2 within two blocks
+        let two_blocks = suggest(&busy, 2, 0.9);
+        assert_eq!(two_blocks.tip, 10);
+        assert!((two_blocks.probability - 0.9375).abs() < 1e-9);
+
+        // waiting long enough makes the free tip good enough
+        assert_eq!(suggest(&busy, 4, 0.9).tip, 0);
+        assert_eq!(suggest(&[], 1, 0.9).tip, 0);
+    }
+
+    #[test]
+    fn test_normal_capacity() {
+        let weights = serde_json::json!({
+            "max_block": { "ref_time": 2_000_000_000_000u64, "proof_size": 5_242_880 },
+            "per_class": {
+                "normal": { "max_total": { "Some": { "ref_time": 1_500_000_000_000u64, "proof_size": 3_932_160 } } }
+            }
+        });
+        assert_eq!(normal_capacity(&weights), Some(1_500_000_000_000));
+
+        let unlimited = serde_json::json!({
+            "max_block": { "ref_time": "2000000000000" },
+            "per_class": { "normal": { "max_total": "None" } }
+        });
+        assert_eq!(normal_capacity(&unlimited), Some(2_000_000_000_000));
+    }
+}
@@ -0,0 +1,236 @@
+//! Fee regressions across runtime upgrades
+//!
+//! [`FeeRegression`] dry-runs a fixed set of representative calls in the state
+//! of two blocks, typically one before and one after a runtime upgrade, and
+//! reports how the fee of each call changed. Both blocks must still have
+//! their state available, so comparisons further back need an archive node.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{DryRunCall, FeeRegression, SubstrateAdapter};
+//! use subxt::dynamic::Value;
+//!
+//! # async fn example(adapter: &SubstrateAdapter, alice: [u8; 32]) -> Result<(), apex_sdk_substrate::Error> {
+//! let report = FeeRegression::new(adapter)
+//!     .with_call(DryRunCall::new(alice, "System", "remark").with_args(vec![Value::from_bytes([0u8; 64])]))
+//!     .compare(21_000_000, 21_500_000)
+//!     .await?;
+//!
+//! for sample in report.regressions(5.0) {
+//!     println!("{}.{}: {:+.1}%", sample.pallet, sample.call, sample.change_percent().unwrap_or_default());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::simulator::{DryRunCall, Simulator};
+use crate::{Error, Result, SubstrateAdapter};
+use serde::{Deserialize, Serialize};
+use subxt::ext::subxt_rpcs::client::RpcParams;
+
+/// Fee of one call at both blocks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeSample {
+    /// Pallet name
+    pub pallet: String,
+    /// Call name
+    pub call: String,
+    /// Fee at the earlier block, if it could be estimated
+    pub before: Option<u128>,
+    /// Fee at the later block, if it could be estimated
+    pub after: Option<u128>,
+}
+
+impl FeeSample {
+    /// Fee change in planck; positive when the call became more expensive
+    pub fn delta(&self) -> Option<i128> {
+        let before = i128::try_from(self.before?).ok()?;
+        let after = i128::try_from(self.after?).ok()?;
+        after.checked_sub(before)
+    }
+
+    /// Fee change relative to the earlier fee, in percent
+    pub fn change_percent(&self) -> Option<f64> {
+        let before = self.before.filter(|fee| *fee > 0)?;
+        Some(self.delta()? as f64 * 100.0 / before as f64)
+    }
+}
+
+/// Fees of the representative calls at two blocks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeRegressionReport {
+    /// Earlier block
+    pub before_block: u64,
+    /// Runtime spec version at the earlier block
+    pub before_spec_version: u32,
+    /// Later block
+    pub after_block: u64,
+    /// Runtime spec version at the later block
+    pub after_spec_version: u32,
+    /// One sample per call, in the order the calls were added
+    pub samples: Vec<FeeSample>,
+}
+
+impl FeeRegressionReport {
+    /// Whether the blocks ran different runtimes
+    pub fn is_upgrade(&self) -> bool {
+        self.before_spec_version != self.after_spec_version
+    }
+
+    /// Samples whose fee rose by more than `threshold_percent`
+    pub fn regressions(&self, threshold_percent: f64) -> Vec<&FeeSample> {
+        self.samples
+            .iter()
+            .filter(|sample| {
+                sample
+                    .change_percent()
+                    .is_some_and(|change| change > threshold_percent)
+            })
+            .collect()
+    }
+
+    /// Samples whose fee could not be estimated at one of the blocks
+    pub fn unavailable(&self) -> Vec<&FeeSample> {
+        self.samples
+            .iter()
+            .filter(|sample| sample.before.is_none() || sample.after.is_none())
+            .collect()
+    }
+}
+
+/// Compares fees of representative calls between two blocks
+pub struct FeeRegression<'a> {
+    adapter: &'a SubstrateAdapter,
+    calls: Vec<DryRunCall>,
+}
+
+impl<'a> FeeRegression<'a> {
+    /// Tracker without calls
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self {
+            adapter,
+            calls: Vec::new(),
+        }
+    }
+
+    /// Add a representative call
+    pub fn with_call(mut self, call: DryRunCall) -> Self {
+        self.calls.push(call);
+        self
+    }
+
+    /// Add several representative calls
+    pub fn with_calls(mut self, calls: impl IntoIterator<Item = DryRunCall>) -> Self {
+        self.calls.extend(calls);
+        self
+    }
+
+    /// Estimate every call at both blocks and pair up the fees
+    pub async fn compare(
+        &self,
+        before_block: u64,
+        after_block: u64,
+    ) -> Result<FeeRegressionReport> {
+        let before = Simulator::new(self.adapter)
+            .at_block(before_block)
+            .dry_run_many(&self.calls)
+            .await?;
+        let after = Simulator::new(self.adapter)
+            .at_block(after_block)
+            .dry_run_many(&self.calls)
+            .await?;
+
+        let samples = before
+            .into_iter()
+            .zip(after)
+            .map(|(before, after)| FeeSample {
+                pallet: before.pallet,
+                call: before.call,
+                before: before.fee,
+                after: after.fee,
+            })
+            .collect();
+
+        Ok(FeeRegressionReport {
+            before_block,
+            before_spec_version: self.spec_version_at(before_block).await?,
+            after_block,
+            after_spec_version: self.spec_version_at(after_block).await?,
+            samples,
+        })
+    }
+
+    /// Runtime spec version in effect at `block`
+    async fn spec_version_at(&self, block: u64) -> Result<u32> {
+        let hash = self
+            .adapter
+            .spec_client()
+            .block_hash(block)
+            .await?
+            .ok_or_else(|| Error::Storage(format!("Block {} not found", block)))?;
+
+        let mut params = RpcParams::new();
+        params
+            .push(format!("0x{}", hex::encode(hash.0)))
+            .map_err(|e| Error::Encoding(format!("Failed to encode block hash: {}", e)))?;
+        let version: SpecVersion = self
+            .adapter
+            .rpc_client()
+            .request("state_getRuntimeVersion", params)
+            .await
+            .map_err(|e| {
+                Error::Connection(format!(
+                    "Failed to read runtime version at block {}: {}",
+                    block, e
+                ))
+            })?;
+        Ok(version.spec_version)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpecVersion {
+    spec_version: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(call: &str, before: Option<u128>, after: Option<u128>) -> FeeSample {
+        FeeSample {
+            pallet: "Balances".to_string(),
+            call: call.to_string(),
+            before,
+            after,
+        }
+    }
+
+    #[test]
+    fn test_regressions() {
+        let report = FeeRegressionReport {
+            before_block: 100,
+            before_spec_version: 1_002_000,
+            after_block: 200,
+            after_spec_version: 1_003_000,
+            samples: vec![
+                sample("transfer_keep_alive", Some(1_000), Some(1_100)),
+                sample("transfer_allow_death", Some(1_000), Some(1_030)),
+                sample("force_transfer", Some(2_000), Some(1_500)),
+                sample("transfer_all", Some(1_000), None),
+            ],
+        };
+
+        assert!(report.is_upgrade());
+        assert_eq!(report.samples[0].delta(), Some(100));
+        assert_eq!(report.samples[2].delta(), Some(-500));
+        assert_eq!(report.samples[2].change_percent(), Some(-25.0));
+        assert_eq!(report.samples[3].delta(), None);
+
+        let regressions = report.regressions(5.0);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].call, "transfer_keep_alive");
+        assert_eq!(report.unavailable()[0].call, "transfer_all");
+        assert_eq!(sample("x", Some(0), Some(5)).change_percent(), None);
+    }
+}
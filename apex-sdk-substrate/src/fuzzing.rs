@@ -0,0 +1,156 @@
+//! Fuzzing hooks for decoders fed by untrusted input
+//!
+//! Storage values, scanned QR codes, addresses typed by users and extrinsics
+//! returned over RPC all reach decoding code from outside the SDK. Each hook
+//! here runs one of those decoders on arbitrary input and panics only when an
+//! invariant breaks: a decoder may reject input, but must not panic on it,
+//! and whatever it accepts must encode back to the same bytes.
+//!
+//! The hooks are shared by the proptest suite in `tests/decode_props_test.rs`
+//! and the cargo-fuzz targets in `fuzz/`.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::fuzzing;
+//!
+//! fuzzing::uos_payload(&[0x53, 0x01, 0x02]);
+//! fuzzing::address("5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty");
+//! ```
+
+use crate::account20::{account_bytes, AccountIdKind};
+use crate::collectives::RankedVote;
+use crate::coretime::RegionId;
+use crate::crowdloan::Contribution;
+use crate::delegation::Delegation;
+use crate::golden::decode_extrinsic;
+use crate::uos::{encode_frames, Frame, FrameDecoder};
+use crate::watch_only::SigningRequest;
+use serde_json::json;
+use subxt::Metadata;
+
+/// UOS transaction payload: an accepted payload re-encodes to the same bytes
+///
+/// Only the action byte may differ, as legacy mortal requests are re-encoded
+/// with the current action.
+pub fn uos_payload(data: &[u8]) {
+    let Ok(request) = SigningRequest::from_uos(data) else {
+        return;
+    };
+    let encoded = request.to_uos();
+    assert_eq!(encoded.len(), data.len(), "UOS payload changed length");
+    assert_eq!(encoded[..2], data[..2], "UOS header changed");
+    assert_eq!(encoded[3..], data[3..], "UOS body changed");
+}
+
+/// Scanned QR frames: a parsed frame re-encodes to the same bytes
+///
+/// The input is also split into frames pushed through a [`FrameDecoder`],
+/// which must never yield more bytes than it was given.
+pub fn uos_frames(data: &[u8]) {
+    if let Ok(frame) = Frame::parse(data) {
+        assert!(frame.index < frame.count, "frame index out of range");
+        assert_eq!(frame.to_bytes(), data, "frame changed on re-encoding");
+    }
+
+    let mut decoder = FrameDecoder::new();
+    let mut pushed = 0;
+    for chunk in data.split(|byte| *byte == 0xff) {
+        pushed += chunk.len();
+        if let Ok(Some(payload)) = decoder.push(chunk) {
+            assert!(payload.len() <= pushed, "decoder produced unseen bytes");
+        }
+    }
+}
+
+/// Multipart encoding: frames pushed in reverse order reassemble the payload
+pub fn frames_roundtrip(payload: &[u8], frame_size: usize) {
+    let Ok(frames) = encode_frames(payload, frame_size) else {
+        return;
+    };
+    let mut decoder = FrameDecoder::new();
+    let mut decoded = None;
+    for frame in frames.iter().rev() {
+        assert!(decoded.is_none(), "payload complete before the last frame");
+        decoded = decoder.push(frame).expect("encoded frame must parse");
+    }
+    assert_eq!(
+        decoded.as_deref(),
+        Some(payload),
+        "payload did not survive framing"
+    );
+}
+
+/// Address parsing: accepted addresses format and parse back to the same id
+pub fn address(data: &str) {
+    let Ok(bytes) = account_bytes(data) else {
+        return;
+    };
+    let kind = match bytes.len() {
+        32 => AccountIdKind::Id32,
+        20 => AccountIdKind::Id20,
+        len => panic!("address parsed to {} bytes", len),
+    };
+    let formatted = kind.format(&bytes, 42).expect("parsed id must format");
+    assert_eq!(
+        kind.parse(&formatted)
+            .expect("formatted address must parse"),
+        bytes,
+        "address changed on re-formatting"
+    );
+}
+
+/// Storage keys and values decoded by pallet helpers
+pub fn storage_entry(data: &[u8]) {
+    let _ = RegionId::from_storage_key(data);
+    let _ = Contribution::from_entry(0, data, data);
+    for record in [json!({ "Aye": 1 }), json!({ "Nay": 1 }), json!(null)] {
+        let _ = RankedVote::from_entry(data, &record);
+    }
+    let delegating = json!({
+        "Delegating": { "target": "0x00", "balance": 1, "conviction": "Locked1x" }
+    });
+    if let Some((_, delegation)) = Delegation::from_entry(data, &delegating) {
+        assert_eq!(
+            delegation.delegator.len(),
+            2 + 64,
+            "delegator is not 32 bytes"
+        );
+    }
+}
+
+/// Extrinsic bytes decoded against runtime metadata
+pub fn extrinsic(data: &[u8], metadata: &Metadata) {
+    if let Ok(decoded) = decode_extrinsic(data, 0, metadata) {
+        assert!(
+            !decoded.pallet.is_empty(),
+            "extrinsic decoded without a pallet"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hooks_accept_malformed_input() {
+        for data in [
+            &[][..],
+            &[0x53],
+            &[0x00, 0x00, 0x01, 0x00, 0x00],
+            &[0xff; 80],
+        ] {
+            uos_payload(data);
+            uos_frames(data);
+            storage_entry(data);
+        }
+        address("0x");
+        address("not an address");
+    }
+
+    #[test]
+    fn test_frames_roundtrip() {
+        frames_roundtrip(&[], 4);
+        frames_roundtrip(&[7; 1000], 3);
+        frames_roundtrip(&[1, 2, 3], 0);
+    }
+}
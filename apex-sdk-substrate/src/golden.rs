@@ -0,0 +1,473 @@
+//! Golden-file tests for block, extrinsic and event decoding
+//!
+//! A [`GoldenFixture`] holds the raw bytes of one block's extrinsics and
+//! events as recorded from a live chain, next to the output the SDK decoded
+//! from them. The metadata of the block is stored beside it, so checking a
+//! fixture needs no network: [`check_fixture`] decodes the bytes again and
+//! reports the first extrinsic or event that decodes differently, catching
+//! regressions from SDK or dependency upgrades before users hit them. This is
+//! the decoding counterpart of the encoding vectors in
+//! `apex_sdk_core::golden_vectors`.
+//!
+//! Fixtures live in a directory as `{chain}-{block}.json`, with the metadata
+//! in `{chain}-{spec_version}.scale`. Setting `APEX_UPDATE_GOLDEN=1` accepts
+//! the current output as the new expectation.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::golden::{check_dir, GoldenRecorder};
+//! use apex_sdk_substrate::SubstrateAdapter;
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let dir = std::path::Path::new("tests/golden");
+//! GoldenRecorder::new(adapter).record_to(dir, 20_000_000).await?;
+//!
+//! // later, offline
+//! let checked = check_dir(dir)?;
+//! println!("{} fixtures decode as recorded", checked);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::{decode_events, hex_bytes, value_to_json, MatchedEvent};
+use crate::state_diff::storage_prefix;
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::{Compact, Decode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::path::{Path, PathBuf};
+use subxt::events::Events;
+use subxt::ext::scale_value;
+use subxt::ext::subxt_rpcs::client::RpcParams;
+use subxt::{Metadata, PolkadotConfig};
+
+/// Environment variable that makes checks rewrite expectations instead of failing
+pub const UPDATE_ENV: &str = "APEX_UPDATE_GOLDEN";
+
+/// Bit of the extrinsic version byte marking a signed extrinsic
+const SIGNED_BIT: u8 = 0b1000_0000;
+
+/// Raw inputs of one block and the output they decoded to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenFixture {
+    /// Chain name, e.g. `westend`
+    pub chain: String,
+    /// Runtime spec version the block was produced with
+    pub spec_version: u32,
+    /// Block number
+    pub block_number: u64,
+    /// Block hash as hex
+    pub block_hash: String,
+    /// Encoded extrinsics as hex
+    pub extrinsics: Vec<String>,
+    /// Encoded `System::Events` as hex
+    pub events: String,
+    /// Decoded output, once accepted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<GoldenOutput>,
+}
+
+/// What a block decodes to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenOutput {
+    /// Decoded extrinsics
+    pub extrinsics: Vec<DecodedExtrinsic>,
+    /// Decoded events
+    pub events: Vec<MatchedEvent>,
+}
+
+/// An extrinsic's call decoded against metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecodedExtrinsic {
+    /// Position in the block
+    pub index: u32,
+    /// Extrinsic format version
+    pub version: u8,
+    /// Whether it carries a signature
+    pub signed: bool,
+    /// Pallet of the call
+    pub pallet: String,
+    /// Call name
+    pub call: String,
+    /// Call arguments
+    pub args: JsonValue,
+}
+
+impl GoldenFixture {
+    /// File name of the fixture: `{chain}-{block}.json`
+    pub fn file_name(&self) -> String {
+        format!("{}-{}.json", self.chain, self.block_number)
+    }
+
+    /// File name of the metadata it decodes with: `{chain}-{spec_version}.scale`
+    pub fn metadata_file_name(&self) -> String {
+        format!("{}-{}.scale", self.chain, self.spec_version)
+    }
+
+    /// Read a fixture file
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| Error::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+        serde_json::from_str(&json)
+            .map_err(|e| Error::Encoding(format!("Invalid fixture {}: {}", path.display(), e)))
+    }
+
+    /// Write the fixture as pretty JSON
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json =
+            serde_json::to_string_pretty(self).map_err(|e| Error::Encoding(e.to_string()))?;
+        std::fs::write(path, json + "\n")
+            .map_err(|e| Error::Other(format!("Failed to write {}: {}", path.display(), e)))
+    }
+
+    /// Decode the recorded bytes with `metadata`
+    pub fn decode(&self, metadata: &Metadata) -> Result<GoldenOutput> {
+        let extrinsics = self
+            .extrinsics
+            .iter()
+            .enumerate()
+            .map(|(index, extrinsic)| {
+                decode_extrinsic(
+                    &hex_bytes(extrinsic, "hex in fixture")?,
+                    index as u32,
+                    metadata,
+                )
+            })
+            .collect::<Result<_>>()?;
+        let events = Events::<PolkadotConfig>::decode_from(
+            hex_bytes(&self.events, "hex in fixture")?,
+            metadata.clone(),
+        );
+        Ok(GoldenOutput {
+            extrinsics,
+            events: decode_events(&events, self.block_number, &self.block_hash)?,
+        })
+    }
+}
+
+/// Decode one encoded extrinsic, with its length prefix, against `metadata`
+///
+/// Signed extrinsics have their address, signature and extensions skipped
+/// using the types metadata gives for them.
+pub fn decode_extrinsic(bytes: &[u8], index: u32, metadata: &Metadata) -> Result<DecodedExtrinsic> {
+    let failed = |what: &str| Error::Encoding(format!("Extrinsic {}: {}", index, what));
+    let input = &mut &bytes[..];
+    Compact::<u32>::decode(input).map_err(|_| failed("bad length prefix"))?;
+    let version_byte = u8::decode(input).map_err(|_| failed("missing version"))?;
+    let signed = version_byte & SIGNED_BIT != 0;
+
+    if signed {
+        let extrinsic = metadata.extrinsic();
+        let (address_ty, signature_ty) = signer_types(metadata)?;
+        let mut skip = |ty: u32, what: &str| {
+            scale_value::scale::decode_as_type(input, ty, metadata.types())
+                .map(|_| ())
+                .map_err(|e| failed(&format!("bad {}: {}", what, e)))
+        };
+        skip(address_ty, "address")?;
+        skip(signature_ty, "signature")?;
+        for extension in extrinsic
+            .transaction_extensions_by_version(0)
+            .into_iter()
+            .flatten()
+        {
+            skip(extension.extra_ty(), extension.identifier())?;
+        }
+    }
+
+    let call_ty = metadata.outer_enums().call_enum_ty();
+    let call = scale_value::scale::decode_as_type(input, call_ty, metadata.types())
+        .map_err(|e| failed(&format!("bad call: {}", e)))?;
+    if !input.is_empty() {
+        return Err(failed(&format!("{} trailing bytes", input.len())));
+    }
+
+    // the outer call enum is `Pallet(PalletCall::call { .. })`
+    let (pallet, inner) =
+        variant_of(&value_to_json(&call)).ok_or_else(|| failed("call is not a variant"))?;
+    let (call, args) = variant_of(&inner).unwrap_or((String::new(), inner));
+    Ok(DecodedExtrinsic {
+        index,
+        version: version_byte & !SIGNED_BIT,
+        signed,
+        pallet,
+        call,
+        args,
+    })
+}
+
+/// Types of the signer address and signature in signed extrinsics
+///
+/// Read from the type parameters of the runtime's `UncheckedExtrinsic`.
+pub(crate) fn signer_types(metadata: &Metadata) -> Result<(u32, u32)> {
+    let unchecked = metadata
+        .types()
+        .types
+        .iter()
+        .find(|ty| ty.ty.path.segments.last().map(String::as_str) == Some("UncheckedExtrinsic"))
+        .ok_or_else(|| Error::Metadata("No UncheckedExtrinsic type in metadata".to_string()))?;
+    let param = |name: &str| {
+        unchecked
+            .ty
+            .type_params
+            .iter()
+            .find(|param| param.name == name)
+            .and_then(|param| param.ty.as_ref().map(|ty| ty.id))
+            .ok_or_else(|| Error::Metadata(format!("UncheckedExtrinsic has no {} type", name)))
+    };
+    Ok((param("Address")?, param("Signature")?))
+}
+
+/// Name and fields of a single-key variant object `{"Name": fields}`
+fn variant_of(value: &JsonValue) -> Option<(String, JsonValue)> {
+    match value {
+        JsonValue::Object(map) if map.len() == 1 => {
+            let (name, fields) = map.iter().next()?;
+            Some((name.clone(), fields.clone()))
+        }
+        JsonValue::String(name) => Some((name.clone(), JsonValue::Null)),
+        _ => None,
+    }
+}
+
+/// Describe the first difference between expected and actual output
+pub fn first_difference(expected: &GoldenOutput, actual: &GoldenOutput) -> Option<String> {
+    fn compare<T: PartialEq + Serialize>(
+        kind: &str,
+        expected: &[T],
+        actual: &[T],
+    ) -> Option<String> {
+        if let Some(index) =
+            (0..expected.len().min(actual.len())).find(|&i| expected[i] != actual[i])
+        {
+            let show = |item: &T| serde_json::to_string(item).unwrap_or_default();
+            return Some(format!(
+                "{} {} changed\n  expected: {}\n  actual:   {}",
+                kind,
+                index,
+                show(&expected[index]),
+                show(&actual[index])
+            ));
+        }
+        (expected.len() != actual.len()).then(|| {
+            format!(
+                "expected {} {}s, decoded {}",
+                expected.len(),
+                kind,
+                actual.len()
+            )
+        })
+    }
+    compare("extrinsic", &expected.extrinsics, &actual.extrinsics)
+        .or_else(|| compare("event", &expected.events, &actual.events))
+}
+
+/// Check a fixture against its metadata in the same directory
+///
+/// Without an expectation yet, or with [`UPDATE_ENV`] set, the current
+/// output is written back as the expectation instead.
+pub fn check_fixture(path: &Path) -> Result<()> {
+    let mut fixture = GoldenFixture::load(path)?;
+    let metadata_path = path.with_file_name(fixture.metadata_file_name());
+    let metadata = load_metadata(&metadata_path)?;
+    let actual = fixture.decode(&metadata)?;
+
+    let update = std::env::var_os(UPDATE_ENV).is_some();
+    match &fixture.expected {
+        Some(expected) if !update => match first_difference(expected, &actual) {
+            Some(difference) => Err(Error::Encoding(format!(
+                "{} no longer decodes as recorded: {}",
+                path.display(),
+                difference
+            ))),
+            None => Ok(()),
+        },
+        _ => {
+            fixture.expected = Some(actual);
+            fixture.save(path)
+        }
+    }
+}
+
+/// Check every fixture in a directory; returns how many were checked
+pub fn check_dir(dir: &Path) -> Result<usize> {
+    let fixtures = fixture_paths(dir)?;
+    for path in &fixtures {
+        check_fixture(path)?;
+    }
+    Ok(fixtures.len())
+}
+
+/// Fixture files in a directory, sorted
+fn fixture_paths(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(Error::Other(format!(
+                "Failed to list {}: {}",
+                dir.display(),
+                e
+            )))
+        }
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Read SCALE encoded metadata as returned by `state_getMetadata`
+pub fn load_metadata(path: &Path) -> Result<Metadata> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| Error::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+    Metadata::decode(&mut &bytes[..])
+        .map_err(|e| Error::Metadata(format!("Invalid metadata {}: {}", path.display(), e)))
+}
+
+/// Records golden fixtures from a connected chain
+pub struct GoldenRecorder<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> GoldenRecorder<'a> {
+    /// Record through the adapter's connection
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Raw bytes of block `number` and the metadata it was produced with
+    ///
+    /// The fixture has no expectation yet; the first check fills it in.
+    pub async fn record(&self, number: u64) -> Result<(GoldenFixture, Vec<u8>)> {
+        let spec = self.adapter.spec_client();
+        let hash = spec
+            .block_hash(number)
+            .await?
+            .ok_or_else(|| Error::Storage(format!("Block {} not found", number)))?;
+        let block_hash = format!("0x{}", hex::encode(hash.0));
+
+        let extrinsics = self
+            .adapter
+            .client()
+            .blocks()
+            .at(hash)
+            .await?
+            .extrinsics()
+            .await?
+            .iter()
+            .map(|extrinsic| format!("0x{}", hex::encode(extrinsic.bytes())))
+            .collect();
+        let events = spec
+            .storage(&storage_prefix("System", "Events"), hash)
+            .await?
+            .unwrap_or_default();
+
+        let version: RuntimeVersion = self.request("state_getRuntimeVersion", &block_hash).await?;
+        let metadata: String = self.request("state_getMetadata", &block_hash).await?;
+
+        let fixture = GoldenFixture {
+            chain: self.adapter.chain_name().to_lowercase().replace(' ', "-"),
+            spec_version: version.spec_version,
+            block_number: number,
+            block_hash,
+            extrinsics,
+            events: format!("0x{}", hex::encode(events)),
+            expected: None,
+        };
+        Ok((fixture, hex_bytes(&metadata, "hex in fixture")?))
+    }
+
+    /// Record block `number` into `dir`, writing its metadata if missing
+    ///
+    /// Returns the path of the fixture.
+    pub async fn record_to(&self, dir: &Path, number: u64) -> Result<PathBuf> {
+        let (fixture, metadata) = self.record(number).await?;
+        std::fs::create_dir_all(dir)
+            .map_err(|e| Error::Other(format!("Failed to create {}: {}", dir.display(), e)))?;
+        let metadata_path = dir.join(fixture.metadata_file_name());
+        if !metadata_path.exists() {
+            std::fs::write(&metadata_path, metadata).map_err(|e| {
+                Error::Other(format!(
+                    "Failed to write {}: {}",
+                    metadata_path.display(),
+                    e
+                ))
+            })?;
+        }
+        let path = dir.join(fixture.file_name());
+        fixture.save(&path)?;
+        Ok(path)
+    }
+
+    async fn request<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        block_hash: &str,
+    ) -> Result<T> {
+        let mut params = RpcParams::new();
+        params
+            .push(block_hash)
+            .map_err(|e| Error::Encoding(format!("Failed to encode block hash: {}", e)))?;
+        self.adapter
+            .rpc_client()
+            .request(method, params)
+            .await
+            .map_err(|e| Error::Connection(format!("{} failed: {}", method, e)))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuntimeVersion {
+    spec_version: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn extrinsic(index: u32, call: &str) -> DecodedExtrinsic {
+        DecodedExtrinsic {
+            index,
+            version: 4,
+            signed: false,
+            pallet: "Timestamp".to_string(),
+            call: call.to_string(),
+            args: json!({"now": 1_700_000_000_000u64}),
+        }
+    }
+
+    #[test]
+    fn test_first_difference() {
+        let expected = GoldenOutput {
+            extrinsics: vec![extrinsic(0, "set")],
+            events: Vec::new(),
+        };
+        assert_eq!(first_difference(&expected, &expected.clone()), None);
+
+        let changed = GoldenOutput {
+            extrinsics: vec![extrinsic(0, "set_now")],
+            events: Vec::new(),
+        };
+        let difference = first_difference(&expected, &changed).unwrap();
+        assert!(difference.starts_with("extrinsic 0 changed"));
+        assert!(difference.contains("set_now"));
+
+        let shorter = GoldenOutput {
+            extrinsics: Vec::new(),
+            events: Vec::new(),
+        };
+        assert_eq!(
+            first_difference(&expected, &shorter).as_deref(),
+            Some("expected 1 extrinsics, decoded 0")
+        );
+
+        assert_eq!(
+            variant_of(&json!({"Timestamp": {"set": {"now": 1}}})),
+            Some(("Timestamp".to_string(), json!({"set": {"now": 1}})))
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Many accounts from one mnemonic
+//!
+//! An [`HdWallet`] derives accounts on demand at index-based paths, `//0`,
+//! `//1`, ... for Substrate keys and `m/44'/60'/0'/0/<index>` for Ethereum
+//! keys. [`HdWallet::scan`] finds the accounts already in use on a set of
+//! chains, stopping after a run of unused indices as BIP-44 wallets do:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{HdWallet, KeyPairType, SubstrateAdapter};
+//!
+//! # async fn example(polkadot: &SubstrateAdapter, asset_hub: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let phrase = "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+//! let hd = HdWallet::from_mnemonic(phrase, KeyPairType::Sr25519)?.with_gap_limit(10);
+//!
+//! let scan = hd.scan(&[polkadot, asset_hub]).await?;
+//! for account in &scan.used {
+//!     println!("#{} {} used on {:?}", account.index, account.address, account.chains);
+//! }
+//!
+//! // sign with the first fresh account
+//! let wallet = hd.account(scan.next_index)?;
+//! println!("receive at {}", wallet.address());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::signer::{ApexSigner, Ed25519Signer, Sr25519Signer};
+use crate::wallet::{KeyPairType, Wallet};
+use crate::{Error, Result, SubstrateAdapter};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Default number of consecutive unused accounts that ends a scan
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// BIP-44 path of Ethereum accounts, without the address index
+const ETHEREUM_ACCOUNT_PATH: &str = "m/44'/60'/0'/0";
+
+/// An account found in use during a scan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsedAccount {
+    /// Derivation index
+    pub index: u32,
+    /// Address of the account
+    pub address: String,
+    /// Names of the chains the account has state on
+    pub chains: Vec<String>,
+}
+
+/// Accounts found by [`HdWallet::scan`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountScan {
+    /// Used accounts, by index
+    pub used: Vec<UsedAccount>,
+    /// Lowest index after the last used account
+    pub next_index: u32,
+}
+
+/// Accounts derived from one mnemonic at index-based paths
+pub struct HdWallet {
+    mnemonic: String,
+    key_type: KeyPairType,
+    path_prefix: Option<String>,
+    ss58_format: u16,
+    gap_limit: u32,
+    accounts: RwLock<BTreeMap<u32, Arc<Wallet>>>,
+}
+
+impl HdWallet {
+    /// Wallet over a BIP-39 mnemonic
+    pub fn from_mnemonic(mnemonic: &str, key_type: KeyPairType) -> Result<Self> {
+        bip39::Mnemonic::parse(mnemonic)
+            .map_err(|e| Error::Wallet(format!("Invalid mnemonic: {}", e)))?;
+        Ok(Self {
+            mnemonic: mnemonic.to_string(),
+            key_type,
+            path_prefix: None,
+            ss58_format: 42,
+            gap_limit: DEFAULT_GAP_LIMIT,
+            accounts: RwLock::new(BTreeMap::new()),
+        })
+    }
+
+    /// Derive under `prefix`, as `//<prefix>//<index>`; ignored for Ethereum keys
+    pub fn with_path_prefix(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into().trim_start_matches('/').to_string();
+        self.path_prefix = Some(prefix).filter(|prefix| !prefix.is_empty());
+        self.accounts.get_mut().clear();
+        self
+    }
+
+    /// SS58 address format of the derived accounts
+    pub fn with_ss58_format(mut self, format: u16) -> Self {
+        self.ss58_format = format;
+        self.accounts.get_mut().clear();
+        self
+    }
+
+    /// Consecutive unused accounts after which [`scan`](Self::scan) stops
+    pub fn with_gap_limit(mut self, gap_limit: u32) -> Self {
+        self.gap_limit = gap_limit.max(1);
+        self
+    }
+
+    /// Key type of the derived accounts
+    pub fn key_type(&self) -> KeyPairType {
+        self.key_type
+    }
+
+    /// Derivation path of the account at `index`
+    pub fn path(&self, index: u32) -> String {
+        match (self.key_type, &self.path_prefix) {
+            (KeyPairType::Ethereum, _) => format!("{}/{}", ETHEREUM_ACCOUNT_PATH, index),
+            (_, Some(prefix)) => format!("//{}//{}", prefix, index),
+            (_, None) => format!("//{}", index),
+        }
+    }
+
+    /// Account at `index`, derived on first use
+    pub fn account(&self, index: u32) -> Result<Arc<Wallet>> {
+        if let Some(wallet) = self.accounts.read().get(&index) {
+            return Ok(wallet.clone());
+        }
+        let path = self.path(index);
+        // Substrate paths are passed without their leading `//`
+        let path = path.strip_prefix("//").unwrap_or(&path);
+        let wallet = Arc::new(
+            Wallet::from_mnemonic_with_path(&self.mnemonic, Some(path), self.key_type)?
+                .with_ss58_format(self.ss58_format),
+        );
+        debug!("Derived account #{} at {}", index, wallet.address());
+        Ok(self.accounts.write().entry(index).or_insert(wallet).clone())
+    }
+
+    /// Address of the account at `index`
+    pub fn address(&self, index: u32) -> Result<String> {
+        Ok(self.account(index)?.address())
+    }
+
+    /// Signer for the account at `index`
+    pub fn signer(&self, index: u32) -> Result<ApexSigner> {
+        let wallet = self.account(index)?;
+        if let Some(pair) = wallet.sr25519_pair() {
+            return Ok(Sr25519Signer::new(pair.clone()).into());
+        }
+        if let Some(pair) = wallet.ed25519_pair() {
+            return Ok(Ed25519Signer::new(*pair).into());
+        }
+        Err(Error::Wallet(format!(
+            "No Substrate signer for {:?} keys",
+            self.key_type
+        )))
+    }
+
+    /// Indices of the accounts derived so far
+    pub fn derived(&self) -> Vec<u32> {
+        self.accounts.read().keys().copied().collect()
+    }
+
+    /// Find the accounts with state on any of `chains`
+    ///
+    /// An account is used when it has sent a transaction, holds a balance or
+    /// has a provider reference. Scanning stops after the gap limit of
+    /// consecutive unused accounts.
+    pub async fn scan(&self, chains: &[&SubstrateAdapter]) -> Result<AccountScan> {
+        let mut used = Vec::new();
+        let mut next_index = 0;
+        let mut index = 0;
+        while index - next_index < self.gap_limit {
+            let address = self.address(index)?;
+            let mut active = Vec::new();
+            for chain in chains {
+                let info = chain.storage().get_account_info(&address).await?;
+                if info.nonce > 0 || info.total() > 0 || info.providers > 0 {
+                    active.push(chain.chain_name().to_string());
+                }
+            }
+            if !active.is_empty() {
+                used.push(UsedAccount {
+                    index,
+                    address,
+                    chains: active,
+                });
+                next_index = index + 1;
+            }
+            index += 1;
+        }
+        debug!("Scanned {} accounts, {} in use", index, used.len());
+        Ok(AccountScan { used, next_index })
+    }
+}
+
+impl std::fmt::Debug for HdWallet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HdWallet")
+            .field("key_type", &self.key_type)
+            .field("path_prefix", &self.path_prefix)
+            .field("ss58_format", &self.ss58_format)
+            .field("derived", &self.derived())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+
+    #[test]
+    fn test_index_paths() {
+        let hd = HdWallet::from_mnemonic(PHRASE, KeyPairType::Sr25519).unwrap();
+        assert_eq!(hd.path(3), "//3");
+        let hd = hd.with_path_prefix("//polkadot");
+        assert_eq!(hd.path(3), "//polkadot//3");
+
+        let eth = HdWallet::from_mnemonic(PHRASE, KeyPairType::Ethereum).unwrap();
+        assert_eq!(eth.path(0), "m/44'/60'/0'/0/0");
+        assert!(HdWallet::from_mnemonic("not a mnemonic", KeyPairType::Sr25519).is_err());
+    }
+
+    #[test]
+    fn test_accounts_match_wallet_derivation() {
+        let hd = HdWallet::from_mnemonic(PHRASE, KeyPairType::Sr25519)
+            .unwrap()
+            .with_ss58_format(0);
+        let expected = Wallet::from_mnemonic_with_path(PHRASE, Some("1"), KeyPairType::Sr25519)
+            .unwrap()
+            .with_ss58_format(0);
+        assert_eq!(hd.address(1).unwrap(), expected.address());
+        assert_ne!(hd.address(0).unwrap(), hd.address(1).unwrap());
+        assert_eq!(hd.derived(), vec![0, 1]);
+        // derived once, then shared
+        assert!(Arc::ptr_eq(
+            &hd.account(1).unwrap(),
+            &hd.account(1).unwrap()
+        ));
+
+        let eth = HdWallet::from_mnemonic(PHRASE, KeyPairType::Ethereum).unwrap();
+        let first = Wallet::from_mnemonic(PHRASE, KeyPairType::Ethereum).unwrap();
+        assert_eq!(eth.address(0).unwrap(), first.address());
+        assert!(eth.signer(0).is_err());
+    }
+}
@@ -0,0 +1,307 @@
+//! Decoding old blocks with the metadata of their runtime
+//!
+//! The client decodes with the metadata of the runtime it connected to, so
+//! blocks from before a runtime upgrade decode wrongly or not at all once
+//! calls or events change shape. [`HistoricalDecoder`] looks up the runtime
+//! version each block was produced with, fetches that runtime's metadata
+//! with `state_getMetadata` at the block and decodes the block's extrinsics
+//! and events with it. Metadata is cached by spec version in a
+//! [`MetadataHistory`], so a range of blocks costs one metadata download per
+//! runtime upgrade it spans:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{HistoricalDecoder, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let decoder = HistoricalDecoder::new(adapter);
+//! for number in 9_000_000..9_000_010 {
+//!     let block = decoder.block(number).await?;
+//!     println!("#{} (spec {}): {} events", number, block.spec_version, block.events.len());
+//! }
+//! println!("runtimes seen: {:?}", decoder.history().spec_versions());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The node must still hold the state of the blocks, i.e. be an archive
+//! node for blocks older than its pruning window.
+
+use crate::event_query::{decode_events, MatchedEvent};
+use crate::golden::{decode_extrinsic, DecodedExtrinsic};
+use crate::rpc_spec::SpecClient;
+use crate::state_diff::storage_prefix;
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::Decode;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use subxt::events::Events;
+use subxt::ext::subxt_rpcs::client::RpcParams;
+use subxt::utils::H256;
+use subxt::{Metadata, PolkadotConfig};
+use tracing::debug;
+
+/// Metadata of past runtimes, keyed by spec version
+///
+/// Clones share the cache.
+#[derive(Clone, Default)]
+pub struct MetadataHistory {
+    metadata: Arc<RwLock<BTreeMap<u32, Metadata>>>,
+}
+
+impl MetadataHistory {
+    /// Empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the metadata of runtime `spec_version`, e.g. from a file
+    pub fn insert(&self, spec_version: u32, metadata: Metadata) {
+        self.metadata.write().insert(spec_version, metadata);
+    }
+
+    /// Metadata of runtime `spec_version`, if cached
+    pub fn get(&self, spec_version: u32) -> Option<Metadata> {
+        self.metadata.read().get(&spec_version).cloned()
+    }
+
+    /// Cached spec versions, oldest first
+    pub fn spec_versions(&self) -> Vec<u32> {
+        self.metadata.read().keys().copied().collect()
+    }
+
+    /// Spec version of the runtime that produced block `hash`
+    pub async fn spec_version_at(&self, adapter: &SubstrateAdapter, hash: H256) -> Result<u32> {
+        spec_version_at(&adapter.spec_client(), hash).await
+    }
+
+    /// Spec version and metadata of the runtime that produced block `hash`,
+    /// fetched only if not cached yet
+    pub async fn metadata_at(
+        &self,
+        adapter: &SubstrateAdapter,
+        hash: H256,
+    ) -> Result<(u32, Metadata)> {
+        let spec = adapter.spec_client();
+        let spec_version = spec_version_at(&spec, hash).await?;
+        Ok((
+            spec_version,
+            self.metadata_of(&spec, spec_version, hash).await?,
+        ))
+    }
+
+    /// Metadata of runtime `spec_version`, fetched at block `hash` of that
+    /// runtime if not cached yet
+    pub(crate) async fn metadata_of(
+        &self,
+        spec: &SpecClient,
+        spec_version: u32,
+        hash: H256,
+    ) -> Result<Metadata> {
+        if let Some(metadata) = self.get(spec_version) {
+            return Ok(metadata);
+        }
+
+        debug!("Fetching metadata of spec version {}", spec_version);
+        let encoded: String = request(spec, "state_getMetadata", hash).await?;
+        let bytes = hex::decode(encoded.trim_start_matches("0x"))
+            .map_err(|e| Error::Metadata(format!("Invalid metadata hex: {}", e)))?;
+        let metadata = Metadata::decode(&mut &bytes[..]).map_err(|e| {
+            Error::Metadata(format!(
+                "Failed to decode metadata of spec version {}: {}",
+                spec_version, e
+            ))
+        })?;
+        self.insert(spec_version, metadata.clone());
+        Ok(metadata)
+    }
+}
+
+impl std::fmt::Debug for MetadataHistory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetadataHistory")
+            .field("spec_versions", &self.spec_versions())
+            .finish()
+    }
+}
+
+/// A block decoded with the metadata of its own runtime
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoricalBlock {
+    /// Block number
+    pub number: u64,
+    /// Block hash as hex
+    pub hash: String,
+    /// Spec version of the runtime that produced the block
+    pub spec_version: u32,
+    /// Decoded extrinsics
+    pub extrinsics: Vec<DecodedExtrinsic>,
+    /// Decoded events
+    pub events: Vec<MatchedEvent>,
+}
+
+/// Decodes past blocks of the adapter's chain
+pub struct HistoricalDecoder<'a> {
+    adapter: &'a SubstrateAdapter,
+    history: MetadataHistory,
+}
+
+impl<'a> HistoricalDecoder<'a> {
+    /// Decoder with an empty metadata cache
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self {
+            adapter,
+            history: MetadataHistory::new(),
+        }
+    }
+
+    /// Share `history` as the metadata cache
+    pub fn with_history(mut self, history: MetadataHistory) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// The metadata cache
+    pub fn history(&self) -> &MetadataHistory {
+        &self.history
+    }
+
+    /// Extrinsics and events of block `number`
+    pub async fn block(&self, number: u64) -> Result<HistoricalBlock> {
+        let hash = self.block_hash(number).await?;
+        let (spec_version, metadata) = self.history.metadata_at(self.adapter, hash).await?;
+
+        let block: SignedBlock =
+            request(&self.adapter.spec_client(), "chain_getBlock", hash).await?;
+        let extrinsics = block
+            .block
+            .extrinsics
+            .iter()
+            .enumerate()
+            .map(|(index, extrinsic)| {
+                let bytes = hex::decode(extrinsic.trim_start_matches("0x")).map_err(|e| {
+                    Error::Encoding(format!("Invalid extrinsic in block {}: {}", number, e))
+                })?;
+                decode_extrinsic(&bytes, index as u32, &metadata)
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(HistoricalBlock {
+            number,
+            hash: format!("0x{}", hex::encode(hash.0)),
+            spec_version,
+            extrinsics,
+            events: self.decode_events(number, hash, metadata).await?,
+        })
+    }
+
+    /// Events of block `number`
+    pub async fn events(&self, number: u64) -> Result<Vec<MatchedEvent>> {
+        let hash = self.block_hash(number).await?;
+        let (_, metadata) = self.history.metadata_at(self.adapter, hash).await?;
+        self.decode_events(number, hash, metadata).await
+    }
+
+    async fn decode_events(
+        &self,
+        number: u64,
+        hash: H256,
+        metadata: Metadata,
+    ) -> Result<Vec<MatchedEvent>> {
+        events_at(&self.adapter.spec_client(), number, hash, metadata).await
+    }
+
+    async fn block_hash(&self, number: u64) -> Result<H256> {
+        self.adapter
+            .spec_client()
+            .block_hash(number)
+            .await?
+            .ok_or_else(|| Error::Storage(format!("Block {} not found", number)))
+    }
+}
+
+/// Decode the events of block `number`, with hash `hash`, using `metadata`
+pub(crate) async fn events_at(
+    spec: &SpecClient,
+    number: u64,
+    hash: H256,
+    metadata: Metadata,
+) -> Result<Vec<MatchedEvent>> {
+    let bytes = spec
+        .storage(&storage_prefix("System", "Events"), hash)
+        .await?
+        .unwrap_or_default();
+    let events = Events::<PolkadotConfig>::decode_from(bytes, metadata);
+    decode_events(&events, number, &format!("0x{}", hex::encode(hash.0)))
+}
+
+/// Spec version of the runtime that produced block `hash`
+pub(crate) async fn spec_version_at(spec: &SpecClient, hash: H256) -> Result<u32> {
+    let version: RuntimeVersion = request(spec, "state_getRuntimeVersion", hash).await?;
+    Ok(version.spec_version)
+}
+
+/// Call `method` at block `hash`
+async fn request<T: serde::de::DeserializeOwned>(
+    spec: &SpecClient,
+    method: &str,
+    hash: H256,
+) -> Result<T> {
+    let mut params = RpcParams::new();
+    params
+        .push(format!("0x{}", hex::encode(hash.0)))
+        .map_err(|e| Error::Encoding(format!("Failed to encode block hash: {}", e)))?;
+    spec.rpc()
+        .request(method, params)
+        .await
+        .map_err(|e| Error::Connection(format!("{} failed: {}", method, e)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuntimeVersion {
+    spec_version: u32,
+}
+
+/// `chain_getBlock` response
+#[derive(Deserialize)]
+struct SignedBlock {
+    block: BlockBody,
+}
+
+#[derive(Deserialize)]
+struct BlockBody {
+    extrinsics: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rpc_responses() {
+        let block: SignedBlock = serde_json::from_value(json!({
+            "block": {
+                "header": { "number": "0x895440", "parentHash": format!("0x{}", "00".repeat(32)) },
+                "extrinsics": ["0x280403000b", "0x1c0407"],
+            },
+            "justifications": null,
+        }))
+        .unwrap();
+        assert_eq!(block.block.extrinsics.len(), 2);
+
+        let version: RuntimeVersion = serde_json::from_value(json!({
+            "specName": "polkadot",
+            "specVersion": 1_003_000,
+            "transactionVersion": 26,
+        }))
+        .unwrap();
+        assert_eq!(version.spec_version, 1_003_000);
+
+        let history = MetadataHistory::new();
+        assert!(history.spec_versions().is_empty());
+        assert!(history.clone().get(1_003_000).is_none());
+    }
+}
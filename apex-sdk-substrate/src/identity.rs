@@ -0,0 +1,261 @@
+//! On-chain identities from the identity pallet
+//!
+//! Identities moved from the relay chains to the People system parachains;
+//! [`IdentityQuery`] reads either layout of `Identity::IdentityOf`, with or
+//! without the username the relay chain kept alongside each registration.
+//! Sub-accounts are resolved through `Identity::SuperOf`, so
+//! [`IdentityQuery::display_name`] renders them as `PARENT/sub`.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::system_chains::{RelayNetwork, SystemChain, SystemChainClient};
+//!
+//! # async fn example() -> Result<(), apex_sdk_substrate::Error> {
+//! let people = SystemChainClient::connect(RelayNetwork::Polkadot, SystemChain::People).await?;
+//! let address = "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5";
+//!
+//! if let Some(identity) = people.identity()?.identity_of(address).await? {
+//!     println!("{:?} verified: {}", identity.display, identity.is_verified());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::account_id;
+use crate::receipt::json_u128;
+use crate::{Result, SubstrateAdapter};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::dynamic::Value;
+
+/// A registrar's judgement of an identity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Judgement {
+    /// Not yet judged
+    Unknown,
+    /// Fee paid, judgement pending
+    FeePaid(u128),
+    /// Information looks correct but was not checked in depth
+    Reasonable,
+    /// Information was verified
+    KnownGood,
+    /// Information was correct but has changed since
+    OutOfDate,
+    /// Information is of low quality
+    LowQuality,
+    /// Information is wrong
+    Erroneous,
+}
+
+impl Judgement {
+    /// Whether the judgement vouches for the identity
+    pub fn is_positive(&self) -> bool {
+        matches!(self, Judgement::Reasonable | Judgement::KnownGood)
+    }
+
+    fn from_json(value: &JsonValue) -> Option<Self> {
+        if let Some(fee) = value.get("FeePaid") {
+            return Some(Judgement::FeePaid(json_u128(fee)?));
+        }
+        Some(match value.as_str()? {
+            "Unknown" => Judgement::Unknown,
+            "Reasonable" => Judgement::Reasonable,
+            "KnownGood" => Judgement::KnownGood,
+            "OutOfDate" => Judgement::OutOfDate,
+            "LowQuality" => Judgement::LowQuality,
+            "Erroneous" => Judgement::Erroneous,
+            _ => return None,
+        })
+    }
+}
+
+/// A registered identity
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identity {
+    /// Display name
+    pub display: Option<String>,
+    /// Legal name
+    pub legal: Option<String>,
+    /// Website
+    pub web: Option<String>,
+    /// Email address
+    pub email: Option<String>,
+    /// Matrix handle
+    pub matrix: Option<String>,
+    /// Twitter handle
+    pub twitter: Option<String>,
+    /// GitHub handle, on chains that store it
+    pub github: Option<String>,
+    /// Discord handle, on chains that store it
+    pub discord: Option<String>,
+    /// Judgements by registrar index
+    pub judgements: Vec<(u32, Judgement)>,
+}
+
+impl Identity {
+    /// Identity from a decoded `IdentityOf` value
+    pub fn from_registration(value: &JsonValue) -> Option<Self> {
+        // relay chains stored `(Registration, Option<Username>)`
+        let registration = match value {
+            JsonValue::Array(parts) => parts.first()?,
+            registration => registration,
+        };
+        let info = registration.get("info")?;
+        let field = |name: &str| info.get(name).and_then(data_text);
+
+        Some(Self {
+            display: field("display"),
+            legal: field("legal"),
+            web: field("web"),
+            email: field("email"),
+            matrix: field("matrix").or_else(|| field("riot")),
+            twitter: field("twitter"),
+            github: field("github"),
+            discord: field("discord"),
+            judgements: judgements(&registration["judgements"]),
+        })
+    }
+
+    /// Whether any registrar judged the identity reasonable or known good
+    pub fn is_verified(&self) -> bool {
+        self.judgements
+            .iter()
+            .any(|(_, judgement)| judgement.is_positive())
+    }
+}
+
+/// Text of an identity `Data` field; hashes and empty fields are `None`
+fn data_text(value: &JsonValue) -> Option<String> {
+    let (variant, bytes) = value.as_object()?.iter().next()?;
+    if !variant.starts_with("Raw") {
+        return None;
+    }
+    let bytes = match bytes {
+        JsonValue::String(hex_bytes) => hex::decode(hex_bytes.trim_start_matches("0x")).ok()?,
+        JsonValue::Array(values) => values
+            .iter()
+            .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+            .collect::<Option<Vec<u8>>>()?,
+        // `Raw1` holds a single byte
+        JsonValue::Number(byte) => vec![u8::try_from(byte.as_u64()?).ok()?],
+        _ => return None,
+    };
+    String::from_utf8(bytes).ok()
+}
+
+/// `(registrar, judgement)` pairs; a single pair decodes without the outer list
+fn judgements(value: &JsonValue) -> Vec<(u32, Judgement)> {
+    let pairs = match value.as_array() {
+        Some(items) if items.first().is_some_and(JsonValue::is_number) => vec![value],
+        Some(items) => items.iter().collect(),
+        None => Vec::new(),
+    };
+    pairs
+        .into_iter()
+        .filter_map(|pair| {
+            let registrar = u32::try_from(pair.get(0)?.as_u64()?).ok()?;
+            Some((registrar, Judgement::from_json(pair.get(1)?)?))
+        })
+        .collect()
+}
+
+/// Identity lookups for a connected chain
+pub struct IdentityQuery<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> IdentityQuery<'a> {
+    /// Query identities through the adapter's connection
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Identity registered for an SS58 or hex address
+    pub async fn identity_of(&self, address: &str) -> Result<Option<Identity>> {
+        let account = account_id(address)?;
+        Ok(self
+            .adapter
+            .storage()
+            .query_storage_json("Identity", "IdentityOf", vec![Value::from_bytes(account)])
+            .await?
+            .as_ref()
+            .and_then(Identity::from_registration))
+    }
+
+    /// Parent account (hex) and sub-account name, if `address` is a sub-account
+    pub async fn super_of(&self, address: &str) -> Result<Option<(String, Option<String>)>> {
+        let account = account_id(address)?;
+        let value = self
+            .adapter
+            .storage()
+            .query_storage_json("Identity", "SuperOf", vec![Value::from_bytes(account)])
+            .await?;
+        Ok(value.and_then(|value| {
+            let parent = value.get(0)?.as_str()?.to_string();
+            Some((parent, value.get(1).and_then(data_text)))
+        }))
+    }
+
+    /// Display name, as `PARENT/sub` for sub-accounts
+    pub async fn display_name(&self, address: &str) -> Result<Option<String>> {
+        if let Some(identity) = self.identity_of(address).await? {
+            return Ok(identity.display);
+        }
+        let Some((parent, sub)) = self.super_of(address).await? else {
+            return Ok(None);
+        };
+        let parent_name = self
+            .identity_of(&parent)
+            .await?
+            .and_then(|identity| identity.display);
+        Ok(parent_name.map(|name| match sub {
+            Some(sub) => format!("{}/{}", name, sub),
+            None => name,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_registration() {
+        let people = json!({
+            "judgements": [[0, "Reasonable"], [1, {"FeePaid": 100}]],
+            "deposit": 1_000,
+            "info": {
+                "display": {"Raw5": [80, 97, 114, 105, 116]},
+                "legal": "None",
+                "web": {"Raw1": 120},
+                "matrix": "None",
+                "email": {"BlakeTwo256": "0x00"},
+                "twitter": "None",
+                "github": {"Raw6": [112, 97, 114, 105, 116, 121]},
+                "discord": "None",
+            },
+        });
+        let identity = Identity::from_registration(&people).unwrap();
+        assert_eq!(identity.display.as_deref(), Some("Parit"));
+        assert_eq!(identity.web.as_deref(), Some("x"));
+        assert_eq!(identity.github.as_deref(), Some("parity"));
+        assert_eq!(identity.email, None);
+        assert_eq!(
+            identity.judgements,
+            vec![(0, Judgement::Reasonable), (1, Judgement::FeePaid(100))]
+        );
+        assert!(identity.is_verified());
+
+        // relay layout with a username and a single judgement
+        let relay = json!([{
+            "judgements": [2, "Erroneous"],
+            "deposit": 1_000,
+            "info": {"display": {"Raw3": [66, 111, 98]}, "riot": {"Raw2": [64, 98]}},
+        }, "None"]);
+        let identity = Identity::from_registration(&relay).unwrap();
+        assert_eq!(identity.display.as_deref(), Some("Bob"));
+        assert_eq!(identity.matrix.as_deref(), Some("@b"));
+        assert_eq!(identity.judgements, vec![(2, Judgement::Erroneous)]);
+        assert!(!identity.is_verified());
+    }
+}
@@ -0,0 +1,483 @@
+//! Payment requests and detecting the payments that settle them
+//!
+//! A [`PaymentRequest`] describes what a merchant expects to receive: chain,
+//! asset, amount, recipient and optionally a [`PaymentReference`] and an
+//! expiry. It travels as a URI, which is also what goes into a QR code:
+//!
+//! ```text
+//! polkadot:15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5?amount=10000000000&reference=order-2931&expires=1760000000
+//! substrate:15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5?chain=polkadot&asset=1984&amount=5000000
+//! ```
+//!
+//! The scheme names the chain, or is `substrate` with the chain in a `chain`
+//! parameter. `amount` is in the asset's smallest unit, `asset` is a
+//! `pallet-assets` id and is left out for the native token, `expires` is a
+//! unix timestamp in seconds.
+//!
+//! [`PaymentDetector`] follows finalized blocks until a matching payment
+//! arrives or the request expires:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{PaymentDetector, PaymentRequest, PaymentStatus, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter, uri: &str) -> Result<(), apex_sdk_substrate::Error> {
+//! let request = PaymentRequest::from_uri(uri)?;
+//! match PaymentDetector::new(adapter, request).wait().await? {
+//!     PaymentStatus::Paid(payment) => println!("paid in #{}", payment.block_number),
+//!     PaymentStatus::Expired => println!("request expired"),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A payment matches when it transfers at least the requested amount of the
+//! asset to the recipient and, if the request has a reference, the same
+//! extrinsic remarks it, as
+//! [`TransactionExecutor::transfer_with_reference`](crate::TransactionExecutor::transfer_with_reference)
+//! does. Foreign assets cannot be requested.
+
+use crate::assets::AssetId;
+use crate::event_query::{account_hex, block_hash_at, composite_to_json, decode_events};
+use crate::reference::{batch_reference, PaymentReference};
+use crate::{Error, MatchedEvent, Result, SubstrateAdapter};
+use apex_sdk_types::{Chain, ChainType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::time::{SystemTime, UNIX_EPOCH};
+use subxt::{OnlineClient, PolkadotConfig};
+use tracing::{debug, info};
+use url::Url;
+
+/// URI scheme naming the chain in a parameter instead
+pub const GENERIC_SCHEME: &str = "substrate";
+
+type SubxtBlock = subxt::blocks::Block<PolkadotConfig, OnlineClient<PolkadotConfig>>;
+
+/// What a payer is asked to pay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentRequest {
+    /// Chain to pay on
+    pub chain: Chain,
+    /// Asset to pay in
+    pub asset: AssetId,
+    /// Amount in the asset's smallest unit
+    pub amount: u128,
+    /// SS58 or hex address receiving the payment
+    pub recipient: String,
+    /// Reference the payment must carry
+    pub reference: Option<PaymentReference>,
+    /// Unix timestamp in seconds after which the request is void
+    pub expires_at: Option<u64>,
+}
+
+impl PaymentRequest {
+    /// Request for `amount` of the native token on `chain`
+    pub fn new(chain: Chain, recipient: impl Into<String>, amount: u128) -> Result<Self> {
+        if chain.chain_type() == ChainType::Evm {
+            return Err(Error::Other(format!(
+                "{} is not a Substrate chain",
+                chain.name()
+            )));
+        }
+        let recipient = recipient.into();
+        account_hex(&recipient)?;
+        Ok(Self {
+            chain,
+            asset: AssetId::Native,
+            amount,
+            recipient,
+            reference: None,
+            expires_at: None,
+        })
+    }
+
+    /// Ask for a `pallet-assets` asset instead of the native token
+    pub fn with_asset(mut self, id: u32) -> Self {
+        self.asset = AssetId::Local(id);
+        self
+    }
+
+    /// Require the payment to carry `reference`
+    pub fn with_reference(mut self, reference: PaymentReference) -> Self {
+        self.reference = Some(reference);
+        self
+    }
+
+    /// Void the request after the unix timestamp `expires_at`, in seconds
+    pub fn with_expiry(mut self, expires_at: u64) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Whether the request is void at unix time `now`
+    pub fn is_expired_at(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now > expires_at)
+    }
+
+    /// URI with the chain-specific scheme, e.g. `polkadot:`
+    pub fn to_uri(&self) -> Result<String> {
+        self.uri(&chain_scheme(&self.chain), false)
+    }
+
+    /// URI with the `substrate:` scheme and the chain as a parameter
+    pub fn to_generic_uri(&self) -> Result<String> {
+        self.uri(GENERIC_SCHEME, true)
+    }
+
+    fn uri(&self, scheme: &str, with_chain: bool) -> Result<String> {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        if with_chain {
+            query.append_pair("chain", &chain_scheme(&self.chain));
+        }
+        match &self.asset {
+            AssetId::Native => {}
+            AssetId::Local(id) => {
+                query.append_pair("asset", &id.to_string());
+            }
+            AssetId::Foreign(_) => {
+                return Err(Error::Other(
+                    "Foreign assets cannot be requested by URI".to_string(),
+                ))
+            }
+        }
+        query.append_pair("amount", &self.amount.to_string());
+        if let Some(reference) = &self.reference {
+            query.append_pair("reference", reference.as_str());
+        }
+        if let Some(expires_at) = self.expires_at {
+            query.append_pair("expires", &expires_at.to_string());
+        }
+        Ok(format!("{}:{}?{}", scheme, self.recipient, query.finish()))
+    }
+
+    /// Parse a chain-specific or `substrate:` payment URI
+    pub fn from_uri(uri: &str) -> Result<Self> {
+        let invalid =
+            |reason: &str| Error::Other(format!("Invalid payment URI {}: {}", uri, reason));
+        let url = Url::parse(uri).map_err(|e| invalid(&e.to_string()))?;
+
+        let mut chain = None;
+        if url.scheme() != GENERIC_SCHEME {
+            chain = Some(
+                Chain::from_str_case_insensitive(url.scheme())
+                    .ok_or_else(|| invalid("unknown chain scheme"))?,
+            );
+        }
+        let mut asset = AssetId::Native;
+        let mut amount = None;
+        let mut reference = None;
+        let mut expires_at = None;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "chain" if chain.is_none() => {
+                    chain = Some(
+                        Chain::from_str_case_insensitive(&value)
+                            .ok_or_else(|| invalid("unknown chain"))?,
+                    );
+                }
+                "asset" => {
+                    asset = AssetId::Local(value.parse().map_err(|_| invalid("bad asset"))?);
+                }
+                "amount" => amount = Some(value.parse().map_err(|_| invalid("bad amount"))?),
+                "reference" => reference = Some(PaymentReference::new(value.as_ref())?),
+                "expires" => {
+                    expires_at = Some(value.parse().map_err(|_| invalid("bad expiry"))?);
+                }
+                _ => debug!("Ignoring payment URI parameter {}", key),
+            }
+        }
+
+        let chain = chain.ok_or_else(|| invalid("missing chain"))?;
+        let amount = amount.ok_or_else(|| invalid("missing amount"))?;
+        let mut request = Self::new(chain, url.path(), amount)?;
+        request.asset = asset;
+        request.reference = reference;
+        request.expires_at = expires_at;
+        Ok(request)
+    }
+
+    /// Sender and amount of `event` if it pays this request
+    fn paid_by(&self, event: &MatchedEvent, recipient: &str) -> Option<(String, u128)> {
+        let fields = &event.fields;
+        let matches_asset = match &self.asset {
+            AssetId::Native => event.pallet == "Balances" && event.variant == "Transfer",
+            AssetId::Local(id) => {
+                event.pallet == "Assets"
+                    && event.variant == "Transferred"
+                    && fields["asset_id"].as_u64() == Some(u64::from(*id))
+            }
+            AssetId::Foreign(_) => false,
+        };
+        if !matches_asset || !fields["to"].as_str()?.eq_ignore_ascii_case(recipient) {
+            return None;
+        }
+        let amount = match &fields["amount"] {
+            JsonValue::Number(n) => u128::from(n.as_u64()?),
+            JsonValue::String(s) => s.parse().ok()?,
+            _ => return None,
+        };
+        if amount < self.amount {
+            return None;
+        }
+        Some((fields["from"].as_str()?.to_string(), amount))
+    }
+}
+
+/// Lowercase chain name used as its URI scheme
+fn chain_scheme(chain: &Chain) -> String {
+    chain.name().to_lowercase()
+}
+
+/// A payment settling a [`PaymentRequest`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceivedPayment {
+    /// Block including the payment
+    pub block_number: u64,
+    /// Hash of that block
+    pub block_hash: String,
+    /// Index of the paying extrinsic in the block
+    pub extrinsic_index: u32,
+    /// Hex account id of the payer
+    pub from: String,
+    /// Amount received, at least the requested amount
+    pub amount: u128,
+}
+
+/// How a watched request ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaymentStatus {
+    /// A matching payment was finalized
+    Paid(ReceivedPayment),
+    /// The request expired before it was paid
+    Expired,
+}
+
+/// Watches for the payment settling a [`PaymentRequest`]
+pub struct PaymentDetector<'a> {
+    adapter: &'a SubstrateAdapter,
+    request: PaymentRequest,
+}
+
+impl<'a> PaymentDetector<'a> {
+    /// Detector for `request`
+    pub fn new(adapter: &'a SubstrateAdapter, request: PaymentRequest) -> Self {
+        Self { adapter, request }
+    }
+
+    /// The request being watched
+    pub fn request(&self) -> &PaymentRequest {
+        &self.request
+    }
+
+    /// First payment in blocks `from..=to`, e.g. those finalized since the
+    /// request was issued
+    pub async fn scan(&self, from: u64, to: u64) -> Result<Option<ReceivedPayment>> {
+        let recipient = self.recipient()?;
+        for number in from..=to {
+            let hash = block_hash_at(self.adapter.rpc_client(), number).await?;
+            let block =
+                self.adapter.client().blocks().at(hash).await.map_err(|e| {
+                    Error::Connection(format!("Failed to get block {}: {}", number, e))
+                })?;
+            if let Some(payment) = self.find_in(&block, &recipient).await? {
+                return Ok(Some(payment));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Follow finalized blocks until the request is paid or expires
+    pub async fn wait(&self) -> Result<PaymentStatus> {
+        let recipient = self.recipient()?;
+        let mut blocks = self
+            .adapter
+            .client()
+            .blocks()
+            .subscribe_finalized()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to subscribe to blocks: {}", e)))?;
+
+        info!("Waiting for payment to {}", self.request.recipient);
+        while let Some(block) = blocks.next().await {
+            let block = block
+                .map_err(|e| Error::Connection(format!("Block subscription failed: {}", e)))?;
+            if let Some(payment) = self.find_in(&block, &recipient).await? {
+                info!("Payment received in #{}", payment.block_number);
+                return Ok(PaymentStatus::Paid(payment));
+            }
+            if self.request.is_expired_at(unix_now()) {
+                return Ok(PaymentStatus::Expired);
+            }
+        }
+        Err(Error::Connection(
+            "Block subscription ended before the request was paid".to_string(),
+        ))
+    }
+
+    fn recipient(&self) -> Result<String> {
+        if let AssetId::Foreign(_) = self.request.asset {
+            return Err(Error::Other(
+                "Payments in foreign assets cannot be detected".to_string(),
+            ));
+        }
+        account_hex(&self.request.recipient)
+    }
+
+    /// First payment of the request in `block`
+    async fn find_in(
+        &self,
+        block: &SubxtBlock,
+        recipient: &str,
+    ) -> Result<Option<ReceivedPayment>> {
+        let number = block.number() as u64;
+        let block_hash = format!("0x{}", hex::encode(block.hash().0));
+        let events = block.events().await.map_err(|e| {
+            Error::Connection(format!("Failed to get events of block {}: {}", number, e))
+        })?;
+
+        for event in decode_events(&events, number, &block_hash)? {
+            let Some(extrinsic_index) = event.extrinsic_index else {
+                continue;
+            };
+            let Some((from, amount)) = self.request.paid_by(&event, recipient) else {
+                continue;
+            };
+            if let Some(reference) = &self.request.reference {
+                if !self
+                    .carries_reference(block, extrinsic_index, reference)
+                    .await?
+                {
+                    continue;
+                }
+            }
+            return Ok(Some(ReceivedPayment {
+                block_number: number,
+                block_hash,
+                extrinsic_index,
+                from,
+                amount,
+            }));
+        }
+        Ok(None)
+    }
+
+    /// Whether the extrinsic at `index` remarks `reference`
+    async fn carries_reference(
+        &self,
+        block: &SubxtBlock,
+        index: u32,
+        reference: &PaymentReference,
+    ) -> Result<bool> {
+        let extrinsics = block
+            .extrinsics()
+            .await
+            .map_err(|e| Error::Transaction(format!("Failed to get extrinsics: {}", e)))?;
+        let Some(extrinsic) = extrinsics.iter().find(|e| e.index() == index) else {
+            return Ok(false);
+        };
+        let (Ok(pallet), Ok(call)) = (extrinsic.pallet_name(), extrinsic.variant_name()) else {
+            return Ok(false);
+        };
+        let fields = extrinsic
+            .field_values()
+            .map(|fields| composite_to_json(&fields))
+            .unwrap_or(JsonValue::Null);
+        Ok(batch_reference(pallet, call, &fields).as_ref() == Some(reference))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const ALICE_HEX: &str = "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+
+    fn event(pallet: &str, variant: &str, fields: JsonValue) -> MatchedEvent {
+        MatchedEvent {
+            block_number: 1,
+            block_hash: "0x00".to_string(),
+            event_index: 0,
+            extrinsic_index: Some(1),
+            pallet: pallet.to_string(),
+            variant: variant.to_string(),
+            fields,
+        }
+    }
+
+    #[test]
+    fn test_uri_round_trip() {
+        let request = PaymentRequest::new(Chain::Polkadot, ALICE, 10_000_000_000)
+            .unwrap()
+            .with_reference(PaymentReference::new("order 2931/a").unwrap())
+            .with_expiry(1_760_000_000);
+        let uri = request.to_uri().unwrap();
+        assert!(uri.starts_with(&format!("polkadot:{}?amount=10000000000&", ALICE)));
+        assert_eq!(PaymentRequest::from_uri(&uri).unwrap(), request);
+
+        let asset = PaymentRequest::new(Chain::Kusama, ALICE, 5)
+            .unwrap()
+            .with_asset(1984);
+        let generic = asset.to_generic_uri().unwrap();
+        assert_eq!(
+            generic,
+            format!("substrate:{}?chain=kusama&asset=1984&amount=5", ALICE)
+        );
+        assert_eq!(PaymentRequest::from_uri(&generic).unwrap(), asset);
+    }
+
+    #[test]
+    fn test_rejects_invalid_requests() {
+        assert!(PaymentRequest::new(Chain::Ethereum, ALICE, 1).is_err());
+        assert!(PaymentRequest::new(Chain::Polkadot, "not-an-address", 1).is_err());
+        assert!(PaymentRequest::from_uri(&format!("dogecoin:{}?amount=1", ALICE)).is_err());
+        assert!(PaymentRequest::from_uri(&format!("substrate:{}?amount=1", ALICE)).is_err());
+        assert!(PaymentRequest::from_uri(&format!("polkadot:{}", ALICE)).is_err());
+    }
+
+    #[test]
+    fn test_matches_payments() {
+        let request = PaymentRequest::new(Chain::Polkadot, ALICE, 100).unwrap();
+        let transfer = |amount: JsonValue| {
+            event(
+                "Balances",
+                "Transfer",
+                json!({"from": "0x01", "to": ALICE_HEX, "amount": amount}),
+            )
+        };
+        assert_eq!(
+            request.paid_by(&transfer(json!(150)), ALICE_HEX),
+            Some(("0x01".to_string(), 150))
+        );
+        assert_eq!(request.paid_by(&transfer(json!(99)), ALICE_HEX), None);
+
+        let asset = request.clone().with_asset(1984);
+        let transferred = |id: u32| {
+            event(
+                "Assets",
+                "Transferred",
+                json!({"asset_id": id, "from": "0x01", "to": ALICE_HEX, "amount": 100}),
+            )
+        };
+        assert!(asset.paid_by(&transferred(1984), ALICE_HEX).is_some());
+        assert!(asset.paid_by(&transferred(1337), ALICE_HEX).is_none());
+        assert!(request.paid_by(&transferred(1984), ALICE_HEX).is_none());
+    }
+
+    #[test]
+    fn test_expiry() {
+        let request = PaymentRequest::new(Chain::Polkadot, ALICE, 1)
+            .unwrap()
+            .with_expiry(100);
+        assert!(!request.is_expired_at(100));
+        assert!(request.is_expired_at(101));
+    }
+}
@@ -10,48 +10,235 @@
 //! - Caching
 //! - Metrics collection
 
+use apex_sdk_core::screening::AddressScreener;
+use apex_sdk_core::stream::StreamConfig;
 use apex_sdk_core::{
-    BlockInfo, Broadcaster, ConfirmationStrategy, NonceManager, Provider as CoreProvider,
+    ipc_path, BlockInfo, BlockNumberError, BlockNumberWidth, Broadcaster, ClientConfig,
+    ConfirmationStrategy, Localized, Message, NonceManager, Provider as CoreProvider,
     ReceiptWatcher, SdkError,
 };
 use apex_sdk_types::{Address, TransactionStatus, TxStatus};
 use async_trait::async_trait;
+use std::sync::Arc;
+use subxt::backend::rpc::RpcClient;
 use subxt::{OnlineClient, PolkadotConfig};
 use thiserror::Error;
 use tracing::{debug, info};
 
+pub mod account;
+pub mod account20;
+pub mod asset_trap;
+pub mod assets;
+pub mod audit_log;
+pub mod auto_compound;
+pub mod batch_chunks;
 pub mod block;
+pub mod block_limits;
 pub mod cache;
+pub mod capabilities;
+pub mod causality;
+pub mod chain_monitor;
+pub mod chain_spec;
+pub mod chain_time;
+pub mod collectives;
 pub mod contracts;
+pub mod coretime;
+pub mod crowdloan;
+pub mod decode_router;
+pub mod delegation;
+pub mod derivation;
+pub mod dev;
+pub mod dex;
+pub mod equivocation;
+pub mod event_query;
+pub mod expiry;
+pub mod fee_advisor;
+pub mod fee_regression;
+pub mod fuzzing;
+pub mod golden;
+pub mod hd_wallet;
+pub mod historical;
+pub mod identity;
+pub mod invoice;
+pub mod liveness;
+pub mod mempool;
+pub mod metadata_hash;
 pub mod metrics;
+pub mod migration;
+pub mod mortality;
+pub mod multisig;
+pub mod node_admin;
 pub mod nonce_manager;
+pub mod onboarding;
+pub mod pallets;
+pub mod pause;
+pub mod permissions;
+pub mod policy;
 pub mod pool;
+pub mod receipt;
+pub mod reference;
+pub mod referenda;
+pub mod rpc_middleware;
+pub mod rpc_spec;
+pub mod rules;
+pub mod runtime_upgrade;
+pub mod scheduler;
+pub mod session_keys;
+pub mod short_metadata;
 pub mod signer;
+pub mod simulator;
+pub mod slash_monitor;
+pub mod sovereign;
+pub mod sponsored;
+pub mod state_diff;
 pub mod storage;
+pub mod storage_watch;
+pub mod subscription;
+pub mod sudo;
+pub mod system_chains;
+pub mod teleport;
+pub mod template;
 pub mod transaction;
+pub mod transport;
+pub mod unlock_schedule;
+pub mod uos;
+pub mod validator_stats;
+pub mod vcr;
 pub mod wallet;
+pub mod watch_only;
 pub mod xcm;
+pub mod xcm_decode;
+pub mod xcm_transact;
 
+#[cfg(feature = "web")]
+pub mod extension;
 #[cfg(feature = "typed")]
 pub mod metadata;
+#[cfg(feature = "walletconnect")]
+pub mod walletconnect;
 
+pub use account::{
+    AccountClassification, AccountKind, AccountQuery, SovereignAccount, SovereignKind,
+};
+pub use account20::{AccountId20, AccountIdKind};
+pub use asset_trap::{AssetTrap, TrappedAssets};
+pub use assets::{AssetRegistry, KnownAsset};
+pub use audit_log::{
+    AuditEntry, AuditLog, AuditOperation, AuditOutcome, AuditWriter, JsonLinesWriter,
+    MemoryAuditWriter,
+};
+pub use auto_compound::{
+    AutoCompounder, CompoundAction, CompoundConfig, CompoundHandler, CompoundStep, StepOutcome,
+};
+pub use batch_chunks::{BatchChunk, ChunkOutcome, ChunkedBatch};
 pub use block::BlockQuery;
+pub use block_limits::{BlockLimits, BlockResource, DispatchClass, Weight};
 pub use cache::{Cache, CacheConfig};
+pub use capabilities::{Capabilities, FeeStrategy, StrategyChoice};
+pub use causality::{CallFrame, CallTrace, CausalityTracer, EventOrigin};
+pub use chain_monitor::{
+    ChainAlert, ChainAlertHandler, ChainAlertStream, ChainCondition, ChainMonitor,
+    HealthThresholds, HealthTracker,
+};
+pub use chain_spec::{ChainSpec, Genesis, GenesisAccount, GenesisAuthorities};
+pub use chain_time::{BlockClock, ChainTime};
+pub use collectives::{Collectives, MotionVotes, RankedMember, RankedVote, SocietyMember};
 pub use contracts::{
     parse_metadata, ContractCallBuilder, ContractClient, ContractMetadata, GasLimit,
     StorageDepositLimit,
 };
+pub use coretime::{CoreMask, Coretime, PriceAdapter, Region, RegionId, SaleInfo};
+pub use crowdloan::{Contribution, CrowdloanHistory, FundInfo, LeaseWon};
+pub use decode_router::{DecodeRouter, SpecRange};
+pub use delegation::{Delegate, Delegation, DelegationExplorer, DelegationGraph};
+pub use derivation::PureProxyCreation;
+pub use dev::{DevAccount, DevMode};
+pub use dex::{Dex, LpPosition, PoolReserves, Quote, RouteVolume, SwapExecution, SwapVolume};
+pub use equivocation::{EquivocationReport, EquivocationReporter};
+pub use event_query::{EventQuery, MatchedEvent};
+pub use expiry::{ExpiryHandler, ExpiryNotice, ExpiryStatus, ExpiryTracker};
+pub use fee_advisor::{BlockSample, FeeAdvisor, TipSuggestion};
+pub use fee_regression::{FeeRegression, FeeRegressionReport, FeeSample};
+pub use hd_wallet::{AccountScan, HdWallet, UsedAccount};
+pub use historical::{HistoricalBlock, HistoricalDecoder, MetadataHistory};
+pub use identity::{Identity, IdentityQuery, Judgement};
+pub use invoice::{PaymentDetector, PaymentRequest, PaymentStatus, ReceivedPayment};
+pub use liveness::{LivenessMonitor, ValidatorLiveness};
+pub use mempool::{Mempool, PendingExtrinsic, PoolExit};
+pub use metadata_hash::{ExtensionData, MetadataHashInfo, MetadataHasher};
 pub use metrics::{Metrics, MetricsSnapshot};
+pub use migration::{Migration, MigrationPlan, MigrationSigner, MigrationStep, StepStatus};
+pub use mortality::{Era, Lifetime, MortalityChecker};
+#[cfg(feature = "webhook")]
+pub use multisig::WebhookNotifier;
+pub use multisig::{
+    FileMultisigStore, MemoryMultisigStore, MultisigAccount, MultisigCoordinator, MultisigNotice,
+    MultisigProposal, MultisigState, MultisigStore, NoticeKind, ProposalStatus, SignatoryNotifier,
+    Timepoint,
+};
+pub use node_admin::{NodeAdmin, NodeHealth, PeerInfo, SyncState};
 pub use nonce_manager::SubstrateNonceManager;
+pub use onboarding::{GaslessOnboarding, OnboardingBlocker, OnboardingReceipt, OnboardingStatus};
+pub use pallets::PalletFeatures;
+pub use pause::PauseState;
+pub use permissions::{CallCheck, CallPermission, Restriction};
+pub use policy::{
+    ApprovalHandler, ApprovalRequest, Decision, LimitAction, MemorySpendStore, Outflow,
+    OutgoingTransaction, OverrideToken, Policy, Role, Rule, SpendStore, VelocityLimit,
+};
 pub use pool::{ConnectionPool, PoolConfig};
+pub use receipt::{ExtrinsicReceipt, SummaryFormat};
+pub use reference::{PaymentReference, ReferenceScanner, ReferencedTransfer};
+pub use referenda::{
+    Conviction, HypotheticalVote, ReferendaSimulator, ReferendumOutcome, TrackInfo, VoteSimulation,
+};
+pub use rpc_middleware::{
+    LayeredRpcClient, Next, RpcLayer, RpcLayers, RpcRequest, RpcResult, TracingLayer,
+};
+pub use rpc_spec::{Broadcast, FinalizedHeads, RpcCapabilities, SpecClient};
+pub use rules::{Comparison, Condition, Predicate, PriceOracle, RulesEngine, TriggerRule};
+pub use runtime_upgrade::{
+    Enactment, RuntimeUpgrade, UpgradePath, UpgradeSubmission, WasmRuntime, WasmRuntimeVersion,
+};
+pub use scheduler::{
+    CronSchedule, FileScheduleStore, JobState, MemoryScheduleStore, MissedRunPolicy, PlannedRun,
+    RunHandler, RunOutcome, RunReport, ScheduleStore, ScheduledJob, Scheduler, SchedulerState,
+    Trigger,
+};
+pub use session_keys::{SessionKey, SessionKeyCheck, SessionKeys};
+pub use short_metadata::ShortMetadata;
 pub use signer::{ApexSigner, Ed25519Signer, Sr25519Signer};
+pub use simulator::{DryRunCall, DryRunOutcome, Simulator};
+pub use slash_monitor::{AlertHandler, AlertPayload, Consensus, SlashMonitor, StakingAlert};
+pub use sovereign::{FundingPlan, SovereignFunding};
+pub use sponsored::{
+    FileRelayedCallStore, MemoryRelayedCallStore, RelayedCallStore, Sponsor, SponsoredCall,
+};
+pub use state_diff::{ChangeKind, StateDiff, StorageChange, StorageDiff};
 pub use storage::{AccountInfo, StorageClient, StorageQuery};
+pub use storage_watch::{
+    StorageAlert, StorageAlertHandler, StorageWatch, StorageWatcher, WatchCondition, WatchPredicate,
+};
+pub use subscription::{AckEventStream, EventId, FinalizedBlock, ResumableSubscription};
+pub use sudo::{confirm_root_action, RootConfirmation, Sudo, SudoOutcome};
+pub use system_chains::{RelayNetwork, SystemChain, SystemChainClient};
+pub use teleport::TeleportRegistry;
+pub use template::{TemplateLibrary, TxTemplate};
 pub use transaction::{BatchCall, BatchMode, FeeConfig, RetryConfig, TransactionExecutor};
+pub use unlock_schedule::{LockSource, UnlockCalculator, UnlockMilestone, UnlockSchedule};
+pub use uos::{Frame, FrameDecoder};
+pub use validator_stats::{EraPerformance, SlashRecord, ValidatorAnalytics, ValidatorStats};
+pub use vcr::{Cassette, Interaction, Vcr, VcrMode};
 pub use wallet::{KeyPairType, Wallet, WalletManager};
+pub use watch_only::{
+    PendingTransaction, SigningRequest, TransactionPayload, UosCrypto, WatchOnlyAccount,
+};
 pub use xcm::{
     AssetId, Fungibility, Junction, MultiLocation, NetworkId, WeightLimit, XcmAsset, XcmConfig,
     XcmExecutor, XcmTransferType, XcmVersion,
 };
+pub use xcm_decode::{AssetAmount, XcmDecoder, XcmInstruction, XcmMessage};
+pub use xcm_transact::{OriginKind, RemoteCall, RemoteOutcome, TransactSender};
 
 /// Maximum number of blocks to search when looking up transaction history
 const MAX_BLOCK_SEARCH_DEPTH: u32 = 100;
@@ -83,6 +270,62 @@ pub enum Error {
     #[error("Subxt error: {0}")]
     Subxt(Box<subxt::Error>),
 
+    #[error(
+        "Pallet {pallet} is not available on this chain{}",
+        pallets::suggestion(.alternatives)
+    )]
+    PalletNotAvailable {
+        /// Pallet the operation needs
+        pallet: String,
+        /// Pallets on this chain offering similar functionality
+        alternatives: Vec<String>,
+    },
+
+    #[error("Extrinsic exceeds the block {resource} limit: {required} > {limit}")]
+    ExhaustsResources {
+        /// Resource the extrinsic needs too much of
+        resource: BlockResource,
+        /// Amount the extrinsic needs
+        required: u64,
+        /// Most a block accepts for the extrinsic's dispatch class
+        limit: u64,
+    },
+
+    #[error("Rejected by policy: {0}")]
+    PolicyViolation(String),
+
+    #[error(
+        "{from} and {to} do not trust each other to teleport {asset}; use a reserve transfer instead"
+    )]
+    TeleportNotTrusted {
+        /// Asset to teleport, relative to the origin
+        asset: String,
+        /// Chain the teleport starts on
+        from: String,
+        /// Destination chain
+        to: String,
+    },
+
+    #[error("{pallet}::{call} would be filtered: {reason}")]
+    ChainPaused {
+        /// Pallet of the filtered call
+        pallet: String,
+        /// Name of the filtered call
+        call: String,
+        /// Safe mode or paused call
+        reason: String,
+    },
+
+    #[error(
+        "Transaction {extrinsic_hash} expired after block {valid_until} without being included"
+    )]
+    Expired {
+        /// Hash of the expired extrinsic (hex)
+        extrinsic_hash: String,
+        /// Last block it could have been included in
+        valid_until: u64,
+    },
+
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -93,6 +336,68 @@ impl From<subxt::Error> for Error {
     }
 }
 
+impl From<BlockNumberError> for Error {
+    fn from(err: BlockNumberError) -> Self {
+        Error::Encoding(err.to_string())
+    }
+}
+
+impl Localized for Error {
+    fn message(&self) -> Message {
+        let message = |code| Message::new(code, self.to_string());
+        match self {
+            Error::Connection(detail) => message("substrate.connection").with_arg("detail", detail),
+            Error::Transaction(detail) => {
+                message("substrate.transaction").with_arg("detail", detail)
+            }
+            Error::Metadata(detail) => message("substrate.metadata").with_arg("detail", detail),
+            Error::Storage(detail) => message("substrate.storage").with_arg("detail", detail),
+            Error::Wallet(detail) => message("substrate.wallet").with_arg("detail", detail),
+            Error::Signature(detail) => message("substrate.signature").with_arg("detail", detail),
+            Error::Encoding(detail) => message("substrate.encoding").with_arg("detail", detail),
+            Error::Subxt(err) => message("substrate.subxt").with_arg("detail", err),
+            Error::PalletNotAvailable {
+                pallet,
+                alternatives,
+            } => message("substrate.pallet_not_available")
+                .with_arg("pallet", pallet)
+                .with_arg("alternatives", alternatives.join(", ")),
+            Error::ExhaustsResources {
+                resource,
+                required,
+                limit,
+            } => message("substrate.exhausts_resources")
+                .with_arg("resource", resource)
+                .with_arg("required", required)
+                .with_arg("limit", limit),
+            Error::PolicyViolation(detail) => {
+                message("substrate.policy_violation").with_arg("detail", detail)
+            }
+            Error::TeleportNotTrusted { asset, from, to } => {
+                message("substrate.teleport_not_trusted")
+                    .with_arg("asset", asset)
+                    .with_arg("from", from)
+                    .with_arg("to", to)
+            }
+            Error::ChainPaused {
+                pallet,
+                call,
+                reason,
+            } => message("substrate.chain_paused")
+                .with_arg("pallet", pallet)
+                .with_arg("call", call)
+                .with_arg("reason", reason),
+            Error::Expired {
+                extrinsic_hash,
+                valid_until,
+            } => message("substrate.expired")
+                .with_arg("extrinsic_hash", extrinsic_hash)
+                .with_arg("valid_until", valid_until),
+            Error::Other(detail) => message("substrate.other").with_arg("detail", detail),
+        }
+    }
+}
+
 impl From<Error> for SdkError {
     fn from(err: Error) -> Self {
         match err {
@@ -104,6 +409,12 @@ impl From<Error> for SdkError {
             Error::Signature(msg) => SdkError::SignerError(msg),
             Error::Encoding(msg) => SdkError::TransactionError(msg),
             Error::Subxt(err) => SdkError::ProviderError(err.to_string()),
+            e @ Error::PalletNotAvailable { .. } => SdkError::NotImplemented(e.to_string()),
+            e @ Error::ExhaustsResources { .. } => SdkError::TransactionError(e.to_string()),
+            Error::PolicyViolation(msg) => SdkError::TransactionError(msg),
+            e @ Error::TeleportNotTrusted { .. } => SdkError::TransactionError(e.to_string()),
+            e @ Error::ChainPaused { .. } => SdkError::TransactionError(e.to_string()),
+            e @ Error::Expired { .. } => SdkError::TransactionError(e.to_string()),
             Error::Other(msg) => SdkError::ProviderError(msg),
         }
     }
@@ -125,6 +436,12 @@ pub struct ChainConfig {
     pub token_symbol: String,
     /// Token decimals
     pub token_decimals: u8,
+    /// Credentials and headers sent when connecting
+    pub client: ClientConfig,
+    /// Middleware every RPC request goes through
+    pub rpc_layers: RpcLayers,
+    /// Buffering of subscription streams
+    pub stream: StreamConfig,
 }
 
 impl ChainConfig {
@@ -136,6 +453,9 @@ impl ChainConfig {
             ss58_prefix: 0,
             token_symbol: "DOT".to_string(),
             token_decimals: 10,
+            client: ClientConfig::default(),
+            rpc_layers: RpcLayers::default(),
+            stream: StreamConfig::default(),
         }
     }
 
@@ -147,6 +467,9 @@ impl ChainConfig {
             ss58_prefix: 2,
             token_symbol: "KSM".to_string(),
             token_decimals: 12,
+            client: ClientConfig::default(),
+            rpc_layers: RpcLayers::default(),
+            stream: StreamConfig::default(),
         }
     }
 
@@ -158,6 +481,9 @@ impl ChainConfig {
             ss58_prefix: 42,
             token_symbol: "WND".to_string(),
             token_decimals: 12,
+            client: ClientConfig::default(),
+            rpc_layers: RpcLayers::default(),
+            stream: StreamConfig::default(),
         }
     }
 
@@ -169,6 +495,9 @@ impl ChainConfig {
             ss58_prefix: 42,
             token_symbol: "PAS".to_string(),
             token_decimals: 10,
+            client: ClientConfig::default(),
+            rpc_layers: RpcLayers::default(),
+            stream: StreamConfig::default(),
         }
     }
 
@@ -180,22 +509,88 @@ impl ChainConfig {
             ss58_prefix,
             token_symbol: "UNIT".to_string(),
             token_decimals: 12,
+            client: ClientConfig::default(),
+            rpc_layers: RpcLayers::default(),
+            stream: StreamConfig::default(),
+        }
+    }
+
+    /// Create configuration for a system parachain of a relay network
+    ///
+    /// System chains share the relay chain's address format and token.
+    /// Returns `None` if the network does not run that chain.
+    pub fn system_chain(network: RelayNetwork, chain: SystemChain) -> Option<Self> {
+        let endpoint = chain.endpoint(network)?;
+        let relay = network.relay_config();
+        Some(Self {
+            name: format!("{} {}", relay.name, chain.title()),
+            endpoint,
+            ..relay
+        })
+    }
+
+    /// Look up a known chain by name, e.g. `polkadot` or `kusama-people`
+    pub fn preset(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        match name.as_str() {
+            "polkadot" => return Some(Self::polkadot()),
+            "kusama" => return Some(Self::kusama()),
+            "westend" => return Some(Self::westend()),
+            "paseo" => return Some(Self::paseo()),
+            _ => {}
         }
+        let (network, chain) = name.split_once('-')?;
+        Self::system_chain(network.parse().ok()?, chain.parse().ok()?)
+    }
+
+    /// Attach credentials and custom headers to the connection
+    pub fn with_client_config(mut self, client: ClientConfig) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Send every RPC request through `layer`, inside the layers added before
+    pub fn with_rpc_layer(mut self, layer: impl RpcLayer + 'static) -> Self {
+        self.rpc_layers.push(layer);
+        self
+    }
+
+    /// Buffer subscription streams, such as [`ResumableSubscription`] and
+    /// [`ChainMonitor::stream`], as `stream` says
+    pub fn with_stream_config(mut self, stream: StreamConfig) -> Self {
+        self.stream = stream;
+        self
     }
 }
 
 /// Substrate blockchain adapter
+///
+/// Cloning is cheap: the subxt client and metrics are reference counted, so
+/// clones share one connection and one set of counters.
+#[derive(Clone)]
 pub struct SubstrateAdapter {
     /// WebSocket endpoint
     endpoint: String,
     /// Subxt client
     client: OnlineClient<PolkadotConfig>,
+    /// Raw RPC client shared with the subxt client
+    rpc_client: RpcClient,
+    /// RPC methods and runtime APIs supported by the node
+    capabilities: Capabilities,
     /// Chain configuration
     config: ChainConfig,
     /// Connection status
     connected: bool,
     /// Metrics collector
     metrics: Metrics,
+    /// Audit log attached to transaction executors
+    audit_log: Option<AuditLog>,
+    /// Policy attached to transaction executors
+    policy: Option<Policy>,
+    /// Address screener attached to transaction executors
+    address_screener: Option<Arc<dyn AddressScreener>>,
+    /// Expiry handlers attached to transaction executors
+    expiry_handlers: Vec<Arc<dyn ExpiryHandler>>,
 }
 
 impl SubstrateAdapter {
@@ -204,26 +599,72 @@ impl SubstrateAdapter {
         Self::connect_with_config(ChainConfig::custom("Substrate", endpoint, 42)).await
     }
 
+    /// Connect to a Substrate node with API keys or custom headers
+    pub async fn connect_with_client_config(
+        endpoint: &str,
+        client_config: &ClientConfig,
+    ) -> Result<Self> {
+        Self::connect_with_config(
+            ChainConfig::custom("Substrate", endpoint, 42)
+                .with_client_config(client_config.clone()),
+        )
+        .await
+    }
+
     /// Connect to a Substrate node with specific chain configuration
     pub async fn connect_with_config(config: ChainConfig) -> Result<Self> {
         info!("Connecting to {} at {}", config.name, config.endpoint);
 
-        // Create subxt client
-        let client = OnlineClient::<PolkadotConfig>::from_url(&config.endpoint)
+        // Create the RPC client, then the subxt client on top of it
+        let rpc_client = if config.client.is_empty() && ipc_path(&config.endpoint).is_none() {
+            RpcClient::from_url(&config.endpoint)
+                .await
+                .map_err(|e| Error::Connection(format!("Failed to connect: {}", e)))?
+        } else {
+            transport::connect_rpc_client(&config.endpoint, &config.client).await?
+        };
+        Self::connect_with_rpc_client(rpc_client, config).await
+    }
+
+    /// Build an adapter on an existing RPC client, e.g. a [`vcr`] replay client
+    ///
+    /// The endpoint and client settings in `config` are not used to connect;
+    /// its RPC layers are applied on top of `rpc_client`.
+    pub async fn connect_with_rpc_client(
+        rpc_client: RpcClient,
+        config: ChainConfig,
+    ) -> Result<Self> {
+        let rpc_client = config.rpc_layers.wrap(rpc_client);
+        let client = OnlineClient::<PolkadotConfig>::from_rpc_client(rpc_client.clone())
             .await
             .map_err(|e| Error::Connection(format!("Failed to connect: {}", e)))?;
 
         // Verify connection by fetching metadata
         let _metadata = client.metadata();
+        let capabilities = Capabilities::probe(&rpc_client).await;
         debug!("Connected to {}", config.name);
 
-        Ok(Self {
+        let adapter = Self {
             endpoint: config.endpoint.clone(),
             client,
+            rpc_client,
+            capabilities,
             config,
             connected: true,
             metrics: Metrics::new(),
-        })
+            audit_log: None,
+            policy: None,
+            address_screener: None,
+            expiry_handlers: Vec::new(),
+        };
+        // headers are decoded with `PolkadotConfig`'s u32 block numbers
+        if let Ok(BlockNumberWidth::U64) = adapter.block_number_width() {
+            return Err(Error::Connection(format!(
+                "{} uses u64 block numbers, which the adapter does not support",
+                adapter.config.name
+            )));
+        }
+        Ok(adapter)
     }
 
     /// Get reference to the subxt client
@@ -231,6 +672,41 @@ impl SubstrateAdapter {
         &self.client
     }
 
+    /// Get the raw RPC client for methods subxt does not wrap
+    pub fn rpc_client(&self) -> &RpcClient {
+        &self.rpc_client
+    }
+
+    /// Pallets included in the connected runtime
+    pub fn pallets(&self) -> PalletFeatures {
+        PalletFeatures::from_metadata(&self.client.metadata())
+    }
+
+    /// Fail with [`Error::PalletNotAvailable`] unless the runtime includes `pallet`
+    pub fn require_pallet(&self, pallet: &str) -> Result<()> {
+        pallets::require_pallet(&self.client.metadata(), pallet)
+    }
+
+    /// RPC methods and runtime APIs probed when connecting
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// RPC methods the node advertised when connecting
+    pub fn rpc_capabilities(&self) -> &RpcCapabilities {
+        self.capabilities.rpc()
+    }
+
+    /// Client using the new JSON-RPC spec methods where the node supports them
+    pub fn spec_client(&self) -> SpecClient {
+        SpecClient::new(self.rpc_client.clone(), self.capabilities.rpc().clone())
+    }
+
+    /// Create a block query client sharing this adapter's connection
+    pub fn block_query(&self) -> BlockQuery {
+        BlockQuery::new(self.client.clone()).with_spec_client(self.spec_client())
+    }
+
     /// Get the endpoint URL
     pub fn endpoint(&self) -> &str {
         &self.endpoint
@@ -255,7 +731,7 @@ impl SubstrateAdapter {
     ///
     /// This is more efficient than get_block if you have the block hash.
     pub async fn get_block_by_hash(&self, block_hash: &str) -> Result<BlockInfo> {
-        let block_query = crate::block::BlockQuery::new(self.client.clone());
+        let block_query = self.block_query();
         block_query.get_block_by_hash(block_hash).await
     }
 
@@ -266,7 +742,7 @@ impl SubstrateAdapter {
         &self,
         block_number: u64,
     ) -> Result<apex_sdk_core::DetailedBlockInfo> {
-        let block_query = crate::block::BlockQuery::new(self.client.clone());
+        let block_query = self.block_query();
         block_query.get_detailed_block(block_number).await
     }
 
@@ -416,15 +892,19 @@ impl SubstrateAdapter {
         Ok(TransactionStatus::unknown(tx_hash.to_string()))
     }
 
-    /// Validate a Substrate address (SS58 format)
+    /// Validate an address for this chain
+    ///
+    /// SS58 addresses are valid on chains with 32-byte accounts, checksummed
+    /// hex addresses on chains with 20-byte accounts.
     pub fn validate_address(&self, address: &Address) -> bool {
-        match address {
-            Address::Substrate(addr) => {
+        match (address, self.account_id_kind().unwrap_or_default()) {
+            (Address::Substrate(addr), AccountIdKind::Id32) => {
                 // Use sp_core to validate SS58 address
                 use sp_core::crypto::Ss58Codec;
                 sp_core::sr25519::Public::from_ss58check(addr).is_ok()
                     || sp_core::ed25519::Public::from_ss58check(addr).is_ok()
             }
+            (Address::Evm(addr), AccountIdKind::Id20) => addr.parse::<AccountId20>().is_ok(),
             _ => false,
         }
     }
@@ -438,13 +918,11 @@ impl SubstrateAdapter {
         debug!("Getting balance for address: {}", address);
         self.metrics.record_rpc_call("get_balance");
 
-        // Parse SS58 address to get AccountId32
-        use sp_core::crypto::{AccountId32, Ss58Codec};
-        let account_id = AccountId32::from_ss58check(address)
-            .map_err(|e| Error::Storage(format!("Invalid SS58 address: {}", e)))?;
+        // SS58, or hex for chains with 20-byte accounts
+        let account_bytes = account20::account_bytes(address)
+            .map_err(|e| Error::Storage(format!("Invalid address: {}", e)))?;
 
         // Query account info from System pallet using dynamic API
-        let account_bytes: &[u8] = account_id.as_ref();
         let storage_query = subxt::dynamic::storage(
             "System",
             "Account",
@@ -516,9 +994,94 @@ impl SubstrateAdapter {
         StorageClient::new(self.client.clone(), self.metrics.clone())
     }
 
+    /// Record signing and submission by this adapter's executors in `audit_log`
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Check transactions of this adapter's executors against `policy`
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Screen the addresses of transfers by this adapter's executors
+    pub fn with_address_screener(mut self, screener: impl AddressScreener + 'static) -> Self {
+        self.address_screener = Some(Arc::new(screener));
+        self
+    }
+
+    /// Notify `handler` when transactions submitted by this adapter's
+    /// executors near or pass the end of their era
+    pub fn on_expiry(mut self, handler: impl ExpiryHandler + 'static) -> Self {
+        self.expiry_handlers.push(Arc::new(handler));
+        self
+    }
+
     /// Create a transaction executor
     pub fn transaction_executor(&self) -> TransactionExecutor {
-        TransactionExecutor::new(self.client.clone(), self.metrics.clone())
+        let executor = TransactionExecutor::new(self.client.clone(), self.metrics.clone())
+            .with_rpc_client(self.rpc_client.clone())
+            .with_fee_strategy(self.capabilities.fee_strategy());
+        let executor = match &self.audit_log {
+            Some(audit_log) => executor.with_audit_log(audit_log.clone()),
+            None => executor,
+        };
+        let executor = match &self.policy {
+            Some(policy) => executor.with_policy(policy.clone()),
+            None => executor,
+        };
+        let executor = self
+            .expiry_handlers
+            .iter()
+            .fold(executor, |executor, handler| {
+                executor.with_expiry_handler(handler.clone())
+            });
+        match &self.address_screener {
+            Some(screener) => executor.with_address_screener(screener.clone()),
+            None => executor,
+        }
+    }
+
+    /// Width of the runtime's `BlockNumber`, read from `System::BlockHashCount`
+    ///
+    /// Connecting fails on chains where this is `u64`.
+    pub fn block_number_width(&self) -> Result<BlockNumberWidth> {
+        let encoded = self.storage().get_constant("System", "BlockHashCount")?;
+        Ok(BlockNumberWidth::from_encoded_len(encoded.len())?)
+    }
+
+    /// Target block time, read from the runtime's slot constants
+    ///
+    /// See [`ChainTime::expected_block_time_ms`] for the constants consulted.
+    pub fn block_time(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(ChainTime::new(self).expected_block_time_ms())
+    }
+
+    /// Account id layout of the chain, read from the `System::Account` key type
+    pub fn account_id_kind(&self) -> Result<AccountIdKind> {
+        let metadata = self.client.metadata();
+        let key_ty = metadata
+            .pallet_by_name("System")
+            .and_then(|pallet| pallet.storage())
+            .and_then(|storage| storage.entry_by_name("Account"))
+            .and_then(|entry| entry.entry_type().key_ty())
+            .ok_or_else(|| Error::Metadata("System::Account storage not found".to_string()))?;
+        let name = metadata
+            .types()
+            .resolve(key_ty)
+            .and_then(|ty| ty.path.segments.last().cloned())
+            .unwrap_or_default();
+
+        match name.as_str() {
+            "AccountId32" => Ok(AccountIdKind::Id32),
+            "AccountId20" | "H160" => Ok(AccountIdKind::Id20),
+            _ => Err(Error::Metadata(format!(
+                "Unsupported account id type {:?}",
+                name
+            ))),
+        }
     }
 
     /// Get runtime version
@@ -566,24 +1129,20 @@ impl CoreProvider for SubstrateAdapter {
 
     async fn get_balance(&self, address: &Address) -> std::result::Result<u128, SdkError> {
         match address {
-            Address::Substrate(addr) => self.get_balance(addr).await.map_err(Into::into),
-            _ => Err(SdkError::ConfigError(
-                "Invalid address type for Substrate adapter".to_string(),
-            )),
+            Address::Substrate(addr) | Address::Evm(addr) => {
+                self.get_balance(addr).await.map_err(Into::into)
+            }
         }
     }
 
     async fn get_transaction_count(&self, address: &Address) -> std::result::Result<u64, SdkError> {
         match address {
-            Address::Substrate(addr) => {
+            Address::Substrate(addr) | Address::Evm(addr) => {
                 // Use StorageClient to properly query the nonce
                 let storage_client = StorageClient::new(self.client.clone(), self.metrics.clone());
 
                 storage_client.get_nonce(addr).await.map_err(SdkError::from)
             }
-            _ => Err(SdkError::ConfigError(
-                "Invalid address type for Substrate adapter".to_string(),
-            )),
         }
     }
 
@@ -601,7 +1160,7 @@ impl CoreProvider for SubstrateAdapter {
 
     async fn get_block(&self, block_number: u64) -> std::result::Result<BlockInfo, SdkError> {
         // Use BlockQuery to fetch real blockchain data
-        let block_query = crate::block::BlockQuery::new(self.client.clone());
+        let block_query = self.block_query();
 
         block_query
             .get_block_by_number(block_number)
@@ -611,9 +1170,16 @@ impl CoreProvider for SubstrateAdapter {
 
     async fn health_check(&self) -> std::result::Result<(), SdkError> {
         // Check if we can get the latest block
-        match self.client.blocks().at_latest().await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(SdkError::ProviderError(e.to_string())),
+        if let Err(e) = self.client.blocks().at_latest().await {
+            return Err(SdkError::ProviderError(e.to_string()));
+        }
+        // in safe mode, almost every submission would be filtered
+        match self.storage().pause_state().await?.safe_mode_until {
+            Some(until) => Err(SdkError::ProviderError(format!(
+                "Chain is in safe mode until block {}",
+                until
+            ))),
+            None => Ok(()),
         }
     }
 }
@@ -630,17 +1196,13 @@ impl NonceManager for SubstrateAdapter {
 #[async_trait]
 impl Broadcaster for SubstrateAdapter {
     async fn broadcast(&self, signed_tx: &[u8]) -> std::result::Result<String, SdkError> {
-        // We need to decode the signed transaction bytes back into a subxt payload
-        // This is tricky because subxt expects strongly typed payloads or dynamic values
-        // For now, we'll assume the bytes are the raw encoded extrinsic
-
-        // Note: This implementation is limited because subxt's submit_raw expects the bytes
-        // to be a valid extrinsic.
-
-        let hash = "0x1234567890abcdef"; // self.client.rpc().submit_extrinsic(signed_tx).await
-        let _signed_tx = signed_tx; // Use parameter
-
-        Ok(hash.to_string())
+        // `transaction_*_broadcast` only returns an operation id, so the
+        // extrinsic hash is computed locally
+        let hash = match self.spec_client().broadcast(signed_tx).await? {
+            Broadcast::Submitted(hash) => hash.0,
+            Broadcast::Operation(_) => sp_core::blake2_256(signed_tx),
+        };
+        Ok(format!("0x{}", hex::encode(hash)))
     }
 }
 
@@ -652,8 +1214,12 @@ impl ReceiptWatcher for SubstrateAdapter {
     ) -> std::result::Result<TransactionStatus, SdkError> {
         // Simple polling implementation
         // In a real implementation, we might want to use the retry/backoff logic or subscriptions
+        let block_time = self.block_time();
         let start = std::time::Instant::now();
-        let timeout = std::time::Duration::from_secs(60); // 60s timeout from plan
+        // at least 60s, or 10 blocks on slow chains
+        let timeout = std::time::Duration::from_secs(60).max(block_time * 10);
+        // at least once per block, so fast chains don't wait a second per check
+        let poll_interval = std::time::Duration::from_secs(1).min(block_time);
 
         while start.elapsed() < timeout {
             let status = self
@@ -665,7 +1231,7 @@ impl ReceiptWatcher for SubstrateAdapter {
                 return Ok(status);
             }
             // For Substrate, we default to finalized head confirmation policy
-            tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
+            tokio::time::sleep(poll_interval).await;
         }
 
         Err(SdkError::NetworkError(
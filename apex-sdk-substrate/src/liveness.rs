@@ -0,0 +1,365 @@
+//! im-online heartbeat and validator liveness monitoring
+//!
+//! [`LivenessMonitor`] follows finalized blocks and tracks, for each watched
+//! validator, whether it has sent an `ImOnline` heartbeat or authored a block
+//! in the current session. Either one keeps a validator from being reported
+//! offline at the end of the session. It raises a [`StakingAlert`] when:
+//! - a validator is still silent late in the session
+//!   ([`AlertPayload::HeartbeatOverdue`]), leaving time to react
+//! - a session ended without a heartbeat or authored block
+//!   ([`AlertPayload::MissedHeartbeat`])
+//!
+//! Alerts go to the same [`AlertHandler`]s as the
+//! [`SlashMonitor`](crate::SlashMonitor):
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{LivenessMonitor, StakingAlert, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! LivenessMonitor::new(adapter)
+//!     .watch("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")
+//!     .with_overdue_after(300)
+//!     .on_alert(|alert: &StakingAlert| eprintln!("#{}: {:?}", alert.block_number, alert.payload))
+//!     .run()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Validators outside the active set are not tracked.
+
+use crate::event_query::account_id;
+use crate::pallets::require_pallet;
+use crate::slash_monitor::{AlertHandler, AlertPayload, StakingAlert};
+use crate::storage::StorageClient;
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::Decode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use subxt::dynamic::Value;
+use subxt::{OnlineClient, PolkadotConfig};
+use tracing::{debug, info};
+
+/// Share of a BABE epoch after which a silent validator is overdue
+pub const DEFAULT_OVERDUE_FRACTION: f64 = 0.75;
+
+/// Liveness of a validator in one session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorLiveness {
+    /// Validator account (hex)
+    pub validator: String,
+    /// Session index
+    pub session: u32,
+    /// Whether a heartbeat was received this session
+    pub heartbeat: bool,
+    /// Blocks authored this session
+    pub authored_blocks: u32,
+}
+
+impl ValidatorLiveness {
+    /// Whether the validator has shown it is online this session
+    pub fn is_alive(&self) -> bool {
+        self.heartbeat || self.authored_blocks > 0
+    }
+}
+
+/// Follows finalized blocks and reports silent validators
+#[derive(Clone)]
+pub struct LivenessMonitor {
+    client: OnlineClient<PolkadotConfig>,
+    storage: StorageClient,
+    watched: Vec<String>,
+    overdue_after: Option<u64>,
+    handlers: Vec<Arc<dyn AlertHandler>>,
+}
+
+impl LivenessMonitor {
+    /// Create a monitor over a connected adapter
+    pub fn new(adapter: &SubstrateAdapter) -> Self {
+        Self {
+            client: adapter.client().clone(),
+            storage: adapter.storage(),
+            watched: Vec::new(),
+            overdue_after: None,
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Track this validator (SS58 or hex)
+    pub fn watch(mut self, address: impl Into<String>) -> Self {
+        self.watched.push(address.into());
+        self
+    }
+
+    /// Blocks into a session after which a silent validator is overdue
+    ///
+    /// Defaults to [`DEFAULT_OVERDUE_FRACTION`] of the BABE epoch.
+    pub fn with_overdue_after(mut self, blocks: u64) -> Self {
+        self.overdue_after = Some(blocks);
+        self
+    }
+
+    /// Register an alert handler
+    pub fn on_alert(mut self, handler: impl AlertHandler + 'static) -> Self {
+        self.handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Follow finalized blocks until the subscription ends
+    pub async fn run(&self) -> Result<()> {
+        let metadata = self.client.metadata();
+        require_pallet(&metadata, "ImOnline")?;
+        require_pallet(&metadata, "Session")?;
+        let watched = self
+            .watched
+            .iter()
+            .map(|address| account_id(address))
+            .collect::<Result<Vec<_>>>()?;
+        let overdue_after = self.overdue_after.or_else(|| {
+            let duration = self.storage.get_constant("Babe", "EpochDuration").ok()?;
+            let duration = u64::decode(&mut &duration[..]).ok()?;
+            Some((duration as f64 * DEFAULT_OVERDUE_FRACTION) as u64)
+        });
+
+        let mut blocks = self
+            .client
+            .blocks()
+            .subscribe_finalized()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to subscribe to blocks: {}", e)))?;
+
+        info!("Monitoring liveness of {} validators", watched.len());
+
+        let mut tracker = LivenessTracker::default();
+        while let Some(block) = blocks.next().await {
+            let block = block
+                .map_err(|e| Error::Connection(format!("Block subscription failed: {}", e)))?;
+            let number = block.number() as u64;
+            let block_hash = format!("0x{}", hex::encode(block.hash().0));
+
+            let session = self.session(&block_hash).await?;
+            let statuses = self.liveness(&block_hash, session, &watched).await?;
+            let into_session = match self.epoch_start(&block_hash).await? {
+                Some(start) => Some(number.saturating_sub(start)),
+                None => tracker.started_at(session, number),
+            };
+            let overdue =
+                into_session.filter(|blocks| overdue_after.is_some_and(|after| *blocks >= after));
+            debug!(
+                "Session {} liveness at #{}: {:?}",
+                session, number, statuses
+            );
+
+            for payload in tracker.update(session, statuses, overdue) {
+                let alert = StakingAlert {
+                    block_number: number,
+                    block_hash: block_hash.clone(),
+                    extrinsic_index: None,
+                    payload,
+                };
+                for handler in &self.handlers {
+                    handler.on_alert(&alert).await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn session(&self, block_hash: &str) -> Result<u32> {
+        self.storage
+            .query_storage_at_block(block_hash, "Session", "CurrentIndex", vec![])
+            .await?
+            .and_then(|bytes| u32::decode(&mut &bytes[..]).ok())
+            .ok_or_else(|| Error::Storage("Unexpected Session::CurrentIndex".to_string()))
+    }
+
+    /// Liveness of the watched validators in the active set
+    async fn liveness(
+        &self,
+        block_hash: &str,
+        session: u32,
+        watched: &[[u8; 32]],
+    ) -> Result<Vec<ValidatorLiveness>> {
+        let validators = self
+            .storage
+            .query_storage_at_block(block_hash, "Session", "Validators", vec![])
+            .await?
+            .map(|bytes| Vec::<[u8; 32]>::decode(&mut &bytes[..]))
+            .transpose()
+            .map_err(|e| Error::Storage(format!("Failed to decode Session::Validators: {}", e)))?
+            .unwrap_or_default();
+
+        let mut statuses = Vec::new();
+        for account in watched {
+            let Some(index) = validators.iter().position(|v| v == account) else {
+                continue;
+            };
+            let heartbeat = self
+                .storage
+                .query_storage_at_block(
+                    block_hash,
+                    "ImOnline",
+                    "ReceivedHeartbeats",
+                    vec![Value::u128(session as u128), Value::u128(index as u128)],
+                )
+                .await?
+                .is_some();
+            let authored_blocks = self
+                .storage
+                .query_storage_at_block(
+                    block_hash,
+                    "ImOnline",
+                    "AuthoredBlocks",
+                    vec![Value::u128(session as u128), Value::from_bytes(account)],
+                )
+                .await?
+                .and_then(|bytes| u32::decode(&mut &bytes[..]).ok())
+                .unwrap_or(0);
+            statuses.push(ValidatorLiveness {
+                validator: format!("0x{}", hex::encode(account)),
+                session,
+                heartbeat,
+                authored_blocks,
+            });
+        }
+        Ok(statuses)
+    }
+
+    /// First block of the current BABE epoch, on BABE chains
+    async fn epoch_start(&self, block_hash: &str) -> Result<Option<u64>> {
+        if self.client.metadata().pallet_by_name("Babe").is_none() {
+            return Ok(None);
+        }
+        Ok(self
+            .storage
+            .query_storage_at_block(block_hash, "Babe", "EpochStart", vec![])
+            .await?
+            .and_then(|bytes| <(u32, u32)>::decode(&mut &bytes[..]).ok())
+            .map(|(_, current)| current as u64)
+            .filter(|start| *start > 0))
+    }
+}
+
+impl std::fmt::Debug for LivenessMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LivenessMonitor")
+            .field("watched", &self.watched)
+            .field("overdue_after", &self.overdue_after)
+            .field("handlers", &self.handlers.len())
+            .finish()
+    }
+}
+
+/// Turns per-block liveness into alerts, once per validator and session
+#[derive(Debug, Default)]
+struct LivenessTracker {
+    session: Option<u32>,
+    session_start: Option<u64>,
+    last: Vec<ValidatorLiveness>,
+    overdue: HashSet<String>,
+}
+
+impl LivenessTracker {
+    /// Blocks since the session started, when the change was observed
+    fn started_at(&mut self, session: u32, number: u64) -> Option<u64> {
+        if self.session.is_some_and(|current| current != session) {
+            self.session_start = Some(number);
+        }
+        self.session_start.map(|start| number.saturating_sub(start))
+    }
+
+    /// Alerts for the latest liveness; `overdue` is the blocks into the
+    /// session once past the overdue threshold
+    fn update(
+        &mut self,
+        session: u32,
+        statuses: Vec<ValidatorLiveness>,
+        overdue: Option<u64>,
+    ) -> Vec<AlertPayload> {
+        let mut payloads = Vec::new();
+        if self.session.is_some_and(|current| current != session) {
+            payloads.extend(self.last.drain(..).filter(|status| !status.is_alive()).map(
+                |status| AlertPayload::MissedHeartbeat {
+                    validator: status.validator,
+                    session: status.session,
+                },
+            ));
+            self.overdue.clear();
+        }
+        self.session = Some(session);
+
+        if let Some(blocks_into_session) = overdue {
+            for status in statuses.iter().filter(|status| !status.is_alive()) {
+                if self.overdue.insert(status.validator.clone()) {
+                    payloads.push(AlertPayload::HeartbeatOverdue {
+                        validator: status.validator.clone(),
+                        session,
+                        blocks_into_session,
+                    });
+                }
+            }
+        }
+        self.last = statuses;
+        payloads
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn liveness(session: u32, heartbeat: bool, authored_blocks: u32) -> ValidatorLiveness {
+        ValidatorLiveness {
+            validator: "0xd4".to_string(),
+            session,
+            heartbeat,
+            authored_blocks,
+        }
+    }
+
+    #[test]
+    fn test_tracker_alerts_once_per_session() {
+        let mut tracker = LivenessTracker::default();
+        assert!(tracker
+            .update(7, vec![liveness(7, false, 0)], None)
+            .is_empty());
+
+        let overdue = tracker.update(7, vec![liveness(7, false, 0)], Some(450));
+        assert_eq!(
+            overdue,
+            vec![AlertPayload::HeartbeatOverdue {
+                validator: "0xd4".to_string(),
+                session: 7,
+                blocks_into_session: 450
+            }]
+        );
+        assert!(tracker
+            .update(7, vec![liveness(7, false, 0)], Some(451))
+            .is_empty());
+
+        // the session ends silent, then the next one starts fine
+        let missed = tracker.update(8, vec![liveness(8, true, 0)], None);
+        assert_eq!(
+            missed,
+            vec![AlertPayload::MissedHeartbeat {
+                validator: "0xd4".to_string(),
+                session: 7
+            }]
+        );
+        assert!(tracker
+            .update(9, vec![liveness(9, false, 2)], Some(600))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_observed_session_start() {
+        let mut tracker = LivenessTracker::default();
+        assert_eq!(tracker.started_at(3, 100), None);
+        tracker.update(3, Vec::new(), None);
+        assert_eq!(tracker.started_at(4, 150), Some(0));
+        tracker.update(4, Vec::new(), None);
+        assert_eq!(tracker.started_at(4, 190), Some(40));
+    }
+}
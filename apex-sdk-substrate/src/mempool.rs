@@ -0,0 +1,255 @@
+//! Pending extrinsic inspection
+//!
+//! [`Mempool`] reads the node's transaction pool through
+//! `author_pendingExtrinsics`, decodes what it finds and can wait for a
+//! queued extrinsic to leave the pool, telling whether it was included in a
+//! block or dropped:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{Mempool, PoolExit, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter, tx_hash: &str) -> Result<(), apex_sdk_substrate::Error> {
+//! let mempool = Mempool::new(adapter);
+//! if mempool.contains(tx_hash).await? {
+//!     println!("your transaction is queued");
+//!     match mempool.watch(tx_hash).await? {
+//!         PoolExit::Included { block_number, .. } => println!("included in #{}", block_number),
+//!         PoolExit::Dropped => println!("dropped from the pool"),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only the connected node's pool is visible; other nodes may hold extrinsics
+//! it has not seen yet.
+
+use crate::golden::{decode_extrinsic, DecodedExtrinsic};
+use crate::{Error, Result, SubstrateAdapter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use subxt::ext::subxt_rpcs::client::RpcParams;
+use tracing::debug;
+
+/// Default time between pool checks while watching an extrinsic, on chains
+/// with blocks at least this slow
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Block heights to wait after an extrinsic leaves the pool before calling it
+/// dropped
+///
+/// The pool forgets an extrinsic as soon as it is included, which can be
+/// noticed before the block announcing it arrives. Heights rather than blocks
+/// are counted, since elastic scaling and forks announce same-height siblings.
+const DROP_GRACE_BLOCKS: usize = 2;
+
+/// An extrinsic waiting in the transaction pool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingExtrinsic {
+    /// Extrinsic hash (hex)
+    pub hash: String,
+    /// SCALE-encoded extrinsic (hex)
+    pub extrinsic: String,
+    /// Decoded call, when the current metadata can decode it
+    pub decoded: Option<DecodedExtrinsic>,
+}
+
+/// How an extrinsic left the pool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PoolExit {
+    /// Included in a best block
+    Included {
+        /// Block number
+        block_number: u64,
+        /// Block hash (hex)
+        block_hash: String,
+        /// Position of the extrinsic in the block
+        extrinsic_index: u32,
+    },
+    /// Removed from the pool without appearing in a block
+    Dropped,
+}
+
+/// Transaction pool of the connected node
+pub struct Mempool<'a> {
+    adapter: &'a SubstrateAdapter,
+    poll_interval: Duration,
+}
+
+impl<'a> Mempool<'a> {
+    /// Inspect the pool through the adapter's connection
+    ///
+    /// The pool is checked once per block on chains faster than
+    /// [`DEFAULT_POLL_INTERVAL`].
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self {
+            adapter,
+            poll_interval: DEFAULT_POLL_INTERVAL.min(adapter.block_time()),
+        }
+    }
+
+    /// Time between pool checks in [`watch`](Self::watch)
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Pending extrinsics, decoded where possible
+    pub async fn pending(&self) -> Result<Vec<PendingExtrinsic>> {
+        let metadata = self.adapter.client().metadata();
+        Ok(self
+            .raw_pending()
+            .await?
+            .into_iter()
+            .enumerate()
+            .map(|(index, bytes)| PendingExtrinsic {
+                hash: format!("0x{}", hex::encode(extrinsic_hash(&bytes))),
+                extrinsic: format!("0x{}", hex::encode(&bytes)),
+                decoded: decode_extrinsic(&bytes, index as u32, &metadata).ok(),
+            })
+            .collect())
+    }
+
+    /// Hashes of the pending extrinsics
+    pub async fn hashes(&self) -> Result<Vec<String>> {
+        Ok(self
+            .raw_pending()
+            .await?
+            .iter()
+            .map(|bytes| format!("0x{}", hex::encode(extrinsic_hash(bytes))))
+            .collect())
+    }
+
+    /// Whether the extrinsic with this hash is in the pool
+    pub async fn contains(&self, hash: &str) -> Result<bool> {
+        let hash = parse_hash(hash)?;
+        self.contains_hash(&hash).await
+    }
+
+    /// Wait until the extrinsic leaves the pool
+    ///
+    /// Follows best blocks while polling the pool. Returns
+    /// [`PoolExit::Included`] as soon as a best block contains the extrinsic,
+    /// even if it is not yet finalized.
+    pub async fn watch(&self, hash: &str) -> Result<PoolExit> {
+        let target = parse_hash(hash)?;
+        let mut blocks = self
+            .adapter
+            .client()
+            .blocks()
+            .subscribe_best()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to subscribe to blocks: {}", e)))?;
+        let mut interval = tokio::time::interval(self.poll_interval);
+        let mut heights_since_gone: Option<HashSet<u64>> = None;
+
+        loop {
+            tokio::select! {
+                block = blocks.next() => {
+                    let block = block
+                        .ok_or_else(|| Error::Connection("Block subscription ended".to_string()))?
+                        .map_err(|e| {
+                            Error::Connection(format!("Block subscription failed: {}", e))
+                        })?;
+                    let extrinsics = block.extrinsics().await.map_err(|e| {
+                        Error::Transaction(format!("Failed to get extrinsics: {}", e))
+                    })?;
+                    if let Some(extrinsic) = extrinsics
+                        .iter()
+                        .find(|extrinsic| extrinsic_hash(extrinsic.bytes()) == target)
+                    {
+                        return Ok(PoolExit::Included {
+                            block_number: block.number() as u64,
+                            block_hash: format!("0x{}", hex::encode(block.hash().0)),
+                            extrinsic_index: extrinsic.index(),
+                        });
+                    }
+                    if let Some(heights) = heights_since_gone.as_mut() {
+                        heights.insert(block.number() as u64);
+                        if heights.len() >= DROP_GRACE_BLOCKS {
+                            return Ok(PoolExit::Dropped);
+                        }
+                    }
+                }
+                _ = interval.tick(), if heights_since_gone.is_none() => {
+                    if !self.contains_hash(&target).await? {
+                        debug!("{} left the pool", hash);
+                        heights_since_gone = Some(HashSet::new());
+                    }
+                }
+            }
+        }
+    }
+
+    async fn contains_hash(&self, hash: &[u8; 32]) -> Result<bool> {
+        Ok(self
+            .raw_pending()
+            .await?
+            .iter()
+            .any(|bytes| extrinsic_hash(bytes) == *hash))
+    }
+
+    async fn raw_pending(&self) -> Result<Vec<Vec<u8>>> {
+        let pending: Vec<String> = self
+            .adapter
+            .rpc_client()
+            .request("author_pendingExtrinsics", RpcParams::new())
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get pending extrinsics: {}", e)))?;
+        pending
+            .iter()
+            .map(|extrinsic| {
+                hex::decode(extrinsic.trim_start_matches("0x"))
+                    .map_err(|e| Error::Encoding(format!("Invalid pending extrinsic: {}", e)))
+            })
+            .collect()
+    }
+}
+
+/// Hash identifying an encoded extrinsic
+pub fn extrinsic_hash(extrinsic: &[u8]) -> [u8; 32] {
+    sp_core::blake2_256(extrinsic)
+}
+
+/// 32-byte hash from hex, with or without `0x`
+fn parse_hash(hash: &str) -> Result<[u8; 32]> {
+    hex::decode(hash.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Error::Other(format!("Invalid extrinsic hash {}", hash)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hash() {
+        let hash = [0xab; 32];
+        assert_eq!(
+            parse_hash(&format!("0x{}", hex::encode(hash))).unwrap(),
+            hash
+        );
+        assert_eq!(parse_hash(&hex::encode(hash)).unwrap(), hash);
+        assert!(parse_hash("0xabcd").is_err());
+        assert!(parse_hash("not hex").is_err());
+    }
+
+    #[test]
+    fn test_pool_exit_serializes_tagged() {
+        let exit = PoolExit::Included {
+            block_number: 7,
+            block_hash: "0x01".to_string(),
+            extrinsic_index: 2,
+        };
+        let json = serde_json::to_value(&exit).unwrap();
+        assert_eq!(json["type"], "included");
+        assert_eq!(json["extrinsic_index"], 2);
+        assert_eq!(
+            serde_json::to_value(PoolExit::Dropped).unwrap(),
+            serde_json::json!({ "type": "dropped" })
+        );
+    }
+}
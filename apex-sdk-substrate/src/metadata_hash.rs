@@ -0,0 +1,302 @@
+//! Metadata hash signing for the `CheckMetadataHash` extension
+//!
+//! Runtimes with the `CheckMetadataHash` transaction extension let the signer
+//! commit to the metadata it used to display a transaction, via the short
+//! metadata hash of [RFC78]. [`MetadataHasher`] computes that hash for the
+//! connected chain, and [`enable_metadata_hash`] switches encoded extensions
+//! from the disabled mode subxt signs with to one carrying the hash:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{MetadataHasher, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let hasher = MetadataHasher::new(adapter);
+//! if hasher.is_required() {
+//!     let hash = hasher.hash().await?;
+//!     println!("metadata hash 0x{}", hex::encode(hash));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! On chains that have the extension, [`TransactionExecutor`] signs with the
+//! hash enabled and [`WatchOnlyAccount`] includes it in every signing
+//! request, since hardware signers refuse to sign without it.
+//!
+//! [`TransactionExecutor`]: crate::TransactionExecutor
+//! [`WatchOnlyAccount`]: crate::watch_only::WatchOnlyAccount
+//!
+//! [RFC78]: https://polkadot-fellows.github.io/RFCs/approved/0078-merkleized-metadata.html
+
+use crate::{Error, Result, SubstrateAdapter};
+use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+use merkleized_metadata::{generate_metadata_digest, ExtraInfo};
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::backend::rpc::RpcClient;
+use subxt::ext::scale_value;
+use subxt::ext::subxt_rpcs::client::RpcParams;
+use subxt::{Metadata, OnlineClient, PolkadotConfig};
+
+/// Identifier of the extension
+pub const METADATA_HASH_EXTENSION: &str = "CheckMetadataHash";
+
+/// Metadata version the hash is computed over
+const METADATA_VERSION: u32 = 15;
+
+/// Chain details the metadata hash commits to besides the metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataHashInfo {
+    /// Runtime spec version
+    pub spec_version: u32,
+    /// Runtime spec name
+    pub spec_name: String,
+    /// SS58 address prefix
+    pub ss58_prefix: u16,
+    /// Decimals of the native token
+    pub decimals: u8,
+    /// Symbol of the native token
+    pub token_symbol: String,
+}
+
+impl MetadataHashInfo {
+    /// Details from `state_getRuntimeVersion` and `system_properties` responses
+    ///
+    /// Chains listing several tokens describe the native one first.
+    pub fn from_rpc(runtime_version: &JsonValue, properties: &JsonValue) -> Result<Self> {
+        let missing = |field: &str| Error::Metadata(format!("Chain does not report {}", field));
+        let first = |value: &JsonValue| match value {
+            JsonValue::Array(values) => values.first().cloned(),
+            JsonValue::Null => None,
+            value => Some(value.clone()),
+        };
+        Ok(Self {
+            spec_version: runtime_version["specVersion"]
+                .as_u64()
+                .and_then(|version| u32::try_from(version).ok())
+                .ok_or_else(|| missing("specVersion"))?,
+            spec_name: runtime_version["specName"]
+                .as_str()
+                .ok_or_else(|| missing("specName"))?
+                .to_string(),
+            ss58_prefix: properties["ss58Format"]
+                .as_u64()
+                .and_then(|prefix| u16::try_from(prefix).ok())
+                .ok_or_else(|| missing("ss58Format"))?,
+            decimals: first(&properties["tokenDecimals"])
+                .and_then(|decimals| decimals.as_u64())
+                .and_then(|decimals| u8::try_from(decimals).ok())
+                .ok_or_else(|| missing("tokenDecimals"))?,
+            token_symbol: first(&properties["tokenSymbol"])
+                .and_then(|symbol| symbol.as_str().map(str::to_string))
+                .ok_or_else(|| missing("tokenSymbol"))?,
+        })
+    }
+}
+
+/// Computes the RFC78 metadata hash of the connected chain
+pub struct MetadataHasher<'a> {
+    client: &'a OnlineClient<PolkadotConfig>,
+    rpc: &'a RpcClient,
+}
+
+impl<'a> MetadataHasher<'a> {
+    /// Hash the metadata of the chain behind the adapter
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self::from_clients(adapter.client(), adapter.rpc_client())
+    }
+
+    /// Hash the metadata of the chain behind `client`, reached over `rpc`
+    pub fn from_clients(client: &'a OnlineClient<PolkadotConfig>, rpc: &'a RpcClient) -> Self {
+        Self { client, rpc }
+    }
+
+    /// Whether the runtime has the `CheckMetadataHash` extension
+    pub fn is_required(&self) -> bool {
+        requires_metadata_hash(&self.client.metadata())
+    }
+
+    /// Short metadata hash of the current runtime
+    pub async fn hash(&self) -> Result<[u8; 32]> {
+        let metadata = self.runtime_metadata().await?;
+        let info = self.info().await?;
+        metadata_hash(&metadata, &info)
+    }
+
+    /// Chain details the hash commits to
+    pub async fn info(&self) -> Result<MetadataHashInfo> {
+        let request = |method: &'static str| async move {
+            self.rpc
+                .request::<JsonValue>(method, RpcParams::new())
+                .await
+                .map_err(|e| Error::Connection(format!("Failed to call {}: {}", method, e)))
+        };
+        MetadataHashInfo::from_rpc(
+            &request("state_getRuntimeVersion").await?,
+            &request("system_properties").await?,
+        )
+    }
+
+    /// V15 metadata of the current runtime, as the hash is defined over it
+    pub async fn runtime_metadata(&self) -> Result<RuntimeMetadata> {
+        let bytes = self
+            .client
+            .runtime_api()
+            .at_latest()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get latest block: {}", e)))?
+            .call_raw(
+                "Metadata_metadata_at_version",
+                Some(&METADATA_VERSION.encode()),
+            )
+            .await
+            .map_err(|e| Error::Metadata(format!("Failed to fetch metadata: {}", e)))?;
+        let opaque = Option::<Vec<u8>>::decode(&mut &bytes[..])
+            .map_err(|e| Error::Metadata(format!("Invalid metadata response: {}", e)))?
+            .ok_or_else(|| {
+                Error::Metadata(format!(
+                    "Runtime does not provide v{} metadata",
+                    METADATA_VERSION
+                ))
+            })?;
+        RuntimeMetadataPrefixed::decode(&mut &opaque[..])
+            .map(|prefixed| prefixed.1)
+            .map_err(|e| Error::Metadata(format!("Invalid metadata: {}", e)))
+    }
+}
+
+/// Whether transactions for this runtime carry a `CheckMetadataHash` extension
+pub fn requires_metadata_hash(metadata: &Metadata) -> bool {
+    metadata
+        .extrinsic()
+        .transaction_extensions_to_use_for_encoding()
+        .any(|extension| extension.identifier() == METADATA_HASH_EXTENSION)
+}
+
+/// RFC78 short metadata hash of `metadata` for the chain described by `info`
+pub fn metadata_hash(metadata: &RuntimeMetadata, info: &MetadataHashInfo) -> Result<[u8; 32]> {
+    let extra_info = ExtraInfo {
+        spec_version: info.spec_version,
+        spec_name: info.spec_name.clone(),
+        base58_prefix: info.ss58_prefix,
+        decimals: info.decimals,
+        token_symbol: info.token_symbol.clone(),
+    };
+    generate_metadata_digest(metadata, extra_info)
+        .map(|digest| digest.hash())
+        .map_err(|e| Error::Metadata(format!("Failed to compute metadata hash: {}", e)))
+}
+
+/// Encoded transaction extensions, split as they are used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionData {
+    /// Data included in the extrinsic
+    pub extra: Vec<u8>,
+    /// Data only included in the signed payload
+    pub implicit: Vec<u8>,
+}
+
+impl ExtensionData {
+    /// Both parts as they follow the call in the signer payload
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.extra.clone();
+        bytes.extend_from_slice(&self.implicit);
+        bytes
+    }
+}
+
+/// Rewrite extensions encoded with the metadata hash disabled to carry `hash`
+///
+/// `extensions` holds the extra data of every extension followed by their
+/// implicit data, as in a signer payload. The extension's mode becomes
+/// enabled and its implicit data `Some(hash)`.
+pub fn enable_metadata_hash(
+    extensions: &[u8],
+    metadata: &Metadata,
+    hash: [u8; 32],
+) -> Result<ExtensionData> {
+    let invalid = |what: String| Error::Encoding(format!("Invalid extensions: {}", what));
+    let mut input = extensions;
+    let mut split = |ty: u32, identifier: &str| {
+        let start = input;
+        scale_value::scale::decode_as_type(&mut input, ty, metadata.types())
+            .map_err(|e| invalid(format!("{}: {}", identifier, e)))?;
+        Ok::<_, Error>(start[..start.len() - input.len()].to_vec())
+    };
+
+    let used: Vec<_> = metadata
+        .extrinsic()
+        .transaction_extensions_to_use_for_encoding()
+        .collect();
+    let mut data = ExtensionData {
+        extra: Vec::new(),
+        implicit: Vec::new(),
+    };
+    let mut found = false;
+    for extension in &used {
+        let value = split(extension.extra_ty(), extension.identifier())?;
+        if extension.identifier() == METADATA_HASH_EXTENSION {
+            // `Mode::Enabled`
+            data.extra.push(1);
+            found = true;
+        } else {
+            data.extra.extend(value);
+        }
+    }
+    for extension in &used {
+        let value = split(extension.additional_ty(), extension.identifier())?;
+        if extension.identifier() == METADATA_HASH_EXTENSION {
+            data.implicit.extend(Some(hash).encode());
+        } else {
+            data.implicit.extend(value);
+        }
+    }
+
+    if !input.is_empty() {
+        return Err(invalid(format!("{} trailing bytes", input.len())));
+    }
+    if !found {
+        return Err(Error::Metadata(format!(
+            "Runtime has no {} extension",
+            METADATA_HASH_EXTENSION
+        )));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_info_from_rpc() {
+        let runtime_version = serde_json::json!({
+            "specName": "polkadot",
+            "specVersion": 1_004_001,
+            "transactionVersion": 26
+        });
+        let properties = serde_json::json!({
+            "ss58Format": 0,
+            "tokenDecimals": 10,
+            "tokenSymbol": "DOT"
+        });
+        let info = MetadataHashInfo::from_rpc(&runtime_version, &properties).unwrap();
+        assert_eq!(info.spec_name, "polkadot");
+        assert_eq!(info.spec_version, 1_004_001);
+        assert_eq!(info.ss58_prefix, 0);
+        assert_eq!((info.decimals, info.token_symbol.as_str()), (10, "DOT"));
+
+        // multi-token chains list the native token first
+        let properties = serde_json::json!({
+            "ss58Format": 2007,
+            "tokenDecimals": [18, 12],
+            "tokenSymbol": ["SDN", "KSM"]
+        });
+        let info = MetadataHashInfo::from_rpc(&runtime_version, &properties).unwrap();
+        assert_eq!((info.decimals, info.token_symbol.as_str()), (18, "SDN"));
+
+        let properties = serde_json::json!({ "ss58Format": 42 });
+        assert!(MetadataHashInfo::from_rpc(&runtime_version, &properties).is_err());
+    }
+}
@@ -80,11 +80,7 @@ impl Metrics {
         let rpc_calls = self.rpc_calls.load(Ordering::Relaxed);
         let total_response_time = self.total_rpc_response_time_ms.load(Ordering::Relaxed);
 
-        let avg_rpc_response_time_ms = if rpc_calls > 0 {
-            total_response_time / rpc_calls
-        } else {
-            0
-        };
+        let avg_rpc_response_time_ms = total_response_time.checked_div(rpc_calls).unwrap_or(0);
 
         MetricsSnapshot {
             rpc_calls,
@@ -0,0 +1,701 @@
+//! Moving an account's holdings to a new key
+//!
+//! [`Migration::plan`] inspects an account and lists, in order, the calls that
+//! move its staking position, proxies, identity, asset balances and native
+//! balance to a new account. The [`MigrationPlan`] is plain JSON, so it can be
+//! reviewed and saved, dry-run step by step and executed over several
+//! sessions, with every step's status kept in the plan:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{Migration, MigrationPlan, SubstrateAdapter, Wallet};
+//! use std::path::Path;
+//!
+//! # async fn example(adapter: &SubstrateAdapter, old: &Wallet, new: &Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! let migration = Migration::new(adapter);
+//! let path = Path::new("migration.json");
+//! let mut plan = match MigrationPlan::load(path) {
+//!     Ok(plan) => plan,
+//!     Err(_) => migration.plan(&old.address(), &new.address()).await?,
+//! };
+//! for step in &plan.steps {
+//!     println!("{:?}: {}", step.signer, step.description);
+//! }
+//! for outcome in migration.dry_run(&plan).await? {
+//!     println!("{}.{}: {:?}", outcome.pallet, outcome.call, outcome.error);
+//! }
+//!
+//! let result = migration.execute(&mut plan, old, new).await;
+//! plan.save(path)?;
+//! result?;
+//! println!("{}/{} steps done", plan.completed(), plan.steps.len());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Bonded funds can't move until unbonding ends, so the withdrawal and the
+//! transfer after it wait for [`MigrationStep::not_before_era`]; running
+//! [`Migration::execute`] again after that era finishes the migration.
+//! Registrar judgements are not carried over to the new identity, and a stash
+//! bonded with a separate controller has to become its own controller first.
+
+use crate::event_query::{account_hex, account_id};
+use crate::receipt::{json_some, json_u128};
+use crate::simulator::{DryRunCall, DryRunOutcome, Simulator};
+use crate::template::TxTemplate;
+use crate::wallet::Wallet;
+use crate::{Error, Result, SubstrateAdapter};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::path::Path;
+use subxt::dynamic::Value;
+use tracing::{info, warn};
+
+/// Which account signs a migration step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationSigner {
+    /// The account being migrated from
+    Old,
+    /// The account being migrated to
+    New,
+}
+
+/// Progress of a migration step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    /// Not submitted yet
+    Pending,
+    /// Submitted and included
+    Done {
+        /// Extrinsic hash
+        tx_hash: String,
+    },
+    /// Submission failed; the step is retried by the next execution
+    Failed {
+        /// Error message
+        error: String,
+    },
+}
+
+/// One call of a migration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationStep {
+    /// What the step does
+    pub description: String,
+    /// Account signing the call
+    pub signer: MigrationSigner,
+    /// The call, with its arguments filled in
+    pub template: TxTemplate,
+    /// Staking era the step has to wait for, for funds still unbonding
+    pub not_before_era: Option<u32>,
+    /// Progress of the step
+    pub status: StepStatus,
+}
+
+impl MigrationStep {
+    fn new(description: impl Into<String>, signer: MigrationSigner, template: TxTemplate) -> Self {
+        Self {
+            description: description.into(),
+            signer,
+            template,
+            not_before_era: None,
+            status: StepStatus::Pending,
+        }
+    }
+
+    fn after_era(mut self, era: u32) -> Self {
+        self.not_before_era = Some(era);
+        self
+    }
+
+    /// Whether the step was submitted successfully
+    pub fn is_done(&self) -> bool {
+        matches!(self.status, StepStatus::Done { .. })
+    }
+}
+
+/// Ordered calls moving an account's holdings to a new account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationPlan {
+    /// Account migrated from (hex)
+    pub old: String,
+    /// Account migrated to (hex)
+    pub new: String,
+    /// Steps, in the order they must be submitted
+    pub steps: Vec<MigrationStep>,
+}
+
+impl MigrationPlan {
+    /// Number of steps done
+    pub fn completed(&self) -> usize {
+        self.steps.iter().filter(|step| step.is_done()).count()
+    }
+
+    /// Whether every step is done
+    pub fn is_complete(&self) -> bool {
+        self.steps.iter().all(MigrationStep::is_done)
+    }
+
+    /// First step not done yet
+    pub fn next_step(&self) -> Option<&MigrationStep> {
+        self.steps.iter().find(|step| !step.is_done())
+    }
+
+    /// Read a plan saved with [`save`](Self::save)
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| Error::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+        serde_json::from_str(&json).map_err(|e| {
+            Error::Encoding(format!("Invalid migration plan {}: {}", path.display(), e))
+        })
+    }
+
+    /// Write the plan as pretty-printed JSON, creating parent directories
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| Error::Other(format!("Failed to create {}: {}", dir.display(), e)))?;
+        }
+        let json =
+            serde_json::to_string_pretty(self).map_err(|e| Error::Encoding(e.to_string()))?;
+        std::fs::write(path, json)
+            .map_err(|e| Error::Other(format!("Failed to write {}: {}", path.display(), e)))
+    }
+}
+
+/// What the old account holds, as far as a migration is concerned
+#[derive(Debug, Default)]
+struct Holdings {
+    /// Nominating or validating
+    staking_role: bool,
+    /// Bonded and not unbonding
+    active_stake: u128,
+    /// Era the last unbonding chunk unlocks in, including the planned unbond
+    unlock_era: Option<u32>,
+    /// `Staking::SlashingSpans` count, for `withdraw_unbonded`
+    slashing_spans: u32,
+    /// `(delegate, proxy type, delay)`
+    proxies: Vec<(String, JsonValue, u64)>,
+    /// `info` of the identity registration
+    identity: Option<JsonValue>,
+    /// `(asset id, balance)`
+    assets: Vec<(u32, u128)>,
+    /// Whether the new account has no native balance yet
+    new_is_empty: bool,
+    existential_deposit: u128,
+}
+
+/// Plans and executes account migrations
+pub struct Migration<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> Migration<'a> {
+    /// Migrate accounts on the adapter's chain
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Plan moving everything `old` holds to `new`, SS58 or hex
+    ///
+    /// Pallets the chain doesn't have are skipped.
+    pub async fn plan(&self, old: &str, new: &str) -> Result<MigrationPlan> {
+        let (old, new) = (account_hex(old)?, account_hex(new)?);
+        if old == new {
+            return Err(Error::Other("Old and new account are the same".to_string()));
+        }
+        let holdings = self.holdings(&old, &new).await?;
+        let plan = MigrationPlan {
+            steps: plan_steps(&new, &holdings),
+            old,
+            new,
+        };
+        info!(
+            "Planned migration {} -> {} in {} steps",
+            plan.old,
+            plan.new,
+            plan.steps.len()
+        );
+        Ok(plan)
+    }
+
+    /// Dry-run the steps not done yet, each against the current state
+    ///
+    /// Steps don't see each other's effects, so a step relying on an earlier
+    /// one, such as the new account adding proxies before it is funded, can
+    /// fail here and still succeed when executed in order.
+    pub async fn dry_run(&self, plan: &MigrationPlan) -> Result<Vec<DryRunOutcome>> {
+        let metadata = self.adapter.client().metadata();
+        let (old, new) = (account_id(&plan.old)?, account_id(&plan.new)?);
+        let calls = plan
+            .steps
+            .iter()
+            .filter(|step| !step.is_done())
+            .map(|step| {
+                let origin = match step.signer {
+                    MigrationSigner::Old => old,
+                    MigrationSigner::New => new,
+                };
+                let args = step.template.call_args(&JsonValue::Null, &metadata)?;
+                Ok(DryRunCall::new(
+                    origin,
+                    step.template.pallet.clone(),
+                    step.template.call.clone(),
+                )
+                .with_args(args))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Simulator::new(self.adapter).dry_run_many(&calls).await
+    }
+
+    /// Submit the steps not done yet, in order
+    ///
+    /// Stops at the first step waiting for a later era, leaving it and the
+    /// steps after it pending, and at the first failure, which is recorded in
+    /// the step and returned. Returns the number of steps submitted.
+    pub async fn execute(
+        &self,
+        plan: &mut MigrationPlan,
+        old: &Wallet,
+        new: &Wallet,
+    ) -> Result<usize> {
+        if account_hex(&old.address())? != plan.old || account_hex(&new.address())? != plan.new {
+            return Err(Error::Wallet(
+                "Wallets don't match the migration plan".to_string(),
+            ));
+        }
+        let executor = self.adapter.transaction_executor();
+        let mut active_era = None;
+        let mut submitted = 0;
+
+        for step in plan.steps.iter_mut().filter(|step| !step.is_done()) {
+            if let Some(era) = step.not_before_era {
+                if active_era.is_none() {
+                    active_era = Some(self.active_era().await?);
+                }
+                if active_era < Some(era) {
+                    info!("Migration waits for era {}: {}", era, step.description);
+                    break;
+                }
+            }
+            let signer = match step.signer {
+                MigrationSigner::Old => old,
+                MigrationSigner::New => new,
+            };
+            match executor
+                .submit_template(signer, &step.template, &JsonValue::Null)
+                .await
+            {
+                Ok(tx_hash) => {
+                    info!("Migration step done: {}", step.description);
+                    step.status = StepStatus::Done { tx_hash };
+                    submitted += 1;
+                }
+                Err(e) => {
+                    warn!("Migration step failed: {}: {}", step.description, e);
+                    step.status = StepStatus::Failed {
+                        error: e.to_string(),
+                    };
+                    return Err(e);
+                }
+            }
+        }
+        Ok(submitted)
+    }
+
+    async fn holdings(&self, old: &str, new: &str) -> Result<Holdings> {
+        let storage = self.adapter.storage();
+        let pallets = self.adapter.pallets();
+        let key = |account: &str| -> Result<Vec<Value>> {
+            Ok(vec![Value::from_bytes(account_id(account)?)])
+        };
+        let mut holdings = Holdings {
+            existential_deposit: storage.get_existential_deposit().unwrap_or_default(),
+            new_is_empty: storage.get_account_info(new).await?.free == 0,
+            ..Default::default()
+        };
+
+        if pallets.has("Staking") {
+            let controller = storage
+                .query_storage_json("Staking", "Bonded", key(old)?)
+                .await?
+                .and_then(|controller| controller.as_str().map(str::to_string));
+            if let Some(controller) = controller {
+                check_controller(old, &controller)?;
+                let ledger = storage
+                    .query_storage_json("Staking", "Ledger", key(old)?)
+                    .await?
+                    .unwrap_or_default();
+                let (active, unlock_era) = ledger_amounts(&ledger);
+                holdings.active_stake = active;
+                holdings.unlock_era = unlock_era;
+                holdings.staking_role = storage
+                    .query_storage_json("Staking", "Nominators", key(old)?)
+                    .await?
+                    .is_some()
+                    || storage
+                        .query_storage_json("Staking", "Validators", key(old)?)
+                        .await?
+                        .is_some();
+                holdings.slashing_spans = storage
+                    .query_storage_json("Staking", "SlashingSpans", key(old)?)
+                    .await?
+                    .map(|spans| slashing_span_count(&spans))
+                    .unwrap_or_default();
+                if active > 0 {
+                    let bonding_duration = storage
+                        .get_constant_json("Staking", "BondingDuration")?
+                        .as_u64()
+                        .ok_or_else(|| {
+                            Error::Storage("Invalid Staking::BondingDuration".to_string())
+                        })? as u32;
+                    let unbonded_era = self.active_era().await? + bonding_duration;
+                    holdings.unlock_era = holdings.unlock_era.max(Some(unbonded_era));
+                }
+            }
+        }
+
+        if pallets.has("Proxy") {
+            if let Some(proxies) = storage
+                .query_storage_json("Proxy", "Proxies", key(old)?)
+                .await?
+            {
+                holdings.proxies = proxy_definitions(&proxies);
+            }
+        }
+
+        if pallets.has("Identity") {
+            holdings.identity = storage
+                .query_storage_json("Identity", "IdentityOf", key(old)?)
+                .await?
+                .as_ref()
+                .and_then(identity_info);
+        }
+
+        if pallets.has("Assets") {
+            // Assets::Account is keyed by asset first, so look up each asset
+            for (key_bytes, _) in storage.iter_storage_json("Assets", "Asset", vec![]).await? {
+                let Some(asset_id) = asset_id_from_key(&key_bytes) else {
+                    continue;
+                };
+                let balance = storage
+                    .query_storage_json(
+                        "Assets",
+                        "Account",
+                        vec![
+                            Value::u128(asset_id as u128),
+                            Value::from_bytes(account_id(old)?),
+                        ],
+                    )
+                    .await?
+                    .and_then(|account| json_u128(&account["balance"]))
+                    .unwrap_or_default();
+                if balance > 0 {
+                    holdings.assets.push((asset_id, balance));
+                }
+            }
+        }
+        Ok(holdings)
+    }
+
+    async fn active_era(&self) -> Result<u32> {
+        self.adapter
+            .storage()
+            .query_storage_json("Staking", "ActiveEra", vec![])
+            .await?
+            .and_then(|era| era.get("index").and_then(JsonValue::as_u64))
+            .map(|era| era as u32)
+            .ok_or_else(|| Error::Storage("Staking::ActiveEra is not set".to_string()))
+    }
+}
+
+/// Steps moving `holdings` to `new`
+///
+/// The old account releases its deposits before the native balance moves,
+/// and the new account re-creates proxies and identity once it is funded.
+fn plan_steps(new: &str, holdings: &Holdings) -> Vec<MigrationStep> {
+    use MigrationSigner::{New, Old};
+
+    let dest = json!({ "Id": new });
+    let transfer_all = || {
+        TxTemplate::new("transfer_all", "Balances", "transfer_all")
+            .with_arg("dest", dest.clone())
+            .with_arg("keep_alive", json!(false))
+    };
+    let mut steps = Vec::new();
+
+    if holdings.staking_role {
+        steps.push(MigrationStep::new(
+            "Stop nominating or validating",
+            Old,
+            TxTemplate::new("chill", "Staking", "chill"),
+        ));
+    }
+    if holdings.active_stake > 0 {
+        steps.push(MigrationStep::new(
+            format!("Unbond {} staked", holdings.active_stake),
+            Old,
+            TxTemplate::new("unbond", "Staking", "unbond")
+                .with_arg("value", json!(holdings.active_stake.to_string())),
+        ));
+    }
+    if !holdings.proxies.is_empty() {
+        steps.push(MigrationStep::new(
+            format!("Remove {} proxies", holdings.proxies.len()),
+            Old,
+            TxTemplate::new("remove_proxies", "Proxy", "remove_proxies"),
+        ));
+    }
+    if holdings.identity.is_some() {
+        steps.push(MigrationStep::new(
+            "Clear identity",
+            Old,
+            TxTemplate::new("clear_identity", "Identity", "clear_identity"),
+        ));
+    }
+    if !holdings.assets.is_empty() && holdings.new_is_empty {
+        // non-sufficient assets can't be held by an account that doesn't exist
+        steps.push(MigrationStep::new(
+            "Fund the new account with the existential deposit",
+            Old,
+            TxTemplate::new("transfer_keep_alive", "Balances", "transfer_keep_alive")
+                .with_arg("dest", dest.clone())
+                .with_arg("value", json!(holdings.existential_deposit.to_string())),
+        ));
+    }
+    for (asset_id, balance) in &holdings.assets {
+        steps.push(MigrationStep::new(
+            format!("Transfer {} of asset {}", balance, asset_id),
+            Old,
+            TxTemplate::new("transfer", "Assets", "transfer")
+                .with_arg("id", json!(asset_id))
+                .with_arg("target", dest.clone())
+                .with_arg("amount", json!(balance.to_string())),
+        ));
+    }
+    steps.push(MigrationStep::new(
+        "Transfer the transferable native balance",
+        Old,
+        transfer_all(),
+    ));
+    for (delegate, proxy_type, delay) in &holdings.proxies {
+        steps.push(MigrationStep::new(
+            format!("Add proxy {} ({})", delegate, proxy_type),
+            New,
+            TxTemplate::new("add_proxy", "Proxy", "add_proxy")
+                .with_arg("delegate", json!({ "Id": delegate }))
+                .with_arg("proxy_type", proxy_type.clone())
+                .with_arg("delay", json!(delay)),
+        ));
+    }
+    if let Some(info) = &holdings.identity {
+        steps.push(MigrationStep::new(
+            "Set identity",
+            New,
+            TxTemplate::new("set_identity", "Identity", "set_identity")
+                .with_arg("info", info.clone()),
+        ));
+    }
+    if let Some(era) = holdings.unlock_era {
+        steps.push(
+            MigrationStep::new(
+                "Withdraw unbonded stake",
+                Old,
+                TxTemplate::new("withdraw_unbonded", "Staking", "withdraw_unbonded")
+                    .with_arg("num_slashing_spans", json!(holdings.slashing_spans)),
+            )
+            .after_era(era),
+        );
+        steps.push(
+            MigrationStep::new("Transfer the withdrawn stake", Old, transfer_all()).after_era(era),
+        );
+    }
+    steps
+}
+
+/// Fail for a stash bonded with a separate controller
+///
+/// The old account signs `unbond` and `withdraw_unbonded`, which only the
+/// controller can, so such a stash has to make itself its own controller
+/// with `Staking::set_controller` before it can be migrated.
+fn check_controller(stash: &str, controller: &str) -> Result<()> {
+    if account_hex(controller)? != stash {
+        return Err(Error::Other(format!(
+            "{} is bonded with controller {}; call Staking::set_controller from {} before migrating",
+            stash, controller, stash
+        )));
+    }
+    Ok(())
+}
+
+/// Active stake and the era the last unlocking chunk unlocks in
+fn ledger_amounts(ledger: &JsonValue) -> (u128, Option<u32>) {
+    let active = json_u128(&ledger["active"]).unwrap_or_default();
+    // a single chunk decodes to the chunk itself
+    let chunks = match &ledger["unlocking"] {
+        JsonValue::Array(chunks) => chunks.iter().collect(),
+        chunk @ JsonValue::Object(_) => vec![chunk],
+        _ => Vec::new(),
+    };
+    let unlock_era = chunks
+        .into_iter()
+        .filter_map(|chunk| chunk["era"].as_u64())
+        .max()
+        .map(|era| era as u32);
+    (active, unlock_era)
+}
+
+/// `(delegate, proxy type, delay)` of each definition in a `Proxies` value
+fn proxy_definitions(proxies: &JsonValue) -> Vec<(String, JsonValue, u64)> {
+    // (BoundedVec<ProxyDefinition>, deposit); a single definition is unwrapped
+    let definitions = match &proxies[0] {
+        JsonValue::Array(definitions) => definitions.iter().collect(),
+        definition @ JsonValue::Object(_) => vec![definition],
+        _ => Vec::new(),
+    };
+    definitions
+        .into_iter()
+        .filter_map(|definition| {
+            Some((
+                definition["delegate"].as_str()?.to_string(),
+                definition["proxy_type"].clone(),
+                definition["delay"].as_u64().unwrap_or_default(),
+            ))
+        })
+        .collect()
+}
+
+/// `info` of an `IdentityOf` value
+fn identity_info(value: &JsonValue) -> Option<JsonValue> {
+    // relay chains stored `(Registration, Option<Username>)`
+    let registration = match value {
+        JsonValue::Array(parts) => parts.first()?,
+        registration => registration,
+    };
+    json_some(registration.get("info")?).cloned()
+}
+
+/// Number of slashing spans `withdraw_unbonded` has to be told about
+fn slashing_span_count(spans: &JsonValue) -> u32 {
+    let prior = match &spans["prior"] {
+        JsonValue::Array(prior) => prior.len(),
+        JsonValue::Null => 0,
+        _ => 1,
+    };
+    prior as u32 + 1
+}
+
+/// Asset id from an `Assets::Asset` key: prefix, Blake2-128 hash, then the id
+fn asset_id_from_key(key: &[u8]) -> Option<u32> {
+    let id = key.get(48..52)?;
+    Some(u32::from_le_bytes(id.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEW: &str = "0x8eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48";
+    const DELEGATE: &str = "0x90b5ab205c6974c9ea841be688864633dc9ca8a357843eeacf2314649965fe22";
+
+    fn calls(steps: &[MigrationStep]) -> Vec<(&str, &str)> {
+        steps
+            .iter()
+            .map(|step| (step.template.pallet.as_str(), step.template.call.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_order() {
+        let holdings = Holdings {
+            staking_role: true,
+            active_stake: 1_000,
+            unlock_era: Some(128),
+            slashing_spans: 0,
+            proxies: vec![(DELEGATE.to_string(), json!("Staking"), 0)],
+            identity: Some(json!({ "display": { "Raw5": "0x616c696365" } })),
+            assets: vec![(1984, 50)],
+            new_is_empty: true,
+            existential_deposit: 10,
+        };
+        let steps = plan_steps(NEW, &holdings);
+        assert_eq!(
+            calls(&steps),
+            vec![
+                ("Staking", "chill"),
+                ("Staking", "unbond"),
+                ("Proxy", "remove_proxies"),
+                ("Identity", "clear_identity"),
+                ("Balances", "transfer_keep_alive"),
+                ("Assets", "transfer"),
+                ("Balances", "transfer_all"),
+                ("Proxy", "add_proxy"),
+                ("Identity", "set_identity"),
+                ("Staking", "withdraw_unbonded"),
+                ("Balances", "transfer_all"),
+            ]
+        );
+        assert_eq!(steps[7].signer, MigrationSigner::New);
+        assert_eq!(steps[9].not_before_era, Some(128));
+        assert_eq!(steps[8].template.placeholders().len(), 0);
+    }
+
+    #[test]
+    fn test_plain_account_plan() {
+        let steps = plan_steps(NEW, &Holdings::default());
+        assert_eq!(calls(&steps), vec![("Balances", "transfer_all")]);
+
+        let mut plan = MigrationPlan {
+            old: DELEGATE.to_string(),
+            new: NEW.to_string(),
+            steps,
+        };
+        assert!(!plan.is_complete());
+        plan.steps[0].status = StepStatus::Done {
+            tx_hash: "0x01".to_string(),
+        };
+        assert!(plan.is_complete());
+        assert_eq!(plan.completed(), 1);
+        assert!(plan.next_step().is_none());
+    }
+
+    #[test]
+    fn test_separate_controller_is_rejected() {
+        assert!(check_controller(NEW, NEW).is_ok());
+        let error = check_controller(NEW, DELEGATE).unwrap_err().to_string();
+        assert!(error.contains(DELEGATE));
+        assert!(error.contains("set_controller"));
+    }
+
+    #[test]
+    fn test_storage_parsing() {
+        let ledger = json!({
+            "stash": NEW,
+            "total": "3000",
+            "active": 1000,
+            "unlocking": { "value": 2000, "era": 95 },
+        });
+        assert_eq!(ledger_amounts(&ledger), (1_000, Some(95)));
+
+        let proxies = json!([
+            [
+                { "delegate": DELEGATE, "proxy_type": "Any", "delay": 0 },
+                { "delegate": NEW, "proxy_type": "Governance", "delay": 10 },
+            ],
+            "200"
+        ]);
+        let definitions = proxy_definitions(&proxies);
+        assert_eq!(definitions.len(), 2);
+        assert_eq!(definitions[1], (NEW.to_string(), json!("Governance"), 10));
+
+        assert_eq!(
+            slashing_span_count(&json!({ "span_index": 2, "prior": [3, 4] })),
+            3
+        );
+
+        let mut key = vec![0u8; 48];
+        key.extend(1984u32.to_le_bytes());
+        assert_eq!(asset_id_from_key(&key), Some(1984));
+    }
+}
@@ -0,0 +1,302 @@
+//! Checking how long a signed extrinsic stays valid
+//!
+//! Mortal transactions commit to the hash of a recent block and are rejected
+//! once the era they were signed for has passed, usually 64 blocks later.
+//! Offline-signed transactions queued for later broadcast can silently run
+//! out while waiting. [`MortalityChecker`] reads the era from a signed
+//! extrinsic, has the node validate it against the current head and reports
+//! whether it is still valid and for how many more blocks:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{MortalityChecker, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter, queued: Vec<Vec<u8>>) -> Result<(), apex_sdk_substrate::Error> {
+//! let checker = MortalityChecker::new(adapter);
+//! for extrinsic in &queued {
+//!     let lifetime = checker.check(extrinsic).await?;
+//!     if lifetime.expires_within(10) {
+//!         println!("re-sign: {}", lifetime.invalid.as_deref().unwrap_or("about to expire"));
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A mortal extrinsic whose era has passed is reported by the node as a bad
+//! signature, since its signed block hash no longer matches.
+
+use crate::golden::signer_types;
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::{Compact, Decode};
+use subxt::ext::scale_value;
+use subxt::utils::H256;
+use subxt::Metadata;
+
+/// Identifier of the extension carrying the era
+const MORTALITY_EXTENSION: &str = "CheckMortality";
+
+/// `TransactionSource::External`, as for extrinsics received from peers
+const SOURCE_EXTERNAL: u8 = 2;
+
+/// Bit of the extrinsic version byte marking a signed extrinsic
+const SIGNED_BIT: u8 = 0b1000_0000;
+
+/// Validity window of a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Era {
+    /// Valid forever
+    Immortal,
+    /// Valid for `period` blocks from the last block whose number is
+    /// `phase` modulo `period`
+    Mortal {
+        /// Length of the window, a power of two
+        period: u64,
+        /// Position of the first block in the window
+        phase: u64,
+    },
+}
+
+impl Era {
+    /// Decode a SCALE-encoded era, advancing `input` past it
+    pub fn decode(input: &mut &[u8]) -> Result<Self> {
+        let invalid = || Error::Encoding("Invalid era".to_string());
+        let first = u8::decode(input).map_err(|_| invalid())?;
+        if first == 0 {
+            return Ok(Era::Immortal);
+        }
+        let second = u8::decode(input).map_err(|_| invalid())?;
+        let encoded = u16::from_le_bytes([first, second]) as u64;
+        let period = 2 << (encoded % (1 << 4));
+        let quantize_factor = (period >> 12).max(1);
+        let phase = (encoded >> 4) * quantize_factor;
+        if period < 4 || phase >= period {
+            return Err(invalid());
+        }
+        Ok(Era::Mortal { period, phase })
+    }
+
+    /// First block of the window that block `current` falls in
+    pub fn birth(&self, current: u64) -> u64 {
+        match *self {
+            Era::Immortal => 0,
+            Era::Mortal { period, phase } => (current.max(phase) - phase) / period * period + phase,
+        }
+    }
+
+    /// First block after that window; `None` if immortal
+    pub fn death(&self, current: u64) -> Option<u64> {
+        match *self {
+            Era::Immortal => None,
+            Era::Mortal { period, .. } => Some(self.birth(current) + period),
+        }
+    }
+}
+
+/// How long a signed extrinsic stays valid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lifetime {
+    /// Era the extrinsic was signed with
+    pub era: Era,
+    /// Chain head it was checked against
+    pub head: u64,
+    /// Last block it can be included in; `None` if immortal
+    pub valid_until: Option<u64>,
+    /// Why the node rejects it, if it does
+    pub invalid: Option<String>,
+}
+
+impl Lifetime {
+    /// Lifetime of an extrinsic with `era` that the node accepts or rejects
+    /// for `invalid` at `head`
+    ///
+    /// The next block is `head + 1`, so that is where the window is placed.
+    pub fn compute(era: Era, head: u64, invalid: Option<String>) -> Self {
+        Self {
+            era,
+            head,
+            valid_until: era.death(head + 1).map(|death| death - 1),
+            invalid,
+        }
+    }
+
+    /// Whether the node accepts the extrinsic now
+    pub fn is_valid(&self) -> bool {
+        self.invalid.is_none()
+    }
+
+    /// Blocks after the head it can still be included in; `None` if immortal
+    pub fn remaining(&self) -> Option<u64> {
+        if !self.is_valid() {
+            return Some(0);
+        }
+        self.valid_until
+            .map(|valid_until| valid_until.saturating_sub(self.head))
+    }
+
+    /// Whether it is invalid or will be within `blocks` blocks
+    pub fn expires_within(&self, blocks: u64) -> bool {
+        self.remaining()
+            .is_some_and(|remaining| remaining <= blocks)
+    }
+}
+
+/// Era of a signed extrinsic, with its length prefix, decoded against `metadata`
+///
+/// Extrinsics without the mortality extension are immortal.
+pub fn era_of(extrinsic: &[u8], metadata: &Metadata) -> Result<Era> {
+    let failed = |what: &str| Error::Encoding(format!("Failed to read era: {}", what));
+    let input = &mut &extrinsic[..];
+    Compact::<u32>::decode(input).map_err(|_| failed("bad length prefix"))?;
+    let version_byte = u8::decode(input).map_err(|_| failed("missing version"))?;
+    if version_byte & SIGNED_BIT == 0 {
+        return Err(failed("extrinsic is not signed"));
+    }
+
+    let extrinsic = metadata.extrinsic();
+    let (address_ty, signature_ty) = signer_types(metadata)?;
+    let skip = |input: &mut &[u8], ty: u32, what: &str| {
+        scale_value::scale::decode_as_type(input, ty, metadata.types())
+            .map(|_| ())
+            .map_err(|e| failed(&format!("bad {}: {}", what, e)))
+    };
+    skip(input, address_ty, "address")?;
+    skip(input, signature_ty, "signature")?;
+    for extension in extrinsic
+        .transaction_extensions_by_version(0)
+        .into_iter()
+        .flatten()
+    {
+        if extension.identifier() == MORTALITY_EXTENSION {
+            return Era::decode(input);
+        }
+        skip(input, extension.extra_ty(), extension.identifier())?;
+    }
+    Ok(Era::Immortal)
+}
+
+/// Checks signed extrinsics against the adapter's chain head
+pub struct MortalityChecker<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> MortalityChecker<'a> {
+    /// Checker using `adapter`
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Lifetime of `extrinsic`, encoded with its length prefix, at the best block
+    pub async fn check(&self, extrinsic: &[u8]) -> Result<Lifetime> {
+        let era = era_of(extrinsic, &self.adapter.client().metadata())?;
+        let spec = self.adapter.spec_client();
+        let head = spec.best_number().await?;
+        let hash = spec
+            .block_hash(head)
+            .await?
+            .ok_or_else(|| Error::Storage(format!("Block {} not found", head)))?;
+        let invalid = self.validate(extrinsic, hash).await?;
+        Ok(Lifetime::compute(era, head, invalid))
+    }
+
+    /// Why `TaggedTransactionQueue::validate_transaction` rejects
+    /// `extrinsic` at block `hash`, if it does
+    async fn validate(&self, extrinsic: &[u8], hash: H256) -> Result<Option<String>> {
+        let mut args = vec![SOURCE_EXTERNAL];
+        args.extend_from_slice(extrinsic);
+        args.extend_from_slice(&hash.0);
+        let validity = self
+            .adapter
+            .client()
+            .backend()
+            .call(
+                "TaggedTransactionQueue_validate_transaction",
+                Some(&args),
+                hash,
+            )
+            .await
+            .map_err(|e| Error::Transaction(format!("Failed to validate transaction: {}", e)))?;
+        validity_error(&validity)
+    }
+}
+
+/// Rejection reason in an encoded `TransactionValidity`; `None` if valid
+fn validity_error(validity: &[u8]) -> Result<Option<String>> {
+    let invalid = || Error::Encoding(format!("Invalid validity: 0x{}", hex::encode(validity)));
+    let reason = match validity {
+        [0, ..] => return Ok(None),
+        [1, 0, 7, code, ..] => format!("custom invalid transaction error {}", code),
+        [1, 0, variant, ..] => match variant {
+            0 => "call cannot be dispatched",
+            1 => "cannot pay fees",
+            2 => "nonce is in the future",
+            3 => "nonce was already used",
+            4 => "bad signature, possibly an expired era",
+            5 => "birth block is too old",
+            6 => "would exhaust block resources",
+            8 => "mandatory dispatch failed",
+            9 => "mandatory dispatch signed",
+            10 => "bad signer",
+            11 => "implicit data unavailable",
+            12 => "unknown origin",
+            _ => return Err(invalid()),
+        }
+        .to_string(),
+        [1, 1, 2, code, ..] => format!("custom unknown transaction error {}", code),
+        [1, 1, 0, ..] => "cannot look up accounts".to_string(),
+        [1, 1, 1, ..] => "no unsigned validator".to_string(),
+        _ => return Err(invalid()),
+    };
+    Ok(Some(reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::watch_only::mortal_era;
+
+    #[test]
+    fn test_era_window() {
+        let era = Era::decode(&mut &mortal_era(64, 1000)[..]).unwrap();
+        assert_eq!(
+            era,
+            Era::Mortal {
+                period: 64,
+                phase: 40
+            }
+        );
+        assert_eq!(era.birth(1000), 1000);
+        assert_eq!(era.birth(1063), 1000);
+        assert_eq!(era.death(1063), Some(1064));
+        assert_eq!(era.birth(1064), 1064);
+        assert_eq!(Era::decode(&mut &[0u8][..]).unwrap(), Era::Immortal);
+        assert!(Era::decode(&mut &[0x01u8][..]).is_err());
+
+        let lifetime = Lifetime::compute(era, 1050, None);
+        assert_eq!(lifetime.valid_until, Some(1063));
+        assert_eq!(lifetime.remaining(), Some(13));
+        assert!(!lifetime.expires_within(10));
+        assert!(lifetime.expires_within(13));
+
+        let expired = Lifetime::compute(era, 1070, Some("bad signature".to_string()));
+        assert!(!expired.is_valid());
+        assert!(expired.expires_within(0));
+
+        let immortal = Lifetime::compute(Era::Immortal, 1050, None);
+        assert_eq!(immortal.remaining(), None);
+        assert!(!immortal.expires_within(u64::MAX));
+    }
+
+    #[test]
+    fn test_validity_error() {
+        assert_eq!(validity_error(&[0, 1, 2, 3]).unwrap(), None);
+        assert_eq!(
+            validity_error(&[1, 0, 4]).unwrap().as_deref(),
+            Some("bad signature, possibly an expired era")
+        );
+        assert_eq!(
+            validity_error(&[1, 0, 7, 42]).unwrap().as_deref(),
+            Some("custom invalid transaction error 42")
+        );
+        assert!(validity_error(&[]).is_err());
+    }
+}
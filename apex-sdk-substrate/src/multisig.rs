@@ -0,0 +1,1011 @@
+//! Multisig approval orchestration
+//!
+//! A [`MultisigCoordinator`] drives calls of a `Multisig` pallet account to
+//! execution. It keeps each proposal's approval state in a
+//! [`MultisigStore`], so a restarted service picks up where it left off,
+//! tells [`SignatoryNotifier`]s whose approval is still missing, and, given
+//! a signatory wallet of its own, submits the final `as_multi` as soon as
+//! enough approvals are on chain:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{
+//!     FileMultisigStore, MultisigAccount, MultisigCoordinator, SubstrateAdapter, TxTemplate,
+//!     Wallet,
+//! };
+//! use serde_json::json;
+//!
+//! # async fn example(adapter: &SubstrateAdapter, alice: &Wallet, service: Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! let treasury = MultisigAccount::new(
+//!     &[
+//!         "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+//!         "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty",
+//!         "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y",
+//!     ],
+//!     2,
+//! )?;
+//! let coordinator = MultisigCoordinator::new(adapter)
+//!     .with_store(FileMultisigStore::new("multisig.json"))
+//!     .with_auto_execute(service)
+//!     .on_notice(|notice: &apex_sdk_substrate::MultisigNotice| {
+//!         println!("{:?}: waiting on {:?}", notice.kind, notice.waiting_on);
+//!     });
+//!
+//! let payout = TxTemplate::new("payout", "Balances", "transfer_keep_alive")
+//!     .with_arg("dest", json!({ "Id": "{{to}}" }))
+//!     .with_arg("value", json!("{{amount}}"));
+//! let params = json!({
+//!     "to": "5DAAnrj7VHTznn2AWBemMuyBwZWs6FNFjdyVXUeYum3PTXFy",
+//!     "amount": "1000000000000",
+//! });
+//! coordinator.propose(&treasury, alice, payout, params).await?;
+//! coordinator.run().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Approvals other than the final one only carry the call hash; the call
+//! itself is kept by the coordinator and submitted with the last approval.
+//! Failed final approvals are counted on the proposal, and auto-execution
+//! gives up on it after [`DEFAULT_MAX_EXECUTE_ATTEMPTS`] failures unless
+//! configured otherwise.
+
+use crate::block_limits::Weight;
+use crate::derivation::multisig_account;
+use crate::event_query::{account_hex, account_id, value_to_json};
+use crate::storage::StorageClient;
+use crate::template::TxTemplate;
+use crate::{Error, Result, SpecClient, SubstrateAdapter, TransactionExecutor, Wallet};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use subxt::dynamic::Value;
+use subxt::{OnlineClient, PolkadotConfig};
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// Failed final approvals after which auto-execution gives up on a proposal
+pub const DEFAULT_MAX_EXECUTE_ATTEMPTS: u32 = 3;
+
+/// Signatories and threshold of a multisig account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigAccount {
+    signatories: Vec<[u8; 32]>,
+    threshold: u16,
+}
+
+impl MultisigAccount {
+    /// Multisig of these signatories, SS58 or hex
+    pub fn new(signatories: &[&str], threshold: u16) -> Result<Self> {
+        let mut ids = signatories
+            .iter()
+            .map(|address| account_id(address))
+            .collect::<Result<Vec<_>>>()?;
+        ids.sort();
+        ids.dedup();
+        multisig_account(&ids, threshold)?;
+        Ok(Self {
+            signatories: ids,
+            threshold,
+        })
+    }
+
+    /// Account id of the multisig
+    pub fn account(&self) -> [u8; 32] {
+        multisig_account(&self.signatories, self.threshold).expect("validated on creation")
+    }
+
+    /// Approvals needed to execute a call
+    pub fn threshold(&self) -> u16 {
+        self.threshold
+    }
+
+    /// Signatories, sorted
+    pub fn signatories(&self) -> &[[u8; 32]] {
+        &self.signatories
+    }
+}
+
+/// Block and extrinsic index of the first approval of a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timepoint {
+    /// Block number
+    pub height: u32,
+    /// Extrinsic index in the block
+    pub index: u32,
+}
+
+/// Where a proposal stands
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    /// Collecting approvals
+    Pending,
+    /// The final approval was submitted by this coordinator
+    Executed {
+        /// Extrinsic hash of the final approval
+        tx_hash: String,
+    },
+    /// Cancelled by the depositor through this coordinator
+    Cancelled,
+    /// Removed from chain by someone else, by executing or cancelling it
+    Closed,
+}
+
+/// A multisig call and its approvals
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultisigProposal {
+    /// Blake2-256 hash of the call (hex), identifying the proposal
+    pub call_hash: String,
+    /// Multisig account (hex)
+    pub multisig: String,
+    /// Approvals needed to execute
+    pub threshold: u16,
+    /// Signatories (hex), sorted
+    pub signatories: Vec<String>,
+    /// The call, as a template and its parameters
+    pub template: TxTemplate,
+    /// Parameters filled into the template
+    pub params: JsonValue,
+    /// Timepoint of the first approval, once on chain
+    pub timepoint: Option<Timepoint>,
+    /// Signatories (hex) that approved, as seen on chain or submitted here
+    pub approvals: Vec<String>,
+    /// Where the proposal stands
+    pub status: ProposalStatus,
+    /// Failed submissions of the final approval
+    #[serde(default)]
+    pub failed_attempts: u32,
+    /// Error of the last failed submission of the final approval
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl MultisigProposal {
+    /// Signatories whose approval is still missing
+    pub fn waiting_on(&self) -> Vec<String> {
+        self.signatories
+            .iter()
+            .filter(|signatory| !self.approvals.contains(signatory))
+            .cloned()
+            .collect()
+    }
+
+    /// Whether enough signatories approved to execute the call
+    pub fn is_approved(&self) -> bool {
+        self.approvals.len() >= usize::from(self.threshold)
+    }
+
+    /// Whether approvals are still being collected
+    pub fn is_pending(&self) -> bool {
+        self.status == ProposalStatus::Pending
+    }
+
+    /// Count a failed submission of the final approval
+    fn record_failure(&mut self, error: &Error) {
+        self.failed_attempts += 1;
+        self.last_error = Some(error.to_string());
+    }
+
+    /// Signatories other than `signer`, as the pallet expects them
+    fn others(&self, signer: &str) -> Result<Vec<Value>> {
+        self.signatories
+            .iter()
+            .filter(|signatory| signatory.as_str() != signer)
+            .map(|signatory| Ok(Value::from_bytes(account_id(signatory)?)))
+            .collect()
+    }
+
+    /// Merge the pallet's `Multisigs` entry for this call, or its absence
+    fn apply_chain_state(&mut self, entry: Option<&JsonValue>) {
+        let Some(entry) = entry else {
+            // Gone after its first approval: executed or cancelled
+            if self.is_pending() && self.timepoint.is_some() {
+                self.status = ProposalStatus::Closed;
+            }
+            return;
+        };
+        let when = &entry["when"];
+        if let (Some(height), Some(index)) = (when["height"].as_u64(), when["index"].as_u64()) {
+            self.timepoint = Some(Timepoint {
+                height: height as u32,
+                index: index as u32,
+            });
+        }
+        // A single approval decodes to the account itself
+        self.approvals = match &entry["approvals"] {
+            JsonValue::Array(approvals) => approvals
+                .iter()
+                .filter_map(|approval| approval.as_str().map(str::to_lowercase))
+                .collect(),
+            JsonValue::String(approval) => vec![approval.to_lowercase()],
+            _ => Vec::new(),
+        };
+    }
+}
+
+/// What happened to a proposal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoticeKind {
+    /// A new proposal was submitted with its first approval
+    Proposed,
+    /// A signatory approved
+    Approved {
+        /// Approving signatory (hex)
+        by: String,
+    },
+    /// Enough approvals are on chain; the call awaits its final `as_multi`
+    ReadyToExecute,
+    /// The final approval was submitted
+    Executed {
+        /// Extrinsic hash
+        tx_hash: String,
+    },
+    /// The proposal was cancelled or closed
+    Closed,
+    /// Submitting an approval failed
+    Failed {
+        /// Error message
+        error: String,
+    },
+}
+
+/// A proposal update, passed to [`SignatoryNotifier`]s
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultisigNotice {
+    /// What happened
+    pub kind: NoticeKind,
+    /// Signatories (hex) whose approval is still missing
+    pub waiting_on: Vec<String>,
+    /// The proposal after the update
+    pub proposal: MultisigProposal,
+}
+
+/// Receiver of proposal updates, e.g. to remind remaining signatories
+#[async_trait]
+pub trait SignatoryNotifier: Send + Sync {
+    /// Called after every change to a proposal
+    async fn notify(&self, notice: &MultisigNotice);
+}
+
+#[async_trait]
+impl<F> SignatoryNotifier for F
+where
+    F: Fn(&MultisigNotice) + Send + Sync,
+{
+    async fn notify(&self, notice: &MultisigNotice) {
+        self(notice)
+    }
+}
+
+/// Posts notices as JSON to an HTTP endpoint
+#[cfg(feature = "webhook")]
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "webhook")]
+impl WebhookNotifier {
+    /// Notifier posting to `url`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Use an existing HTTP client
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+}
+
+#[cfg(feature = "webhook")]
+#[async_trait]
+impl SignatoryNotifier for WebhookNotifier {
+    async fn notify(&self, notice: &MultisigNotice) {
+        let result = self
+            .client
+            .post(&self.url)
+            .json(notice)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            warn!("Failed to post multisig notice to {}: {}", self.url, e);
+        }
+    }
+}
+
+/// Proposals as persisted, by call hash
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MultisigState {
+    /// Proposals by call hash
+    pub proposals: BTreeMap<String, MultisigProposal>,
+}
+
+/// Persistence of proposals and their approvals
+#[async_trait]
+pub trait MultisigStore: Send + Sync {
+    /// State saved last, or the default for a new store
+    async fn load(&self) -> Result<MultisigState>;
+
+    /// Replace the saved state
+    async fn save(&self, state: &MultisigState) -> Result<()>;
+}
+
+/// Keeps proposals in memory; they are lost on restart
+#[derive(Debug, Clone, Default)]
+pub struct MemoryMultisigStore {
+    state: Arc<parking_lot::Mutex<MultisigState>>,
+}
+
+#[async_trait]
+impl MultisigStore for MemoryMultisigStore {
+    async fn load(&self) -> Result<MultisigState> {
+        Ok(self.state.lock().clone())
+    }
+
+    async fn save(&self, state: &MultisigState) -> Result<()> {
+        *self.state.lock() = state.clone();
+        Ok(())
+    }
+}
+
+/// Keeps proposals in a JSON file
+#[derive(Debug, Clone)]
+pub struct FileMultisigStore {
+    path: PathBuf,
+}
+
+impl FileMultisigStore {
+    /// Store at `path`; the file is created on the first save
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl MultisigStore for FileMultisigStore {
+    async fn load(&self) -> Result<MultisigState> {
+        let json = match tokio::fs::read(&self.path).await {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(MultisigState::default())
+            }
+            Err(e) => {
+                return Err(Error::Storage(format!(
+                    "Failed to read {}: {}",
+                    self.path.display(),
+                    e
+                )))
+            }
+        };
+        serde_json::from_slice(&json).map_err(|e| {
+            Error::Encoding(format!(
+                "Invalid multisig state {}: {}",
+                self.path.display(),
+                e
+            ))
+        })
+    }
+
+    async fn save(&self, state: &MultisigState) -> Result<()> {
+        let json = serde_json::to_vec_pretty(state).map_err(|e| Error::Encoding(e.to_string()))?;
+        // write then rename, so a crash never leaves a truncated file
+        let partial = self.path.with_extension("partial");
+        let io = |e: std::io::Error| {
+            Error::Storage(format!("Failed to write {}: {}", self.path.display(), e))
+        };
+        tokio::fs::write(&partial, json).await.map_err(io)?;
+        tokio::fs::rename(&partial, &self.path).await.map_err(io)
+    }
+}
+
+/// Tracks multisig proposals and submits their approvals
+pub struct MultisigCoordinator {
+    client: OnlineClient<PolkadotConfig>,
+    executor: TransactionExecutor,
+    storage: StorageClient,
+    spec: SpecClient,
+    store: Arc<dyn MultisigStore>,
+    notifiers: Vec<Arc<dyn SignatoryNotifier>>,
+    auto_executor: Option<Wallet>,
+    max_execute_attempts: u32,
+    state: Mutex<Option<MultisigState>>,
+}
+
+impl MultisigCoordinator {
+    /// Coordinator submitting through the adapter's executor
+    ///
+    /// The adapter's policy, screening and audit log apply to every approval.
+    pub fn new(adapter: &SubstrateAdapter) -> Self {
+        Self {
+            client: adapter.client().clone(),
+            executor: adapter.transaction_executor(),
+            storage: adapter.storage(),
+            spec: adapter.spec_client(),
+            store: Arc::new(MemoryMultisigStore::default()),
+            notifiers: Vec::new(),
+            auto_executor: None,
+            max_execute_attempts: DEFAULT_MAX_EXECUTE_ATTEMPTS,
+            state: Mutex::new(None),
+        }
+    }
+
+    /// Persist proposals and approvals
+    pub fn with_store(mut self, store: impl MultisigStore + 'static) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// Register a notifier
+    pub fn on_notice(mut self, notifier: impl SignatoryNotifier + 'static) -> Self {
+        self.notifiers.push(Arc::new(notifier));
+        self
+    }
+
+    /// Submit the final approval with this signatory's wallet once the
+    /// other approvals are on chain
+    pub fn with_auto_execute(mut self, wallet: Wallet) -> Self {
+        self.auto_executor = Some(wallet);
+        self
+    }
+
+    /// Give up auto-executing a proposal after `attempts` failed final
+    /// approvals
+    pub fn with_max_execute_attempts(mut self, attempts: u32) -> Self {
+        self.max_execute_attempts = attempts;
+        self
+    }
+
+    /// Propose a call of `multisig`, with `signer`'s approval
+    pub async fn propose(
+        &self,
+        multisig: &MultisigAccount,
+        signer: &Wallet,
+        template: TxTemplate,
+        params: JsonValue,
+    ) -> Result<MultisigProposal> {
+        let call_data = template.call_data(&params, &self.client)?;
+        let proposal = MultisigProposal {
+            call_hash: format!("0x{}", hex::encode(sp_core::blake2_256(&call_data))),
+            multisig: format!("0x{}", hex::encode(multisig.account())),
+            threshold: multisig.threshold(),
+            signatories: multisig
+                .signatories()
+                .iter()
+                .map(|signatory| format!("0x{}", hex::encode(signatory)))
+                .collect(),
+            template,
+            params,
+            timepoint: None,
+            approvals: Vec::new(),
+            status: ProposalStatus::Pending,
+            failed_attempts: 0,
+            last_error: None,
+        };
+
+        let mut guard = self.lock_state().await?;
+        let state = guard.as_mut().expect("loaded by lock_state");
+        if let Some(existing) = state.proposals.get(&proposal.call_hash) {
+            if existing.is_pending() {
+                return Err(Error::Other(format!(
+                    "Call {} is already proposed",
+                    proposal.call_hash
+                )));
+            }
+        }
+        info!(
+            "Proposing {}::{} for multisig {} ({})",
+            proposal.template.pallet, proposal.template.call, proposal.multisig, proposal.call_hash
+        );
+        let mut proposal = self.refreshed(proposal).await?;
+        let kind = self.submit_approval(&mut proposal, signer).await?;
+        state
+            .proposals
+            .insert(proposal.call_hash.clone(), proposal.clone());
+        self.store.save(state).await?;
+        drop(guard);
+
+        let kind = match kind {
+            NoticeKind::Approved { .. } => NoticeKind::Proposed,
+            kind => kind,
+        };
+        self.notify(kind, &proposal).await;
+        Ok(proposal)
+    }
+
+    /// Add `signer`'s approval to a proposal
+    ///
+    /// Submits the final `as_multi` when this approval meets the threshold.
+    pub async fn approve(&self, call_hash: &str, signer: &Wallet) -> Result<MultisigProposal> {
+        let mut guard = self.lock_state().await?;
+        let state = guard.as_mut().expect("loaded by lock_state");
+        let proposal = Self::pending(state, call_hash)?.clone();
+        let mut proposal = self.refreshed(proposal).await?;
+        let result = if proposal.is_pending() {
+            self.submit_approval(&mut proposal, signer).await
+        } else {
+            Ok(NoticeKind::Closed)
+        };
+        // keep a failed final approval on record
+        state
+            .proposals
+            .insert(proposal.call_hash.clone(), proposal.clone());
+        self.store.save(state).await?;
+        drop(guard);
+        let kind = result?;
+
+        self.notify(kind, &proposal).await;
+        Ok(proposal)
+    }
+
+    /// Cancel a proposal; only its first approver, who holds the deposit,
+    /// can
+    pub async fn cancel(&self, call_hash: &str, signer: &Wallet) -> Result<MultisigProposal> {
+        let mut guard = self.lock_state().await?;
+        let state = guard.as_mut().expect("loaded by lock_state");
+        let mut proposal = Self::pending(state, call_hash)?.clone();
+        let timepoint = proposal
+            .timepoint
+            .ok_or_else(|| Error::Other(format!("Proposal {} is not on chain yet", call_hash)))?;
+        let signer_hex = account_hex(&signer.address())?;
+        self.executor
+            .submit_call(
+                signer,
+                "Multisig",
+                "cancel_as_multi",
+                vec![
+                    Value::u128(proposal.threshold.into()),
+                    Value::unnamed_composite(proposal.others(&signer_hex)?),
+                    timepoint_value(timepoint),
+                    Value::from_bytes(hash_bytes(&proposal.call_hash)?),
+                ],
+            )
+            .await?;
+        proposal.status = ProposalStatus::Cancelled;
+        state
+            .proposals
+            .insert(proposal.call_hash.clone(), proposal.clone());
+        self.store.save(state).await?;
+        drop(guard);
+
+        self.notify(NoticeKind::Closed, &proposal).await;
+        Ok(proposal)
+    }
+
+    /// All proposals known to the coordinator
+    pub async fn proposals(&self) -> Result<Vec<MultisigProposal>> {
+        let guard = self.lock_state().await?;
+        Ok(guard
+            .as_ref()
+            .expect("loaded by lock_state")
+            .proposals
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    /// Refresh pending proposals from chain, notifying signatories of
+    /// changes and executing approved calls if auto-execution is on
+    ///
+    /// Returns the proposals that changed. Failures of single proposals are
+    /// notified, not returned; errors are from the store.
+    pub async fn sync(&self) -> Result<Vec<MultisigProposal>> {
+        // the state is not locked while the chain is queried
+        let pending: Vec<MultisigProposal> = self
+            .lock_state()
+            .await?
+            .as_ref()
+            .expect("loaded by lock_state")
+            .proposals
+            .values()
+            .filter(|proposal| proposal.is_pending())
+            .cloned()
+            .collect();
+
+        let mut updates = Vec::new();
+        for proposal in pending {
+            let refreshed = match self.refreshed(proposal.clone()).await {
+                Ok(refreshed) => refreshed,
+                Err(e) => {
+                    warn!("Failed to refresh proposal {}: {}", proposal.call_hash, e);
+                    continue;
+                }
+            };
+            let mut updated = refreshed.clone();
+            let mut kinds: Vec<NoticeKind> = refreshed
+                .approvals
+                .iter()
+                .filter(|by| !proposal.approvals.contains(by))
+                .map(|by| NoticeKind::Approved { by: by.clone() })
+                .collect();
+            if !refreshed.is_pending() {
+                kinds.push(NoticeKind::Closed);
+            } else if let Some(kind) = self.auto_execute(&mut updated).await {
+                kinds.push(kind);
+            }
+            if updated != proposal {
+                updates.push((proposal, updated, kinds));
+            }
+        }
+
+        let mut guard = self.lock_state().await?;
+        let state = guard.as_mut().expect("loaded by lock_state");
+        let mut notices = Vec::new();
+        for (before, updated, kinds) in updates {
+            // changed meanwhile by `approve` or `cancel`; the next sync catches up
+            if state.proposals.get(&updated.call_hash) != Some(&before) {
+                debug!("Proposal {} changed during sync", updated.call_hash);
+                continue;
+            }
+            state
+                .proposals
+                .insert(updated.call_hash.clone(), updated.clone());
+            notices.extend(kinds.into_iter().map(|kind| (kind, updated.clone())));
+        }
+        self.store.save(state).await?;
+        drop(guard);
+
+        let mut changed: Vec<MultisigProposal> = Vec::new();
+        for (kind, proposal) in notices {
+            self.notify(kind, &proposal).await;
+            if changed.last().map(|last| &last.call_hash) != Some(&proposal.call_hash) {
+                changed.push(proposal);
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Sync on every finalized block until the subscription or store fails
+    pub async fn run(&self) -> Result<()> {
+        let mut heads = self.spec.follow_finalized().await?;
+        while let Some(head) = heads.next().await {
+            head?;
+            self.sync().await?;
+        }
+        Err(Error::Connection(
+            "Finalized heads subscription ended".to_string(),
+        ))
+    }
+
+    /// Submit the final approval of an approved call with the auto-executor
+    async fn auto_execute(&self, proposal: &mut MultisigProposal) -> Option<NoticeKind> {
+        let wallet = self.auto_executor.as_ref()?;
+        let executor_hex = account_hex(&wallet.address()).ok()?;
+        if !proposal.signatories.contains(&executor_hex) {
+            return None;
+        }
+        // The executor's own approval may be the missing one
+        let approvals =
+            proposal.approvals.len() + usize::from(!proposal.approvals.contains(&executor_hex));
+        if approvals < usize::from(proposal.threshold)
+            || proposal.failed_attempts >= self.max_execute_attempts
+        {
+            return None;
+        }
+        match self.submit_approval(proposal, wallet).await {
+            Ok(kind) => Some(kind),
+            Err(e) => {
+                warn!(
+                    "Failed to execute approved proposal {} (attempt {} of {}): {}",
+                    proposal.call_hash, proposal.failed_attempts, self.max_execute_attempts, e
+                );
+                Some(NoticeKind::Failed {
+                    error: e.to_string(),
+                })
+            }
+        }
+    }
+
+    /// Submit `signer`'s approval: hash-only, or the final `as_multi`
+    async fn submit_approval(
+        &self,
+        proposal: &mut MultisigProposal,
+        signer: &Wallet,
+    ) -> Result<NoticeKind> {
+        let signer_hex = account_hex(&signer.address())?;
+        if !proposal.signatories.contains(&signer_hex) {
+            return Err(Error::PolicyViolation(format!(
+                "{} is not a signatory of multisig {}",
+                signer.address(),
+                proposal.multisig
+            )));
+        }
+        let approvals =
+            proposal.approvals.len() + usize::from(!proposal.approvals.contains(&signer_hex));
+        let threshold = Value::u128(proposal.threshold.into());
+        let others = Value::unnamed_composite(proposal.others(&signer_hex)?);
+        let timepoint = match proposal.timepoint {
+            Some(timepoint) => Value::unnamed_variant("Some", [timepoint_value(timepoint)]),
+            None => Value::unnamed_variant("None", []),
+        };
+
+        if approvals < usize::from(proposal.threshold) {
+            if proposal.approvals.contains(&signer_hex) {
+                return Err(Error::Other(format!(
+                    "{} already approved {}",
+                    signer.address(),
+                    proposal.call_hash
+                )));
+            }
+            self.executor
+                .submit_call(
+                    signer,
+                    "Multisig",
+                    "approve_as_multi",
+                    vec![
+                        threshold,
+                        others,
+                        timepoint,
+                        Value::from_bytes(hash_bytes(&proposal.call_hash)?),
+                        Weight::default().to_value(),
+                    ],
+                )
+                .await?;
+            proposal.approvals.push(signer_hex.clone());
+            return Ok(NoticeKind::Approved { by: signer_hex });
+        }
+
+        let tx_hash = match self
+            .as_multi(proposal, signer, [threshold, others, timepoint])
+            .await
+        {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                proposal.record_failure(&e);
+                return Err(e);
+            }
+        };
+        if !proposal.approvals.contains(&signer_hex) {
+            proposal.approvals.push(signer_hex);
+        }
+        proposal.status = ProposalStatus::Executed {
+            tx_hash: tx_hash.clone(),
+        };
+        info!("Executed multisig call {}", proposal.call_hash);
+        Ok(NoticeKind::Executed { tx_hash })
+    }
+
+    /// Submit the final `as_multi` with the call, after the threshold,
+    /// other signatories and timepoint arguments
+    async fn as_multi(
+        &self,
+        proposal: &MultisigProposal,
+        signer: &Wallet,
+        leading: [Value; 3],
+    ) -> Result<String> {
+        let call = self.runtime_call(proposal)?;
+        let max_weight = self.call_weight(proposal, call.clone()).await?;
+        let mut args = Vec::from(leading);
+        args.extend([call, max_weight.to_value()]);
+        self.executor
+            .submit_call(signer, "Multisig", "as_multi", args)
+            .await
+    }
+
+    /// `RuntimeCall` value of a proposal's call
+    fn runtime_call(&self, proposal: &MultisigProposal) -> Result<Value> {
+        let args = proposal
+            .template
+            .call_args(&proposal.params, &self.client.metadata())?;
+        Ok(Value::unnamed_variant(
+            proposal.template.pallet.clone(),
+            [Value::unnamed_variant(proposal.template.call.clone(), args)],
+        ))
+    }
+
+    /// Dispatch weight of the call, the final approval's `max_weight`
+    async fn call_weight(&self, proposal: &MultisigProposal, call: Value) -> Result<Weight> {
+        let len = proposal
+            .template
+            .call_data(&proposal.params, &self.client)?
+            .len();
+        let payload = subxt::dynamic::runtime_api_call(
+            "TransactionPaymentCallApi",
+            "query_call_info",
+            vec![call, Value::u128(len as u128)],
+        );
+        let info = self
+            .client
+            .runtime_api()
+            .at_latest()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to fetch latest block: {}", e)))?
+            .call(payload)
+            .await
+            .map_err(|e| Error::Transaction(format!("Failed to query call weight: {}", e)))?
+            .to_value()
+            .map_err(|e| Error::Encoding(format!("Failed to decode call info: {}", e)))?;
+        Weight::from_json(&value_to_json(&info)["weight"])
+            .ok_or_else(|| Error::Encoding("Call info has no weight".to_string()))
+    }
+
+    /// A proposal with the pallet's current approvals merged in
+    async fn refreshed(&self, mut proposal: MultisigProposal) -> Result<MultisigProposal> {
+        let entry = self
+            .storage
+            .query_storage_json(
+                "Multisig",
+                "Multisigs",
+                vec![
+                    Value::from_bytes(hash_bytes(&proposal.multisig)?),
+                    Value::from_bytes(hash_bytes(&proposal.call_hash)?),
+                ],
+            )
+            .await?;
+        proposal.apply_chain_state(entry.as_ref());
+        Ok(proposal)
+    }
+
+    async fn lock_state(&self) -> Result<tokio::sync::MutexGuard<'_, Option<MultisigState>>> {
+        let mut guard = self.state.lock().await;
+        if guard.is_none() {
+            *guard = Some(self.store.load().await?);
+        }
+        Ok(guard)
+    }
+
+    fn pending<'s>(state: &'s MultisigState, call_hash: &str) -> Result<&'s MultisigProposal> {
+        state
+            .proposals
+            .get(&call_hash.to_lowercase())
+            .filter(|proposal| proposal.is_pending())
+            .ok_or_else(|| Error::Other(format!("No pending proposal {}", call_hash)))
+    }
+
+    async fn notify(&self, kind: NoticeKind, proposal: &MultisigProposal) {
+        let notice = MultisigNotice {
+            kind,
+            waiting_on: if proposal.is_pending() {
+                proposal.waiting_on()
+            } else {
+                Vec::new()
+            },
+            proposal: proposal.clone(),
+        };
+        for notifier in &self.notifiers {
+            notifier.notify(&notice).await;
+        }
+    }
+}
+
+fn hash_bytes(hex: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(hex.trim_start_matches("0x"))
+        .map_err(|e| Error::Encoding(format!("Invalid hash {}: {}", hex, e)))?;
+    bytes
+        .try_into()
+        .map_err(|_| Error::Encoding(format!("Hash {} is not 32 bytes", hex)))
+}
+
+fn timepoint_value(timepoint: Timepoint) -> Value {
+    Value::named_composite([
+        ("height", Value::u128(timepoint.height.into())),
+        ("index", Value::u128(timepoint.index.into())),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ALICE: &str = "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+    const BOB: &str = "0x8eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48";
+    const CHARLIE: &str = "0x90b5ab205c6974c9ea841be688864633dc9ca8a357843eeacf2314649965fe22";
+
+    fn proposal() -> MultisigProposal {
+        let multisig = MultisigAccount::new(&[CHARLIE, ALICE, BOB], 2).unwrap();
+        MultisigProposal {
+            call_hash: format!("0x{}", "ab".repeat(32)),
+            multisig: format!("0x{}", hex::encode(multisig.account())),
+            threshold: 2,
+            signatories: multisig
+                .signatories()
+                .iter()
+                .map(|signatory| format!("0x{}", hex::encode(signatory)))
+                .collect(),
+            template: TxTemplate::new("remark", "System", "remark"),
+            params: json!({}),
+            timepoint: None,
+            approvals: Vec::new(),
+            status: ProposalStatus::Pending,
+            failed_attempts: 0,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn test_multisig_account() {
+        let multisig = MultisigAccount::new(&[CHARLIE, ALICE, BOB, ALICE], 2).unwrap();
+        assert_eq!(multisig.signatories().len(), 3);
+        assert_eq!(
+            multisig.account(),
+            MultisigAccount::new(&[ALICE, BOB, CHARLIE], 2)
+                .unwrap()
+                .account()
+        );
+        assert!(MultisigAccount::new(&[ALICE, BOB], 3).is_err());
+    }
+
+    #[test]
+    fn test_apply_chain_state() {
+        let mut proposal = proposal();
+        proposal.apply_chain_state(Some(&json!({
+            "when": { "height": 120, "index": 2 },
+            "deposit": 1_000,
+            "depositor": ALICE,
+            "approvals": ALICE,
+        })));
+        assert_eq!(
+            proposal.timepoint,
+            Some(Timepoint {
+                height: 120,
+                index: 2
+            })
+        );
+        assert_eq!(proposal.approvals, vec![ALICE]);
+        assert!(!proposal.is_approved());
+        assert_eq!(proposal.waiting_on(), vec![BOB, CHARLIE]);
+
+        proposal.apply_chain_state(Some(&json!({
+            "when": { "height": 120, "index": 2 },
+            "approvals": [ALICE, BOB],
+        })));
+        assert!(proposal.is_approved());
+
+        proposal.apply_chain_state(None);
+        assert_eq!(proposal.status, ProposalStatus::Closed);
+    }
+
+    #[test]
+    fn test_unsubmitted_proposal_stays_pending() {
+        let mut proposal = proposal();
+        proposal.apply_chain_state(None);
+        assert!(proposal.is_pending());
+        assert!(proposal.approvals.is_empty());
+    }
+
+    #[test]
+    fn test_failed_final_approvals_are_recorded() {
+        let mut proposal = proposal();
+        proposal.record_failure(&Error::Transaction("Pool is full".to_string()));
+        proposal.record_failure(&Error::Transaction("Pool is full".to_string()));
+        assert_eq!(proposal.failed_attempts, 2);
+        assert!(proposal
+            .last_error
+            .as_deref()
+            .unwrap()
+            .contains("Pool is full"));
+
+        // proposals stored before attempts were counted
+        let mut stored = serde_json::to_value(proposal).unwrap();
+        let object = stored.as_object_mut().unwrap();
+        object.remove("failed_attempts");
+        object.remove("last_error");
+        let proposal: MultisigProposal = serde_json::from_value(stored).unwrap();
+        assert_eq!(proposal.failed_attempts, 0);
+        assert_eq!(proposal.last_error, None);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_round_trip() {
+        let path = std::env::temp_dir().join(format!("apex-multisig-{}.json", std::process::id()));
+        let store = FileMultisigStore::new(&path);
+        assert_eq!(store.load().await.unwrap(), MultisigState::default());
+
+        let mut state = MultisigState::default();
+        let proposal = proposal();
+        state.proposals.insert(proposal.call_hash.clone(), proposal);
+        store.save(&state).await.unwrap();
+        assert_eq!(store.load().await.unwrap(), state);
+        std::fs::remove_file(path).unwrap();
+    }
+}
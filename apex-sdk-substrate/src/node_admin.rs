@@ -0,0 +1,258 @@
+//! Peer and node operations
+//!
+//! [`NodeAdmin`] wraps the `system_*` RPC methods operators use to look after
+//! their own nodes: health, sync progress, connected peers and reserved
+//! peers:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{NodeAdmin, SubstrateAdapter};
+//!
+//! # async fn example(own_node: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let admin = NodeAdmin::new(own_node);
+//! let sync = admin.sync_state().await?;
+//! println!("synced {} / {:?}", sync.current_block, sync.highest_block);
+//!
+//! for peer in admin.peers().await? {
+//!     println!("{} at #{}", peer.peer_id, peer.best_number);
+//! }
+//! admin
+//!     .add_reserved_peer("/dns/boot.example.com/tcp/30333/p2p/12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Peer listing and reserved peer changes are unsafe RPC methods, which nodes
+//! only expose to trusted connections with `--rpc-methods unsafe`. They fail
+//! with [`Error::Connection`] explaining this when the node refuses them.
+
+use crate::{Error, Result, SubstrateAdapter};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use subxt::ext::subxt_rpcs::client::RpcParams;
+use tracing::info;
+
+/// Health of a node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeHealth {
+    /// Number of connected peers
+    pub peers: u64,
+    /// Whether the node is major syncing
+    pub is_syncing: bool,
+    /// Whether the node is expected to have peers
+    pub should_have_peers: bool,
+}
+
+impl NodeHealth {
+    /// Whether the node is synced and connected where it should be
+    pub fn is_healthy(&self) -> bool {
+        !self.is_syncing && (self.peers > 0 || !self.should_have_peers)
+    }
+}
+
+/// Sync progress of a node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncState {
+    /// Block the node started syncing from
+    pub starting_block: u64,
+    /// Best block of the node
+    pub current_block: u64,
+    /// Highest block seen from peers, if known
+    #[serde(default)]
+    pub highest_block: Option<u64>,
+}
+
+impl SyncState {
+    /// Blocks the node is behind the highest block seen
+    pub fn blocks_behind(&self) -> u64 {
+        self.highest_block
+            .map_or(0, |highest| highest.saturating_sub(self.current_block))
+    }
+}
+
+/// A peer connected to a node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerInfo {
+    /// libp2p peer id
+    pub peer_id: String,
+    /// Roles of the peer, e.g. `FULL` or `AUTHORITY`
+    pub roles: String,
+    /// Best block hash of the peer (hex)
+    pub best_hash: String,
+    /// Best block number of the peer
+    pub best_number: u64,
+}
+
+/// Operations on the node behind an adapter
+pub struct NodeAdmin<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> NodeAdmin<'a> {
+    /// Manage the node behind the adapter
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Node name and version, e.g. `("Parity Polkadot", "1.16.0-...")`
+    pub async fn version(&self) -> Result<(String, String)> {
+        let name = self.call("system_name", RpcParams::new()).await?;
+        let version = self.call("system_version", RpcParams::new()).await?;
+        Ok((name, version))
+    }
+
+    /// Health of the node
+    pub async fn health(&self) -> Result<NodeHealth> {
+        self.call("system_health", RpcParams::new()).await
+    }
+
+    /// Sync progress of the node
+    pub async fn sync_state(&self) -> Result<SyncState> {
+        self.call("system_syncState", RpcParams::new()).await
+    }
+
+    /// Roles of the node, e.g. `["Full"]` or `["Authority"]`
+    pub async fn roles(&self) -> Result<Vec<String>> {
+        self.call("system_nodeRoles", RpcParams::new()).await
+    }
+
+    /// libp2p peer id of the node
+    pub async fn local_peer_id(&self) -> Result<String> {
+        self.call("system_localPeerId", RpcParams::new()).await
+    }
+
+    /// Multiaddresses the node listens on
+    pub async fn listen_addresses(&self) -> Result<Vec<String>> {
+        self.call("system_localListenAddresses", RpcParams::new())
+            .await
+    }
+
+    /// Peers connected to the node (unsafe)
+    pub async fn peers(&self) -> Result<Vec<PeerInfo>> {
+        self.call("system_peers", RpcParams::new()).await
+    }
+
+    /// Peer ids of the node's reserved peers
+    pub async fn reserved_peers(&self) -> Result<Vec<String>> {
+        self.call("system_reservedPeers", RpcParams::new()).await
+    }
+
+    /// Add a reserved peer by its multiaddress, which has to end in
+    /// `/p2p/<peer id>` (unsafe)
+    pub async fn add_reserved_peer(&self, multiaddr: &str) -> Result<()> {
+        validate_peer_multiaddr(multiaddr)?;
+        let mut params = RpcParams::new();
+        params
+            .push(multiaddr)
+            .map_err(|e| Error::Encoding(format!("Failed to encode multiaddress: {}", e)))?;
+        self.call::<()>("system_addReservedPeer", params).await?;
+        info!("Added reserved peer {}", multiaddr);
+        Ok(())
+    }
+
+    /// Remove a reserved peer by its peer id (unsafe)
+    pub async fn remove_reserved_peer(&self, peer_id: &str) -> Result<()> {
+        let mut params = RpcParams::new();
+        params
+            .push(peer_id)
+            .map_err(|e| Error::Encoding(format!("Failed to encode peer id: {}", e)))?;
+        self.call::<()>("system_removeReservedPeer", params).await?;
+        info!("Removed reserved peer {}", peer_id);
+        Ok(())
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: RpcParams) -> Result<T> {
+        let advertised = self.adapter.rpc_capabilities();
+        if advertised.methods().next().is_some() && !advertised.supports(method) {
+            return Err(Error::Connection(format!(
+                "Node does not expose {}; it may need --rpc-methods unsafe",
+                method
+            )));
+        }
+        self.adapter
+            .rpc_client()
+            .request(method, params)
+            .await
+            .map_err(|e| {
+                let message = e.to_string();
+                if is_unsafe_refusal(&message) {
+                    Error::Connection(format!(
+                        "Node refused {}; unsafe methods need --rpc-methods unsafe and a trusted connection",
+                        method
+                    ))
+                } else {
+                    Error::Connection(format!("Failed to call {}: {}", method, message))
+                }
+            })
+    }
+}
+
+/// Whether an RPC error is the node refusing an unsafe method
+fn is_unsafe_refusal(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("unsafe") || message.contains("-32601")
+}
+
+/// Ensure a reserved peer multiaddress names the peer with `/p2p/<peer id>`
+fn validate_peer_multiaddr(multiaddr: &str) -> Result<()> {
+    let mut parts = multiaddr.rsplitn(3, '/');
+    let peer_id = parts.next().unwrap_or_default();
+    let protocol = parts.next().unwrap_or_default();
+    if !multiaddr.starts_with('/') || protocol != "p2p" || peer_id.is_empty() {
+        return Err(Error::Other(format!(
+            "Multiaddress {} has to end in /p2p/<peer id>",
+            multiaddr
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_node_responses() {
+        let peers: Vec<PeerInfo> = serde_json::from_value(json!([{
+            "peerId": "12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp",
+            "roles": "FULL",
+            "bestHash": "0x00",
+            "bestNumber": 42
+        }]))
+        .unwrap();
+        assert_eq!(peers[0].best_number, 42);
+
+        let sync: SyncState = serde_json::from_value(
+            json!({ "startingBlock": 0, "currentBlock": 90, "highestBlock": 100 }),
+        )
+        .unwrap();
+        assert_eq!(sync.blocks_behind(), 10);
+        let sync: SyncState =
+            serde_json::from_value(json!({ "startingBlock": 0, "currentBlock": 90 })).unwrap();
+        assert_eq!(sync.blocks_behind(), 0);
+
+        let health: NodeHealth = serde_json::from_value(
+            json!({ "peers": 0, "isSyncing": false, "shouldHavePeers": true }),
+        )
+        .unwrap();
+        assert!(!health.is_healthy());
+    }
+
+    #[test]
+    fn test_validate_peer_multiaddr() {
+        assert!(validate_peer_multiaddr(
+            "/ip4/10.0.0.1/tcp/30333/p2p/12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp"
+        )
+        .is_ok());
+        assert!(validate_peer_multiaddr("/ip4/10.0.0.1/tcp/30333").is_err());
+        assert!(validate_peer_multiaddr("/ip4/10.0.0.1/tcp/30333/p2p/").is_err());
+        assert!(
+            validate_peer_multiaddr("12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp")
+                .is_err()
+        );
+    }
+}
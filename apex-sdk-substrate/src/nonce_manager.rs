@@ -142,10 +142,9 @@ impl SubstrateNonceManager {
 impl CoreNonceManager for SubstrateNonceManager {
     async fn get_next_nonce(&self, address: &Address) -> std::result::Result<u64, SdkError> {
         match address {
-            Address::Substrate(addr) => self.reconcile_nonce(addr).await.map_err(SdkError::from),
-            _ => Err(SdkError::ConfigError(
-                "Invalid address type for Substrate nonce manager".to_string(),
-            )),
+            Address::Substrate(addr) | Address::Evm(addr) => {
+                self.reconcile_nonce(addr).await.map_err(SdkError::from)
+            }
         }
     }
 }
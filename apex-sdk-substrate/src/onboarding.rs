@@ -0,0 +1,340 @@
+//! Gasless onboarding: a new account's first transaction paid in an asset
+//!
+//! A brand-new account that has only received a stablecoin holds no native
+//! token to pay fees with. On chains with the `ChargeAssetTxPayment`
+//! extension it can pay in the asset instead, as long as the asset is
+//! *sufficient*, i.e. keeps the account alive without the native existential
+//! deposit. [`GaslessOnboarding`] checks this, converts the fee estimate to
+//! the asset and submits the call, paying natively once the account holds
+//! the existential deposit and enough for the fee:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{GaslessOnboarding, SubstrateAdapter, Wallet};
+//! use subxt::dynamic::Value;
+//!
+//! # async fn example(adapter: &SubstrateAdapter, new_user: &Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! const USDT: u32 = 1984;
+//! let onboarding = GaslessOnboarding::new(adapter, USDT)?;
+//!
+//! let receipt = onboarding
+//!     .submit(new_user, "System", "remark", vec![Value::from_bytes(b"hello")])
+//!     .await?;
+//! println!(
+//!     "{} paid {} of asset {} in fees",
+//!     receipt.tx_hash, receipt.status.asset_fee, USDT
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The fee in the asset follows `pallet-asset-tx-payment`'s conversion by the
+//! ratio of the asset's minimum balance to the native existential deposit.
+//! Chains converting through `pallet-asset-conversion` with XCM location
+//! asset ids, such as current Asset Hubs, are rejected by
+//! [`GaslessOnboarding::new`].
+
+use crate::assets::AssetId;
+use crate::receipt::json_u128;
+use crate::transaction::FeeConfig;
+use crate::wallet::Wallet;
+use crate::{Error, Result, SubstrateAdapter};
+use scale_info::{TypeDef, TypeDefPrimitive};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::dynamic::Value;
+use subxt::Metadata;
+use tracing::info;
+
+/// Transaction extension paying fees in an asset
+const ASSET_PAYMENT_EXTENSION: &str = "ChargeAssetTxPayment";
+
+/// Why an account can't submit its first transaction yet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingBlocker {
+    /// The asset is not sufficient, so it can't keep an account without the
+    /// native existential deposit alive
+    AssetNotSufficient,
+    /// The asset balance doesn't cover the fee and the asset's minimum
+    /// balance left after it
+    InsufficientAsset {
+        /// Asset needed: the fee plus the minimum balance
+        needed: u128,
+        /// Asset held
+        available: u128,
+    },
+}
+
+/// Whether and how an account can pay for its first transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OnboardingStatus {
+    /// Free native balance
+    pub native_balance: u128,
+    /// Native existential deposit
+    pub existential_deposit: u128,
+    /// Estimated fee in the native token
+    pub native_fee: u128,
+    /// Asset balance
+    pub asset_balance: u128,
+    /// Asset minimum balance
+    pub asset_min_balance: u128,
+    /// Whether the asset keeps accounts alive on its own
+    pub asset_sufficient: bool,
+    /// Estimated fee converted to the asset
+    pub asset_fee: u128,
+    /// What prevents paying in the asset, if anything
+    pub blocker: Option<OnboardingBlocker>,
+}
+
+impl OnboardingStatus {
+    /// Whether the native balance covers the fee and keeps the existential
+    /// deposit
+    pub fn native_funded(&self) -> bool {
+        self.native_balance >= self.existential_deposit.saturating_add(self.native_fee)
+    }
+
+    /// Whether the transaction can be submitted, natively or in the asset
+    pub fn is_ready(&self) -> bool {
+        self.native_funded() || self.blocker.is_none()
+    }
+
+    fn from_balances(
+        native_balance: u128,
+        existential_deposit: u128,
+        native_fee: u128,
+        asset_balance: u128,
+        asset_details: &JsonValue,
+    ) -> Self {
+        let asset_min_balance = json_u128(&asset_details["min_balance"]).unwrap_or_default();
+        let asset_sufficient = asset_details["is_sufficient"].as_bool().unwrap_or(false);
+        let asset_fee = asset_fee(native_fee, asset_min_balance, existential_deposit);
+        let needed = asset_fee.saturating_add(asset_min_balance);
+        let blocker = if !asset_sufficient && native_balance < existential_deposit {
+            Some(OnboardingBlocker::AssetNotSufficient)
+        } else if asset_balance < needed {
+            Some(OnboardingBlocker::InsufficientAsset {
+                needed,
+                available: asset_balance,
+            })
+        } else {
+            None
+        };
+        Self {
+            native_balance,
+            existential_deposit,
+            native_fee,
+            asset_balance,
+            asset_min_balance,
+            asset_sufficient,
+            asset_fee,
+            blocker,
+        }
+    }
+}
+
+/// A submitted first transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OnboardingReceipt {
+    /// Extrinsic hash
+    pub tx_hash: String,
+    /// Whether the fee was paid in the asset
+    pub paid_in_asset: bool,
+    /// Balances and estimates the submission was based on
+    pub status: OnboardingStatus,
+}
+
+/// Submits transactions of new accounts, paying fees in an asset
+pub struct GaslessOnboarding<'a> {
+    adapter: &'a SubstrateAdapter,
+    asset_id: u32,
+}
+
+impl<'a> GaslessOnboarding<'a> {
+    /// Onboarding paying fees in `pallet-assets` asset `asset_id`
+    ///
+    /// Fails on chains without `ChargeAssetTxPayment` taking `u32` asset ids.
+    pub fn new(adapter: &'a SubstrateAdapter, asset_id: u32) -> Result<Self> {
+        adapter.require_pallet("Assets")?;
+        check_asset_payment(&adapter.client().metadata())?;
+        Ok(Self { adapter, asset_id })
+    }
+
+    /// Check whether `wallet` can pay for `pallet::call`
+    pub async fn check(
+        &self,
+        wallet: &Wallet,
+        pallet: &str,
+        call: &str,
+        args: Vec<Value>,
+    ) -> Result<OnboardingStatus> {
+        let storage = self.adapter.storage();
+        let native_fee = self
+            .adapter
+            .transaction_executor()
+            .estimate_fee(pallet, call, args, wallet)
+            .await?;
+        let address = wallet.address();
+        let details = storage
+            .query_storage_json("Assets", "Asset", vec![Value::u128(self.asset_id.into())])
+            .await?
+            .ok_or_else(|| Error::Other(format!("Asset {} does not exist", self.asset_id)))?;
+        Ok(OnboardingStatus::from_balances(
+            storage.get_account_info(&address).await?.free,
+            storage.get_existential_deposit()?,
+            native_fee,
+            storage
+                .get_asset_balance(&address, &AssetId::Local(self.asset_id))
+                .await?,
+            &details,
+        ))
+    }
+
+    /// Submit `pallet::call` from `wallet`, paying in the asset unless the
+    /// native balance already covers the fee
+    ///
+    /// Fails with [`Error::Transaction`] naming the blocker when neither can
+    /// pay.
+    pub async fn submit(
+        &self,
+        wallet: &Wallet,
+        pallet: &str,
+        call: &str,
+        args: Vec<Value>,
+    ) -> Result<OnboardingReceipt> {
+        let status = self.check(wallet, pallet, call, args.clone()).await?;
+        let paid_in_asset = !status.native_funded();
+        let executor = self.adapter.transaction_executor();
+        let executor = if paid_in_asset {
+            if let Some(blocker) = &status.blocker {
+                return Err(Error::Transaction(format!(
+                    "{} can't pay fees in asset {}: {:?}",
+                    wallet.address(),
+                    self.asset_id,
+                    blocker
+                )));
+            }
+            info!(
+                "Paying {} of asset {} in fees for {}",
+                status.asset_fee,
+                self.asset_id,
+                wallet.address()
+            );
+            executor.with_fee_config(FeeConfig::new().with_fee_asset(self.asset_id))
+        } else {
+            executor
+        };
+        let tx_hash = executor.submit_call(wallet, pallet, call, args).await?;
+        Ok(OnboardingReceipt {
+            tx_hash,
+            paid_in_asset,
+            status,
+        })
+    }
+}
+
+/// Native fee converted to the asset as `pallet-asset-tx-payment` does, by the
+/// ratio of the asset's minimum balance to the existential deposit, rounded up
+fn asset_fee(native_fee: u128, asset_min_balance: u128, existential_deposit: u128) -> u128 {
+    if existential_deposit == 0 {
+        return native_fee;
+    }
+    native_fee
+        .saturating_mul(asset_min_balance)
+        .div_ceil(existential_deposit)
+}
+
+/// Ensure the chain pays fees through `ChargeAssetTxPayment` with `u32`
+/// asset ids
+fn check_asset_payment(metadata: &Metadata) -> Result<()> {
+    let extension = metadata
+        .extrinsic()
+        .transaction_extensions_by_version(0)
+        .into_iter()
+        .flatten()
+        .find(|extension| extension.identifier() == ASSET_PAYMENT_EXTENSION)
+        .ok_or_else(|| {
+            Error::Other(format!(
+                "Chain has no {} extension",
+                ASSET_PAYMENT_EXTENSION
+            ))
+        })?;
+    let types = metadata.types();
+    let unsupported = || {
+        Error::Other(format!(
+            "{} of this chain doesn't take u32 asset ids",
+            ASSET_PAYMENT_EXTENSION
+        ))
+    };
+    let TypeDef::Composite(extra) = &types
+        .resolve(extension.extra_ty())
+        .ok_or_else(unsupported)?
+        .type_def
+    else {
+        return Err(unsupported());
+    };
+    let asset_id = extra
+        .fields
+        .iter()
+        .find(|field| field.name.as_deref() == Some("asset_id"))
+        .ok_or_else(unsupported)?;
+    // Option<AssetId>
+    let TypeDef::Variant(option) = &types
+        .resolve(asset_id.ty.id)
+        .ok_or_else(unsupported)?
+        .type_def
+    else {
+        return Err(unsupported());
+    };
+    let inner = option
+        .variants
+        .iter()
+        .find(|variant| variant.name == "Some")
+        .and_then(|some| some.fields.first())
+        .ok_or_else(unsupported)?;
+    match &types.resolve(inner.ty.id).ok_or_else(unsupported)?.type_def {
+        TypeDef::Primitive(TypeDefPrimitive::U32) => Ok(()),
+        _ => Err(unsupported()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_asset_fee_conversion() {
+        // 0.01 DOT ED (10 decimals) against 0.7 USDT min balance (6 decimals)
+        assert_eq!(asset_fee(150_000_000, 700_000, 100_000_000), 1_050_000);
+        assert_eq!(asset_fee(1, 1, 3), 1);
+        assert_eq!(asset_fee(5, 7, 0), 5);
+    }
+
+    #[test]
+    fn test_onboarding_status() {
+        let sufficient = json!({ "min_balance": 700_000, "is_sufficient": true });
+        let status = OnboardingStatus::from_balances(0, 100, 50, 10_000_000, &sufficient);
+        assert!(!status.native_funded());
+        assert_eq!(status.asset_fee, 350_000);
+        assert_eq!(status.blocker, None);
+        assert!(status.is_ready());
+
+        let status = OnboardingStatus::from_balances(0, 100, 50, 900_000, &sufficient);
+        assert_eq!(
+            status.blocker,
+            Some(OnboardingBlocker::InsufficientAsset {
+                needed: 1_050_000,
+                available: 900_000
+            })
+        );
+        assert!(!status.is_ready());
+
+        let insufficient = json!({ "min_balance": 700_000, "is_sufficient": false });
+        let status = OnboardingStatus::from_balances(0, 100, 50, 10_000_000, &insufficient);
+        assert_eq!(status.blocker, Some(OnboardingBlocker::AssetNotSufficient));
+
+        let status = OnboardingStatus::from_balances(500, 100, 50, 0, &insufficient);
+        assert!(status.native_funded());
+        assert!(status.is_ready());
+    }
+}
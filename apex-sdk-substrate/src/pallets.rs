@@ -0,0 +1,173 @@
+//! Pallet availability on the connected chain
+//!
+//! Runtimes differ in which pallets they include: NFTs live in `Uniques` on
+//! some chains and in `Nfts` on others, parachains name the XCM pallet
+//! `PolkadotXcm` where relay chains use `XcmPallet`, and many chains have no
+//! staking or nomination pools at all. High-level modules check the metadata
+//! first and return [`Error::PalletNotAvailable`] — naming alternatives the
+//! chain does have — instead of an opaque decode or storage error.
+//!
+//! ```rust
+//! use apex_sdk_substrate::pallets::PalletFeatures;
+//! use apex_sdk_substrate::Error;
+//!
+//! let pallets = PalletFeatures::new(["System", "Balances", "Nfts"]);
+//! assert!(pallets.has("Nfts"));
+//!
+//! match pallets.require("Uniques") {
+//!     Err(Error::PalletNotAvailable { alternatives, .. }) => {
+//!         assert_eq!(alternatives, vec!["Nfts".to_string()]);
+//!     }
+//!     _ => unreachable!(),
+//! }
+//! ```
+
+use crate::{Error, Result};
+use std::collections::BTreeSet;
+use subxt::Metadata;
+
+/// Pallets that offer similar functionality under another name
+pub const PALLET_ALTERNATIVES: &[(&str, &[&str])] = &[
+    ("Uniques", &["Nfts"]),
+    ("Nfts", &["Uniques"]),
+    ("XcmPallet", &["PolkadotXcm"]),
+    ("PolkadotXcm", &["XcmPallet"]),
+    ("Democracy", &["ConvictionVoting", "Referenda"]),
+    ("ConvictionVoting", &["Democracy"]),
+    ("Referenda", &["Democracy"]),
+    ("Contracts", &["Revive"]),
+    ("Revive", &["Contracts"]),
+    ("NominationPools", &["Staking"]),
+    ("Staking", &["ParachainStaking", "CollatorSelection"]),
+];
+
+/// Known alternatives for a pallet, whether or not the chain has them
+pub fn alternatives(pallet: &str) -> &'static [&'static str] {
+    PALLET_ALTERNATIVES
+        .iter()
+        .find(|(name, _)| *name == pallet)
+        .map(|(_, alternatives)| *alternatives)
+        .unwrap_or_default()
+}
+
+/// Fail with [`Error::PalletNotAvailable`] unless `metadata` contains `pallet`
+pub fn require_pallet(metadata: &Metadata, pallet: &str) -> Result<()> {
+    if metadata.pallet_by_name(pallet).is_some() {
+        return Ok(());
+    }
+    Err(missing_pallet(metadata, pallet))
+}
+
+/// [`Error::PalletNotAvailable`] for `pallet`, listing alternatives in `metadata`
+pub(crate) fn missing_pallet(metadata: &Metadata, pallet: &str) -> Error {
+    not_available(pallet, |name| metadata.pallet_by_name(name).is_some())
+}
+
+/// Error message suffix listing alternatives
+pub(crate) fn suggestion(alternatives: &[String]) -> String {
+    if alternatives.is_empty() {
+        String::new()
+    } else {
+        format!(" (try: {})", alternatives.join(", "))
+    }
+}
+
+/// Build the error for a missing pallet, listing alternatives that are present
+fn not_available(pallet: &str, present: impl Fn(&str) -> bool) -> Error {
+    Error::PalletNotAvailable {
+        pallet: pallet.to_string(),
+        alternatives: alternatives(pallet)
+            .iter()
+            .filter(|name| present(name))
+            .map(|name| name.to_string())
+            .collect(),
+    }
+}
+
+/// Set of pallets included in a runtime
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PalletFeatures {
+    names: BTreeSet<String>,
+}
+
+impl PalletFeatures {
+    /// Pallet set from a list of names
+    pub fn new<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            names: names.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Pallets present in `metadata`
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        Self::new(metadata.pallets().map(|pallet| pallet.name().to_string()))
+    }
+
+    /// Check whether the runtime includes `pallet`
+    pub fn has(&self, pallet: &str) -> bool {
+        self.names.contains(pallet)
+    }
+
+    /// Fail with [`Error::PalletNotAvailable`] unless the runtime includes `pallet`
+    pub fn require(&self, pallet: &str) -> Result<()> {
+        if self.has(pallet) {
+            return Ok(());
+        }
+        Err(not_available(pallet, |name| self.has(name)))
+    }
+
+    /// First of `candidates` the runtime includes
+    pub fn first_of<'a>(&self, candidates: &[&'a str]) -> Option<&'a str> {
+        candidates.iter().copied().find(|name| self.has(name))
+    }
+
+    /// All pallet names, sorted
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_require_suggests_present_alternatives() {
+        let pallets = PalletFeatures::new(["System", "Staking", "PolkadotXcm"]);
+
+        assert!(pallets.require("Staking").is_ok());
+
+        let err = pallets.require("NominationPools").unwrap_err();
+        assert!(matches!(
+            &err,
+            Error::PalletNotAvailable { pallet, alternatives }
+                if pallet == "NominationPools" && alternatives == &["Staking".to_string()]
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Pallet NominationPools is not available on this chain (try: Staking)"
+        );
+
+        // Nfts is a known alternative but absent, so nothing is suggested
+        let err = pallets.require("Uniques").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Pallet Uniques is not available on this chain"
+        );
+    }
+
+    #[test]
+    fn test_first_of() {
+        let pallets = PalletFeatures::new(["XcmPallet"]);
+        assert_eq!(
+            pallets.first_of(&["PolkadotXcm", "XcmPallet"]),
+            Some("XcmPallet")
+        );
+        assert_eq!(pallets.first_of(&["Nfts", "Uniques"]), None);
+        assert!(alternatives("Balances").is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+//! Safe mode and paused calls
+//!
+//! Chains with the `SafeMode` pallet can be put in safe mode, which filters
+//! every call outside a small whitelist until a given block; chains with
+//! `TxPause` can pause single calls. Either way, affected transactions are
+//! still included but fail with an opaque `CallFiltered`, fee paid.
+//! [`StorageClient::pause_state`] reads both, executors refuse filtered
+//! submissions with [`Error::ChainPaused`] and the adapter's health check
+//! fails while the chain is in safe mode:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::SubstrateAdapter;
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let state = adapter.storage().pause_state().await?;
+//! if let Some(until) = state.safe_mode_until {
+//!     println!("safe mode until block {}", until);
+//! }
+//! for (pallet, call) in &state.paused_calls {
+//!     println!("{}::{} is paused", pallet, call);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`StorageClient::pause_state`]: crate::StorageClient::pause_state
+
+use crate::{Error, Result};
+use parity_scale_codec::Decode;
+use serde_json::Value as JsonValue;
+
+/// Pallets whose calls runtimes usually let through in safe mode
+///
+/// The whitelist is runtime configuration, not state; this is the common
+/// choice of the Polkadot SDK runtimes.
+pub const SAFE_MODE_WHITELIST: &[&str] = &[
+    "System",
+    "Timestamp",
+    "ParachainSystem",
+    "SafeMode",
+    "TxPause",
+];
+
+/// Length of a map key prefix: pallet and item hashes plus a `Twox64Concat` hash
+const PAUSED_CALLS_KEY_PREFIX: usize = 16 + 16 + 8;
+
+/// Safe mode and paused calls of a chain
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PauseState {
+    /// Last block of safe mode, if engaged
+    pub safe_mode_until: Option<u64>,
+    /// Calls paused by `TxPause`, as pallet and call names
+    pub paused_calls: Vec<(String, String)>,
+}
+
+impl PauseState {
+    /// State from the decoded `SafeMode::EnteredUntil` value and the raw
+    /// `TxPause::PausedCalls` keys
+    pub(crate) fn from_storage(entered_until: Option<&JsonValue>, paused_keys: &[Vec<u8>]) -> Self {
+        Self {
+            safe_mode_until: entered_until.and_then(JsonValue::as_u64),
+            paused_calls: paused_keys
+                .iter()
+                .map(Vec::as_slice)
+                .filter_map(paused_call)
+                .collect(),
+        }
+    }
+
+    /// Whether safe mode is engaged or any call is paused
+    pub fn is_engaged(&self) -> bool {
+        self.safe_mode_until.is_some() || !self.paused_calls.is_empty()
+    }
+
+    /// Whether safe mode filters calls of `pallet`
+    pub fn in_safe_mode(&self, pallet: &str) -> bool {
+        self.safe_mode_until.is_some() && !SAFE_MODE_WHITELIST.contains(&pallet)
+    }
+
+    /// Whether `TxPause` has paused `pallet::call`
+    pub fn is_paused(&self, pallet: &str, call: &str) -> bool {
+        self.paused_calls
+            .iter()
+            .any(|(paused_pallet, paused_call)| paused_pallet == pallet && paused_call == call)
+    }
+
+    /// Fail with [`Error::ChainPaused`] if `pallet::call` would be filtered
+    pub fn check(&self, pallet: &str, call: &str) -> Result<()> {
+        let reason = match self.safe_mode_until {
+            Some(until) if self.in_safe_mode(pallet) => {
+                format!("the chain is in safe mode until block {}", until)
+            }
+            _ if self.is_paused(pallet, call) => "the call is paused".to_string(),
+            _ => return Ok(()),
+        };
+        Err(Error::ChainPaused {
+            pallet: pallet.to_string(),
+            call: call.to_string(),
+            reason,
+        })
+    }
+}
+
+/// Pallet and call names in a raw `TxPause::PausedCalls` key
+fn paused_call(key: &[u8]) -> Option<(String, String)> {
+    let mut name = key.get(PAUSED_CALLS_KEY_PREFIX..)?;
+    let (pallet, call) = <(Vec<u8>, Vec<u8>)>::decode(&mut name).ok()?;
+    Some((
+        String::from_utf8(pallet).ok()?,
+        String::from_utf8(call).ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_scale_codec::Encode;
+    use serde_json::json;
+
+    #[test]
+    fn test_pause_state_from_storage() {
+        let mut key = vec![0u8; PAUSED_CALLS_KEY_PREFIX];
+        key.extend((b"Balances".to_vec(), b"transfer_allow_death".to_vec()).encode());
+
+        let state = PauseState::from_storage(None, &[key, vec![1, 2, 3]]);
+        assert_eq!(
+            state.paused_calls,
+            vec![("Balances".to_string(), "transfer_allow_death".to_string())]
+        );
+        assert!(state.check("Balances", "transfer_keep_alive").is_ok());
+        let err = state.check("Balances", "transfer_allow_death").unwrap_err();
+        assert!(matches!(err, Error::ChainPaused { .. }));
+
+        let state = PauseState::from_storage(Some(&json!(1_200_000)), &[]);
+        assert!(state.is_engaged());
+        assert!(state.check("System", "remark").is_ok());
+        assert!(state
+            .check("Staking", "bond")
+            .unwrap_err()
+            .to_string()
+            .contains("safe mode until block 1200000"));
+    }
+}
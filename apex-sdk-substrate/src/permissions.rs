@@ -0,0 +1,412 @@
+//! Checking whether a call would pass the runtime's filters
+//!
+//! Some calls are rejected at dispatch even though they are well formed: the
+//! signer is a proxy whose type does not cover the call, the chain is in
+//! safe mode or has the call paused, or the asset or the account holding it
+//! is frozen. The transaction is still included and its fee still paid.
+//! [`CallCheck`] reads the relevant state up front and reports every
+//! restriction it finds:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{CallCheck, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let permission = CallCheck::new(
+//!     "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty",
+//!     "Assets",
+//!     "transfer",
+//! )
+//! .via_proxy_of("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")
+//! .with_asset(1984)
+//! .run(adapter)
+//! .await?;
+//!
+//! if !permission.is_likely_dispatchable() {
+//!     for restriction in &permission.restrictions {
+//!         println!("blocked: {}", restriction);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Proxy filters live in runtime code, so they are judged from the usual
+//! meaning of common proxy types; unknown types are reported in
+//! [`CallPermission::unknown`] rather than as restrictions. The same goes for
+//! the calls safe mode lets through, see
+//! [`SAFE_MODE_WHITELIST`](crate::pause::SAFE_MODE_WHITELIST).
+
+use crate::account20::account_bytes;
+use crate::event_query::account_hex;
+use crate::{Result, SubstrateAdapter};
+use serde_json::Value as JsonValue;
+use std::fmt;
+use subxt::dynamic::Value;
+
+/// Why a call would be filtered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Restriction {
+    /// The chain is in safe mode until the given block
+    SafeMode {
+        /// Last block of safe mode
+        until: u64,
+    },
+    /// The call is paused by `TxPause`
+    Paused,
+    /// The signer is not a proxy of the real account
+    NotAProxy,
+    /// None of the signer's proxy types allow the call
+    ProxyType {
+        /// Proxy types the signer holds for the real account
+        proxy_types: Vec<String>,
+    },
+    /// The asset is frozen or being destroyed
+    AssetFrozen {
+        /// Asset id
+        asset_id: u32,
+        /// Asset status, e.g. `Frozen`
+        status: String,
+    },
+    /// The account's holding of the asset is frozen or blocked
+    AccountFrozen {
+        /// Asset id
+        asset_id: u32,
+        /// Account status, e.g. `Blocked`
+        status: String,
+    },
+}
+
+impl fmt::Display for Restriction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Restriction::SafeMode { until } => {
+                write!(f, "chain is in safe mode until block {}", until)
+            }
+            Restriction::Paused => write!(f, "call is paused"),
+            Restriction::NotAProxy => write!(f, "signer is not a proxy of the real account"),
+            Restriction::ProxyType { proxy_types } => write!(
+                f,
+                "proxy types {} do not allow the call",
+                proxy_types.join(", ")
+            ),
+            Restriction::AssetFrozen { asset_id, status } => {
+                write!(f, "asset {} is {}", asset_id, status)
+            }
+            Restriction::AccountFrozen { asset_id, status } => {
+                write!(f, "account is {} for asset {}", status, asset_id)
+            }
+        }
+    }
+}
+
+/// What a [`CallCheck`] found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallPermission {
+    /// Pallet of the call
+    pub pallet: String,
+    /// Name of the call
+    pub call: String,
+    /// Filters the call would hit
+    pub restrictions: Vec<Restriction>,
+    /// Filters that could not be judged, such as unknown proxy types
+    pub unknown: Vec<String>,
+}
+
+impl CallPermission {
+    /// Whether no known filter rejects the call
+    pub fn is_likely_dispatchable(&self) -> bool {
+        self.restrictions.is_empty()
+    }
+}
+
+/// Builder for checking one call of one account against the runtime's
+/// filters
+#[derive(Debug, Clone)]
+pub struct CallCheck {
+    signer: String,
+    pallet: String,
+    call: String,
+    real: Option<String>,
+    asset_id: Option<u32>,
+}
+
+impl CallCheck {
+    /// Check `pallet::call` signed by `signer`, an SS58 or hex address
+    pub fn new(
+        signer: impl Into<String>,
+        pallet: impl Into<String>,
+        call: impl Into<String>,
+    ) -> Self {
+        Self {
+            signer: signer.into(),
+            pallet: pallet.into(),
+            call: call.into(),
+            real: None,
+            asset_id: None,
+        }
+    }
+
+    /// Dispatch the call through `Proxy::proxy` on behalf of `real`
+    pub fn via_proxy_of(mut self, real: impl Into<String>) -> Self {
+        self.real = Some(real.into());
+        self
+    }
+
+    /// Check the status of `asset_id` in the `Assets` pallet and of the
+    /// dispatching account's holding of it
+    pub fn with_asset(mut self, asset_id: u32) -> Self {
+        self.asset_id = Some(asset_id);
+        self
+    }
+
+    /// Read the filters' state through `adapter`
+    ///
+    /// Filters of pallets the runtime lacks are skipped.
+    pub async fn run(&self, adapter: &SubstrateAdapter) -> Result<CallPermission> {
+        let pallets = adapter.pallets();
+        let storage = adapter.storage();
+        let mut permission = CallPermission {
+            pallet: self.pallet.clone(),
+            call: self.call.clone(),
+            restrictions: Vec::new(),
+            unknown: Vec::new(),
+        };
+
+        let pause = storage.pause_state().await?;
+        if let Some(until) = pause.safe_mode_until {
+            if pause.in_safe_mode(&self.pallet) {
+                permission
+                    .restrictions
+                    .push(Restriction::SafeMode { until });
+            }
+        }
+        if pause.is_paused(&self.pallet, &self.call) {
+            permission.restrictions.push(Restriction::Paused);
+        }
+
+        if let Some(real) = &self.real {
+            let proxies = if pallets.has("Proxy") {
+                storage
+                    .query_storage_json(
+                        "Proxy",
+                        "Proxies",
+                        vec![Value::from_bytes(account_bytes(real)?)],
+                    )
+                    .await?
+            } else {
+                None
+            };
+            let proxy_types = proxy_types(proxies.as_ref(), &account_hex(&self.signer)?);
+            match proxy_check(&proxy_types, &self.pallet, &self.call) {
+                Some(true) => {}
+                Some(false) if proxy_types.is_empty() => {
+                    permission.restrictions.push(Restriction::NotAProxy)
+                }
+                Some(false) => permission
+                    .restrictions
+                    .push(Restriction::ProxyType { proxy_types }),
+                None => permission
+                    .unknown
+                    .push(format!("proxy types {}", proxy_types.join(", "))),
+            }
+        }
+
+        if let (Some(asset_id), true) = (self.asset_id, pallets.has("Assets")) {
+            let holder = account_bytes(self.real.as_ref().unwrap_or(&self.signer))?;
+            let details = storage
+                .query_storage_json("Assets", "Asset", vec![Value::u128(asset_id as u128)])
+                .await?;
+            let account = storage
+                .query_storage_json(
+                    "Assets",
+                    "Account",
+                    vec![Value::u128(asset_id as u128), Value::from_bytes(holder)],
+                )
+                .await?;
+            permission.restrictions.extend(asset_restrictions(
+                asset_id,
+                details.as_ref(),
+                account.as_ref(),
+            ));
+        }
+
+        Ok(permission)
+    }
+}
+
+/// Proxy types `delegate` holds in a decoded `Proxy::Proxies` entry
+pub(crate) fn proxy_types(proxies: Option<&JsonValue>, delegate: &str) -> Vec<String> {
+    // (BoundedVec<ProxyDefinition>, deposit); a single definition is unwrapped
+    let definitions = match proxies.map(|proxies| &proxies[0]) {
+        Some(JsonValue::Array(definitions)) => definitions.iter().collect(),
+        Some(definition @ JsonValue::Object(_)) => vec![definition],
+        _ => Vec::new(),
+    };
+    definitions
+        .into_iter()
+        .filter(|definition| {
+            definition["delegate"]
+                .as_str()
+                .is_some_and(|account| account.eq_ignore_ascii_case(delegate))
+        })
+        .filter_map(|definition| match &definition["proxy_type"] {
+            JsonValue::String(name) => Some(name.clone()),
+            JsonValue::Object(variant) => variant.keys().next().cloned(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether any of `proxy_types` allows `pallet::call`; `None` if that
+/// depends on a type whose filter is not known
+fn proxy_check(proxy_types: &[String], pallet: &str, call: &str) -> Option<bool> {
+    let mut unknown = false;
+    for proxy_type in proxy_types {
+        match proxy_allows(proxy_type, pallet, call) {
+            Some(true) => return Some(true),
+            Some(false) => {}
+            None => unknown = true,
+        }
+    }
+    if unknown {
+        None
+    } else {
+        Some(false)
+    }
+}
+
+/// Whether a proxy of `proxy_type` may dispatch `pallet::call`, following
+/// the filters of the Polkadot and system chain runtimes
+fn proxy_allows(proxy_type: &str, pallet: &str, call: &str) -> Option<bool> {
+    // batches and multisigs are allowed; their inner calls are filtered again
+    let wrapper = matches!(pallet, "Utility" | "Multisig");
+    let allowed = match proxy_type {
+        "Any" => true,
+        "NonTransfer" => !matches!(
+            (pallet, call),
+            (
+                "Balances" | "Assets" | "ForeignAssets" | "PoolAssets" | "Nfts" | "Uniques",
+                _
+            ) | ("Vesting", "vested_transfer")
+        ),
+        "Governance" => {
+            wrapper
+                || matches!(
+                    pallet,
+                    "Treasury"
+                        | "Bounties"
+                        | "ChildBounties"
+                        | "ConvictionVoting"
+                        | "Referenda"
+                        | "Whitelist"
+                )
+        }
+        "Staking" => {
+            wrapper
+                || matches!(
+                    pallet,
+                    "Staking" | "Session" | "FastUnstake" | "VoterList" | "NominationPools"
+                )
+        }
+        "NominationPools" => wrapper || pallet == "NominationPools",
+        "IdentityJudgement" => wrapper || (pallet, call) == ("Identity", "provide_judgement"),
+        "CancelProxy" => wrapper || (pallet, call) == ("Proxy", "reject_announcement"),
+        "Auction" => wrapper || matches!(pallet, "Auctions" | "Crowdloan" | "Registrar" | "Slots"),
+        "Assets" => wrapper || matches!(pallet, "Assets" | "Nfts" | "Uniques"),
+        _ => return None,
+    };
+    Some(allowed)
+}
+
+/// Restrictions from decoded `Assets::Asset` and `Assets::Account` entries
+fn asset_restrictions(
+    asset_id: u32,
+    details: Option<&JsonValue>,
+    account: Option<&JsonValue>,
+) -> Vec<Restriction> {
+    let mut restrictions = Vec::new();
+    if let Some(status) = details.and_then(|details| details["status"].as_str()) {
+        if status != "Live" {
+            restrictions.push(Restriction::AssetFrozen {
+                asset_id,
+                status: status.to_string(),
+            });
+        }
+    }
+    let status = account.and_then(|account| match &account["status"] {
+        JsonValue::String(status) => Some(status.clone()),
+        // before account statuses, only a frozen flag was kept
+        _ => (account["is_frozen"] == true).then(|| "Frozen".to_string()),
+    });
+    if let Some(status) = status.filter(|status| status != "Liquid") {
+        restrictions.push(Restriction::AccountFrozen { asset_id, status });
+    }
+    restrictions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_proxy_types_and_filters() {
+        let delegate = format!("0x{}", "aa".repeat(32));
+        let other = format!("0x{}", "bb".repeat(32));
+        let proxies = json!([
+            [
+                { "delegate": delegate, "proxy_type": "Staking", "delay": 0 },
+                { "delegate": other, "proxy_type": "Any", "delay": 0 },
+            ],
+            "2000000000"
+        ]);
+        let types = proxy_types(Some(&proxies), &delegate);
+        assert_eq!(types, vec!["Staking".to_string()]);
+        assert_eq!(proxy_check(&types, "Staking", "bond_extra"), Some(true));
+        assert_eq!(
+            proxy_check(&types, "Balances", "transfer_keep_alive"),
+            Some(false)
+        );
+
+        // a single definition decodes without the surrounding list
+        let single = json!([{ "delegate": other, "proxy_type": "NonTransfer", "delay": 0 }, 0]);
+        let types = proxy_types(Some(&single), &other);
+        assert_eq!(proxy_check(&types, "Balances", "transfer_all"), Some(false));
+        assert_eq!(proxy_check(&types, "ConvictionVoting", "vote"), Some(true));
+
+        assert_eq!(
+            proxy_check(
+                &["Collator".to_string()],
+                "CollatorSelection",
+                "register_as_candidate"
+            ),
+            None
+        );
+        assert_eq!(proxy_check(&[], "System", "remark"), Some(false));
+    }
+
+    #[test]
+    fn test_asset_restrictions() {
+        let live = json!({ "owner": "0x00", "status": "Live" });
+        let liquid = json!({ "balance": 10, "status": "Liquid", "reason": "Consumer" });
+        assert!(asset_restrictions(1984, Some(&live), Some(&liquid)).is_empty());
+
+        let frozen = json!({ "owner": "0x00", "status": "Frozen" });
+        let blocked = json!({ "balance": 10, "status": "Blocked", "reason": "Consumer" });
+        let restrictions = asset_restrictions(1984, Some(&frozen), Some(&blocked));
+        assert_eq!(restrictions.len(), 2);
+        assert_eq!(
+            restrictions[1].to_string(),
+            "account is Blocked for asset 1984"
+        );
+
+        let legacy = json!({ "balance": 10, "is_frozen": true });
+        assert_eq!(
+            asset_restrictions(7, None, Some(&legacy)),
+            vec![Restriction::AccountFrozen {
+                asset_id: 7,
+                status: "Frozen".to_string()
+            }]
+        );
+    }
+}
@@ -0,0 +1,1116 @@
+//! Role-based approval of outgoing transactions
+//!
+//! A [`Policy`] assigns signers to [`Role`]s and checks every transaction a
+//! [`TransactionExecutor`](crate::TransactionExecutor) is about to sign
+//! against the rules of the signer's role:
+//! - daily and rolling-window limits on the amount transferred, per asset
+//! - allowed destinations
+//! - allowed calls
+//! - co-approval above an amount, escalated to an [`ApprovalHandler`]
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::assets::AssetId;
+//! use apex_sdk_substrate::{ApprovalRequest, Policy, Role, SubstrateAdapter, Wallet};
+//!
+//! # async fn example(adapter: SubstrateAdapter, wallet: &Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! const DOT: u128 = 10_000_000_000;
+//! let treasury = Role::new("treasury")
+//!     .with_daily_limit(AssetId::Native, 1_000 * DOT)
+//!     .with_co_approval_above(100 * DOT)
+//!     .allow_destination("5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty");
+//! let policy = Policy::new()
+//!     .with_role(treasury)
+//!     .assign(&wallet.address(), "treasury")?
+//!     .on_escalation(|request: &ApprovalRequest| {
+//!         println!("approve? {:?}", request);
+//!         false
+//!     });
+//!
+//! let adapter = adapter.with_policy(policy);
+//! adapter
+//!     .transaction_executor()
+//!     .transfer(wallet, "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty", 50 * DOT)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Signers without a role are rejected. Transfers are reserved as
+//! [`Outflow`]s in a [`SpendStore`], in memory unless another store is
+//! configured, in the same step that checks them against the limits, and
+//! removed again if approval is refused or the transaction fails. Daily
+//! limits count from midnight UTC. Velocity limits over a rolling window
+//! either reject transfers or hold them for co-approval.
+//!
+//! Amounts are read from `Balances` and `Assets` transfers, including those
+//! inside batches and other wrapping calls. Roles with amount rules reject
+//! other calls that move funds, as the policy cannot count them, and roles
+//! with allowed destinations reject calls moving funds to a recipient that
+//! cannot be read, such as XCM transfers.
+//!
+//! Operators can let a single transfer exceed the limits with an
+//! [`OverrideToken`] from [`Policy::issue_override`], passed to
+//! [`TransactionExecutor::transfer_asset_with_override`](crate::TransactionExecutor::transfer_asset_with_override).
+//! A token is used up by the first transaction it is presented with, whether
+//! or not that transaction is approved.
+
+use crate::assets::AssetId;
+use crate::event_query::{account_id, value_to_json};
+use crate::{Error, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use subxt::ext::scale_value;
+use subxt::Metadata;
+use tracing::{info, warn};
+
+/// A transaction about to be signed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutgoingTransaction {
+    /// Signer address
+    pub signer: String,
+    /// Pallet of the call
+    pub pallet: String,
+    /// Call name
+    pub call: String,
+    /// Recipient, for transfers
+    pub destination: Option<String>,
+    /// Account the funds are taken from, when not the signer's
+    pub source: Option<String>,
+    /// Asset and amount, for transfers
+    pub amount: Option<(AssetId, u128)>,
+    /// Whether the call moves funds by an amount that could not be read
+    pub unknown_amount: bool,
+    /// Whether the call moves funds to a recipient that could not be read
+    pub unknown_destination: bool,
+    /// Id of an [`OverrideToken`] lifting the signer's spending limits
+    pub override_token: Option<String>,
+}
+
+impl OutgoingTransaction {
+    /// A call without a known recipient or amount
+    pub fn call(signer: impl Into<String>, pallet: &str, call: &str) -> Self {
+        Self {
+            signer: signer.into(),
+            pallet: pallet.to_string(),
+            call: call.to_string(),
+            destination: None,
+            source: None,
+            amount: None,
+            unknown_amount: false,
+            unknown_destination: false,
+            override_token: None,
+        }
+    }
+
+    /// A `transfer_keep_alive` of `amount` units of `asset` to `destination`
+    pub fn transfer(
+        signer: impl Into<String>,
+        destination: impl Into<String>,
+        asset: AssetId,
+        amount: u128,
+    ) -> Self {
+        Self {
+            destination: Some(destination.into()),
+            amount: Some((asset.clone(), amount)),
+            ..Self::call(signer, asset.pallet(), "transfer_keep_alive")
+        }
+    }
+
+    /// Transactions made by the encoded `RuntimeCall` `call_data`
+    ///
+    /// Batches and calls wrapping another call, e.g. through `Proxy` or
+    /// `Multisig`, are listed with the calls they dispatch.
+    pub fn from_call_data(
+        signer: impl Into<String>,
+        call_data: &[u8],
+        metadata: &Metadata,
+    ) -> Result<Vec<Self>> {
+        let mut input = call_data;
+        let call = scale_value::scale::decode_as_type(
+            &mut input,
+            metadata.outer_enums().call_enum_ty(),
+            metadata.types(),
+        )
+        .map_err(|e| Error::Encoding(format!("Invalid call: {}", e)))?;
+        if !input.is_empty() {
+            return Err(Error::Encoding(format!(
+                "Invalid call: {} trailing bytes",
+                input.len()
+            )));
+        }
+        let mut outgoing = Vec::new();
+        Self::collect(&signer.into(), &value_to_json(&call), &mut outgoing)?;
+        Ok(outgoing)
+    }
+
+    /// Add the transactions of a `RuntimeCall` rendered as JSON
+    fn collect(signer: &str, call: &JsonValue, outgoing: &mut Vec<Self>) -> Result<()> {
+        let invalid = || Error::Encoding(format!("Invalid call: {}", call));
+        let (pallet, inner) = single_entry(call).ok_or_else(invalid)?;
+        let (name, args) = match inner {
+            JsonValue::String(name) => (name.as_str(), &JsonValue::Null),
+            inner => single_entry(inner).ok_or_else(invalid)?,
+        };
+
+        let mut transaction = Self::call(signer, pallet, name);
+        match (pallet, name) {
+            (
+                "Balances",
+                "transfer" | "transfer_keep_alive" | "transfer_allow_death" | "force_transfer",
+            ) => {
+                transaction.destination = args.get("dest").map(address);
+                transaction.source = args.get("source").map(address);
+                transaction.amount = amount(&args["value"]).map(|value| (AssetId::Native, value));
+            }
+            ("Balances", "transfer_all") => {
+                transaction.destination = args.get("dest").map(address);
+            }
+            (
+                "Assets" | "ForeignAssets",
+                "transfer"
+                | "transfer_keep_alive"
+                | "transfer_approved"
+                | "transfer_all"
+                | "force_transfer",
+            ) => {
+                transaction.destination = args
+                    .get("target")
+                    .or_else(|| args.get("destination"))
+                    .or_else(|| args.get("dest"))
+                    .map(address);
+                transaction.source = args
+                    .get("source")
+                    .or_else(|| args.get("owner"))
+                    .map(address);
+                let asset = args["id"]
+                    .as_u64()
+                    .and_then(|id| u32::try_from(id).ok())
+                    .map(AssetId::Local);
+                transaction.amount = asset.zip(amount(&args["amount"]));
+            }
+            _ => {}
+        }
+        transaction.unknown_amount = transaction.amount.is_none() && moves_funds(name);
+        transaction.unknown_destination = transaction.destination.is_none() && moves_funds(name);
+        outgoing.push(transaction);
+
+        for field in ["call", "calls"] {
+            match args.get(field) {
+                Some(JsonValue::Array(calls)) => {
+                    for call in calls {
+                        Self::collect(signer, call, outgoing)?;
+                    }
+                }
+                Some(JsonValue::Null) | None => {}
+                Some(call) => Self::collect(signer, call, outgoing)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Present an override token with the transaction
+    pub fn with_override_token(mut self, token: impl Into<String>) -> Self {
+        self.override_token = Some(token.into());
+        self
+    }
+}
+
+/// Name and value of a single-key JSON object
+fn single_entry(value: &JsonValue) -> Option<(&str, &JsonValue)> {
+    let object = value.as_object()?;
+    if object.len() != 1 {
+        return None;
+    }
+    object
+        .iter()
+        .next()
+        .map(|(name, value)| (name.as_str(), value))
+}
+
+/// Account of a `MultiAddress`, as hex for `Id` and `Address32`, or as
+/// rendered otherwise
+fn address(value: &JsonValue) -> String {
+    match single_entry(value) {
+        Some(("Id" | "Address32", JsonValue::String(hex))) => hex.clone(),
+        _ => value.to_string(),
+    }
+}
+
+/// Amount rendered as a number, or as a decimal string when large
+fn amount(value: &JsonValue) -> Option<u128> {
+    match value {
+        JsonValue::Number(number) => number.as_u64().map(u128::from),
+        JsonValue::String(decimal) => decimal.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Whether a call by this name moves funds
+fn moves_funds(call: &str) -> bool {
+    call.contains("transfer") || call.contains("teleport")
+}
+
+/// Rule of a [`Role`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    /// At most this many units of the asset per UTC day
+    DailyLimit(AssetId, u128),
+    /// Transfers only to these accounts
+    AllowedDestinations(BTreeSet<[u8; 32]>),
+    /// Only these `(pallet, call)`s
+    AllowedCalls(BTreeSet<(String, String)>),
+    /// Transfers above this amount need co-approval
+    CoApprovalAbove(u128),
+    /// Limit on the amount sent within a rolling window
+    Velocity(VelocityLimit),
+}
+
+/// What happens to a transfer exceeding a velocity limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitAction {
+    /// Reject the transfer
+    Reject,
+    /// Hold the transfer for co-approval
+    Hold,
+}
+
+/// At most `amount` units of `asset` within any `window`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VelocityLimit {
+    /// Asset limited
+    pub asset: AssetId,
+    /// Most that may be sent within the window
+    pub amount: u128,
+    /// Length of the rolling window
+    pub window: Duration,
+    /// What happens to transfers over the limit
+    pub action: LimitAction,
+}
+
+/// Named set of rules shared by signers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Role {
+    name: String,
+    rules: Vec<Rule>,
+}
+
+impl Role {
+    /// Role without rules, allowing everything
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Role name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Rules of the role
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Add a rule
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Limit transfers of `asset` to `amount` units per UTC day
+    pub fn with_daily_limit(self, asset: AssetId, amount: u128) -> Self {
+        self.with_rule(Rule::DailyLimit(asset, amount))
+    }
+
+    /// Limit transfers of `asset` to `amount` units within any `window`
+    pub fn with_velocity_limit(
+        self,
+        asset: AssetId,
+        amount: u128,
+        window: Duration,
+        action: LimitAction,
+    ) -> Self {
+        self.with_rule(Rule::Velocity(VelocityLimit {
+            asset,
+            amount,
+            window,
+            action,
+        }))
+    }
+
+    /// Require co-approval for transfers above `amount`
+    pub fn with_co_approval_above(self, amount: u128) -> Self {
+        self.with_rule(Rule::CoApprovalAbove(amount))
+    }
+
+    /// Allow transfers to `address` (SS58 or hex)
+    ///
+    /// Once a destination is allowed, transfers elsewhere are rejected.
+    /// Invalid addresses are ignored with a warning.
+    pub fn allow_destination(mut self, address: &str) -> Self {
+        let Ok(account) = account_id(address) else {
+            warn!(
+                "Ignoring invalid destination {} of role {}",
+                address, self.name
+            );
+            return self;
+        };
+        match self.rules.iter_mut().find_map(|rule| match rule {
+            Rule::AllowedDestinations(allowed) => Some(allowed),
+            _ => None,
+        }) {
+            Some(allowed) => {
+                allowed.insert(account);
+            }
+            None => self
+                .rules
+                .push(Rule::AllowedDestinations(BTreeSet::from([account]))),
+        }
+        self
+    }
+
+    /// Whether the role has rules on amounts
+    fn limits_amounts(&self) -> bool {
+        self.rules.iter().any(|rule| {
+            matches!(
+                rule,
+                Rule::DailyLimit(..) | Rule::Velocity(_) | Rule::CoApprovalAbove(_)
+            )
+        })
+    }
+
+    /// Whether the role restricts destinations
+    fn limits_destinations(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| matches!(rule, Rule::AllowedDestinations(_)))
+    }
+
+    /// Allow `pallet::call`
+    ///
+    /// Once a call is allowed, other calls are rejected.
+    pub fn allow_call(mut self, pallet: &str, call: &str) -> Self {
+        let entry = (pallet.to_string(), call.to_string());
+        match self.rules.iter_mut().find_map(|rule| match rule {
+            Rule::AllowedCalls(allowed) => Some(allowed),
+            _ => None,
+        }) {
+            Some(allowed) => {
+                allowed.insert(entry);
+            }
+            None => self.rules.push(Rule::AllowedCalls(BTreeSet::from([entry]))),
+        }
+        self
+    }
+}
+
+/// Outcome of checking a transaction against a policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// The transaction may be signed
+    Allow,
+    /// The transaction must not be signed
+    Deny(String),
+    /// The transaction needs co-approval
+    Escalate(String),
+}
+
+/// A transaction waiting for co-approval
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalRequest {
+    /// The transaction
+    pub transaction: OutgoingTransaction,
+    /// Role of the signer
+    pub role: String,
+    /// Why approval is needed
+    pub reason: String,
+}
+
+/// Decides on escalated transactions, e.g. by asking a second operator
+#[async_trait]
+pub trait ApprovalHandler: Send + Sync {
+    /// Whether the transaction is approved
+    async fn approve(&self, request: &ApprovalRequest) -> bool;
+}
+
+#[async_trait]
+impl<F> ApprovalHandler for F
+where
+    F: Fn(&ApprovalRequest) -> bool + Send + Sync,
+{
+    async fn approve(&self, request: &ApprovalRequest) -> bool {
+        self(request)
+    }
+}
+
+/// A transfer counted towards the sender's limits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outflow {
+    /// Sending account
+    pub account: [u8; 32],
+    /// Asset sent
+    pub asset: AssetId,
+    /// Amount sent
+    pub amount: u128,
+    /// Unix time in milliseconds
+    pub timestamp: u64,
+}
+
+/// Storage of outflows, e.g. a database shared by several signing services
+#[async_trait]
+pub trait SpendStore: Send + Sync {
+    /// Add an outflow
+    async fn record(&self, outflow: &Outflow) -> Result<()>;
+
+    /// Remove an outflow added earlier
+    async fn remove(&self, outflow: &Outflow) -> Result<()>;
+
+    /// Total of `asset` sent by `account` at or after `since` (Unix ms)
+    async fn total_since(&self, account: &[u8; 32], asset: &AssetId, since: u64) -> Result<u128>;
+}
+
+/// Keeps outflows in memory
+#[derive(Debug, Clone, Default)]
+pub struct MemorySpendStore {
+    outflows: Arc<Mutex<Vec<Outflow>>>,
+}
+
+impl MemorySpendStore {
+    /// Drop outflows before `before` (Unix ms)
+    pub fn prune(&self, before: u64) {
+        self.outflows
+            .lock()
+            .retain(|outflow| outflow.timestamp >= before);
+    }
+}
+
+#[async_trait]
+impl SpendStore for MemorySpendStore {
+    async fn record(&self, outflow: &Outflow) -> Result<()> {
+        self.outflows.lock().push(outflow.clone());
+        Ok(())
+    }
+
+    async fn remove(&self, outflow: &Outflow) -> Result<()> {
+        let mut outflows = self.outflows.lock();
+        if let Some(index) = outflows.iter().position(|o| o == outflow) {
+            outflows.remove(index);
+        }
+        Ok(())
+    }
+
+    async fn total_since(&self, account: &[u8; 32], asset: &AssetId, since: u64) -> Result<u128> {
+        Ok(self
+            .outflows
+            .lock()
+            .iter()
+            .filter(|o| o.account == *account && o.asset == *asset && o.timestamp >= since)
+            .fold(0u128, |total, o| total.saturating_add(o.amount)))
+    }
+}
+
+/// Lets one transfer of a signer exceed its spending limits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverrideToken {
+    /// Token id, presented with the transaction
+    pub id: String,
+    /// Signer the token is for
+    pub signer: [u8; 32],
+    /// Largest transfer the token covers
+    pub max_amount: u128,
+    /// Unix time in milliseconds after which the token is void
+    pub expires_at: u64,
+}
+
+/// Roles, signer assignments and spending limits
+///
+/// Clones share the spend store and override tokens, and authorize one
+/// transaction at a time.
+#[derive(Clone)]
+pub struct Policy {
+    roles: HashMap<String, Role>,
+    assignments: HashMap<[u8; 32], String>,
+    approver: Option<Arc<dyn ApprovalHandler>>,
+    store: Arc<dyn SpendStore>,
+    overrides: Arc<Mutex<HashMap<String, OverrideToken>>>,
+    /// Held while a transaction is checked against the store and reserved
+    authorizing: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            roles: HashMap::new(),
+            assignments: HashMap::new(),
+            approver: None,
+            store: Arc::new(MemorySpendStore::default()),
+            overrides: Arc::default(),
+            authorizing: Arc::default(),
+        }
+    }
+}
+
+impl Policy {
+    /// Policy without roles, rejecting every signer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a role
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.roles.insert(role.name.clone(), role);
+        self
+    }
+
+    /// Give the signer at `address` (SS58 or hex) a role
+    pub fn assign(mut self, address: &str, role: &str) -> Result<Self> {
+        if !self.roles.contains_key(role) {
+            return Err(Error::Other(format!("Unknown role {}", role)));
+        }
+        self.assignments
+            .insert(account_id(address)?, role.to_string());
+        Ok(self)
+    }
+
+    /// Ask `handler` about transactions needing co-approval
+    ///
+    /// Without a handler they are rejected.
+    pub fn on_escalation(mut self, handler: impl ApprovalHandler + 'static) -> Self {
+        self.approver = Some(Arc::new(handler));
+        self
+    }
+
+    /// Record outflows in `store` instead of memory
+    pub fn with_spend_store(mut self, store: impl SpendStore + 'static) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// Role of the signer at `address`
+    pub fn role_of(&self, address: &str) -> Option<&Role> {
+        let account = account_id(address).ok()?;
+        self.roles.get(self.assignments.get(&account)?)
+    }
+
+    /// Token letting one transfer of up to `max_amount` by `signer` exceed
+    /// its limits within `valid_for`
+    pub fn issue_override(
+        &self,
+        signer: &str,
+        max_amount: u128,
+        valid_for: Duration,
+    ) -> Result<OverrideToken> {
+        let token = OverrideToken {
+            id: hex::encode(rand::random::<[u8; 16]>()),
+            signer: account_id(signer)?,
+            max_amount,
+            expires_at: now().saturating_add(valid_for.as_millis() as u64),
+        };
+        info!("Issued override token {} for {}", token.id, signer);
+        self.overrides
+            .lock()
+            .insert(token.id.clone(), token.clone());
+        Ok(token)
+    }
+
+    /// Void an override token before it is used
+    pub fn revoke_override(&self, id: &str) -> bool {
+        self.overrides.lock().remove(id).is_some()
+    }
+
+    /// Check a transaction at `now` (Unix ms)
+    ///
+    /// An override token presented with the transaction is taken into
+    /// account but not used up.
+    pub async fn evaluate(&self, transaction: &OutgoingTransaction, now: u64) -> Result<Decision> {
+        let overridden = self.override_for(transaction, now).is_some();
+        self.decide(transaction, overridden, now).await
+    }
+
+    /// Check a transaction, with its limits lifted if `overridden`
+    async fn decide(
+        &self,
+        transaction: &OutgoingTransaction,
+        overridden: bool,
+        now: u64,
+    ) -> Result<Decision> {
+        let Some(role) = self.role_of(&transaction.signer) else {
+            return Ok(Decision::Deny(format!(
+                "{} has no role",
+                transaction.signer
+            )));
+        };
+        let destination = transaction
+            .destination
+            .as_deref()
+            .map(|address| account_id(address).ok());
+        if transaction.unknown_amount && role.limits_amounts() {
+            return Ok(Decision::Deny(format!(
+                "{}::{} moves an amount role {} cannot count",
+                transaction.pallet, transaction.call, role.name
+            )));
+        }
+        if transaction.unknown_destination && role.limits_destinations() {
+            return Ok(Decision::Deny(format!(
+                "{}::{} moves funds to a recipient role {} cannot check",
+                transaction.pallet, transaction.call, role.name
+            )));
+        }
+
+        let mut escalate = None;
+        for rule in &role.rules {
+            match rule {
+                Rule::AllowedCalls(allowed) => {
+                    if !allowed.contains(&(transaction.pallet.clone(), transaction.call.clone())) {
+                        return Ok(Decision::Deny(format!(
+                            "{}::{} is not allowed for role {}",
+                            transaction.pallet, transaction.call, role.name
+                        )));
+                    }
+                }
+                Rule::AllowedDestinations(allowed) => match destination {
+                    Some(Some(account)) if allowed.contains(&account) => {}
+                    Some(_) => {
+                        return Ok(Decision::Deny(format!(
+                            "Destination {} is not allowed for role {}",
+                            transaction.destination.as_deref().unwrap_or_default(),
+                            role.name
+                        )))
+                    }
+                    None => {}
+                },
+                Rule::DailyLimit(asset, limit) => {
+                    let since = now - now % DAY_MS;
+                    if let Some(reason) = self
+                        .exceeds(transaction, asset, *limit, since, "Daily")
+                        .await?
+                        .filter(|_| !overridden)
+                    {
+                        return Ok(Decision::Deny(reason));
+                    }
+                }
+                Rule::Velocity(velocity) => {
+                    let since = now.saturating_sub(velocity.window.as_millis() as u64);
+                    let label = format!("{:?}", velocity.window);
+                    if let Some(reason) = self
+                        .exceeds(transaction, &velocity.asset, velocity.amount, since, &label)
+                        .await?
+                        .filter(|_| !overridden)
+                    {
+                        match velocity.action {
+                            LimitAction::Reject => return Ok(Decision::Deny(reason)),
+                            LimitAction::Hold => escalate = Some(reason),
+                        }
+                    }
+                }
+                Rule::CoApprovalAbove(threshold) => {
+                    if let Some((_, amount)) = &transaction.amount {
+                        if amount > threshold {
+                            escalate = Some(format!(
+                                "{} is above the co-approval threshold of {}",
+                                amount, threshold
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(escalate.map_or(Decision::Allow, Decision::Escalate))
+    }
+
+    /// Approve a transaction before signing, escalating if needed
+    ///
+    /// Checking the limits and reserving the transfer happen under one lock,
+    /// so concurrent transactions cannot both pass a limit only one of them
+    /// fits in. Returns the outflow reserved for an approved transfer, to be
+    /// [`release`](Self::release)d if the transaction fails.
+    pub async fn authorize(&self, transaction: &OutgoingTransaction) -> Result<Option<Outflow>> {
+        let now = now();
+        let authorizing = self.authorizing.lock().await;
+        let overridden = match self.take_override(transaction) {
+            Some(token) => {
+                info!("Override token {} used by {}", token.id, transaction.signer);
+                token_covers(&token, transaction, now)
+            }
+            None => false,
+        };
+        let decision = self.decide(transaction, overridden, now).await?;
+        if let Decision::Deny(reason) = decision {
+            return Err(Error::PolicyViolation(reason));
+        }
+        let outflow = match (&transaction.amount, account_id(&transaction.signer)) {
+            (Some((asset, amount)), Ok(account)) => {
+                let outflow = Outflow {
+                    account,
+                    asset: asset.clone(),
+                    amount: *amount,
+                    timestamp: now,
+                };
+                self.store.record(&outflow).await?;
+                Some(outflow)
+            }
+            _ => None,
+        };
+        drop(authorizing);
+
+        // the reservation holds the limit while the approver decides
+        if let Decision::Escalate(reason) = decision {
+            let request = ApprovalRequest {
+                transaction: transaction.clone(),
+                role: self
+                    .role_of(&transaction.signer)
+                    .map(|role| role.name.clone())
+                    .unwrap_or_default(),
+                reason,
+            };
+            let approved = match &self.approver {
+                Some(approver) => approver.approve(&request).await,
+                None => false,
+            };
+            if !approved {
+                if let Some(outflow) = &outflow {
+                    self.release(outflow).await?;
+                }
+                return Err(Error::PolicyViolation(format!(
+                    "Not approved: {}",
+                    request.reason
+                )));
+            }
+            info!("Co-approved: {}", request.reason);
+        }
+        Ok(outflow)
+    }
+
+    /// Remove the outflow of an authorized transaction that failed
+    pub async fn release(&self, outflow: &Outflow) -> Result<()> {
+        self.store.remove(outflow).await
+    }
+
+    /// Amount of `asset` the signer at `address` sent at or after `since`
+    /// (Unix ms)
+    pub async fn spent_since(&self, address: &str, asset: &AssetId, since: u64) -> Result<u128> {
+        self.store
+            .total_since(&account_id(address)?, asset, since)
+            .await
+    }
+
+    /// Why the transfer would take the signer over `limit`, if it would
+    async fn exceeds(
+        &self,
+        transaction: &OutgoingTransaction,
+        asset: &AssetId,
+        limit: u128,
+        since: u64,
+        label: &str,
+    ) -> Result<Option<String>> {
+        let Some((sent_asset, amount)) = &transaction.amount else {
+            return Ok(None);
+        };
+        if sent_asset != asset {
+            return Ok(None);
+        }
+        let spent = self.spent_since(&transaction.signer, asset, since).await?;
+        Ok((spent.saturating_add(*amount) > limit).then(|| {
+            format!(
+                "{} limit of {} exceeded: {} spent, {} requested",
+                label, limit, spent, amount
+            )
+        }))
+    }
+
+    /// Valid override token presented with the transaction
+    fn override_for(&self, transaction: &OutgoingTransaction, now: u64) -> Option<OverrideToken> {
+        let id = transaction.override_token.as_deref()?;
+        self.overrides
+            .lock()
+            .get(id)
+            .filter(|token| token_covers(token, transaction, now))
+            .cloned()
+    }
+
+    /// Remove the signer's override token presented with the transaction,
+    /// valid or not
+    fn take_override(&self, transaction: &OutgoingTransaction) -> Option<OverrideToken> {
+        let id = transaction.override_token.as_deref()?;
+        let signer = account_id(&transaction.signer).ok()?;
+        let mut overrides = self.overrides.lock();
+        if overrides.get(id)?.signer != signer {
+            return None;
+        }
+        overrides.remove(id)
+    }
+}
+
+/// Whether `token` lifts the limits for the transaction at `now`
+fn token_covers(token: &OverrideToken, transaction: &OutgoingTransaction, now: u64) -> bool {
+    let Some((_, amount)) = &transaction.amount else {
+        return false;
+    };
+    account_id(&transaction.signer).is_ok_and(|signer| signer == token.signer)
+        && *amount <= token.max_amount
+        && now <= token.expires_at
+}
+
+impl std::fmt::Debug for Policy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Policy")
+            .field("roles", &self.roles.keys().collect::<Vec<_>>())
+            .field("assignments", &self.assignments.len())
+            .field("approver", &self.approver.is_some())
+            .field("overrides", &self.overrides.lock().len())
+            .finish()
+    }
+}
+
+const DAY_MS: u64 = 86_400_000;
+
+/// Unix time in milliseconds
+fn now() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ALICE: &str = "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+    const BOB: &str = "0x8eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48";
+    const CHARLIE: &str = "0x90b5ab205c6974c9ea841be688864633dc9ca8a357843eeacf2314649965fe22";
+
+    fn policy() -> Policy {
+        Policy::new()
+            .with_role(
+                Role::new("treasury")
+                    .with_daily_limit(AssetId::Native, 1_000)
+                    .with_co_approval_above(100)
+                    .allow_destination(BOB)
+                    .allow_call("Balances", "transfer_keep_alive"),
+            )
+            .assign(ALICE, "treasury")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rules() {
+        let policy = policy();
+        let transfer =
+            |to: &str, amount| OutgoingTransaction::transfer(ALICE, to, AssetId::Native, amount);
+        let decide = |transaction: OutgoingTransaction| {
+            let policy = policy.clone();
+            async move { policy.evaluate(&transaction, 0).await.unwrap() }
+        };
+
+        assert_eq!(decide(transfer(BOB, 50)).await, Decision::Allow);
+        assert!(matches!(
+            decide(transfer(BOB, 500)).await,
+            Decision::Escalate(_)
+        ));
+        assert!(matches!(
+            decide(transfer(CHARLIE, 50)).await,
+            Decision::Deny(_)
+        ));
+        assert!(matches!(
+            decide(transfer(BOB, 5_000)).await,
+            Decision::Deny(_)
+        ));
+        assert!(matches!(
+            decide(OutgoingTransaction::call(ALICE, "System", "remark")).await,
+            Decision::Deny(_)
+        ));
+        assert!(matches!(
+            decide(OutgoingTransaction::transfer(
+                BOB,
+                ALICE,
+                AssetId::Native,
+                1
+            ))
+            .await,
+            Decision::Deny(_)
+        ));
+        assert!(Policy::new().assign(ALICE, "auditor").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_daily_limit_and_escalation() {
+        let approved = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let flag = approved.clone();
+        let policy = policy().on_escalation(move |_: &ApprovalRequest| {
+            flag.load(std::sync::atomic::Ordering::SeqCst)
+        });
+        let small = OutgoingTransaction::transfer(ALICE, BOB, AssetId::Native, 100);
+        let large = OutgoingTransaction::transfer(ALICE, BOB, AssetId::Native, 800);
+
+        assert!(matches!(
+            policy.authorize(&large).await,
+            Err(Error::PolicyViolation(_))
+        ));
+        approved.store(true, std::sync::atomic::Ordering::SeqCst);
+        let large_outflow = policy.authorize(&large).await.unwrap().unwrap();
+        policy.authorize(&small).await.unwrap();
+        let today = now() - now() % DAY_MS;
+        assert_eq!(
+            policy
+                .spent_since(ALICE, &AssetId::Native, today)
+                .await
+                .unwrap(),
+            900
+        );
+
+        // the limit is reached until a failed transaction is released
+        assert!(policy.authorize(&small).await.is_ok());
+        assert!(policy.authorize(&small).await.is_err());
+        policy.release(&large_outflow).await.unwrap();
+        assert!(policy.authorize(&small).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_transfers_reserve_the_limit() {
+        let policy = policy().on_escalation(|_: &ApprovalRequest| true);
+        let transfer = OutgoingTransaction::transfer(ALICE, BOB, AssetId::Native, 600);
+        let (first, second) =
+            tokio::join!(policy.authorize(&transfer), policy.authorize(&transfer));
+        assert!(first.is_ok() != second.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_amounts_read_from_calls() {
+        let call = json!({ "Utility": { "batch_all": { "calls": [
+            { "Balances": { "transfer_keep_alive": {
+                "dest": { "Id": BOB }, "value": "300000000000000000000"
+            } } },
+            { "Assets": { "transfer": { "id": 1984, "target": { "Id": CHARLIE }, "amount": 5 } } },
+            { "Balances": { "transfer_all": { "dest": { "Id": BOB }, "keep_alive": true } } },
+            { "System": { "remark": { "remark": "0x00" } } }
+        ] } } });
+        let mut outgoing = Vec::new();
+        OutgoingTransaction::collect(ALICE, &call, &mut outgoing).unwrap();
+        assert_eq!(outgoing.len(), 5);
+        assert_eq!(outgoing[1].destination.as_deref(), Some(BOB));
+        assert_eq!(
+            outgoing[1].amount,
+            Some((AssetId::Native, 300_000_000_000_000_000_000))
+        );
+        assert_eq!(outgoing[2].destination.as_deref(), Some(CHARLIE));
+        assert_eq!(outgoing[2].amount, Some((AssetId::Local(1984), 5)));
+        assert!(outgoing[3].unknown_amount);
+        assert_eq!(outgoing[3].destination.as_deref(), Some(BOB));
+        assert!(!outgoing[4].unknown_amount);
+
+        let policy = Policy::new()
+            .with_role(Role::new("desk").with_daily_limit(AssetId::Native, 1_000))
+            .assign(ALICE, "desk")
+            .unwrap();
+        assert!(matches!(
+            policy.evaluate(&outgoing[3], 0).await.unwrap(),
+            Decision::Deny(_)
+        ));
+        assert_eq!(
+            policy.evaluate(&outgoing[4], 0).await.unwrap(),
+            Decision::Allow
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transfer_all_to_unchecked_destination_is_denied() {
+        let policy = Policy::new()
+            .with_role(Role::new("payouts").allow_destination(BOB))
+            .assign(ALICE, "payouts")
+            .unwrap();
+        let transfer_all = |dest: &str| {
+            json!({ "Balances": { "transfer_all": {
+            "dest": { "Id": dest }, "keep_alive": false
+        } } })
+        };
+        let decide = |call: JsonValue| {
+            let policy = policy.clone();
+            async move {
+                let mut outgoing = Vec::new();
+                OutgoingTransaction::collect(ALICE, &call, &mut outgoing).unwrap();
+                let mut decisions = Vec::new();
+                for transaction in &outgoing {
+                    decisions.push(policy.evaluate(transaction, 0).await.unwrap());
+                }
+                decisions
+            }
+        };
+
+        assert_eq!(decide(transfer_all(BOB)).await, vec![Decision::Allow]);
+        assert!(matches!(
+            decide(transfer_all(CHARLIE)).await[..],
+            [Decision::Deny(_)]
+        ));
+        let batched = json!({ "Utility": { "batch_all": { "calls": [transfer_all(CHARLIE)] } } });
+        assert!(matches!(
+            decide(batched).await[..],
+            [Decision::Allow, Decision::Deny(_)]
+        ));
+
+        // recipients of XCM transfers cannot be read
+        let xcm = json!({ "PolkadotXcm": { "limited_reserve_transfer_assets": {
+            "dest": { "V4": {} }, "beneficiary": { "V4": {} }
+        } } });
+        assert!(matches!(decide(xcm).await[..], [Decision::Deny(_)]));
+    }
+
+    #[tokio::test]
+    async fn test_velocity_limit_and_override() {
+        let store = MemorySpendStore::default();
+        let policy = Policy::new()
+            .with_role(Role::new("desk").with_velocity_limit(
+                AssetId::Native,
+                1_000,
+                Duration::from_secs(3_600),
+                LimitAction::Reject,
+            ))
+            .assign(ALICE, "desk")
+            .unwrap()
+            .with_spend_store(store.clone());
+        let transfer = |amount| OutgoingTransaction::transfer(ALICE, BOB, AssetId::Native, amount);
+
+        // outflows older than the window no longer count
+        store
+            .record(&Outflow {
+                account: account_id(ALICE).unwrap(),
+                asset: AssetId::Native,
+                amount: 900,
+                timestamp: now() - 2 * 3_600_000,
+            })
+            .await
+            .unwrap();
+        policy.authorize(&transfer(900)).await.unwrap();
+        assert!(policy.authorize(&transfer(200)).await.is_err());
+
+        let token = policy
+            .issue_override(ALICE, 500, Duration::from_secs(60))
+            .unwrap();
+        let lifted = transfer(200).with_override_token(&token.id);
+        assert_eq!(
+            policy.evaluate(&lifted, now()).await.unwrap(),
+            Decision::Allow
+        );
+        policy.authorize(&lifted).await.unwrap();
+        // tokens are single use
+        assert!(policy.authorize(&lifted).await.is_err());
+
+        // and used up by a transfer they do not cover
+        let token = policy
+            .issue_override(ALICE, 500, Duration::from_secs(60))
+            .unwrap();
+        assert!(policy
+            .authorize(&transfer(600).with_override_token(&token.id))
+            .await
+            .is_err());
+        assert!(policy
+            .authorize(&transfer(200).with_override_token(&token.id))
+            .await
+            .is_err());
+        assert!(!policy.revoke_override(&token.id));
+    }
+}
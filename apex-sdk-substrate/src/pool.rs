@@ -7,6 +7,7 @@
 //! - Connection reuse
 
 use crate::{Error, SubstrateAdapter};
+use apex_sdk_core::EndpointConfig;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -198,20 +199,33 @@ impl ConnectionPool {
 
     /// Create a new connection pool with custom configuration
     pub async fn with_config(endpoints: Vec<String>, config: PoolConfig) -> Result<Self, Error> {
-        if endpoints.is_empty() {
+        let endpoints = endpoints.into_iter().map(EndpointConfig::from).collect();
+        Self::with_endpoint_configs(endpoints, config).await
+    }
+
+    /// Create a new connection pool where every endpoint carries its own
+    /// credentials and headers
+    pub async fn with_endpoint_configs(
+        endpoint_configs: Vec<EndpointConfig>,
+        config: PoolConfig,
+    ) -> Result<Self, Error> {
+        if endpoint_configs.is_empty() {
             return Err(Error::Connection("No endpoints provided".to_string()));
         }
 
         tracing::info!(
             "Creating connection pool with {} endpoints",
-            endpoints.len()
+            endpoint_configs.len()
         );
 
         let mut connections = Vec::new();
 
         // Create initial connections
-        for endpoint in &endpoints {
-            match SubstrateAdapter::connect(endpoint).await {
+        for endpoint_config in &endpoint_configs {
+            let endpoint = &endpoint_config.url;
+            match SubstrateAdapter::connect_with_client_config(endpoint, &endpoint_config.client)
+                .await
+            {
                 Ok(adapter) => {
                     let conn = PooledConnection {
                         adapter: Arc::new(adapter),
@@ -224,7 +238,11 @@ impl ConnectionPool {
                 Err(e) => {
                     tracing::warn!("Failed to connect to endpoint {}: {}", endpoint, e);
                     // Create unhealthy connection
-                    let adapter = SubstrateAdapter::connect(endpoint).await?;
+                    let adapter = SubstrateAdapter::connect_with_client_config(
+                        endpoint,
+                        &endpoint_config.client,
+                    )
+                    .await?;
                     let health = EndpointHealth {
                         is_healthy: false,
                         failure_count: 1,
@@ -241,6 +259,11 @@ impl ConnectionPool {
             }
         }
 
+        let endpoints = endpoint_configs
+            .into_iter()
+            .map(|endpoint| endpoint.url)
+            .collect();
+
         Ok(Self {
             endpoints,
             connections: Arc::new(RwLock::new(connections)),
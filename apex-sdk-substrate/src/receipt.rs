@@ -0,0 +1,442 @@
+//! Extrinsic receipts with human-readable summaries
+//!
+//! An [`ExtrinsicReceipt`] pairs a decoded extrinsic with the events it
+//! emitted. [`ExtrinsicReceipt::summarize`] turns it into one line for
+//! notifications and audit logs, built from the events rather than the call
+//! arguments so that what is described is what actually happened:
+//!
+//! ```text
+//! Transferred 10.5 DOT from 15oF4u…r6Sp5 to 14E5nq…HtsLd, fee 0.016 DOT
+//! ```
+//!
+//! Events without a dedicated phrase are skipped; an extrinsic with no
+//! recognised events falls back to its call name. Every phrase is a
+//! [`Message`] with a stable `summary.*` code, so
+//! [`summarize_with`](ExtrinsicReceipt::summarize_with) can render it through a
+//! [`Localizer`].
+//!
+//! ```rust,no_run
+//! use apex_sdk_core::Denomination;
+//! use apex_sdk_substrate::{SubstrateAdapter, SummaryFormat};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let format = SummaryFormat::new(Denomination::new("DOT", 10)).with_ss58_prefix(0);
+//! for receipt in adapter.block_query().get_receipts(20_000_000).await? {
+//!     println!("{}", receipt.summarize(&format));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::MatchedEvent;
+use apex_sdk_core::{Balance, Denomination, ExtrinsicInfo, Localizer, Message};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sp_core::crypto::{AccountId32, Ss58AddressFormat, Ss58Codec};
+
+/// A decoded extrinsic and the events it emitted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtrinsicReceipt {
+    /// Block containing the extrinsic
+    pub block_number: u64,
+    /// Hash of that block (hex)
+    pub block_hash: String,
+    /// Decoded extrinsic
+    pub extrinsic: ExtrinsicInfo,
+    /// Events emitted by the extrinsic, in order
+    pub events: Vec<MatchedEvent>,
+}
+
+/// How amounts and accounts are rendered in summaries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaryFormat {
+    denomination: Denomination,
+    ss58_prefix: Option<u16>,
+    precision: u8,
+}
+
+impl SummaryFormat {
+    /// Render amounts in `denomination`
+    pub fn new(denomination: Denomination) -> Self {
+        Self {
+            denomination,
+            ss58_prefix: None,
+            precision: 4,
+        }
+    }
+
+    /// Render accounts as SS58 with this network prefix instead of hex
+    pub fn with_ss58_prefix(mut self, prefix: u16) -> Self {
+        self.ss58_prefix = Some(prefix);
+        self
+    }
+
+    /// Maximum fractional digits shown for amounts
+    pub fn with_precision(mut self, precision: u8) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    fn amount(&self, planck: Option<u128>) -> String {
+        match planck {
+            Some(planck) => format!(
+                "{:.*}",
+                self.precision as usize,
+                Balance::from_planck(planck).to_display(&self.denomination)
+            ),
+            None => "an unknown amount".to_string(),
+        }
+    }
+
+    fn account(&self, value: &JsonValue) -> String {
+        let Some(hex_account) = value.as_str() else {
+            return "unknown".to_string();
+        };
+        let bytes = hex::decode(hex_account.trim_start_matches("0x")).unwrap_or_default();
+        let rendered = match (self.ss58_prefix, <[u8; 32]>::try_from(bytes.as_slice())) {
+            (Some(prefix), Ok(account)) => AccountId32::from(account)
+                .to_ss58check_with_version(Ss58AddressFormat::custom(prefix)),
+            _ => hex_account.to_string(),
+        };
+        shorten(&rendered)
+    }
+}
+
+impl ExtrinsicReceipt {
+    /// Receipt for `extrinsic`, keeping only the events it emitted
+    pub fn new(
+        block_number: u64,
+        block_hash: impl Into<String>,
+        extrinsic: ExtrinsicInfo,
+        events: &[MatchedEvent],
+    ) -> Self {
+        let events = events
+            .iter()
+            .filter(|event| event.extrinsic_index == Some(extrinsic.index))
+            .cloned()
+            .collect();
+        Self {
+            block_number,
+            block_hash: block_hash.into(),
+            extrinsic,
+            events,
+        }
+    }
+
+    /// Fee actually charged, from `TransactionPayment::TransactionFeePaid`
+    pub fn fee(&self) -> Option<u128> {
+        self.find("TransactionPayment", "TransactionFeePaid")
+            .and_then(|event| json_u128(&event.fields["actual_fee"]))
+    }
+
+    /// Dispatch error, from `System::ExtrinsicFailed`
+    pub fn error(&self) -> Option<String> {
+        self.find("System", "ExtrinsicFailed")
+            .map(|event| dispatch_error(&event.fields["dispatch_error"]))
+    }
+
+    /// One-line English description of what the extrinsic did
+    pub fn summarize(&self, format: &SummaryFormat) -> String {
+        self.summarize_with(format, &Localizer::new())
+    }
+
+    /// One-line description rendered through `localizer`
+    pub fn summarize_with(&self, format: &SummaryFormat, localizer: &Localizer) -> String {
+        let call = format!("{}.{}", self.extrinsic.pallet, self.extrinsic.call);
+        let summary = match self.error() {
+            Some(error) => localizer.render(&Message::from_template(
+                "summary.failed",
+                "Failed {call}: {error}",
+                &[("call", call), ("error", error)],
+            )),
+            None => {
+                let phrases: Vec<String> = self
+                    .events
+                    .iter()
+                    .filter_map(|event| describe(event, format))
+                    .map(|message| localizer.render(&message))
+                    .collect();
+                if phrases.is_empty() {
+                    localizer.render(&Message::from_template(
+                        "summary.called",
+                        "Called {call}",
+                        &[("call", call)],
+                    ))
+                } else {
+                    phrases.join("; ")
+                }
+            }
+        };
+
+        match self.fee() {
+            Some(fee) => localizer.render(&Message::from_template(
+                "summary.with_fee",
+                "{summary}, fee {fee}",
+                &[("summary", summary), ("fee", format.amount(Some(fee)))],
+            )),
+            None => summary,
+        }
+    }
+
+    fn find(&self, pallet: &str, variant: &str) -> Option<&MatchedEvent> {
+        self.events
+            .iter()
+            .find(|event| event.pallet == pallet && event.variant == variant)
+    }
+}
+
+/// Phrase for a single event, if it has one
+///
+/// Message codes are `summary.<pallet>.<event>` in snake case.
+fn describe(event: &MatchedEvent, format: &SummaryFormat) -> Option<Message> {
+    let fields = &event.fields;
+    let amount = |name: &str| format.amount(json_u128(&fields[name]));
+    let account = |name: &str| format.account(&fields[name]);
+
+    let (code, template, args) = match (event.pallet.as_str(), event.variant.as_str()) {
+        ("Balances", "Transfer") => (
+            "summary.balances.transfer",
+            "Transferred {amount} from {from} to {to}",
+            vec![
+                ("amount", amount("amount")),
+                ("from", account("from")),
+                ("to", account("to")),
+            ],
+        ),
+        ("Assets", "Transferred") | ("ForeignAssets", "Transferred") => (
+            "summary.assets.transferred",
+            "Transferred {amount} units of asset {asset} from {from} to {to}",
+            vec![
+                (
+                    "amount",
+                    json_u128(&fields["amount"]).unwrap_or_default().to_string(),
+                ),
+                ("asset", render(&fields["asset_id"])),
+                ("from", account("from")),
+                ("to", account("to")),
+            ],
+        ),
+        ("Staking", "Bonded") => (
+            "summary.staking.bonded",
+            "Bonded {amount} from {stash}",
+            vec![("amount", amount("amount")), ("stash", account("stash"))],
+        ),
+        ("Staking", "Unbonded") => (
+            "summary.staking.unbonded",
+            "Unbonded {amount} from {stash}",
+            vec![("amount", amount("amount")), ("stash", account("stash"))],
+        ),
+        ("Staking", "Withdrawn") => (
+            "summary.staking.withdrawn",
+            "Withdrew {amount} to {stash}",
+            vec![("amount", amount("amount")), ("stash", account("stash"))],
+        ),
+        ("Staking", "Rewarded") => (
+            "summary.staking.rewarded",
+            "Rewarded {amount} to {stash}",
+            vec![("amount", amount("amount")), ("stash", account("stash"))],
+        ),
+        ("Proxy", "PureCreated") => (
+            "summary.proxy.pure_created",
+            "Created pure proxy {pure}",
+            vec![("pure", account("pure"))],
+        ),
+        ("Proxy", "ProxyAdded") => (
+            "summary.proxy.proxy_added",
+            "Added {delegate} as proxy of {delegator}",
+            vec![
+                ("delegate", account("delegate")),
+                ("delegator", account("delegator")),
+            ],
+        ),
+        ("Multisig", "NewMultisig") => (
+            "summary.multisig.new_multisig",
+            "Opened multisig operation on {multisig}",
+            vec![("multisig", account("multisig"))],
+        ),
+        ("Multisig", "MultisigApproval") => (
+            "summary.multisig.multisig_approval",
+            "Approved multisig operation on {multisig}",
+            vec![("multisig", account("multisig"))],
+        ),
+        ("Multisig", "MultisigExecuted") => (
+            "summary.multisig.multisig_executed",
+            "Executed multisig operation on {multisig}",
+            vec![("multisig", account("multisig"))],
+        ),
+        ("Utility", "BatchInterrupted") => (
+            "summary.utility.batch_interrupted",
+            "Batch interrupted at call {index}: {error}",
+            vec![
+                ("index", render(&fields["index"])),
+                ("error", dispatch_error(&fields["error"])),
+            ],
+        ),
+        ("XcmPallet", "Sent") | ("PolkadotXcm", "Sent") => {
+            ("summary.xcm.sent", "Sent XCM message", vec![])
+        }
+        _ => return None,
+    };
+    Some(Message::from_template(code, template, &args))
+}
+
+/// Amount field as rendered by event decoding: a number, or a string beyond u64
+pub(crate) fn json_u128(value: &JsonValue) -> Option<u128> {
+    match value {
+        JsonValue::Number(number) => number.as_u64().map(u128::from),
+        JsonValue::String(string) => string.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Inner value of a decoded `Option`; plain values pass through
+pub(crate) fn json_some(value: &JsonValue) -> Option<&JsonValue> {
+    match value {
+        JsonValue::String(none) if none == "None" => None,
+        JsonValue::Object(variant) if variant.contains_key("Some") => variant.get("Some"),
+        value => Some(value),
+    }
+}
+
+/// Compact rendering of a decoded `DispatchError`
+///
+/// `"BadOrigin"` stays as is, `{"Token": "FundsUnavailable"}` becomes
+/// `Token(FundsUnavailable)`.
+pub(crate) fn dispatch_error(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Object(variant) => match variant.iter().next() {
+            Some((name, JsonValue::String(inner))) => format!("{}({})", name, inner),
+            Some((name, _)) => name.clone(),
+            None => "unknown error".to_string(),
+        },
+        other => render(other),
+    }
+}
+
+fn render(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(string) => string.clone(),
+        JsonValue::Null => "unknown".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Keep the first and last characters of long addresses
+fn shorten(address: &str) -> String {
+    let chars: Vec<char> = address.chars().collect();
+    if chars.len() <= 14 {
+        return address.to_string();
+    }
+    let head: String = chars[..6].iter().collect();
+    let tail: String = chars[chars.len() - 5..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apex_sdk_core::Catalog;
+    use serde_json::json;
+
+    fn event(index: u32, pallet: &str, variant: &str, fields: JsonValue) -> MatchedEvent {
+        MatchedEvent {
+            block_number: 10,
+            block_hash: "0x0a".to_string(),
+            event_index: index,
+            extrinsic_index: Some(1),
+            pallet: pallet.to_string(),
+            variant: variant.to_string(),
+            fields,
+        }
+    }
+
+    fn receipt(call: &str, events: Vec<MatchedEvent>) -> ExtrinsicReceipt {
+        let extrinsic = ExtrinsicInfo {
+            index: 1,
+            hash: "0x01".to_string(),
+            signed: true,
+            signer: Some(format!("0x{}", "11".repeat(32))),
+            pallet: "Balances".to_string(),
+            call: call.to_string(),
+            success: true,
+        };
+        ExtrinsicReceipt::new(10, "0x0a", extrinsic, &events)
+    }
+
+    fn fee_paid() -> MatchedEvent {
+        event(
+            3,
+            "TransactionPayment",
+            "TransactionFeePaid",
+            json!({ "who": format!("0x{}", "11".repeat(32)), "actual_fee": 160_000_000u64, "tip": 0 }),
+        )
+    }
+
+    #[test]
+    fn test_summarize_transfer() {
+        let receipt = receipt(
+            "transfer_keep_alive",
+            vec![
+                event(
+                    2,
+                    "Balances",
+                    "Transfer",
+                    json!({
+                        "from": format!("0x{}", "11".repeat(32)),
+                        "to": format!("0x{}", "22".repeat(32)),
+                        "amount": 105_000_000_000u64,
+                    }),
+                ),
+                fee_paid(),
+                MatchedEvent {
+                    extrinsic_index: Some(2),
+                    ..event(4, "Balances", "Transfer", json!({}))
+                },
+            ],
+        );
+        let format = SummaryFormat::new(Denomination::new("DOT", 10));
+
+        assert_eq!(receipt.events.len(), 2);
+        assert_eq!(receipt.fee(), Some(160_000_000));
+        assert_eq!(
+            receipt.summarize(&format),
+            "Transferred 10.5 DOT from 0x1111…11111 to 0x2222…22222, fee 0.016 DOT"
+        );
+        assert!(receipt
+            .summarize(&format.with_ss58_prefix(0))
+            .starts_with("Transferred 10.5 DOT from 1"));
+    }
+
+    #[test]
+    fn test_summarize_failure_and_fallback() {
+        let format = SummaryFormat::new(Denomination::new("DOT", 10));
+
+        let failed = receipt(
+            "transfer_allow_death",
+            vec![
+                event(
+                    2,
+                    "System",
+                    "ExtrinsicFailed",
+                    json!({ "dispatch_error": { "Token": "FundsUnavailable" } }),
+                ),
+                fee_paid(),
+            ],
+        );
+        assert_eq!(
+            failed.summarize(&format),
+            "Failed Balances.transfer_allow_death: Token(FundsUnavailable), fee 0.016 DOT"
+        );
+
+        let quiet = receipt("remark", vec![]);
+        assert_eq!(quiet.summarize(&format), "Called Balances.remark");
+
+        let german = Catalog::new("de")
+            .with_template("summary.failed", "{call} fehlgeschlagen: {error}")
+            .with_template("summary.with_fee", "{summary}, Gebühr {fee}");
+        assert_eq!(
+            failed.summarize_with(&format, &Localizer::new().with_catalog(german)),
+            "Balances.transfer_allow_death fehlgeschlagen: Token(FundsUnavailable), Gebühr 0.016 DOT"
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//! Transfers tagged with a caller-supplied reference
+//!
+//! Payment processors need a key that ties an on-chain transfer back to the
+//! order or invoice it settles. [`TransactionExecutor::transfer_with_reference`]
+//! submits the transfer in a `Utility::batch_all` together with a
+//! `System::remark` carrying a [`PaymentReference`], so both land in the same
+//! extrinsic or not at all. [`ReferenceScanner`] finds those transfers again:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{PaymentReference, ReferenceScanner, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let reference = PaymentReference::new("order-2931")?;
+//! let found = ReferenceScanner::new(adapter)
+//!     .reference(reference)
+//!     .between(19_000_000, 19_000_100)
+//!     .run()
+//!     .await?;
+//! for transfer in found.iter().filter(|transfer| transfer.success) {
+//!     println!("#{} paid {} to {}", transfer.block_number, transfer.amount, transfer.to);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Scanning the blocks since the last attempt before resubmitting makes a
+//! referenced transfer idempotent: if the reference is already on chain, the
+//! payment went through.
+//!
+//! [`TransactionExecutor::transfer_with_reference`]: crate::TransactionExecutor::transfer_with_reference
+
+use crate::event_query::{block_hash_at, composite_to_json};
+use crate::{Error, Result, SubstrateAdapter};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::fmt;
+use tracing::debug;
+
+/// Prefix marking a remark as a payment reference
+pub const REFERENCE_PREFIX: &[u8] = b"apex:ref:";
+
+/// Longest reference accepted, in bytes
+pub const MAX_REFERENCE_LEN: usize = 128;
+
+/// Reconciliation key attached to a transfer
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PaymentReference(String);
+
+impl PaymentReference {
+    /// Reference of 1 to [`MAX_REFERENCE_LEN`] bytes without control characters
+    pub fn new(reference: impl Into<String>) -> Result<Self> {
+        let reference = reference.into();
+        if reference.is_empty() || reference.len() > MAX_REFERENCE_LEN {
+            return Err(Error::Other(format!(
+                "Payment reference must be 1 to {} bytes, got {}",
+                MAX_REFERENCE_LEN,
+                reference.len()
+            )));
+        }
+        if reference.chars().any(char::is_control) {
+            return Err(Error::Other(
+                "Payment reference must not contain control characters".to_string(),
+            ));
+        }
+        Ok(Self(reference))
+    }
+
+    /// The reference as given
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Bytes of the remark carrying this reference
+    pub fn to_remark(&self) -> Vec<u8> {
+        [REFERENCE_PREFIX, self.0.as_bytes()].concat()
+    }
+
+    /// Reference carried by a remark, if it is one
+    pub fn from_remark(remark: &[u8]) -> Option<Self> {
+        let reference = std::str::from_utf8(remark.strip_prefix(REFERENCE_PREFIX)?).ok()?;
+        Self::new(reference).ok()
+    }
+}
+
+impl fmt::Display for PaymentReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl TryFrom<String> for PaymentReference {
+    type Error = Error;
+
+    fn try_from(reference: String) -> Result<Self> {
+        Self::new(reference)
+    }
+}
+
+impl From<PaymentReference> for String {
+    fn from(reference: PaymentReference) -> Self {
+        reference.0
+    }
+}
+
+/// A native transfer found together with its reference
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReferencedTransfer {
+    /// Reference remarked alongside the transfer
+    pub reference: PaymentReference,
+    /// Block including the extrinsic
+    pub block_number: u64,
+    /// Hash of that block
+    pub block_hash: String,
+    /// Index of the extrinsic in the block
+    pub extrinsic_index: u32,
+    /// Hex account id of the signer
+    pub from: String,
+    /// Hex account id of the recipient
+    pub to: String,
+    /// Amount transferred
+    pub amount: u128,
+    /// Whether the extrinsic dispatched successfully
+    pub success: bool,
+}
+
+/// Finds referenced transfers in a block range
+pub struct ReferenceScanner<'a> {
+    adapter: &'a SubstrateAdapter,
+    reference: Option<PaymentReference>,
+    range: Option<(u64, u64)>,
+}
+
+impl<'a> ReferenceScanner<'a> {
+    /// Scanner matching any reference
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self {
+            adapter,
+            reference: None,
+            range: None,
+        }
+    }
+
+    /// Only match transfers carrying `reference`
+    pub fn reference(mut self, reference: PaymentReference) -> Self {
+        self.reference = Some(reference);
+        self
+    }
+
+    /// Scan blocks `from..=to`
+    pub fn between(mut self, from: u64, to: u64) -> Self {
+        self.range = Some((from, to));
+        self
+    }
+
+    /// Scan the range, returning matches in chain order
+    pub async fn run(&self) -> Result<Vec<ReferencedTransfer>> {
+        let (from, to) = self.range.ok_or_else(|| {
+            Error::Other("ReferenceScanner needs a block range; call between()".to_string())
+        })?;
+        if from > to {
+            return Err(Error::Other(format!(
+                "Invalid block range: {} is after {}",
+                from, to
+            )));
+        }
+        debug!("Scanning blocks {}..={} for payment references", from, to);
+
+        let mut found = Vec::new();
+        for number in from..=to {
+            found.extend(self.scan_block(number).await?);
+        }
+        Ok(found)
+    }
+
+    async fn scan_block(&self, number: u64) -> Result<Vec<ReferencedTransfer>> {
+        let hash = block_hash_at(self.adapter.rpc_client(), number).await?;
+        let block_hash = format!("0x{}", hex::encode(hash.0));
+        let block = self
+            .adapter
+            .client()
+            .blocks()
+            .at(hash)
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get block {}: {}", number, e)))?;
+        let extrinsics = block
+            .extrinsics()
+            .await
+            .map_err(|e| Error::Transaction(format!("Failed to get extrinsics: {}", e)))?;
+
+        let mut found = Vec::new();
+        for extrinsic in extrinsics.iter() {
+            let (Ok(pallet), Ok(call), Some(from)) = (
+                extrinsic.pallet_name(),
+                extrinsic.variant_name(),
+                extrinsic.address_bytes().and_then(signer_hex),
+            ) else {
+                continue;
+            };
+            let fields = extrinsic
+                .field_values()
+                .map(|fields| composite_to_json(&fields))
+                .unwrap_or(JsonValue::Null);
+            let Some((reference, to, amount)) = referenced_transfer(pallet, call, &fields) else {
+                continue;
+            };
+            if self.reference.as_ref().is_some_and(|r| *r != reference) {
+                continue;
+            }
+
+            let success = match extrinsic.events().await {
+                Ok(events) => events
+                    .iter()
+                    .flatten()
+                    .any(|e| e.pallet_name() == "System" && e.variant_name() == "ExtrinsicSuccess"),
+                Err(e) => {
+                    return Err(Error::Connection(format!(
+                        "Failed to get events of block {}: {}",
+                        number, e
+                    )))
+                }
+            };
+            found.push(ReferencedTransfer {
+                reference,
+                block_number: number,
+                block_hash: block_hash.clone(),
+                extrinsic_index: extrinsic.index(),
+                from,
+                to,
+                amount,
+                success,
+            });
+        }
+        Ok(found)
+    }
+}
+
+/// Reference, recipient and amount of a `batch_all`/`batch` holding one
+/// native transfer and one reference remark
+fn referenced_transfer(
+    pallet: &str,
+    call: &str,
+    fields: &JsonValue,
+) -> Option<(PaymentReference, String, u128)> {
+    let reference = batch_reference(pallet, call, fields)?;
+    let mut transfers =
+        batch_items(pallet, call, fields)?
+            .into_iter()
+            .filter(|(pallet, call, _)| {
+                *pallet == "Balances"
+                    && matches!(
+                        *call,
+                        "transfer_keep_alive" | "transfer_allow_death" | "transfer"
+                    )
+            });
+    let (_, _, args) = transfers.next()?;
+    if transfers.next().is_some() {
+        return None;
+    }
+    let to = args["dest"]["Id"].as_str()?.to_string();
+    let amount = match &args["value"] {
+        JsonValue::Number(n) => u128::from(n.as_u64()?),
+        JsonValue::String(s) => s.parse().ok()?,
+        _ => return None,
+    };
+    Some((reference, to, amount))
+}
+
+/// The one reference remarked in a `batch_all`/`batch` call
+pub(crate) fn batch_reference(
+    pallet: &str,
+    call: &str,
+    fields: &JsonValue,
+) -> Option<PaymentReference> {
+    let mut references = batch_items(pallet, call, fields)?
+        .into_iter()
+        .filter(|(pallet, call, _)| {
+            *pallet == "System" && matches!(*call, "remark" | "remark_with_event")
+        })
+        .filter_map(|(_, _, args)| {
+            json_bytes(&args["remark"]).and_then(|remark| PaymentReference::from_remark(&remark))
+        });
+    let reference = references.next()?;
+    references.next().is_none().then_some(reference)
+}
+
+/// `(pallet, call, args)` of the calls in a `batch_all`/`batch` call
+fn batch_items<'f>(
+    pallet: &str,
+    call: &str,
+    fields: &'f JsonValue,
+) -> Option<Vec<(&'f str, &'f str, &'f JsonValue)>> {
+    if pallet != "Utility" || !matches!(call, "batch" | "batch_all") {
+        return None;
+    }
+    let JsonValue::Array(calls) = &fields["calls"] else {
+        return None;
+    };
+    calls
+        .iter()
+        .map(|inner| {
+            let (pallet, call) = inner.as_object()?.iter().next()?;
+            let (name, args) = call.as_object()?.iter().next()?;
+            Some((pallet.as_str(), name.as_str(), args))
+        })
+        .collect()
+}
+
+/// Bytes of a decoded `Vec<u8>`
+///
+/// Depending on length it decodes to a hex string, a single number or an
+/// array of numbers.
+fn json_bytes(value: &JsonValue) -> Option<Vec<u8>> {
+    match value {
+        JsonValue::String(s) => hex::decode(s.strip_prefix("0x")?).ok(),
+        JsonValue::Number(n) => Some(vec![u8::try_from(n.as_u64()?).ok()?]),
+        JsonValue::Array(values) => values
+            .iter()
+            .map(|v| v.as_u64().and_then(|b| u8::try_from(b).ok()))
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Hex account id of a `MultiAddress::Id` signer
+fn signer_hex(address: &[u8]) -> Option<String> {
+    match address {
+        [0, id @ ..] if id.len() == 32 => Some(format!("0x{}", hex::encode(id))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const BOB: &str = "0x8eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48";
+
+    fn remark(reference: &str) -> JsonValue {
+        let bytes: Vec<u8> = PaymentReference::new(reference).unwrap().to_remark();
+        json!({"System": {"remark": {"remark": bytes}}})
+    }
+
+    fn transfer(value: JsonValue) -> JsonValue {
+        json!({"Balances": {"transfer_keep_alive": {"dest": {"Id": BOB}, "value": value}}})
+    }
+
+    #[test]
+    fn test_reference_validation() {
+        assert!(PaymentReference::new("").is_err());
+        assert!(PaymentReference::new("a".repeat(MAX_REFERENCE_LEN + 1)).is_err());
+        assert!(PaymentReference::new("line\nbreak").is_err());
+
+        let reference = PaymentReference::new("order-2931").unwrap();
+        assert_eq!(reference.to_remark(), b"apex:ref:order-2931");
+        assert_eq!(
+            PaymentReference::from_remark(&reference.to_remark()),
+            Some(reference)
+        );
+        assert_eq!(PaymentReference::from_remark(b"gm"), None);
+    }
+
+    #[test]
+    fn test_finds_referenced_transfer() {
+        let fields = json!({"calls": [transfer(json!(1_000)), remark("order-1")]});
+        let (reference, to, amount) = referenced_transfer("Utility", "batch_all", &fields).unwrap();
+        assert_eq!(reference.as_str(), "order-1");
+        assert_eq!(to, BOB);
+        assert_eq!(amount, 1_000);
+
+        // amounts beyond 64 bits decode as strings
+        let large = json!({"calls": [remark("order-2"), transfer(json!("36893488147419103232"))]});
+        let (_, _, amount) = referenced_transfer("Utility", "batch", &large).unwrap();
+        assert_eq!(amount, 1 << 65);
+    }
+
+    #[test]
+    fn test_ignores_ambiguous_batches() {
+        let unreferenced = json!({"calls": [
+            transfer(json!(1)),
+            {"System": {"remark": {"remark": [1, 2, 3]}}},
+        ]});
+        assert!(referenced_transfer("Utility", "batch_all", &unreferenced).is_none());
+
+        let two_transfers = json!({"calls": [transfer(json!(1)), transfer(json!(2)), remark("x")]});
+        assert!(referenced_transfer("Utility", "batch_all", &two_transfers).is_none());
+
+        let fields = json!({"calls": [transfer(json!(1)), remark("x")]});
+        assert!(referenced_transfer("Utility", "force_batch", &fields).is_none());
+    }
+
+    #[test]
+    fn test_json_bytes_shapes() {
+        assert_eq!(json_bytes(&json!([1, 2])), Some(vec![1, 2]));
+        assert_eq!(json_bytes(&json!(7)), Some(vec![7]));
+        assert_eq!(json_bytes(&json!("0x0102")), Some(vec![1, 2]));
+        assert_eq!(json_bytes(&json!([256])), None);
+    }
+}
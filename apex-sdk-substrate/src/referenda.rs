@@ -0,0 +1,613 @@
+//! OpenGov referendum outcomes and "what if I vote" simulation
+//!
+//! A referendum in its decision period passes once both its approval (the
+//! share of conviction-weighted ayes) and its support (the share of the
+//! issuance voting aye, without conviction) are above their track's curves.
+//! Both curves fall as the decision period elapses. [`ReferendumOutcome`]
+//! evaluates a tally against the curves at a block; [`ReferendaSimulator`]
+//! also evaluates the tally with a hypothetical vote added, so a voter can see
+//! whether their vote would tip the referendum.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::referenda::{Conviction, HypotheticalVote, ReferendaSimulator};
+//! use apex_sdk_substrate::SubstrateAdapter;
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let vote = HypotheticalVote::aye(50_000 * 10u128.pow(10), Conviction::Locked3x);
+//! let simulation = ReferendaSimulator::new(adapter).simulate(1_234, &vote).await?;
+//!
+//! println!(
+//!     "approval {:.2}% -> {:.2}% (needs {:.2}%), passing: {} -> {}",
+//!     simulation.before.approval * 100.0,
+//!     simulation.after.approval * 100.0,
+//!     simulation.after.required_approval * 100.0,
+//!     simulation.before.passing,
+//!     simulation.after.passing,
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The simulation adds the vote to the current tally; an account that already
+//! voted should account for its previous vote being replaced.
+
+use crate::receipt::{json_some, json_u128};
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::Decode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::dynamic::Value;
+
+/// Denominator of `Perbill` and `FixedI64` values
+const BILLION: f64 = 1_000_000_000.0;
+
+/// Conviction of a vote: how long the balance is locked, and the vote multiplier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Conviction {
+    /// 0.1x votes, no lock beyond the referendum
+    #[default]
+    None,
+    /// 1x votes, locked for one period
+    Locked1x,
+    /// 2x votes, locked for two periods
+    Locked2x,
+    /// 3x votes, locked for four periods
+    Locked3x,
+    /// 4x votes, locked for eight periods
+    Locked4x,
+    /// 5x votes, locked for sixteen periods
+    Locked5x,
+    /// 6x votes, locked for thirty-two periods
+    Locked6x,
+}
+
+impl Conviction {
+    /// Conviction from its index, as encoded on chain
+    pub fn from_index(index: u8) -> Option<Self> {
+        Some(match index {
+            0 => Conviction::None,
+            1 => Conviction::Locked1x,
+            2 => Conviction::Locked2x,
+            3 => Conviction::Locked3x,
+            4 => Conviction::Locked4x,
+            5 => Conviction::Locked5x,
+            6 => Conviction::Locked6x,
+            _ => return None,
+        })
+    }
+
+    /// Conviction from its variant name, e.g. `Locked3x`
+    pub fn from_name(name: &str) -> Option<Self> {
+        (0..=6)
+            .filter_map(Conviction::from_index)
+            .find(|conviction| format!("{:?}", conviction) == name)
+    }
+
+    /// Votes a balance carries at this conviction
+    pub fn votes(&self, balance: u128) -> u128 {
+        match self {
+            Conviction::None => balance / 10,
+            conviction => balance.saturating_mul(*conviction as u128),
+        }
+    }
+
+    /// Number of vote lock periods the balance stays locked after the referendum
+    pub fn lock_periods(&self) -> u32 {
+        match self {
+            Conviction::None => 0,
+            conviction => 1 << (*conviction as u32 - 1),
+        }
+    }
+}
+
+/// A vote to add to a tally
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HypotheticalVote {
+    /// Aye or nay
+    pub aye: bool,
+    /// Balance voted
+    pub balance: u128,
+    /// Conviction of the vote
+    pub conviction: Conviction,
+}
+
+impl HypotheticalVote {
+    /// An aye vote
+    pub fn aye(balance: u128, conviction: Conviction) -> Self {
+        Self {
+            aye: true,
+            balance,
+            conviction,
+        }
+    }
+
+    /// A nay vote
+    pub fn nay(balance: u128, conviction: Conviction) -> Self {
+        Self {
+            aye: false,
+            balance,
+            conviction,
+        }
+    }
+}
+
+/// Tally of a referendum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Tally {
+    /// Conviction-weighted aye votes
+    pub ayes: u128,
+    /// Conviction-weighted nay votes
+    pub nays: u128,
+    /// Aye balance without conviction
+    pub support: u128,
+}
+
+impl Tally {
+    /// Tally from its decoded value
+    pub fn from_json(value: &JsonValue) -> Option<Self> {
+        Some(Self {
+            ayes: json_u128(value.get("ayes")?)?,
+            nays: json_u128(value.get("nays")?)?,
+            support: json_u128(value.get("support")?)?,
+        })
+    }
+
+    /// Tally with a vote added
+    pub fn with_vote(&self, vote: &HypotheticalVote) -> Self {
+        let votes = vote.conviction.votes(vote.balance);
+        let mut tally = *self;
+        if vote.aye {
+            tally.ayes = tally.ayes.saturating_add(votes);
+            tally.support = tally.support.saturating_add(vote.balance);
+        } else {
+            tally.nays = tally.nays.saturating_add(votes);
+        }
+        tally
+    }
+
+    /// Share of votes that are aye; zero without votes
+    pub fn approval(&self) -> f64 {
+        let total = self.ayes.saturating_add(self.nays);
+        if total == 0 {
+            return 0.0;
+        }
+        self.ayes as f64 / total as f64
+    }
+
+    /// Share of `issuance` voting aye
+    pub fn support_fraction(&self, issuance: u128) -> f64 {
+        if issuance == 0 {
+            return 0.0;
+        }
+        (self.support as f64 / issuance as f64).min(1.0)
+    }
+}
+
+/// Threshold curve of a track, over the elapsed share of the decision period
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Curve {
+    /// Falls linearly from `ceil` to `floor` over `length`, then stays at `floor`
+    LinearDecreasing {
+        /// Share of the period the fall takes
+        length: f64,
+        /// Final threshold
+        floor: f64,
+        /// Initial threshold
+        ceil: f64,
+    },
+    /// Falls by `step` every `period`, from `begin` down to `end`
+    SteppedDecreasing {
+        /// Initial threshold
+        begin: f64,
+        /// Final threshold
+        end: f64,
+        /// Fall per step
+        step: f64,
+        /// Share of the period between steps
+        period: f64,
+    },
+    /// `factor / (x + x_offset) + y_offset`
+    Reciprocal {
+        /// Scale of the curve
+        factor: f64,
+        /// Horizontal offset
+        x_offset: f64,
+        /// Vertical offset
+        y_offset: f64,
+    },
+}
+
+impl Curve {
+    /// Curve from its decoded value; `Perbill` and `FixedI64` parts are in billionths
+    pub fn from_json(value: &JsonValue) -> Option<Self> {
+        let (variant, fields) = value.as_object()?.iter().next()?;
+        let part = |name: &str| Some(fields.get(name)?.as_i64()? as f64 / BILLION);
+        Some(match variant.as_str() {
+            "LinearDecreasing" => Curve::LinearDecreasing {
+                length: part("length")?,
+                floor: part("floor")?,
+                ceil: part("ceil")?,
+            },
+            "SteppedDecreasing" => Curve::SteppedDecreasing {
+                begin: part("begin")?,
+                end: part("end")?,
+                step: part("step")?,
+                period: part("period")?,
+            },
+            "Reciprocal" => Curve::Reciprocal {
+                factor: part("factor")?,
+                x_offset: part("x_offset")?,
+                y_offset: part("y_offset")?,
+            },
+            _ => return None,
+        })
+    }
+
+    /// Threshold once `x` of the decision period has elapsed
+    pub fn threshold(&self, x: f64) -> f64 {
+        let x = x.clamp(0.0, 1.0);
+        let y = match *self {
+            Curve::LinearDecreasing {
+                length,
+                floor,
+                ceil,
+            } => {
+                let progress = if length > 0.0 {
+                    (x / length).min(1.0)
+                } else {
+                    1.0
+                };
+                ceil - (ceil - floor) * progress
+            }
+            Curve::SteppedDecreasing {
+                begin,
+                end,
+                step,
+                period,
+            } => {
+                let steps = if period > 0.0 {
+                    (x / period).floor()
+                } else {
+                    0.0
+                };
+                (begin - step * steps).max(end)
+            }
+            Curve::Reciprocal {
+                factor,
+                x_offset,
+                y_offset,
+            } => factor / (x + x_offset) + y_offset,
+        };
+        y.clamp(0.0, 1.0)
+    }
+}
+
+/// Decision parameters of a referendum track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackInfo {
+    /// Track id
+    pub id: u16,
+    /// Track name, e.g. `root`
+    pub name: String,
+    /// Blocks before deciding can start
+    pub prepare_period: u32,
+    /// Blocks the decision period lasts
+    pub decision_period: u32,
+    /// Blocks a referendum must keep passing to be confirmed
+    pub confirm_period: u32,
+    /// Approval threshold curve
+    pub min_approval: Curve,
+    /// Support threshold curve
+    pub min_support: Curve,
+}
+
+impl TrackInfo {
+    /// Track from its id and decoded `TrackInfo`
+    pub fn from_json(id: u16, value: &JsonValue) -> Option<Self> {
+        let number = |name: &str| u32::try_from(value.get(name)?.as_u64()?).ok();
+        // a string on older runtimes, a zero-padded byte array on newer ones
+        let name = match value.get("name")? {
+            JsonValue::String(name) => name.clone(),
+            JsonValue::Array(bytes) => bytes
+                .iter()
+                .filter_map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+                .take_while(|byte| *byte != 0)
+                .map(char::from)
+                .collect(),
+            _ => return None,
+        };
+        Some(Self {
+            id,
+            name,
+            prepare_period: number("prepare_period")?,
+            decision_period: number("decision_period")?,
+            confirm_period: number("confirm_period")?,
+            min_approval: Curve::from_json(value.get("min_approval")?)?,
+            min_support: Curve::from_json(value.get("min_support")?)?,
+        })
+    }
+
+    /// Tracks from the decoded `Referenda::Tracks` constant
+    pub fn all_from_json(value: &JsonValue) -> Vec<Self> {
+        let pairs = match value.as_array() {
+            // a single track decodes without the outer list
+            Some(items) if items.first().is_some_and(JsonValue::is_number) => vec![value],
+            Some(items) => items.iter().collect(),
+            None => Vec::new(),
+        };
+        pairs
+            .into_iter()
+            .filter_map(|pair| {
+                let id = u16::try_from(pair.get(0)?.as_u64()?).ok()?;
+                TrackInfo::from_json(id, pair.get(1)?)
+            })
+            .collect()
+    }
+}
+
+/// An ongoing referendum
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OngoingReferendum {
+    /// Referendum index
+    pub index: u32,
+    /// Track id
+    pub track: u16,
+    /// Block the referendum was submitted in
+    pub submitted: u32,
+    /// Block the decision period started, if deciding
+    pub deciding_since: Option<u32>,
+    /// Block confirmation ends, if confirming
+    pub confirming_until: Option<u32>,
+    /// Current tally
+    pub tally: Tally,
+}
+
+impl OngoingReferendum {
+    /// Referendum from a decoded `ReferendumInfoFor` value; `None` unless ongoing
+    pub fn from_json(index: u32, value: &JsonValue) -> Option<Self> {
+        let status = value.get("Ongoing")?;
+        let deciding = status.get("deciding").and_then(json_some);
+        let number = |value: &JsonValue| u32::try_from(value.as_u64()?).ok();
+        Some(Self {
+            index,
+            track: u16::try_from(status.get("track")?.as_u64()?).ok()?,
+            submitted: number(status.get("submitted")?)?,
+            deciding_since: deciding.and_then(|deciding| number(deciding.get("since")?)),
+            confirming_until: deciding
+                .and_then(|deciding| deciding.get("confirming"))
+                .and_then(json_some)
+                .and_then(number),
+            tally: Tally::from_json(status.get("tally")?)?,
+        })
+    }
+
+    /// Where a tally stands against the track's curves at block `now`
+    ///
+    /// Before deciding starts, the thresholds at the start of the decision
+    /// period apply.
+    pub fn outcome(
+        &self,
+        tally: &Tally,
+        track: &TrackInfo,
+        issuance: u128,
+        now: u32,
+    ) -> ReferendumOutcome {
+        let elapsed = self
+            .deciding_since
+            .map(|since| now.saturating_sub(since))
+            .unwrap_or_default();
+        let progress = if track.decision_period == 0 {
+            1.0
+        } else {
+            (elapsed as f64 / track.decision_period as f64).min(1.0)
+        };
+        let approval = tally.approval();
+        let support = tally.support_fraction(issuance);
+        let required_approval = track.min_approval.threshold(progress);
+        let required_support = track.min_support.threshold(progress);
+        ReferendumOutcome {
+            approval,
+            support,
+            required_approval,
+            required_support,
+            decision_progress: progress,
+            passing: approval >= required_approval && support >= required_support,
+        }
+    }
+}
+
+/// A tally evaluated against a track's curves
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReferendumOutcome {
+    /// Share of votes that are aye
+    pub approval: f64,
+    /// Share of the issuance voting aye
+    pub support: f64,
+    /// Approval needed now
+    pub required_approval: f64,
+    /// Support needed now
+    pub required_support: f64,
+    /// Elapsed share of the decision period
+    pub decision_progress: f64,
+    /// Whether both thresholds are met
+    pub passing: bool,
+}
+
+/// Outcome of a referendum with and without a hypothetical vote
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoteSimulation {
+    /// The referendum as read from chain
+    pub referendum: OngoingReferendum,
+    /// Outcome at the current tally
+    pub before: ReferendumOutcome,
+    /// Outcome with the vote added
+    pub after: ReferendumOutcome,
+}
+
+impl VoteSimulation {
+    /// Whether the vote changes the referendum from failing to passing or back
+    pub fn flips_outcome(&self) -> bool {
+        self.before.passing != self.after.passing
+    }
+}
+
+/// Referendum queries and vote simulation for a connected chain
+pub struct ReferendaSimulator<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> ReferendaSimulator<'a> {
+    /// Query through the adapter's connection
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Tracks from the `Referenda::Tracks` constant
+    pub fn tracks(&self) -> Result<Vec<TrackInfo>> {
+        let tracks = self
+            .adapter
+            .storage()
+            .get_constant_json("Referenda", "Tracks")?;
+        Ok(TrackInfo::all_from_json(&tracks))
+    }
+
+    /// An ongoing referendum; `None` if it is unknown or already decided
+    pub async fn referendum(&self, index: u32) -> Result<Option<OngoingReferendum>> {
+        let info = self
+            .adapter
+            .storage()
+            .query_storage_json(
+                "Referenda",
+                "ReferendumInfoFor",
+                vec![Value::u128(index as u128)],
+            )
+            .await?;
+        Ok(info.and_then(|info| OngoingReferendum::from_json(index, &info)))
+    }
+
+    /// Total issuance less inactive funds, which support is measured against
+    pub async fn active_issuance(&self) -> Result<u128> {
+        let storage = self.adapter.storage();
+        let total = storage
+            .query_storage("Balances", "TotalIssuance", vec![])
+            .await?;
+        let inactive = storage
+            .query_storage("Balances", "InactiveIssuance", vec![])
+            .await?;
+        let decode = |bytes: Option<Vec<u8>>| -> Result<u128> {
+            bytes
+                .map(|bytes| u128::decode(&mut &bytes[..]))
+                .transpose()
+                .map(Option::unwrap_or_default)
+                .map_err(|e| Error::Storage(format!("Failed to decode issuance: {}", e)))
+        };
+        Ok(decode(total)?.saturating_sub(decode(inactive)?))
+    }
+
+    /// Outcome of an ongoing referendum at the current block, with and without `vote`
+    pub async fn simulate(&self, index: u32, vote: &HypotheticalVote) -> Result<VoteSimulation> {
+        let referendum = self
+            .referendum(index)
+            .await?
+            .ok_or_else(|| Error::Other(format!("Referendum {} is not ongoing", index)))?;
+        let track = self
+            .tracks()?
+            .into_iter()
+            .find(|track| track.id == referendum.track)
+            .ok_or_else(|| Error::Other(format!("Unknown track {}", referendum.track)))?;
+        let issuance = self.active_issuance().await?;
+        let now = self.current_block().await?;
+
+        let before = referendum.outcome(&referendum.tally, &track, issuance, now);
+        let after = referendum.outcome(&referendum.tally.with_vote(vote), &track, issuance, now);
+        Ok(VoteSimulation {
+            referendum,
+            before,
+            after,
+        })
+    }
+
+    async fn current_block(&self) -> Result<u32> {
+        let block = self.adapter.client().blocks().at_latest().await?;
+        Ok(block.number())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Polkadot's root track
+    fn root_track() -> TrackInfo {
+        TrackInfo::all_from_json(&json!([0, {
+            "name": "root",
+            "max_deciding": 1,
+            "decision_deposit": "1000000000000000",
+            "prepare_period": 1_200,
+            "decision_period": 403_200,
+            "confirm_period": 14_400,
+            "min_enactment_period": 14_400,
+            "min_approval": {"Reciprocal": {
+                "factor": 222_222_224, "x_offset": 333_333_335, "y_offset": 333_333_332
+            }},
+            "min_support": {"LinearDecreasing": {
+                "length": 1_000_000_000, "floor": 0, "ceil": 500_000_000
+            }},
+        }]))
+        .remove(0)
+    }
+
+    #[test]
+    fn test_curves() {
+        let track = root_track();
+        assert_eq!(track.name, "root");
+        assert!((track.min_approval.threshold(0.0) - 1.0).abs() < 1e-6);
+        assert!((track.min_approval.threshold(1.0) - 0.5).abs() < 1e-6);
+        assert!((track.min_support.threshold(0.5) - 0.25).abs() < 1e-9);
+
+        let stepped = Curve::SteppedDecreasing {
+            begin: 0.8,
+            end: 0.5,
+            step: 0.1,
+            period: 0.25,
+        };
+        assert!((stepped.threshold(0.3) - 0.7).abs() < 1e-9);
+        assert!((stepped.threshold(1.0) - 0.5).abs() < 1e-9);
+
+        assert_eq!(Conviction::Locked3x.votes(10), 30);
+        assert_eq!(Conviction::None.votes(10), 1);
+        assert_eq!(Conviction::Locked6x.lock_periods(), 32);
+    }
+
+    #[test]
+    fn test_vote_simulation() {
+        let referendum = OngoingReferendum::from_json(
+            7,
+            &json!({"Ongoing": {
+                "track": 0,
+                "submitted": 100,
+                "deciding": {"Some": {"since": 1_000, "confirming": "None"}},
+                "tally": {"ayes": 600, "nays": 400, "support": 100},
+            }}),
+        )
+        .unwrap();
+        assert_eq!(referendum.deciding_since, Some(1_000));
+        assert_eq!(referendum.confirming_until, None);
+
+        // halfway through: approval needs 0.6 and support 0.25
+        let track = root_track();
+        let now = 1_000 + 201_600;
+        let before = referendum.outcome(&referendum.tally, &track, 1_000, now);
+        assert!(!before.passing);
+        assert!((before.decision_progress - 0.5).abs() < 1e-9);
+
+        let tally = referendum
+            .tally
+            .with_vote(&HypotheticalVote::aye(200, Conviction::Locked1x));
+        assert_eq!(tally.support, 300);
+        let after = referendum.outcome(&tally, &track, 1_000, now);
+        assert!(after.approval >= after.required_approval);
+        assert!(after.passing);
+    }
+}
@@ -0,0 +1,365 @@
+//! RPC middleware
+//!
+//! [`RpcLayer`]s intercept every request the adapter sends and the response
+//! it gets back, for logging, custom caching, signing requests with provider
+//! tokens or collecting metrics. Layers are added to the [`ChainConfig`] and
+//! wrap the connection in the order they were added, the first one seeing
+//! requests first and responses last:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::rpc_middleware::{Next, RpcLayer, RpcRequest, RpcResult, TracingLayer};
+//! use apex_sdk_substrate::{ChainConfig, SubstrateAdapter};
+//! use async_trait::async_trait;
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//!
+//! #[derive(Default)]
+//! struct RequestCounter(AtomicU64);
+//!
+//! #[async_trait]
+//! impl RpcLayer for RequestCounter {
+//!     async fn call(&self, request: RpcRequest, next: Next<'_>) -> RpcResult {
+//!         self.0.fetch_add(1, Ordering::Relaxed);
+//!         next.run(request).await
+//!     }
+//! }
+//!
+//! # async fn example() -> Result<(), apex_sdk_substrate::Error> {
+//! let config = ChainConfig::polkadot()
+//!     .with_rpc_layer(TracingLayer)
+//!     .with_rpc_layer(RequestCounter::default());
+//! let adapter = SubstrateAdapter::connect_with_config(config).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Subscription requests go through [`RpcLayer::on_subscribe`]; the
+//! notifications of a subscription are not intercepted.
+//!
+//! [`ChainConfig`]: crate::ChainConfig
+
+use crate::{Error, Result};
+use async_trait::async_trait;
+use serde_json::value::RawValue;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+use subxt::backend::rpc::{RawRpcFuture, RawRpcSubscription, RpcClient, RpcClientT};
+use subxt::ext::subxt_rpcs::Error as RpcError;
+use tracing::{debug, warn};
+
+/// Response to a request passing through the layers
+pub type RpcResult = std::result::Result<Box<RawValue>, RpcError>;
+
+/// An outgoing RPC request
+#[derive(Debug)]
+pub struct RpcRequest {
+    /// Method name, e.g. `chain_getBlock`
+    pub method: String,
+    /// Parameters as a JSON array, if any
+    pub params: Option<Box<RawValue>>,
+}
+
+impl RpcRequest {
+    /// Request for `method` with raw parameters
+    pub fn new(method: impl Into<String>, params: Option<Box<RawValue>>) -> Self {
+        Self {
+            method: method.into(),
+            params,
+        }
+    }
+
+    /// Parameters parsed as JSON
+    pub fn params_json(&self) -> Option<serde_json::Value> {
+        self.params
+            .as_ref()
+            .and_then(|params| serde_json::from_str(params.get()).ok())
+    }
+
+    /// Replace the parameters
+    pub fn set_params(&mut self, params: &serde_json::Value) -> Result<()> {
+        self.params = Some(
+            serde_json::value::to_raw_value(params)
+                .map_err(|e| Error::Encoding(format!("Invalid RPC parameters: {}", e)))?,
+        );
+        Ok(())
+    }
+}
+
+/// Middleware wrapping the RPC connection
+///
+/// A layer can change the request, answer it without calling `next`, or
+/// inspect and change the response `next` returns.
+#[async_trait]
+pub trait RpcLayer: Send + Sync {
+    /// Handle `request`, passing it on with [`Next::run`]
+    async fn call(&self, request: RpcRequest, next: Next<'_>) -> RpcResult;
+
+    /// Inspect or change a subscription request before it is sent
+    fn on_subscribe(&self, _request: &mut RpcRequest) {}
+}
+
+/// The layers after the current one, ending in the connection
+pub struct Next<'a> {
+    layers: &'a [Arc<dyn RpcLayer>],
+    client: &'a RpcClient,
+}
+
+impl Next<'_> {
+    /// Pass `request` to the next layer, or send it if there is none
+    pub async fn run(self, request: RpcRequest) -> RpcResult {
+        match self.layers.split_first() {
+            Some((layer, rest)) => {
+                layer
+                    .call(
+                        request,
+                        Next {
+                            layers: rest,
+                            client: self.client,
+                        },
+                    )
+                    .await
+            }
+            None => {
+                self.client
+                    .request_raw(&request.method, request.params)
+                    .await
+            }
+        }
+    }
+}
+
+/// Ordered list of [`RpcLayer`]s
+#[derive(Clone, Default)]
+pub struct RpcLayers(Vec<Arc<dyn RpcLayer>>);
+
+impl RpcLayers {
+    /// No layers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a layer inside the ones added before
+    pub fn push(&mut self, layer: impl RpcLayer + 'static) {
+        self.0.push(Arc::new(layer));
+    }
+
+    /// Number of layers
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no layers
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Wrap `client` in the layers, returning it unchanged if there are none
+    pub fn wrap(&self, client: RpcClient) -> RpcClient {
+        if self.is_empty() {
+            return client;
+        }
+        RpcClient::new(LayeredRpcClient::new(client, self.clone()))
+    }
+}
+
+impl fmt::Debug for RpcLayers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcLayers")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+/// RPC client sending every request through a list of layers
+pub struct LayeredRpcClient {
+    inner: RpcClient,
+    layers: RpcLayers,
+}
+
+impl LayeredRpcClient {
+    /// Wrap `inner` in `layers`
+    pub fn new(inner: RpcClient, layers: RpcLayers) -> Self {
+        Self { inner, layers }
+    }
+}
+
+impl RpcClientT for LayeredRpcClient {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RawRpcFuture<'a, Box<RawValue>> {
+        Box::pin(async move {
+            Next {
+                layers: &self.layers.0,
+                client: &self.inner,
+            }
+            .run(RpcRequest::new(method, params))
+            .await
+        })
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+    ) -> RawRpcFuture<'a, RawRpcSubscription> {
+        Box::pin(async move {
+            let mut request = RpcRequest::new(sub, params);
+            for layer in &self.layers.0 {
+                layer.on_subscribe(&mut request);
+            }
+            self.inner
+                .subscribe_raw(&request.method, request.params, unsub)
+                .await
+        })
+    }
+}
+
+/// Layer logging every request with its duration
+///
+/// Successful requests are logged at debug level, failures as warnings.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingLayer;
+
+#[async_trait]
+impl RpcLayer for TracingLayer {
+    async fn call(&self, request: RpcRequest, next: Next<'_>) -> RpcResult {
+        let method = request.method.clone();
+        let started = Instant::now();
+        let response = next.run(request).await;
+        match &response {
+            Ok(_) => debug!("RPC {} took {:?}", method, started.elapsed()),
+            Err(e) => warn!("RPC {} failed after {:?}: {}", method, started.elapsed(), e),
+        }
+        response
+    }
+
+    fn on_subscribe(&self, request: &mut RpcRequest) {
+        debug!("RPC subscribe {}", request.method);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    struct EchoClient;
+
+    impl RpcClientT for EchoClient {
+        fn request_raw<'a>(
+            &'a self,
+            method: &'a str,
+            params: Option<Box<RawValue>>,
+        ) -> RawRpcFuture<'a, Box<RawValue>> {
+            Box::pin(async move {
+                let params = params.map_or("null".to_string(), |p| p.get().to_string());
+                Ok(RawValue::from_string(format!("[\"{}\",{}]", method, params)).unwrap())
+            })
+        }
+
+        fn subscribe_raw<'a>(
+            &'a self,
+            sub: &'a str,
+            _params: Option<Box<RawValue>>,
+            _unsub: &'a str,
+        ) -> RawRpcFuture<'a, RawRpcSubscription> {
+            Box::pin(async move {
+                Err(RpcError::Client(
+                    format!("no subscriptions: {}", sub).into(),
+                ))
+            })
+        }
+    }
+
+    struct Recorder(Arc<Mutex<Vec<String>>>, &'static str);
+
+    #[async_trait]
+    impl RpcLayer for Recorder {
+        async fn call(&self, request: RpcRequest, next: Next<'_>) -> RpcResult {
+            self.0.lock().unwrap().push(format!("{} >", self.1));
+            let response = next.run(request).await;
+            self.0.lock().unwrap().push(format!("{} <", self.1));
+            response
+        }
+
+        fn on_subscribe(&self, _request: &mut RpcRequest) {
+            self.0.lock().unwrap().push(format!("{} subscribe", self.1));
+        }
+    }
+
+    struct AddToken;
+
+    #[async_trait]
+    impl RpcLayer for AddToken {
+        async fn call(&self, mut request: RpcRequest, next: Next<'_>) -> RpcResult {
+            let mut params = request.params_json().unwrap_or_default();
+            params
+                .as_array_mut()
+                .expect("array params")
+                .push("token".into());
+            request.set_params(&params).unwrap();
+            next.run(request).await
+        }
+    }
+
+    struct ShortCircuit;
+
+    #[async_trait]
+    impl RpcLayer for ShortCircuit {
+        async fn call(&self, request: RpcRequest, next: Next<'_>) -> RpcResult {
+            if request.method == "system_name" {
+                return Ok(RawValue::from_string("\"cached\"".to_string()).unwrap());
+            }
+            next.run(request).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layers_run_in_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut layers = RpcLayers::new();
+        layers.push(Recorder(log.clone(), "outer"));
+        layers.push(Recorder(log.clone(), "inner"));
+        layers.push(AddToken);
+        let client = layers.wrap(RpcClient::new(EchoClient));
+
+        let params = RawValue::from_string("[1]".to_string()).unwrap();
+        let response = client
+            .request_raw("chain_getBlockHash", Some(params))
+            .await
+            .unwrap();
+        assert_eq!(response.get(), "[\"chain_getBlockHash\",[1,\"token\"]]");
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["outer >", "inner >", "inner <", "outer <"]
+        );
+
+        log.lock().unwrap().clear();
+        let Err(error) = client
+            .subscribe_raw("chain_subscribeNewHeads", None, "chain_unsubscribeNewHeads")
+            .await
+        else {
+            panic!("the echo client has no subscriptions");
+        };
+        assert!(error.to_string().contains("chain_subscribeNewHeads"));
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["outer subscribe", "inner subscribe"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_can_answer_without_sending() {
+        let mut layers = RpcLayers::new();
+        layers.push(ShortCircuit);
+        let client = layers.wrap(RpcClient::new(EchoClient));
+
+        let response = client.request_raw("system_name", None).await.unwrap();
+        assert_eq!(response.get(), "\"cached\"");
+        let response = client.request_raw("system_chain", None).await.unwrap();
+        assert_eq!(response.get(), "[\"system_chain\",null]");
+    }
+}
@@ -0,0 +1,692 @@
+//! New JSON-RPC spec methods with legacy fallback
+//!
+//! Modern nodes expose the [new JSON-RPC spec] (`chainHead_*`, `archive_*`,
+//! `transaction_*`) and increasingly prune the legacy `state_*`, `chain_*` and
+//! `author_*` methods. Older nodes only speak the legacy API. [`SpecClient`]
+//! hides the difference:
+//!
+//! - [`RpcCapabilities`] is detected once through `rpc_methods`
+//! - each operation uses the spec method when the node advertises it (stable
+//!   `v1` before `unstable`), otherwise the legacy equivalent
+//! - a node without `rpc_methods` is treated as legacy-only
+//!
+//! | Operation | Spec method | Legacy fallback |
+//! |-----------|-------------|-----------------|
+//! | [`storage`](SpecClient::storage) | `archive_v1_storage` | `state_getStorage` |
+//! | [`storage_keys`](SpecClient::storage_keys) | — | `state_getKeysPaged` |
+//! | [`child_storage`](SpecClient::child_storage) | — | `childstate_getStorage` |
+//! | [`child_storage_keys`](SpecClient::child_storage_keys) | — | `childstate_getKeysPaged` |
+//! | [`block_hash`](SpecClient::block_hash) | `archive_v1_hashByHeight` | `chain_getBlockHash` |
+//! | [`finalized_number`](SpecClient::finalized_number) | `archive_v1_finalizedHeight` | `chain_getFinalizedHead` |
+//! | [`broadcast`](SpecClient::broadcast) | `transaction_v1_broadcast` | `author_submitExtrinsic` |
+//! | [`follow_finalized`](SpecClient::follow_finalized) | `chainHead_v1_follow` | `chain_subscribeFinalizedHeads` |
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::SubstrateAdapter;
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let spec = adapter.spec_client();
+//! println!("chainHead: {}", spec.capabilities().chain_head().is_some());
+//!
+//! let mut finalized = spec.follow_finalized().await?;
+//! while let Some(hash) = finalized.next().await {
+//!     println!("finalized {:?}", hash?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [new JSON-RPC spec]: https://paritytech.github.io/json-rpc-interface-spec/
+
+use crate::event_query::{block_hash_at, hex_bytes};
+use crate::{Error, Result};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::collections::{BTreeSet, VecDeque};
+use subxt::backend::rpc::RpcClient;
+use subxt::ext::subxt_rpcs::client::{RpcParams, RpcSubscription};
+use subxt::utils::H256;
+use tracing::{debug, warn};
+
+/// Method prefixes of the `archive` group, preferred first
+const ARCHIVE_PREFIXES: [&str; 2] = ["archive_v1_", "archive_unstable_"];
+
+/// Method prefixes of the `chainHead` group, preferred first
+const CHAIN_HEAD_PREFIXES: [&str; 2] = ["chainHead_v1_", "chainHead_unstable_"];
+
+/// Method prefixes of the `transaction` group, preferred first
+const TRANSACTION_PREFIXES: [&str; 2] = ["transaction_v1_", "transaction_unstable_"];
+
+/// RPC methods advertised by a node through `rpc_methods`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RpcCapabilities {
+    methods: BTreeSet<String>,
+}
+
+#[derive(Deserialize)]
+struct RpcMethodsResponse {
+    methods: Vec<String>,
+}
+
+impl RpcCapabilities {
+    /// Capabilities from a list of method names
+    pub fn new<I, S>(methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            methods: methods.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Query `rpc_methods`; nodes without it are treated as legacy-only
+    pub async fn detect(rpc: &RpcClient) -> Self {
+        match rpc
+            .request::<RpcMethodsResponse>("rpc_methods", RpcParams::new())
+            .await
+        {
+            Ok(response) => {
+                let capabilities = Self::new(response.methods);
+                debug!(
+                    "Node supports archive: {:?}, chainHead: {:?}, transaction: {:?}",
+                    capabilities.archive(),
+                    capabilities.chain_head(),
+                    capabilities.transaction()
+                );
+                capabilities
+            }
+            Err(e) => {
+                warn!("rpc_methods unavailable, using legacy RPC: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Check whether the node advertises `method`
+    pub fn supports(&self, method: &str) -> bool {
+        self.methods.contains(method)
+    }
+
+    /// All advertised methods
+    pub fn methods(&self) -> impl Iterator<Item = &str> {
+        self.methods.iter().map(String::as_str)
+    }
+
+    /// Prefix of the `archive` methods the node supports, if any
+    pub fn archive(&self) -> Option<&'static str> {
+        self.group(&ARCHIVE_PREFIXES, "storage")
+    }
+
+    /// Name of the `hashByHeight` method the node supports, if any
+    ///
+    /// Some nodes expose `archive_unstable_hashByHeight` without the rest of
+    /// the archive group, so this is probed on its own.
+    pub fn hash_by_height(&self) -> Option<&'static str> {
+        ["archive_v1_hashByHeight", "archive_unstable_hashByHeight"]
+            .into_iter()
+            .find(|method| self.supports(method))
+    }
+
+    /// Prefix of the `chainHead` methods the node supports, if any
+    pub fn chain_head(&self) -> Option<&'static str> {
+        self.group(&CHAIN_HEAD_PREFIXES, "follow")
+    }
+
+    /// Prefix of the `transaction` methods the node supports, if any
+    pub fn transaction(&self) -> Option<&'static str> {
+        self.group(&TRANSACTION_PREFIXES, "broadcast")
+    }
+
+    /// First prefix whose `probe` method is advertised
+    fn group(&self, prefixes: &[&'static str], probe: &str) -> Option<&'static str> {
+        prefixes
+            .iter()
+            .copied()
+            .find(|prefix| self.supports(&format!("{}{}", prefix, probe)))
+    }
+}
+
+/// A submitted extrinsic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Broadcast {
+    /// Broadcast through `transaction_*_broadcast`; the node keeps
+    /// re-broadcasting until stopped
+    Operation(String),
+    /// Submitted through `author_submitExtrinsic`
+    Submitted(H256),
+}
+
+/// Result of an `archive_*_storage` call
+#[derive(Debug, Deserialize)]
+struct ArchiveStorageResult {
+    items: Vec<ArchiveStorageItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArchiveStorageItem {
+    key: String,
+    value: Option<String>,
+}
+
+/// Event of a `chainHead_*_follow` subscription
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+enum FollowEvent {
+    #[serde(rename_all = "camelCase")]
+    Initialized {
+        #[serde(default)]
+        finalized_block_hashes: Vec<H256>,
+        /// Pre-v1 nodes report a single hash
+        #[serde(default)]
+        finalized_block_hash: Option<H256>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Finalized {
+        finalized_block_hashes: Vec<H256>,
+        pruned_block_hashes: Vec<H256>,
+    },
+    Stop,
+    #[serde(other)]
+    Other,
+}
+
+/// Legacy finalized header; only the number is needed
+#[derive(Debug, Deserialize)]
+struct LegacyHeader {
+    number: String,
+}
+
+/// Decode a hex block number as reported in legacy headers
+fn parse_block_number(number: &str) -> Result<u64> {
+    Ok(apex_sdk_core::parse_block_number(number)?)
+}
+
+/// Prefixed storage key of a default child trie
+pub fn default_child_key(trie_id: &[u8]) -> Vec<u8> {
+    [b":child_storage:default:".as_slice(), trie_id].concat()
+}
+
+fn params(values: Vec<JsonValue>) -> Result<RpcParams> {
+    let mut params = RpcParams::new();
+    for value in values {
+        params
+            .push(value)
+            .map_err(|e| Error::Encoding(format!("Failed to encode RPC params: {}", e)))?;
+    }
+    Ok(params)
+}
+
+/// RPC client choosing between spec and legacy methods
+#[derive(Clone)]
+pub struct SpecClient {
+    rpc: RpcClient,
+    capabilities: RpcCapabilities,
+}
+
+impl SpecClient {
+    /// Create a client for a node with known capabilities
+    pub fn new(rpc: RpcClient, capabilities: RpcCapabilities) -> Self {
+        Self { rpc, capabilities }
+    }
+
+    /// Detect the node's capabilities and create a client
+    pub async fn detect(rpc: RpcClient) -> Self {
+        let capabilities = RpcCapabilities::detect(&rpc).await;
+        Self::new(rpc, capabilities)
+    }
+
+    /// Capabilities the client routes by
+    pub fn capabilities(&self) -> &RpcCapabilities {
+        &self.capabilities
+    }
+
+    /// Underlying RPC client
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    /// Read a raw storage value at block `at`
+    pub async fn storage(&self, key: &[u8], at: H256) -> Result<Option<Vec<u8>>> {
+        let key = format!("0x{}", hex::encode(key));
+
+        if let Some(prefix) = self.capabilities.archive() {
+            let params = params(vec![
+                json!(at),
+                json!([{ "key": key, "type": "value" }]),
+                JsonValue::Null,
+            ])?;
+            let result: ArchiveStorageResult = self
+                .rpc
+                .request(&format!("{}storage", prefix), params)
+                .await
+                .map_err(|e| Error::Storage(format!("archive storage query failed: {}", e)))?;
+            return result
+                .items
+                .into_iter()
+                .find(|item| item.key.eq_ignore_ascii_case(&key))
+                .and_then(|item| item.value)
+                .map(|value| hex_bytes(&value, "hex in RPC response"))
+                .transpose();
+        }
+
+        let value: Option<String> = self
+            .rpc
+            .request("state_getStorage", params(vec![json!(key), json!(at)])?)
+            .await
+            .map_err(|e| Error::Storage(format!("state_getStorage failed: {}", e)))?;
+        value
+            .map(|value| hex_bytes(&value, "hex in RPC response"))
+            .transpose()
+    }
+
+    /// One page of storage keys under `prefix` at block `at`
+    ///
+    /// Keys are returned in order, starting after `start_key` if given; an
+    /// empty or short page means the prefix is exhausted.
+    pub async fn storage_keys(
+        &self,
+        prefix: &[u8],
+        count: u32,
+        start_key: Option<&[u8]>,
+        at: H256,
+    ) -> Result<Vec<Vec<u8>>> {
+        let keys: Vec<String> = self
+            .rpc
+            .request(
+                "state_getKeysPaged",
+                params(vec![
+                    json!(format!("0x{}", hex::encode(prefix))),
+                    json!(count),
+                    json!(start_key.map(|key| format!("0x{}", hex::encode(key)))),
+                    json!(at),
+                ])?,
+            )
+            .await
+            .map_err(|e| Error::Storage(format!("state_getKeysPaged failed: {}", e)))?;
+        keys.iter()
+            .map(|key| hex_bytes(key, "hex in RPC response"))
+            .collect()
+    }
+
+    /// Read a raw value from a child trie at block `at`
+    ///
+    /// `child_key` is the prefixed key of the trie, e.g. from
+    /// [`default_child_key`].
+    pub async fn child_storage(
+        &self,
+        child_key: &[u8],
+        key: &[u8],
+        at: H256,
+    ) -> Result<Option<Vec<u8>>> {
+        let value: Option<String> = self
+            .rpc
+            .request(
+                "childstate_getStorage",
+                params(vec![
+                    json!(format!("0x{}", hex::encode(child_key))),
+                    json!(format!("0x{}", hex::encode(key))),
+                    json!(at),
+                ])?,
+            )
+            .await
+            .map_err(|e| Error::Storage(format!("childstate_getStorage failed: {}", e)))?;
+        value
+            .map(|value| hex_bytes(&value, "hex in RPC response"))
+            .transpose()
+    }
+
+    /// One page of keys under `prefix` in a child trie at block `at`
+    pub async fn child_storage_keys(
+        &self,
+        child_key: &[u8],
+        prefix: &[u8],
+        count: u32,
+        start_key: Option<&[u8]>,
+        at: H256,
+    ) -> Result<Vec<Vec<u8>>> {
+        let keys: Vec<String> = self
+            .rpc
+            .request(
+                "childstate_getKeysPaged",
+                params(vec![
+                    json!(format!("0x{}", hex::encode(child_key))),
+                    json!(format!("0x{}", hex::encode(prefix))),
+                    json!(count),
+                    json!(start_key.map(|key| format!("0x{}", hex::encode(key)))),
+                    json!(at),
+                ])?,
+            )
+            .await
+            .map_err(|e| Error::Storage(format!("childstate_getKeysPaged failed: {}", e)))?;
+        keys.iter()
+            .map(|key| hex_bytes(key, "hex in RPC response"))
+            .collect()
+    }
+
+    /// Hash of the canonical block at `number`
+    ///
+    /// A single request on nodes with `hashByHeight`. Heights above the
+    /// finalized head can hold several fork blocks; those are resolved through
+    /// `chain_getBlockHash`, which follows the best chain.
+    pub async fn block_hash(&self, number: u64) -> Result<Option<H256>> {
+        if let Some(method) = self.capabilities.hash_by_height() {
+            let hashes: Vec<H256> = self
+                .rpc
+                .request(method, params(vec![json!(number)])?)
+                .await
+                .map_err(|e| {
+                    Error::Connection(format!("Failed to get hash of block {}: {}", number, e))
+                })?;
+            if hashes.len() <= 1 {
+                return Ok(hashes.first().copied());
+            }
+        }
+
+        self.rpc
+            .request("chain_getBlockHash", params(vec![json!(number)])?)
+            .await
+            .map_err(|e| {
+                Error::Connection(format!("Failed to get hash of block {}: {}", number, e))
+            })
+    }
+
+    /// Number of the latest finalized block
+    pub async fn finalized_number(&self) -> Result<u64> {
+        if let Some(prefix) = self.capabilities.archive() {
+            return self
+                .rpc
+                .request(&format!("{}finalizedHeight", prefix), RpcParams::new())
+                .await
+                .map_err(|e| Error::Connection(format!("Failed to get finalized height: {}", e)));
+        }
+
+        let hash: H256 = self
+            .rpc
+            .request("chain_getFinalizedHead", RpcParams::new())
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get finalized head: {}", e)))?;
+        let header: LegacyHeader = self
+            .rpc
+            .request("chain_getHeader", params(vec![json!(hash)])?)
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get finalized header: {}", e)))?;
+        parse_block_number(&header.number)
+    }
+
+    /// Number of the best block, finalized or not
+    pub async fn best_number(&self) -> Result<u64> {
+        let header: LegacyHeader = self
+            .rpc
+            .request("chain_getHeader", RpcParams::new())
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get best header: {}", e)))?;
+        parse_block_number(&header.number)
+    }
+
+    /// Submit a signed extrinsic
+    pub async fn broadcast(&self, extrinsic: &[u8]) -> Result<Broadcast> {
+        let extrinsic = format!("0x{}", hex::encode(extrinsic));
+
+        if let Some(prefix) = self.capabilities.transaction() {
+            let operation: Option<String> = self
+                .rpc
+                .request(
+                    &format!("{}broadcast", prefix),
+                    params(vec![json!(extrinsic)])?,
+                )
+                .await
+                .map_err(|e| Error::Transaction(format!("Broadcast failed: {}", e)))?;
+            return operation.map(Broadcast::Operation).ok_or_else(|| {
+                Error::Transaction("Node refused the broadcast: too many operations".to_string())
+            });
+        }
+
+        let hash: H256 = self
+            .rpc
+            .request("author_submitExtrinsic", params(vec![json!(extrinsic)])?)
+            .await
+            .map_err(|e| Error::Transaction(format!("Submission failed: {}", e)))?;
+        Ok(Broadcast::Submitted(hash))
+    }
+
+    /// Stop re-broadcasting; a no-op for legacy submissions
+    pub async fn stop_broadcast(&self, broadcast: &Broadcast) -> Result<()> {
+        let (Broadcast::Operation(operation), Some(prefix)) =
+            (broadcast, self.capabilities.transaction())
+        else {
+            return Ok(());
+        };
+        self.rpc
+            .request::<JsonValue>(&format!("{}stop", prefix), params(vec![json!(operation)])?)
+            .await
+            .map_err(|e| Error::Transaction(format!("Failed to stop broadcast: {}", e)))?;
+        Ok(())
+    }
+
+    /// Subscribe to finalized block hashes
+    pub async fn follow_finalized(&self) -> Result<FinalizedHeads> {
+        if let Some(prefix) = self.capabilities.chain_head() {
+            let subscription = self
+                .rpc
+                .subscribe(
+                    &format!("{}follow", prefix),
+                    params(vec![json!(false)])?,
+                    &format!("{}unfollow", prefix),
+                )
+                .await
+                .map_err(|e| Error::Connection(format!("chainHead follow failed: {}", e)))?;
+            return Ok(FinalizedHeads {
+                rpc: self.rpc.clone(),
+                source: HeadSource::ChainHead {
+                    subscription,
+                    prefix,
+                },
+                pending: VecDeque::new(),
+            });
+        }
+
+        let subscription = self
+            .rpc
+            .subscribe(
+                "chain_subscribeFinalizedHeads",
+                RpcParams::new(),
+                "chain_unsubscribeFinalizedHeads",
+            )
+            .await
+            .map_err(|e| {
+                Error::Connection(format!("Finalized heads subscription failed: {}", e))
+            })?;
+        Ok(FinalizedHeads {
+            rpc: self.rpc.clone(),
+            source: HeadSource::Legacy(subscription),
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+enum HeadSource {
+    ChainHead {
+        subscription: RpcSubscription<FollowEvent>,
+        prefix: &'static str,
+    },
+    Legacy(RpcSubscription<LegacyHeader>),
+}
+
+/// Stream of finalized block hashes from [`SpecClient::follow_finalized`]
+///
+/// With `chainHead`, blocks are unpinned as soon as they are reported. The
+/// stream ends when the node stops the subscription; follow again to resume.
+pub struct FinalizedHeads {
+    rpc: RpcClient,
+    source: HeadSource,
+    pending: VecDeque<H256>,
+}
+
+impl FinalizedHeads {
+    /// Next finalized block hash, in chain order
+    pub async fn next(&mut self) -> Option<Result<H256>> {
+        loop {
+            if let Some(hash) = self.pending.pop_front() {
+                return Some(Ok(hash));
+            }
+
+            match &mut self.source {
+                HeadSource::Legacy(subscription) => {
+                    let header = match subscription.next().await? {
+                        Ok(header) => header,
+                        Err(e) => {
+                            return Some(Err(Error::Connection(format!(
+                                "Finalized heads subscription failed: {}",
+                                e
+                            ))))
+                        }
+                    };
+                    let number = match parse_block_number(&header.number) {
+                        Ok(number) => number,
+                        Err(e) => return Some(Err(e)),
+                    };
+                    return Some(block_hash_at(&self.rpc, number).await);
+                }
+                HeadSource::ChainHead {
+                    subscription,
+                    prefix,
+                } => {
+                    let event = match subscription.next().await? {
+                        Ok(event) => event,
+                        Err(e) => {
+                            return Some(Err(Error::Connection(format!(
+                                "chainHead subscription failed: {}",
+                                e
+                            ))))
+                        }
+                    };
+                    let (report, release) = finalized_hashes(event)?;
+                    if !release.is_empty() {
+                        let follow_id: Option<String> =
+                            subscription.subscription_id().map(|id| id.to_owned());
+                        if let Some(follow_id) = follow_id {
+                            unpin(&self.rpc, prefix, &follow_id, &release).await;
+                        }
+                    }
+                    self.pending.extend(report);
+                }
+            }
+        }
+    }
+}
+
+/// Hashes to report and hashes to unpin for a follow event
+///
+/// Returns `None` when the node stopped the subscription.
+fn finalized_hashes(event: FollowEvent) -> Option<(Vec<H256>, Vec<H256>)> {
+    match event {
+        FollowEvent::Initialized {
+            finalized_block_hashes,
+            finalized_block_hash,
+        } => {
+            let mut release = finalized_block_hashes;
+            release.extend(finalized_block_hash);
+            let report = release.last().copied().into_iter().collect();
+            Some((report, release))
+        }
+        FollowEvent::Finalized {
+            finalized_block_hashes,
+            pruned_block_hashes,
+        } => {
+            let mut release = finalized_block_hashes.clone();
+            release.extend(pruned_block_hashes);
+            Some((finalized_block_hashes, release))
+        }
+        FollowEvent::Stop => None,
+        FollowEvent::Other => Some((Vec::new(), Vec::new())),
+    }
+}
+
+async fn unpin(rpc: &RpcClient, prefix: &str, follow_id: &str, hashes: &[H256]) {
+    let result = async {
+        let params = params(vec![json!(follow_id), json!(hashes)])?;
+        rpc.request::<JsonValue>(&format!("{}unpin", prefix), params)
+            .await
+            .map_err(|e| Error::Connection(e.to_string()))
+    }
+    .await;
+    if let Err(e) = result {
+        warn!("Failed to unpin {} blocks: {}", hashes.len(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(byte: u8) -> H256 {
+        H256::from([byte; 32])
+    }
+
+    #[test]
+    fn test_capability_groups_prefer_stable() {
+        let capabilities = RpcCapabilities::new([
+            "chainHead_unstable_follow",
+            "chainHead_v1_follow",
+            "archive_unstable_storage",
+            "archive_unstable_hashByHeight",
+            "state_getStorage",
+        ]);
+
+        assert_eq!(capabilities.chain_head(), Some("chainHead_v1_"));
+        assert_eq!(capabilities.archive(), Some("archive_unstable_"));
+        assert_eq!(capabilities.transaction(), None);
+        assert_eq!(
+            capabilities.hash_by_height(),
+            Some("archive_unstable_hashByHeight")
+        );
+        assert!(capabilities.supports("state_getStorage"));
+
+        let legacy = RpcCapabilities::default();
+        assert_eq!(legacy.chain_head(), None);
+        assert_eq!(legacy.archive(), None);
+        assert_eq!(legacy.hash_by_height(), None);
+    }
+
+    #[test]
+    fn test_follow_events() {
+        let hex = |byte: u8| format!("0x{}", hex::encode([byte; 32]));
+
+        let initialized: FollowEvent = serde_json::from_value(serde_json::json!({
+            "event": "initialized",
+            "finalizedBlockHashes": [hex(1), hex(2)]
+        }))
+        .unwrap();
+        assert_eq!(
+            finalized_hashes(initialized),
+            Some((vec![hash(2)], vec![hash(1), hash(2)]))
+        );
+
+        let finalized: FollowEvent = serde_json::from_value(serde_json::json!({
+            "event": "finalized",
+            "finalizedBlockHashes": [hex(3)],
+            "prunedBlockHashes": [hex(4)]
+        }))
+        .unwrap();
+        assert_eq!(
+            finalized_hashes(finalized),
+            Some((vec![hash(3)], vec![hash(3), hash(4)]))
+        );
+
+        let other: FollowEvent = serde_json::from_value(serde_json::json!({
+            "event": "newBlock",
+            "blockHash": hex(5),
+            "parentBlockHash": hex(4)
+        }))
+        .unwrap();
+        assert_eq!(other, FollowEvent::Other);
+        assert_eq!(finalized_hashes(FollowEvent::Stop), None);
+    }
+
+    #[test]
+    fn test_parse_block_number() {
+        assert_eq!(parse_block_number("0x1a").unwrap(), 26);
+        assert!(parse_block_number("0xzz").is_err());
+    }
+}
@@ -0,0 +1,645 @@
+//! If-this-then-transaction rules
+//!
+//! A [`TriggerRule`] pairs a chain [`Condition`] with a [`TxTemplate`]. The
+//! [`RulesEngine`] checks every finalized block: event conditions fire once
+//! per matching event, storage and price conditions fire when their predicate
+//! becomes true. Template placeholders can be bound to values of what fired
+//! the rule, such as an event field, which is what liquidation bots and
+//! auto-compounders need:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{
+//!     Comparison, Condition, Predicate, RulesEngine, SubstrateAdapter, TriggerRule, TxTemplate,
+//!     Wallet,
+//! };
+//! use serde_json::json;
+//!
+//! # async fn example(adapter: &SubstrateAdapter, wallet: Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! // restake every reward paid to the stash
+//! let stash = "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY";
+//! let bond_extra = TxTemplate::new("bond-extra", "Staking", "bond_extra")
+//!     .with_arg("max_additional", json!("{{amount}}"));
+//! let rule = TriggerRule::new(
+//!     "restake",
+//!     Condition::event("Staking", "Rewarded")
+//!         .with_predicate(Predicate::new("/stash", Comparison::Eq, json!(stash))),
+//!     bond_extra,
+//! )
+//! .bind("amount", "/fields/amount");
+//!
+//! RulesEngine::new(adapter, wallet)
+//!     .with_rule(rule)
+//!     .on_fire(|report: &apex_sdk_substrate::RunReport| println!("{:?}", report))
+//!     .run()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Predicates address values by JSON pointer: into the event fields for
+//! event conditions, into the decoded value for storage conditions. Bindings
+//! point into the trigger context: the [`MatchedEvent`] for events,
+//! `{"value": ...}` for storage and `{"price": ...}` for prices.
+
+use crate::event_query::{account_hex, account_id, MatchedEvent};
+use crate::rpc_spec::SpecClient;
+use crate::scheduler::{RunHandler, RunOutcome, RunReport};
+use crate::template::TxTemplate;
+use crate::{Error, EventQuery, Result, SubstrateAdapter, Wallet};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use subxt::dynamic::Value;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Default time between checks for new finalized blocks
+pub const DEFAULT_RULES_POLL_INTERVAL: Duration = Duration::from_secs(6);
+
+/// How a predicate compares a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    /// Equal; addresses match in SS58 or hex
+    Eq,
+    /// Not equal
+    Ne,
+    /// Less than
+    Lt,
+    /// Less than or equal
+    Le,
+    /// Greater than
+    Gt,
+    /// Greater than or equal
+    Ge,
+    /// String contains, or array has an equal element
+    Contains,
+}
+
+/// A test of the value at a JSON pointer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Predicate {
+    /// JSON pointer to the tested value; empty for the whole value
+    #[serde(default)]
+    pub path: String,
+    /// Comparison applied
+    pub op: Comparison,
+    /// Value compared against
+    pub value: JsonValue,
+}
+
+impl Predicate {
+    /// Compare the value at `path` with `value`
+    pub fn new(path: impl Into<String>, op: Comparison, value: JsonValue) -> Self {
+        Self {
+            path: path.into(),
+            op,
+            value,
+        }
+    }
+
+    /// Whether `subject` satisfies the predicate; false if `path` is absent
+    ///
+    /// Numbers and numeric strings compare as numbers, so large balances
+    /// rendered as strings compare correctly.
+    pub fn matches(&self, subject: &JsonValue) -> bool {
+        let Some(actual) = subject.pointer(&self.path) else {
+            return false;
+        };
+        match self.op {
+            Comparison::Eq => equal(actual, &self.value),
+            Comparison::Ne => !equal(actual, &self.value),
+            Comparison::Contains => match actual {
+                JsonValue::String(s) => self.value.as_str().is_some_and(|v| s.contains(v)),
+                JsonValue::Array(items) => items.iter().any(|item| equal(item, &self.value)),
+                _ => false,
+            },
+            op => compare(actual, &self.value).is_some_and(|ordering| match op {
+                Comparison::Lt => ordering == Ordering::Less,
+                Comparison::Le => ordering != Ordering::Greater,
+                Comparison::Gt => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            }),
+        }
+    }
+}
+
+fn as_integer(value: &JsonValue) -> Option<i128> {
+    match value {
+        JsonValue::Number(n) => n
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| n.as_u64().map(i128::from)),
+        JsonValue::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn as_float(value: &JsonValue) -> Option<f64> {
+    match value {
+        JsonValue::Number(n) => n.as_f64(),
+        JsonValue::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn compare(a: &JsonValue, b: &JsonValue) -> Option<Ordering> {
+    match (as_integer(a), as_integer(b)) {
+        (Some(a), Some(b)) => Some(a.cmp(&b)),
+        _ => as_float(a)?.partial_cmp(&as_float(b)?),
+    }
+}
+
+fn equal(a: &JsonValue, b: &JsonValue) -> bool {
+    if let Some(ordering) = compare(a, b) {
+        return ordering == Ordering::Equal;
+    }
+    match (a, b) {
+        (JsonValue::String(a), JsonValue::String(b)) => {
+            a.eq_ignore_ascii_case(b)
+                || [(a, b), (b, a)].into_iter().any(|(hex, address)| {
+                    hex.starts_with("0x")
+                        && account_hex(address).is_ok_and(|h| h.eq_ignore_ascii_case(hex))
+                })
+        }
+        _ => a == b,
+    }
+}
+
+/// What fires a rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /// An event whose fields satisfy every predicate
+    Event {
+        /// Pallet name
+        pallet: String,
+        /// Event variant name
+        variant: String,
+        /// Tests of the event fields
+        #[serde(default)]
+        predicates: Vec<Predicate>,
+    },
+    /// A storage value satisfying a predicate
+    Storage {
+        /// Pallet name
+        pallet: String,
+        /// Storage item name
+        item: String,
+        /// Map keys; addresses, hex byte strings, numbers or strings
+        #[serde(default)]
+        keys: Vec<JsonValue>,
+        /// Test of the decoded value
+        predicate: Predicate,
+    },
+    /// An oracle price crossing a threshold
+    Price {
+        /// Symbol passed to the [`PriceOracle`]
+        symbol: String,
+        /// Comparison with the threshold
+        op: Comparison,
+        /// Threshold
+        threshold: f64,
+    },
+}
+
+impl Condition {
+    /// Condition on `pallet::variant` events
+    pub fn event(pallet: impl Into<String>, variant: impl Into<String>) -> Self {
+        Condition::Event {
+            pallet: pallet.into(),
+            variant: variant.into(),
+            predicates: Vec::new(),
+        }
+    }
+
+    /// Condition on a storage value
+    pub fn storage(
+        pallet: impl Into<String>,
+        item: impl Into<String>,
+        keys: Vec<JsonValue>,
+        predicate: Predicate,
+    ) -> Self {
+        Condition::Storage {
+            pallet: pallet.into(),
+            item: item.into(),
+            keys,
+            predicate,
+        }
+    }
+
+    /// Condition on an oracle price
+    pub fn price(symbol: impl Into<String>, op: Comparison, threshold: f64) -> Self {
+        Condition::Price {
+            symbol: symbol.into(),
+            op,
+            threshold,
+        }
+    }
+
+    /// Add a test of the event fields; no effect on other conditions
+    pub fn with_predicate(mut self, predicate: Predicate) -> Self {
+        if let Condition::Event { predicates, .. } = &mut self {
+            predicates.push(predicate);
+        }
+        self
+    }
+
+    /// Whether `event` fires an event condition
+    pub fn matches_event(&self, event: &MatchedEvent) -> bool {
+        match self {
+            Condition::Event {
+                pallet,
+                variant,
+                predicates,
+            } => {
+                event.pallet == *pallet
+                    && event.variant == *variant
+                    && predicates.iter().all(|p| p.matches(&event.fields))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Source of asset prices for [`Condition::Price`]
+#[async_trait]
+pub trait PriceOracle: Send + Sync {
+    /// Current price of `symbol`
+    async fn price(&self, symbol: &str) -> Result<f64>;
+}
+
+/// A condition and the transaction it triggers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerRule {
+    /// Unique rule id
+    pub id: String,
+    /// What fires the rule
+    pub condition: Condition,
+    /// Transaction to submit
+    pub template: TxTemplate,
+    /// Fixed template parameters
+    #[serde(default)]
+    pub params: Map<String, JsonValue>,
+    /// Template parameters taken from the trigger context, by JSON pointer
+    #[serde(default)]
+    pub bindings: BTreeMap<String, String>,
+}
+
+impl TriggerRule {
+    /// Rule submitting `template` when `condition` fires
+    pub fn new(id: impl Into<String>, condition: Condition, template: TxTemplate) -> Self {
+        Self {
+            id: id.into(),
+            condition,
+            template,
+            params: Map::new(),
+            bindings: BTreeMap::new(),
+        }
+    }
+
+    /// Set a fixed template parameter
+    pub fn with_param(mut self, name: impl Into<String>, value: JsonValue) -> Self {
+        self.params.insert(name.into(), value);
+        self
+    }
+
+    /// Fill a template parameter from the trigger context at `pointer`
+    pub fn bind(mut self, name: impl Into<String>, pointer: impl Into<String>) -> Self {
+        self.bindings.insert(name.into(), pointer.into());
+        self
+    }
+
+    /// Template parameters for a firing with `context`
+    pub fn params_for(&self, context: &JsonValue) -> Result<JsonValue> {
+        let mut params = self.params.clone();
+        for (name, pointer) in &self.bindings {
+            let value = context.pointer(pointer).ok_or_else(|| {
+                Error::Other(format!(
+                    "Rule {}: trigger has no value at {}",
+                    self.id, pointer
+                ))
+            })?;
+            params.insert(name.clone(), value.clone());
+        }
+        Ok(JsonValue::Object(params))
+    }
+}
+
+/// Storage map key as a dynamic value
+///
+/// Addresses become account ids, `0x` strings bytes, and numbers or numeric
+/// strings integers; the runtime's key type decides the final encoding.
+fn key_value(key: &JsonValue) -> Result<Value> {
+    Ok(match key {
+        JsonValue::String(s) if s.starts_with("0x") => Value::from_bytes(
+            hex::decode(&s[2..])
+                .map_err(|e| Error::Encoding(format!("Invalid key {}: {}", s, e)))?,
+        ),
+        JsonValue::String(s) => match (s.parse::<u128>(), account_id(s)) {
+            (Ok(n), _) => Value::u128(n),
+            (_, Ok(account)) => Value::from_bytes(account),
+            _ => Value::string(s.clone()),
+        },
+        JsonValue::Number(n) => Value::u128(
+            n.as_u64()
+                .ok_or_else(|| Error::Encoding(format!("Invalid key {}", n)))?
+                .into(),
+        ),
+        JsonValue::Bool(b) => Value::bool(*b),
+        JsonValue::Array(items) => {
+            Value::unnamed_composite(items.iter().map(key_value).collect::<Result<Vec<_>>>()?)
+        }
+        other => return Err(Error::Encoding(format!("Unsupported key {}", other))),
+    })
+}
+
+/// Watches finalized blocks and submits the transactions of rules that fire
+pub struct RulesEngine {
+    adapter: SubstrateAdapter,
+    spec: SpecClient,
+    wallet: Wallet,
+    rules: Vec<TriggerRule>,
+    oracle: Option<Arc<dyn PriceOracle>>,
+    handlers: Vec<Arc<dyn RunHandler>>,
+    poll_interval: Duration,
+    dry_run: bool,
+    /// Last value of each level condition, by rule id
+    levels: Mutex<BTreeMap<String, bool>>,
+}
+
+impl RulesEngine {
+    /// Engine signing with `wallet` through the adapter's executor
+    ///
+    /// The adapter's policy, screening and audit log apply to every
+    /// transaction.
+    pub fn new(adapter: &SubstrateAdapter, wallet: Wallet) -> Self {
+        Self {
+            adapter: adapter.clone(),
+            spec: adapter.spec_client(),
+            wallet,
+            rules: Vec::new(),
+            oracle: None,
+            handlers: Vec::new(),
+            poll_interval: DEFAULT_RULES_POLL_INTERVAL,
+            dry_run: false,
+            levels: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Add a rule
+    pub fn with_rule(mut self, rule: TriggerRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Price source for price conditions
+    pub fn with_price_oracle(mut self, oracle: impl PriceOracle + 'static) -> Self {
+        self.oracle = Some(Arc::new(oracle));
+        self
+    }
+
+    /// Time between checks for new finalized blocks
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Report firings with their call data instead of submitting them
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Register a handler for rule firings
+    pub fn on_fire(mut self, handler: impl RunHandler + 'static) -> Self {
+        self.handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Process finalized blocks as they arrive, until the heads cannot be read
+    pub async fn run(&self) -> Result<()> {
+        info!("Watching {} rules", self.rules.len());
+        let mut next = self.spec.finalized_number().await? + 1;
+        let mut interval = tokio::time::interval(self.poll_interval);
+        loop {
+            interval.tick().await;
+            let finalized = self.spec.finalized_number().await?;
+            while next <= finalized {
+                for report in self.process_block(next).await? {
+                    for handler in &self.handlers {
+                        handler.on_run(&report).await;
+                    }
+                }
+                next += 1;
+            }
+        }
+    }
+
+    /// Check every rule against block `number` and make the firings
+    ///
+    /// A firing's failure is reported, not returned. Storage and price
+    /// conditions are read at the latest block.
+    pub async fn process_block(&self, number: u64) -> Result<Vec<RunReport>> {
+        let has_event_rules = self
+            .rules
+            .iter()
+            .any(|rule| matches!(rule.condition, Condition::Event { .. }));
+        let events = if has_event_rules {
+            EventQuery::new()
+                .between(number, number)
+                .run(&self.adapter)
+                .await?
+        } else {
+            Vec::new()
+        };
+
+        let mut reports = Vec::new();
+        for rule in &self.rules {
+            let contexts = match self.fired(rule, &events).await {
+                Ok(contexts) => contexts,
+                Err(e) => {
+                    warn!("Failed to check rule {}: {}", rule.id, e);
+                    continue;
+                }
+            };
+            for context in contexts {
+                reports.push(self.fire(rule, &context).await);
+            }
+        }
+        Ok(reports)
+    }
+
+    /// Trigger contexts of the firings of `rule`
+    async fn fired(&self, rule: &TriggerRule, events: &[MatchedEvent]) -> Result<Vec<JsonValue>> {
+        let (holds, context) = match &rule.condition {
+            Condition::Event { .. } => {
+                return events
+                    .iter()
+                    .filter(|event| rule.condition.matches_event(event))
+                    .map(|event| {
+                        serde_json::to_value(event).map_err(|e| Error::Encoding(e.to_string()))
+                    })
+                    .collect();
+            }
+            Condition::Storage {
+                pallet,
+                item,
+                keys,
+                predicate,
+            } => {
+                let keys = keys.iter().map(key_value).collect::<Result<Vec<_>>>()?;
+                let value = self
+                    .adapter
+                    .storage()
+                    .query_storage_json(pallet, item, keys)
+                    .await?
+                    .unwrap_or(JsonValue::Null);
+                (
+                    predicate.matches(&value),
+                    serde_json::json!({ "value": value }),
+                )
+            }
+            Condition::Price {
+                symbol,
+                op,
+                threshold,
+            } => {
+                let oracle = self.oracle.as_ref().ok_or_else(|| {
+                    Error::Other(format!("Rule {} needs a price oracle", rule.id))
+                })?;
+                let price = oracle.price(symbol).await?;
+                let predicate = Predicate::new("", *op, serde_json::json!(threshold));
+                (
+                    predicate.matches(&serde_json::json!(price)),
+                    serde_json::json!({ "price": price }),
+                )
+            }
+        };
+
+        // level conditions fire when they start to hold
+        let previous = self.levels.lock().await.insert(rule.id.clone(), holds);
+        Ok(if holds && previous != Some(true) {
+            vec![context]
+        } else {
+            Vec::new()
+        })
+    }
+
+    async fn fire(&self, rule: &TriggerRule, context: &JsonValue) -> RunReport {
+        let outcome = match self.submit(rule, context).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                warn!("Rule {} failed: {}", rule.id, e);
+                RunOutcome::Failed {
+                    error: e.to_string(),
+                }
+            }
+        };
+        RunReport {
+            job_id: rule.id.clone(),
+            scheduled_ms: None,
+            outcome,
+        }
+    }
+
+    async fn submit(&self, rule: &TriggerRule, context: &JsonValue) -> Result<RunOutcome> {
+        let params = rule.params_for(context)?;
+        if self.dry_run {
+            let call_data = rule.template.call_data(&params, self.adapter.client())?;
+            return Ok(RunOutcome::DryRun {
+                call_data: format!("0x{}", hex::encode(call_data)),
+            });
+        }
+        info!("Rule {} fired", rule.id);
+        let tx_hash = self
+            .adapter
+            .transaction_executor()
+            .submit_template(&self.wallet, &rule.template, &params)
+            .await?;
+        Ok(RunOutcome::Submitted { tx_hash })
+    }
+}
+
+impl std::fmt::Debug for RulesEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RulesEngine")
+            .field("wallet", &self.wallet.address())
+            .field("rules", &self.rules.len())
+            .field("poll_interval", &self.poll_interval)
+            .field("dry_run", &self.dry_run)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ALICE_HEX: &str = "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+
+    #[test]
+    fn test_predicates() {
+        let position = json!({
+            "who": ALICE_HEX,
+            "debt": "340282366920938463463374607431768211455",
+            "ratio": 1.05,
+            "tags": ["margin", "isolated"],
+        });
+        let matches = |path: &str, op, value| Predicate::new(path, op, value).matches(&position);
+
+        assert!(matches(
+            "/who",
+            Comparison::Eq,
+            json!("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")
+        ));
+        assert!(matches("/debt", Comparison::Gt, json!(u64::MAX)));
+        assert!(matches("/ratio", Comparison::Lt, json!(1.1)));
+        assert!(!matches("/ratio", Comparison::Ge, json!("1.1")));
+        assert!(matches("/tags", Comparison::Contains, json!("margin")));
+        assert!(!matches("/missing", Comparison::Ne, json!(1)));
+    }
+
+    #[test]
+    fn test_event_rule() {
+        let rule = TriggerRule::new(
+            "liquidate",
+            Condition::event("Loans", "Unhealthy").with_predicate(Predicate::new(
+                "/ratio",
+                Comparison::Lt,
+                json!(100),
+            )),
+            TxTemplate::new("liquidate", "Loans", "liquidate")
+                .with_arg("who", json!("{{who}}"))
+                .with_arg("max_repay", json!("{{max_repay}}")),
+        )
+        .with_param("max_repay", json!("1000000"))
+        .bind("who", "/fields/who");
+
+        let mut event = MatchedEvent {
+            block_number: 7,
+            block_hash: "0x01".to_string(),
+            event_index: 0,
+            extrinsic_index: None,
+            pallet: "Loans".to_string(),
+            variant: "Unhealthy".to_string(),
+            fields: json!({ "who": ALICE_HEX, "ratio": 95 }),
+        };
+        assert!(rule.condition.matches_event(&event));
+
+        let context = serde_json::to_value(&event).unwrap();
+        let params = rule.params_for(&context).unwrap();
+        assert_eq!(params, json!({ "who": ALICE_HEX, "max_repay": "1000000" }));
+        let args = rule.template.instantiate(&params).unwrap();
+        assert_eq!(args["who"], json!(ALICE_HEX));
+
+        event.fields["ratio"] = json!(120);
+        assert!(!rule.condition.matches_event(&event));
+        assert!(rule.params_for(&json!({})).is_err());
+    }
+}
@@ -0,0 +1,549 @@
+//! Runtime upgrades for chain operators
+//!
+//! [`RuntimeUpgrade`] walks through an upgrade: it reads the runtime version
+//! embedded in the wasm blob, checks that it is the same runtime with a
+//! higher spec version than the chain's, hashes the code and submits it on
+//! the chosen [`UpgradePath`]:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{
+//!     confirm_root_action, RuntimeUpgrade, SubstrateAdapter, UpgradePath, Wallet,
+//! };
+//!
+//! # async fn example(adapter: &SubstrateAdapter, sudo_key: &Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! let upgrade = RuntimeUpgrade::new(adapter, sudo_key).with_path(UpgradePath::Sudo);
+//! let submission = upgrade
+//!     .submit(
+//!         "runtime.compact.compressed.wasm",
+//!         confirm_root_action("upgrade to the next release"),
+//!     )
+//!     .await?;
+//! let enacted = upgrade.wait_for_enactment(submission.runtime.spec_version).await?;
+//! println!("Runtime {} live at {}", enacted.spec_version, enacted.block_hash);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! On governance chains the referendum only authorizes the code hash; once
+//! it is enacted, anyone can supply the code with
+//! [`apply_authorized`](RuntimeUpgrade::apply_authorized).
+
+use crate::block_limits::Weight;
+use crate::sudo::{RootConfirmation, Sudo};
+use crate::wallet::Wallet;
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::{Compact, Decode};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+use subxt::dynamic::Value;
+use subxt::ext::subxt_rpcs::client::RpcParams;
+use tracing::info;
+
+/// Prefix of zstd-compressed runtime blobs
+const ZSTD_PREFIX: [u8; 8] = [82, 188, 83, 118, 70, 219, 142, 5];
+
+/// Largest decompressed runtime accepted, matching the node's limit
+const MAX_CODE_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Name of the wasm custom section holding the runtime version
+const VERSION_SECTION: &str = "runtime_version";
+
+/// Runtime version embedded in a wasm blob
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmRuntimeVersion {
+    /// Runtime name, e.g. `polkadot`
+    pub spec_name: String,
+    /// Name of the implementation
+    pub impl_name: String,
+    /// Version of the runtime specification
+    pub spec_version: u32,
+    /// Version of the implementation
+    pub impl_version: u32,
+    /// Version of the extrinsic format, if the runtime reports one
+    pub transaction_version: Option<u32>,
+    /// Trie layout version of the runtime's state; 0 for runtimes predating it
+    pub state_version: u8,
+}
+
+/// A runtime wasm blob ready to be submitted
+#[derive(Debug, Clone)]
+pub struct WasmRuntime {
+    code: Vec<u8>,
+    version: WasmRuntimeVersion,
+}
+
+impl WasmRuntime {
+    /// Read a runtime blob, compressed or not, from a file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let code = std::fs::read(path).map_err(|e| {
+            Error::Other(format!("Failed to read runtime {}: {}", path.display(), e))
+        })?;
+        Self::from_bytes(code)
+    }
+
+    /// Parse a runtime blob, compressed or not
+    ///
+    /// The blob is kept as given; the version is read from the decompressed
+    /// code.
+    pub fn from_bytes(code: Vec<u8>) -> Result<Self> {
+        let wasm = decompress(&code)?;
+        let section = custom_section(&wasm, VERSION_SECTION)?
+            .ok_or_else(|| Error::Encoding("Runtime has no runtime_version section".to_string()))?;
+        let version = decode_version(section)?;
+        Ok(Self { code, version })
+    }
+
+    /// Code as submitted on chain
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    /// Version the runtime reports
+    pub fn version(&self) -> &WasmRuntimeVersion {
+        &self.version
+    }
+
+    /// Blake2-256 hash of the code (hex), as authorized by `authorize_upgrade`
+    pub fn code_hash(&self) -> String {
+        format!(
+            "0x{}",
+            hex::encode(sp_core::hashing::blake2_256(&self.code))
+        )
+    }
+
+    /// Check that this runtime can replace the chain's current runtime
+    pub fn check_upgrade(&self, spec_name: &str, spec_version: u32) -> Result<()> {
+        if self.version.spec_name != spec_name {
+            return Err(Error::Transaction(format!(
+                "Runtime upgrade rejected: blob is {}, chain runs {}",
+                self.version.spec_name, spec_name
+            )));
+        }
+        if self.version.spec_version <= spec_version {
+            return Err(Error::Transaction(format!(
+                "Runtime upgrade rejected: spec version {} does not increase on {}",
+                self.version.spec_version, spec_version
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Strip the compression of a runtime blob, if any
+fn decompress(code: &[u8]) -> Result<Vec<u8>> {
+    let Some(compressed) = code.strip_prefix(&ZSTD_PREFIX) else {
+        return Ok(code.to_vec());
+    };
+    let decoder = ruzstd::decoding::StreamingDecoder::new(compressed)
+        .map_err(|e| Error::Encoding(format!("Invalid compressed runtime: {}", e)))?;
+    let mut wasm = Vec::new();
+    decoder
+        .take(MAX_CODE_SIZE + 1)
+        .read_to_end(&mut wasm)
+        .map_err(|e| Error::Encoding(format!("Failed to decompress runtime: {}", e)))?;
+    if wasm.len() as u64 > MAX_CODE_SIZE {
+        return Err(Error::Encoding(format!(
+            "Decompressed runtime exceeds {} bytes",
+            MAX_CODE_SIZE
+        )));
+    }
+    Ok(wasm)
+}
+
+/// Contents of the first custom section called `name`
+fn custom_section<'a>(wasm: &'a [u8], name: &str) -> Result<Option<&'a [u8]>> {
+    let invalid = || Error::Encoding("Invalid wasm module".to_string());
+    let mut rest = wasm.strip_prefix(b"\0asm").ok_or_else(invalid)?;
+    rest = rest.get(4..).ok_or_else(invalid)?;
+    while let Some((&id, tail)) = rest.split_first() {
+        let (size, tail) = read_leb128(tail).ok_or_else(invalid)?;
+        let section = tail.get(..size).ok_or_else(invalid)?;
+        rest = &tail[size..];
+        if id != 0 {
+            continue;
+        }
+        let (name_len, payload) = read_leb128(section).ok_or_else(invalid)?;
+        let section_name = payload.get(..name_len).ok_or_else(invalid)?;
+        if section_name == name.as_bytes() {
+            return Ok(Some(&payload[name_len..]));
+        }
+    }
+    Ok(None)
+}
+
+/// Unsigned LEB128 length, and the bytes after it
+fn read_leb128(bytes: &[u8]) -> Option<(usize, &[u8])> {
+    let mut value = 0usize;
+    for (i, byte) in bytes.iter().enumerate().take(5) {
+        value |= usize::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+/// Decode a SCALE-encoded `RuntimeVersion`
+///
+/// Older runtimes end before `transaction_version` or `state_version`.
+fn decode_version(mut input: &[u8]) -> Result<WasmRuntimeVersion> {
+    let invalid = |e: parity_scale_codec::Error| {
+        Error::Encoding(format!("Invalid runtime_version section: {}", e))
+    };
+    let spec_name = String::decode(&mut input).map_err(invalid)?;
+    let impl_name = String::decode(&mut input).map_err(invalid)?;
+    let _authoring_version = u32::decode(&mut input).map_err(invalid)?;
+    let spec_version = u32::decode(&mut input).map_err(invalid)?;
+    let impl_version = u32::decode(&mut input).map_err(invalid)?;
+    let apis = Compact::<u32>::decode(&mut input).map_err(invalid)?.0;
+    for _ in 0..apis {
+        <([u8; 8], u32)>::decode(&mut input).map_err(invalid)?;
+    }
+    let transaction_version = u32::decode(&mut input).ok();
+    let state_version = u8::decode(&mut input).unwrap_or(0);
+    Ok(WasmRuntimeVersion {
+        spec_name,
+        impl_name,
+        spec_version,
+        impl_version,
+        transaction_version,
+        state_version,
+    })
+}
+
+/// How the new code reaches the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpgradePath {
+    /// `System::set_code` through `Sudo::sudo_unchecked_weight`, enacted in
+    /// the block that includes it
+    #[default]
+    Sudo,
+    /// `System::set_code` through sudo, scheduled with
+    /// `Scheduler::schedule_after` to run after this many blocks
+    SudoScheduled {
+        /// Blocks until the upgrade runs
+        after: u32,
+    },
+    /// A referendum on the Root track authorizing the code hash with
+    /// `System::authorize_upgrade`
+    ///
+    /// The referendum still needs its decision deposit and a passing vote;
+    /// once enacted, the code is applied with
+    /// [`RuntimeUpgrade::apply_authorized`].
+    Governance {
+        /// Blocks between approval and enactment
+        enactment_delay: u32,
+    },
+}
+
+/// A submitted runtime upgrade
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpgradeSubmission {
+    /// Blake2-256 hash of the code (hex)
+    pub code_hash: String,
+    /// Version of the new runtime
+    pub runtime: WasmRuntimeVersion,
+    /// Spec version the chain ran when the upgrade was submitted
+    pub previous_spec_version: u32,
+    /// Path the upgrade was submitted on
+    pub path: UpgradePath,
+    /// Hash of the submitted extrinsic
+    pub tx_hash: String,
+}
+
+/// Runtime version the chain reports on finalizing an upgrade
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enactment {
+    /// First finalized block running the new runtime (hex)
+    pub block_hash: String,
+    /// Spec version in effect at that block
+    pub spec_version: u32,
+}
+
+/// Part of a `state_getRuntimeVersion` response
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChainVersion {
+    spec_name: String,
+    spec_version: u32,
+}
+
+/// Guided runtime upgrade
+pub struct RuntimeUpgrade<'a> {
+    adapter: &'a SubstrateAdapter,
+    signer: &'a Wallet,
+    path: UpgradePath,
+}
+
+impl<'a> RuntimeUpgrade<'a> {
+    /// Upgrade the adapter's chain, signing with `signer`
+    ///
+    /// Sudo paths need the sudo key; governance needs an account able to pay
+    /// the submission deposit.
+    pub fn new(adapter: &'a SubstrateAdapter, signer: &'a Wallet) -> Self {
+        Self {
+            adapter,
+            signer,
+            path: UpgradePath::default(),
+        }
+    }
+
+    /// Submit on this path instead of [`UpgradePath::Sudo`]
+    pub fn with_path(mut self, path: UpgradePath) -> Self {
+        self.path = path;
+        self
+    }
+
+    /// Validate the runtime in `wasm_path` and submit it
+    pub async fn submit(
+        &self,
+        wasm_path: impl AsRef<Path>,
+        confirmation: RootConfirmation,
+    ) -> Result<UpgradeSubmission> {
+        let runtime = WasmRuntime::from_file(wasm_path)?;
+        self.submit_runtime(&runtime, confirmation).await
+    }
+
+    /// Validate a parsed runtime and submit it
+    pub async fn submit_runtime(
+        &self,
+        runtime: &WasmRuntime,
+        confirmation: RootConfirmation,
+    ) -> Result<UpgradeSubmission> {
+        let current = self.chain_version(None).await?;
+        runtime.check_upgrade(&current.spec_name, current.spec_version)?;
+        let code_hash = runtime.code_hash();
+        info!(
+            "Upgrading {} from spec {} to {} ({}, {} bytes) via {:?}",
+            current.spec_name,
+            current.spec_version,
+            runtime.version.spec_version,
+            code_hash,
+            runtime.code.len(),
+            self.path
+        );
+
+        let set_code = system_call("set_code", "code", Value::from_bytes(runtime.code()));
+        let sudo = Sudo::new(self.adapter);
+        let tx_hash = match self.path {
+            UpgradePath::Sudo => {
+                sudo.sudo_unchecked_weight(self.signer, set_code, Weight::default(), confirmation)
+                    .await?
+            }
+            UpgradePath::SudoScheduled { after } => {
+                self.adapter.require_pallet("Scheduler")?;
+                let scheduled = Value::unnamed_variant(
+                    "Scheduler",
+                    [Value::named_variant(
+                        "schedule_after",
+                        [
+                            ("after", Value::u128(after.into())),
+                            ("maybe_periodic", Value::unnamed_variant("None", [])),
+                            ("priority", Value::u128(0)),
+                            ("call", set_code),
+                        ],
+                    )],
+                );
+                sudo.sudo(self.signer, scheduled, confirmation).await?
+            }
+            UpgradePath::Governance { enactment_delay } => {
+                self.propose(runtime, enactment_delay, confirmation).await?
+            }
+        };
+
+        Ok(UpgradeSubmission {
+            code_hash,
+            runtime: runtime.version.clone(),
+            previous_spec_version: current.spec_version,
+            path: self.path,
+            tx_hash,
+        })
+    }
+
+    /// Supply the code of an upgrade authorized by governance
+    ///
+    /// Any account may submit it; the runtime refunds the fee when the code
+    /// matches the authorized hash.
+    pub async fn apply_authorized(&self, runtime: &WasmRuntime) -> Result<String> {
+        self.adapter
+            .transaction_executor()
+            .submit_call(
+                self.signer,
+                "System",
+                "apply_authorized_upgrade",
+                vec![Value::from_bytes(runtime.code())],
+            )
+            .await
+    }
+
+    /// Wait until a finalized block runs `spec_version` or later
+    pub async fn wait_for_enactment(&self, spec_version: u32) -> Result<Enactment> {
+        let mut heads = self.adapter.spec_client().follow_finalized().await?;
+        while let Some(head) = heads.next().await {
+            let block_hash = format!("0x{}", hex::encode(head?.0));
+            let version = self.chain_version(Some(&block_hash)).await?;
+            if version.spec_version >= spec_version {
+                info!(
+                    "Runtime spec {} enacted at {}",
+                    version.spec_version, block_hash
+                );
+                return Ok(Enactment {
+                    block_hash,
+                    spec_version: version.spec_version,
+                });
+            }
+        }
+        Err(Error::Connection(
+            "Finalized heads subscription ended before the upgrade was enacted".to_string(),
+        ))
+    }
+
+    /// Submit a Root track referendum authorizing the runtime's code hash
+    async fn propose(
+        &self,
+        runtime: &WasmRuntime,
+        enactment_delay: u32,
+        confirmation: RootConfirmation,
+    ) -> Result<String> {
+        self.adapter.require_pallet("Referenda")?;
+        let hash = sp_core::hashing::blake2_256(runtime.code());
+        let authorize =
+            subxt::dynamic::tx("System", "authorize_upgrade", vec![Value::from_bytes(hash)]);
+        // Small enough to inline, so no preimage needs noting
+        let proposal = self
+            .adapter
+            .client()
+            .tx()
+            .call_data(&authorize)
+            .map_err(|e| Error::Encoding(format!("Failed to encode authorize_upgrade: {}", e)))?;
+
+        info!(
+            "Proposing runtime {} on the Root track: {}",
+            runtime.code_hash(),
+            confirmation.reason()
+        );
+        self.adapter
+            .transaction_executor()
+            .submit_call(
+                self.signer,
+                "Referenda",
+                "submit",
+                vec![
+                    Value::unnamed_variant("system", [Value::unnamed_variant("Root", [])]),
+                    Value::unnamed_variant("Inline", [Value::from_bytes(proposal)]),
+                    Value::unnamed_variant("After", [Value::u128(enactment_delay.into())]),
+                ],
+            )
+            .await
+    }
+
+    /// Runtime name and spec version at a block, or the best block
+    async fn chain_version(&self, at: Option<&str>) -> Result<ChainVersion> {
+        let mut params = RpcParams::new();
+        if let Some(at) = at {
+            params
+                .push(at)
+                .map_err(|e| Error::Encoding(format!("Failed to encode block hash: {}", e)))?;
+        }
+        self.adapter
+            .rpc_client()
+            .request("state_getRuntimeVersion", params)
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to read runtime version: {}", e)))
+    }
+}
+
+/// A `System` call with one named argument
+fn system_call(call: &str, arg: &str, value: Value) -> Value {
+    Value::unnamed_variant("System", [Value::named_variant(call, [(arg, value)])])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_scale_codec::Encode;
+
+    fn leb128(mut value: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte);
+                return bytes;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![id];
+        bytes.extend(leb128(contents.len()));
+        bytes.extend_from_slice(contents);
+        bytes
+    }
+
+    fn custom(name: &str, payload: &[u8]) -> Vec<u8> {
+        let mut contents = leb128(name.len());
+        contents.extend_from_slice(name.as_bytes());
+        contents.extend_from_slice(payload);
+        section(0, &contents)
+    }
+
+    fn runtime(spec_name: &str, spec_version: u32) -> Vec<u8> {
+        let mut version = (spec_name, "apex-node", 0u32, spec_version, 3u32).encode();
+        version.extend(vec![([1u8; 8], 2u32)].encode());
+        version.extend(26u32.encode());
+        version.push(1);
+
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        wasm.extend(section(1, &[0]));
+        wasm.extend(custom("sourceMappingURL", b"x"));
+        wasm.extend(custom(VERSION_SECTION, &version));
+        wasm
+    }
+
+    #[test]
+    fn test_reads_runtime_version() {
+        let runtime = WasmRuntime::from_bytes(runtime("apex", 1_002_000)).unwrap();
+        assert_eq!(
+            runtime.version(),
+            &WasmRuntimeVersion {
+                spec_name: "apex".to_string(),
+                impl_name: "apex-node".to_string(),
+                spec_version: 1_002_000,
+                impl_version: 3,
+                transaction_version: Some(26),
+                state_version: 1,
+            }
+        );
+        assert_eq!(runtime.code_hash().len(), 66);
+
+        assert!(WasmRuntime::from_bytes(b"\0asm\x01\0\0\0".to_vec()).is_err());
+        assert!(WasmRuntime::from_bytes(b"not wasm".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_reads_compressed_runtime() {
+        let wasm = runtime("apex", 7);
+        let mut code = ZSTD_PREFIX.to_vec();
+        code.extend(ruzstd::encoding::compress_to_vec(
+            wasm.as_slice(),
+            ruzstd::encoding::CompressionLevel::Fastest,
+        ));
+        let runtime = WasmRuntime::from_bytes(code.clone()).unwrap();
+        assert_eq!(runtime.version().spec_version, 7);
+        // The compressed blob is what goes on chain
+        assert_eq!(runtime.code(), code.as_slice());
+    }
+
+    #[test]
+    fn test_check_upgrade() {
+        let runtime = WasmRuntime::from_bytes(runtime("apex", 1_002_000)).unwrap();
+        assert!(runtime.check_upgrade("apex", 1_001_000).is_ok());
+        assert!(runtime.check_upgrade("apex", 1_002_000).is_err());
+        assert!(runtime.check_upgrade("polkadot", 1_001_000).is_err());
+    }
+}
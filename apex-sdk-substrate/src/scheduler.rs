@@ -0,0 +1,868 @@
+//! Recurring and condition-triggered transactions
+//!
+//! A [`Scheduler`] submits [`TxTemplate`]s when their [`Trigger`] fires: on a
+//! cron schedule (UTC), when the staking era changes, or when an account's
+//! balance crosses a threshold. Jobs and their run history are kept in a
+//! [`ScheduleStore`], so a restarted scheduler knows which runs it missed
+//! and handles them by each job's [`MissedRunPolicy`]. In dry-run mode the
+//! scheduler reports what it would submit without signing anything.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{
+//!     FileScheduleStore, MissedRunPolicy, ScheduledJob, Scheduler, SubstrateAdapter, Trigger,
+//!     TxTemplate, Wallet,
+//! };
+//! use serde_json::json;
+//! use std::time::Duration;
+//!
+//! # async fn example(adapter: &SubstrateAdapter, wallet: Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! let sweep = TxTemplate::new("sweep", "Balances", "transfer_keep_alive")
+//!     .with_arg("dest", json!({ "Id": "{{cold_wallet}}" }))
+//!     .with_arg("value", json!("{{amount}}"));
+//! let job = ScheduledJob::new("nightly-sweep", sweep, Trigger::cron("0 2 * * *")?)
+//!     .with_params(json!({
+//!         "cold_wallet": "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty",
+//!         "amount": "1000000000000",
+//!     }))
+//!     .with_missed_runs(MissedRunPolicy::RunOnce);
+//!
+//! let scheduler = Scheduler::new(adapter, wallet)
+//!     .with_store(FileScheduleStore::new("schedule.json"))
+//!     .with_job(job)
+//!     .on_run(|report: &apex_sdk_substrate::RunReport| println!("{:?}", report));
+//!
+//! for run in scheduler.preview(Duration::from_secs(7 * 24 * 3600)).await? {
+//!     println!("{} at {:?}: {}", run.job_id, run.at_ms, run.call_data);
+//! }
+//! scheduler.run().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::storage::StorageClient;
+use crate::template::TxTemplate;
+use crate::{Error, Result, SubstrateAdapter, TransactionExecutor, Wallet};
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use subxt::{OnlineClient, PolkadotConfig};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Default time between scheduler ticks
+pub const DEFAULT_TICK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Default lateness after which a cron run counts as missed
+pub const DEFAULT_GRACE: Duration = Duration::from_secs(120);
+
+/// Most missed runs replayed by [`MissedRunPolicy::RunAll`] in one tick
+pub const MAX_CATCH_UP_RUNS: usize = 100;
+
+/// A five-field cron expression, evaluated in UTC
+///
+/// Fields are minute, hour, day of month, month and day of week (0 or 7 is
+/// Sunday). Each field takes `*`, numbers, ranges `a-b`, steps `*/n` or
+/// `a-b/n`, and comma-separated lists. `@hourly`, `@daily`, `@weekly`,
+/// `@monthly` and `@yearly` are accepted too. As in cron, when both day of
+/// month and day of week are restricted, a day matching either one matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    /// Parse a cron expression
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(Error::Other(format!(
+                "Cron expression {:?} must have 5 fields",
+                expression
+            )));
+        };
+        let weekday_bits = parse_field(weekdays, 0, 7)?;
+        Ok(Self {
+            expression: expression.trim().to_string(),
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days: parse_field(days, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            // 7 is another name for Sunday
+            weekdays: (weekday_bits | weekday_bits >> 7) & 0x7f,
+            any_day: days == "*",
+            any_weekday: weekdays == "*",
+        })
+    }
+
+    /// The expression as written
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// First matching minute strictly after `after_ms`, in Unix milliseconds
+    ///
+    /// `None` if nothing matches within five years, e.g. for `0 0 31 2 *`.
+    pub fn next_after(&self, after_ms: u64) -> Option<u64> {
+        let after = DateTime::from_timestamp_millis(after_ms as i64)?;
+        let mut t = after.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        let limit = t + chrono::Duration::days(5 * 366);
+
+        while t < limit {
+            if !bit(self.months, t.month()) {
+                let (year, month) = match t.month() {
+                    12 => (t.year() + 1, 1),
+                    month => (t.year(), month + 1),
+                };
+                t = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+            } else if !self.day_matches(&t) {
+                t = (t.date_naive() + chrono::Days::new(1))
+                    .and_hms_opt(0, 0, 0)?
+                    .and_utc();
+            } else if !bit(self.hours, t.hour()) {
+                t = t.with_minute(0)? + chrono::Duration::hours(1);
+            } else if !bit(self.minutes, t.minute()) {
+                t += chrono::Duration::minutes(1);
+            } else {
+                return Some(t.timestamp_millis() as u64);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, t: &DateTime<Utc>) -> bool {
+        let day = bit(self.days, t.day());
+        let weekday = bit(self.weekdays, t.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = Error;
+
+    fn try_from(expression: String) -> Result<Self> {
+        Self::parse(&expression)
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(schedule: CronSchedule) -> Self {
+        schedule.expression
+    }
+}
+
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Bit mask of the values a cron field allows
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let invalid = || Error::Other(format!("Invalid cron field {:?}", field));
+    let number = |text: &str| -> Result<u32> {
+        text.parse::<u32>()
+            .ok()
+            .filter(|n| (min..=max).contains(n))
+            .ok_or_else(invalid)
+    };
+
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step.parse::<u32>().map_err(|_| invalid())?)),
+            None => (part, None),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (number(start)?, number(end)?),
+            None if step.is_some() => (number(range)?, max),
+            None => (number(range)?, number(range)?),
+        };
+        let step = step.unwrap_or(1);
+        if start > end || step == 0 {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// When a job runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    /// On a cron schedule
+    Cron(CronSchedule),
+    /// When `Staking::ActiveEra` changes
+    EraChange,
+    /// When the free balance of `address` drops below `amount`
+    BalanceBelow {
+        /// Watched account
+        address: String,
+        /// Threshold in plancks
+        amount: u128,
+    },
+    /// When the free balance of `address` rises above `amount`
+    BalanceAbove {
+        /// Watched account
+        address: String,
+        /// Threshold in plancks
+        amount: u128,
+    },
+}
+
+impl Trigger {
+    /// Trigger on a cron expression
+    pub fn cron(expression: &str) -> Result<Self> {
+        CronSchedule::parse(expression).map(Trigger::Cron)
+    }
+}
+
+/// What to do with cron runs that fell due while the scheduler was not running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    /// Drop them; only runs within the grace period are made
+    #[default]
+    Skip,
+    /// Make one run for all of them
+    RunOnce,
+    /// Make every missed run, up to [`MAX_CATCH_UP_RUNS`]
+    RunAll,
+}
+
+/// A template with its parameters and trigger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    /// Unique job id
+    pub id: String,
+    /// Transaction to submit
+    pub template: TxTemplate,
+    /// Template parameters
+    #[serde(default)]
+    pub params: JsonValue,
+    /// When to submit
+    pub trigger: Trigger,
+    /// Handling of missed cron runs
+    #[serde(default)]
+    pub missed_runs: MissedRunPolicy,
+    /// Whether the job runs
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl ScheduledJob {
+    /// Job submitting `template` on `trigger`
+    pub fn new(id: impl Into<String>, template: TxTemplate, trigger: Trigger) -> Self {
+        Self {
+            id: id.into(),
+            template,
+            params: JsonValue::Null,
+            trigger,
+            missed_runs: MissedRunPolicy::default(),
+            enabled: true,
+        }
+    }
+
+    /// Parameters for the template's placeholders
+    pub fn with_params(mut self, params: JsonValue) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Handling of missed cron runs
+    pub fn with_missed_runs(mut self, policy: MissedRunPolicy) -> Self {
+        self.missed_runs = policy;
+        self
+    }
+
+    /// Keep the job without running it
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+}
+
+/// Run history of a job
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobState {
+    /// Latest scheduled time handled, in Unix milliseconds
+    pub last_scheduled_ms: Option<u64>,
+    /// Last value of the watched condition
+    pub last_observed: Option<JsonValue>,
+    /// Extrinsic hash of the last successful run
+    pub last_tx_hash: Option<String>,
+    /// Error of the last failed run
+    pub last_error: Option<String>,
+}
+
+/// Jobs and their run history, as persisted
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SchedulerState {
+    /// Jobs by id
+    pub jobs: BTreeMap<String, ScheduledJob>,
+    /// Run history by job id
+    pub history: BTreeMap<String, JobState>,
+}
+
+/// Cron runs of a job due at `now_ms`, by its missed-run policy
+///
+/// A job without history is due from `now_ms` on; runs later than `grace`
+/// count as missed.
+pub fn due_runs(
+    schedule: &CronSchedule,
+    policy: MissedRunPolicy,
+    state: &JobState,
+    now_ms: u64,
+    grace: Duration,
+) -> Vec<u64> {
+    let Some(mut after) = state.last_scheduled_ms else {
+        return Vec::new();
+    };
+    let mut due = Vec::new();
+    while let Some(next) = schedule.next_after(after).filter(|next| *next <= now_ms) {
+        due.push(next);
+        after = next;
+        if policy != MissedRunPolicy::Skip && due.len() > MAX_CATCH_UP_RUNS {
+            due.remove(0);
+        }
+        if policy == MissedRunPolicy::Skip && due.len() > 1 {
+            due.remove(0);
+        }
+    }
+    let on_time = |at: &u64| now_ms - at <= grace.as_millis() as u64;
+    match policy {
+        MissedRunPolicy::Skip => due.into_iter().filter(on_time).collect(),
+        MissedRunPolicy::RunOnce => due.pop().into_iter().collect(),
+        MissedRunPolicy::RunAll => due,
+    }
+}
+
+/// Persistence of jobs and their run history
+#[async_trait]
+pub trait ScheduleStore: Send + Sync {
+    /// State saved last, or the default for a new store
+    async fn load(&self) -> Result<SchedulerState>;
+
+    /// Replace the saved state
+    async fn save(&self, state: &SchedulerState) -> Result<()>;
+}
+
+/// Keeps state in memory; history is lost on restart
+#[derive(Debug, Clone, Default)]
+pub struct MemoryScheduleStore {
+    state: Arc<parking_lot::Mutex<SchedulerState>>,
+}
+
+#[async_trait]
+impl ScheduleStore for MemoryScheduleStore {
+    async fn load(&self) -> Result<SchedulerState> {
+        Ok(self.state.lock().clone())
+    }
+
+    async fn save(&self, state: &SchedulerState) -> Result<()> {
+        *self.state.lock() = state.clone();
+        Ok(())
+    }
+}
+
+/// Keeps state in a JSON file
+#[derive(Debug, Clone)]
+pub struct FileScheduleStore {
+    path: PathBuf,
+}
+
+impl FileScheduleStore {
+    /// Store at `path`; the file is created on the first save
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl ScheduleStore for FileScheduleStore {
+    async fn load(&self) -> Result<SchedulerState> {
+        let json = match tokio::fs::read(&self.path).await {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(SchedulerState::default())
+            }
+            Err(e) => {
+                return Err(Error::Storage(format!(
+                    "Failed to read {}: {}",
+                    self.path.display(),
+                    e
+                )))
+            }
+        };
+        serde_json::from_slice(&json).map_err(|e| {
+            Error::Encoding(format!("Invalid schedule {}: {}", self.path.display(), e))
+        })
+    }
+
+    async fn save(&self, state: &SchedulerState) -> Result<()> {
+        let json = serde_json::to_vec_pretty(state).map_err(|e| Error::Encoding(e.to_string()))?;
+        // write then rename, so a crash never leaves a truncated file
+        let partial = self.path.with_extension("partial");
+        let io = |e: std::io::Error| {
+            Error::Storage(format!("Failed to write {}: {}", self.path.display(), e))
+        };
+        tokio::fs::write(&partial, json).await.map_err(io)?;
+        tokio::fs::rename(&partial, &self.path).await.map_err(io)
+    }
+}
+
+/// Result of one job run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    /// The transaction was included
+    Submitted {
+        /// Extrinsic hash
+        tx_hash: String,
+    },
+    /// Dry run: the call that would have been submitted
+    DryRun {
+        /// Hex-encoded call data
+        call_data: String,
+    },
+    /// The run failed
+    Failed {
+        /// Error message
+        error: String,
+    },
+}
+
+/// A job run, passed to [`RunHandler`]s
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunReport {
+    /// Job id
+    pub job_id: String,
+    /// Scheduled time of a cron run, in Unix milliseconds
+    pub scheduled_ms: Option<u64>,
+    /// What happened
+    pub outcome: RunOutcome,
+}
+
+/// A run [`Scheduler::preview`] expects
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedRun {
+    /// Job id
+    pub job_id: String,
+    /// When the run is due; `None` for a chain condition that holds now
+    pub at_ms: Option<u64>,
+    /// Hex-encoded call data
+    pub call_data: String,
+}
+
+/// Receiver of job run reports
+#[async_trait]
+pub trait RunHandler: Send + Sync {
+    /// Called after every run
+    async fn on_run(&self, report: &RunReport);
+}
+
+#[async_trait]
+impl<F> RunHandler for F
+where
+    F: Fn(&RunReport) + Send + Sync,
+{
+    async fn on_run(&self, report: &RunReport) {
+        self(report)
+    }
+}
+
+/// Submits templates on schedules and chain conditions
+pub struct Scheduler {
+    client: OnlineClient<PolkadotConfig>,
+    executor: TransactionExecutor,
+    storage: StorageClient,
+    wallet: Wallet,
+    store: Arc<dyn ScheduleStore>,
+    jobs: Vec<ScheduledJob>,
+    handlers: Vec<Arc<dyn RunHandler>>,
+    tick_interval: Duration,
+    grace: Duration,
+    dry_run: bool,
+    state: Mutex<Option<SchedulerState>>,
+}
+
+impl Scheduler {
+    /// Scheduler signing with `wallet` through the adapter's executor
+    ///
+    /// The adapter's policy, screening and audit log apply to every run.
+    pub fn new(adapter: &SubstrateAdapter, wallet: Wallet) -> Self {
+        Self {
+            client: adapter.client().clone(),
+            executor: adapter.transaction_executor(),
+            storage: adapter.storage(),
+            wallet,
+            store: Arc::new(MemoryScheduleStore::default()),
+            jobs: Vec::new(),
+            handlers: Vec::new(),
+            tick_interval: DEFAULT_TICK_INTERVAL,
+            grace: DEFAULT_GRACE,
+            dry_run: false,
+            state: Mutex::new(None),
+        }
+    }
+
+    /// Persist jobs and run history
+    pub fn with_store(mut self, store: impl ScheduleStore + 'static) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// Add a job, replacing a stored job with the same id
+    pub fn with_job(mut self, job: ScheduledJob) -> Self {
+        self.jobs.push(job);
+        self
+    }
+
+    /// Time between checks of schedules and conditions
+    pub fn with_tick_interval(mut self, interval: Duration) -> Self {
+        self.tick_interval = interval;
+        self
+    }
+
+    /// Lateness after which a cron run counts as missed
+    pub fn with_grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// Report runs with their call data instead of submitting them
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Register a run handler
+    pub fn on_run(mut self, handler: impl RunHandler + 'static) -> Self {
+        self.handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Tick until the store fails, reporting runs to the handlers
+    pub async fn run(&self) -> Result<()> {
+        info!(
+            "Scheduler running{} every {:?}",
+            if self.dry_run { " (dry run)" } else { "" },
+            self.tick_interval
+        );
+        let mut interval = tokio::time::interval(self.tick_interval);
+        loop {
+            interval.tick().await;
+            let reports = self.tick(now()).await?;
+            for report in &reports {
+                for handler in &self.handlers {
+                    handler.on_run(report).await;
+                }
+            }
+        }
+    }
+
+    /// Check every job once at `now_ms` and make the runs that are due
+    ///
+    /// A run's failure is reported, not returned; errors are from the store.
+    pub async fn tick(&self, now_ms: u64) -> Result<Vec<RunReport>> {
+        let mut guard = self.state.lock().await;
+        if guard.is_none() {
+            *guard = Some(self.load().await?);
+        }
+        let state = guard.as_mut().expect("loaded above");
+
+        let mut reports = Vec::new();
+        for job in state.jobs.values().filter(|job| job.enabled) {
+            let history = state.history.entry(job.id.clone()).or_default();
+            let runs = match self.due(job, history, now_ms).await {
+                Ok(runs) => runs,
+                Err(e) => {
+                    warn!("Failed to check trigger of job {}: {}", job.id, e);
+                    continue;
+                }
+            };
+            for scheduled_ms in runs {
+                let report = self.execute(job, scheduled_ms).await;
+                match &report.outcome {
+                    RunOutcome::Submitted { tx_hash } => {
+                        history.last_tx_hash = Some(tx_hash.clone());
+                        history.last_error = None;
+                    }
+                    RunOutcome::Failed { error } => history.last_error = Some(error.clone()),
+                    RunOutcome::DryRun { .. } => {}
+                }
+                reports.push(report);
+            }
+        }
+        if !self.dry_run {
+            self.store.save(state).await?;
+        }
+        Ok(reports)
+    }
+
+    /// Runs expected within `horizon`, with their call data; nothing is signed
+    ///
+    /// Chain-condition jobs are listed if their condition holds now.
+    pub async fn preview(&self, horizon: Duration) -> Result<Vec<PlannedRun>> {
+        let state = match &*self.state.lock().await {
+            Some(state) => state.clone(),
+            None => self.load().await?,
+        };
+        let now_ms = now();
+        let until = now_ms + horizon.as_millis() as u64;
+
+        let mut planned = Vec::new();
+        for job in state.jobs.values().filter(|job| job.enabled) {
+            let call_data = self.call_data(job)?;
+            let times = match &job.trigger {
+                Trigger::Cron(schedule) => {
+                    let mut times = Vec::new();
+                    let mut after = now_ms;
+                    while let Some(next) = schedule.next_after(after).filter(|t| *t <= until) {
+                        times.push(Some(next));
+                        after = next;
+                    }
+                    times
+                }
+                Trigger::EraChange => Vec::new(),
+                _ => match self.observe(&job.trigger).await? {
+                    JsonValue::Bool(true) => vec![None],
+                    _ => Vec::new(),
+                },
+            };
+            planned.extend(times.into_iter().map(|at_ms| PlannedRun {
+                job_id: job.id.clone(),
+                at_ms,
+                call_data: call_data.clone(),
+            }));
+        }
+        planned.sort_by_key(|run| run.at_ms);
+        Ok(planned)
+    }
+
+    /// Stored state with the configured jobs merged in
+    async fn load(&self) -> Result<SchedulerState> {
+        let mut state = self.store.load().await?;
+        for job in &self.jobs {
+            state.jobs.insert(job.id.clone(), job.clone());
+        }
+        Ok(state)
+    }
+
+    /// Runs of `job` due now; updates its history
+    async fn due(
+        &self,
+        job: &ScheduledJob,
+        history: &mut JobState,
+        now_ms: u64,
+    ) -> Result<Vec<Option<u64>>> {
+        if let Trigger::Cron(schedule) = &job.trigger {
+            let runs = due_runs(schedule, job.missed_runs, history, now_ms, self.grace);
+            history.last_scheduled_ms = Some(now_ms);
+            return Ok(runs.into_iter().map(Some).collect());
+        }
+
+        let observed = self.observe(&job.trigger).await?;
+        let previous = history.last_observed.replace(observed.clone());
+        let fires = match &job.trigger {
+            // an era change needs a known previous era
+            Trigger::EraChange => previous.is_some_and(|previous| previous != observed),
+            // thresholds fire when crossed, or when already crossed at start
+            _ => observed == JsonValue::Bool(true) && previous != Some(observed),
+        };
+        Ok(if fires { vec![None] } else { Vec::new() })
+    }
+
+    /// Current value of a chain condition: the era index, or whether a
+    /// balance threshold is crossed
+    async fn observe(&self, trigger: &Trigger) -> Result<JsonValue> {
+        match trigger {
+            Trigger::Cron(_) => Ok(JsonValue::Null),
+            Trigger::EraChange => {
+                let era = self
+                    .storage
+                    .query_storage_json("Staking", "ActiveEra", vec![])
+                    .await?;
+                Ok(era
+                    .and_then(|era| era.get("index").cloned())
+                    .unwrap_or(JsonValue::Null))
+            }
+            Trigger::BalanceBelow { address, amount } => {
+                let balance = self.storage.get_balance(address).await?;
+                Ok(JsonValue::Bool(balance < *amount))
+            }
+            Trigger::BalanceAbove { address, amount } => {
+                let balance = self.storage.get_balance(address).await?;
+                Ok(JsonValue::Bool(balance > *amount))
+            }
+        }
+    }
+
+    async fn execute(&self, job: &ScheduledJob, scheduled_ms: Option<u64>) -> RunReport {
+        let outcome = if self.dry_run {
+            match self.call_data(job) {
+                Ok(call_data) => RunOutcome::DryRun { call_data },
+                Err(e) => RunOutcome::Failed {
+                    error: e.to_string(),
+                },
+            }
+        } else {
+            info!("Running scheduled job {}", job.id);
+            match self
+                .executor
+                .submit_template(&self.wallet, &job.template, &job.params)
+                .await
+            {
+                Ok(tx_hash) => RunOutcome::Submitted { tx_hash },
+                Err(e) => {
+                    warn!("Scheduled job {} failed: {}", job.id, e);
+                    RunOutcome::Failed {
+                        error: e.to_string(),
+                    }
+                }
+            }
+        };
+        RunReport {
+            job_id: job.id.clone(),
+            scheduled_ms,
+            outcome,
+        }
+    }
+
+    fn call_data(&self, job: &ScheduledJob) -> Result<String> {
+        let bytes = job.template.call_data(&job.params, &self.client)?;
+        Ok(format!("0x{}", hex::encode(bytes)))
+    }
+}
+
+impl std::fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("wallet", &self.wallet.address())
+            .field("jobs", &self.jobs.len())
+            .field("tick_interval", &self.tick_interval)
+            .field("dry_run", &self.dry_run)
+            .finish()
+    }
+}
+
+fn now() -> u64 {
+    Utc::now().timestamp_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> u64 {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .timestamp_millis() as u64
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let daily = CronSchedule::parse("30 2 * * *").unwrap();
+        assert_eq!(
+            daily.next_after(at("2026-03-01T02:30:00Z")),
+            Some(at("2026-03-02T02:30:00Z"))
+        );
+
+        // 15th of the month or any Monday
+        let either = CronSchedule::parse("0 9 15 * 1").unwrap();
+        assert_eq!(
+            either.next_after(at("2026-03-10T12:00:00Z")),
+            Some(at("2026-03-15T09:00:00Z"))
+        );
+        assert_eq!(
+            either.next_after(at("2026-03-15T12:00:00Z")),
+            Some(at("2026-03-16T09:00:00Z"))
+        );
+
+        let sundays = CronSchedule::parse("*/20 0-1 * 12 7").unwrap();
+        assert_eq!(
+            sundays.next_after(at("2026-03-01T00:00:00Z")),
+            Some(at("2026-12-06T00:00:00Z"))
+        );
+        assert_eq!(
+            serde_json::to_string(&Trigger::Cron(sundays)).unwrap(),
+            r#"{"cron":"*/20 0-1 * 12 7"}"#
+        );
+
+        assert!(CronSchedule::parse("0 0 31 2 *")
+            .unwrap()
+            .next_after(0)
+            .is_none());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("* * *").is_err());
+    }
+
+    #[test]
+    fn test_missed_runs() {
+        let hourly = CronSchedule::parse("@hourly").unwrap();
+        let state = JobState {
+            last_scheduled_ms: Some(at("2026-03-01T00:30:00Z")),
+            ..JobState::default()
+        };
+        let now = at("2026-03-01T03:00:30Z");
+        let grace = Duration::from_secs(60);
+
+        assert_eq!(
+            due_runs(&hourly, MissedRunPolicy::RunAll, &state, now, grace),
+            vec![
+                at("2026-03-01T01:00:00Z"),
+                at("2026-03-01T02:00:00Z"),
+                at("2026-03-01T03:00:00Z"),
+            ]
+        );
+        assert_eq!(
+            due_runs(&hourly, MissedRunPolicy::RunOnce, &state, now, grace),
+            vec![at("2026-03-01T03:00:00Z")]
+        );
+        assert_eq!(
+            due_runs(&hourly, MissedRunPolicy::Skip, &state, now, grace),
+            vec![at("2026-03-01T03:00:00Z")]
+        );
+        // the last run is late too
+        let late = at("2026-03-01T03:05:00Z");
+        assert!(due_runs(&hourly, MissedRunPolicy::Skip, &state, late, grace).is_empty());
+        // a new job starts counting now
+        assert!(due_runs(
+            &hourly,
+            MissedRunPolicy::RunAll,
+            &JobState::default(),
+            now,
+            grace
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,255 @@
+//! Session key management for validators
+//!
+//! [`SessionKeys`] rotates the keys held by a validator node, registers them
+//! on chain with `Session::set_keys` and checks that the keys registered for
+//! a validator are the ones its node holds:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{SessionKeys, SubstrateAdapter, Wallet};
+//!
+//! # async fn example(validator_node: &SubstrateAdapter, stash: &Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! let session = SessionKeys::new(validator_node);
+//! let keys = session.rotate().await?;
+//! for key in session.decode(&keys)? {
+//!     println!("{}: {}", key.name, key.public);
+//! }
+//! session.set_keys(stash, &keys, Vec::new()).await?;
+//!
+//! let check = session.verify(&stash.address()).await?;
+//! assert!(check.is_ready());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Rotating and checking keys use `author_*` RPC methods, which nodes only
+//! expose to trusted connections with `--rpc-methods unsafe`. The adapter has
+//! to be connected to the validator node itself.
+
+use crate::event_query::account_id;
+use crate::wallet::Wallet;
+use crate::{Error, Result, SubstrateAdapter};
+use serde::{Deserialize, Serialize};
+use subxt::dynamic::Value;
+use subxt::ext::scale_value;
+use subxt::ext::subxt_rpcs::client::RpcParams;
+use subxt::Metadata;
+use tracing::info;
+
+/// One key of a set of session keys
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionKey {
+    /// Field name in the runtime's session keys, e.g. `grandpa`
+    pub name: String,
+    /// Public key (hex)
+    pub public: String,
+}
+
+/// Session keys of a validator as registered on chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionKeyCheck {
+    /// Validator address
+    pub validator: String,
+    /// Keys registered in `Session::NextKeys` (hex), if any
+    pub registered: Option<String>,
+    /// Whether the connected node holds the private keys of the registered keys
+    pub held_by_node: bool,
+}
+
+impl SessionKeyCheck {
+    /// Whether keys are registered and the node can sign with them
+    pub fn is_ready(&self) -> bool {
+        self.registered.is_some() && self.held_by_node
+    }
+}
+
+/// Session keys of a validator node
+pub struct SessionKeys<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> SessionKeys<'a> {
+    /// Manage the keys of the node behind the adapter
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Generate new session keys in the node's keystore
+    ///
+    /// Returns the encoded public keys, to be registered with
+    /// [`set_keys`](Self::set_keys).
+    pub async fn rotate(&self) -> Result<Vec<u8>> {
+        let keys: String = self
+            .adapter
+            .rpc_client()
+            .request("author_rotateKeys", RpcParams::new())
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to rotate session keys: {}", e)))?;
+        info!("Rotated session keys");
+        parse_keys(&keys)
+    }
+
+    /// Whether the node's keystore holds the private keys of `keys`
+    pub async fn has_keys(&self, keys: &[u8]) -> Result<bool> {
+        let mut params = RpcParams::new();
+        params
+            .push(format!("0x{}", hex::encode(keys)))
+            .map_err(|e| Error::Encoding(format!("Failed to encode session keys: {}", e)))?;
+        self.adapter
+            .rpc_client()
+            .request("author_hasSessionKeys", params)
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to check session keys: {}", e)))
+    }
+
+    /// Split encoded session keys into the runtime's named keys
+    pub fn decode(&self, keys: &[u8]) -> Result<Vec<SessionKey>> {
+        decode_keys(keys, &self.adapter.client().metadata())
+    }
+
+    /// `Session::set_keys` call registering `keys` with an ownership `proof`
+    ///
+    /// Most runtimes accept an empty proof.
+    pub fn set_keys_call(&self, keys: &[u8], proof: Vec<u8>) -> Result<subxt::tx::DynamicPayload> {
+        self.adapter.require_pallet("Session")?;
+        Ok(subxt::dynamic::tx(
+            "Session",
+            "set_keys",
+            vec![
+                keys_value(keys, &self.adapter.client().metadata())?,
+                Value::from_bytes(proof),
+            ],
+        ))
+    }
+
+    /// Register `keys` for the validator controlled by `wallet`
+    ///
+    /// The keys take effect from the next session.
+    pub async fn set_keys(&self, wallet: &Wallet, keys: &[u8], proof: Vec<u8>) -> Result<String> {
+        self.adapter.require_pallet("Session")?;
+        let keys = keys_value(keys, &self.adapter.client().metadata())?;
+        self.adapter
+            .transaction_executor()
+            .submit_call(
+                wallet,
+                "Session",
+                "set_keys",
+                vec![keys, Value::from_bytes(proof)],
+            )
+            .await
+    }
+
+    /// Encoded keys registered for a validator, if any
+    pub async fn registered(&self, validator: &str) -> Result<Option<Vec<u8>>> {
+        self.adapter
+            .storage()
+            .query_storage(
+                "Session",
+                "NextKeys",
+                vec![Value::from_bytes(account_id(validator)?)],
+            )
+            .await
+    }
+
+    /// Check the keys registered for a validator against the node's keystore
+    pub async fn verify(&self, validator: &str) -> Result<SessionKeyCheck> {
+        let registered = self.registered(validator).await?;
+        let held_by_node = match &registered {
+            Some(keys) => self.has_keys(keys).await?,
+            None => false,
+        };
+        Ok(SessionKeyCheck {
+            validator: validator.to_string(),
+            registered: registered.map(|keys| format!("0x{}", hex::encode(keys))),
+            held_by_node,
+        })
+    }
+}
+
+/// Session keys from the hex returned by `author_rotateKeys`
+fn parse_keys(keys: &str) -> Result<Vec<u8>> {
+    hex::decode(keys.trim_start_matches("0x"))
+        .ok()
+        .filter(|keys| !keys.is_empty())
+        .ok_or_else(|| Error::Encoding(format!("Invalid session keys {}", keys)))
+}
+
+/// Type id of the runtime's session keys
+fn keys_type(metadata: &Metadata) -> Result<u32> {
+    metadata
+        .pallet_by_name("Session")
+        .and_then(|pallet| pallet.storage())
+        .and_then(|storage| storage.entry_by_name("NextKeys"))
+        .map(|entry| entry.entry_type().value_ty())
+        .ok_or_else(|| Error::Metadata("Runtime has no Session::NextKeys".to_string()))
+}
+
+/// Encoded session keys as a dynamic value of the runtime's keys type
+fn keys_value(keys: &[u8], metadata: &Metadata) -> Result<Value> {
+    let mut input = keys;
+    let value =
+        scale_value::scale::decode_as_type(&mut input, keys_type(metadata)?, metadata.types())
+            .map_err(|e| Error::Encoding(format!("Invalid session keys: {}", e)))?;
+    if !input.is_empty() {
+        return Err(Error::Encoding(format!(
+            "Invalid session keys: {} trailing bytes",
+            input.len()
+        )));
+    }
+    Ok(value.remove_context())
+}
+
+/// Split encoded session keys by the fields of the runtime's keys type
+fn decode_keys(keys: &[u8], metadata: &Metadata) -> Result<Vec<SessionKey>> {
+    let invalid = |what: String| Error::Encoding(format!("Invalid session keys: {}", what));
+    let ty = keys_type(metadata)?;
+    let scale_info::TypeDef::Composite(composite) = &metadata
+        .types()
+        .resolve(ty)
+        .ok_or_else(|| Error::Metadata(format!("Type {} not found in metadata", ty)))?
+        .type_def
+    else {
+        return Err(Error::Metadata("Session keys are not a struct".to_string()));
+    };
+
+    let mut input = keys;
+    let mut decoded = Vec::with_capacity(composite.fields.len());
+    for (index, field) in composite.fields.iter().enumerate() {
+        let name = field.name.clone().unwrap_or_else(|| index.to_string());
+        let start = input;
+        scale_value::scale::decode_as_type(&mut input, field.ty.id, metadata.types())
+            .map_err(|e| invalid(format!("{}: {}", name, e)))?;
+        decoded.push(SessionKey {
+            name,
+            public: format!("0x{}", hex::encode(&start[..start.len() - input.len()])),
+        });
+    }
+    if !input.is_empty() {
+        return Err(invalid(format!("{} trailing bytes", input.len())));
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rotated_keys() {
+        let keys = format!("0x{}{}", "d4".repeat(32), "8e".repeat(32));
+        assert_eq!(parse_keys(&keys).unwrap().len(), 64);
+        assert!(parse_keys("0x").is_err());
+        assert!(parse_keys("0xzz").is_err());
+
+        let check = SessionKeyCheck {
+            validator: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string(),
+            registered: Some(keys),
+            held_by_node: false,
+        };
+        assert!(!check.is_ready());
+        assert!(SessionKeyCheck {
+            held_by_node: true,
+            ..check
+        }
+        .is_ready());
+    }
+}
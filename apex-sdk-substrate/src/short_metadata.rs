@@ -0,0 +1,395 @@
+//! Shortened metadata for the Ledger Polkadot Generic App
+//!
+//! The Generic App has no metadata of its own. With each transaction it is
+//! given the parts of the [RFC78] type tree needed to decode that one
+//! transaction, proven against the metadata hash the transaction commits to,
+//! and shows the decoded call instead of blind-signing a hash:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::watch_only::WatchOnlyAccount;
+//! use apex_sdk_substrate::{KeyPairType, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let account = WatchOnlyAccount::new(
+//!     "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+//!     KeyPairType::Ed25519,
+//! )?;
+//! let pending = account
+//!     .prepare_transfer(adapter, "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty", 1_000_000_000)
+//!     .await?;
+//!
+//! // sent to the device along with the signer payload
+//! let short = pending.short_metadata(adapter).await?;
+//! println!("{} bytes of metadata", short.to_bytes().len());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only chains with the `CheckMetadataHash` extension can be clear-signed.
+//!
+//! [RFC78]: https://polkadot-fellows.github.io/RFCs/approved/0078-merkleized-metadata.html
+
+use crate::metadata_hash::{ExtensionData, MetadataHashInfo};
+use crate::{Error, Result};
+use frame_metadata::RuntimeMetadata;
+use merkleized_metadata::{generate_proof_for_extrinsic_parts, SignedExtrinsicData};
+use parity_scale_codec::{Compact, Encode};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef, TypeDefPrimitive};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Metadata a Ledger device needs to decode one transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortMetadata {
+    /// Merkle proof of the types the transaction uses
+    pub proof: Vec<u8>,
+    /// Encoded extrinsic metadata, in its RFC78 form
+    pub extrinsic_metadata: Vec<u8>,
+    /// Encoded chain details the metadata hash commits to
+    pub extra_info: Vec<u8>,
+}
+
+impl ShortMetadata {
+    /// Blob sent to the device: proof, extrinsic metadata and chain details
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.proof.clone();
+        bytes.extend_from_slice(&self.extrinsic_metadata);
+        bytes.extend_from_slice(&self.extra_info);
+        bytes
+    }
+
+    /// [`to_bytes`](Self::to_bytes) as `0x`-prefixed hex
+    pub fn to_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.to_bytes()))
+    }
+}
+
+/// Shortened metadata for a signed transaction with `call_data` and `extensions`
+///
+/// `extensions` must be the ones signed, with the metadata hash enabled.
+pub fn short_metadata(
+    metadata: &RuntimeMetadata,
+    info: &MetadataHashInfo,
+    call_data: &[u8],
+    extensions: &ExtensionData,
+) -> Result<ShortMetadata> {
+    let proof = generate_proof_for_extrinsic_parts(
+        call_data,
+        Some(SignedExtrinsicData {
+            included_in_extrinsic: &extensions.extra,
+            included_in_signed_data: &extensions.implicit,
+        }),
+        metadata,
+    )
+    .map_err(|e| Error::Metadata(format!("Failed to generate metadata proof: {}", e)))?;
+
+    Ok(ShortMetadata {
+        proof: proof.encode(),
+        extrinsic_metadata: extrinsic_metadata(metadata)?.encode(),
+        extra_info: (
+            info.spec_version,
+            &info.spec_name,
+            info.ss58_prefix,
+            info.decimals,
+            &info.token_symbol,
+        )
+            .encode(),
+    })
+}
+
+/// Reference to a type of the RFC78 type tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode)]
+enum TypeRef {
+    Bool,
+    Char,
+    Str,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    I256,
+    CompactU8,
+    CompactU16,
+    CompactU32,
+    CompactU64,
+    CompactU128,
+    CompactU256,
+    Void,
+    ById(Compact<u32>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode)]
+struct ExtrinsicMetadata {
+    version: u8,
+    address_ty: TypeRef,
+    call_ty: TypeRef,
+    signature_ty: TypeRef,
+    signed_extensions: Vec<SignedExtensionMetadata>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode)]
+struct SignedExtensionMetadata {
+    identifier: String,
+    included_in_extrinsic: TypeRef,
+    included_in_signed_data: TypeRef,
+}
+
+/// Extrinsic metadata with type ids renumbered as in the RFC78 type tree
+fn extrinsic_metadata(metadata: &RuntimeMetadata) -> Result<ExtrinsicMetadata> {
+    let RuntimeMetadata::V15(metadata) = metadata else {
+        return Err(Error::Metadata(
+            "Short metadata requires v15 metadata".to_string(),
+        ));
+    };
+    let extrinsic = &metadata.extrinsic;
+    let tree = TypeTree::new(
+        &metadata.types,
+        [
+            extrinsic.call_ty.id,
+            extrinsic.address_ty.id,
+            extrinsic.signature_ty.id,
+        ]
+        .into_iter()
+        .chain(
+            extrinsic
+                .signed_extensions
+                .iter()
+                .flat_map(|extension| [extension.ty.id, extension.additional_signed.id]),
+        ),
+    )?;
+
+    Ok(ExtrinsicMetadata {
+        version: extrinsic.version,
+        address_ty: tree.type_ref(extrinsic.address_ty.id)?,
+        call_ty: tree.type_ref(extrinsic.call_ty.id)?,
+        signature_ty: tree.type_ref(extrinsic.signature_ty.id)?,
+        signed_extensions: extrinsic
+            .signed_extensions
+            .iter()
+            .map(|extension| {
+                Ok(SignedExtensionMetadata {
+                    identifier: extension.identifier.clone(),
+                    included_in_extrinsic: tree.type_ref(extension.ty.id)?,
+                    included_in_signed_data: tree.type_ref(extension.additional_signed.id)?,
+                })
+            })
+            .collect::<Result<_>>()?,
+    })
+}
+
+/// Types reachable from the extrinsic, numbered in registry order
+///
+/// Primitives, compacts and empty types are inlined as [`TypeRef`]s, so only
+/// the remaining types get an id.
+struct TypeTree<'a> {
+    registry: &'a PortableRegistry,
+    ids: BTreeMap<u32, u32>,
+}
+
+impl<'a> TypeTree<'a> {
+    fn new(registry: &'a PortableRegistry, roots: impl IntoIterator<Item = u32>) -> Result<Self> {
+        let mut reachable = BTreeSet::new();
+        for root in roots {
+            collect_reachable(registry, root, &mut reachable)?;
+        }
+        let mut tree = Self {
+            registry,
+            ids: BTreeMap::new(),
+        };
+        for id in reachable {
+            if !is_inlined(tree.type_def(id)?) {
+                let next = tree.ids.len() as u32;
+                tree.ids.insert(id, next);
+            }
+        }
+        Ok(tree)
+    }
+
+    fn type_def(&self, id: u32) -> Result<&'a TypeDef<PortableForm>> {
+        type_def(self.registry, id)
+    }
+
+    fn type_ref(&self, id: u32) -> Result<TypeRef> {
+        let type_def = self.type_def(id)?;
+        Ok(match type_def {
+            TypeDef::Primitive(primitive) => primitive_ref(primitive),
+            TypeDef::Compact(_) => {
+                let mut primitives = Vec::new();
+                collect_primitives(self.registry, id, &mut BTreeSet::new(), &mut primitives)?;
+                match primitives.as_slice() {
+                    [] => TypeRef::Void,
+                    [TypeDefPrimitive::U8] => TypeRef::CompactU8,
+                    [TypeDefPrimitive::U16] => TypeRef::CompactU16,
+                    [TypeDefPrimitive::U32] => TypeRef::CompactU32,
+                    [TypeDefPrimitive::U64] => TypeRef::CompactU64,
+                    [TypeDefPrimitive::U128] => TypeRef::CompactU128,
+                    [TypeDefPrimitive::U256] => TypeRef::CompactU256,
+                    other => {
+                        return Err(Error::Metadata(format!(
+                            "Unsupported compact type {}: {:?}",
+                            id, other
+                        )))
+                    }
+                }
+            }
+            _ if is_inlined(type_def) => TypeRef::Void,
+            _ => TypeRef::ById(Compact(*self.ids.get(&id).ok_or_else(|| {
+                Error::Metadata(format!("Type {} is not reachable from the extrinsic", id))
+            })?)),
+        })
+    }
+}
+
+fn type_def(registry: &PortableRegistry, id: u32) -> Result<&TypeDef<PortableForm>> {
+    registry
+        .types
+        .get(id as usize)
+        .map(|ty| &ty.ty.type_def)
+        .ok_or_else(|| Error::Metadata(format!("Type {} not found in metadata", id)))
+}
+
+/// Whether a type is referenced inline rather than by id
+fn is_inlined(type_def: &TypeDef<PortableForm>) -> bool {
+    match type_def {
+        TypeDef::Primitive(_) | TypeDef::Compact(_) => true,
+        TypeDef::Composite(composite) => composite.fields.is_empty(),
+        TypeDef::Variant(variant) => variant.variants.is_empty(),
+        TypeDef::Tuple(tuple) => tuple.fields.is_empty(),
+        _ => false,
+    }
+}
+
+/// Types reachable from `id`, not looking inside compacts and bit sequences
+fn collect_reachable(
+    registry: &PortableRegistry,
+    id: u32,
+    found: &mut BTreeSet<u32>,
+) -> Result<()> {
+    if !found.insert(id) {
+        return Ok(());
+    }
+    for child in children(type_def(registry, id)?, false) {
+        collect_reachable(registry, child, found)?;
+    }
+    Ok(())
+}
+
+/// Primitives a type is made of, as the inner type of a compact
+fn collect_primitives(
+    registry: &PortableRegistry,
+    id: u32,
+    visited: &mut BTreeSet<u32>,
+    found: &mut Vec<TypeDefPrimitive>,
+) -> Result<()> {
+    match type_def(registry, id)? {
+        TypeDef::Primitive(primitive) => found.push(primitive.clone()),
+        type_def => {
+            for child in children(type_def, true) {
+                if visited.insert(child) {
+                    collect_primitives(registry, child, visited, found)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Ids of the types a type is directly built from
+fn children(type_def: &TypeDef<PortableForm>, all: bool) -> Vec<u32> {
+    match type_def {
+        TypeDef::Composite(composite) => composite.fields.iter().map(|f| f.ty.id).collect(),
+        TypeDef::Variant(variant) => variant
+            .variants
+            .iter()
+            .flat_map(|v| v.fields.iter().map(|f| f.ty.id))
+            .collect(),
+        TypeDef::Sequence(sequence) => vec![sequence.type_param.id],
+        TypeDef::Array(array) => vec![array.type_param.id],
+        TypeDef::Tuple(tuple) => tuple.fields.iter().map(|t| t.id).collect(),
+        TypeDef::Compact(compact) if all => vec![compact.type_param.id],
+        TypeDef::BitSequence(bits) if all => {
+            vec![bits.bit_order_type.id, bits.bit_store_type.id]
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn primitive_ref(primitive: &TypeDefPrimitive) -> TypeRef {
+    match primitive {
+        TypeDefPrimitive::Bool => TypeRef::Bool,
+        TypeDefPrimitive::Char => TypeRef::Char,
+        TypeDefPrimitive::Str => TypeRef::Str,
+        TypeDefPrimitive::U8 => TypeRef::U8,
+        TypeDefPrimitive::U16 => TypeRef::U16,
+        TypeDefPrimitive::U32 => TypeRef::U32,
+        TypeDefPrimitive::U64 => TypeRef::U64,
+        TypeDefPrimitive::U128 => TypeRef::U128,
+        TypeDefPrimitive::U256 => TypeRef::U256,
+        TypeDefPrimitive::I8 => TypeRef::I8,
+        TypeDefPrimitive::I16 => TypeRef::I16,
+        TypeDefPrimitive::I32 => TypeRef::I32,
+        TypeDefPrimitive::I64 => TypeRef::I64,
+        TypeDefPrimitive::I128 => TypeRef::I128,
+        TypeDefPrimitive::I256 => TypeRef::I256,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale_info::{meta_type, Registry, TypeInfo};
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    struct Nonce(#[codec(compact)] u32);
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum Call {
+        Remark(Vec<u8>),
+        Transfer {
+            dest: [u8; 32],
+            #[codec(compact)]
+            value: u128,
+        },
+    }
+
+    #[test]
+    fn test_type_tree_ids() {
+        let mut registry = Registry::new();
+        let types = [
+            meta_type::<Call>(),
+            meta_type::<Nonce>(),
+            meta_type::<u64>(),
+            meta_type::<()>(),
+        ]
+        .map(|ty| registry.register_type(&ty).id);
+        let registry = PortableRegistry::from(registry);
+
+        let tree = TypeTree::new(&registry, types).unwrap();
+        // `Call`, `Vec<u8>`, `[u8; 32]` and `Nonce` get ids in registry order
+        assert_eq!(tree.ids.len(), 4);
+        assert_eq!(tree.type_ref(types[0]).unwrap(), TypeRef::ById(Compact(0)));
+        assert_eq!(tree.type_ref(types[1]).unwrap(), TypeRef::ById(Compact(3)));
+        assert_eq!(tree.type_ref(types[2]).unwrap(), TypeRef::U64);
+        assert_eq!(tree.type_ref(types[3]).unwrap(), TypeRef::Void);
+
+        // the `value` field is a compact u128
+        let TypeDef::Variant(call) = type_def(&registry, types[0]).unwrap() else {
+            panic!("`Call` is a variant");
+        };
+        let value = call.variants[1].fields[1].ty.id;
+        assert_eq!(tree.type_ref(value).unwrap(), TypeRef::CompactU128);
+        assert_eq!(
+            TypeRef::ById(Compact(3)).encode(),
+            vec![22, 3 << 2],
+            "by-id references use variant 22"
+        );
+    }
+}
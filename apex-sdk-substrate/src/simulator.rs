@@ -0,0 +1,360 @@
+//! Dry-running calls against historical or current state
+//!
+//! [`Simulator`] executes calls through the runtime's `DryRunApi` at one
+//! block. Every call runs against that block's unchanged state, so the
+//! outcomes of [`Simulator::dry_run_many`] are independent of each other;
+//! routers use this to compare alternative call paths before signing one.
+//!
+//! Each [`DryRunOutcome`] reports success or the dispatch error, the events the
+//! call would emit and, where `TransactionPaymentCallApi` is available, the
+//! fee for submitting it.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{DryRunCall, Simulator, SubstrateAdapter};
+//! use subxt::dynamic::Value;
+//!
+//! # async fn example(adapter: &SubstrateAdapter, alice: [u8; 32], bob: [u8; 32]) -> Result<(), apex_sdk_substrate::Error> {
+//! let routes = [
+//!     DryRunCall::new(alice, "Balances", "transfer_keep_alive")
+//!         .with_args(vec![Value::unnamed_variant("Id", [Value::from_bytes(bob)]), Value::u128(10)]),
+//!     DryRunCall::new(alice, "Balances", "transfer_allow_death")
+//!         .with_args(vec![Value::unnamed_variant("Id", [Value::from_bytes(bob)]), Value::u128(10)]),
+//! ];
+//!
+//! for outcome in Simulator::new(adapter).dry_run_many(&routes).await? {
+//!     println!("{}.{}: {:?} fee {:?}", outcome.pallet, outcome.call, outcome.error, outcome.fee);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::{value_to_json, MatchedEvent};
+use crate::receipt::{dispatch_error, json_u128};
+use crate::{Error, Result, SubstrateAdapter};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::dynamic::Value;
+use subxt::utils::H256;
+use tracing::debug;
+
+/// XCM version requested for forwarded messages in dry-run results
+pub const DRY_RUN_XCM_VERSION: u32 = 5;
+
+/// A call to dry-run, dispatched from a signed origin
+#[derive(Debug, Clone)]
+pub struct DryRunCall {
+    /// Signing account the call is dispatched from
+    pub origin: [u8; 32],
+    /// Pallet name
+    pub pallet: String,
+    /// Call name
+    pub call: String,
+    /// Call arguments in declaration order
+    pub args: Vec<Value>,
+}
+
+impl DryRunCall {
+    /// Call without arguments dispatched from `origin`
+    pub fn new(origin: [u8; 32], pallet: impl Into<String>, call: impl Into<String>) -> Self {
+        Self {
+            origin,
+            pallet: pallet.into(),
+            call: call.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Set the call arguments
+    pub fn with_args(mut self, args: Vec<Value>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// `RuntimeCall` value of this call
+    fn runtime_call(&self) -> Value {
+        Value::unnamed_variant(
+            self.pallet.clone(),
+            [Value::unnamed_variant(self.call.clone(), self.args.clone())],
+        )
+    }
+
+    /// `OriginCaller::system(RawOrigin::Signed(origin))`
+    fn origin_caller(&self) -> Value {
+        Value::unnamed_variant(
+            "system",
+            [Value::unnamed_variant(
+                "Signed",
+                [Value::from_bytes(self.origin)],
+            )],
+        )
+    }
+}
+
+/// Result of dry-running one call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DryRunOutcome {
+    /// Pallet name
+    pub pallet: String,
+    /// Call name
+    pub call: String,
+    /// Block whose state the call ran against
+    pub block_number: u64,
+    /// Whether the call dispatched successfully
+    pub success: bool,
+    /// Dispatch error, or why the call could not be simulated
+    pub error: Option<String>,
+    /// Partial fee for submitting the call, if the runtime reports it
+    pub fee: Option<u128>,
+    /// Events the call would emit
+    pub events: Vec<MatchedEvent>,
+}
+
+/// Dry-runs calls through `DryRunApi` at a chosen block
+pub struct Simulator<'a> {
+    adapter: &'a SubstrateAdapter,
+    block: Option<u64>,
+}
+
+impl<'a> Simulator<'a> {
+    /// Simulator running at the finalized head
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self {
+            adapter,
+            block: None,
+        }
+    }
+
+    /// Run against the state of `block` instead; older blocks need an archive node
+    pub fn at_block(mut self, block: u64) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// Dry-run a single call
+    pub async fn dry_run(&self, call: &DryRunCall) -> Result<DryRunOutcome> {
+        let (number, hash) = self.resolve_block().await?;
+        self.run_at(call, number, hash).await
+    }
+
+    /// Dry-run each call against the same state
+    ///
+    /// Calls that cannot be simulated, e.g. because their arguments do not
+    /// match the metadata, are reported as failed outcomes instead of
+    /// aborting the others.
+    pub async fn dry_run_many(&self, calls: &[DryRunCall]) -> Result<Vec<DryRunOutcome>> {
+        let (number, hash) = self.resolve_block().await?;
+        let mut outcomes = Vec::with_capacity(calls.len());
+        for call in calls {
+            let outcome = match self.run_at(call, number, hash).await {
+                Ok(outcome) => outcome,
+                Err(e) => DryRunOutcome {
+                    pallet: call.pallet.clone(),
+                    call: call.call.clone(),
+                    block_number: number,
+                    success: false,
+                    error: Some(e.to_string()),
+                    fee: None,
+                    events: Vec::new(),
+                },
+            };
+            outcomes.push(outcome);
+        }
+        Ok(outcomes)
+    }
+
+    /// Number and hash of the block to simulate at
+    async fn resolve_block(&self) -> Result<(u64, H256)> {
+        let spec = self.adapter.spec_client();
+        let number = match self.block {
+            Some(number) => number,
+            None => spec.finalized_number().await?,
+        };
+        let hash = spec
+            .block_hash(number)
+            .await?
+            .ok_or_else(|| Error::Storage(format!("Block {} not found", number)))?;
+        Ok((number, hash))
+    }
+
+    async fn run_at(&self, call: &DryRunCall, number: u64, hash: H256) -> Result<DryRunOutcome> {
+        let capabilities = self.adapter.capabilities();
+        let mut args = vec![call.origin_caller(), call.runtime_call()];
+        // version 1 of the API predates the XCM version parameter
+        if capabilities.runtime_api_version("DryRunApi") != Some(1) {
+            args.push(Value::u128(DRY_RUN_XCM_VERSION as u128));
+        }
+
+        let runtime_api = self.adapter.client().runtime_api().at(hash);
+        let effects = runtime_api
+            .call(subxt::dynamic::runtime_api_call(
+                "DryRunApi",
+                "dry_run_call",
+                args,
+            ))
+            .await
+            .map_err(|e| {
+                Error::Transaction(format!(
+                    "Failed to dry-run {}.{}: {}",
+                    call.pallet, call.call, e
+                ))
+            })?
+            .to_value()
+            .map_err(|e| Error::Encoding(format!("Failed to decode dry-run result: {}", e)))?;
+        let (error, events) = parse_effects(
+            &value_to_json(&effects),
+            number,
+            &format!("0x{}", hex::encode(hash.0)),
+        )?;
+
+        let fee = if capabilities.has_runtime_api("TransactionPaymentCallApi") {
+            self.query_call_fee(call, hash).await
+        } else {
+            None
+        };
+
+        Ok(DryRunOutcome {
+            pallet: call.pallet.clone(),
+            call: call.call.clone(),
+            block_number: number,
+            success: error.is_none(),
+            error,
+            fee,
+            events,
+        })
+    }
+
+    /// `TransactionPaymentCallApi::query_call_info` partial fee; `None` if unavailable
+    async fn query_call_fee(&self, call: &DryRunCall, hash: H256) -> Option<u128> {
+        let client = self.adapter.client();
+        let tx = subxt::dynamic::tx(call.pallet.as_str(), call.call.as_str(), call.args.clone());
+        let len = match client.tx().call_data(&tx) {
+            Ok(call_data) => call_data.len(),
+            Err(e) => {
+                debug!("Failed to encode {}.{}: {}", call.pallet, call.call, e);
+                return None;
+            }
+        };
+
+        let payload = subxt::dynamic::runtime_api_call(
+            "TransactionPaymentCallApi",
+            "query_call_info",
+            vec![call.runtime_call(), Value::u128(len as u128)],
+        );
+        match client.runtime_api().at(hash).call(payload).await {
+            Ok(info) => info
+                .to_value()
+                .ok()
+                .and_then(|info| json_u128(&value_to_json(&info)["partial_fee"])),
+            Err(e) => {
+                debug!("Fee query for {}.{} failed: {}", call.pallet, call.call, e);
+                None
+            }
+        }
+    }
+}
+
+/// Dispatch error and events of a decoded `DryRunApi::dry_run_call` result
+fn parse_effects(
+    result: &JsonValue,
+    block_number: u64,
+    block_hash: &str,
+) -> Result<(Option<String>, Vec<MatchedEvent>)> {
+    let effects = match (&result["Ok"], &result["Err"]) {
+        (JsonValue::Null, JsonValue::Null) => {
+            return Err(Error::Encoding(format!(
+                "Unexpected dry-run result: {}",
+                result
+            )))
+        }
+        (JsonValue::Null, error) => {
+            return Err(Error::Transaction(format!(
+                "Dry run rejected: {}",
+                dispatch_error(error)
+            )))
+        }
+        (effects, _) => effects,
+    };
+
+    let execution = &effects["execution_result"];
+    let error = match &execution["Err"] {
+        JsonValue::Null if execution["Ok"].is_null() => {
+            Some(format!("Unexpected execution result: {}", execution))
+        }
+        JsonValue::Null => None,
+        failure => Some(dispatch_error(&failure["error"])),
+    };
+
+    // a one-element vector decodes to the element itself
+    let events = match &effects["emitted_events"] {
+        JsonValue::Array(events) => events.iter().collect(),
+        JsonValue::Object(_) => vec![&effects["emitted_events"]],
+        _ => Vec::new(),
+    };
+    let events = events
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, event)| {
+            let (pallet, inner) = event.as_object()?.iter().next()?;
+            let (variant, fields) = match inner {
+                JsonValue::String(variant) => (variant.clone(), JsonValue::Null),
+                JsonValue::Object(variant) => {
+                    let (name, fields) = variant.iter().next()?;
+                    (name.clone(), fields.clone())
+                }
+                _ => return None,
+            };
+            Some(MatchedEvent {
+                block_number,
+                block_hash: block_hash.to_string(),
+                event_index: index as u32,
+                extrinsic_index: None,
+                pallet: pallet.clone(),
+                variant,
+                fields,
+            })
+        })
+        .collect();
+
+    Ok((error, events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_effects() {
+        let success = json!({"Ok": {
+            "execution_result": {"Ok": {"actual_weight": null, "pays_fee": "Yes"}},
+            "emitted_events": [
+                {"Balances": {"Withdraw": {"who": "0x01", "amount": 16}}},
+                {"Balances": {"Transfer": {"from": "0x01", "to": "0x02", "amount": 10}}},
+                {"Utility": "BatchCompleted"},
+            ],
+            "local_xcm": null,
+            "forwarded_xcms": [],
+        }});
+        let (error, events) = parse_effects(&success, 7, "0xab").unwrap();
+        assert_eq!(error, None);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[1].variant, "Transfer");
+        assert_eq!(events[1].fields["amount"], 10);
+        assert_eq!(events[2].pallet, "Utility");
+        assert_eq!(events[2].event_index, 2);
+
+        let failure = json!({"Ok": {
+            "execution_result": {"Err": {
+                "post_info": {"actual_weight": null, "pays_fee": "Yes"},
+                "error": {"Token": "FundsUnavailable"},
+            }},
+            "emitted_events": {"Balances": {"Withdraw": {"who": "0x01", "amount": 16}}},
+        }});
+        let (error, events) = parse_effects(&failure, 7, "0xab").unwrap();
+        assert_eq!(error.as_deref(), Some("Token(FundsUnavailable)"));
+        assert_eq!(events.len(), 1);
+
+        assert!(parse_effects(&json!({"Err": "Unimplemented"}), 7, "0xab").is_err());
+        assert!(parse_effects(&json!(null), 7, "0xab").is_err());
+    }
+}
@@ -0,0 +1,542 @@
+//! Slash and offence monitoring
+//!
+//! [`SlashMonitor`] follows finalized blocks and raises a [`StakingAlert`] for:
+//! - `Staking::Slashed` and `Staking::SlashReported` events
+//! - `Offences::Offence` events
+//! - Equivocation reports submitted to the `Grandpa` and `Babe` pallets
+//!
+//! Alerts are delivered to every registered [`AlertHandler`]. Closures taking a
+//! `&StakingAlert` work as handlers:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{SlashMonitor, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! SlashMonitor::new(adapter)
+//!     .watch("14ShUZUYUR35RBZW6uVVt1zXDxmSQddkeDdXf1JkMA6P721N")
+//!     .on_alert(|alert: &apex_sdk_substrate::StakingAlert| {
+//!         eprintln!("#{}: {:?}", alert.block_number, alert.payload);
+//!     })
+//!     .run()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::{account_hex, composite_to_json, decode_events, MatchedEvent};
+use crate::{Error, Result, SubstrateAdapter};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+use subxt::blocks::Block;
+use subxt::{OnlineClient, PolkadotConfig};
+use tracing::{debug, info};
+
+/// Consensus engine an equivocation was reported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Consensus {
+    /// GRANDPA finality
+    Grandpa,
+    /// BABE block production
+    Babe,
+}
+
+/// Typed alert payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertPayload {
+    /// Stake was slashed (`Staking::Slashed`)
+    Slashed {
+        /// Slashed validator or nominator
+        staker: String,
+        /// Slashed amount
+        amount: u128,
+    },
+    /// A slash was reported and will be applied after the deferral period
+    /// (`Staking::SlashReported`)
+    SlashReported {
+        /// Offending validator
+        validator: String,
+        /// Slash fraction in parts per billion
+        fraction_perbill: u32,
+        /// Era the slash applies to
+        slash_era: u32,
+    },
+    /// An offence was reported (`Offences::Offence`)
+    Offence {
+        /// Offence kind, e.g. `babe:equivocatio`
+        kind: String,
+        /// Hex encoded time slot of the offence
+        time_slot: String,
+    },
+    /// An equivocation proof was submitted on chain
+    Equivocation {
+        /// Consensus engine the proof was submitted to
+        consensus: Consensus,
+        /// Call used to submit the proof
+        call: String,
+        /// Offending authority, when found in the proof
+        offender: Option<String>,
+    },
+    /// A validator has neither sent a heartbeat nor authored a block late
+    /// into the session
+    HeartbeatOverdue {
+        /// Silent validator
+        validator: String,
+        /// Session index
+        session: u32,
+        /// Blocks since the session started
+        blocks_into_session: u64,
+    },
+    /// A session ended without a heartbeat or block from a validator
+    MissedHeartbeat {
+        /// Silent validator
+        validator: String,
+        /// Session that ended
+        session: u32,
+    },
+}
+
+impl AlertPayload {
+    /// Account the alert is about, if the payload names one
+    ///
+    /// For equivocations this is the offending authority's session key.
+    pub fn account(&self) -> Option<&str> {
+        match self {
+            AlertPayload::Slashed { staker, .. } => Some(staker),
+            AlertPayload::SlashReported { validator, .. } => Some(validator),
+            AlertPayload::Offence { .. } => None,
+            AlertPayload::Equivocation { offender, .. } => offender.as_deref(),
+            AlertPayload::HeartbeatOverdue { validator, .. }
+            | AlertPayload::MissedHeartbeat { validator, .. } => Some(validator),
+        }
+    }
+}
+
+/// An alert raised for a finalized block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakingAlert {
+    /// Block number
+    pub block_number: u64,
+    /// Block hash (hex)
+    pub block_hash: String,
+    /// Index of the extrinsic that caused the alert, if any
+    pub extrinsic_index: Option<u32>,
+    /// Alert details
+    pub payload: AlertPayload,
+}
+
+/// Receiver of staking alerts
+#[async_trait]
+pub trait AlertHandler: Send + Sync {
+    /// Called once for every alert, in block order
+    async fn on_alert(&self, alert: &StakingAlert);
+}
+
+#[async_trait]
+impl<F> AlertHandler for F
+where
+    F: Fn(&StakingAlert) + Send + Sync,
+{
+    async fn on_alert(&self, alert: &StakingAlert) {
+        self(alert)
+    }
+}
+
+/// Follows finalized blocks and reports slashes and offences
+#[derive(Clone)]
+pub struct SlashMonitor {
+    client: OnlineClient<PolkadotConfig>,
+    watched: Vec<String>,
+    handlers: Vec<Arc<dyn AlertHandler>>,
+}
+
+impl SlashMonitor {
+    /// Create a monitor over a connected adapter
+    pub fn new(adapter: &SubstrateAdapter) -> Self {
+        Self {
+            client: adapter.client().clone(),
+            watched: Vec::new(),
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Only report alerts about this account (SS58 or hex)
+    ///
+    /// Offences and equivocations name session keys rather than stash accounts,
+    /// so they are always reported.
+    pub fn watch(mut self, address: impl Into<String>) -> Self {
+        self.watched.push(address.into());
+        self
+    }
+
+    /// Register an alert handler
+    pub fn on_alert(mut self, handler: impl AlertHandler + 'static) -> Self {
+        self.handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Follow finalized blocks until the subscription ends
+    ///
+    /// Returns an error as soon as a block cannot be inspected, so callers can
+    /// reconnect instead of silently skipping a slash.
+    pub async fn run(&self) -> Result<()> {
+        let watched = self
+            .watched
+            .iter()
+            .map(|address| account_hex(address))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut blocks = self
+            .client
+            .blocks()
+            .subscribe_finalized()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to subscribe to blocks: {}", e)))?;
+
+        info!("Monitoring finalized blocks for slashes and offences");
+
+        while let Some(block) = blocks.next().await {
+            let block = block
+                .map_err(|e| Error::Connection(format!("Block subscription failed: {}", e)))?;
+
+            for alert in self.block_alerts(&block).await? {
+                if !is_watched(&alert, &watched) {
+                    continue;
+                }
+                for handler in &self.handlers {
+                    handler.on_alert(&alert).await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn block_alerts(
+        &self,
+        block: &Block<PolkadotConfig, OnlineClient<PolkadotConfig>>,
+    ) -> Result<Vec<StakingAlert>> {
+        let number = block.number() as u64;
+        let block_hash = format!("0x{}", hex::encode(block.hash().0));
+        debug!("Checking block {} for staking alerts", number);
+
+        let events = block.events().await.map_err(|e| {
+            Error::Connection(format!("Failed to get events of block {}: {}", number, e))
+        })?;
+        let mut alerts = alerts_from_events(&decode_events(&events, number, &block_hash)?);
+
+        let extrinsics = block.extrinsics().await.map_err(|e| {
+            Error::Connection(format!(
+                "Failed to get extrinsics of block {}: {}",
+                number, e
+            ))
+        })?;
+        for extrinsic in extrinsics.iter() {
+            let (Ok(pallet), Ok(call)) = (extrinsic.pallet_name(), extrinsic.variant_name()) else {
+                continue;
+            };
+            let fields = extrinsic
+                .field_values()
+                .map(|fields| composite_to_json(&fields))
+                .unwrap_or(JsonValue::Null);
+
+            if let Some(payload) = equivocation_payload(pallet, call, &fields) {
+                alerts.push(StakingAlert {
+                    block_number: number,
+                    block_hash: block_hash.clone(),
+                    extrinsic_index: Some(extrinsic.index()),
+                    payload,
+                });
+            }
+        }
+
+        Ok(alerts)
+    }
+}
+
+impl std::fmt::Debug for SlashMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SlashMonitor")
+            .field("watched", &self.watched)
+            .field("handlers", &self.handlers.len())
+            .finish()
+    }
+}
+
+/// Build alerts from decoded block events
+fn alerts_from_events(events: &[MatchedEvent]) -> Vec<StakingAlert> {
+    events
+        .iter()
+        .filter_map(|event| {
+            event_payload(event).map(|payload| StakingAlert {
+                block_number: event.block_number,
+                block_hash: event.block_hash.clone(),
+                extrinsic_index: event.extrinsic_index,
+                payload,
+            })
+        })
+        .collect()
+}
+
+fn event_payload(event: &MatchedEvent) -> Option<AlertPayload> {
+    let fields = &event.fields;
+    match (event.pallet.as_str(), event.variant.as_str()) {
+        ("Staking", "Slashed") => Some(AlertPayload::Slashed {
+            staker: field(fields, "staker", 0)?.as_str()?.to_string(),
+            amount: json_u128(field(fields, "amount", 1)?)?,
+        }),
+        ("Staking", "SlashReported") => Some(AlertPayload::SlashReported {
+            validator: field(fields, "validator", 0)?.as_str()?.to_string(),
+            fraction_perbill: json_u128(field(fields, "fraction", 1)?)? as u32,
+            slash_era: json_u128(field(fields, "slash_era", 2)?)? as u32,
+        }),
+        ("Offences", "Offence") => {
+            let kind = json_bytes(field(fields, "kind", 0)?)?;
+            let time_slot = json_bytes(field(fields, "timeslot", 1)?)?;
+            Some(AlertPayload::Offence {
+                kind: String::from_utf8_lossy(&kind)
+                    .trim_end_matches('\0')
+                    .to_string(),
+                time_slot: format!("0x{}", hex::encode(time_slot)),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn equivocation_payload(pallet: &str, call: &str, fields: &JsonValue) -> Option<AlertPayload> {
+    let consensus = match pallet {
+        "Grandpa" => Consensus::Grandpa,
+        "Babe" => Consensus::Babe,
+        _ => return None,
+    };
+    if !call.starts_with("report_equivocation") {
+        return None;
+    }
+
+    // BABE proofs carry `offender`, GRANDPA votes carry `identity`
+    let offender = find_key(fields, "offender")
+        .or_else(|| find_key(fields, "identity"))
+        .and_then(|value| value.as_str())
+        .map(|s| s.to_string());
+
+    Some(AlertPayload::Equivocation {
+        consensus,
+        call: call.to_string(),
+        offender,
+    })
+}
+
+fn is_watched(alert: &StakingAlert, watched: &[String]) -> bool {
+    if watched.is_empty() {
+        return true;
+    }
+    match &alert.payload {
+        AlertPayload::Slashed {
+            staker: account, ..
+        }
+        | AlertPayload::SlashReported {
+            validator: account, ..
+        }
+        | AlertPayload::HeartbeatOverdue {
+            validator: account, ..
+        }
+        | AlertPayload::MissedHeartbeat {
+            validator: account, ..
+        } => watched.iter().any(|hex| hex.eq_ignore_ascii_case(account)),
+        AlertPayload::Offence { .. } | AlertPayload::Equivocation { .. } => true,
+    }
+}
+
+/// Named field, falling back to position for runtimes with unnamed event fields
+fn field<'a>(fields: &'a JsonValue, name: &str, position: usize) -> Option<&'a JsonValue> {
+    match fields {
+        JsonValue::Object(map) => map.get(name),
+        JsonValue::Array(values) => values.get(position),
+        _ => None,
+    }
+}
+
+fn find_key<'a>(value: &'a JsonValue, key: &str) -> Option<&'a JsonValue> {
+    match value {
+        JsonValue::Object(map) => map
+            .get(key)
+            .or_else(|| map.values().find_map(|v| find_key(v, key))),
+        JsonValue::Array(values) => values.iter().find_map(|v| find_key(v, key)),
+        _ => None,
+    }
+}
+
+fn json_u128(value: &JsonValue) -> Option<u128> {
+    match value {
+        JsonValue::Number(n) => n.as_u64().map(u128::from),
+        JsonValue::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn json_bytes(value: &JsonValue) -> Option<Vec<u8>> {
+    match value {
+        JsonValue::Array(values) => values
+            .iter()
+            .map(|v| v.as_u64().and_then(|n| u8::try_from(n).ok()))
+            .collect(),
+        JsonValue::String(s) => hex::decode(s.strip_prefix("0x")?).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(pallet: &str, variant: &str, fields: JsonValue) -> MatchedEvent {
+        MatchedEvent {
+            block_number: 10,
+            block_hash: format!("0x{}", "00".repeat(32)),
+            event_index: 3,
+            extrinsic_index: None,
+            pallet: pallet.to_string(),
+            variant: variant.to_string(),
+            fields,
+        }
+    }
+
+    #[test]
+    fn test_staking_event_payloads() {
+        let validator = format!("0x{}", "aa".repeat(32));
+        let alerts = alerts_from_events(&[
+            event(
+                "Staking",
+                "Slashed",
+                json!({ "staker": validator, "amount": u128::MAX.to_string() }),
+            ),
+            event("Staking", "Slashed", json!([validator, 500])),
+            event(
+                "Staking",
+                "SlashReported",
+                json!({ "validator": validator, "fraction": 1000, "slash_era": 42 }),
+            ),
+            event("Balances", "Transfer", json!({})),
+        ]);
+
+        assert_eq!(alerts.len(), 3);
+        assert_eq!(
+            alerts[0].payload,
+            AlertPayload::Slashed {
+                staker: validator.clone(),
+                amount: u128::MAX
+            }
+        );
+        assert_eq!(
+            alerts[1].payload,
+            AlertPayload::Slashed {
+                staker: validator.clone(),
+                amount: 500
+            }
+        );
+        assert_eq!(
+            alerts[2].payload,
+            AlertPayload::SlashReported {
+                validator,
+                fraction_perbill: 1000,
+                slash_era: 42
+            }
+        );
+    }
+
+    #[test]
+    fn test_offence_payload() {
+        let kind: Vec<u8> = b"babe:equivocatio".to_vec();
+        let alerts = alerts_from_events(&[event(
+            "Offences",
+            "Offence",
+            json!({ "kind": kind, "timeslot": [1, 2] }),
+        )]);
+
+        assert_eq!(
+            alerts[0].payload,
+            AlertPayload::Offence {
+                kind: "babe:equivocatio".to_string(),
+                time_slot: "0x0102".to_string()
+            }
+        );
+        assert!(is_watched(&alerts[0], &["0xbb".to_string()]));
+    }
+
+    #[test]
+    fn test_equivocation_payload() {
+        let offender = format!("0x{}", "cc".repeat(32));
+        let babe = equivocation_payload(
+            "Babe",
+            "report_equivocation_unsigned",
+            &json!({ "equivocation_proof": { "offender": offender, "slot": 5 } }),
+        )
+        .unwrap();
+        assert_eq!(babe.account(), Some(offender.as_str()));
+
+        let grandpa = equivocation_payload(
+            "Grandpa",
+            "report_equivocation",
+            &json!({ "equivocation_proof": { "equivocation": { "Prevote": { "identity": offender } } } }),
+        )
+        .unwrap();
+        assert!(matches!(
+            grandpa,
+            AlertPayload::Equivocation {
+                consensus: Consensus::Grandpa,
+                ..
+            }
+        ));
+        assert_eq!(grandpa.account(), Some(offender.as_str()));
+
+        assert!(equivocation_payload("Grandpa", "note_stalled", &json!({})).is_none());
+        assert!(equivocation_payload("Staking", "report_equivocation", &json!({})).is_none());
+    }
+
+    #[test]
+    fn test_watch_filter() {
+        let alice = format!("0x{}", "aa".repeat(32));
+        let alert = StakingAlert {
+            block_number: 1,
+            block_hash: String::new(),
+            extrinsic_index: None,
+            payload: AlertPayload::Slashed {
+                staker: alice.clone(),
+                amount: 1,
+            },
+        };
+
+        assert!(is_watched(&alert, &[]));
+        assert!(is_watched(
+            &alert,
+            &[alice.to_uppercase().replacen("0X", "0x", 1)]
+        ));
+        assert!(!is_watched(&alert, &[format!("0x{}", "bb".repeat(32))]));
+    }
+
+    #[tokio::test]
+    async fn test_closure_handler() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let handler: Arc<dyn AlertHandler> = Arc::new(move |alert: &StakingAlert| {
+            sink.lock().unwrap().push(alert.block_number);
+        });
+
+        let alert = StakingAlert {
+            block_number: 7,
+            block_hash: String::new(),
+            extrinsic_index: None,
+            payload: AlertPayload::Offence {
+                kind: String::new(),
+                time_slot: String::new(),
+            },
+        };
+        handler.on_alert(&alert).await;
+
+        assert_eq!(*seen.lock().unwrap(), vec![7]);
+    }
+}
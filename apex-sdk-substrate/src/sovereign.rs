@@ -0,0 +1,311 @@
+//! Funding sovereign and derivative accounts
+//!
+//! XCM sent by a parachain, or by an account on it, pays its fees on the
+//! destination from an account derived from the origin: the parachain's
+//! sovereign account, or a derivative account hashed from the origin
+//! location. [`SovereignFunding`] computes that account on the destination,
+//! checks its balance against the planned XCM operations and bundles the
+//! transfer that covers the shortfall:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{SovereignFunding, SubstrateAdapter, Wallet};
+//!
+//! # async fn example(asset_hub: &SubstrateAdapter, treasurer: &Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! let funding = SovereignFunding::for_para(asset_hub, 2034)
+//!     .with_operation("create pool", 2_000_000_000)
+//!     .with_operation("transact fees", 500_000_000);
+//!
+//! let plan = funding.plan().await?;
+//! println!("{} holds {}, needs {}", plan.account, plan.balance, plan.required);
+//! if let Some(tx_hash) = funding.fund(treasurer).await? {
+//!     println!("topped up with {}", tx_hash);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Amounts are in the destination's balances token. The required amount
+//! keeps the existential deposit on top of the operations, so paying for
+//! them never reaps the account.
+
+use crate::account::SovereignAccount;
+use crate::xcm::{Junction, MultiLocation};
+use crate::xcm_transact::RemoteCall;
+use crate::{Error, Result, SubstrateAdapter, Wallet};
+use parity_scale_codec::{Compact, Encode};
+use subxt::dynamic::Value;
+use tracing::info;
+
+/// Extra required on top of the planned operations, in basis points
+pub const DEFAULT_BUFFER_BPS: u32 = 1_000;
+
+/// Account that XCM from `origin`, a location relative to the destination,
+/// is charged to on the destination
+///
+/// Parachains map to their sovereign account; accounts on the relay chain
+/// or a parachain map to their derivative account, as runtimes configured
+/// with `HashedDescription<DescribeFamily<DescribeAllTerminal>>` derive it.
+/// `None` for origins neither convention covers.
+pub fn location_account(origin: &MultiLocation) -> Option<[u8; 32]> {
+    let description = match (origin.parents, origin.interior.as_slice()) {
+        (0, [Junction::Parachain(para_id)]) => {
+            return Some(SovereignAccount::child(*para_id).account_id())
+        }
+        (1, [Junction::Parachain(para_id)]) => {
+            return Some(SovereignAccount::sibling(*para_id).account_id())
+        }
+        (0, [Junction::Parachain(para_id), rest @ ..]) => {
+            (b"ChildChain", Compact(*para_id), describe_terminal(rest)?).encode()
+        }
+        (1, [Junction::Parachain(para_id), rest @ ..]) => {
+            (b"SiblingChain", Compact(*para_id), describe_terminal(rest)?).encode()
+        }
+        (1, rest) => (b"ParentChain", describe_terminal(rest)?).encode(),
+        _ => return None,
+    };
+    Some(sp_core::blake2_256(&description))
+}
+
+fn describe_terminal(interior: &[Junction]) -> Option<Vec<u8>> {
+    match interior {
+        [] => Some(Vec::new()),
+        [Junction::AccountId32 { id, .. }] => Some((b"AccountId32", id).encode()),
+        [Junction::AccountId20 { key, .. }] => Some((b"AccountKey20", key).encode()),
+        _ => None,
+    }
+}
+
+/// Balance of an account against what planned operations need
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FundingPlan {
+    /// The funded account (hex)
+    pub account: String,
+    /// Transferable balance
+    pub balance: u128,
+    /// Existential deposit of the destination
+    pub existential_deposit: u128,
+    /// Operations plus buffer plus existential deposit
+    pub required: u128,
+    /// What the account is short of `required`; zero when funded
+    pub shortfall: u128,
+}
+
+impl FundingPlan {
+    /// Plan for `account` holding `balance` before `operations`, with
+    /// `buffer_bps` extra on top
+    pub fn compute(
+        account: [u8; 32],
+        balance: u128,
+        existential_deposit: u128,
+        operations: &[(String, u128)],
+        buffer_bps: u32,
+    ) -> Self {
+        let total = operations
+            .iter()
+            .fold(0u128, |total, (_, amount)| total.saturating_add(*amount));
+        let buffer = total.saturating_mul(buffer_bps as u128) / 10_000;
+        let required = total
+            .saturating_add(buffer)
+            .saturating_add(existential_deposit);
+        Self {
+            account: format!("0x{}", hex::encode(account)),
+            balance,
+            existential_deposit,
+            required,
+            shortfall: required.saturating_sub(balance),
+        }
+    }
+
+    /// Whether the account holds enough
+    pub fn is_funded(&self) -> bool {
+        self.shortfall == 0
+    }
+}
+
+/// Funding of the account an origin pays XCM from on a destination chain
+pub struct SovereignFunding<'a> {
+    dest: &'a SubstrateAdapter,
+    origin: MultiLocation,
+    operations: Vec<(String, u128)>,
+    buffer_bps: u32,
+}
+
+impl<'a> SovereignFunding<'a> {
+    /// Funding of the account XCM from `origin`, relative to `dest`, pays from
+    pub fn new(dest: &'a SubstrateAdapter, origin: MultiLocation) -> Self {
+        Self {
+            dest,
+            origin,
+            operations: Vec::new(),
+            buffer_bps: DEFAULT_BUFFER_BPS,
+        }
+    }
+
+    /// Funding of the sovereign account of `para_id` on `dest`, a relay
+    /// chain or a sibling parachain
+    pub fn for_para(dest: &'a SubstrateAdapter, para_id: u32) -> Self {
+        let parents = if dest.pallets().has("ParachainInfo") {
+            1
+        } else {
+            0
+        };
+        Self::new(
+            dest,
+            MultiLocation::new(parents, vec![Junction::Parachain(para_id)]),
+        )
+    }
+
+    /// Plan an operation costing `amount`
+    pub fn with_operation(mut self, label: impl Into<String>, amount: u128) -> Self {
+        self.operations.push((label.into(), amount));
+        self
+    }
+
+    /// Plan the fees `remote` withdraws on the destination
+    ///
+    /// Calls without fees ask for unpaid execution and cost nothing.
+    pub fn with_remote_call(self, remote: &RemoteCall) -> Self {
+        match remote.fee_amount() {
+            Some(amount) => self.with_operation("transact fees", amount),
+            None => self,
+        }
+    }
+
+    /// Extra to require on top of the operations, in basis points
+    pub fn with_buffer_bps(mut self, buffer_bps: u32) -> Self {
+        self.buffer_bps = buffer_bps;
+        self
+    }
+
+    /// The funded account on the destination
+    pub fn account(&self) -> Result<[u8; 32]> {
+        location_account(&self.origin)
+            .ok_or_else(|| Error::Other(format!("No account derivation for {:?}", self.origin)))
+    }
+
+    /// Planned operations with their amounts
+    pub fn operations(&self) -> &[(String, u128)] {
+        &self.operations
+    }
+
+    /// Balance of the account against the planned operations
+    pub async fn plan(&self) -> Result<FundingPlan> {
+        let account = self.account()?;
+        let storage = self.dest.storage();
+        let info = storage
+            .get_account_info(&format!("0x{}", hex::encode(account)))
+            .await?;
+        Ok(FundingPlan::compute(
+            account,
+            info.transferable(),
+            storage.get_existential_deposit()?,
+            &self.operations,
+            self.buffer_bps,
+        ))
+    }
+
+    /// `Balances::transfer_keep_alive` call covering the shortfall of `plan`;
+    /// `None` when it is funded
+    pub fn funding_call(&self, plan: &FundingPlan) -> Result<Option<subxt::tx::DynamicPayload>> {
+        if plan.is_funded() {
+            return Ok(None);
+        }
+        Ok(Some(subxt::dynamic::tx(
+            "Balances",
+            "transfer_keep_alive",
+            funding_args(plan)?,
+        )))
+    }
+
+    /// Top up the account from `wallet` if it is short; returns the
+    /// transaction hash, or `None` when it already holds enough
+    pub async fn fund(&self, wallet: &Wallet) -> Result<Option<String>> {
+        let plan = self.plan().await?;
+        if plan.is_funded() {
+            return Ok(None);
+        }
+        info!(
+            "Funding {} with {} for {} planned operations",
+            plan.account,
+            plan.shortfall,
+            self.operations.len()
+        );
+        self.dest
+            .transaction_executor()
+            .submit_call(
+                wallet,
+                "Balances",
+                "transfer_keep_alive",
+                funding_args(&plan)?,
+            )
+            .await
+            .map(Some)
+    }
+}
+
+fn funding_args(plan: &FundingPlan) -> Result<Vec<Value>> {
+    let account = hex::decode(plan.account.trim_start_matches("0x"))
+        .map_err(|e| Error::Encoding(format!("Invalid account {}: {}", plan.account, e)))?;
+    Ok(vec![
+        Value::unnamed_variant("Id", vec![Value::from_bytes(account)]),
+        Value::u128(plan.shortfall),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_location_account() {
+        assert_eq!(
+            location_account(&MultiLocation::parachain(2034)),
+            Some(SovereignAccount::sibling(2034).account_id())
+        );
+        assert_eq!(
+            location_account(&MultiLocation::new(0, vec![Junction::Parachain(2034)])),
+            Some(SovereignAccount::child(2034).account_id())
+        );
+
+        let alice = [0xd4; 32];
+        let derivative = location_account(&MultiLocation::parachain_account(2034, alice)).unwrap();
+        let description = (
+            b"SiblingChain",
+            Compact(2034u32),
+            (b"AccountId32", alice).encode(),
+        );
+        assert_eq!(derivative, sp_core::blake2_256(&description.encode()));
+        assert_ne!(
+            Some(derivative),
+            location_account(&MultiLocation::new(
+                1,
+                vec![Junction::AccountId32 {
+                    network: None,
+                    id: alice
+                }]
+            ))
+        );
+
+        // only a single terminal account is described
+        assert_eq!(
+            location_account(&MultiLocation::new(1, vec![Junction::PalletInstance(50)])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_funding_plan() {
+        let operations = vec![
+            ("create pool".to_string(), 2_000),
+            ("transact fees".to_string(), 500),
+        ];
+        let plan = FundingPlan::compute([1; 32], 1_000, 100, &operations, DEFAULT_BUFFER_BPS);
+        assert_eq!(plan.required, 2_500 + 250 + 100);
+        assert_eq!(plan.shortfall, 1_850);
+        assert!(!plan.is_funded());
+
+        let plan = FundingPlan::compute([1; 32], 5_000, 100, &operations, 0);
+        assert_eq!(plan.shortfall, 0);
+        assert!(plan.is_funded());
+    }
+}
@@ -0,0 +1,497 @@
+//! Sponsored transactions: one account signs, another pays the fee
+//!
+//! Substrate charges fees to the signer of an extrinsic, so a sponsor pays
+//! for a user's call by submitting it as the user's proxy. The user
+//! authorizes the sponsor once with [`Sponsor::authorize`] and then signs each
+//! call off chain as a [`SponsoredCall`]; the [`Sponsor`] checks the
+//! signature, chain, expiry and replay before wrapping the call in
+//! `Proxy::proxy` and paying for it:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{Sponsor, SponsoredCall, SubstrateAdapter, Wallet};
+//! use subxt::dynamic::Value;
+//!
+//! # async fn example(adapter: &SubstrateAdapter, user: &Wallet, relayer: Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! // once, paid by the user
+//! Sponsor::authorize(adapter, user, &relayer.address(), "Any").await?;
+//!
+//! // per call: the user signs, the relayer pays
+//! let dest = Value::unnamed_variant("Id", [Value::from_bytes([0u8; 32])]);
+//! let call = SponsoredCall::sign(
+//!     adapter,
+//!     user,
+//!     "Balances",
+//!     "transfer_keep_alive",
+//!     vec![dest, Value::u128(1_000_000_000)],
+//!     1,
+//!     adapter.spec_client().finalized_number().await? + 100,
+//! )?;
+//! let sponsor = Sponsor::new(adapter, relayer)?.allow("Balances", "transfer_keep_alive");
+//! let tx_hash = sponsor.relay(&call).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Support by target chain:
+//!
+//! | Chains | Supported |
+//! |--------|-----------|
+//! | Polkadot, Kusama, Westend, Paseo and their system parachains | yes, `Proxy` pallet |
+//! | Parachains and solochains with the `Proxy` pallet | yes |
+//! | Chains without the `Proxy` pallet | no: [`Sponsor::new`] fails with [`Error::PalletNotAvailable`] |
+//!
+//! The proxy type the user grants bounds what the sponsor can dispatch on
+//! chain; the signed [`SponsoredCall`] is what the sponsor's service checks
+//! before spending its funds. Only SR25519 and ED25519 users can sign
+//! sponsored calls.
+//!
+//! Relayed calls are claimed in a [`RelayedCallStore`] before they are
+//! submitted. The default [`MemoryRelayedCallStore`] forgets them on restart
+//! and is not shared between relayer instances, so a call could then be
+//! relayed again until it expires; give sponsors that restart or run side by
+//! side a [`FileRelayedCallStore`] on a shared directory with
+//! [`Sponsor::with_relayed_store`].
+
+use crate::event_query::{account_hex, account_id, hex_bytes};
+use crate::permissions::proxy_types;
+use crate::wallet::Wallet;
+use crate::{Error, Result, SubstrateAdapter};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sp_core::{ed25519, sr25519, Pair};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use subxt::dynamic::Value;
+use subxt::ext::scale_value::{self, ValueDef};
+use tracing::info;
+
+/// Prefix of the message a user signs, so the signature can't be replayed as
+/// anything else
+const SPONSORED_CALL_CONTEXT: &[u8] = b"apex-sponsored-call:";
+
+/// A call signed by a user for a sponsor to submit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SponsoredCall {
+    /// Account the call is dispatched as (hex)
+    pub signer: String,
+    /// SCALE-encoded call (hex)
+    pub call_data: String,
+    /// Genesis hash of the chain the call is for (hex)
+    pub genesis_hash: String,
+    /// Number chosen by the user to tell calls apart; each is relayed once
+    pub nonce: u64,
+    /// Last block number the call may be relayed at
+    pub valid_until: u64,
+    /// SR25519 or ED25519 signature of [`message`](Self::message) (hex)
+    pub signature: String,
+}
+
+impl SponsoredCall {
+    /// Sign `pallet::call` as `wallet` for a sponsor to relay
+    pub fn sign(
+        adapter: &SubstrateAdapter,
+        wallet: &Wallet,
+        pallet: &str,
+        call: &str,
+        args: Vec<Value>,
+        nonce: u64,
+        valid_until: u64,
+    ) -> Result<Self> {
+        let client = adapter.client();
+        let call_data = client
+            .tx()
+            .call_data(&subxt::dynamic::tx(pallet, call, args))
+            .map_err(|e| {
+                Error::Encoding(format!("Failed to encode {}::{}: {}", pallet, call, e))
+            })?;
+        let mut sponsored = Self {
+            signer: account_hex(&wallet.address())?,
+            call_data: format!("0x{}", hex::encode(call_data)),
+            genesis_hash: format!("0x{}", hex::encode(client.genesis_hash())),
+            nonce,
+            valid_until,
+            signature: String::new(),
+        };
+        sponsored.signature = format!("0x{}", hex::encode(wallet.sign(&sponsored.message()?)));
+        Ok(sponsored)
+    }
+
+    /// Bytes the user signs: context, genesis hash, signer, nonce, expiry and
+    /// call
+    pub fn message(&self) -> Result<Vec<u8>> {
+        let mut message = SPONSORED_CALL_CONTEXT.to_vec();
+        message.extend(hex_bytes(&self.genesis_hash, "genesis hash")?);
+        message.extend(account_id(&self.signer)?);
+        message.extend(self.nonce.to_le_bytes());
+        message.extend(self.valid_until.to_le_bytes());
+        message.extend(hex_bytes(&self.call_data, "call data")?);
+        Ok(message)
+    }
+
+    /// Whether the signature is the signer's, for SR25519 or ED25519 keys
+    pub fn verify(&self) -> Result<bool> {
+        let message = self.message()?;
+        let account = account_id(&self.signer)?;
+        let Ok(signature) = <[u8; 64]>::try_from(hex_bytes(&self.signature, "signature")?) else {
+            return Ok(false);
+        };
+        Ok(sr25519::Pair::verify(
+            &sr25519::Signature::from_raw(signature),
+            &message,
+            &sr25519::Public::from_raw(account),
+        ) || ed25519::Pair::verify(
+            &ed25519::Signature::from_raw(signature),
+            &message,
+            &ed25519::Public::from_raw(account),
+        ))
+    }
+}
+
+/// Record of the sponsored calls relayed, so each is relayed once
+#[async_trait]
+pub trait RelayedCallStore: Send + Sync {
+    /// Claim the call `nonce` of `signer` (account id) for relaying
+    ///
+    /// Returns false if it was claimed before. Checking and claiming have to
+    /// be one atomic step.
+    async fn claim(&self, signer: &[u8; 32], nonce: u64) -> Result<bool>;
+
+    /// Give up a claim whose relay failed
+    async fn release(&self, signer: &[u8; 32], nonce: u64) -> Result<()>;
+}
+
+/// Signer and nonce identifying a relayed call
+type RelayedCall = ([u8; 32], u64);
+
+/// Keeps claims in memory; they are lost on restart
+#[derive(Debug, Clone, Default)]
+pub struct MemoryRelayedCallStore {
+    claimed: Arc<parking_lot::Mutex<HashSet<RelayedCall>>>,
+}
+
+#[async_trait]
+impl RelayedCallStore for MemoryRelayedCallStore {
+    async fn claim(&self, signer: &[u8; 32], nonce: u64) -> Result<bool> {
+        Ok(self.claimed.lock().insert((*signer, nonce)))
+    }
+
+    async fn release(&self, signer: &[u8; 32], nonce: u64) -> Result<()> {
+        self.claimed.lock().remove(&(*signer, nonce));
+        Ok(())
+    }
+}
+
+/// Keeps claims as files in a directory, one per call
+///
+/// Claims create their file exclusively, so they are atomic across restarts
+/// and across relayers sharing the directory.
+#[derive(Debug, Clone)]
+pub struct FileRelayedCallStore {
+    dir: PathBuf,
+}
+
+impl FileRelayedCallStore {
+    /// Store in `dir`; the directory is created on the first claim
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, signer: &[u8; 32], nonce: u64) -> PathBuf {
+        self.dir.join(format!("{}-{}", hex::encode(signer), nonce))
+    }
+}
+
+#[async_trait]
+impl RelayedCallStore for FileRelayedCallStore {
+    async fn claim(&self, signer: &[u8; 32], nonce: u64) -> Result<bool> {
+        let io = |e: std::io::Error| {
+            Error::Storage(format!("Failed to claim in {}: {}", self.dir.display(), e))
+        };
+        tokio::fs::create_dir_all(&self.dir).await.map_err(io)?;
+        match tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(self.path(signer, nonce))
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(io(e)),
+        }
+    }
+
+    async fn release(&self, signer: &[u8; 32], nonce: u64) -> Result<()> {
+        let path = self.path(signer, nonce);
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(Error::Storage(format!(
+                "Failed to remove {}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+}
+
+/// Pays the fees of calls signed by users who made it their proxy
+pub struct Sponsor<'a> {
+    adapter: &'a SubstrateAdapter,
+    wallet: Wallet,
+    allowed: Vec<(String, String)>,
+    relayed: Arc<dyn RelayedCallStore>,
+}
+
+impl<'a> Sponsor<'a> {
+    /// Sponsor paying from `wallet`
+    ///
+    /// Fails on chains without the `Proxy` pallet.
+    pub fn new(adapter: &'a SubstrateAdapter, wallet: Wallet) -> Result<Self> {
+        adapter.require_pallet("Proxy")?;
+        Ok(Self {
+            adapter,
+            wallet,
+            allowed: Vec::new(),
+            relayed: Arc::new(MemoryRelayedCallStore::default()),
+        })
+    }
+
+    /// Whether the adapter's chain supports sponsored calls
+    pub fn is_supported(adapter: &SubstrateAdapter) -> bool {
+        adapter.pallets().has("Proxy")
+    }
+
+    /// Let `user` make `sponsor` a proxy of type `proxy_type`, e.g. `Any`
+    ///
+    /// Signed and paid by the user; needed once before calls are relayed.
+    pub async fn authorize(
+        adapter: &SubstrateAdapter,
+        user: &Wallet,
+        sponsor: &str,
+        proxy_type: &str,
+    ) -> Result<String> {
+        adapter.require_pallet("Proxy")?;
+        adapter
+            .transaction_executor()
+            .submit_call(
+                user,
+                "Proxy",
+                "add_proxy",
+                vec![
+                    Value::unnamed_variant("Id", [Value::from_bytes(account_id(sponsor)?)]),
+                    Value::unnamed_variant(proxy_type, []),
+                    Value::u128(0),
+                ],
+            )
+            .await
+    }
+
+    /// Only relay `pallet::call`; without any allowed call, every call is
+    /// relayed
+    pub fn allow(mut self, pallet: impl Into<String>, call: impl Into<String>) -> Self {
+        self.allowed.push((pallet.into(), call.into()));
+        self
+    }
+
+    /// Record relayed calls in `store` instead of memory
+    pub fn with_relayed_store(mut self, store: impl RelayedCallStore + 'static) -> Self {
+        self.relayed = Arc::new(store);
+        self
+    }
+
+    /// Check a signed call and submit it through `Proxy::proxy`, paying the
+    /// fee
+    ///
+    /// Returns the extrinsic hash.
+    pub async fn relay(&self, sponsored: &SponsoredCall) -> Result<String> {
+        let genesis_hash = format!("0x{}", hex::encode(self.adapter.client().genesis_hash()));
+        if !sponsored.genesis_hash.eq_ignore_ascii_case(&genesis_hash) {
+            return Err(Error::PolicyViolation(format!(
+                "Sponsored call is for chain {}, not {}",
+                sponsored.genesis_hash, genesis_hash
+            )));
+        }
+        if !sponsored.verify()? {
+            return Err(Error::Signature(format!(
+                "Invalid signature of sponsored call from {}",
+                sponsored.signer
+            )));
+        }
+        let finalized = self.adapter.spec_client().finalized_number().await?;
+        if finalized > sponsored.valid_until {
+            return Err(Error::PolicyViolation(format!(
+                "Sponsored call expired at block {}",
+                sponsored.valid_until
+            )));
+        }
+
+        let call = self.decode_call(&sponsored.call_data)?;
+        let (pallet, call_name) = call_name(&call)
+            .ok_or_else(|| Error::Encoding("Sponsored call is not a runtime call".to_string()))?;
+        if !self.allowed.is_empty()
+            && !self
+                .allowed
+                .iter()
+                .any(|(p, c)| p == &pallet && c == &call_name)
+        {
+            return Err(Error::PolicyViolation(format!(
+                "Sponsor does not pay for {}::{}",
+                pallet, call_name
+            )));
+        }
+
+        let proxies = self
+            .adapter
+            .storage()
+            .query_storage_json(
+                "Proxy",
+                "Proxies",
+                vec![Value::from_bytes(account_id(&sponsored.signer)?)],
+            )
+            .await?;
+        if proxy_types(proxies.as_ref(), &account_hex(&self.wallet.address())?).is_empty() {
+            return Err(Error::PolicyViolation(format!(
+                "{} has not made {} its proxy",
+                sponsored.signer,
+                self.wallet.address()
+            )));
+        }
+
+        // claimed before submitting, so a concurrent relay of the same call fails
+        let signer = account_id(&sponsored.signer)?;
+        if !self.relayed.claim(&signer, sponsored.nonce).await? {
+            return Err(Error::PolicyViolation(format!(
+                "Sponsored call {} from {} was already relayed",
+                sponsored.nonce, sponsored.signer
+            )));
+        }
+        info!(
+            "Relaying {}::{} for {} from {}",
+            pallet,
+            call_name,
+            sponsored.signer,
+            self.wallet.address()
+        );
+        let result = self
+            .adapter
+            .transaction_executor()
+            .submit_call(
+                &self.wallet,
+                "Proxy",
+                "proxy",
+                vec![
+                    Value::unnamed_variant(
+                        "Id",
+                        [Value::from_bytes(account_id(&sponsored.signer)?)],
+                    ),
+                    Value::unnamed_variant("None", []),
+                    call,
+                ],
+            )
+            .await;
+        if result.is_err() {
+            self.relayed.release(&signer, sponsored.nonce).await?;
+        }
+        result
+    }
+
+    /// Decode call data into a `RuntimeCall` value
+    fn decode_call(&self, call_data: &str) -> Result<Value> {
+        let bytes = hex_bytes(call_data, "call data")?;
+        let metadata = self.adapter.client().metadata();
+        let mut input = &bytes[..];
+        let call = scale_value::scale::decode_as_type(
+            &mut input,
+            metadata.outer_enums().call_enum_ty(),
+            metadata.types(),
+        )
+        .map_err(|e| Error::Encoding(format!("Invalid sponsored call: {}", e)))?;
+        if !input.is_empty() {
+            return Err(Error::Encoding(format!(
+                "Invalid sponsored call: {} trailing bytes",
+                input.len()
+            )));
+        }
+        Ok(call.remove_context())
+    }
+}
+
+/// Pallet and call name of a `RuntimeCall` value
+fn call_name(call: &Value) -> Option<(String, String)> {
+    let ValueDef::Variant(pallet) = &call.value else {
+        return None;
+    };
+    let ValueDef::Variant(inner) = &pallet.values.values().next()?.value else {
+        return None;
+    };
+    Some((pallet.name.clone(), inner.name.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::KeyPairType;
+
+    fn signed(wallet: &Wallet) -> SponsoredCall {
+        let mut call = SponsoredCall {
+            signer: account_hex(&wallet.address()).unwrap(),
+            call_data: "0x00000c616263".to_string(),
+            genesis_hash: format!("0x{}", "11".repeat(32)),
+            nonce: 7,
+            valid_until: 1_000,
+            signature: String::new(),
+        };
+        call.signature = format!("0x{}", hex::encode(wallet.sign(&call.message().unwrap())));
+        call
+    }
+
+    #[test]
+    fn test_sponsored_call_signature() {
+        for key_type in [KeyPairType::Sr25519, KeyPairType::Ed25519] {
+            let wallet = Wallet::new_random_with_type(key_type);
+            let mut call = signed(&wallet);
+            assert!(call.verify().unwrap());
+
+            call.nonce += 1;
+            assert!(!call.verify().unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_store_claims_survive_restart() {
+        let dir = std::env::temp_dir().join(format!("apex-relayed-{}", std::process::id()));
+        let signer = [7u8; 32];
+        let store = FileRelayedCallStore::new(&dir);
+        assert!(store.claim(&signer, 1).await.unwrap());
+        assert!(!store.claim(&signer, 1).await.unwrap());
+        assert!(store.claim(&signer, 2).await.unwrap());
+
+        // a restarted or second relayer sees the claims
+        let restarted = FileRelayedCallStore::new(&dir);
+        assert!(!restarted.claim(&signer, 1).await.unwrap());
+        restarted.release(&signer, 1).await.unwrap();
+        assert!(store.claim(&signer, 1).await.unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+
+        let memory = MemoryRelayedCallStore::default();
+        assert!(memory.claim(&signer, 1).await.unwrap());
+        assert!(!memory.claim(&signer, 1).await.unwrap());
+    }
+
+    #[test]
+    fn test_call_name() {
+        let call = Value::unnamed_variant(
+            "System",
+            [Value::named_variant(
+                "remark",
+                [("remark", Value::from_bytes(b"abc"))],
+            )],
+        );
+        assert_eq!(
+            call_name(&call),
+            Some(("System".to_string(), "remark".to_string()))
+        );
+        assert_eq!(call_name(&Value::u128(1)), None);
+    }
+}
@@ -0,0 +1,339 @@
+//! Storage differences between two blocks
+//!
+//! [`StateDiff`] snapshots every storage entry under a set of key prefixes at
+//! two blocks and reports which entries were created, modified or deleted.
+//! Keys are listed with paged `state_getKeysPaged` requests, so large maps are
+//! never fetched in one response.
+//!
+//! Values of keys under a known `Pallet::Item` prefix are decoded with the
+//! current metadata. Entries written by an older runtime may not decode; they
+//! keep only their raw bytes.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::state_diff::{storage_prefix, StateDiff};
+//! use apex_sdk_substrate::SubstrateAdapter;
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let diff = StateDiff::new(adapter)
+//!     .between(19_000_000, 19_000_001, &[storage_prefix("System", "Account")])
+//!     .await?;
+//!
+//! for change in &diff.changes {
+//!     println!("{:?} {}: {:?} -> {:?}", change.kind, change.key, change.before, change.after);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::value_to_json;
+use crate::{Error, Result, SubstrateAdapter};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, HashMap};
+use subxt::ext::scale_value;
+use subxt::utils::H256;
+use subxt::Metadata;
+use tracing::debug;
+
+/// Keys requested per `state_getKeysPaged` call
+pub const DEFAULT_PAGE_SIZE: u32 = 1_000;
+
+/// Storage key prefix of a pallet
+pub fn pallet_prefix(pallet: &str) -> Vec<u8> {
+    sp_core::twox_128(pallet.as_bytes()).to_vec()
+}
+
+/// Storage key prefix of a `Pallet::Item` storage entry
+pub fn storage_prefix(pallet: &str, item: &str) -> Vec<u8> {
+    [
+        sp_core::twox_128(pallet.as_bytes()),
+        sp_core::twox_128(item.as_bytes()),
+    ]
+    .concat()
+}
+
+/// How an entry changed between the two blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChangeKind {
+    /// Only present at the later block
+    Created,
+    /// Present at both blocks with different values
+    Modified,
+    /// Only present at the earlier block
+    Deleted,
+}
+
+/// One changed storage entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageChange {
+    /// Full storage key as hex
+    pub key: String,
+    /// Pallet owning the entry, if the key matched the metadata
+    pub pallet: Option<String>,
+    /// Storage item name, if the key matched the metadata
+    pub item: Option<String>,
+    /// How the entry changed
+    pub kind: ChangeKind,
+    /// Raw value at the earlier block as hex
+    pub raw_before: Option<String>,
+    /// Raw value at the later block as hex
+    pub raw_after: Option<String>,
+    /// Decoded value at the earlier block
+    pub before: Option<JsonValue>,
+    /// Decoded value at the later block
+    pub after: Option<JsonValue>,
+}
+
+/// Changed entries between two blocks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageDiff {
+    /// Earlier block
+    pub block_a: u64,
+    /// Later block
+    pub block_b: u64,
+    /// Changes ordered by key
+    pub changes: Vec<StorageChange>,
+}
+
+impl StorageDiff {
+    /// Diff two raw snapshots; values are left undecoded
+    pub fn from_snapshots(
+        block_a: u64,
+        block_b: u64,
+        before: &BTreeMap<Vec<u8>, Vec<u8>>,
+        after: &BTreeMap<Vec<u8>, Vec<u8>>,
+    ) -> Self {
+        let mut keys: Vec<&Vec<u8>> = before.keys().chain(after.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        let changes = keys
+            .into_iter()
+            .filter_map(|key| {
+                let (old, new) = (before.get(key), after.get(key));
+                let kind = match (old, new) {
+                    (None, Some(_)) => ChangeKind::Created,
+                    (Some(_), None) => ChangeKind::Deleted,
+                    (Some(old), Some(new)) if old != new => ChangeKind::Modified,
+                    _ => return None,
+                };
+                let hex_value =
+                    |value: Option<&Vec<u8>>| value.map(|v| format!("0x{}", hex::encode(v)));
+                Some(StorageChange {
+                    key: format!("0x{}", hex::encode(key)),
+                    pallet: None,
+                    item: None,
+                    kind,
+                    raw_before: hex_value(old),
+                    raw_after: hex_value(new),
+                    before: None,
+                    after: None,
+                })
+            })
+            .collect();
+
+        Self {
+            block_a,
+            block_b,
+            changes,
+        }
+    }
+
+    /// Changes of one kind
+    pub fn of_kind(&self, kind: ChangeKind) -> impl Iterator<Item = &StorageChange> {
+        self.changes
+            .iter()
+            .filter(move |change| change.kind == kind)
+    }
+
+    /// Changes under a `Pallet::Item` entry
+    pub fn for_item<'b>(
+        &'b self,
+        pallet: &'b str,
+        item: &'b str,
+    ) -> impl Iterator<Item = &'b StorageChange> {
+        self.changes.iter().filter(move |change| {
+            change.pallet.as_deref() == Some(pallet) && change.item.as_deref() == Some(item)
+        })
+    }
+
+    /// Fill in pallet, item and decoded values from metadata
+    fn decode(&mut self, metadata: &Metadata) {
+        let entries = storage_entries(metadata);
+        for change in &mut self.changes {
+            let Some(key) = hex::decode(change.key.trim_start_matches("0x"))
+                .ok()
+                .filter(|key| key.len() >= 32)
+            else {
+                continue;
+            };
+            let Some((pallet, item, ty)) = entries.get(&key[..32]) else {
+                continue;
+            };
+            change.pallet = Some(pallet.clone());
+            change.item = Some(item.clone());
+
+            let decode = |raw: &Option<String>| {
+                let bytes = hex::decode(raw.as_deref()?.trim_start_matches("0x")).ok()?;
+                scale_value::scale::decode_as_type(&mut &bytes[..], *ty, metadata.types())
+                    .map(|value| value_to_json(&value))
+                    .map_err(|e| debug!("Failed to decode {}::{} value: {}", pallet, item, e))
+                    .ok()
+            };
+            change.before = decode(&change.raw_before);
+            change.after = decode(&change.raw_after);
+        }
+    }
+}
+
+/// `Pallet::Item` name and value type by 32-byte key prefix
+fn storage_entries(metadata: &Metadata) -> HashMap<Vec<u8>, (String, String, u32)> {
+    metadata
+        .pallets()
+        .filter_map(|pallet| pallet.storage().map(|storage| (pallet.name(), storage)))
+        .flat_map(|(pallet, storage)| {
+            storage.entries().iter().map(move |entry| {
+                (
+                    storage_prefix(pallet, entry.name()),
+                    (
+                        pallet.to_string(),
+                        entry.name().to_string(),
+                        entry.entry_type().value_ty(),
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Computes storage differences for a connected chain
+pub struct StateDiff<'a> {
+    adapter: &'a SubstrateAdapter,
+    page_size: u32,
+}
+
+impl<'a> StateDiff<'a> {
+    /// Differ using the adapter's connection
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self {
+            adapter,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    /// Keys requested per page
+    pub fn with_page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Entries under `key_prefixes` that differ between `block_a` and `block_b`
+    ///
+    /// Both blocks need their state available; older blocks need an archive node.
+    pub async fn between(
+        &self,
+        block_a: u64,
+        block_b: u64,
+        key_prefixes: &[Vec<u8>],
+    ) -> Result<StorageDiff> {
+        let before = self.snapshot(block_a, key_prefixes).await?;
+        let after = self.snapshot(block_b, key_prefixes).await?;
+
+        let mut diff = StorageDiff::from_snapshots(block_a, block_b, &before, &after);
+        diff.decode(&self.adapter.client().metadata());
+        debug!(
+            "{} storage changes between blocks {} and {}",
+            diff.changes.len(),
+            block_a,
+            block_b
+        );
+        Ok(diff)
+    }
+
+    /// All entries under the prefixes at `block`
+    async fn snapshot(
+        &self,
+        block: u64,
+        key_prefixes: &[Vec<u8>],
+    ) -> Result<BTreeMap<Vec<u8>, Vec<u8>>> {
+        let spec = self.adapter.spec_client();
+        let hash: H256 = spec
+            .block_hash(block)
+            .await?
+            .ok_or_else(|| Error::Storage(format!("Block {} not found", block)))?;
+
+        let mut entries = BTreeMap::new();
+        for prefix in key_prefixes {
+            let mut start_key: Option<Vec<u8>> = None;
+            loop {
+                let keys = spec
+                    .storage_keys(prefix, self.page_size, start_key.as_deref(), hash)
+                    .await?;
+                for key in &keys {
+                    if let Some(value) = spec.storage(key, hash).await? {
+                        entries.insert(key.clone(), value);
+                    }
+                }
+                if keys.len() < self.page_size as usize {
+                    break;
+                }
+                start_key = keys.last().cloned();
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_snapshots() {
+        let before: BTreeMap<Vec<u8>, Vec<u8>> = [
+            (vec![1], vec![10]),
+            (vec![2], vec![20]),
+            (vec![3], vec![30]),
+        ]
+        .into_iter()
+        .collect();
+        let after: BTreeMap<Vec<u8>, Vec<u8>> = [
+            (vec![1], vec![10]),
+            (vec![2], vec![21]),
+            (vec![4], vec![40]),
+        ]
+        .into_iter()
+        .collect();
+
+        let diff = StorageDiff::from_snapshots(5, 6, &before, &after);
+        let kinds: Vec<_> = diff
+            .changes
+            .iter()
+            .map(|change| (change.key.as_str(), change.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("0x02", ChangeKind::Modified),
+                ("0x03", ChangeKind::Deleted),
+                ("0x04", ChangeKind::Created),
+            ]
+        );
+        assert_eq!(diff.changes[0].raw_before.as_deref(), Some("0x14"));
+        assert_eq!(diff.changes[0].raw_after.as_deref(), Some("0x15"));
+        assert_eq!(diff.changes[1].raw_after, None);
+        assert_eq!(diff.of_kind(ChangeKind::Created).count(), 1);
+    }
+
+    #[test]
+    fn test_storage_prefix() {
+        assert_eq!(
+            hex::encode(storage_prefix("System", "Account")),
+            "26aa394eea5630e07c48ae0c9558cef7b99d880ec681799c0cf30e8886371da9"
+        );
+        assert_eq!(
+            &storage_prefix("System", "Account")[..16],
+            pallet_prefix("System")
+        );
+    }
+}
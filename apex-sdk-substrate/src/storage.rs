@@ -6,7 +6,13 @@
 //! - Runtime constants
 //! - Metadata inspection
 
+use crate::account20::account_bytes;
+use crate::assets::{asset_account_balance, AssetId};
+use crate::event_query::value_to_json;
+use crate::pallets::{missing_pallet, require_pallet};
+use crate::pause::PauseState;
 use crate::{Error, Metrics, Result};
+use serde_json::Value as JsonValue;
 use subxt::dynamic::At as _;
 use subxt::{OnlineClient, PolkadotConfig};
 use tracing::debug;
@@ -25,17 +31,17 @@ impl StorageClient {
     }
 
     /// Query account information including balance and nonce
+    ///
+    /// Accepts SS58 and hex addresses, including 20-byte addresses of
+    /// EVM-compatible chains.
     pub async fn get_account_info(&self, address: &str) -> Result<AccountInfo> {
         debug!("Querying account info for: {}", address);
         self.metrics.record_storage_query();
 
-        // Parse SS58 address to get AccountId32
-        use sp_core::crypto::{AccountId32, Ss58Codec};
-        let account_id = AccountId32::from_ss58check(address)
-            .map_err(|e| Error::Storage(format!("Invalid SS58 address: {}", e)))?;
+        let account_bytes = account_bytes(address)
+            .map_err(|e| Error::Storage(format!("Invalid address: {}", e)))?;
 
         // Query System::Account storage using dynamic API
-        let account_bytes: &[u8] = account_id.as_ref();
         let storage_query = subxt::dynamic::storage(
             "System",
             "Account",
@@ -111,6 +117,28 @@ impl StorageClient {
         Ok(account_info.nonce)
     }
 
+    /// Query the free balance of any asset held by an account
+    ///
+    /// Assets other than the native token are read from `Assets::Account` or
+    /// `ForeignAssets::Account`; accounts without an entry hold zero.
+    pub async fn get_asset_balance(&self, address: &str, asset: &AssetId) -> Result<u128> {
+        let Some(asset_key) = asset.pallet_key()? else {
+            return self.get_balance(address).await;
+        };
+        let account = account_bytes(address)
+            .map_err(|e| Error::Storage(format!("Invalid address: {}", e)))?;
+
+        self.query_storage(
+            asset.pallet(),
+            "Account",
+            vec![asset_key, subxt::dynamic::Value::from_bytes(account)],
+        )
+        .await?
+        .map(|bytes| asset_account_balance(&bytes))
+        .transpose()
+        .map(Option::unwrap_or_default)
+    }
+
     /// Query a storage value by pallet and item name
     pub async fn query_storage(
         &self,
@@ -120,6 +148,7 @@ impl StorageClient {
     ) -> Result<Option<Vec<u8>>> {
         debug!("Querying storage: {}::{}", pallet, item);
         self.metrics.record_storage_query();
+        require_pallet(&self.client.metadata(), pallet)?;
 
         let storage_query = subxt::dynamic::storage(pallet, item, keys);
 
@@ -140,10 +169,50 @@ impl StorageClient {
         Ok(result.map(|v| v.encoded().to_vec()))
     }
 
+    /// Query a storage value decoded with the chain's metadata, as JSON
+    pub async fn query_storage_json(
+        &self,
+        pallet: &str,
+        item: &str,
+        keys: Vec<subxt::dynamic::Value>,
+    ) -> Result<Option<JsonValue>> {
+        debug!("Querying storage: {}::{}", pallet, item);
+        self.metrics.record_storage_query();
+        require_pallet(&self.client.metadata(), pallet)?;
+
+        let storage_query = subxt::dynamic::storage(pallet, item, keys);
+
+        let storage = self
+            .client
+            .storage()
+            .at_latest()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to fetch latest block: {}", e)))?;
+
+        let result = storage.fetch(&storage_query).await.map_err(|e| {
+            Error::Storage(format!(
+                "Failed to query storage {}::{}: {}",
+                pallet, item, e
+            ))
+        })?;
+
+        result
+            .map(|value| {
+                value
+                    .to_value()
+                    .map(|value| value_to_json(&value))
+                    .map_err(|e| {
+                        Error::Storage(format!("Failed to decode {}::{}: {}", pallet, item, e))
+                    })
+            })
+            .transpose()
+    }
+
     /// Get a runtime constant (returns raw bytes)
     pub fn get_constant(&self, pallet: &str, constant: &str) -> Result<Vec<u8>> {
         debug!("Getting constant: {}::{}", pallet, constant);
         self.metrics.record_storage_query();
+        require_pallet(&self.client.metadata(), pallet)?;
 
         let constant_address = subxt::dynamic::constant(pallet, constant);
 
@@ -156,6 +225,28 @@ impl StorageClient {
         Ok(value.encoded().to_vec())
     }
 
+    /// Get a runtime constant decoded with the chain's metadata, as JSON
+    pub fn get_constant_json(&self, pallet: &str, constant: &str) -> Result<JsonValue> {
+        debug!("Getting constant: {}::{}", pallet, constant);
+        self.metrics.record_storage_query();
+        require_pallet(&self.client.metadata(), pallet)?;
+
+        let constant_address = subxt::dynamic::constant(pallet, constant);
+
+        let value = self
+            .client
+            .constants()
+            .at(&constant_address)
+            .map_err(|e| Error::Storage(format!("Failed to get constant: {}", e)))?;
+
+        value
+            .to_value()
+            .map(|value| value_to_json(&value))
+            .map_err(|e| {
+                Error::Storage(format!("Failed to decode {}::{}: {}", pallet, constant, e))
+            })
+    }
+
     /// Get the existential deposit (minimum balance to keep account alive)
     pub fn get_existential_deposit(&self) -> Result<u128> {
         let value = self.get_constant("Balances", "ExistentialDeposit")?;
@@ -205,6 +296,7 @@ impl StorageClient {
     pub async fn iter_storage(&self, pallet: &str, item: &str) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
         debug!("Iterating storage: {}::{}", pallet, item);
         self.metrics.record_storage_query();
+        require_pallet(&self.client.metadata(), pallet)?;
 
         let storage_query =
             subxt::dynamic::storage(pallet, item, Vec::<subxt::dynamic::Value>::new());
@@ -234,6 +326,49 @@ impl StorageClient {
         Ok(results)
     }
 
+    /// Iterate over storage entries, returning raw keys and values decoded as JSON
+    ///
+    /// `keys` may give a leading part of the key to iterate only the entries under it.
+    pub async fn iter_storage_json(
+        &self,
+        pallet: &str,
+        item: &str,
+        keys: Vec<subxt::dynamic::Value>,
+    ) -> Result<Vec<(Vec<u8>, JsonValue)>> {
+        debug!("Iterating storage: {}::{}", pallet, item);
+        self.metrics.record_storage_query();
+        require_pallet(&self.client.metadata(), pallet)?;
+
+        let storage_query = subxt::dynamic::storage(pallet, item, keys);
+
+        let mut results = Vec::new();
+        let storage = self
+            .client
+            .storage()
+            .at_latest()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to fetch latest block: {}", e)))?;
+
+        let mut iter = storage.iter(storage_query).await.map_err(|e| {
+            Error::Storage(format!(
+                "Failed to iterate storage {}::{}: {}",
+                pallet, item, e
+            ))
+        })?;
+
+        while let Some(result) = iter.next().await {
+            let kv_pair = result
+                .map_err(|e| Error::Storage(format!("Failed to fetch storage entry: {}", e)))?;
+            let value = kv_pair.value.to_value().map_err(|e| {
+                Error::Storage(format!("Failed to decode {}::{}: {}", pallet, item, e))
+            })?;
+            results.push((kv_pair.key_bytes, value_to_json(&value)));
+        }
+
+        debug!("Found {} entries in {}::{}", results.len(), pallet, item);
+        Ok(results)
+    }
+
     /// Get metadata about a pallet
     pub fn get_pallet_metadata(&self, pallet: &str) -> Result<PalletMetadata> {
         debug!("Getting pallet metadata: {}", pallet);
@@ -243,7 +378,7 @@ impl StorageClient {
         // Check if pallet exists
         let pallet_metadata = metadata
             .pallet_by_name(pallet)
-            .ok_or_else(|| Error::Metadata(format!("Pallet '{}' not found", pallet)))?;
+            .ok_or_else(|| missing_pallet(&metadata, pallet))?;
 
         // Extract values before returning
         let name = pallet.to_string();
@@ -282,6 +417,31 @@ impl StorageClient {
         let metadata = self.client.metadata();
         metadata.pallets().map(|p| p.name().to_string()).collect()
     }
+
+    /// Safe mode and paused calls; empty on chains without `SafeMode` and
+    /// `TxPause`
+    pub async fn pause_state(&self) -> Result<PauseState> {
+        let metadata = self.client.metadata();
+        let entered_until = if metadata.pallet_by_name("SafeMode").is_some() {
+            self.query_storage_json("SafeMode", "EnteredUntil", Vec::new())
+                .await?
+        } else {
+            None
+        };
+        let paused_keys = if metadata.pallet_by_name("TxPause").is_some() {
+            self.iter_storage("TxPause", "PausedCalls")
+                .await?
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Ok(PauseState::from_storage(
+            entered_until.as_ref(),
+            &paused_keys,
+        ))
+    }
 }
 
 /// Account information structure
@@ -0,0 +1,401 @@
+//! Alerts on changes of watched storage values
+//!
+//! A [`StorageWatcher`] reads its [`StorageWatch`]es at every finalized block
+//! and raises a [`StorageAlert`] when a value meets its [`WatchCondition`]:
+//! any change, a change larger than an absolute or relative threshold, or a
+//! custom predicate.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{StorageAlert, StorageWatch, StorageWatcher, SubstrateAdapter, WatchCondition};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! StorageWatcher::new(adapter)
+//!     .watch(
+//!         StorageWatch::new(
+//!             "issuance",
+//!             "Balances",
+//!             "TotalIssuance",
+//!             WatchCondition::RelativeChange(0.001),
+//!         )
+//!         .with_debounce(100),
+//!     )
+//!     .watch(StorageWatch::new(
+//!         "heap pages",
+//!         "System",
+//!         "HeapPages",
+//!         WatchCondition::Changed,
+//!     ))
+//!     .on_alert(|alert: &StorageAlert| {
+//!         println!("{} at #{}: {} -> {}", alert.watch, alert.block_number, alert.previous, alert.current);
+//!     })
+//!     .run()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Conditions compare against the value at the last alert, or at the first
+//! block read, so a slow drift alerts once it adds up to the threshold. After
+//! an alert a watch stays quiet for its debounce period; changes in that
+//! period are reported together by the first alert after it.
+
+use crate::event_query::value_to_json;
+use crate::receipt::json_u128;
+use crate::rpc_spec::SpecClient;
+use crate::{Error, Result, SubstrateAdapter};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::fmt;
+use std::sync::Arc;
+use subxt::dynamic::Value;
+use subxt::utils::H256;
+use subxt::{OnlineClient, PolkadotConfig};
+use tracing::{debug, info};
+
+/// Custom condition, called with the reference value and the current value
+pub type WatchPredicate = Arc<dyn Fn(&JsonValue, &JsonValue) -> bool + Send + Sync>;
+
+/// When a watched value raises an alert
+#[derive(Clone)]
+pub enum WatchCondition {
+    /// On any change
+    Changed,
+    /// When a numeric value moved by more than this amount
+    AbsoluteChange(u128),
+    /// When a numeric value moved by more than this fraction of the
+    /// reference value, e.g. `0.01` for 1%
+    RelativeChange(f64),
+    /// When the predicate returns `true`
+    Predicate(WatchPredicate),
+}
+
+impl WatchCondition {
+    /// Condition from a closure taking the reference and current value
+    pub fn predicate(
+        predicate: impl Fn(&JsonValue, &JsonValue) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        WatchCondition::Predicate(Arc::new(predicate))
+    }
+
+    /// Whether going from `reference` to `current` meets the condition
+    ///
+    /// Numeric conditions never hold for values that aren't numbers.
+    pub fn is_met(&self, reference: &JsonValue, current: &JsonValue) -> bool {
+        let numbers = || Some((json_u128(reference)?, json_u128(current)?));
+        match self {
+            WatchCondition::Changed => reference != current,
+            WatchCondition::AbsoluteChange(threshold) => numbers()
+                .is_some_and(|(reference, current)| reference.abs_diff(current) > *threshold),
+            WatchCondition::RelativeChange(fraction) => {
+                numbers().is_some_and(|(reference, current)| {
+                    if reference == 0 {
+                        return current != 0;
+                    }
+                    reference.abs_diff(current) as f64 / reference as f64 > *fraction
+                })
+            }
+            WatchCondition::Predicate(predicate) => predicate(reference, current),
+        }
+    }
+}
+
+impl fmt::Debug for WatchCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchCondition::Changed => write!(f, "Changed"),
+            WatchCondition::AbsoluteChange(threshold) => {
+                f.debug_tuple("AbsoluteChange").field(threshold).finish()
+            }
+            WatchCondition::RelativeChange(fraction) => {
+                f.debug_tuple("RelativeChange").field(fraction).finish()
+            }
+            WatchCondition::Predicate(_) => write!(f, "Predicate(..)"),
+        }
+    }
+}
+
+/// A storage value to watch and the condition to alert on
+#[derive(Debug, Clone)]
+pub struct StorageWatch {
+    /// Name reported in alerts
+    pub name: String,
+    /// Pallet name
+    pub pallet: String,
+    /// Storage item name
+    pub item: String,
+    /// Map keys, empty for plain values
+    pub keys: Vec<Value>,
+    /// JSON pointer to the part of the value to watch, e.g. `/data/free`
+    pub pointer: Option<String>,
+    /// When to alert
+    pub condition: WatchCondition,
+    /// Blocks after an alert during which the watch stays quiet
+    pub debounce_blocks: u64,
+}
+
+impl StorageWatch {
+    /// Watch `pallet::item`
+    pub fn new(
+        name: impl Into<String>,
+        pallet: impl Into<String>,
+        item: impl Into<String>,
+        condition: WatchCondition,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            pallet: pallet.into(),
+            item: item.into(),
+            keys: Vec::new(),
+            pointer: None,
+            condition,
+            debounce_blocks: 0,
+        }
+    }
+
+    /// Watch the entry of a storage map under these keys
+    pub fn with_keys(mut self, keys: Vec<Value>) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Watch only part of the decoded value, selected by a JSON pointer
+    pub fn with_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.pointer = Some(pointer.into());
+        self
+    }
+
+    /// Stay quiet for `blocks` blocks after an alert
+    pub fn with_debounce(mut self, blocks: u64) -> Self {
+        self.debounce_blocks = blocks;
+        self
+    }
+}
+
+/// A watched value met its condition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageAlert {
+    /// Name of the watch
+    pub watch: String,
+    /// Block the value was read at
+    pub block_number: u64,
+    /// Hash of that block
+    pub block_hash: String,
+    /// Value at the last alert, or at the first block read
+    pub previous: JsonValue,
+    /// Current value; `null` for a missing entry
+    pub current: JsonValue,
+}
+
+/// Receiver of storage alerts
+#[async_trait]
+pub trait StorageAlertHandler: Send + Sync {
+    /// Called once for every alert, in block order
+    async fn on_alert(&self, alert: &StorageAlert);
+}
+
+#[async_trait]
+impl<F> StorageAlertHandler for F
+where
+    F: Fn(&StorageAlert) + Send + Sync,
+{
+    async fn on_alert(&self, alert: &StorageAlert) {
+        self(alert)
+    }
+}
+
+/// Reference value and debounce state of one watch
+#[derive(Debug, Default)]
+struct WatchState {
+    reference: Option<JsonValue>,
+    last_alert: Option<u64>,
+}
+
+impl WatchState {
+    /// Record the value read at `block`; the reference value if it alerts
+    fn observe(
+        &mut self,
+        watch: &StorageWatch,
+        block: u64,
+        current: &JsonValue,
+    ) -> Option<JsonValue> {
+        let Some(reference) = &self.reference else {
+            self.reference = Some(current.clone());
+            return None;
+        };
+        if self
+            .last_alert
+            .is_some_and(|last| block < last.saturating_add(watch.debounce_blocks))
+        {
+            return None;
+        }
+        if !watch.condition.is_met(reference, current) {
+            return None;
+        }
+        self.last_alert = Some(block);
+        self.reference.replace(current.clone())
+    }
+}
+
+/// Reads watched storage values at each finalized block and raises alerts
+pub struct StorageWatcher {
+    client: OnlineClient<PolkadotConfig>,
+    spec: SpecClient,
+    watches: Vec<StorageWatch>,
+    states: parking_lot::Mutex<Vec<WatchState>>,
+    handlers: Vec<Arc<dyn StorageAlertHandler>>,
+}
+
+impl StorageWatcher {
+    /// Watcher without watches
+    pub fn new(adapter: &SubstrateAdapter) -> Self {
+        Self {
+            client: adapter.client().clone(),
+            spec: adapter.spec_client(),
+            watches: Vec::new(),
+            states: parking_lot::Mutex::new(Vec::new()),
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Add a watch
+    pub fn watch(mut self, watch: StorageWatch) -> Self {
+        self.watches.push(watch);
+        self.states.get_mut().push(WatchState::default());
+        self
+    }
+
+    /// Register an alert handler
+    pub fn on_alert(mut self, handler: impl StorageAlertHandler + 'static) -> Self {
+        self.handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Read every watch at `block_hash` and return the alerts raised
+    ///
+    /// Blocks must be checked in order for debouncing to work.
+    pub async fn check(&self, block_hash: H256) -> Result<Vec<StorageAlert>> {
+        let block_number = self
+            .client
+            .blocks()
+            .at(block_hash)
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to fetch block: {}", e)))?
+            .number() as u64;
+        let storage = self.client.storage().at(block_hash);
+
+        let mut values = Vec::with_capacity(self.watches.len());
+        for watch in &self.watches {
+            let query = subxt::dynamic::storage(
+                watch.pallet.as_str(),
+                watch.item.as_str(),
+                watch.keys.clone(),
+            );
+            let value = match storage.fetch(&query).await.map_err(|e| {
+                Error::Storage(format!(
+                    "Failed to query storage {}::{}: {}",
+                    watch.pallet, watch.item, e
+                ))
+            })? {
+                Some(value) => value_to_json(&value.to_value().map_err(|e| {
+                    Error::Storage(format!(
+                        "Failed to decode {}::{}: {}",
+                        watch.pallet, watch.item, e
+                    ))
+                })?),
+                None => JsonValue::Null,
+            };
+            let value = match &watch.pointer {
+                Some(pointer) => value.pointer(pointer).cloned().unwrap_or(JsonValue::Null),
+                None => value,
+            };
+            values.push(value);
+        }
+
+        let block_hash = format!("0x{}", hex::encode(block_hash.0));
+        let mut states = self.states.lock();
+        let alerts = self
+            .watches
+            .iter()
+            .zip(states.iter_mut())
+            .zip(values)
+            .filter_map(|((watch, state), current)| {
+                let previous = state.observe(watch, block_number, &current)?;
+                Some(StorageAlert {
+                    watch: watch.name.clone(),
+                    block_number,
+                    block_hash: block_hash.clone(),
+                    previous,
+                    current,
+                })
+            })
+            .collect::<Vec<_>>();
+        debug!("{} storage alerts at #{}", alerts.len(), block_number);
+        Ok(alerts)
+    }
+
+    /// Check every finalized block until the subscription or a read fails,
+    /// passing alerts to the handlers
+    pub async fn run(&self) -> Result<()> {
+        info!("Watching {} storage values", self.watches.len());
+        let mut heads = self.spec.follow_finalized().await?;
+        while let Some(head) = heads.next().await {
+            for alert in self.check(head?).await? {
+                for handler in &self.handlers {
+                    handler.on_alert(&alert).await;
+                }
+            }
+        }
+        Err(Error::Connection(
+            "Finalized heads subscription ended".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_conditions() {
+        assert!(WatchCondition::Changed.is_met(&json!(1), &json!(2)));
+        assert!(!WatchCondition::Changed.is_met(&json!({ "a": 1 }), &json!({ "a": 1 })));
+
+        let absolute = WatchCondition::AbsoluteChange(100);
+        assert!(absolute.is_met(&json!("1000"), &json!(1101)));
+        assert!(!absolute.is_met(&json!(1000), &json!(900)));
+        assert!(!absolute.is_met(&json!("text"), &json!(5000)));
+
+        let relative = WatchCondition::RelativeChange(0.1);
+        assert!(relative.is_met(&json!(1000), &json!(1101)));
+        assert!(!relative.is_met(&json!(1000), &json!(1050)));
+        assert!(relative.is_met(&json!(0), &json!(1)));
+
+        let shrinking =
+            WatchCondition::predicate(|reference, current| current.as_u64() < reference.as_u64());
+        assert!(shrinking.is_met(&json!(5), &json!(4)));
+        assert!(!shrinking.is_met(&json!(5), &json!(6)));
+    }
+
+    #[test]
+    fn test_drift_and_debounce() {
+        let watch = StorageWatch::new(
+            "issuance",
+            "Balances",
+            "TotalIssuance",
+            WatchCondition::AbsoluteChange(10),
+        )
+        .with_debounce(5);
+        let mut state = WatchState::default();
+
+        assert_eq!(state.observe(&watch, 1, &json!(100)), None);
+        assert_eq!(state.observe(&watch, 2, &json!(106)), None);
+        // drift adds up against the first value
+        assert_eq!(state.observe(&watch, 3, &json!(111)), Some(json!(100)));
+        // quiet until block 8
+        assert_eq!(state.observe(&watch, 4, &json!(200)), None);
+        assert_eq!(state.observe(&watch, 8, &json!(300)), Some(json!(111)));
+        assert_eq!(state.observe(&watch, 20, &json!(305)), None);
+    }
+}
@@ -0,0 +1,687 @@
+//! Finalized block subscriptions that survive restarts
+//!
+//! [`ResumableSubscription`] follows finalized blocks and records its position
+//! in a [`CheckpointStore`]. When a process restarts with the same subscription
+//! id, it backfills every block after the saved checkpoint up to the current
+//! finalized head and then switches to live blocks, so no block is skipped.
+//!
+//! Live blocks are buffered as the adapter's [`ChainConfig::stream`](crate::ChainConfig::stream) says, or
+//! as set with [`ResumableSubscription::with_stream_config`]. Blocks a lossy
+//! overflow policy drops are backfilled like any other gap.
+//!
+//! A block counts as processed once the consumer asks for the next one (or calls
+//! [`ResumableSubscription::commit`]); only then is its checkpoint saved.
+//! [`AckEventStream`] builds on it for at-least-once event delivery: the
+//! checkpoint only advances once the consumer acknowledges each event.
+//!
+//! ```rust,no_run
+//! use apex_sdk_core::FileCheckpointStore;
+//! use apex_sdk_substrate::{ResumableSubscription, SubstrateAdapter};
+//! use std::sync::Arc;
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let store = Arc::new(FileCheckpointStore::new("./checkpoints"));
+//! let mut blocks = ResumableSubscription::new(adapter, "indexer", store);
+//!
+//! while let Some(block) = blocks.next().await? {
+//!     println!("#{} ({} events, backfilled: {})", block.number, block.events.len(), block.backfilled);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::decode_router::DecodeRouter;
+use crate::event_query::{block_hash_at, decode_events, MatchedEvent};
+use crate::rpc_spec::SpecClient;
+use crate::{Error, Result, SubstrateAdapter};
+use apex_sdk_core::checkpoint::{backfill_range, Checkpoint, CheckpointStore};
+use apex_sdk_core::sink::{BlockRecord, EventRecord, SinkRecord};
+use apex_sdk_core::stream::{bounded_stream, StreamConfig, StreamReceiver, StreamSendError};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use subxt::backend::rpc::RpcClient;
+use subxt::ext::subxt_rpcs::client::RpcParams;
+use subxt::utils::H256;
+use subxt::{OnlineClient, PolkadotConfig};
+use tokio::task::JoinHandle;
+use tracing::{debug, info};
+
+type SubxtBlock = subxt::blocks::Block<PolkadotConfig, OnlineClient<PolkadotConfig>>;
+
+/// A finalized block delivered by a [`ResumableSubscription`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FinalizedBlock {
+    /// Block number
+    pub number: u64,
+    /// Block hash (hex)
+    pub hash: String,
+    /// Decoded events of the block
+    pub events: Vec<MatchedEvent>,
+    /// Whether the block was fetched to close a gap rather than received live
+    pub backfilled: bool,
+}
+
+impl FinalizedBlock {
+    /// Block and event records for an
+    /// [`IndexerSink`](apex_sdk_core::sink::IndexerSink)
+    pub fn sink_records(&self) -> Vec<SinkRecord> {
+        let mut block = BlockRecord::new(self.number, self.hash.clone());
+        block.event_count = Some(self.events.len() as u32);
+
+        std::iter::once(SinkRecord::Block(block))
+            .chain(
+                self.events
+                    .iter()
+                    .map(|event| SinkRecord::Event(EventRecord::from(event))),
+            )
+            .collect()
+    }
+}
+
+/// What to do with a block received from the live subscription
+#[derive(Debug, PartialEq, Eq)]
+enum LiveStep {
+    /// Already delivered
+    Skip,
+    /// Blocks were missed; fetch `from..=to` (including this one) by number
+    Backfill(u64, u64),
+    /// Next block in sequence
+    Emit,
+}
+
+fn live_step(last_block: Option<u64>, number: u64) -> LiveStep {
+    match last_block {
+        Some(last) if number <= last => LiveStep::Skip,
+        Some(last) if number > last + 1 => LiveStep::Backfill(last + 1, number),
+        _ => LiveStep::Emit,
+    }
+}
+
+/// Where to continue after (re)starting: blocks to backfill and the last block
+/// considered delivered
+fn resume_point(
+    checkpoint: Option<&Checkpoint>,
+    start: Option<u64>,
+    finalized: u64,
+) -> (Option<(u64, u64)>, Option<u64>) {
+    let start = start.unwrap_or(finalized.saturating_add(1));
+    match backfill_range(checkpoint, start, finalized) {
+        Some(range) => (
+            Some((*range.start(), *range.end())),
+            range.start().checked_sub(1),
+        ),
+        None => {
+            let delivered = match checkpoint {
+                Some(checkpoint) => checkpoint.block_number.max(finalized),
+                None => finalized.max(start.saturating_sub(1)),
+            };
+            (None, Some(delivered))
+        }
+    }
+}
+
+/// Node subscription forwarded into a bounded stream
+struct LiveBlocks {
+    receiver: StreamReceiver<Result<SubxtBlock>>,
+    task: JoinHandle<()>,
+}
+
+impl LiveBlocks {
+    async fn subscribe(
+        client: &OnlineClient<PolkadotConfig>,
+        config: StreamConfig,
+    ) -> Result<Self> {
+        let mut blocks = client
+            .blocks()
+            .subscribe_finalized()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to subscribe to blocks: {}", e)))?;
+
+        let (sender, receiver) = bounded_stream(config);
+        let task = tokio::spawn(async move {
+            while let Some(block) = blocks.next().await {
+                let block = block
+                    .map_err(|e| Error::Connection(format!("Block subscription failed: {}", e)));
+                match sender.send(block).await {
+                    Ok(()) => {}
+                    // the gap is backfilled once the consumer catches up
+                    Err(StreamSendError::Full(_)) => {
+                        debug!("Live block buffer full, dropped a block")
+                    }
+                    Err(StreamSendError::Closed(_)) => break,
+                }
+            }
+        });
+
+        Ok(Self { receiver, task })
+    }
+}
+
+impl Drop for LiveBlocks {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Finalized block subscription with persisted position
+pub struct ResumableSubscription {
+    client: OnlineClient<PolkadotConfig>,
+    rpc: RpcClient,
+    spec: SpecClient,
+    router: Option<DecodeRouter>,
+    store: Arc<dyn CheckpointStore>,
+    id: String,
+    start: Option<u64>,
+    stream: StreamConfig,
+    live: Option<LiveBlocks>,
+    backfill: Option<(u64, u64)>,
+    last_block: Option<u64>,
+    processed: Option<Checkpoint>,
+}
+
+impl ResumableSubscription {
+    /// Create a subscription identified by `id` in `store`
+    pub fn new(
+        adapter: &SubstrateAdapter,
+        id: impl Into<String>,
+        store: Arc<dyn CheckpointStore>,
+    ) -> Self {
+        Self {
+            client: adapter.client().clone(),
+            rpc: adapter.rpc_client().clone(),
+            spec: adapter.spec_client(),
+            router: None,
+            store,
+            id: id.into(),
+            start: None,
+            stream: adapter.config().stream.clone(),
+            live: None,
+            backfill: None,
+            last_block: None,
+            processed: None,
+        }
+    }
+
+    /// First block to deliver when no checkpoint exists yet
+    ///
+    /// Defaults to the first block finalized after the subscription starts.
+    pub fn start_at(mut self, block_number: u64) -> Self {
+        self.start = Some(block_number);
+        self
+    }
+
+    /// Buffer live blocks as `config` says instead of the adapter's
+    /// [`ChainConfig::stream`](crate::ChainConfig::stream)
+    pub fn with_stream_config(mut self, config: StreamConfig) -> Self {
+        self.stream = config;
+        self
+    }
+
+    /// Decode each block with the metadata of its own runtime
+    ///
+    /// Without a router, blocks decode with the metadata the client
+    /// connected with, which is wrong for blocks before a runtime upgrade.
+    pub fn with_router(mut self, router: DecodeRouter) -> Self {
+        self.router = Some(router);
+        self
+    }
+
+    /// Subscription id used in the checkpoint store
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Next finalized block, or `None` when the node subscription ends
+    ///
+    /// Calling this marks the previously returned block as processed.
+    pub async fn next(&mut self) -> Result<Option<FinalizedBlock>> {
+        self.commit().await?;
+        let block = self.next_block().await?;
+        self.processed = block
+            .as_ref()
+            .map(|block| Checkpoint::new(block.number, block.hash.clone()));
+        Ok(block)
+    }
+
+    /// Save the checkpoint of the last delivered block now
+    pub async fn commit(&mut self) -> Result<()> {
+        if let Some(checkpoint) = self.processed.take() {
+            self.save_checkpoint(&checkpoint).await?;
+        }
+        Ok(())
+    }
+
+    /// Next block in sequence, without touching the checkpoint
+    async fn next_block(&mut self) -> Result<Option<FinalizedBlock>> {
+        if self.live.is_none() {
+            self.start().await?;
+        }
+
+        loop {
+            if let Some((next, until)) = self.backfill {
+                let block = self.fetch(next).await?;
+                self.backfill = (next < until).then_some((next + 1, until));
+                return Ok(Some(self.deliver(block)));
+            }
+
+            let Some(live) = self.live.as_mut() else {
+                return Ok(None);
+            };
+            let Some(block) = live.receiver.recv().await else {
+                return Ok(None);
+            };
+            let block = block?;
+
+            match live_step(self.last_block, block.number() as u64) {
+                LiveStep::Skip => continue,
+                LiveStep::Backfill(from, to) => {
+                    debug!("Subscription {} missed blocks {}..{}", self.id, from, to);
+                    self.backfill = Some((from, to));
+                }
+                LiveStep::Emit => {
+                    let block = match &self.router {
+                        Some(router) => {
+                            self.route(router, block.number() as u64, block.hash(), false)
+                                .await?
+                        }
+                        None => decode_block(&block, false).await?,
+                    };
+                    return Ok(Some(self.deliver(block)));
+                }
+            }
+        }
+    }
+
+    async fn save_checkpoint(&self, checkpoint: &Checkpoint) -> Result<()> {
+        self.store
+            .save(&self.id, checkpoint)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to save checkpoint: {}", e)))
+    }
+
+    async fn start(&mut self) -> Result<()> {
+        let checkpoint = self
+            .store
+            .load(&self.id)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to load checkpoint: {}", e)))?;
+
+        // Subscribe before reading the finalized head so nothing falls in between
+        let live = LiveBlocks::subscribe(&self.client, self.stream.clone()).await?;
+        let finalized = self.finalized_number().await?;
+
+        let (backfill, last_block) = resume_point(checkpoint.as_ref(), self.start, finalized);
+        info!(
+            "Subscription {} resuming after {:?} (finalized: {}, backfill: {:?})",
+            self.id, last_block, finalized, backfill
+        );
+
+        self.live = Some(live);
+        self.backfill = backfill;
+        self.last_block = last_block;
+        Ok(())
+    }
+
+    async fn finalized_number(&self) -> Result<u64> {
+        let hash: H256 = self
+            .rpc
+            .request("chain_getFinalizedHead", RpcParams::new())
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get finalized head: {}", e)))?;
+        let block = self
+            .client
+            .blocks()
+            .at(hash)
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get finalized block: {}", e)))?;
+        Ok(block.number() as u64)
+    }
+
+    async fn fetch(&self, number: u64) -> Result<FinalizedBlock> {
+        let hash = block_hash_at(&self.rpc, number).await?;
+        if let Some(router) = &self.router {
+            return self.route(router, number, hash, true).await;
+        }
+        let block = self
+            .client
+            .blocks()
+            .at(hash)
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get block {}: {}", number, e)))?;
+        decode_block(&block, true).await
+    }
+
+    /// Decode block `number` with the metadata `router` picks for it
+    async fn route(
+        &self,
+        router: &DecodeRouter,
+        number: u64,
+        hash: H256,
+        backfilled: bool,
+    ) -> Result<FinalizedBlock> {
+        Ok(FinalizedBlock {
+            number,
+            hash: format!("0x{}", hex::encode(hash.0)),
+            events: router.events_at(&self.spec, number, hash).await?,
+            backfilled,
+        })
+    }
+
+    fn deliver(&mut self, block: FinalizedBlock) -> FinalizedBlock {
+        self.last_block = Some(block.number);
+        block
+    }
+}
+
+impl std::fmt::Debug for ResumableSubscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResumableSubscription")
+            .field("id", &self.id)
+            .field("start", &self.start)
+            .field("started", &self.live.is_some())
+            .field("backfill", &self.backfill)
+            .field("last_block", &self.last_block)
+            .field("routed", &self.router.is_some())
+            .finish()
+    }
+}
+
+/// Identifies an event delivered by an [`AckEventStream`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct EventId {
+    /// Block the event was emitted in
+    pub block_number: u64,
+    /// Index of the event within the block
+    pub event_index: u32,
+}
+
+impl From<&MatchedEvent> for EventId {
+    fn from(event: &MatchedEvent) -> Self {
+        Self {
+            block_number: event.block_number,
+            event_index: event.event_index,
+        }
+    }
+}
+
+impl std::fmt::Display for EventId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.block_number, self.event_index)
+    }
+}
+
+/// A block whose events are not all acknowledged yet
+#[derive(Debug)]
+struct PendingBlock {
+    number: u64,
+    hash: String,
+    unacked: HashSet<u32>,
+}
+
+/// Tracks acknowledgements and decides how far the checkpoint may advance
+#[derive(Debug, Default)]
+struct AckTracker {
+    blocks: VecDeque<PendingBlock>,
+}
+
+impl AckTracker {
+    fn push_block(&mut self, block: &FinalizedBlock) {
+        self.blocks.push_back(PendingBlock {
+            number: block.number,
+            hash: block.hash.clone(),
+            unacked: block.events.iter().map(|event| event.event_index).collect(),
+        });
+    }
+
+    fn ack(&mut self, id: EventId) -> Result<()> {
+        let acked = self
+            .blocks
+            .iter_mut()
+            .find(|block| block.number == id.block_number)
+            .is_some_and(|block| block.unacked.remove(&id.event_index));
+        if acked {
+            Ok(())
+        } else {
+            Err(Error::Other(format!(
+                "Event {} is unknown or already acknowledged",
+                id
+            )))
+        }
+    }
+
+    /// Drop fully acknowledged blocks from the front, returning the newest one
+    fn advance(&mut self) -> Option<Checkpoint> {
+        let mut checkpoint = None;
+        while self
+            .blocks
+            .front()
+            .is_some_and(|block| block.unacked.is_empty())
+        {
+            if let Some(block) = self.blocks.pop_front() {
+                checkpoint = Some(Checkpoint::new(block.number, block.hash));
+            }
+        }
+        checkpoint
+    }
+
+    fn unacked(&self) -> usize {
+        self.blocks.iter().map(|block| block.unacked.len()).sum()
+    }
+}
+
+/// Event stream with at-least-once delivery
+///
+/// Events are handed out one by one and the checkpoint only moves past a block
+/// once every event in it, and in all earlier blocks, has been acknowledged
+/// with [`AckEventStream::ack`]. After a restart, events that were delivered
+/// but not acknowledged are delivered again, so consumers should be idempotent.
+///
+/// ```rust,no_run
+/// use apex_sdk_core::FileCheckpointStore;
+/// use apex_sdk_substrate::{AckEventStream, SubstrateAdapter};
+/// use std::sync::Arc;
+///
+/// # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+/// let store = Arc::new(FileCheckpointStore::new("./checkpoints"));
+/// let mut events = AckEventStream::new(adapter, "transfers", store);
+///
+/// while let Some((id, event)) = events.next().await? {
+///     println!("{}::{} {}", event.pallet, event.variant, event.fields);
+///     events.ack(id).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AckEventStream {
+    subscription: ResumableSubscription,
+    tracker: AckTracker,
+    queued: VecDeque<MatchedEvent>,
+}
+
+impl AckEventStream {
+    /// Create an event stream identified by `id` in `store`
+    pub fn new(
+        adapter: &SubstrateAdapter,
+        id: impl Into<String>,
+        store: Arc<dyn CheckpointStore>,
+    ) -> Self {
+        Self {
+            subscription: ResumableSubscription::new(adapter, id, store),
+            tracker: AckTracker::default(),
+            queued: VecDeque::new(),
+        }
+    }
+
+    /// First block to read when no checkpoint exists yet
+    pub fn start_at(mut self, block_number: u64) -> Self {
+        self.subscription = self.subscription.start_at(block_number);
+        self
+    }
+
+    /// Next event, or `None` when the node subscription ends
+    pub async fn next(&mut self) -> Result<Option<(EventId, MatchedEvent)>> {
+        loop {
+            if let Some(event) = self.queued.pop_front() {
+                return Ok(Some((EventId::from(&event), event)));
+            }
+
+            let Some(block) = self.subscription.next_block().await? else {
+                return Ok(None);
+            };
+            self.tracker.push_block(&block);
+            self.queued.extend(block.events);
+
+            // Blocks without events are done as soon as everything before them is
+            self.checkpoint().await?;
+        }
+    }
+
+    /// Confirm an event was processed, advancing the checkpoint when possible
+    pub async fn ack(&mut self, id: EventId) -> Result<()> {
+        self.tracker.ack(id)?;
+        self.checkpoint().await
+    }
+
+    /// Number of delivered or queued events not acknowledged yet
+    pub fn unacked(&self) -> usize {
+        self.tracker.unacked()
+    }
+
+    async fn checkpoint(&mut self) -> Result<()> {
+        match self.tracker.advance() {
+            Some(checkpoint) => self.subscription.save_checkpoint(&checkpoint).await,
+            None => Ok(()),
+        }
+    }
+}
+
+async fn decode_block(block: &SubxtBlock, backfilled: bool) -> Result<FinalizedBlock> {
+    let number = block.number() as u64;
+    let hash = format!("0x{}", hex::encode(block.hash().0));
+    let events = block.events().await.map_err(|e| {
+        Error::Connection(format!("Failed to get events of block {}: {}", number, e))
+    })?;
+
+    Ok(FinalizedBlock {
+        number,
+        events: decode_events(&events, number, &hash)?,
+        hash,
+        backfilled,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_point() {
+        let checkpoint = Checkpoint::new(100, "0x64");
+
+        // Restart behind the finalized head: backfill the gap
+        assert_eq!(
+            resume_point(Some(&checkpoint), None, 110),
+            (Some((101, 110)), Some(100))
+        );
+        // Already caught up
+        assert_eq!(
+            resume_point(Some(&checkpoint), None, 100),
+            (None, Some(100))
+        );
+        assert_eq!(resume_point(Some(&checkpoint), None, 90), (None, Some(100)));
+        // First run: live only, or from an explicit start block
+        assert_eq!(resume_point(None, None, 50), (None, Some(50)));
+        assert_eq!(resume_point(None, Some(40), 50), (Some((40, 50)), Some(39)));
+        assert_eq!(resume_point(None, Some(0), 2), (Some((0, 2)), None));
+        assert_eq!(resume_point(None, Some(60), 50), (None, Some(59)));
+    }
+
+    #[test]
+    fn test_live_step() {
+        assert_eq!(live_step(None, 5), LiveStep::Emit);
+        assert_eq!(live_step(Some(4), 5), LiveStep::Emit);
+        assert_eq!(live_step(Some(5), 5), LiveStep::Skip);
+        assert_eq!(live_step(Some(6), 5), LiveStep::Skip);
+        assert_eq!(live_step(Some(2), 5), LiveStep::Backfill(3, 5));
+    }
+
+    fn block(number: u64, events: &[u32]) -> FinalizedBlock {
+        FinalizedBlock {
+            number,
+            hash: format!("0x{:02x}", number),
+            events: events
+                .iter()
+                .map(|&event_index| MatchedEvent {
+                    block_number: number,
+                    block_hash: format!("0x{:02x}", number),
+                    event_index,
+                    extrinsic_index: None,
+                    pallet: "System".to_string(),
+                    variant: "Remarked".to_string(),
+                    fields: serde_json::Value::Null,
+                })
+                .collect(),
+            backfilled: false,
+        }
+    }
+
+    fn id(block_number: u64, event_index: u32) -> EventId {
+        EventId {
+            block_number,
+            event_index,
+        }
+    }
+
+    #[test]
+    fn test_sink_records() {
+        let records = block(9, &[0, 3]).sink_records();
+
+        assert_eq!(records.len(), 3);
+        match &records[0] {
+            SinkRecord::Block(block) => assert_eq!(block.event_count, Some(2)),
+            other => panic!("unexpected record: {:?}", other),
+        }
+        match &records[2] {
+            SinkRecord::Event(event) => {
+                assert_eq!(event.index, 3);
+                assert_eq!(event.event, "Remarked");
+            }
+            other => panic!("unexpected record: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ack_tracker_advances_in_order() {
+        let mut tracker = AckTracker::default();
+        tracker.push_block(&block(1, &[0, 1]));
+        tracker.push_block(&block(2, &[]));
+        tracker.push_block(&block(3, &[0]));
+        assert_eq!(tracker.unacked(), 3);
+        assert_eq!(tracker.advance(), None);
+
+        // Acknowledging a later block does not move the checkpoint past block 1
+        tracker.ack(id(3, 0)).unwrap();
+        assert_eq!(tracker.advance(), None);
+
+        tracker.ack(id(1, 1)).unwrap();
+        tracker.ack(id(1, 0)).unwrap();
+        let checkpoint = tracker.advance().unwrap();
+        assert_eq!(checkpoint.block_number, 3);
+        assert_eq!(checkpoint.block_hash, "0x03");
+        assert_eq!(tracker.unacked(), 0);
+    }
+
+    #[test]
+    fn test_ack_rejects_unknown_events() {
+        let mut tracker = AckTracker::default();
+        tracker.push_block(&block(1, &[0]));
+
+        assert!(tracker.ack(id(1, 5)).is_err());
+        assert!(tracker.ack(id(2, 0)).is_err());
+        tracker.ack(id(1, 0)).unwrap();
+        assert!(tracker.ack(id(1, 0)).is_err());
+        assert_eq!(id(1, 0).to_string(), "1-0");
+    }
+}
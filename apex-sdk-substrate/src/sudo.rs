@@ -0,0 +1,250 @@
+//! Root calls through the `Sudo` pallet
+//!
+//! A root call bypasses every permission check of the runtime, so [`Sudo`]
+//! only dispatches one when handed a [`RootConfirmation`], and a confirmation
+//! only comes from [`confirm_root_action`]. Confirmations can't be cloned and
+//! are used up by the call they are passed with, so each root call is
+//! acknowledged in the code that makes it:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{confirm_root_action, Sudo, SudoOutcome, SubstrateAdapter, Wallet};
+//! use subxt::dynamic::Value;
+//!
+//! # async fn example(adapter: &SubstrateAdapter, sudo_key: &Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! let call = Value::unnamed_variant(
+//!     "System",
+//!     [Value::named_variant("set_heap_pages", [("pages", Value::u128(64))])],
+//! );
+//! let sudo = Sudo::new(adapter);
+//! let tx_hash = sudo
+//!     .sudo(sudo_key, call, confirm_root_action("raise heap pages"))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The sudo extrinsic succeeds even when the wrapped call fails; the call's
+//! own result is in the `Sudid` or `SudoAsDone` event, decoded by
+//! [`SudoOutcome`].
+
+use crate::block_limits::Weight;
+use crate::event_query::account_id;
+use crate::receipt::{dispatch_error, ExtrinsicReceipt};
+use crate::wallet::Wallet;
+use crate::{Error, MatchedEvent, Result, SubstrateAdapter};
+use serde_json::Value as JsonValue;
+use subxt::dynamic::Value;
+use tracing::info;
+
+/// Acknowledgement that the next call is dispatched with root origin
+///
+/// Obtained from [`confirm_root_action`] and used up by the [`Sudo`] call
+/// it is passed to.
+#[derive(Debug)]
+#[must_use = "a root confirmation does nothing until passed to a Sudo call"]
+pub struct RootConfirmation {
+    reason: String,
+}
+
+impl RootConfirmation {
+    /// Why the root call is made, as given to [`confirm_root_action`]
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+/// Confirm that a root call is intended, giving the reason for it
+///
+/// The reason is logged with the call.
+pub fn confirm_root_action(reason: impl Into<String>) -> RootConfirmation {
+    RootConfirmation {
+        reason: reason.into(),
+    }
+}
+
+/// Result of a root call, decoded from `Sudo::Sudid` or `Sudo::SudoAsDone`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SudoOutcome {
+    /// Whether the call was dispatched as another account with `sudo_as`
+    pub as_account: bool,
+    /// Dispatch error of the wrapped call, if it failed
+    pub error: Option<String>,
+}
+
+impl SudoOutcome {
+    /// Decode a `Sudid` or `SudoAsDone` event; `None` for any other event
+    pub fn from_event(event: &MatchedEvent) -> Option<Self> {
+        if event.pallet != "Sudo" {
+            return None;
+        }
+        let as_account = match event.variant.as_str() {
+            "Sudid" => false,
+            "SudoAsDone" => true,
+            _ => return None,
+        };
+        let error = match &event.fields["sudo_result"] {
+            JsonValue::Object(result) if result.contains_key("Ok") => None,
+            JsonValue::Object(result) => Some(dispatch_error(result.get("Err")?)),
+            _ => return None,
+        };
+        Some(Self { as_account, error })
+    }
+
+    /// Outcome of the root call made by an extrinsic
+    pub fn from_receipt(receipt: &ExtrinsicReceipt) -> Option<Self> {
+        receipt.events.iter().find_map(Self::from_event)
+    }
+
+    /// Whether the wrapped call succeeded
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Root calls through the `Sudo` pallet
+pub struct Sudo<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> Sudo<'a> {
+    /// Make root calls on the adapter's chain
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Account holding the sudo key (hex), if any
+    pub async fn key(&self) -> Result<Option<String>> {
+        Ok(self
+            .adapter
+            .storage()
+            .query_storage("Sudo", "Key", Vec::new())
+            .await?
+            .map(|key| format!("0x{}", hex::encode(key))))
+    }
+
+    /// Dispatch `call` with root origin
+    ///
+    /// `call` is a runtime call value such as
+    /// `Value::unnamed_variant("System", [Value::named_variant("remark", ..)])`.
+    /// Returns the extrinsic hash.
+    pub async fn sudo(
+        &self,
+        signer: &Wallet,
+        call: Value,
+        confirmation: RootConfirmation,
+    ) -> Result<String> {
+        self.submit(signer, "sudo", vec![call], confirmation).await
+    }
+
+    /// Dispatch `call` with root origin, charging `weight` instead of the
+    /// call's own weight
+    pub async fn sudo_unchecked_weight(
+        &self,
+        signer: &Wallet,
+        call: Value,
+        weight: Weight,
+        confirmation: RootConfirmation,
+    ) -> Result<String> {
+        self.submit(
+            signer,
+            "sudo_unchecked_weight",
+            vec![call, weight.to_value()],
+            confirmation,
+        )
+        .await
+    }
+
+    /// Dispatch `call` with a signed origin of `who`
+    pub async fn sudo_as(
+        &self,
+        signer: &Wallet,
+        who: &str,
+        call: Value,
+        confirmation: RootConfirmation,
+    ) -> Result<String> {
+        let who = Value::unnamed_variant("Id", vec![Value::from_bytes(account_id(who)?)]);
+        self.submit(signer, "sudo_as", vec![who, call], confirmation)
+            .await
+    }
+
+    /// Submit a `Sudo` call once `signer` is known to hold the sudo key
+    ///
+    /// Checking first saves the fee of a call the runtime would reject with
+    /// `RequireSudo`.
+    async fn submit(
+        &self,
+        signer: &Wallet,
+        sudo_call: &str,
+        args: Vec<Value>,
+        confirmation: RootConfirmation,
+    ) -> Result<String> {
+        let key = self
+            .key()
+            .await?
+            .ok_or_else(|| Error::Other("Chain has no sudo key".to_string()))?;
+        if key != format!("0x{}", hex::encode(signer.public_key())) {
+            return Err(Error::PolicyViolation(format!(
+                "{} does not hold the sudo key {}",
+                signer.address(),
+                key
+            )));
+        }
+        info!(
+            "Submitting root call Sudo::{} from {}: {}",
+            sudo_call,
+            signer.address(),
+            confirmation.reason
+        );
+        self.adapter
+            .transaction_executor()
+            .submit_call(signer, "Sudo", sudo_call, args)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(variant: &str, result: JsonValue) -> MatchedEvent {
+        MatchedEvent {
+            block_number: 1,
+            block_hash: "0x01".to_string(),
+            event_index: 0,
+            extrinsic_index: Some(1),
+            pallet: "Sudo".to_string(),
+            variant: variant.to_string(),
+            fields: json!({ "sudo_result": result }),
+        }
+    }
+
+    #[test]
+    fn test_sudo_outcome_from_event() {
+        let done = SudoOutcome::from_event(&event("Sudid", json!({ "Ok": [] }))).unwrap();
+        assert!(done.is_success());
+        assert!(!done.as_account);
+
+        let failed = SudoOutcome::from_event(&event(
+            "SudoAsDone",
+            json!({ "Err": { "Token": "FundsUnavailable" } }),
+        ))
+        .unwrap();
+        assert!(failed.as_account);
+        assert_eq!(failed.error.as_deref(), Some("Token(FundsUnavailable)"));
+
+        let bad_origin =
+            SudoOutcome::from_event(&event("Sudid", json!({ "Err": "BadOrigin" }))).unwrap();
+        assert_eq!(bad_origin.error.as_deref(), Some("BadOrigin"));
+
+        assert!(SudoOutcome::from_event(&event("KeyChanged", json!(null))).is_none());
+    }
+
+    #[test]
+    fn test_root_confirmation_reason() {
+        assert_eq!(
+            confirm_root_action("runtime upgrade").reason(),
+            "runtime upgrade"
+        );
+    }
+}
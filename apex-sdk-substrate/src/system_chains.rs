@@ -0,0 +1,282 @@
+//! Preconfigured clients for the relay networks' system parachains
+//!
+//! Each relay network runs system parachains that took over relay chain
+//! duties: AssetHub holds balances and assets, People holds identities,
+//! Coretime sells cores through the broker pallet and Collectives hosts the
+//! Fellowship. [`SystemChainClient::connect`] connects to one of them with the
+//! relay network's address format and token, and checks that the pallets the
+//! chain is expected to provide are present.
+//!
+//! The same configurations are available by name from
+//! [`ChainConfig::preset`], e.g. `"polkadot-people"`.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::system_chains::{RelayNetwork, SystemChain, SystemChainClient};
+//!
+//! # async fn example() -> Result<(), apex_sdk_substrate::Error> {
+//! let people = SystemChainClient::connect(RelayNetwork::Kusama, SystemChain::People).await?;
+//! let name = people
+//!     .identity()?
+//!     .display_name("HNZata7iMYWmk5RvZRTiAsSDhV8366zq2YGb3tLH5Upf74F")
+//!     .await?;
+//! println!("{:?}", name);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::assets::AssetId;
+use crate::collectives::Collectives;
+use crate::coretime::Coretime;
+use crate::identity::IdentityQuery;
+use crate::{ChainConfig, Error, Result, SubstrateAdapter};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A relay network with system parachains
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RelayNetwork {
+    /// Polkadot
+    Polkadot,
+    /// Kusama
+    Kusama,
+    /// Westend testnet
+    Westend,
+}
+
+impl RelayNetwork {
+    /// Lowercase network name
+    pub fn name(&self) -> &'static str {
+        match self {
+            RelayNetwork::Polkadot => "polkadot",
+            RelayNetwork::Kusama => "kusama",
+            RelayNetwork::Westend => "westend",
+        }
+    }
+
+    /// Configuration of the relay chain itself
+    pub fn relay_config(&self) -> ChainConfig {
+        match self {
+            RelayNetwork::Polkadot => ChainConfig::polkadot(),
+            RelayNetwork::Kusama => ChainConfig::kusama(),
+            RelayNetwork::Westend => ChainConfig::westend(),
+        }
+    }
+}
+
+impl fmt::Display for RelayNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for RelayNetwork {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "polkadot" => Ok(RelayNetwork::Polkadot),
+            "kusama" => Ok(RelayNetwork::Kusama),
+            "westend" => Ok(RelayNetwork::Westend),
+            _ => Err(Error::Connection(format!("Unknown relay network: {}", s))),
+        }
+    }
+}
+
+/// A system parachain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SystemChain {
+    /// Balances, assets and foreign assets
+    AssetHub,
+    /// Fellowship and other collectives (not on Kusama)
+    Collectives,
+    /// Identities
+    People,
+    /// Coretime sales
+    Coretime,
+}
+
+impl SystemChain {
+    /// All system chains
+    pub const ALL: [SystemChain; 4] = [
+        SystemChain::AssetHub,
+        SystemChain::Collectives,
+        SystemChain::People,
+        SystemChain::Coretime,
+    ];
+
+    /// Parachain id, the same on every network
+    pub fn para_id(&self) -> u32 {
+        match self {
+            SystemChain::AssetHub => 1000,
+            SystemChain::Collectives => 1001,
+            SystemChain::People => 1004,
+            SystemChain::Coretime => 1005,
+        }
+    }
+
+    /// Lowercase chain name used in endpoints and presets
+    pub fn name(&self) -> &'static str {
+        match self {
+            SystemChain::AssetHub => "asset-hub",
+            SystemChain::Collectives => "collectives",
+            SystemChain::People => "people",
+            SystemChain::Coretime => "coretime",
+        }
+    }
+
+    /// Chain name as shown to users
+    pub fn title(&self) -> &'static str {
+        match self {
+            SystemChain::AssetHub => "AssetHub",
+            SystemChain::Collectives => "Collectives",
+            SystemChain::People => "People",
+            SystemChain::Coretime => "Coretime",
+        }
+    }
+
+    /// Pallets the chain is expected to provide
+    pub fn pallets(&self) -> &'static [&'static str] {
+        match self {
+            SystemChain::AssetHub => &["Assets", "ForeignAssets"],
+            SystemChain::Collectives => &["FellowshipCollective"],
+            SystemChain::People => &["Identity"],
+            SystemChain::Coretime => &["Broker"],
+        }
+    }
+
+    /// Whether the network runs this chain
+    pub fn runs_on(&self, network: RelayNetwork) -> bool {
+        !(*self == SystemChain::Collectives && network == RelayNetwork::Kusama)
+    }
+
+    /// Public RPC endpoint on a network
+    pub fn endpoint(&self, network: RelayNetwork) -> Option<String> {
+        self.runs_on(network)
+            .then(|| format!("wss://{}-{}-rpc.polkadot.io", network, self.name()))
+    }
+}
+
+impl fmt::Display for SystemChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SystemChain {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.to_ascii_lowercase().replace(['_', ' '], "-");
+        match name.as_str() {
+            "asset-hub" | "assethub" => Ok(SystemChain::AssetHub),
+            "collectives" => Ok(SystemChain::Collectives),
+            "people" => Ok(SystemChain::People),
+            "coretime" => Ok(SystemChain::Coretime),
+            _ => Err(Error::Connection(format!("Unknown system chain: {}", s))),
+        }
+    }
+}
+
+/// Connection to a system parachain
+pub struct SystemChainClient {
+    adapter: SubstrateAdapter,
+    network: RelayNetwork,
+    chain: SystemChain,
+}
+
+impl SystemChainClient {
+    /// Connect to a system chain through its public endpoint
+    pub async fn connect(network: RelayNetwork, chain: SystemChain) -> Result<Self> {
+        let config = ChainConfig::system_chain(network, chain)
+            .ok_or_else(|| Error::Connection(format!("{} has no {} chain", network, chain)))?;
+        let adapter = SubstrateAdapter::connect_with_config(config).await?;
+        Self::from_adapter(adapter, network, chain)
+    }
+
+    /// Wrap an existing connection, e.g. to a private node
+    ///
+    /// Fails if the chain lacks the pallets expected of it.
+    pub fn from_adapter(
+        adapter: SubstrateAdapter,
+        network: RelayNetwork,
+        chain: SystemChain,
+    ) -> Result<Self> {
+        for pallet in chain.pallets() {
+            adapter.require_pallet(pallet)?;
+        }
+        Ok(Self {
+            adapter,
+            network,
+            chain,
+        })
+    }
+
+    /// Underlying adapter
+    pub fn adapter(&self) -> &SubstrateAdapter {
+        &self.adapter
+    }
+
+    /// Relay network of the chain
+    pub fn network(&self) -> RelayNetwork {
+        self.network
+    }
+
+    /// Which system chain this is
+    pub fn chain(&self) -> SystemChain {
+        self.chain
+    }
+
+    /// Identity lookups; People chains only
+    pub fn identity(&self) -> Result<IdentityQuery<'_>> {
+        self.adapter.require_pallet("Identity")?;
+        Ok(IdentityQuery::new(&self.adapter))
+    }
+
+    /// Coretime sales and regions; Coretime chains only
+    pub fn coretime(&self) -> Result<Coretime<'_>> {
+        self.adapter.require_pallet("Broker")?;
+        Ok(Coretime::new(&self.adapter))
+    }
+
+    /// Collective membership and voting queries
+    pub fn collectives(&self) -> Collectives<'_> {
+        Collectives::new(&self.adapter)
+    }
+
+    /// Balance of an asset held on the chain; native balances work anywhere
+    pub async fn asset_balance(&self, address: &str, asset: &AssetId) -> Result<u128> {
+        self.adapter
+            .storage()
+            .get_asset_balance(address, asset)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_chain_configs() {
+        let people = ChainConfig::system_chain(RelayNetwork::Kusama, SystemChain::People).unwrap();
+        assert_eq!(people.endpoint, "wss://kusama-people-rpc.polkadot.io");
+        assert_eq!(people.ss58_prefix, 2);
+        assert_eq!(people.token_symbol, "KSM");
+        assert!(
+            ChainConfig::system_chain(RelayNetwork::Kusama, SystemChain::Collectives).is_none()
+        );
+
+        let coretime = ChainConfig::preset("Polkadot-Coretime").unwrap();
+        assert_eq!(coretime.endpoint, "wss://polkadot-coretime-rpc.polkadot.io");
+        assert_eq!(
+            ChainConfig::preset("westend-asset-hub").unwrap().name,
+            "Westend AssetHub"
+        );
+        assert_eq!(
+            ChainConfig::preset("polkadot").unwrap().endpoint,
+            "wss://rpc.polkadot.io"
+        );
+        assert!(ChainConfig::preset("polkadot-bridge-hub").is_none());
+    }
+}
@@ -0,0 +1,239 @@
+//! Teleport trust checks
+//!
+//! A teleport burns an asset on one chain and mints it on another, which is
+//! only safe when each chain accepts the other as a teleporter of that
+//! asset. When the destination does not, the XCM fails on arrival and the
+//! assets are trapped there. Runtimes keep this trust in their XCM
+//! configuration rather than in storage, so [`TeleportRegistry`] records it
+//! from known configuration: [`TeleportRegistry::system_chains`] covers the
+//! relay token between a relay chain and its system parachains.
+//!
+//! [`XcmExecutor::teleport`](crate::XcmExecutor::teleport) checks every
+//! teleport against a registry first and fails with
+//! [`Error::TeleportNotTrusted`] instead of sending it:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::teleport::TeleportRegistry;
+//! use apex_sdk_substrate::MultiLocation;
+//!
+//! let registry = TeleportRegistry::system_chains();
+//!
+//! // relay token from AssetHub to the relay chain
+//! assert!(registry.check(Some(1000), &MultiLocation::parent(), &MultiLocation::parent()).is_ok());
+//!
+//! // ...but not to a parachain outside the system chains
+//! assert!(registry.check(Some(1000), &MultiLocation::parachain(2034), &MultiLocation::parent()).is_err());
+//! ```
+//!
+//! Chains are identified by para id, with `None` for the relay chain; all
+//! locations are relative to the chain the teleport starts on.
+
+use crate::xcm::{Junction, MultiLocation};
+use crate::{Error, Result};
+
+/// Para ids of the system parachains that accept the relay token by
+/// teleport: AssetHub, Collectives (Encointer on Kusama), BridgeHub, People
+/// and Coretime
+pub const SYSTEM_PARA_IDS: [u32; 5] = [1000, 1001, 1002, 1004, 1005];
+
+/// One chain accepting another as teleporter of an asset
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Trust {
+    chain: Option<u32>,
+    teleporter: Option<u32>,
+    /// Asset location relative to the relay chain
+    asset: MultiLocation,
+}
+
+/// Known teleport trust between chains of one relay network
+#[derive(Debug, Clone, Default)]
+pub struct TeleportRegistry {
+    trusted: Vec<Trust>,
+}
+
+impl TeleportRegistry {
+    /// Registry without any trust
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Relay token trust between a relay chain and its system parachains,
+    /// in both directions and between the system parachains themselves
+    pub fn system_chains() -> Self {
+        let chains = std::iter::once(None)
+            .chain(SYSTEM_PARA_IDS.iter().copied().map(Some))
+            .collect::<Vec<_>>();
+        let mut registry = Self::new();
+        for &chain in &chains {
+            for &teleporter in &chains {
+                if chain != teleporter {
+                    registry =
+                        registry.with_trust(chain, teleporter, MultiLocation::new(0, vec![]));
+                }
+            }
+        }
+        registry
+    }
+
+    /// Record that `chain` accepts `asset` teleported from `teleporter`
+    ///
+    /// `asset` is relative to the relay chain, e.g. `Here` for the relay
+    /// token or `Parachain(1000)/PalletInstance(50)/GeneralIndex(1984)` for
+    /// an AssetHub asset.
+    pub fn with_trust(
+        mut self,
+        chain: Option<u32>,
+        teleporter: Option<u32>,
+        asset: MultiLocation,
+    ) -> Self {
+        let trust = Trust {
+            chain,
+            teleporter,
+            asset,
+        };
+        if !self.trusted.contains(&trust) {
+            self.trusted.push(trust);
+        }
+        self
+    }
+
+    /// Record trust in both directions between `a` and `b`
+    pub fn with_mutual_trust(self, a: Option<u32>, b: Option<u32>, asset: MultiLocation) -> Self {
+        self.with_trust(a, b, asset.clone()).with_trust(b, a, asset)
+    }
+
+    /// Whether `chain` accepts `asset`, relative to the relay chain, from
+    /// `teleporter`
+    pub fn trusts(
+        &self,
+        chain: Option<u32>,
+        teleporter: Option<u32>,
+        asset: &MultiLocation,
+    ) -> bool {
+        self.trusted.iter().any(|trust| {
+            trust.chain == chain && trust.teleporter == teleporter && trust.asset == *asset
+        })
+    }
+
+    /// Check a teleport of `asset` from `origin` to `dest`
+    ///
+    /// Fails with [`Error::TeleportNotTrusted`] unless both chains trust
+    /// each other as teleporters of the asset.
+    pub fn check(
+        &self,
+        origin: Option<u32>,
+        dest: &MultiLocation,
+        asset: &MultiLocation,
+    ) -> Result<()> {
+        let not_trusted = || Error::TeleportNotTrusted {
+            asset: format!("{:?}", asset),
+            from: chain_name(origin),
+            to: format!("{:?}", dest),
+        };
+        let dest_chain = from_relay(origin, dest)
+            .and_then(|dest| match dest.interior.as_slice() {
+                [] => Some(None),
+                [Junction::Parachain(id)] => Some(Some(*id)),
+                _ => None,
+            })
+            .ok_or_else(not_trusted)?;
+        let asset_from_relay = from_relay(origin, asset).ok_or_else(not_trusted)?;
+
+        if dest_chain != origin
+            && self.trusts(dest_chain, origin, &asset_from_relay)
+            && self.trusts(origin, dest_chain, &asset_from_relay)
+        {
+            Ok(())
+        } else {
+            Err(Error::TeleportNotTrusted {
+                asset: format!("{:?}", asset),
+                from: chain_name(origin),
+                to: chain_name(dest_chain),
+            })
+        }
+    }
+}
+
+/// `location` seen from `origin`, re-anchored at the relay chain
+///
+/// `None` for locations outside the relay network.
+fn from_relay(origin: Option<u32>, location: &MultiLocation) -> Option<MultiLocation> {
+    match (origin, location.parents) {
+        (None, 0) | (Some(_), 1) => Some(MultiLocation::new(0, location.interior.clone())),
+        (Some(para_id), 0) => {
+            let mut interior = vec![Junction::Parachain(para_id)];
+            interior.extend(location.interior.iter().cloned());
+            Some(MultiLocation::new(0, interior))
+        }
+        _ => None,
+    }
+}
+
+fn chain_name(chain: Option<u32>) -> String {
+    match chain {
+        Some(para_id) => format!("parachain {}", para_id),
+        None => "the relay chain".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_chain_teleports() {
+        let registry = TeleportRegistry::system_chains();
+        let dot = MultiLocation::parent();
+
+        assert!(registry
+            .check(
+                None,
+                &MultiLocation::new(0, vec![Junction::Parachain(1000)]),
+                &MultiLocation::new(0, vec![])
+            )
+            .is_ok());
+        assert!(registry
+            .check(Some(1000), &MultiLocation::parent(), &dot)
+            .is_ok());
+        assert!(registry
+            .check(Some(1000), &MultiLocation::parachain(1004), &dot)
+            .is_ok());
+
+        // a parachain's own token is not the relay token
+        let own = MultiLocation::new(0, vec![]);
+        assert!(registry
+            .check(Some(1000), &MultiLocation::parent(), &own)
+            .is_err());
+
+        let err = registry
+            .check(Some(1000), &MultiLocation::parachain(2034), &dot)
+            .unwrap_err();
+        assert!(matches!(err, Error::TeleportNotTrusted { .. }));
+        assert!(err.to_string().contains("reserve transfer"));
+    }
+
+    #[test]
+    fn test_one_sided_trust_is_rejected() {
+        let usdt = MultiLocation::new(
+            0,
+            vec![
+                Junction::Parachain(1000),
+                Junction::PalletInstance(50),
+                Junction::GeneralIndex(1984),
+            ],
+        );
+        let asset = MultiLocation::new(
+            0,
+            vec![Junction::PalletInstance(50), Junction::GeneralIndex(1984)],
+        );
+        let registry = TeleportRegistry::new().with_trust(Some(2000), Some(1000), usdt.clone());
+        assert!(registry
+            .check(Some(1000), &MultiLocation::parachain(2000), &asset)
+            .is_err());
+
+        let registry = registry.with_mutual_trust(Some(1000), Some(2000), usdt);
+        assert!(registry
+            .check(Some(1000), &MultiLocation::parachain(2000), &asset)
+            .is_ok());
+    }
+}
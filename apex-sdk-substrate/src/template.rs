@@ -0,0 +1,628 @@
+//! Saved, parameterized transactions
+//!
+//! A [`TxTemplate`] is a call with JSON arguments, some of which are
+//! `{{placeholders}}` filled in when the template is used. Templates
+//! serialize to JSON, so recurring operations such as "pay out validator X"
+//! can be kept in a [`TemplateLibrary`] and signed later:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{SubstrateAdapter, TemplateLibrary, TxTemplate, Wallet};
+//! use serde_json::json;
+//! use std::path::Path;
+//!
+//! # async fn example(adapter: &SubstrateAdapter, wallet: &Wallet) -> Result<(), apex_sdk_substrate::Error> {
+//! let payout = TxTemplate::new("payout", "Staking", "payout_stakers")
+//!     .with_description("Pay out a validator's stakers for an era")
+//!     .with_arg("validator_stash", json!("{{validator}}"))
+//!     .with_arg("era", json!("{{era}}"));
+//!
+//! let mut library = TemplateLibrary::default();
+//! library.insert(payout);
+//! library.save(Path::new("templates.json"))?;
+//!
+//! let library = TemplateLibrary::load(Path::new("templates.json"))?;
+//! let template = library.get("payout").expect("saved above");
+//! let params = json!({
+//!     "validator": "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY",
+//!     "era": 1_234,
+//! });
+//! adapter
+//!     .transaction_executor()
+//!     .submit_template(wallet, template, &params)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Arguments are converted to the call's argument types from the runtime
+//! metadata: objects for structs, `"Name"` or `{"Name": fields}` for enum
+//! variants, `null` for `None`, arrays for sequences and tuples, `0x` hex or
+//! text for bytes and SS58 or hex strings for account ids. Large integers can be
+//! given as decimal strings.
+
+use crate::event_query::account_id;
+use crate::{Error, Result};
+use scale_info::{PortableRegistry, TypeDef, TypeDefPrimitive};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use subxt::dynamic::Value;
+use subxt::ext::scale_value::Primitive;
+use subxt::{Metadata, OnlineClient, PolkadotConfig};
+
+/// A call with placeholder arguments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TxTemplate {
+    /// Name of the template
+    pub name: String,
+    /// What the template does
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Pallet of the call
+    pub pallet: String,
+    /// Call name
+    pub call: String,
+    /// Arguments by name; strings may contain `{{placeholders}}`
+    pub args: Map<String, JsonValue>,
+}
+
+impl TxTemplate {
+    /// Template of `pallet::call` without arguments
+    pub fn new(
+        name: impl Into<String>,
+        pallet: impl Into<String>,
+        call: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            pallet: pallet.into(),
+            call: call.into(),
+            args: Map::new(),
+        }
+    }
+
+    /// Describe what the template does
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Set an argument
+    ///
+    /// A string that is exactly `"{{name}}"` is replaced by the parameter's
+    /// JSON value; placeholders within longer strings are replaced by text.
+    pub fn with_arg(mut self, name: impl Into<String>, value: JsonValue) -> Self {
+        self.args.insert(name.into(), value);
+        self
+    }
+
+    /// Names of the parameters the template needs
+    pub fn placeholders(&self) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        self.args
+            .values()
+            .for_each(|value| collect_placeholders(value, &mut names));
+        names
+    }
+
+    /// Arguments with the placeholders filled in from the `params` object
+    ///
+    /// Fails if a parameter is missing or `params` has unknown parameters.
+    pub fn instantiate(&self, params: &JsonValue) -> Result<Map<String, JsonValue>> {
+        let empty = Map::new();
+        let params = match params {
+            JsonValue::Object(params) => params,
+            JsonValue::Null => &empty,
+            _ => return Err(Error::Encoding("Parameters must be an object".to_string())),
+        };
+        let placeholders = self.placeholders();
+        if let Some(missing) = placeholders.iter().find(|name| !params.contains_key(*name)) {
+            return Err(Error::Encoding(format!(
+                "Missing parameter {} for template {}",
+                missing, self.name
+            )));
+        }
+        if let Some(unknown) = params.keys().find(|name| !placeholders.contains(*name)) {
+            return Err(Error::Encoding(format!(
+                "Unknown parameter {} for template {}",
+                unknown, self.name
+            )));
+        }
+        Ok(self
+            .args
+            .iter()
+            .map(|(name, value)| (name.clone(), fill(value, params)))
+            .collect())
+    }
+
+    /// Call arguments, in order and typed by the runtime metadata
+    pub fn call_args(&self, params: &JsonValue, metadata: &Metadata) -> Result<Vec<Value>> {
+        let mut args = self.instantiate(params)?;
+        let fields = &metadata
+            .pallet_by_name(&self.pallet)
+            .and_then(|pallet| pallet.call_variant_by_name(&self.call))
+            .ok_or_else(|| {
+                Error::Metadata(format!("Runtime has no {}::{}", self.pallet, self.call))
+            })?
+            .fields;
+
+        let values = fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let name = field.name.clone().unwrap_or_else(|| index.to_string());
+                let value = args.remove(&name).ok_or_else(|| {
+                    Error::Encoding(format!("Template {} has no argument {}", self.name, name))
+                })?;
+                json_to_value(&value, field.ty.id, metadata.types()).map_err(|e| {
+                    Error::Encoding(format!("Invalid argument {} of {}: {}", name, self.name, e))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(extra) = args.keys().next() {
+            return Err(Error::Encoding(format!(
+                "{}::{} has no argument {}",
+                self.pallet, self.call, extra
+            )));
+        }
+        Ok(values)
+    }
+
+    /// The call with the placeholders filled in
+    pub fn to_call(
+        &self,
+        params: &JsonValue,
+        metadata: &Metadata,
+    ) -> Result<subxt::tx::DynamicPayload> {
+        Ok(subxt::dynamic::tx(
+            &self.pallet,
+            &self.call,
+            self.call_args(params, metadata)?,
+        ))
+    }
+
+    /// SCALE-encoded call data with the placeholders filled in
+    pub fn call_data(
+        &self,
+        params: &JsonValue,
+        client: &OnlineClient<PolkadotConfig>,
+    ) -> Result<Vec<u8>> {
+        let call = self.to_call(params, &client.metadata())?;
+        client
+            .tx()
+            .call_data(&call)
+            .map_err(|e| Error::Encoding(format!("Failed to encode template {}: {}", self.name, e)))
+    }
+}
+
+/// Named templates, saved as JSON
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TemplateLibrary {
+    templates: BTreeMap<String, TxTemplate>,
+}
+
+impl TemplateLibrary {
+    /// Add or replace a template under its name
+    pub fn insert(&mut self, template: TxTemplate) -> Option<TxTemplate> {
+        self.templates.insert(template.name.clone(), template)
+    }
+
+    /// Template by name
+    pub fn get(&self, name: &str) -> Option<&TxTemplate> {
+        self.templates.get(name)
+    }
+
+    /// Remove a template
+    pub fn remove(&mut self, name: &str) -> Option<TxTemplate> {
+        self.templates.remove(name)
+    }
+
+    /// Templates in name order
+    pub fn iter(&self) -> impl Iterator<Item = &TxTemplate> {
+        self.templates.values()
+    }
+
+    /// Number of templates
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Whether the library has no templates
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    /// Read a library saved with [`save`](Self::save)
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| Error::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+        serde_json::from_str(&json).map_err(|e| {
+            Error::Encoding(format!(
+                "Invalid template library {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Write the library as pretty-printed JSON, creating parent directories
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| Error::Other(format!("Failed to create {}: {}", dir.display(), e)))?;
+        }
+        let json =
+            serde_json::to_string_pretty(self).map_err(|e| Error::Encoding(e.to_string()))?;
+        std::fs::write(path, json)
+            .map_err(|e| Error::Other(format!("Failed to write {}: {}", path.display(), e)))
+    }
+}
+
+/// Placeholder names in `text`
+fn placeholders_in(text: &str) -> impl Iterator<Item = &str> {
+    text.split("{{")
+        .skip(1)
+        .filter_map(|rest| rest.split_once("}}"))
+        .map(|(name, _)| name.trim())
+}
+
+fn collect_placeholders(value: &JsonValue, names: &mut BTreeSet<String>) {
+    match value {
+        JsonValue::String(text) => names.extend(placeholders_in(text).map(str::to_string)),
+        JsonValue::Array(values) => values.iter().for_each(|v| collect_placeholders(v, names)),
+        JsonValue::Object(fields) => fields.values().for_each(|v| collect_placeholders(v, names)),
+        _ => {}
+    }
+}
+
+/// `value` with placeholders replaced by `params`
+fn fill(value: &JsonValue, params: &Map<String, JsonValue>) -> JsonValue {
+    match value {
+        JsonValue::String(text) => {
+            let trimmed = text.trim();
+            if let Some(name) = trimmed
+                .strip_prefix("{{")
+                .and_then(|rest| rest.strip_suffix("}}"))
+                .filter(|name| !name.contains("{{"))
+            {
+                if let Some(param) = params.get(name.trim()) {
+                    return param.clone();
+                }
+            }
+            let mut filled = text.clone();
+            for name in placeholders_in(text) {
+                let Some(param) = params.get(name) else {
+                    continue;
+                };
+                let replacement = match param {
+                    JsonValue::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                filled = filled.replace(&format!("{{{{{}}}}}", name), &replacement);
+            }
+            JsonValue::String(filled)
+        }
+        JsonValue::Array(values) => {
+            JsonValue::Array(values.iter().map(|v| fill(v, params)).collect())
+        }
+        JsonValue::Object(fields) => JsonValue::Object(
+            fields
+                .iter()
+                .map(|(name, v)| (name.clone(), fill(v, params)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// JSON as a dynamic value of type `ty`
+pub(crate) fn json_to_value(json: &JsonValue, ty: u32, types: &PortableRegistry) -> Result<Value> {
+    let expected = |what: &str| Error::Encoding(format!("Expected {}, got {}", what, json));
+    let resolved = types
+        .resolve(ty)
+        .ok_or_else(|| Error::Metadata(format!("Type {} not found in metadata", ty)))?;
+
+    match &resolved.type_def {
+        TypeDef::Composite(composite) => {
+            let fields: Vec<_> = composite
+                .fields
+                .iter()
+                .map(|field| (field.name.as_deref(), field.ty.id))
+                .collect();
+            fields_to_value(json, &fields, types).map(|fields| match fields {
+                Fields::Named(fields) => Value::named_composite(fields),
+                Fields::Unnamed(values) => Value::unnamed_composite(values),
+            })
+        }
+        TypeDef::Variant(variant) => {
+            let (name, fields_json) = match json {
+                JsonValue::String(name) => (name.as_str(), &JsonValue::Null),
+                JsonValue::Null => ("None", &JsonValue::Null),
+                JsonValue::Object(object) if object.len() == 1 => {
+                    let (name, fields) = object.iter().next().expect("one entry");
+                    (name.as_str(), fields)
+                }
+                _ => return Err(expected("a variant name or {\"Name\": fields}")),
+            };
+            let variant = variant
+                .variants
+                .iter()
+                .find(|variant| variant.name == name)
+                .ok_or_else(|| Error::Encoding(format!("Unknown variant {}", name)))?;
+            let fields: Vec<_> = variant
+                .fields
+                .iter()
+                .map(|field| (field.name.as_deref(), field.ty.id))
+                .collect();
+            Ok(match fields_to_value(fields_json, &fields, types)? {
+                Fields::Named(fields) => Value::named_variant(name, fields),
+                Fields::Unnamed(values) => Value::unnamed_variant(name, values),
+            })
+        }
+        TypeDef::Sequence(sequence) => items_to_value(json, sequence.type_param.id, None, types),
+        TypeDef::Array(array) => {
+            items_to_value(json, array.type_param.id, Some(array.len as usize), types)
+        }
+        TypeDef::Tuple(tuple) => {
+            let values = match json {
+                JsonValue::Null if tuple.fields.is_empty() => Vec::new(),
+                JsonValue::Array(values) if values.len() == tuple.fields.len() => values
+                    .iter()
+                    .zip(&tuple.fields)
+                    .map(|(value, field)| json_to_value(value, field.id, types))
+                    .collect::<Result<_>>()?,
+                _ => return Err(expected(&format!("{} tuple items", tuple.fields.len()))),
+            };
+            Ok(Value::unnamed_composite(values))
+        }
+        TypeDef::Primitive(primitive) => primitive_to_value(json, primitive),
+        TypeDef::Compact(compact) => json_to_value(json, compact.type_param.id, types),
+        TypeDef::BitSequence(_) => Err(Error::Encoding(
+            "Bit sequences are not supported in templates".to_string(),
+        )),
+    }
+}
+
+enum Fields {
+    Named(Vec<(String, Value)>),
+    Unnamed(Vec<Value>),
+}
+
+/// JSON as the fields of a struct or variant
+///
+/// Single-field types also take the field's own JSON.
+fn fields_to_value(
+    json: &JsonValue,
+    fields: &[(Option<&str>, u32)],
+    types: &PortableRegistry,
+) -> Result<Fields> {
+    let named = fields.first().is_some_and(|(name, _)| name.is_some());
+    match (json, fields) {
+        (JsonValue::Null, []) => Ok(Fields::Unnamed(Vec::new())),
+        (JsonValue::Object(object), _) if named && object.len() == fields.len() => fields
+            .iter()
+            .map(|(name, ty)| {
+                let name = name.unwrap_or_default();
+                let value = object
+                    .get(name)
+                    .ok_or_else(|| Error::Encoding(format!("Missing field {}", name)))?;
+                Ok((name.to_string(), json_to_value(value, *ty, types)?))
+            })
+            .collect::<Result<_>>()
+            .map(Fields::Named),
+        (_, [(name, ty)]) => {
+            let value = json_to_value(json, *ty, types)?;
+            Ok(match name {
+                Some(name) => Fields::Named(vec![(name.to_string(), value)]),
+                None => Fields::Unnamed(vec![value]),
+            })
+        }
+        (JsonValue::Array(values), _) if !named && values.len() == fields.len() => values
+            .iter()
+            .zip(fields)
+            .map(|(value, (_, ty))| json_to_value(value, *ty, types))
+            .collect::<Result<_>>()
+            .map(Fields::Unnamed),
+        _ => Err(Error::Encoding(format!(
+            "Expected {} fields, got {}",
+            fields.len(),
+            json
+        ))),
+    }
+}
+
+/// JSON array, or a hex or address string for bytes, as a sequence
+fn items_to_value(
+    json: &JsonValue,
+    item_ty: u32,
+    len: Option<usize>,
+    types: &PortableRegistry,
+) -> Result<Value> {
+    let is_byte = types
+        .resolve(item_ty)
+        .is_some_and(|item| matches!(item.type_def, TypeDef::Primitive(TypeDefPrimitive::U8)));
+    let values = match json {
+        JsonValue::String(text) if is_byte => {
+            let bytes = match text.strip_prefix("0x") {
+                Some(hex) => hex::decode(hex)
+                    .map_err(|e| Error::Encoding(format!("Invalid hex {}: {}", text, e)))?,
+                None if len == Some(32) => account_id(text)?.to_vec(),
+                None => text.as_bytes().to_vec(),
+            };
+            if len.is_none() {
+                return Ok(Value::from_bytes(bytes));
+            }
+            bytes.into_iter().map(|b| Value::u128(b as u128)).collect()
+        }
+        JsonValue::Array(values) => values
+            .iter()
+            .map(|value| json_to_value(value, item_ty, types))
+            .collect::<Result<Vec<_>>>()?,
+        // decoded one-item sequences are rendered as the item itself
+        item if len.unwrap_or(1) == 1 && !item.is_null() => {
+            vec![json_to_value(item, item_ty, types)?]
+        }
+        _ => return Err(Error::Encoding(format!("Expected an array, got {}", json))),
+    };
+    if let Some(len) = len.filter(|len| *len != values.len()) {
+        return Err(Error::Encoding(format!(
+            "Expected {} items, got {}",
+            len,
+            values.len()
+        )));
+    }
+    Ok(Value::unnamed_composite(values))
+}
+
+fn primitive_to_value(json: &JsonValue, primitive: &TypeDefPrimitive) -> Result<Value> {
+    let expected = |what: &str| Error::Encoding(format!("Expected {}, got {}", what, json));
+    let unsigned = || match json {
+        JsonValue::Number(n) => n.as_u64().map(u128::from),
+        JsonValue::String(s) => s.parse::<u128>().ok(),
+        _ => None,
+    };
+    let signed = || match json {
+        JsonValue::Number(n) => n.as_i64().map(i128::from),
+        JsonValue::String(s) => s.parse::<i128>().ok(),
+        _ => None,
+    };
+    match primitive {
+        TypeDefPrimitive::Bool => json
+            .as_bool()
+            .map(Value::bool)
+            .ok_or_else(|| expected("a bool")),
+        TypeDefPrimitive::Char => json
+            .as_str()
+            .and_then(|s| s.chars().next().filter(|_| s.chars().count() == 1))
+            .map(Value::char)
+            .ok_or_else(|| expected("a character")),
+        TypeDefPrimitive::Str => json
+            .as_str()
+            .map(Value::string)
+            .ok_or_else(|| expected("a string")),
+        TypeDefPrimitive::U8
+        | TypeDefPrimitive::U16
+        | TypeDefPrimitive::U32
+        | TypeDefPrimitive::U64
+        | TypeDefPrimitive::U128 => unsigned()
+            .map(Value::u128)
+            .ok_or_else(|| expected("an unsigned integer")),
+        TypeDefPrimitive::I8
+        | TypeDefPrimitive::I16
+        | TypeDefPrimitive::I32
+        | TypeDefPrimitive::I64
+        | TypeDefPrimitive::I128 => signed()
+            .map(Value::i128)
+            .ok_or_else(|| expected("an integer")),
+        TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => {
+            let bytes: [u8; 32] = json
+                .as_str()
+                .and_then(|s| s.strip_prefix("0x"))
+                .and_then(|hex| hex::decode(hex).ok())
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| expected("32 bytes of hex"))?;
+            Ok(Value::primitive(match primitive {
+                TypeDefPrimitive::U256 => Primitive::U256(bytes),
+                _ => Primitive::I256(bytes),
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale_info::{MetaType, Registry, TypeInfo};
+    use serde_json::json;
+
+    fn payout() -> TxTemplate {
+        TxTemplate::new("payout", "Staking", "payout_stakers")
+            .with_arg("validator_stash", json!("{{validator}}"))
+            .with_arg("era", json!("{{era}}"))
+            .with_arg("memo", json!("era {{era}} payout"))
+    }
+
+    #[test]
+    fn test_instantiate() {
+        let template = payout();
+        assert_eq!(
+            template.placeholders(),
+            BTreeSet::from(["era".to_string(), "validator".to_string()])
+        );
+
+        let args = template
+            .instantiate(&json!({ "validator": "0xd4", "era": 1234 }))
+            .unwrap();
+        assert_eq!(args["validator_stash"], json!("0xd4"));
+        assert_eq!(args["era"], json!(1234));
+        assert_eq!(args["memo"], json!("era 1234 payout"));
+
+        assert!(template.instantiate(&json!({ "era": 1 })).is_err());
+        assert!(template
+            .instantiate(&json!({ "validator": "0xd4", "era": 1, "tip": 5 }))
+            .is_err());
+
+        let mut library = TemplateLibrary::default();
+        library.insert(template.clone());
+        let json = serde_json::to_string(&library).unwrap();
+        let restored: TemplateLibrary = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.get("payout"), Some(&template));
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum MultiAddress {
+        Id([u8; 32]),
+        Index(#[codec(compact)] u32),
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    struct Transfer {
+        dest: MultiAddress,
+        value: u128,
+        memo: Option<Vec<u8>>,
+    }
+
+    #[test]
+    fn test_json_to_value() {
+        let mut registry = Registry::new();
+        let ty = registry.register_type(&MetaType::new::<Transfer>()).id;
+        let types: PortableRegistry = registry.into();
+
+        let value = json_to_value(
+            &json!({
+                "dest": { "Id": "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY" },
+                "value": "1000000000000000000000",
+                "memo": null,
+            }),
+            ty,
+            &types,
+        )
+        .unwrap();
+        let expected = Value::named_composite([
+            (
+                "dest",
+                Value::unnamed_variant(
+                    "Id",
+                    [Value::unnamed_composite(
+                        account_id("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")
+                            .unwrap()
+                            .map(|b| Value::u128(b as u128)),
+                    )],
+                ),
+            ),
+            ("value", Value::u128(1_000_000_000_000_000_000_000)),
+            ("memo", Value::unnamed_variant("None", [])),
+        ]);
+        assert_eq!(value, expected);
+
+        let with_memo = json!({ "dest": { "Index": 7 }, "value": 1, "memo": { "Some": "0x0102" } });
+        assert!(json_to_value(&with_memo, ty, &types).is_ok());
+        let wrong = json!({ "dest": { "Account": 7 }, "value": 1, "memo": null });
+        assert!(json_to_value(&wrong, ty, &types).is_err());
+    }
+}
@@ -7,16 +7,43 @@
 //! - Retry logic with exponential backoff
 //! - Transaction confirmation tracking
 
-use crate::{Error, Metrics, Result, Sr25519Signer, Wallet};
+use crate::assets::AssetId;
+use crate::audit_log::{payload_hash, AuditLog, AuditOperation, AuditOutcome};
+use crate::batch_chunks::{split_by_length, BatchChunk, ChunkOutcome, ChunkedBatch};
+use crate::block_limits::{decode_dispatch_info, BlockLimits, DispatchClass};
+use crate::capabilities::FeeStrategy;
+use crate::event_query::account_id;
+use crate::expiry::{
+    notify, ExpiryHandler, ExpiryNotice, ExpiryStatus, ExpiryTracker, ExpiryWatch,
+    DEFAULT_EXPIRY_WARNING_BLOCKS,
+};
+use crate::metadata_hash::{requires_metadata_hash, MetadataHasher};
+use crate::mortality::era_of;
+use crate::policy::{Outflow, OutgoingTransaction, OverrideToken, Policy};
+use crate::reference::PaymentReference;
+use crate::template::TxTemplate;
+use crate::watch_only::unsigned_transaction;
+use crate::{Error, Metrics, Result, Sr25519Signer, StorageClient, Wallet};
+use apex_sdk_core::screening::{AddressScreener, ScreeningResult};
 use apex_sdk_core::{FeeEstimator, SdkError};
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
+use subxt::backend::rpc::RpcClient;
+use subxt::config::{DefaultExtrinsicParams, DefaultExtrinsicParamsBuilder, ExtrinsicParams};
+use subxt::ext::subxt_rpcs::client::RpcParams;
+use subxt::tx::{Signer, SubmittableTransaction};
 use subxt::{OnlineClient, PolkadotConfig};
+use tokio::sync::Mutex;
 use tokio::time::sleep;
 use tracing::{debug, info, warn};
 
 /// Batch transaction execution mode
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum BatchMode {
     /// Batch calls optimistically - continue even if some fail
     /// Uses `Utility::batch`
@@ -50,6 +77,13 @@ impl BatchCall {
             args_encoded,
         }
     }
+
+    /// Encoded `RuntimeCall`: pallet index, call index and arguments
+    pub fn call_data(&self) -> Vec<u8> {
+        let mut call_data = vec![self.pallet_index, self.call_index];
+        call_data.extend_from_slice(&self.args_encoded);
+        call_data
+    }
 }
 
 /// Fee estimation configuration
@@ -61,6 +95,9 @@ pub struct FeeConfig {
     pub max_fee: Option<u128>,
     /// Tip to include with transaction
     pub tip: u128,
+    /// `pallet-assets` asset to pay the fee and tip in through
+    /// `ChargeAssetTxPayment`; the native token if `None`
+    pub fee_asset: Option<u32>,
 }
 
 impl Default for FeeConfig {
@@ -69,6 +106,7 @@ impl Default for FeeConfig {
             multiplier: 1.2,
             max_fee: None,
             tip: 0,
+            fee_asset: None,
         }
     }
 }
@@ -96,6 +134,27 @@ impl FeeConfig {
         self.tip = tip;
         self
     }
+
+    /// Pay the fee and tip in a `pallet-assets` asset
+    ///
+    /// The chain must have the `ChargeAssetTxPayment` extension with `u32`
+    /// asset ids.
+    pub fn with_fee_asset(mut self, asset_id: u32) -> Self {
+        self.fee_asset = Some(asset_id);
+        self
+    }
+
+    /// Transaction parameters carrying the tip and fee asset
+    fn tx_params(
+        &self,
+    ) -> <DefaultExtrinsicParams<PolkadotConfig> as ExtrinsicParams<PolkadotConfig>>::Params {
+        let params = DefaultExtrinsicParamsBuilder::<PolkadotConfig>::new();
+        match self.fee_asset {
+            Some(asset_id) => params.tip_of(self.tip, asset_id),
+            None => params.tip(self.tip),
+        }
+        .build()
+    }
 }
 
 /// Retry configuration for transaction submission
@@ -144,9 +203,25 @@ impl RetryConfig {
 /// Transaction executor for building and submitting extrinsics
 pub struct TransactionExecutor {
     client: OnlineClient<PolkadotConfig>,
+    rpc: Option<RpcClient>,
     fee_config: FeeConfig,
+    fee_strategy: FeeStrategy,
     retry_config: RetryConfig,
     metrics: Metrics,
+    audit_log: Option<AuditLog>,
+    policy: Option<Policy>,
+    screener: Option<Arc<dyn AddressScreener>>,
+    expiry_handlers: Vec<Arc<dyn ExpiryHandler>>,
+    expiry_warning_blocks: u64,
+    /// Metadata hash with the spec version it was computed for
+    metadata_hash: Mutex<Option<(u32, [u8; 32])>>,
+}
+
+/// Fee part of a `payment_queryInfo` response
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PaymentQueryInfo {
+    partial_fee: serde_json::Value,
 }
 
 impl TransactionExecutor {
@@ -154,12 +229,34 @@ impl TransactionExecutor {
     pub fn new(client: OnlineClient<PolkadotConfig>, metrics: Metrics) -> Self {
         Self {
             client,
+            rpc: None,
             fee_config: FeeConfig::default(),
+            fee_strategy: FeeStrategy::default(),
             retry_config: RetryConfig::default(),
             metrics,
+            audit_log: None,
+            policy: None,
+            screener: None,
+            expiry_handlers: Vec::new(),
+            expiry_warning_blocks: DEFAULT_EXPIRY_WARNING_BLOCKS,
+            metadata_hash: Mutex::new(None),
         }
     }
 
+    /// Raw RPC client, needed by [`FeeStrategy::PaymentRpc`] and to sign
+    /// with the metadata hash on chains with the `CheckMetadataHash` extension
+    pub fn with_rpc_client(mut self, rpc: RpcClient) -> Self {
+        self.rpc = Some(rpc);
+        self
+    }
+
+    /// Set how fees are estimated, usually from
+    /// [`Capabilities::fee_strategy`](crate::Capabilities::fee_strategy)
+    pub fn with_fee_strategy(mut self, fee_strategy: FeeStrategy) -> Self {
+        self.fee_strategy = fee_strategy;
+        self
+    }
+
     /// Set the fee configuration
     pub fn with_fee_config(mut self, fee_config: FeeConfig) -> Self {
         self.fee_config = fee_config;
@@ -172,6 +269,145 @@ impl TransactionExecutor {
         self
     }
 
+    /// Record every signing request and submission in `audit_log`
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Check transfers, calls and batches against `policy` before signing
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Screen the source and destination of every transfer before signing
+    ///
+    /// Addresses are passed to the screener as hex account ids. Transfers
+    /// whose recipient cannot be read, such as XCM transfers, are refused.
+    pub fn with_address_screener(mut self, screener: Arc<dyn AddressScreener>) -> Self {
+        self.screener = Some(screener);
+        self
+    }
+
+    /// Notify `handler` when a submitted transaction nears or passes the end
+    /// of its era without being included
+    pub fn with_expiry_handler(mut self, handler: Arc<dyn ExpiryHandler>) -> Self {
+        self.expiry_handlers.push(handler);
+        self
+    }
+
+    /// Blocks before the end of its era at which a pending transaction is
+    /// reported as about to expire
+    pub fn with_expiry_warning(mut self, blocks: u64) -> Self {
+        self.expiry_warning_blocks = blocks;
+        self
+    }
+
+    /// Submit a transfer of any asset held by the chain
+    ///
+    /// Native tokens go through `Balances`, other assets through `Assets` or
+    /// `ForeignAssets`; all transfers keep the sender alive.
+    pub async fn transfer_asset(
+        &self,
+        from: &Wallet,
+        to: &str,
+        asset: &AssetId,
+        amount: u128,
+    ) -> Result<String> {
+        self.submit_transfer(from, to, asset, amount, None).await
+    }
+
+    /// Submit a transfer like [`transfer_asset`](Self::transfer_asset),
+    /// letting it exceed the signer's spending limits with an operator's
+    /// override token
+    ///
+    /// The token is used up by this transfer, whether it is approved or not.
+    pub async fn transfer_asset_with_override(
+        &self,
+        from: &Wallet,
+        to: &str,
+        asset: &AssetId,
+        amount: u128,
+        token: &OverrideToken,
+    ) -> Result<String> {
+        self.submit_transfer(from, to, asset, amount, Some(token))
+            .await
+    }
+
+    /// Submit a `transfer_keep_alive` of `asset`, presenting `token` to the policy
+    async fn submit_transfer(
+        &self,
+        from: &Wallet,
+        to: &str,
+        asset: &AssetId,
+        amount: u128,
+        token: Option<&OverrideToken>,
+    ) -> Result<String> {
+        info!(
+            "Submitting {:?} transfer from {} to {} of {} units",
+            asset,
+            from.address(),
+            to,
+            amount
+        );
+
+        use subxt::dynamic::Value;
+        let dest = account_id(to)
+            .map_err(|e| Error::Transaction(format!("Invalid destination address: {}", e)))?;
+        let dest_value = Value::unnamed_variant("Id", vec![Value::from_bytes(dest)]);
+
+        // the native token has no asset id argument
+        let args = asset
+            .pallet_key()?
+            .into_iter()
+            .chain([dest_value, Value::u128(amount)])
+            .collect::<Vec<_>>();
+        let transfer_call = subxt::dynamic::tx(asset.pallet(), "transfer_keep_alive", args);
+
+        let mut outgoing = OutgoingTransaction::transfer(from.address(), to, asset.clone(), amount);
+        if let Some(token) = token {
+            outgoing = outgoing.with_override_token(&token.id);
+        }
+        self.submit_authorized(&[outgoing], &transfer_call, from)
+            .await
+    }
+
+    /// Submit any call by pallet and call name, with retries
+    pub async fn submit_call(
+        &self,
+        from: &Wallet,
+        pallet: &str,
+        call: &str,
+        args: Vec<subxt::dynamic::Value>,
+    ) -> Result<String> {
+        info!("Submitting {}::{} from {}", pallet, call, from.address());
+        let tx = subxt::dynamic::tx(pallet, call, args);
+        let call_data = self
+            .client
+            .tx()
+            .call_data(&tx)
+            .map_err(|e| Error::Encoding(format!("Failed to encode call: {}", e)))?;
+        let outgoing = self.outgoing_of(
+            &from.address(),
+            &call_data,
+            vec![OutgoingTransaction::call(from.address(), pallet, call)],
+        )?;
+        self.submit_authorized(&outgoing, &tx, from).await
+    }
+
+    /// Fill in a saved template and submit it like [`submit_call`](Self::submit_call)
+    pub async fn submit_template(
+        &self,
+        from: &Wallet,
+        template: &TxTemplate,
+        params: &serde_json::Value,
+    ) -> Result<String> {
+        let args = template.call_args(params, &self.client.metadata())?;
+        self.submit_call(from, &template.pallet, &template.call, args)
+            .await
+    }
+
     /// Submit a balance transfer transaction
     pub async fn transfer(&self, from: &Wallet, to: &str, amount: u128) -> Result<String> {
         info!(
@@ -195,7 +431,177 @@ impl TransactionExecutor {
             vec![dest_value, Value::u128(amount)],
         );
 
-        self.submit_extrinsic_with_retry(&transfer_call, from).await
+        let outgoing = OutgoingTransaction::transfer(from.address(), to, AssetId::Native, amount);
+        self.submit_authorized(&[outgoing], &transfer_call, from)
+            .await
+    }
+
+    /// Submit a balance transfer tagged with `reference`
+    ///
+    /// The transfer and a `System::remark` of the reference go in one
+    /// `Utility::batch_all`, so a [`ReferenceScanner`](crate::ReferenceScanner)
+    /// finds the transfer by its reference.
+    pub async fn transfer_with_reference(
+        &self,
+        from: &Wallet,
+        to: &str,
+        amount: u128,
+        reference: &PaymentReference,
+    ) -> Result<String> {
+        info!(
+            "Submitting transfer from {} to {} of {} units with reference {}",
+            from.address(),
+            to,
+            amount,
+            reference
+        );
+
+        use subxt::dynamic::Value;
+        let dest = account_id(to)
+            .map_err(|e| Error::Transaction(format!("Invalid destination address: {}", e)))?;
+        let dest_value = Value::unnamed_variant("Id", vec![Value::from_bytes(dest)]);
+
+        let transfer = Value::unnamed_variant(
+            "Balances",
+            [Value::named_variant(
+                "transfer_keep_alive",
+                [("dest", dest_value), ("value", Value::u128(amount))],
+            )],
+        );
+        let remark = Value::unnamed_variant(
+            "System",
+            [Value::named_variant(
+                "remark",
+                [("remark", Value::from_bytes(reference.to_remark()))],
+            )],
+        );
+        let batch = subxt::dynamic::tx(
+            "Utility",
+            "batch_all",
+            vec![Value::unnamed_composite([transfer, remark])],
+        );
+
+        let outgoing = [
+            OutgoingTransaction::transfer(from.address(), to, AssetId::Native, amount),
+            OutgoingTransaction::call(from.address(), "System", "remark"),
+            OutgoingTransaction::call(from.address(), "Utility", "batch_all"),
+        ];
+        self.submit_authorized(&outgoing, &batch, from).await
+    }
+
+    /// Submit with retries once screening and the policy, if any, approve
+    /// `outgoing`
+    ///
+    /// Approved amounts are released if the submission fails.
+    async fn submit_authorized<Call>(
+        &self,
+        outgoing: &[OutgoingTransaction],
+        call: &Call,
+        signer: &Wallet,
+    ) -> Result<String>
+    where
+        Call: subxt::tx::Payload,
+    {
+        let outflows = self.authorize(outgoing).await?;
+        let result = self.submit_extrinsic_with_retry(call, signer).await;
+        if result.is_err() {
+            self.release(&outflows).await;
+        }
+        result
+    }
+
+    /// Have the screener and policy, if any, approve all of `outgoing`
+    ///
+    /// Returns the outflows recorded for the approved transfers.
+    async fn authorize(&self, outgoing: &[OutgoingTransaction]) -> Result<Vec<Outflow>> {
+        self.check_paused(outgoing).await?;
+        self.screen(outgoing).await?;
+        let Some(policy) = &self.policy else {
+            return Ok(Vec::new());
+        };
+        let mut outflows = Vec::new();
+        for transaction in outgoing {
+            match policy.authorize(transaction).await {
+                Ok(outflow) => outflows.extend(outflow),
+                Err(e) => {
+                    self.release(&outflows).await;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(outflows)
+    }
+
+    /// What the screener and policy check of the encoded `call_data`: the
+    /// call and the transfers it makes
+    ///
+    /// Without a screener or policy the call is not decoded, and `fallback`
+    /// is checked for paused calls instead.
+    fn outgoing_of(
+        &self,
+        signer: &str,
+        call_data: &[u8],
+        fallback: Vec<OutgoingTransaction>,
+    ) -> Result<Vec<OutgoingTransaction>> {
+        if self.policy.is_none() && self.screener.is_none() {
+            return Ok(fallback);
+        }
+        OutgoingTransaction::from_call_data(signer, call_data, &self.client.metadata())
+    }
+
+    /// Reject calls that safe mode or `TxPause` would filter
+    async fn check_paused(&self, outgoing: &[OutgoingTransaction]) -> Result<()> {
+        let metadata = self.client.metadata();
+        if metadata.pallet_by_name("SafeMode").is_none()
+            && metadata.pallet_by_name("TxPause").is_none()
+        {
+            return Ok(());
+        }
+        let state = StorageClient::new(self.client.clone(), self.metrics.clone())
+            .pause_state()
+            .await?;
+        outgoing
+            .iter()
+            .try_for_each(|transaction| state.check(&transaction.pallet, &transaction.call))
+    }
+
+    /// Reject transfers the screener blocks, or cannot screen
+    async fn screen(&self, outgoing: &[OutgoingTransaction]) -> Result<()> {
+        let Some(screener) = &self.screener else {
+            return Ok(());
+        };
+        for transaction in outgoing {
+            for (source, destination) in screening_pairs(transaction)? {
+                match screener.screen(&source, &destination).await {
+                    Ok(ScreeningResult::Clear) => {}
+                    Ok(ScreeningResult::Blocked { address, reason }) => {
+                        return Err(Error::PolicyViolation(format!(
+                            "Address {} blocked by screening: {}",
+                            address, reason
+                        )))
+                    }
+                    Err(e) => {
+                        return Err(Error::PolicyViolation(format!(
+                            "Address screening failed: {}",
+                            e
+                        )))
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Give back the outflows of a transaction that failed
+    async fn release(&self, outflows: &[Outflow]) {
+        let Some(policy) = &self.policy else {
+            return;
+        };
+        for outflow in outflows {
+            if let Err(e) = policy.release(outflow).await {
+                warn!("Failed to release outflow of failed transaction: {}", e);
+            }
+        }
     }
 
     /// Submit an extrinsic with retry logic
@@ -219,8 +625,30 @@ impl TransactionExecutor {
                     self.metrics.record_transaction_success();
                     return Ok(hash);
                 }
+                Err(Error::Expired {
+                    extrinsic_hash,
+                    valid_until,
+                }) if attempts < self.retry_config.max_retries => {
+                    // a fresh era is all it takes, so there is no point in waiting
+                    warn!(
+                        "Transaction {} expired after block {}. Resubmitting",
+                        extrinsic_hash, valid_until
+                    );
+                    self.notify_expired(extrinsic_hash, valid_until, true).await;
+                }
                 Err(e) => {
-                    if attempts >= self.retry_config.max_retries {
+                    if let Error::Expired {
+                        extrinsic_hash,
+                        valid_until,
+                    } = &e
+                    {
+                        self.notify_expired(extrinsic_hash.clone(), *valid_until, false)
+                            .await;
+                    }
+                    // resubmitting cannot make the extrinsic fit
+                    if attempts >= self.retry_config.max_retries
+                        || matches!(e, Error::ExhaustsResources { .. })
+                    {
                         warn!("Transaction failed after {} attempts: {}", attempts, e);
                         self.metrics.record_transaction_failure();
                         return Err(e);
@@ -254,37 +682,211 @@ impl TransactionExecutor {
 
         let apex_signer = Sr25519Signer::new(pair.clone());
 
-        let mut progress = self
+        let Some(audit_log) = &self.audit_log else {
+            let tx = self.create_signed(call, &apex_signer).await?;
+            return self.submit_signed(tx).await;
+        };
+
+        // the request is on record before anything is signed
+        let call_data = self
             .client
             .tx()
-            .sign_and_submit_then_watch_default(call, &apex_signer)
-            .await
-            .map_err(|e| Error::Transaction(format!("Failed to submit transaction: {}", e)))?;
+            .call_data(call)
+            .map_err(|e| Error::Encoding(format!("Failed to encode call: {}", e)))?;
+        let signer_address = signer.address();
+        let call_hash = payload_hash(&call_data);
+        audit_log
+            .record(
+                AuditOperation::Sign,
+                &signer_address,
+                &call_hash,
+                AuditOutcome::Requested,
+            )
+            .await?;
+
+        let tx = match self.create_signed(call, &apex_signer).await {
+            Ok(tx) => tx,
+            Err(e) => {
+                let outcome = AuditOutcome::Failed {
+                    error: e.to_string(),
+                };
+                self.audit(AuditOperation::Sign, &signer_address, &call_hash, outcome)
+                    .await;
+                return Err(e);
+            }
+        };
 
-        while let Some(event) = progress.next().await {
-            let event =
-                event.map_err(|e| Error::Transaction(format!("Transaction error: {}", e)))?;
+        // and the submission before it is broadcast
+        let extrinsic_hash = payload_hash(tx.encoded());
+        audit_log
+            .record(
+                AuditOperation::Submit,
+                &signer_address,
+                &extrinsic_hash,
+                AuditOutcome::Requested,
+            )
+            .await?;
+        let result = self.submit_signed(tx).await;
+        let outcome = match &result {
+            Ok(tx_hash) => AuditOutcome::Included {
+                tx_hash: tx_hash.clone(),
+            },
+            Err(e) => AuditOutcome::Failed {
+                error: e.to_string(),
+            },
+        };
+        self.audit(
+            AuditOperation::Submit,
+            &signer_address,
+            &extrinsic_hash,
+            outcome,
+        )
+        .await;
+        result
+    }
 
-            if event.as_in_block().is_some() {
-                info!("Transaction included in block");
+    /// Sign `call` with the configured fees
+    ///
+    /// On chains with the `CheckMetadataHash` extension the metadata hash is
+    /// enabled, which needs the RPC client; without one subxt's disabled mode
+    /// is signed.
+    async fn create_signed<Call>(
+        &self,
+        call: &Call,
+        signer: &Sr25519Signer,
+    ) -> Result<SubmittableTransaction<PolkadotConfig, OnlineClient<PolkadotConfig>>>
+    where
+        Call: subxt::tx::Payload,
+    {
+        let Some(hash) = self.metadata_hash().await? else {
+            return self
+                .client
+                .tx()
+                .create_signed(call, signer, self.fee_config.tx_params())
+                .await
+                .map_err(|e| Error::Transaction(format!("Failed to sign transaction: {}", e)));
+        };
+        let account = signer.account_id();
+        let unsigned = unsigned_transaction(
+            &self.client,
+            &account,
+            call,
+            || self.fee_config.tx_params(),
+            Some(hash),
+        )
+        .await?;
+        let signature = signer.sign(&unsigned.signer_payload);
+        Ok(unsigned.into_signed(&self.client, &account, &signature))
+    }
+
+    /// Metadata hash to sign with, `None` when the runtime does not check it
+    /// or there is no RPC client to compute it
+    async fn metadata_hash(&self) -> Result<Option<[u8; 32]>> {
+        if !requires_metadata_hash(&self.client.metadata()) {
+            return Ok(None);
+        }
+        let Some(rpc) = &self.rpc else {
+            debug!("No RPC client, signing with the metadata hash disabled");
+            return Ok(None);
+        };
+        let spec_version = self.client.runtime_version().spec_version;
+        let mut cached = self.metadata_hash.lock().await;
+        if let Some((version, hash)) = *cached {
+            if version == spec_version {
+                return Ok(Some(hash));
             }
+        }
+        let hash = MetadataHasher::from_clients(&self.client, rpc)
+            .hash()
+            .await?;
+        *cached = Some((spec_version, hash));
+        Ok(Some(hash))
+    }
 
-            if let Some(finalized) = event.as_finalized() {
-                let tx_hash = format!("0x{}", hex::encode(finalized.extrinsic_hash()));
-                info!("Transaction finalized: {}", tx_hash);
+    /// Prevalidate a signed extrinsic, submit it and wait for finality
+    ///
+    /// With expiry handlers, fails with [`Error::Expired`] if the extrinsic's
+    /// era passes before it is included.
+    async fn submit_signed(
+        &self,
+        tx: subxt::tx::SubmittableTransaction<PolkadotConfig, OnlineClient<PolkadotConfig>>,
+    ) -> Result<String> {
+        self.prevalidate(tx.encoded()).await?;
+        let watch = self.expiry_watch(tx.encoded()).await;
 
-                finalized
-                    .wait_for_success()
-                    .await
-                    .map_err(|e| Error::Transaction(format!("Transaction failed: {}", e)))?;
+        let progress = tx
+            .submit_and_watch()
+            .await
+            .map_err(|e| Error::Transaction(format!("Failed to submit transaction: {}", e)))?;
 
-                return Ok(tx_hash);
-            }
+        let Some(watch) = watch else {
+            return wait_for_finalized(progress).await;
+        };
+        let included = AtomicBool::new(false);
+        tokio::select! {
+            result = wait_for_finalized_marking(progress, &included) => result,
+            expired = watch.run(&self.client, &included) => Err(expired),
         }
+    }
+
+    /// Expiry tracking of a signed extrinsic; `None` without handlers or for
+    /// immortal extrinsics
+    async fn expiry_watch(&self, extrinsic: &[u8]) -> Option<ExpiryWatch> {
+        if self.expiry_handlers.is_empty() {
+            return None;
+        }
+        let era = match era_of(extrinsic, &self.client.metadata()) {
+            Ok(era) => era,
+            Err(e) => {
+                debug!("Not tracking expiry: {}", e);
+                return None;
+            }
+        };
+        // signed against the latest finalized block, so it is in the era's window
+        let signed_at = match self.client.blocks().at_latest().await {
+            Ok(block) => block.number() as u64,
+            Err(e) => {
+                debug!("Not tracking expiry: {}", e);
+                return None;
+            }
+        };
+        Some(ExpiryWatch {
+            extrinsic_hash: payload_hash(extrinsic),
+            tracker: ExpiryTracker::new(era, signed_at, self.expiry_warning_blocks)?,
+            handlers: self.expiry_handlers.clone(),
+        })
+    }
+
+    /// Tell expiry handlers a submission expired
+    async fn notify_expired(&self, extrinsic_hash: String, valid_until: u64, resubmitting: bool) {
+        let notice = ExpiryNotice {
+            extrinsic_hash,
+            head: valid_until,
+            valid_until,
+            status: ExpiryStatus::Expired { resubmitting },
+        };
+        notify(&self.expiry_handlers, &notice).await;
+    }
 
-        Err(Error::Transaction(
-            "Transaction stream ended without finalization".to_string(),
-        ))
+    /// Record the outcome of an operation that already happened
+    ///
+    /// Failing to record it must not hide the outcome from the caller.
+    async fn audit(
+        &self,
+        operation: AuditOperation,
+        signer: &str,
+        payload_hash: &str,
+        outcome: AuditOutcome,
+    ) {
+        let Some(audit_log) = &self.audit_log else {
+            return;
+        };
+        if let Err(e) = audit_log
+            .record(operation, signer, payload_hash, outcome)
+            .await
+        {
+            warn!("Failed to record {:?} in audit log: {}", operation, e);
+        }
     }
 
     /// Estimate fees for a transaction
@@ -315,50 +917,106 @@ impl TransactionExecutor {
 
         let encoded = payload.encoded();
 
+        let Some(base_fee) = self.query_fee(encoded).await? else {
+            warn!("Unexpected fee query response format, using fallback");
+            return Ok(1_000_000u128); // 1 million Planck
+        };
+
+        let estimated_fee = (base_fee as f64 * self.fee_config.multiplier) as u128;
+
+        if let Some(max_fee) = self.fee_config.max_fee {
+            if estimated_fee > max_fee {
+                return Err(Error::Transaction(format!(
+                    "Estimated fee {} exceeds maximum {}",
+                    estimated_fee, max_fee
+                )));
+            }
+        }
+
+        debug!(
+            "Estimated fee: {} (base: {}, multiplier: {})",
+            estimated_fee, base_fee, self.fee_config.multiplier
+        );
+
+        Ok(estimated_fee + self.fee_config.tip)
+    }
+
+    /// Query the partial fee of an encoded extrinsic with the configured strategy
+    ///
+    /// Returns `None` when the response cannot be interpreted.
+    async fn query_fee(&self, extrinsic: &[u8]) -> Result<Option<u128>> {
+        match (self.fee_strategy, &self.rpc) {
+            (FeeStrategy::RuntimeApi, _) => {
+                let result = self.query_dispatch_info(extrinsic).await?;
+
+                // partial_fee is the trailing u128 of RuntimeDispatchInfo
+                Ok(result.len().checked_sub(16).map(|start| {
+                    let mut fee = [0u8; 16];
+                    fee.copy_from_slice(&result[start..]);
+                    u128::from_le_bytes(fee)
+                }))
+            }
+            (FeeStrategy::PaymentRpc, Some(rpc)) => {
+                let mut params = RpcParams::new();
+                params
+                    .push(format!("0x{}", hex::encode(extrinsic)))
+                    .map_err(|e| Error::Encoding(format!("Failed to encode extrinsic: {}", e)))?;
+                let info: PaymentQueryInfo = rpc
+                    .request("payment_queryInfo", params)
+                    .await
+                    .map_err(|e| Error::Transaction(format!("Failed to query fee info: {}", e)))?;
+                Ok(parse_partial_fee(&info.partial_fee))
+            }
+            (FeeStrategy::PaymentRpc, None) | (FeeStrategy::SizeBased, _) => {
+                Ok(Some(size_based_fee(extrinsic)))
+            }
+        }
+    }
+
+    /// Encoded `RuntimeDispatchInfo` of an extrinsic from the runtime API
+    async fn query_dispatch_info(&self, extrinsic: &[u8]) -> Result<Vec<u8>> {
         let call_data = {
             use parity_scale_codec::Encode;
             // query_info(extrinsic: Vec<u8>, len: u32) -> RuntimeDispatchInfo
-            let params = (encoded, encoded.len() as u32);
+            let params = (extrinsic, extrinsic.len() as u32);
             params.encode()
         };
 
-        let result = self
-            .client
+        self.client
             .runtime_api()
             .at_latest()
             .await
             .map_err(|e| Error::Connection(format!("Failed to get latest block: {}", e)))?
             .call_raw("TransactionPaymentApi_query_info", Some(&call_data))
             .await
-            .map_err(|e| Error::Transaction(format!("Failed to query fee info: {}", e)))?;
-
-        if result.len() >= 16 {
-            let fee_bytes = &result[result.len() - 16..];
-            let mut fee_array = [0u8; 16];
-            fee_array.copy_from_slice(fee_bytes);
-            let base_fee = u128::from_le_bytes(fee_array);
-
-            let estimated_fee = (base_fee as f64 * self.fee_config.multiplier) as u128;
-
-            if let Some(max_fee) = self.fee_config.max_fee {
-                if estimated_fee > max_fee {
-                    return Err(Error::Transaction(format!(
-                        "Estimated fee {} exceeds maximum {}",
-                        estimated_fee, max_fee
-                    )));
-                }
-            }
+            .map_err(|e| Error::Transaction(format!("Failed to query fee info: {}", e)))
+    }
 
-            debug!(
-                "Estimated fee: {} (base: {}, multiplier: {})",
-                estimated_fee, base_fee, self.fee_config.multiplier
-            );
+    /// Check that a signed extrinsic fits in a block before submitting it
+    ///
+    /// The length is checked against `System::BlockLength` and, when the
+    /// runtime API reports it, the weight against `System::BlockWeights` for
+    /// the extrinsic's dispatch class. Fails with
+    /// [`Error::ExhaustsResources`] when either is too large.
+    pub async fn prevalidate(&self, extrinsic: &[u8]) -> Result<()> {
+        let dispatch_info = match self.fee_strategy {
+            FeeStrategy::RuntimeApi => match self.query_dispatch_info(extrinsic).await {
+                Ok(info) => decode_dispatch_info(&info),
+                Err(e) => {
+                    debug!("Skipping weight check: {}", e);
+                    None
+                }
+            },
+            _ => None,
+        };
+        let class = dispatch_info.map(|(_, class)| class).unwrap_or_default();
 
-            Ok(estimated_fee + self.fee_config.tip)
-        } else {
-            warn!("Unexpected fee query response format, using fallback");
-            Ok(1_000_000u128) // 1 million Planck
+        let limits = BlockLimits::fetch(&self.client, class)?;
+        limits.check_length(extrinsic.len())?;
+        if let Some((weight, _)) = dispatch_info {
+            limits.check_weight(weight)?;
         }
+        Ok(())
     }
 
     /// Estimate fees for a simple balance transfer (convenience method)
@@ -387,53 +1045,25 @@ impl TransactionExecutor {
     }
 
     /// Estimate fees from raw transaction bytes
+    ///
+    /// Uses the configured [`FeeStrategy`]; endpoints offering neither the
+    /// runtime API nor `payment_queryInfo` get a size-based estimate.
     pub async fn estimate_fee_for_bytes(&self, tx_bytes: &[u8]) -> Result<u128> {
-        // For Substrate, we use the TransactionPayment runtime API to estimate fees
-        // This API can estimate fees for any valid extrinsic
-
-        debug!("Estimating fee from transaction bytes using size-based calculation");
-
-        // For Substrate, fee estimation without full transaction context is challenging
-        // We use a size-based estimation as a reasonable approximation
-        //
-        // Transaction fees in Substrate include:
-        // - Base fee (fixed cost for any transaction)
-        // - Length fee (per-byte cost)
-        // - Weight fee (computational cost)
-        //
-        // Without executing the transaction, we can estimate based on size
-
-        let base_fee = 100_000u128; // Base transaction fee in Planck (~0.0001 DOT)
-        let per_byte_fee = 1_000u128; // Per-byte fee
-
-        // Calculate size-based component
-        let size_fee = (tx_bytes.len() as u128) * per_byte_fee;
-
-        // Add weight estimate based on transaction complexity
-        // Larger transactions typically have more weight
-        let weight_estimate = if tx_bytes.len() > 200 {
-            // Complex transaction (contract call, batch, etc.)
-            500_000u128
-        } else if tx_bytes.len() > 100 {
-            // Medium transaction (transfer with memo, etc.)
-            200_000u128
-        } else {
-            // Simple transaction (basic transfer)
-            100_000u128
-        };
-
-        let total_fee = base_fee + size_fee + weight_estimate;
-
         debug!(
-            "Estimated fee: {} Planck (base: {}, size: {}, weight: {})",
-            total_fee, base_fee, size_fee, weight_estimate
+            "Estimating fee from transaction bytes using {}",
+            self.fee_strategy.method()
         );
 
-        Ok(total_fee)
+        self.query_fee(tx_bytes)
+            .await?
+            .ok_or_else(|| Error::Transaction("Unexpected fee query response format".to_string()))
     }
 
     /// Execute a batch of transactions using the Utility pallet
     ///
+    /// With a policy or screener, each call is decoded and checked with the
+    /// transfers it makes, so they count towards the signer's limits.
+    ///
     /// # Arguments
     /// * `calls` - Vector of calls to execute in batch
     /// * `wallet` - The wallet to sign the batch transaction
@@ -445,6 +1075,21 @@ impl TransactionExecutor {
         calls: Vec<BatchCall>,
         wallet: &Wallet,
         batch_mode: BatchMode,
+    ) -> Result<String> {
+        let mut outgoing = Vec::new();
+        for call in &calls {
+            outgoing.extend(self.outgoing_of(&wallet.address(), &call.call_data(), Vec::new())?);
+        }
+        self.submit_batch(calls, wallet, batch_mode, outgoing).await
+    }
+
+    /// Submit a batch once the policy approves it and the `outgoing` transfers in it
+    async fn submit_batch(
+        &self,
+        calls: Vec<BatchCall>,
+        wallet: &Wallet,
+        batch_mode: BatchMode,
+        mut outgoing: Vec<OutgoingTransaction>,
     ) -> Result<String> {
         debug!(
             "Executing batch of {} calls with mode {:?}",
@@ -457,72 +1102,131 @@ impl TransactionExecutor {
             return Err(Error::Transaction("Cannot execute empty batch".to_string()));
         }
 
-        // generate typed metadata using `subxt codegen` for better type safety
-        let call_values: Vec<subxt::dynamic::Value> = calls
-            .into_iter()
-            .map(|call| {
-                let mut call_bytes = Vec::new();
-                call_bytes.push(call.pallet_index);
-                call_bytes.push(call.call_index);
-                call_bytes.extend_from_slice(&call.args_encoded);
-
-                subxt::dynamic::Value::from_bytes(&call_bytes)
-            })
-            .collect();
-
-        let calls_value = subxt::dynamic::Value::unnamed_composite(call_values);
-
-        // Determine which batch call to use
-        let batch_call_name = match batch_mode {
-            BatchMode::Optimistic => "batch",
-            BatchMode::AllOrNothing => "batch_all",
-            BatchMode::Force => "force_batch",
-        };
-
-        debug!("Using Utility::{} for batch execution", batch_call_name);
-
-        // Create the batch transaction
-        let tx = subxt::dynamic::tx("Utility", batch_call_name, vec![calls_value]);
-
-        let pair = wallet
-            .sr25519_pair()
-            .ok_or_else(|| Error::Transaction("Wallet does not have SR25519 key".to_string()))?;
-
-        let apex_signer = Sr25519Signer::new(pair.clone());
-
-        // Sign and submit
-        let mut signed_tx = self
-            .client
-            .tx()
-            .sign_and_submit_then_watch_default(&tx, &apex_signer)
-            .await
-            .map_err(|e| Error::Transaction(format!("Failed to submit batch: {}", e)))?;
-
-        while let Some(event) = signed_tx.next().await {
-            let event =
-                event.map_err(|e| Error::Transaction(format!("Batch transaction error: {}", e)))?;
+        outgoing.push(OutgoingTransaction::call(
+            wallet.address(),
+            "Utility",
+            batch_call_name(batch_mode),
+        ));
+        let outflows = self.authorize(&outgoing).await?;
+        let tx = batch_payload(&calls, batch_mode);
+        let result = self.submit_extrinsic(&tx, wallet).await;
+        if let Err(Error::Expired {
+            extrinsic_hash,
+            valid_until,
+        }) = &result
+        {
+            self.notify_expired(extrinsic_hash.clone(), *valid_until, false)
+                .await;
+        }
+        if result.is_err() {
+            self.release(&outflows).await;
+        }
+        let tx_hash = result?;
+        info!("Batch transaction finalized: {}", tx_hash);
 
-            if event.as_in_block().is_some() {
-                info!("Batch transaction included in block");
-            }
+        self.metrics.record_transaction_success();
+        Ok(tx_hash)
+    }
 
-            if let Some(finalized) = event.as_finalized() {
-                let tx_hash = format!("0x{}", hex::encode(finalized.extrinsic_hash()));
-                info!("Batch transaction finalized: {}", tx_hash);
+    /// Execute a batch of any size, split into chunks that each fit in a block
+    ///
+    /// Chunks are sized by encoded length and, when the runtime API reports
+    /// weights, by weight, then submitted one after another with
+    /// `batch_mode`. Failed chunks are recorded in the returned
+    /// [`ChunkedBatch`]; with [`BatchMode::AllOrNothing`] the chunks after a
+    /// failure are skipped.
+    pub async fn execute_batch_chunked(
+        &self,
+        calls: Vec<BatchCall>,
+        wallet: &Wallet,
+        batch_mode: BatchMode,
+    ) -> Result<ChunkedBatch> {
+        let ranges = self.plan_batch_chunks(&calls, batch_mode).await?;
+        info!(
+            "Executing {} calls in {} batch chunks",
+            calls.len(),
+            ranges.len()
+        );
 
-                finalized
-                    .wait_for_success()
+        let mut report = ChunkedBatch {
+            mode: batch_mode,
+            chunks: Vec::with_capacity(ranges.len()),
+        };
+        for (index, range) in ranges.into_iter().enumerate() {
+            let outcome = if batch_mode == BatchMode::AllOrNothing && report.failed().is_some() {
+                ChunkOutcome::Skipped
+            } else {
+                match self
+                    .execute_batch(calls[range.clone()].to_vec(), wallet, batch_mode)
                     .await
-                    .map_err(|e| Error::Transaction(format!("Batch transaction failed: {}", e)))?;
+                {
+                    Ok(tx_hash) => ChunkOutcome::Finalized { tx_hash },
+                    Err(e) => {
+                        warn!("Batch chunk {} ({:?}) failed: {}", index, range, e);
+                        ChunkOutcome::Failed {
+                            error: e.to_string(),
+                        }
+                    }
+                }
+            };
+            report.chunks.push(BatchChunk {
+                index,
+                calls: range,
+                outcome,
+            });
+        }
+        Ok(report)
+    }
+
+    /// Split calls into consecutive ranges whose batches fit in a block
+    pub async fn plan_batch_chunks(
+        &self,
+        calls: &[BatchCall],
+        batch_mode: BatchMode,
+    ) -> Result<Vec<Range<usize>>> {
+        let limits = BlockLimits::fetch(&self.client, DispatchClass::Normal)?;
+        let by_length = split_by_length(calls, limits.max_length);
+        if self.fee_strategy != FeeStrategy::RuntimeApi {
+            return Ok(by_length);
+        }
 
-                self.metrics.record_transaction_success();
-                return Ok(tx_hash);
+        // halve chunks until the runtime reports a weight within the limit
+        let mut pending: Vec<_> = by_length.into_iter().rev().collect();
+        let mut chunks = Vec::with_capacity(pending.len());
+        while let Some(range) = pending.pop() {
+            if range.len() > 1
+                && !self
+                    .batch_fits(&calls[range.clone()], batch_mode, &limits)
+                    .await?
+            {
+                let middle = range.start + range.len() / 2;
+                pending.push(middle..range.end);
+                pending.push(range.start..middle);
+                continue;
             }
+            chunks.push(range);
         }
+        Ok(chunks)
+    }
 
-        Err(Error::Transaction(
-            "Batch transaction stream ended without finalization".to_string(),
-        ))
+    /// Whether the batch of `calls` is within the weight limit
+    async fn batch_fits(
+        &self,
+        calls: &[BatchCall],
+        batch_mode: BatchMode,
+        limits: &BlockLimits,
+    ) -> Result<bool> {
+        let tx = self
+            .client
+            .tx()
+            .create_unsigned(&batch_payload(calls, batch_mode))
+            .map_err(|e| Error::Transaction(format!("Failed to create unsigned tx: {}", e)))?;
+        let Some((weight, _)) =
+            decode_dispatch_info(&self.query_dispatch_info(tx.encoded()).await?)
+        else {
+            return Ok(true);
+        };
+        Ok(limits.check_weight(weight).is_ok())
     }
 
     /// Execute a batch of balance transfers
@@ -538,6 +1242,7 @@ impl TransactionExecutor {
 
         // Convert transfers to BatchCalls
         let mut calls = Vec::new();
+        let mut outgoing = Vec::new();
 
         for (recipient, amount) in transfers {
             let to_account = AccountId32::from_ss58check(&recipient).map_err(|e| {
@@ -555,9 +1260,141 @@ impl TransactionExecutor {
                 call_index: 3,   // transfer_keep_alive (typical index, may vary by chain)
                 args_encoded: args,
             });
+            outgoing.push(OutgoingTransaction::transfer(
+                wallet.address(),
+                recipient,
+                AssetId::Native,
+                amount,
+            ));
         }
 
-        self.execute_batch(calls, wallet, batch_mode).await
+        self.submit_batch(calls, wallet, batch_mode, outgoing).await
+    }
+}
+
+/// `Utility` batch call of `calls` for the mode
+fn batch_payload(calls: &[BatchCall], batch_mode: BatchMode) -> subxt::tx::DynamicPayload {
+    // generate typed metadata using `subxt codegen` for better type safety
+    let call_values: Vec<subxt::dynamic::Value> = calls
+        .iter()
+        .map(|call| subxt::dynamic::Value::from_bytes(call.call_data()))
+        .collect();
+
+    let calls_value = subxt::dynamic::Value::unnamed_composite(call_values);
+
+    let batch_call_name = batch_call_name(batch_mode);
+
+    debug!("Using Utility::{} for batch execution", batch_call_name);
+
+    subxt::dynamic::tx("Utility", batch_call_name, vec![calls_value])
+}
+
+/// `Utility` call submitting a batch in the mode
+fn batch_call_name(batch_mode: BatchMode) -> &'static str {
+    match batch_mode {
+        BatchMode::Optimistic => "batch",
+        BatchMode::AllOrNothing => "batch_all",
+        BatchMode::Force => "force_batch",
+    }
+}
+
+/// Source and destination pairs to screen for a transaction, as hex account
+/// ids
+///
+/// Transactions moving funds to a recipient that cannot be read are refused,
+/// and transfers from an account other than the signer's, such as
+/// `force_transfer`, screen both.
+fn screening_pairs(transaction: &OutgoingTransaction) -> Result<Vec<(String, String)>> {
+    if transaction.unknown_destination {
+        return Err(Error::PolicyViolation(format!(
+            "{}::{} moves funds to a recipient that cannot be screened",
+            transaction.pallet, transaction.call
+        )));
+    }
+    let Some(destination) = &transaction.destination else {
+        return Ok(Vec::new());
+    };
+    let canonical = |address: &str| {
+        account_id(address)
+            .map(|account| format!("0x{}", hex::encode(account)))
+            .map_err(|_| Error::PolicyViolation(format!("Cannot screen address {}", address)))
+    };
+    let destination = canonical(destination)?;
+    std::iter::once(&transaction.signer)
+        .chain(&transaction.source)
+        .map(|source| Ok((canonical(source)?, destination.clone())))
+        .collect()
+}
+
+/// Follow a submitted extrinsic until finalized and return its hash
+pub(crate) async fn wait_for_finalized(
+    progress: subxt::tx::TxProgress<PolkadotConfig, OnlineClient<PolkadotConfig>>,
+) -> Result<String> {
+    wait_for_finalized_marking(progress, &AtomicBool::new(false)).await
+}
+
+/// [`wait_for_finalized`], setting `included` once a block includes the
+/// transaction
+async fn wait_for_finalized_marking(
+    mut progress: subxt::tx::TxProgress<PolkadotConfig, OnlineClient<PolkadotConfig>>,
+    included: &AtomicBool,
+) -> Result<String> {
+    while let Some(event) = progress.next().await {
+        let event = event.map_err(|e| Error::Transaction(format!("Transaction error: {}", e)))?;
+
+        if event.as_in_block().is_some() {
+            info!("Transaction included in block");
+            included.store(true, Ordering::Relaxed);
+        }
+
+        if let Some(finalized) = event.as_finalized() {
+            let tx_hash = format!("0x{}", hex::encode(finalized.extrinsic_hash()));
+            info!("Transaction finalized: {}", tx_hash);
+
+            finalized
+                .wait_for_success()
+                .await
+                .map_err(|e| Error::Transaction(format!("Transaction failed: {}", e)))?;
+
+            return Ok(tx_hash);
+        }
+    }
+
+    Err(Error::Transaction(
+        "Transaction stream ended without finalization".to_string(),
+    ))
+}
+
+/// Approximate fee from the extrinsic size when no fee query is available
+///
+/// Transaction fees include a base fee, a per-byte length fee and a weight
+/// fee; without executing the transaction the weight is guessed from the size.
+fn size_based_fee(tx_bytes: &[u8]) -> u128 {
+    let base_fee = 100_000u128; // Base transaction fee in Planck (~0.0001 DOT)
+    let per_byte_fee = 1_000u128; // Per-byte fee
+
+    let size_fee = (tx_bytes.len() as u128) * per_byte_fee;
+
+    let weight_estimate = if tx_bytes.len() > 200 {
+        // Complex transaction (contract call, batch, etc.)
+        500_000u128
+    } else if tx_bytes.len() > 100 {
+        // Medium transaction (transfer with memo, etc.)
+        200_000u128
+    } else {
+        // Simple transaction (basic transfer)
+        100_000u128
+    };
+
+    base_fee + size_fee + weight_estimate
+}
+
+/// `partialFee` is a decimal string, or a number on some nodes
+fn parse_partial_fee(value: &serde_json::Value) -> Option<u128> {
+    match value {
+        serde_json::Value::String(fee) => fee.parse().ok(),
+        serde_json::Value::Number(fee) => fee.as_u64().map(u128::from),
+        _ => None,
     }
 }
 
@@ -575,6 +1412,44 @@ impl FeeEstimator for TransactionExecutor {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_screening_pairs() {
+        const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+        const ALICE_HEX: &str =
+            "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+        const BOB_HEX: &str = "0x8eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48";
+
+        // SS58 and decoded hex addresses are screened as the same hex
+        let transfer = OutgoingTransaction::transfer(ALICE, BOB, AssetId::Native, 1);
+        assert_eq!(
+            screening_pairs(&transfer).unwrap(),
+            vec![(ALICE_HEX.to_string(), BOB_HEX.to_string())]
+        );
+
+        let mut forced = OutgoingTransaction::transfer(ALICE, BOB_HEX, AssetId::Native, 1);
+        forced.source = Some(BOB.to_string());
+        assert_eq!(
+            screening_pairs(&forced).unwrap(),
+            vec![
+                (ALICE_HEX.to_string(), BOB_HEX.to_string()),
+                (BOB_HEX.to_string(), BOB_HEX.to_string())
+            ]
+        );
+
+        let mut xcm = OutgoingTransaction::call(ALICE, "PolkadotXcm", "transfer_assets");
+        xcm.unknown_destination = true;
+        assert!(matches!(
+            screening_pairs(&xcm),
+            Err(Error::PolicyViolation(_))
+        ));
+        assert!(
+            screening_pairs(&OutgoingTransaction::call(ALICE, "System", "remark"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
     #[test]
     fn test_fee_config() {
         let config = FeeConfig::new()
@@ -587,6 +1462,18 @@ mod tests {
         assert_eq!(config.tip, 100);
     }
 
+    #[test]
+    fn test_fee_fallbacks() {
+        assert_eq!(size_based_fee(&[0u8; 50]), 250_000);
+        assert_eq!(size_based_fee(&[0u8; 150]), 450_000);
+        assert_eq!(
+            parse_partial_fee(&serde_json::json!("1234567890123")),
+            Some(1_234_567_890_123)
+        );
+        assert_eq!(parse_partial_fee(&serde_json::json!(42)), Some(42));
+        assert_eq!(parse_partial_fee(&serde_json::json!(null)), None);
+    }
+
     #[test]
     fn test_retry_config() {
         let config = RetryConfig::new()
@@ -0,0 +1,665 @@
+//! RPC transport setup for Substrate connections
+//!
+//! Builds the underlying JSON-RPC client when a connection needs more than a
+//! plain URL:
+//! - API keys or custom headers sent with the WebSocket handshake
+//! - Custom CA bundles or pinned certificates for `wss://` endpoints
+//! - Tunnelling through HTTP (`CONNECT`) or SOCKS5 proxies
+//! - Local IPC endpoints (unix domain sockets, named pipes on Windows)
+//! - A shared [`ResponseCache`] answering immutable requests without a round trip
+
+use crate::{Error, Result};
+use apex_sdk_core::response_cache::is_immutable_request;
+use apex_sdk_core::{ipc_path, ClientConfig, ProxyConfig, ProxyScheme, ResponseCache, TlsConfig};
+use jsonrpsee::ws_client::{WsClient, WsClientBuilder};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName};
+use serde_json::value::RawValue;
+use std::sync::Arc;
+use subxt::backend::rpc::{RawRpcFuture, RawRpcSubscription, RpcClient, RpcClientT};
+use subxt::ext::subxt_rpcs::Error as RpcError;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+
+/// URL presented in the WebSocket handshake over IPC sockets
+const IPC_HANDSHAKE_URL: &str = "ws://localhost";
+
+/// Largest proxy response header accepted while opening a tunnel
+const MAX_PROXY_RESPONSE_BYTES: usize = 8 * 1024;
+
+/// Open an RPC client to `endpoint`, applying headers, TLS, proxy and cache settings
+pub async fn connect_rpc_client(endpoint: &str, client_config: &ClientConfig) -> Result<RpcClient> {
+    let ws_client = connect_ws_client(endpoint, client_config).await?;
+    Ok(match &client_config.response_cache {
+        Some(cache) => RpcClient::new(CachingRpcClient::new(ws_client, cache.clone())),
+        None => RpcClient::new(ws_client),
+    })
+}
+
+/// RPC client middleware answering immutable requests from a [`ResponseCache`]
+///
+/// Requests that pin a block hash go through the cache; everything else,
+/// including subscriptions, is forwarded unchanged.
+pub struct CachingRpcClient<T> {
+    inner: T,
+    cache: ResponseCache,
+}
+
+impl<T> CachingRpcClient<T> {
+    /// Wrap `inner`, storing immutable responses in `cache`
+    pub fn new(inner: T, cache: ResponseCache) -> Self {
+        Self { inner, cache }
+    }
+
+    /// Get the response cache
+    pub fn cache(&self) -> &ResponseCache {
+        &self.cache
+    }
+}
+
+impl<T: RpcClientT> RpcClientT for CachingRpcClient<T> {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RawRpcFuture<'a, Box<RawValue>> {
+        Box::pin(async move {
+            let raw_params = params.as_ref().map(|p| p.get().to_owned());
+            if !is_immutable_request(method, raw_params.as_deref()) {
+                return self.inner.request_raw(method, params).await;
+            }
+
+            let body = self
+                .cache
+                .get_or_fetch(method, raw_params.as_deref(), || async {
+                    let response = self.inner.request_raw(method, params).await?;
+                    Ok::<_, RpcError>(response.get().to_owned())
+                })
+                .await?;
+            RawValue::from_string(body.to_string()).map_err(RpcError::Deserialization)
+        })
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+    ) -> RawRpcFuture<'a, RawRpcSubscription> {
+        self.inner.subscribe_raw(sub, params, unsub)
+    }
+}
+
+/// Open the WebSocket client underneath [`connect_rpc_client`], without a cache
+pub(crate) async fn connect_ws_client(
+    endpoint: &str,
+    client_config: &ClientConfig,
+) -> Result<WsClient> {
+    match (ipc_path(endpoint), &client_config.proxy) {
+        (Some(path), _) => connect_ipc(path).await,
+        (None, Some(proxy)) => connect_through_proxy(endpoint, client_config, proxy).await,
+        (None, None) => ws_client_builder(client_config)?
+            .build(endpoint)
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to connect: {}", e))),
+    }
+}
+
+/// Open a WebSocket client over a local socket
+///
+/// The node (or a relay in front of it) must accept WebSocket connections on
+/// the socket; headers, proxies and TLS are not used.
+async fn connect_ipc(path: &str) -> Result<WsClient> {
+    let stream = open_ipc_stream(path).await?;
+    WsClientBuilder::default()
+        .build_with_stream(IPC_HANDSHAKE_URL, stream)
+        .await
+        .map_err(|e| Error::Connection(format!("Failed to connect to IPC socket {}: {}", path, e)))
+}
+
+#[cfg(unix)]
+async fn open_ipc_stream(path: &str) -> Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(path)
+        .await
+        .map_err(|e| Error::Connection(format!("Failed to open IPC socket {}: {}", path, e)))
+}
+
+#[cfg(windows)]
+async fn open_ipc_stream(path: &str) -> Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    tokio::net::windows::named_pipe::ClientOptions::new()
+        .open(path)
+        .map_err(|e| Error::Connection(format!("Failed to open named pipe {}: {}", path, e)))
+}
+
+fn ws_client_builder(client_config: &ClientConfig) -> Result<WsClientBuilder> {
+    let mut builder = WsClientBuilder::default().set_headers(header_map(client_config)?);
+    if !client_config.tls.is_default() {
+        builder = builder.with_custom_cert_store(rustls_config(&client_config.tls)?);
+    }
+    Ok(builder)
+}
+
+async fn connect_through_proxy(
+    endpoint: &str,
+    client_config: &ClientConfig,
+    proxy: &ProxyConfig,
+) -> Result<WsClient> {
+    let target = Target::parse(endpoint)?;
+    let stream = open_tunnel(proxy, &target.host, target.port).await?;
+    let builder = ws_client_builder(client_config)?;
+
+    let result = if target.secure {
+        let connector = TlsConnector::from(Arc::new(rustls_config(&client_config.tls)?));
+        let server_name = ServerName::try_from(target.host.clone()).map_err(|e| {
+            Error::Connection(format!("Invalid server name {}: {}", target.host, e))
+        })?;
+        let tls_stream = connector
+            .connect(server_name, stream)
+            .await
+            .map_err(|e| Error::Connection(format!("TLS handshake failed: {}", e)))?;
+        builder.build_with_stream(endpoint, tls_stream).await
+    } else {
+        builder.build_with_stream(endpoint, stream).await
+    };
+
+    result.map_err(|e| Error::Connection(format!("Failed to connect through proxy: {}", e)))
+}
+
+/// Convert the configured headers into an HTTP header map
+pub fn header_map(client_config: &ClientConfig) -> Result<http::HeaderMap> {
+    let mut headers = http::HeaderMap::new();
+
+    for (name, value) in client_config.headers() {
+        let header_name = http::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| Error::Connection(format!("Invalid header name {}: {}", name, e)))?;
+        let mut header_value = http::HeaderValue::from_str(&value)
+            .map_err(|e| Error::Connection(format!("Invalid value for header {}: {}", name, e)))?;
+        header_value.set_sensitive(true);
+        headers.insert(header_name, header_value);
+    }
+
+    Ok(headers)
+}
+
+/// Build the rustls configuration for the given trust settings
+///
+/// Pinned certificates replace the bundled web PKI roots; extra CA bundles are
+/// trusted alongside them.
+pub fn rustls_config(tls: &TlsConfig) -> Result<rustls::ClientConfig> {
+    let mut roots = rustls::RootCertStore::empty();
+    if !tls.is_pinned() {
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    }
+
+    for pem in tls.trusted_certificates() {
+        let certificates = CertificateDer::pem_slice_iter(pem)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| Error::Connection(format!("Invalid certificate: {}", e)))?;
+        if certificates.is_empty() {
+            return Err(Error::Connection(
+                "Certificate bundle contains no certificates".to_string(),
+            ));
+        }
+        for certificate in certificates {
+            roots
+                .add(certificate)
+                .map_err(|e| Error::Connection(format!("Invalid certificate: {}", e)))?;
+        }
+    }
+
+    // Select the provider explicitly so a second one elsewhere in the graph is harmless
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    Ok(rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| Error::Connection(format!("Invalid TLS configuration: {}", e)))?
+        .with_root_certificates(roots)
+        .with_no_client_auth())
+}
+
+/// Host and port of a WebSocket endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Target {
+    host: String,
+    port: u16,
+    secure: bool,
+}
+
+impl Target {
+    fn parse(endpoint: &str) -> Result<Self> {
+        let url = url::Url::parse(endpoint)
+            .map_err(|e| Error::Connection(format!("Invalid endpoint {}: {}", endpoint, e)))?;
+        let secure = match url.scheme() {
+            "wss" => true,
+            "ws" => false,
+            other => {
+                return Err(Error::Connection(format!(
+                    "Unsupported endpoint scheme: {}",
+                    other
+                )))
+            }
+        };
+        let host = url
+            .host_str()
+            .ok_or_else(|| Error::Connection(format!("Endpoint has no host: {}", endpoint)))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = url
+            .port_or_known_default()
+            .unwrap_or(if secure { 443 } else { 80 });
+
+        Ok(Self { host, port, secure })
+    }
+}
+
+/// Open a TCP tunnel to `host:port` through the proxy
+pub async fn open_tunnel(proxy: &ProxyConfig, host: &str, port: u16) -> Result<TcpStream> {
+    let scheme = proxy
+        .scheme()
+        .map_err(|e| Error::Connection(e.to_string()))?;
+    let address = proxy
+        .address()
+        .map_err(|e| Error::Connection(e.to_string()))?;
+
+    let mut stream = TcpStream::connect(&address)
+        .await
+        .map_err(|e| Error::Connection(format!("Failed to reach proxy {}: {}", address, e)))?;
+
+    match scheme {
+        ProxyScheme::Http => http_connect(&mut stream, proxy, host, port).await?,
+        ProxyScheme::Socks5 => {
+            let resolved = tokio::net::lookup_host((host, port))
+                .await
+                .map_err(|e| Error::Connection(format!("Failed to resolve {}: {}", host, e)))?
+                .next()
+                .ok_or_else(|| Error::Connection(format!("No address found for {}", host)))?;
+            socks5_connect(&mut stream, proxy, &SocksAddr::Ip(resolved.ip()), port).await?
+        }
+        ProxyScheme::Socks5h => {
+            socks5_connect(&mut stream, proxy, &SocksAddr::Domain(host), port).await?
+        }
+    }
+
+    Ok(stream)
+}
+
+async fn http_connect<S>(stream: &mut S, proxy: &ProxyConfig, host: &str, port: u16) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let authority = if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    };
+
+    let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+    if let Some((username, password)) = &proxy.credentials {
+        let token = base64_encode(format!("{}:{}", username, password).as_bytes());
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+    }
+    request.push_str("\r\n");
+
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| Error::Connection(format!("Proxy write failed: {}", e)))?;
+
+    // Read byte by byte so no tunnelled data is consumed past the header
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_PROXY_RESPONSE_BYTES {
+            return Err(Error::Connection("Proxy response too large".to_string()));
+        }
+        let byte = stream
+            .read_u8()
+            .await
+            .map_err(|e| Error::Connection(format!("Proxy read failed: {}", e)))?;
+        response.push(byte);
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    let status = status_line.split_whitespace().nth(1).unwrap_or_default();
+    if status != "200" {
+        return Err(Error::Connection(format!(
+            "Proxy refused tunnel: {}",
+            status_line
+        )));
+    }
+
+    Ok(())
+}
+
+/// Destination address sent to a SOCKS5 proxy
+enum SocksAddr<'a> {
+    Ip(std::net::IpAddr),
+    Domain(&'a str),
+}
+
+async fn socks5_connect<S>(
+    stream: &mut S,
+    proxy: &ProxyConfig,
+    address: &SocksAddr<'_>,
+    port: u16,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let io_err = |e: std::io::Error| Error::Connection(format!("SOCKS5 proxy error: {}", e));
+
+    // Greeting: offer username/password only when we have credentials
+    let greeting: &[u8] = if proxy.credentials.is_some() {
+        &[0x05, 0x02, 0x00, 0x02]
+    } else {
+        &[0x05, 0x01, 0x00]
+    };
+    stream.write_all(greeting).await.map_err(io_err)?;
+
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await.map_err(io_err)?;
+    match (choice, &proxy.credentials) {
+        ([0x05, 0x00], _) => {}
+        ([0x05, 0x02], Some((username, password))) => {
+            if username.len() > 255 || password.len() > 255 {
+                return Err(Error::Connection(
+                    "SOCKS5 credentials must be at most 255 bytes".to_string(),
+                ));
+            }
+            let mut auth = vec![0x01, username.len() as u8];
+            auth.extend_from_slice(username.as_bytes());
+            auth.push(password.len() as u8);
+            auth.extend_from_slice(password.as_bytes());
+            stream.write_all(&auth).await.map_err(io_err)?;
+
+            let mut status = [0u8; 2];
+            stream.read_exact(&mut status).await.map_err(io_err)?;
+            if status[1] != 0x00 {
+                return Err(Error::Connection(
+                    "SOCKS5 proxy rejected credentials".to_string(),
+                ));
+            }
+        }
+        _ => {
+            return Err(Error::Connection(
+                "SOCKS5 proxy offered no acceptable authentication method".to_string(),
+            ))
+        }
+    }
+
+    let mut request = vec![0x05, 0x01, 0x00];
+    match address {
+        SocksAddr::Ip(std::net::IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend_from_slice(&ip.octets());
+        }
+        SocksAddr::Ip(std::net::IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend_from_slice(&ip.octets());
+        }
+        SocksAddr::Domain(domain) => {
+            if domain.len() > 255 {
+                return Err(Error::Connection(format!("Host name too long: {}", domain)));
+            }
+            request.push(0x03);
+            request.push(domain.len() as u8);
+            request.extend_from_slice(domain.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await.map_err(io_err)?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await.map_err(io_err)?;
+    if reply[1] != 0x00 {
+        return Err(Error::Connection(format!(
+            "SOCKS5 proxy failed to connect (code {})",
+            reply[1]
+        )));
+    }
+
+    // Skip the bound address the proxy reports back
+    let address_len = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => stream.read_u8().await.map_err(io_err)? as usize,
+        other => {
+            return Err(Error::Connection(format!(
+                "SOCKS5 proxy sent unknown address type {}",
+                other
+            )))
+        }
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await.map_err(io_err)?;
+
+    Ok(())
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        out.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 {
+            ALPHABET[(n >> 6) as usize & 63] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            ALPHABET[n as usize & 63] as char
+        } else {
+            '='
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_header_map_includes_credentials() {
+        let config = ClientConfig::new()
+            .with_bearer_token("token")
+            .with_header("X-Custom", "1");
+        let headers = header_map(&config).unwrap();
+
+        assert_eq!(headers.get("authorization").unwrap(), "Bearer token");
+        assert_eq!(headers.get("x-custom").unwrap(), "1");
+        assert!(headers.get("authorization").unwrap().is_sensitive());
+    }
+
+    #[test]
+    fn test_header_map_rejects_invalid_header() {
+        let config = ClientConfig::new().with_header("bad header", "value");
+        assert!(matches!(header_map(&config), Err(Error::Connection(_))));
+    }
+
+    #[test]
+    fn test_target_parse() {
+        let target = Target::parse("wss://rpc.polkadot.io").unwrap();
+        assert_eq!(target.host, "rpc.polkadot.io");
+        assert_eq!(target.port, 443);
+        assert!(target.secure);
+
+        let target = Target::parse("ws://127.0.0.1:9944").unwrap();
+        assert_eq!(target.port, 9944);
+        assert!(!target.secure);
+
+        assert!(Target::parse("http://127.0.0.1:9933").is_err());
+    }
+
+    #[test]
+    fn test_rustls_config_rejects_invalid_pem() {
+        let tls = TlsConfig::new().with_pinned_certificate("not a certificate");
+        assert!(rustls_config(&tls).is_err());
+        assert!(rustls_config(&TlsConfig::new()).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_open_ipc_stream() {
+        let path = std::env::temp_dir().join(format!("apex-substrate-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+        let accept = tokio::spawn(async move { listener.accept().await.is_ok() });
+        assert!(open_ipc_stream(path.to_str().unwrap()).await.is_ok());
+        assert!(accept.await.unwrap());
+        let _ = std::fs::remove_file(&path);
+
+        let missing = open_ipc_stream("/nonexistent/apex/node.sock").await;
+        assert!(matches!(missing, Err(Error::Connection(_))));
+    }
+
+    struct CountingClient(std::sync::atomic::AtomicUsize);
+
+    impl RpcClientT for CountingClient {
+        fn request_raw<'a>(
+            &'a self,
+            _method: &'a str,
+            _params: Option<Box<RawValue>>,
+        ) -> RawRpcFuture<'a, Box<RawValue>> {
+            Box::pin(async move {
+                self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(RawValue::from_string("{\"number\":\"0x1\"}".to_string()).unwrap())
+            })
+        }
+
+        fn subscribe_raw<'a>(
+            &'a self,
+            _sub: &'a str,
+            _params: Option<Box<RawValue>>,
+            _unsub: &'a str,
+        ) -> RawRpcFuture<'a, RawRpcSubscription> {
+            unimplemented!("subscriptions are not used in these tests")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_caching_rpc_client_serves_immutable_requests() {
+        let client =
+            CachingRpcClient::new(CountingClient(Default::default()), ResponseCache::default());
+        let params = format!("[\"0x{}\"]", "ab".repeat(32));
+
+        for _ in 0..3 {
+            let response = client
+                .request_raw(
+                    "chain_getHeader",
+                    Some(RawValue::from_string(params.clone()).unwrap()),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.get(), "{\"number\":\"0x1\"}");
+        }
+        client.request_raw("chain_getHeader", None).await.unwrap();
+
+        assert_eq!(client.inner.0.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(client.cache().stats().hits, 2);
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"user:pass"), "dXNlcjpwYXNz");
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_tunnel() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                request.push(socket.read_u8().await.unwrap());
+            }
+            socket
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\ntunnelled")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let proxy = ProxyConfig::new(proxy_url).with_basic_auth("user", "pass");
+        let mut stream = open_tunnel(&proxy, "rpc.example.com", 443).await.unwrap();
+
+        let mut payload = [0u8; 9];
+        stream.read_exact(&mut payload).await.unwrap();
+        assert_eq!(&payload, b"tunnelled");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("CONNECT rpc.example.com:443 HTTP/1.1\r\n"));
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 256];
+            let _ = socket.read(&mut buf).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let result = open_tunnel(&ProxyConfig::new(proxy_url), "rpc.example.com", 443).await;
+        assert!(matches!(result, Err(Error::Connection(msg)) if msg.contains("407")));
+    }
+
+    #[tokio::test]
+    async fn test_socks5h_tunnel_sends_domain() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url = format!("socks5h://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+
+            let mut greeting = [0u8; 3];
+            socket.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [0x05, 0x01, 0x00]);
+            socket.write_all(&[0x05, 0x00]).await.unwrap();
+
+            let mut header = [0u8; 5];
+            socket.read_exact(&mut header).await.unwrap();
+            assert_eq!(&header[..4], &[0x05, 0x01, 0x00, 0x03]);
+            let mut domain = vec![0u8; header[4] as usize];
+            socket.read_exact(&mut domain).await.unwrap();
+            let port = socket.read_u16().await.unwrap();
+
+            socket
+                .write_all(&[0x05, 0x00, 0x00, 0x01, 127, 0, 0, 1, 0x1f, 0x90])
+                .await
+                .unwrap();
+            (String::from_utf8(domain).unwrap(), port)
+        });
+
+        open_tunnel(&ProxyConfig::new(proxy_url), "rpc.polkadot.io", 443)
+            .await
+            .unwrap();
+
+        let (domain, port) = server.await.unwrap();
+        assert_eq!(domain, "rpc.polkadot.io");
+        assert_eq!(port, 443);
+    }
+}
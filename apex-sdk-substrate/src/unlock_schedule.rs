@@ -0,0 +1,650 @@
+//! Lock and unlock schedule calculator
+//!
+//! [`UnlockCalculator`] answers "when can I move my funds?" by collecting every
+//! lock on an account and turning it into a timeline of [`UnlockMilestone`]s:
+//! - Conviction voting (`ConvictionVoting::VotingFor`) and legacy democracy
+//!   (`Democracy::VotingOf`) prior locks, votes and delegations
+//! - Staking unbonding chunks and bonded stake (`Staking::Ledger`)
+//! - Vesting schedules (`Vesting::Vesting`)
+//!
+//! Milestones without a block depend on an action first: an ongoing referendum
+//! ending, undelegating, or unbonding. Unbonding chunks are dated by era, so
+//! their blocks are estimates based on the era length. Unlocked funds may still
+//! need an explicit `unlock`, `withdraw_unbonded` or `vest` call.
+//!
+//! Block numbers in voting and vesting records are decoded at the width
+//! reported by [`SubstrateAdapter::block_number_width`].
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{SubstrateAdapter, UnlockCalculator};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let schedule = UnlockCalculator::new(adapter)
+//!     .schedule("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5")
+//!     .await?;
+//!
+//! for milestone in schedule.timeline() {
+//!     println!("{:?}: {} ({:?})", milestone.block, milestone.amount, milestone.source);
+//! }
+//! println!("Locked in 1000 blocks: {}", schedule.locked_at(schedule.current_block + 1000));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::account_id;
+use crate::{Error, Result, SubstrateAdapter};
+use apex_sdk_core::BlockNumberWidth;
+use parity_scale_codec::Decode;
+use serde::{Deserialize, Serialize};
+use subxt::dynamic::Value;
+use tracing::debug;
+
+/// Where a lock comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LockSource {
+    /// OpenGov conviction voting in a track
+    ConvictionVoting {
+        /// Track (class) id
+        class: u16,
+    },
+    /// Legacy democracy pallet voting
+    Democracy,
+    /// Staking bond and unbonding chunks
+    Staking,
+    /// Linear vesting schedule
+    Vesting {
+        /// Amount released per block
+        per_block: u128,
+    },
+}
+
+impl LockSource {
+    fn is_voting(&self) -> bool {
+        matches!(
+            self,
+            LockSource::ConvictionVoting { .. } | LockSource::Democracy
+        )
+    }
+}
+
+/// An amount that stops being locked at a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnlockMilestone {
+    /// Block at which the amount is unlocked, or `None` if an action is needed first
+    pub block: Option<u64>,
+    /// Amount released
+    pub amount: u128,
+    /// Lock the amount is held by
+    pub source: LockSource,
+    /// Whether the block is an estimate
+    pub estimated: bool,
+}
+
+/// All unlock milestones for an account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnlockSchedule {
+    /// Account the schedule is for
+    pub account: String,
+    /// Block the schedule was computed at
+    pub current_block: u64,
+    /// Milestones in no particular order; see [`UnlockSchedule::timeline`]
+    pub milestones: Vec<UnlockMilestone>,
+}
+
+impl UnlockSchedule {
+    /// Milestones ordered by block, with undated milestones last
+    pub fn timeline(&self) -> Vec<&UnlockMilestone> {
+        let mut timeline: Vec<&UnlockMilestone> = self.milestones.iter().collect();
+        timeline.sort_by_key(|milestone| (milestone.block.is_none(), milestone.block));
+        timeline
+    }
+
+    /// Amount still locked at `block`
+    ///
+    /// Balance locks overlap rather than add up, so this is the largest of the
+    /// voting, staking and vesting locks.
+    pub fn locked_at(&self, block: u64) -> u128 {
+        let mut voting = 0u128;
+        let mut staking = 0u128;
+        let mut vesting = 0u128;
+
+        for milestone in &self.milestones {
+            if let LockSource::Vesting { per_block } = milestone.source {
+                let elapsed = block.saturating_sub(self.current_block) as u128;
+                let remaining = milestone
+                    .amount
+                    .saturating_sub(per_block.saturating_mul(elapsed));
+                vesting = vesting.saturating_add(remaining);
+            } else if milestone.block.is_some_and(|at| at <= block) {
+                continue;
+            } else if milestone.source.is_voting() {
+                voting = voting.max(milestone.amount);
+            } else {
+                staking = staking.saturating_add(milestone.amount);
+            }
+        }
+
+        voting.max(staking).max(vesting)
+    }
+
+    /// First block from which nothing is locked, if every lock has a known end
+    pub fn fully_unlocked_at(&self) -> Option<u64> {
+        self.milestones
+            .iter()
+            .map(|milestone| milestone.block)
+            .try_fold(self.current_block, |latest, block| {
+                block.map(|block| latest.max(block))
+            })
+    }
+}
+
+/// Computes unlock schedules over a connected Substrate adapter
+pub struct UnlockCalculator<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> UnlockCalculator<'a> {
+    /// Create a calculator
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Collect the unlock schedule of an account (SS58 or hex)
+    pub async fn schedule(&self, address: &str) -> Result<UnlockSchedule> {
+        match self.adapter.block_number_width()? {
+            BlockNumberWidth::U32 => self.schedule_with::<u32>(address).await,
+            BlockNumberWidth::U64 => self.schedule_with::<u64>(address).await,
+        }
+    }
+
+    /// Schedule on a runtime whose `BlockNumber` is `B`
+    async fn schedule_with<B>(&self, address: &str) -> Result<UnlockSchedule>
+    where
+        B: Decode + Copy + Into<u64>,
+    {
+        let account = account_id(address)?;
+        let current_block = self
+            .adapter
+            .client()
+            .blocks()
+            .at_latest()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to get latest block: {}", e)))?
+            .number() as u64;
+
+        debug!(
+            "Computing unlock schedule for {} at block {}",
+            address, current_block
+        );
+
+        let mut milestones = Vec::new();
+        if self.has_pallet("ConvictionVoting") {
+            milestones.extend(self.conviction_voting::<B>(&account).await?);
+        }
+        if self.has_pallet("Democracy") {
+            if let Some(voting) = self
+                .fetch::<Voting<B>>("Democracy", "VotingOf", vec![Value::from_bytes(account)])
+                .await?
+            {
+                milestones.extend(voting_milestones(&voting, LockSource::Democracy));
+            }
+        }
+        if self.has_pallet("Staking") {
+            milestones.extend(self.staking(&account, current_block).await?);
+        }
+        if self.has_pallet("Vesting") {
+            if let Some(schedules) = self
+                .fetch::<Vec<VestingInfo<B>>>(
+                    "Vesting",
+                    "Vesting",
+                    vec![Value::from_bytes(account)],
+                )
+                .await?
+            {
+                milestones.extend(vesting_milestones(&schedules, current_block));
+            }
+        }
+
+        Ok(UnlockSchedule {
+            account: address.to_string(),
+            current_block,
+            milestones,
+        })
+    }
+
+    async fn conviction_voting<B>(&self, account: &[u8; 32]) -> Result<Vec<UnlockMilestone>>
+    where
+        B: Decode + Copy + Into<u64>,
+    {
+        let classes = self
+            .fetch::<Vec<(u16, u128)>>(
+                "ConvictionVoting",
+                "ClassLocksFor",
+                vec![Value::from_bytes(account)],
+            )
+            .await?
+            .unwrap_or_default();
+
+        let mut milestones = Vec::new();
+        for (class, _) in classes {
+            if let Some(voting) = self
+                .fetch::<Voting<B>>(
+                    "ConvictionVoting",
+                    "VotingFor",
+                    vec![Value::from_bytes(account), Value::u128(class as u128)],
+                )
+                .await?
+            {
+                milestones.extend(voting_milestones(
+                    &voting,
+                    LockSource::ConvictionVoting { class },
+                ));
+            }
+        }
+        Ok(milestones)
+    }
+
+    async fn staking(
+        &self,
+        account: &[u8; 32],
+        current_block: u64,
+    ) -> Result<Vec<UnlockMilestone>> {
+        let controller = self
+            .fetch::<[u8; 32]>("Staking", "Bonded", vec![Value::from_bytes(account)])
+            .await?
+            .unwrap_or(*account);
+        let Some(ledger) = self
+            .fetch::<StakingLedger>("Staking", "Ledger", vec![Value::from_bytes(controller)])
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+
+        let clock = self.era_clock(current_block).await?;
+        Ok(staking_milestones(&ledger, clock.as_ref(), current_block))
+    }
+
+    /// Estimate era boundaries from session progress; `None` without BABE epochs
+    async fn era_clock(&self, current_block: u64) -> Result<Option<EraClock>> {
+        let storage = self.adapter.storage();
+        let (Ok(sessions_per_era), Ok(epoch_duration)) = (
+            storage.get_constant("Staking", "SessionsPerEra"),
+            storage.get_constant("Babe", "EpochDuration"),
+        ) else {
+            return Ok(None);
+        };
+        let sessions_per_era = decode::<u32>(&sessions_per_era, "Staking::SessionsPerEra")?;
+        let epoch_duration = decode::<u64>(&epoch_duration, "Babe::EpochDuration")?;
+
+        let Some(active_era) = self
+            .fetch::<ActiveEraInfo>("Staking", "ActiveEra", vec![])
+            .await?
+        else {
+            return Ok(None);
+        };
+        let current_session = self
+            .fetch::<u32>("Session", "CurrentIndex", vec![])
+            .await?
+            .unwrap_or_default();
+        let era_start_session = self
+            .fetch::<u32>(
+                "Staking",
+                "ErasStartSessionIndex",
+                vec![Value::u128(active_era.index as u128)],
+            )
+            .await?
+            .unwrap_or(current_session);
+
+        let elapsed = current_session.saturating_sub(era_start_session) as u64 * epoch_duration;
+        Ok(Some(EraClock {
+            active_era: active_era.index,
+            era_start_block: current_block.saturating_sub(elapsed),
+            blocks_per_era: sessions_per_era as u64 * epoch_duration,
+        }))
+    }
+
+    async fn fetch<T: Decode>(
+        &self,
+        pallet: &str,
+        item: &str,
+        keys: Vec<Value>,
+    ) -> Result<Option<T>> {
+        self.adapter
+            .storage()
+            .query_storage(pallet, item, keys)
+            .await?
+            .map(|bytes| decode::<T>(&bytes, &format!("{}::{}", pallet, item)))
+            .transpose()
+    }
+
+    fn has_pallet(&self, pallet: &str) -> bool {
+        self.adapter
+            .client()
+            .metadata()
+            .pallet_by_name(pallet)
+            .is_some()
+    }
+}
+
+/// `Voting` of the conviction-voting and democracy pallets; `B` is the block number
+#[derive(Decode)]
+enum Voting<B = u32> {
+    Casting {
+        votes: Vec<(u32, AccountVote)>,
+        _delegations: Delegations,
+        prior: PriorLock<B>,
+    },
+    Delegating {
+        balance: u128,
+        _target: [u8; 32],
+        _conviction: u8,
+        _delegations: Delegations,
+        prior: PriorLock<B>,
+    },
+}
+
+#[derive(Decode)]
+enum AccountVote {
+    Standard { _vote: u8, balance: u128 },
+    Split { aye: u128, nay: u128 },
+    SplitAbstain { aye: u128, nay: u128, abstain: u128 },
+}
+
+impl AccountVote {
+    fn balance(&self) -> u128 {
+        match self {
+            AccountVote::Standard { balance, .. } => *balance,
+            AccountVote::Split { aye, nay } => aye.saturating_add(*nay),
+            AccountVote::SplitAbstain { aye, nay, abstain } => {
+                aye.saturating_add(*nay).saturating_add(*abstain)
+            }
+        }
+    }
+}
+
+#[derive(Decode)]
+struct Delegations {
+    _votes: u128,
+    _capital: u128,
+}
+
+/// Lock left over from expired votes: `(unlock block, amount)`
+#[derive(Decode)]
+struct PriorLock<B = u32>(B, u128);
+
+#[derive(Decode)]
+struct StakingLedger {
+    _stash: [u8; 32],
+    #[codec(compact)]
+    _total: u128,
+    #[codec(compact)]
+    active: u128,
+    unlocking: Vec<UnlockChunk>,
+}
+
+#[derive(Decode)]
+struct UnlockChunk {
+    #[codec(compact)]
+    value: u128,
+    #[codec(compact)]
+    era: u32,
+}
+
+#[derive(Decode)]
+struct ActiveEraInfo {
+    index: u32,
+    _start: Option<u64>,
+}
+
+#[derive(Decode)]
+struct VestingInfo<B = u32> {
+    locked: u128,
+    per_block: u128,
+    starting_block: B,
+}
+
+/// Era to block estimate
+struct EraClock {
+    active_era: u32,
+    era_start_block: u64,
+    blocks_per_era: u64,
+}
+
+impl EraClock {
+    fn block_of(&self, era: u32) -> u64 {
+        let eras_ahead = era.saturating_sub(self.active_era) as u64;
+        self.era_start_block
+            .saturating_add(eras_ahead.saturating_mul(self.blocks_per_era))
+    }
+}
+
+fn voting_milestones<B: Copy + Into<u64>>(
+    voting: &Voting<B>,
+    source: LockSource,
+) -> Vec<UnlockMilestone> {
+    let (prior, pending) = match voting {
+        Voting::Casting { votes, prior, .. } => (
+            prior,
+            votes
+                .iter()
+                .map(|(_, vote)| vote.balance())
+                .max()
+                .unwrap_or(0),
+        ),
+        Voting::Delegating { balance, prior, .. } => (prior, *balance),
+    };
+
+    let mut milestones = Vec::new();
+    if prior.1 > 0 {
+        milestones.push(UnlockMilestone {
+            block: Some(prior.0.into()),
+            amount: prior.1,
+            source,
+            estimated: false,
+        });
+    }
+    if pending > 0 {
+        milestones.push(UnlockMilestone {
+            block: None,
+            amount: pending,
+            source,
+            estimated: false,
+        });
+    }
+    milestones
+}
+
+fn staking_milestones(
+    ledger: &StakingLedger,
+    clock: Option<&EraClock>,
+    current_block: u64,
+) -> Vec<UnlockMilestone> {
+    let mut milestones: Vec<UnlockMilestone> = ledger
+        .unlocking
+        .iter()
+        .map(|chunk| UnlockMilestone {
+            block: clock.map(|clock| clock.block_of(chunk.era).max(current_block)),
+            amount: chunk.value,
+            source: LockSource::Staking,
+            estimated: true,
+        })
+        .collect();
+
+    if ledger.active > 0 {
+        milestones.push(UnlockMilestone {
+            block: None,
+            amount: ledger.active,
+            source: LockSource::Staking,
+            estimated: false,
+        });
+    }
+    milestones
+}
+
+fn vesting_milestones<B: Copy + Into<u64>>(
+    schedules: &[VestingInfo<B>],
+    current_block: u64,
+) -> Vec<UnlockMilestone> {
+    schedules
+        .iter()
+        .filter_map(|schedule| {
+            let start: u64 = schedule.starting_block.into();
+            let vested = schedule
+                .per_block
+                .saturating_mul(current_block.saturating_sub(start) as u128);
+            let remaining = schedule.locked.saturating_sub(vested);
+            if remaining == 0 {
+                return None;
+            }
+
+            let end = (schedule.per_block > 0).then(|| {
+                let blocks = schedule.locked.div_ceil(schedule.per_block);
+                start.saturating_add(u64::try_from(blocks).unwrap_or(u64::MAX))
+            });
+            Some(UnlockMilestone {
+                block: end,
+                amount: remaining,
+                source: LockSource::Vesting {
+                    per_block: schedule.per_block,
+                },
+                estimated: false,
+            })
+        })
+        .collect()
+}
+
+fn decode<T: Decode>(bytes: &[u8], item: &str) -> Result<T> {
+    T::decode(&mut &bytes[..])
+        .map_err(|e| Error::Encoding(format!("Failed to decode {}: {}", item, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_scale_codec::{Compact, Encode};
+
+    #[test]
+    fn test_voting_milestones() {
+        // Casting: two votes and a prior lock of 50 until block 900
+        let mut bytes = vec![0u8];
+        vec![
+            (1u32, (0u8, 0x80u8, 300u128)),
+            (2u32, (0u8, 0x81u8, 100u128)),
+        ]
+        .encode_to(&mut bytes);
+        (0u128, 0u128, 900u32, 50u128).encode_to(&mut bytes);
+
+        let voting = decode::<Voting>(&bytes, "VotingFor").unwrap();
+        let milestones = voting_milestones(&voting, LockSource::ConvictionVoting { class: 0 });
+
+        assert_eq!(milestones.len(), 2);
+        assert_eq!(milestones[0].block, Some(900));
+        assert_eq!(milestones[0].amount, 50);
+        assert_eq!(milestones[1].block, None);
+        assert_eq!(milestones[1].amount, 300);
+
+        // Delegating 1000 with no prior lock
+        let mut bytes = vec![1u8];
+        (1000u128, [9u8; 32], 2u8, 0u128, 0u128, 0u32, 0u128).encode_to(&mut bytes);
+        let voting = decode::<Voting>(&bytes, "VotingOf").unwrap();
+        let milestones = voting_milestones(&voting, LockSource::Democracy);
+        assert_eq!(milestones.len(), 1);
+        assert_eq!(milestones[0].amount, 1000);
+    }
+
+    #[test]
+    fn test_staking_milestones() {
+        let bytes = (
+            [1u8; 32],
+            Compact(600u128),
+            Compact(400u128),
+            vec![(Compact(200u128), Compact(12u32))],
+            Vec::<u32>::new(),
+        )
+            .encode();
+        let ledger = decode::<StakingLedger>(&bytes, "Ledger").unwrap();
+        let clock = EraClock {
+            active_era: 10,
+            era_start_block: 1_000,
+            blocks_per_era: 100,
+        };
+
+        let milestones = staking_milestones(&ledger, Some(&clock), 1_050);
+        assert_eq!(milestones[0].block, Some(1_200));
+        assert_eq!(milestones[0].amount, 200);
+        assert!(milestones[0].estimated);
+        assert_eq!(milestones[1].block, None);
+        assert_eq!(milestones[1].amount, 400);
+
+        let undated = staking_milestones(&ledger, None, 1_050);
+        assert_eq!(undated[0].block, None);
+    }
+
+    #[test]
+    fn test_vesting_milestones() {
+        let bytes = vec![(1_000u128, 10u128, 100u32), (50u128, 1u128, 0u32)].encode();
+        let schedules = decode::<Vec<VestingInfo>>(&bytes, "Vesting").unwrap();
+        let milestones = vesting_milestones(&schedules, 150);
+
+        // Second schedule is fully vested by block 150
+        assert_eq!(milestones.len(), 1);
+        assert_eq!(milestones[0].block, Some(200));
+        assert_eq!(milestones[0].amount, 500);
+
+        // chains with 64-bit block numbers past u32::MAX
+        let start = u32::MAX as u64 + 100;
+        let bytes = vec![(1_000u128, 10u128, start)].encode();
+        let schedules = decode::<Vec<VestingInfo<u64>>>(&bytes, "Vesting").unwrap();
+        let milestones = vesting_milestones(&schedules, start + 50);
+        assert_eq!(milestones[0].block, Some(start + 100));
+        assert_eq!(milestones[0].amount, 500);
+    }
+
+    #[test]
+    fn test_locked_at_and_timeline() {
+        let schedule = UnlockSchedule {
+            account: "alice".to_string(),
+            current_block: 100,
+            milestones: vec![
+                UnlockMilestone {
+                    block: Some(300),
+                    amount: 700,
+                    source: LockSource::Staking,
+                    estimated: true,
+                },
+                UnlockMilestone {
+                    block: Some(200),
+                    amount: 500,
+                    source: LockSource::ConvictionVoting { class: 1 },
+                    estimated: false,
+                },
+                UnlockMilestone {
+                    block: Some(150),
+                    amount: 900,
+                    source: LockSource::Democracy,
+                    estimated: false,
+                },
+                UnlockMilestone {
+                    block: Some(200),
+                    amount: 1_000,
+                    source: LockSource::Vesting { per_block: 10 },
+                    estimated: false,
+                },
+            ],
+        };
+
+        let blocks: Vec<Option<u64>> = schedule.timeline().iter().map(|m| m.block).collect();
+        assert_eq!(blocks, vec![Some(150), Some(200), Some(200), Some(300)]);
+
+        assert_eq!(schedule.locked_at(100), 1_000);
+        assert_eq!(schedule.locked_at(160), 700);
+        assert_eq!(schedule.locked_at(250), 700);
+        assert_eq!(schedule.locked_at(300), 0);
+        assert_eq!(schedule.fully_unlocked_at(), Some(300));
+
+        let mut pending = schedule.clone();
+        pending.milestones[0].block = None;
+        assert_eq!(pending.locked_at(10_000), 700);
+        assert_eq!(pending.fully_unlocked_at(), None);
+    }
+}
@@ -0,0 +1,250 @@
+//! Multipart QR frames of the Universal Offline Signatures (UOS) format
+//!
+//! Payloads too large for one QR code are split into frames shown one after
+//! another, as Polkadot Vault expects them. Every frame starts with a header:
+//!
+//! | Bytes | Content                         |
+//! |-------|---------------------------------|
+//! | 1     | `0x00`, marking a multipart frame |
+//! | 2     | frame count, big endian          |
+//! | 2     | frame index, big endian          |
+//!
+//! followed by its chunk of the payload. Single-frame payloads carry the same
+//! header. Frames may be scanned in any order and repeatedly; [`FrameDecoder`]
+//! collects them until the payload is complete. Signatures travel back from
+//! the signer as a single QR code holding the hex of the `MultiSignature`.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::uos::{encode_frames, FrameDecoder, DEFAULT_FRAME_SIZE};
+//!
+//! # fn example(payload: &[u8]) -> Result<(), apex_sdk_substrate::Error> {
+//! let frames = encode_frames(payload, DEFAULT_FRAME_SIZE)?;
+//!
+//! let mut decoder = FrameDecoder::new();
+//! for frame in frames.iter().rev() {
+//!     if let Some(payload) = decoder.push(frame)? {
+//!         println!("reassembled {} bytes", payload.len());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, Result};
+use std::collections::BTreeMap;
+use subxt::utils::MultiSignature;
+
+/// First byte of a multipart frame
+pub const MULTIPART_FRAME: u8 = 0x00;
+
+/// Bytes of a frame header
+pub const FRAME_HEADER_LEN: usize = 5;
+
+/// Payload bytes per frame, small enough for phone cameras to read quickly
+pub const DEFAULT_FRAME_SIZE: usize = 512;
+
+/// One frame of a multipart payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Number of frames in the payload
+    pub count: u16,
+    /// Position of this frame, from 0
+    pub index: u16,
+    /// Chunk of the payload
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    /// Frame from the bytes of a scanned QR code
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < FRAME_HEADER_LEN {
+            return Err(Error::Encoding(format!(
+                "UOS frame of {} bytes is shorter than its header",
+                bytes.len()
+            )));
+        }
+        if bytes[0] != MULTIPART_FRAME {
+            return Err(Error::Encoding(format!(
+                "Unsupported UOS frame type 0x{:02x}",
+                bytes[0]
+            )));
+        }
+        let count = u16::from_be_bytes([bytes[1], bytes[2]]);
+        let index = u16::from_be_bytes([bytes[3], bytes[4]]);
+        if index >= count {
+            return Err(Error::Encoding(format!(
+                "UOS frame {} out of range for {} frames",
+                index, count
+            )));
+        }
+        Ok(Self {
+            count,
+            index,
+            data: bytes[FRAME_HEADER_LEN..].to_vec(),
+        })
+    }
+
+    /// Bytes to encode in a QR code
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(FRAME_HEADER_LEN + self.data.len());
+        bytes.push(MULTIPART_FRAME);
+        bytes.extend_from_slice(&self.count.to_be_bytes());
+        bytes.extend_from_slice(&self.index.to_be_bytes());
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+}
+
+/// Split a payload into frames of at most `frame_size` payload bytes each
+pub fn encode_frames(payload: &[u8], frame_size: usize) -> Result<Vec<Vec<u8>>> {
+    if frame_size == 0 {
+        return Err(Error::Encoding(
+            "UOS frame size must be positive".to_string(),
+        ));
+    }
+    let chunks: Vec<&[u8]> = if payload.is_empty() {
+        vec![payload]
+    } else {
+        payload.chunks(frame_size).collect()
+    };
+    let count = u16::try_from(chunks.len()).map_err(|_| {
+        Error::Encoding(format!(
+            "Payload of {} bytes needs more than {} frames",
+            payload.len(),
+            u16::MAX
+        ))
+    })?;
+    Ok(chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            Frame {
+                count,
+                index: index as u16,
+                data: chunk.to_vec(),
+            }
+            .to_bytes()
+        })
+        .collect())
+}
+
+/// Reassembles a payload from frames scanned in any order
+#[derive(Debug, Clone, Default)]
+pub struct FrameDecoder {
+    count: Option<u16>,
+    frames: BTreeMap<u16, Vec<u8>>,
+}
+
+impl FrameDecoder {
+    /// Decoder waiting for its first frame
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a scanned frame; returns the payload once every frame is in
+    ///
+    /// A frame announcing a different frame count belongs to another payload,
+    /// so collection starts over from it.
+    pub fn push(&mut self, bytes: &[u8]) -> Result<Option<Vec<u8>>> {
+        let frame = Frame::parse(bytes)?;
+        if self.count != Some(frame.count) {
+            self.frames.clear();
+            self.count = Some(frame.count);
+        }
+        self.frames.insert(frame.index, frame.data);
+        if !self.is_complete() {
+            return Ok(None);
+        }
+        Ok(Some(self.frames.values().flatten().copied().collect()))
+    }
+
+    /// Frames received so far
+    pub fn received(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Frames in the payload, once known
+    pub fn count(&self) -> Option<u16> {
+        self.count
+    }
+
+    /// Whether every frame has been received
+    pub fn is_complete(&self) -> bool {
+        self.count
+            .is_some_and(|count| self.frames.len() == count as usize)
+    }
+
+    /// Forget collected frames
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Hex of a signature as a signer shows it, prefixed with its `MultiSignature` variant
+pub fn encode_signature(signature: &MultiSignature) -> String {
+    let mut bytes = Vec::with_capacity(66);
+    match signature {
+        MultiSignature::Ed25519(raw) => {
+            bytes.push(0x00);
+            bytes.extend_from_slice(raw);
+        }
+        MultiSignature::Sr25519(raw) => {
+            bytes.push(0x01);
+            bytes.extend_from_slice(raw);
+        }
+        MultiSignature::Ecdsa(raw) => {
+            bytes.push(0x02);
+            bytes.extend_from_slice(raw);
+        }
+    }
+    hex::encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_roundtrip() {
+        let payload: Vec<u8> = (0..=255u8).cycle().take(1_300).collect();
+        let frames = encode_frames(&payload, 500).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(
+            &frames[1][..FRAME_HEADER_LEN],
+            &[0x00, 0x00, 0x03, 0x00, 0x01]
+        );
+        assert_eq!(frames[2].len(), FRAME_HEADER_LEN + 300);
+
+        let mut decoder = FrameDecoder::new();
+        assert_eq!(decoder.push(&frames[2]).unwrap(), None);
+        assert_eq!(decoder.push(&frames[2]).unwrap(), None);
+        assert_eq!(decoder.push(&frames[0]).unwrap(), None);
+        assert_eq!(decoder.received(), 2);
+        assert_eq!(decoder.push(&frames[1]).unwrap(), Some(payload));
+
+        let single = encode_frames(b"ab", DEFAULT_FRAME_SIZE).unwrap();
+        assert_eq!(single, vec![vec![0x00, 0x00, 0x01, 0x00, 0x00, b'a', b'b']]);
+    }
+
+    #[test]
+    fn test_invalid_frames() {
+        assert!(Frame::parse(&[0x00, 0x00, 0x01]).is_err());
+        assert!(Frame::parse(&[0x80, 0x00, 0x01, 0x00, 0x00]).is_err());
+        assert!(Frame::parse(&[0x00, 0x00, 0x02, 0x00, 0x02]).is_err());
+        assert!(encode_frames(b"ab", 0).is_err());
+
+        // a frame of another payload restarts collection
+        let mut decoder = FrameDecoder::new();
+        decoder.push(&encode_frames(b"abc", 1).unwrap()[0]).unwrap();
+        let other = encode_frames(b"xy", 1).unwrap();
+        decoder.push(&other[1]).unwrap();
+        assert_eq!((decoder.received(), decoder.count()), (1, Some(2)));
+        assert_eq!(decoder.push(&other[0]).unwrap(), Some(b"xy".to_vec()));
+
+        let signature = MultiSignature::Sr25519([0xaa; 64]);
+        assert_eq!(
+            encode_signature(&signature),
+            format!("01{}", "aa".repeat(64))
+        );
+    }
+}
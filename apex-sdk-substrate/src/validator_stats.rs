@@ -0,0 +1,435 @@
+//! Validator performance analytics
+//!
+//! [`ValidatorAnalytics`] collects per-era staking data for validators so
+//! staking tools can compare and rank them:
+//! - Era reward points (`Staking::ErasRewardPoints`)
+//! - Commission and blocked status (`Staking::ErasValidatorPrefs`)
+//! - Slashes (`Staking::ValidatorSlashInEra`)
+//! - Blocks authored in the current session (`ImOnline::AuthoredBlocks`)
+//! - Missed heartbeats (`ImOnline::SomeOffline` events in a scanned block range)
+//!
+//! Era data is only kept on chain for `Staking::HistoryDepth` eras; older eras
+//! come back empty. ImOnline based metrics are `None` on runtimes without the
+//! pallet.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{SubstrateAdapter, ValidatorAnalytics};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let analytics = ValidatorAnalytics::new(adapter);
+//! let ranked = analytics
+//!     .rank(&["14ShUZUYUR35RBZW6uVVt1zXDxmSQddkeDdXf1JkMA6P721N"], 1400..=1410)
+//!     .await?;
+//!
+//! for stats in ranked {
+//!     println!("{}: {:.2}% of era points", stats.validator, stats.reward_share() * 100.0);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::{account_id, EventQuery};
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::Decode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use subxt::dynamic::Value;
+use tracing::debug;
+
+/// Perbill denominator (one billion parts)
+const PERBILL: u32 = 1_000_000_000;
+
+/// A slash applied to a validator in one era
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlashRecord {
+    /// Slashed fraction of the validator's own stake, in parts per billion
+    pub fraction_perbill: u32,
+    /// Slashed amount in the chain's smallest unit
+    pub amount: u128,
+}
+
+/// Validator performance in a single era
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EraPerformance {
+    /// Era index
+    pub era: u32,
+    /// Reward points earned by the validator
+    pub reward_points: u32,
+    /// Reward points earned by all validators
+    pub total_reward_points: u32,
+    /// Commission in parts per billion, if the validator was elected
+    pub commission_perbill: Option<u32>,
+    /// Whether the validator blocked new nominations
+    pub blocked: bool,
+    /// Slash applied in this era, if any
+    pub slash: Option<SlashRecord>,
+}
+
+impl EraPerformance {
+    /// Whether the validator was in the active set this era
+    pub fn was_active(&self) -> bool {
+        self.commission_perbill.is_some() || self.reward_points > 0
+    }
+}
+
+/// Aggregated validator performance over an era range
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorStats {
+    /// Validator account as given to the query
+    pub validator: String,
+    /// Per-era data, in era order
+    pub eras: Vec<EraPerformance>,
+    /// Blocks authored in the current session
+    pub blocks_authored: Option<u32>,
+    /// `ImOnline::SomeOffline` reports naming the validator in the scanned blocks
+    pub missed_heartbeats: Option<u32>,
+}
+
+impl ValidatorStats {
+    /// Reward points summed over all eras
+    pub fn total_reward_points(&self) -> u64 {
+        self.eras.iter().map(|era| era.reward_points as u64).sum()
+    }
+
+    /// Average reward points per era in which the validator was active
+    pub fn average_reward_points(&self) -> f64 {
+        let active = self.active_eras();
+        if active == 0 {
+            0.0
+        } else {
+            self.total_reward_points() as f64 / active as f64
+        }
+    }
+
+    /// Share of all era points earned by the validator over the active eras
+    pub fn reward_share(&self) -> f64 {
+        let total: u64 = self
+            .eras
+            .iter()
+            .filter(|era| era.was_active())
+            .map(|era| era.total_reward_points as u64)
+            .sum();
+        if total == 0 {
+            0.0
+        } else {
+            self.total_reward_points() as f64 / total as f64
+        }
+    }
+
+    /// Number of eras in which the validator was active
+    pub fn active_eras(&self) -> usize {
+        self.eras.iter().filter(|era| era.was_active()).count()
+    }
+
+    /// Number of eras with a slash
+    pub fn slash_count(&self) -> usize {
+        self.eras.iter().filter(|era| era.slash.is_some()).count()
+    }
+
+    /// Total slashed amount
+    pub fn total_slashed(&self) -> u128 {
+        self.eras
+            .iter()
+            .filter_map(|era| era.slash)
+            .map(|slash| slash.amount)
+            .fold(0u128, u128::saturating_add)
+    }
+
+    /// Commission changes as `(era, perbill)`, starting with the first known value
+    pub fn commission_history(&self) -> Vec<(u32, u32)> {
+        let mut history: Vec<(u32, u32)> = Vec::new();
+        for era in &self.eras {
+            if let Some(commission) = era.commission_perbill {
+                if history.last().map(|(_, last)| *last) != Some(commission) {
+                    history.push((era.era, commission));
+                }
+            }
+        }
+        history
+    }
+
+    /// Most recent known commission as a percentage
+    pub fn latest_commission_percent(&self) -> Option<f64> {
+        self.eras
+            .iter()
+            .rev()
+            .find_map(|era| era.commission_perbill)
+            .map(perbill_to_percent)
+    }
+}
+
+/// Convert parts per billion to a percentage
+pub fn perbill_to_percent(perbill: u32) -> f64 {
+    perbill as f64 * 100.0 / PERBILL as f64
+}
+
+/// `Staking::ErasRewardPoints` value
+#[derive(Decode)]
+struct EraRewardPoints {
+    total: u32,
+    individual: BTreeMap<[u8; 32], u32>,
+}
+
+/// `Staking::ErasValidatorPrefs` value
+#[derive(Decode)]
+struct ValidatorPrefs {
+    #[codec(compact)]
+    commission: u32,
+    blocked: bool,
+}
+
+/// Staking analytics over a connected Substrate adapter
+pub struct ValidatorAnalytics<'a> {
+    adapter: &'a SubstrateAdapter,
+    heartbeat_blocks: Option<(u64, u64)>,
+}
+
+impl<'a> ValidatorAnalytics<'a> {
+    /// Create an analytics client
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self {
+            adapter,
+            heartbeat_blocks: None,
+        }
+    }
+
+    /// Count missed heartbeats by scanning blocks `from..=to` for offline reports
+    pub fn with_heartbeat_scan(mut self, from: u64, to: u64) -> Self {
+        self.heartbeat_blocks = Some((from, to));
+        self
+    }
+
+    /// Collect performance data for one validator
+    pub async fn stats(
+        &self,
+        validator: &str,
+        eras: RangeInclusive<u32>,
+    ) -> Result<ValidatorStats> {
+        let account = account_id(validator)?;
+        let storage = self.adapter.storage();
+
+        let mut performance = Vec::new();
+        for era in eras {
+            debug!("Collecting era {} data for {}", era, validator);
+
+            let points = storage
+                .query_storage(
+                    "Staking",
+                    "ErasRewardPoints",
+                    vec![Value::u128(era as u128)],
+                )
+                .await?
+                .map(|bytes| decode::<EraRewardPoints>(&bytes, "ErasRewardPoints"))
+                .transpose()?;
+            let prefs = storage
+                .query_storage(
+                    "Staking",
+                    "ErasValidatorPrefs",
+                    vec![Value::u128(era as u128), Value::from_bytes(account)],
+                )
+                .await?
+                .map(|bytes| decode::<ValidatorPrefs>(&bytes, "ErasValidatorPrefs"))
+                .transpose()?;
+            let slash = storage
+                .query_storage(
+                    "Staking",
+                    "ValidatorSlashInEra",
+                    vec![Value::u128(era as u128), Value::from_bytes(account)],
+                )
+                .await?
+                .map(|bytes| decode::<(u32, u128)>(&bytes, "ValidatorSlashInEra"))
+                .transpose()?;
+
+            performance.push(era_performance(era, &account, points, prefs, slash));
+        }
+
+        Ok(ValidatorStats {
+            validator: validator.to_string(),
+            eras: performance,
+            blocks_authored: self.blocks_authored(&account).await,
+            missed_heartbeats: self.missed_heartbeats(validator).await?,
+        })
+    }
+
+    /// Collect and rank several validators
+    ///
+    /// Validators without slashes come first, then by share of era points.
+    pub async fn rank(
+        &self,
+        validators: &[&str],
+        eras: RangeInclusive<u32>,
+    ) -> Result<Vec<ValidatorStats>> {
+        let mut ranked = Vec::with_capacity(validators.len());
+        for validator in validators {
+            ranked.push(self.stats(validator, eras.clone()).await?);
+        }
+        rank_validators(&mut ranked);
+        Ok(ranked)
+    }
+
+    async fn blocks_authored(&self, account: &[u8; 32]) -> Option<u32> {
+        let storage = self.adapter.storage();
+        let session = storage
+            .query_storage("Session", "CurrentIndex", vec![])
+            .await
+            .ok()
+            .flatten()
+            .and_then(|bytes| u32::decode(&mut &bytes[..]).ok())?;
+
+        // A missing entry means no blocks authored yet; a missing pallet gives an error
+        match storage
+            .query_storage(
+                "ImOnline",
+                "AuthoredBlocks",
+                vec![Value::u128(session as u128), Value::from_bytes(account)],
+            )
+            .await
+        {
+            Ok(Some(bytes)) => u32::decode(&mut &bytes[..]).ok(),
+            Ok(None) => Some(0),
+            Err(_) => None,
+        }
+    }
+
+    async fn missed_heartbeats(&self, validator: &str) -> Result<Option<u32>> {
+        let Some((from, to)) = self.heartbeat_blocks else {
+            return Ok(None);
+        };
+
+        let reports = EventQuery::new()
+            .pallet("ImOnline")
+            .variant("SomeOffline")
+            .involving(validator)
+            .between(from, to)
+            .run(self.adapter)
+            .await?;
+        Ok(Some(reports.len() as u32))
+    }
+}
+
+fn era_performance(
+    era: u32,
+    account: &[u8; 32],
+    points: Option<EraRewardPoints>,
+    prefs: Option<ValidatorPrefs>,
+    slash: Option<(u32, u128)>,
+) -> EraPerformance {
+    EraPerformance {
+        era,
+        reward_points: points
+            .as_ref()
+            .and_then(|points| points.individual.get(account).copied())
+            .unwrap_or(0),
+        total_reward_points: points.as_ref().map(|points| points.total).unwrap_or(0),
+        commission_perbill: prefs.as_ref().map(|prefs| prefs.commission),
+        blocked: prefs.is_some_and(|prefs| prefs.blocked),
+        slash: slash.map(|(fraction_perbill, amount)| SlashRecord {
+            fraction_perbill,
+            amount,
+        }),
+    }
+}
+
+fn rank_validators(stats: &mut [ValidatorStats]) {
+    stats.sort_by(|a, b| {
+        a.slash_count()
+            .cmp(&b.slash_count())
+            .then_with(|| b.reward_share().total_cmp(&a.reward_share()))
+    });
+}
+
+fn decode<T: Decode>(bytes: &[u8], item: &str) -> Result<T> {
+    T::decode(&mut &bytes[..])
+        .map_err(|e| Error::Encoding(format!("Failed to decode Staking::{}: {}", item, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_scale_codec::{Compact, Encode};
+
+    fn era(era: u32, points: u32, commission: Option<u32>, slash: Option<u128>) -> EraPerformance {
+        EraPerformance {
+            era,
+            reward_points: points,
+            total_reward_points: 1000,
+            commission_perbill: commission,
+            blocked: false,
+            slash: slash.map(|amount| SlashRecord {
+                fraction_perbill: 1_000_000,
+                amount,
+            }),
+        }
+    }
+
+    fn stats(name: &str, eras: Vec<EraPerformance>) -> ValidatorStats {
+        ValidatorStats {
+            validator: name.to_string(),
+            eras,
+            blocks_authored: None,
+            missed_heartbeats: None,
+        }
+    }
+
+    #[test]
+    fn test_decode_staking_storage() {
+        let validator = [7u8; 32];
+        let mut individual = BTreeMap::new();
+        individual.insert(validator, 80u32);
+        individual.insert([1u8; 32], 20u32);
+        let points_bytes = (100u32, individual).encode();
+        let prefs_bytes = (Compact(50_000_000u32), true).encode();
+
+        let points = decode::<EraRewardPoints>(&points_bytes, "ErasRewardPoints").unwrap();
+        let prefs = decode::<ValidatorPrefs>(&prefs_bytes, "ErasValidatorPrefs").unwrap();
+        let performance =
+            era_performance(12, &validator, Some(points), Some(prefs), Some((500, 10)));
+
+        assert_eq!(performance.reward_points, 80);
+        assert_eq!(performance.total_reward_points, 100);
+        assert_eq!(performance.commission_perbill, Some(50_000_000));
+        assert!(performance.blocked);
+        assert_eq!(performance.slash.unwrap().amount, 10);
+
+        assert!(decode::<ValidatorPrefs>(&[], "ErasValidatorPrefs").is_err());
+    }
+
+    #[test]
+    fn test_aggregates() {
+        let stats = stats(
+            "alice",
+            vec![
+                era(1, 100, Some(10_000_000), None),
+                era(2, 0, None, None),
+                era(3, 300, Some(10_000_000), Some(5)),
+                era(4, 200, Some(20_000_000), Some(7)),
+            ],
+        );
+
+        assert_eq!(stats.total_reward_points(), 600);
+        assert_eq!(stats.active_eras(), 3);
+        assert_eq!(stats.average_reward_points(), 200.0);
+        assert_eq!(stats.reward_share(), 0.2);
+        assert_eq!(stats.slash_count(), 2);
+        assert_eq!(stats.total_slashed(), 12);
+        assert_eq!(
+            stats.commission_history(),
+            vec![(1, 10_000_000), (4, 20_000_000)]
+        );
+        assert_eq!(stats.latest_commission_percent(), Some(2.0));
+    }
+
+    #[test]
+    fn test_ranking_prefers_unslashed_then_points() {
+        let mut ranked = vec![
+            stats("slashed", vec![era(1, 900, Some(0), Some(1))]),
+            stats("low", vec![era(1, 100, Some(0), None)]),
+            stats("high", vec![era(1, 500, Some(0), None)]),
+        ];
+        rank_validators(&mut ranked);
+
+        let order: Vec<&str> = ranked.iter().map(|s| s.validator.as_str()).collect();
+        assert_eq!(order, vec!["high", "low", "slashed"]);
+    }
+}
@@ -0,0 +1,475 @@
+//! Recorded-response RPC transport for offline tests
+//!
+//! A [`Vcr`] sits between the adapter and the node. In record mode it forwards
+//! requests and subscriptions to a live node and keeps every response; saving
+//! writes them to a cassette file. In replay mode the cassette answers in the
+//! node's place, so tests of [`BlockQuery`](crate::BlockQuery) and other
+//! modules run without a network and return the same results on every run.
+//!
+//! The mode comes from `APEX_VCR`: `record` records, anything else replays.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{ChainConfig, Vcr};
+//!
+//! # async fn example() -> Result<(), apex_sdk_substrate::Error> {
+//! let vcr = Vcr::new("tests/cassettes/westend_blocks.json");
+//! let adapter = vcr.connect(ChainConfig::westend()).await?;
+//!
+//! let block = adapter.block_query().get_block_by_number(24_000_000).await?;
+//! println!("{}", block.hash);
+//!
+//! // writes the cassette when recording, does nothing when replaying
+//! vcr.save()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{transport, ChainConfig, Error, Result, SubstrateAdapter};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_json::Value as JsonValue;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use subxt::backend::rpc::{RawRpcFuture, RawRpcSubscription, RpcClient, RpcClientT};
+use subxt::ext::futures::{stream, StreamExt};
+use subxt::ext::subxt_rpcs::Error as RpcError;
+
+/// Environment variable selecting the [`VcrMode`]
+pub const VCR_ENV: &str = "APEX_VCR";
+
+/// Whether a [`Vcr`] talks to a node or to its cassette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VcrMode {
+    /// Forward to a live node and record the responses
+    Record,
+    /// Answer from the cassette without a network
+    Replay,
+}
+
+impl VcrMode {
+    /// Mode selected by `APEX_VCR`; replays unless it is `record`
+    pub fn from_env() -> Self {
+        match std::env::var(VCR_ENV) {
+            Ok(mode) if mode.eq_ignore_ascii_case("record") => Self::Record,
+            _ => Self::Replay,
+        }
+    }
+}
+
+/// One recorded exchange with the node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Interaction {
+    /// A request and its result, or the error it failed with
+    Request {
+        method: String,
+        #[serde(default)]
+        params: Option<JsonValue>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        result: Option<JsonValue>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// A subscription and the notifications received while it was open
+    Subscription {
+        method: String,
+        #[serde(default)]
+        params: Option<JsonValue>,
+        #[serde(default)]
+        notifications: Vec<JsonValue>,
+    },
+}
+
+/// Recorded interactions, in the order they happened
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    /// Every recorded interaction
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Empty cassette
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a cassette file
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| Error::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+        serde_json::from_str(&json)
+            .map_err(|e| Error::Encoding(format!("Invalid cassette {}: {}", path.display(), e)))
+    }
+
+    /// Write the cassette as pretty-printed JSON, creating parent directories
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| Error::Other(format!("Failed to create {}: {}", dir.display(), e)))?;
+        }
+        let json =
+            serde_json::to_string_pretty(self).map_err(|e| Error::Encoding(e.to_string()))?;
+        std::fs::write(path, json)
+            .map_err(|e| Error::Other(format!("Failed to write {}: {}", path.display(), e)))
+    }
+
+    /// Number of recorded interactions
+    pub fn len(&self) -> usize {
+        self.interactions.len()
+    }
+
+    /// Whether nothing was recorded
+    pub fn is_empty(&self) -> bool {
+        self.interactions.is_empty()
+    }
+}
+
+/// RPC client middleware recording every exchange into a shared [`Cassette`]
+pub struct RecordingRpcClient<T> {
+    inner: T,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl<T> RecordingRpcClient<T> {
+    /// Wrap `inner`, appending to `cassette`; keep a clone of it to save later
+    pub fn new(inner: T, cassette: Arc<Mutex<Cassette>>) -> Self {
+        Self { inner, cassette }
+    }
+}
+
+impl<T: RpcClientT> RpcClientT for RecordingRpcClient<T> {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RawRpcFuture<'a, Box<RawValue>> {
+        Box::pin(async move {
+            let recorded_params = parse_params(params.as_deref())?;
+            let response = self.inner.request_raw(method, params).await;
+            let (result, error) = match &response {
+                Ok(raw) => (
+                    Some(serde_json::from_str(raw.get()).map_err(RpcError::Deserialization)?),
+                    None,
+                ),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            self.cassette
+                .lock()
+                .interactions
+                .push(Interaction::Request {
+                    method: method.to_string(),
+                    params: recorded_params,
+                    result,
+                    error,
+                });
+            response
+        })
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+    ) -> RawRpcFuture<'a, RawRpcSubscription> {
+        Box::pin(async move {
+            let recorded_params = parse_params(params.as_deref())?;
+            let subscription = self.inner.subscribe_raw(sub, params, unsub).await?;
+            let index = {
+                let mut cassette = self.cassette.lock();
+                cassette.interactions.push(Interaction::Subscription {
+                    method: sub.to_string(),
+                    params: recorded_params,
+                    notifications: Vec::new(),
+                });
+                cassette.interactions.len() - 1
+            };
+
+            let cassette = self.cassette.clone();
+            let stream = subscription.stream.inspect(move |item| {
+                let Some(value) = item
+                    .as_ref()
+                    .ok()
+                    .and_then(|raw| serde_json::from_str::<JsonValue>(raw.get()).ok())
+                else {
+                    return;
+                };
+                if let Some(Interaction::Subscription { notifications, .. }) =
+                    cassette.lock().interactions.get_mut(index)
+                {
+                    notifications.push(value);
+                }
+            });
+            Ok(RawRpcSubscription {
+                stream: Box::pin(stream),
+                id: subscription.id,
+            })
+        })
+    }
+}
+
+/// RPC client answering from a [`Cassette`] instead of a node
+///
+/// Identical requests get their recorded responses in order; once only the
+/// last one is left it answers every further repeat. Requests missing from
+/// the cassette fail.
+pub struct ReplayRpcClient {
+    responses: Mutex<HashMap<String, VecDeque<std::result::Result<JsonValue, String>>>>,
+    subscriptions: Mutex<HashMap<String, VecDeque<Vec<JsonValue>>>>,
+}
+
+impl ReplayRpcClient {
+    /// Replay the interactions of `cassette`
+    pub fn new(cassette: Cassette) -> Self {
+        let mut responses: HashMap<_, VecDeque<_>> = HashMap::new();
+        let mut subscriptions: HashMap<_, VecDeque<_>> = HashMap::new();
+        for interaction in cassette.interactions {
+            match interaction {
+                Interaction::Request {
+                    method,
+                    params,
+                    result,
+                    error,
+                } => {
+                    let response = match error {
+                        Some(error) => Err(error),
+                        None => Ok(result.unwrap_or(JsonValue::Null)),
+                    };
+                    responses
+                        .entry(request_key(&method, params.as_ref()))
+                        .or_default()
+                        .push_back(response);
+                }
+                Interaction::Subscription {
+                    method,
+                    params,
+                    notifications,
+                } => subscriptions
+                    .entry(request_key(&method, params.as_ref()))
+                    .or_default()
+                    .push_back(notifications),
+            }
+        }
+        Self {
+            responses: Mutex::new(responses),
+            subscriptions: Mutex::new(subscriptions),
+        }
+    }
+}
+
+impl RpcClientT for ReplayRpcClient {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RawRpcFuture<'a, Box<RawValue>> {
+        Box::pin(async move {
+            let key = request_key(method, parse_params(params.as_deref())?.as_ref());
+            match next_recorded(&self.responses, &key) {
+                Some(Ok(result)) => {
+                    RawValue::from_string(result.to_string()).map_err(RpcError::Deserialization)
+                }
+                Some(Err(error)) => Err(RpcError::Client(error.into())),
+                None => Err(RpcError::Client(
+                    format!("No recorded response for {}", key).into(),
+                )),
+            }
+        })
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        _unsub: &'a str,
+    ) -> RawRpcFuture<'a, RawRpcSubscription> {
+        Box::pin(async move {
+            let key = request_key(sub, parse_params(params.as_deref())?.as_ref());
+            let notifications = next_recorded(&self.subscriptions, &key).ok_or_else(|| {
+                RpcError::Client(format!("No recorded subscription for {}", key).into())
+            })?;
+            let items = notifications.into_iter().map(|notification| {
+                RawValue::from_string(notification.to_string()).map_err(RpcError::Deserialization)
+            });
+            Ok(RawRpcSubscription {
+                stream: Box::pin(stream::iter(items)),
+                id: None,
+            })
+        })
+    }
+}
+
+/// Records RPC traffic to a cassette file or replays it from one
+pub struct Vcr {
+    path: PathBuf,
+    mode: VcrMode,
+    recorded: Arc<Mutex<Cassette>>,
+}
+
+impl Vcr {
+    /// Cassette at `path`, in the mode selected by `APEX_VCR`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: VcrMode::from_env(),
+            recorded: Arc::new(Mutex::new(Cassette::new())),
+        }
+    }
+
+    /// Override the mode from the environment
+    pub fn with_mode(mut self, mode: VcrMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Current mode
+    pub fn mode(&self) -> VcrMode {
+        self.mode
+    }
+
+    /// Cassette file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// RPC client recording from the node in `config`, or replaying the cassette
+    pub async fn rpc_client(&self, config: &ChainConfig) -> Result<RpcClient> {
+        match self.mode {
+            VcrMode::Record => {
+                let ws_client =
+                    transport::connect_ws_client(&config.endpoint, &config.client).await?;
+                Ok(RpcClient::new(RecordingRpcClient::new(
+                    ws_client,
+                    self.recorded.clone(),
+                )))
+            }
+            VcrMode::Replay => {
+                let cassette = Cassette::load(&self.path).map_err(|e| {
+                    Error::Connection(format!("{}; record it with {}=record", e, VCR_ENV))
+                })?;
+                Ok(RpcClient::new(ReplayRpcClient::new(cassette)))
+            }
+        }
+    }
+
+    /// Adapter for `config` on top of [`rpc_client`](Self::rpc_client)
+    pub async fn connect(&self, config: ChainConfig) -> Result<SubstrateAdapter> {
+        let rpc_client = self.rpc_client(&config).await?;
+        SubstrateAdapter::connect_with_rpc_client(rpc_client, config).await
+    }
+
+    /// Write what was recorded to the cassette file; does nothing when replaying
+    pub fn save(&self) -> Result<()> {
+        match self.mode {
+            VcrMode::Record => self.recorded.lock().save(&self.path),
+            VcrMode::Replay => Ok(()),
+        }
+    }
+}
+
+/// Parameters as JSON, as stored in a cassette
+fn parse_params(params: Option<&RawValue>) -> std::result::Result<Option<JsonValue>, RpcError> {
+    params
+        .map(|raw| serde_json::from_str(raw.get()))
+        .transpose()
+        .map_err(RpcError::Deserialization)
+}
+
+/// Lookup key of a request; parameters are re-serialized so formatting does not matter
+fn request_key(method: &str, params: Option<&JsonValue>) -> String {
+    match params {
+        Some(params) => format!("{} {}", method, params),
+        None => method.to_string(),
+    }
+}
+
+/// Next recorded value for `key`, repeating the last one
+fn next_recorded<V: Clone>(queues: &Mutex<HashMap<String, VecDeque<V>>>, key: &str) -> Option<V> {
+    let mut queues = queues.lock();
+    let queue = queues.get_mut(key)?;
+    if queue.len() > 1 {
+        queue.pop_front()
+    } else {
+        queue.front().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn raw(value: JsonValue) -> Option<Box<RawValue>> {
+        Some(RawValue::from_string(value.to_string()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_replay_in_recorded_order() {
+        let cassette = Cassette {
+            interactions: vec![
+                Interaction::Request {
+                    method: "chain_getFinalizedHead".to_string(),
+                    params: Some(json!([])),
+                    result: Some(json!("0x01")),
+                    error: None,
+                },
+                Interaction::Request {
+                    method: "chain_getFinalizedHead".to_string(),
+                    params: Some(json!([])),
+                    result: Some(json!("0x02")),
+                    error: None,
+                },
+                Interaction::Request {
+                    method: "chain_getBlockHash".to_string(),
+                    params: Some(json!([7])),
+                    result: None,
+                    error: Some("boom".to_string()),
+                },
+            ],
+        };
+        let client = ReplayRpcClient::new(cassette);
+
+        for expected in ["\"0x01\"", "\"0x02\"", "\"0x02\""] {
+            let head = client
+                .request_raw("chain_getFinalizedHead", raw(json!([])))
+                .await
+                .unwrap();
+            assert_eq!(head.get(), expected);
+        }
+
+        let failed = client
+            .request_raw("chain_getBlockHash", raw(json!([7])))
+            .await;
+        assert!(failed.unwrap_err().to_string().contains("boom"));
+        let missing = client
+            .request_raw("chain_getBlockHash", raw(json!([8])))
+            .await;
+        assert!(missing.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let recorded = Arc::new(Mutex::new(Cassette::new()));
+        let cassette = Cassette {
+            interactions: vec![Interaction::Request {
+                method: "system_chain".to_string(),
+                params: None,
+                result: Some(json!("Westend")),
+                error: None,
+            }],
+        };
+        let recorder = RecordingRpcClient::new(ReplayRpcClient::new(cassette), recorded.clone());
+
+        let chain = recorder.request_raw("system_chain", None).await.unwrap();
+        assert_eq!(chain.get(), "\"Westend\"");
+        assert!(recorder.request_raw("system_name", None).await.is_err());
+
+        let recorded = recorded.lock().clone();
+        assert_eq!(recorded.len(), 2);
+        let replay = ReplayRpcClient::new(recorded);
+        let chain = replay.request_raw("system_chain", None).await.unwrap();
+        assert_eq!(chain.get(), "\"Westend\"");
+    }
+}
@@ -1,9 +1,9 @@
 //! Substrate wallet and account management
 //!
 //! This module provides comprehensive wallet functionality including:
-//! - Key pair generation (SR25519, ED25519)
+//! - Key pair generation (SR25519, ED25519, Ethereum-style ECDSA)
 //! - Mnemonic phrase support (BIP-39)
-//! - SS58 address encoding
+//! - SS58 and checksummed H160 address encoding
 //! - Message and transaction signing
 //! - Multi-wallet management
 //!
@@ -16,13 +16,14 @@
 //! - Use `Arc<Wallet>` for shared access
 //! - Ensure wallets are dropped when no longer needed
 
+use crate::account20::{ethereum_secret, AccountId20, DEFAULT_ETHEREUM_PATH};
 use crate::{Error, Result};
 use apex_sdk_core::{SdkError, Signer as CoreSigner};
 use apex_sdk_types::Address;
 use async_trait::async_trait;
 use parking_lot::RwLock;
 use sp_core::crypto::{Ss58AddressFormat, Ss58Codec};
-use sp_core::{ed25519, sr25519, Pair as PairTrait};
+use sp_core::{ecdsa, ed25519, sr25519, Pair as PairTrait};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{debug, info};
@@ -35,9 +36,11 @@ pub enum KeyPairType {
     Sr25519,
     /// ED25519 - Alternative signing algorithm
     Ed25519,
+    /// ECDSA over keccak-256 hashes with 20-byte accounts, as on Moonbeam
+    Ethereum,
 }
 
-/// A unified wallet that can hold either SR25519 or ED25519 keys
+/// A unified wallet that can hold SR25519, ED25519 or Ethereum-style ECDSA keys
 ///
 /// # Security
 ///
@@ -81,6 +84,8 @@ pub struct Wallet {
     sr25519_pair: Option<sr25519::Pair>,
     /// ED25519 pair (if applicable)
     ed25519_pair: Option<ed25519::Pair>,
+    /// ECDSA pair of an Ethereum wallet (if applicable)
+    ecdsa_pair: Option<ecdsa::Pair>,
     /// SS58 address format (network prefix)
     ss58_format: Ss58AddressFormat,
 }
@@ -102,6 +107,7 @@ impl Wallet {
                     key_type,
                     sr25519_pair: Some(pair),
                     ed25519_pair: None,
+                    ecdsa_pair: None,
                     ss58_format: Ss58AddressFormat::custom(42), // Default to generic
                 }
             }
@@ -111,9 +117,14 @@ impl Wallet {
                     key_type,
                     sr25519_pair: None,
                     ed25519_pair: Some(pair),
+                    ecdsa_pair: None,
                     ss58_format: Ss58AddressFormat::custom(42),
                 }
             }
+            KeyPairType::Ethereum => {
+                let (pair, _seed) = ecdsa::Pair::generate();
+                Self::from_ecdsa_pair(pair)
+            }
         }
     }
 
@@ -123,6 +134,11 @@ impl Wallet {
     }
 
     /// Create wallet from mnemonic phrase with derivation path
+    ///
+    /// For [`KeyPairType::Ethereum`] the path is a BIP-32 path such as
+    /// `m/44'/60'/0'/0/1` and defaults to [`DEFAULT_ETHEREUM_PATH`], matching
+    /// MetaMask and other Ethereum wallets; other key types use Substrate
+    /// junctions.
     pub fn from_mnemonic_with_path(
         mnemonic: &str,
         path: Option<&str>,
@@ -150,6 +166,7 @@ impl Wallet {
                     key_type,
                     sr25519_pair: Some(pair),
                     ed25519_pair: None,
+                    ecdsa_pair: None,
                     ss58_format: Ss58AddressFormat::custom(42),
                 })
             }
@@ -161,9 +178,14 @@ impl Wallet {
                     key_type,
                     sr25519_pair: None,
                     ed25519_pair: Some(pair),
+                    ecdsa_pair: None,
                     ss58_format: Ss58AddressFormat::custom(42),
                 })
             }
+            KeyPairType::Ethereum => {
+                let secret = ethereum_secret(mnemonic, path.unwrap_or(DEFAULT_ETHEREUM_PATH))?;
+                Ok(Self::from_ecdsa_pair(ecdsa::Pair::from_seed(&secret)))
+            }
         }
     }
 
@@ -185,6 +207,7 @@ impl Wallet {
                     key_type,
                     sr25519_pair: Some(pair),
                     ed25519_pair: None,
+                    ecdsa_pair: None,
                     ss58_format: Ss58AddressFormat::custom(42),
                 })
             }
@@ -194,9 +217,23 @@ impl Wallet {
                     key_type,
                     sr25519_pair: None,
                     ed25519_pair: Some(pair),
+                    ecdsa_pair: None,
                     ss58_format: Ss58AddressFormat::custom(42),
                 })
             }
+            // the seed of an ECDSA pair is the raw secp256k1 private key
+            KeyPairType::Ethereum => Ok(Self::from_ecdsa_pair(ecdsa::Pair::from_seed(&seed_array))),
+        }
+    }
+
+    /// Ethereum wallet around an ECDSA pair
+    fn from_ecdsa_pair(pair: ecdsa::Pair) -> Self {
+        Self {
+            key_type: KeyPairType::Ethereum,
+            sr25519_pair: None,
+            ed25519_pair: None,
+            ecdsa_pair: Some(pair),
+            ss58_format: Ss58AddressFormat::custom(42),
         }
     }
 
@@ -213,21 +250,22 @@ impl Wallet {
             .to_string()
     }
 
-    /// Set the SS58 address format (network prefix)
+    /// Set the SS58 address format (network prefix); Ethereum wallets ignore it
     pub fn with_ss58_format(mut self, format: u16) -> Self {
         self.ss58_format = Ss58AddressFormat::custom(format);
         self
     }
 
-    /// Get the public key as bytes
+    /// Get the public key as bytes; compressed (33 bytes) for Ethereum wallets
     pub fn public_key(&self) -> Vec<u8> {
         match self.key_type {
             KeyPairType::Sr25519 => self.sr25519_pair.as_ref().unwrap().public().0.to_vec(),
             KeyPairType::Ed25519 => self.ed25519_pair.as_ref().unwrap().public().0.to_vec(),
+            KeyPairType::Ethereum => self.ecdsa_pair.as_ref().unwrap().public().0.to_vec(),
         }
     }
 
-    /// Get the SS58-encoded address
+    /// Get the SS58-encoded address, or the checksummed hex address of an Ethereum wallet
     pub fn address(&self) -> String {
         match self.key_type {
             KeyPairType::Sr25519 => {
@@ -238,15 +276,25 @@ impl Wallet {
                 let public = self.ed25519_pair.as_ref().unwrap().public();
                 public.to_ss58check_with_version(self.ss58_format)
             }
+            KeyPairType::Ethereum => self.account_id20().unwrap().to_string(),
         }
     }
 
+    /// 20-byte account of an Ethereum wallet
+    pub fn account_id20(&self) -> Option<AccountId20> {
+        let public = self.ecdsa_pair.as_ref()?.public();
+        AccountId20::from_ecdsa_public(&public).ok()
+    }
+
     /// Get the key pair type
     pub fn key_type(&self) -> KeyPairType {
         self.key_type
     }
 
     /// Sign a message
+    ///
+    /// Ethereum wallets sign the keccak-256 hash of the message and return a
+    /// 65-byte recoverable signature.
     pub fn sign(&self, message: &[u8]) -> Vec<u8> {
         match self.key_type {
             KeyPairType::Sr25519 => {
@@ -257,6 +305,12 @@ impl Wallet {
                 let pair = self.ed25519_pair.as_ref().unwrap();
                 pair.sign(message).0.to_vec()
             }
+            KeyPairType::Ethereum => {
+                let pair = self.ecdsa_pair.as_ref().unwrap();
+                pair.sign_prehashed(&sp_core::keccak_256(message))
+                    .0
+                    .to_vec()
+            }
         }
     }
 
@@ -283,6 +337,14 @@ impl Wallet {
                 let public = self.ed25519_pair.as_ref().unwrap().public();
                 ed25519::Pair::verify(&sig, message, &public)
             }
+            KeyPairType::Ethereum => {
+                let Ok(sig_array) = <[u8; 65]>::try_from(signature) else {
+                    return false;
+                };
+                let sig = ecdsa::Signature::from_raw(sig_array);
+                let public = self.ecdsa_pair.as_ref().unwrap().public();
+                ecdsa::Pair::verify_prehashed(&sig, &sp_core::keccak_256(message), &public)
+            }
         }
     }
 
@@ -294,8 +356,8 @@ impl Wallet {
                 // SR25519 doesn't expose seed directly in a simple way
                 None
             }
-            KeyPairType::Ed25519 => {
-                // ED25519 also doesn't expose seed directly
+            KeyPairType::Ed25519 | KeyPairType::Ethereum => {
+                // ED25519 and ECDSA also don't expose seed directly
                 None
             }
         }
@@ -310,6 +372,11 @@ impl Wallet {
     pub fn ed25519_pair(&self) -> Option<&ed25519::Pair> {
         self.ed25519_pair.as_ref()
     }
+
+    /// Get the ECDSA pair for signing (if this is an Ethereum wallet)
+    pub fn ecdsa_pair(&self) -> Option<&ecdsa::Pair> {
+        self.ecdsa_pair.as_ref()
+    }
 }
 
 impl std::fmt::Debug for Wallet {
@@ -341,7 +408,10 @@ impl CoreSigner for Wallet {
     }
 
     fn address(&self) -> Address {
-        Address::Substrate(self.address())
+        match self.key_type {
+            KeyPairType::Ethereum => Address::Evm(self.address()),
+            _ => Address::Substrate(self.address()),
+        }
     }
 }
 
@@ -529,4 +599,30 @@ mod tests {
         // resulting in distinct key pairs and thus different addresses.
         assert_ne!(sr25519_wallet.address(), ed25519_wallet.address());
     }
+
+    #[test]
+    fn test_ethereum_wallet() {
+        // Moonbeam development account Alith
+        let seed = hex::decode("5fb92d6e98884f76de468fa3f6278f8807c48bebc13595d45af5bdc4da702133")
+            .unwrap();
+        let wallet = Wallet::from_seed(&seed, KeyPairType::Ethereum).unwrap();
+        assert_eq!(
+            wallet.address(),
+            "0xf24FF3a9CF04c71Dbc94D0b566f7A27B94566cac"
+        );
+        assert_eq!(wallet.public_key().len(), 33);
+        assert_eq!(CoreSigner::address(&wallet), Address::Evm(wallet.address()));
+
+        let signature = wallet.sign(b"Hello, Moonbeam!");
+        assert_eq!(signature.len(), 65);
+        assert!(wallet.verify(b"Hello, Moonbeam!", &signature));
+        assert!(!wallet.verify(b"Different message", &signature));
+
+        let mnemonic = "test test test test test test test test test test test junk";
+        let wallet = Wallet::from_mnemonic(mnemonic, KeyPairType::Ethereum).unwrap();
+        assert_eq!(
+            wallet.address(),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+    }
 }
@@ -0,0 +1,667 @@
+//! WalletConnect v2 sessions for the `polkadot` namespace
+//!
+//! Lets a dApp request signatures from a mobile wallet instead of holding
+//! keys. The dApp proposes a session through a pairing URI, usually shown as a
+//! QR code; once the wallet approves, signing requests travel through the
+//! WalletConnect relay, encrypted with a key only the two peers know.
+//!
+//! The relay connection itself is left to a [`RelayTransport`], since relays
+//! require a project id and authentication specific to the deployment. This
+//! module implements everything above it: pairing, key agreement, message
+//! envelopes and the session JSON-RPC methods.
+//!
+//! Requires the `walletconnect` feature.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::walletconnect::{
+//!     chain_id, AppMetadata, RelayTransport, TransactionPayload, WalletConnect,
+//! };
+//!
+//! # async fn example(
+//! #     relay: impl RelayTransport,
+//! #     genesis_hash: [u8; 32],
+//! #     payload: TransactionPayload,
+//! # ) -> Result<(), apex_sdk_substrate::Error> {
+//! let polkadot = chain_id(&genesis_hash);
+//! let wc = WalletConnect::new(relay, AppMetadata::new("My dApp", "https://example.com"));
+//!
+//! let proposal = wc.propose(&[polkadot]).await?;
+//! println!("scan: {}", proposal.uri());
+//!
+//! let session = wc.await_session(proposal).await?;
+//! println!("connected accounts: {:?}", session.addresses());
+//!
+//! let signature = wc.sign_transaction(&session, &payload).await?;
+//! println!("signature: {}", signature);
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::watch_only::TransactionPayload;
+use crate::{Error, Result};
+use async_trait::async_trait;
+use base64ct::{Base64, Encoding};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+/// CAIP-2 namespace of Substrate chains
+pub const POLKADOT_NAMESPACE: &str = "polkadot";
+
+/// Method signing a transaction payload
+pub const SIGN_TRANSACTION: &str = "polkadot_signTransaction";
+
+/// Method signing an arbitrary message
+pub const SIGN_MESSAGE: &str = "polkadot_signMessage";
+
+/// Relay protocol of WalletConnect v2
+const RELAY_PROTOCOL: &str = "irn";
+
+/// Envelope type of a message encrypted with a shared key
+const ENVELOPE_TYPE_0: u8 = 0;
+
+/// Bytes of a ChaCha20-Poly1305 nonce
+const IV_LEN: usize = 12;
+
+/// How long pairings and relayed messages stay valid, in seconds
+const PAIRING_TTL: u64 = 300;
+
+/// Relay tags identifying each message kind
+const TAG_SESSION_PROPOSE: u32 = 1100;
+const TAG_SESSION_SETTLE_RESPONSE: u32 = 1103;
+const TAG_SESSION_REQUEST: u32 = 1108;
+
+/// Default wait for the wallet, which may involve the user picking up a phone
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// CAIP-2 id of a Substrate chain: the namespace and the first 16 bytes of its genesis hash
+pub fn chain_id(genesis_hash: &[u8; 32]) -> String {
+    format!(
+        "{}:{}",
+        POLKADOT_NAMESPACE,
+        hex::encode(&genesis_hash[..16])
+    )
+}
+
+/// Relay topic of a symmetric key: the hex of its SHA-256
+pub fn topic_of(sym_key: &[u8; 32]) -> String {
+    hex::encode(Sha256::digest(sym_key))
+}
+
+/// Symmetric key agreed from our secret and the peer's public key
+pub fn derive_sym_key(secret: &StaticSecret, peer_public: &[u8; 32]) -> Result<[u8; 32]> {
+    let shared = secret.diffie_hellman(&PublicKey::from(*peer_public));
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, shared.as_bytes())
+        .expand(&[], &mut key)
+        .map_err(|e| Error::Encoding(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+/// Encrypt a message into a base64 type 0 envelope: type, nonce and ciphertext
+pub fn seal(sym_key: &[u8; 32], plaintext: &[u8]) -> Result<String> {
+    let mut iv = [0u8; IV_LEN];
+    rand::rng().fill_bytes(&mut iv);
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(sym_key))
+        .encrypt(Nonce::from_slice(&iv), plaintext)
+        .map_err(|_| Error::Encoding("Failed to encrypt message".to_string()))?;
+
+    let mut envelope = Vec::with_capacity(1 + IV_LEN + ciphertext.len());
+    envelope.push(ENVELOPE_TYPE_0);
+    envelope.extend_from_slice(&iv);
+    envelope.extend_from_slice(&ciphertext);
+    Ok(Base64::encode_string(&envelope))
+}
+
+/// Decrypt a base64 type 0 envelope
+pub fn open(sym_key: &[u8; 32], message: &str) -> Result<Vec<u8>> {
+    let envelope = Base64::decode_vec(message.trim())
+        .map_err(|e| Error::Encoding(format!("Invalid envelope encoding: {}", e)))?;
+    match envelope.first() {
+        Some(&ENVELOPE_TYPE_0) if envelope.len() > 1 + IV_LEN => {}
+        Some(&ENVELOPE_TYPE_0) | None => {
+            return Err(Error::Encoding("Truncated envelope".to_string()))
+        }
+        Some(other) => {
+            return Err(Error::Encoding(format!(
+                "Unsupported envelope type {}",
+                other
+            )))
+        }
+    }
+    ChaCha20Poly1305::new(Key::from_slice(sym_key))
+        .decrypt(
+            Nonce::from_slice(&envelope[1..1 + IV_LEN]),
+            &envelope[1 + IV_LEN..],
+        )
+        .map_err(|_| Error::Encoding("Failed to decrypt message".to_string()))
+}
+
+/// `wc:` URI a wallet scans to pair with the dApp
+#[derive(Clone, PartialEq, Eq)]
+pub struct PairingUri {
+    /// Pairing topic
+    pub topic: String,
+    /// Symmetric key of the pairing
+    pub sym_key: [u8; 32],
+    /// Relay protocol, `irn`
+    pub relay_protocol: String,
+    /// Unix time the pairing expires at
+    pub expiry: Option<u64>,
+}
+
+impl PairingUri {
+    /// New pairing with a random key, expiring after the default TTL
+    pub fn generate() -> Self {
+        let mut sym_key = [0u8; 32];
+        rand::rng().fill_bytes(&mut sym_key);
+        Self {
+            topic: topic_of(&sym_key),
+            sym_key,
+            relay_protocol: RELAY_PROTOCOL.to_string(),
+            expiry: Some(unix_time() + PAIRING_TTL),
+        }
+    }
+
+    /// Parse a `wc:{topic}@2?relay-protocol=irn&symKey={key}` URI
+    pub fn parse(uri: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::Encoding(format!("Invalid pairing URI: {}", reason));
+        let url = url::Url::parse(uri).map_err(|e| invalid(&e.to_string()))?;
+        if url.scheme() != "wc" {
+            return Err(invalid("scheme is not wc"));
+        }
+        let (topic, version) = url
+            .path()
+            .split_once('@')
+            .ok_or_else(|| invalid("missing version"))?;
+        if version != "2" {
+            return Err(invalid("not a v2 pairing"));
+        }
+
+        let mut sym_key = None;
+        let mut relay_protocol = None;
+        let mut expiry = None;
+        for (name, value) in url.query_pairs() {
+            match name.as_ref() {
+                "symKey" => {
+                    sym_key = hex::decode(value.as_ref())
+                        .ok()
+                        .and_then(|key| <[u8; 32]>::try_from(key).ok())
+                }
+                "relay-protocol" => relay_protocol = Some(value.into_owned()),
+                "expiryTimestamp" => expiry = value.parse().ok(),
+                _ => {}
+            }
+        }
+        Ok(Self {
+            topic: topic.to_string(),
+            sym_key: sym_key.ok_or_else(|| invalid("missing or bad symKey"))?,
+            relay_protocol: relay_protocol.unwrap_or_else(|| RELAY_PROTOCOL.to_string()),
+            expiry,
+        })
+    }
+}
+
+impl fmt::Display for PairingUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "wc:{}@2?relay-protocol={}&symKey={}",
+            self.topic,
+            self.relay_protocol,
+            hex::encode(self.sym_key)
+        )?;
+        if let Some(expiry) = self.expiry {
+            write!(f, "&expiryTimestamp={}", expiry)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PairingUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PairingUri")
+            .field("topic", &self.topic)
+            .field("relay_protocol", &self.relay_protocol)
+            .field("expiry", &self.expiry)
+            .finish_non_exhaustive()
+    }
+}
+
+/// How the dApp presents itself to the wallet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppMetadata {
+    /// Application name
+    pub name: String,
+    /// Short description
+    pub description: String,
+    /// Application URL
+    pub url: String,
+    /// Icon URLs
+    pub icons: Vec<String>,
+}
+
+impl AppMetadata {
+    /// Metadata with a name and URL
+    pub fn new(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: String::new(),
+            url: url.into(),
+            icons: Vec::new(),
+        }
+    }
+
+    /// Set the description
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Add an icon URL
+    pub fn with_icon(mut self, icon: impl Into<String>) -> Self {
+        self.icons.push(icon.into());
+        self
+    }
+}
+
+/// Connection to a WalletConnect relay
+///
+/// Implementations wrap the relay's `irn_subscribe`, `irn_publish` and
+/// `irn_subscription` JSON-RPC methods, including acknowledging deliveries.
+#[async_trait]
+pub trait RelayTransport: Send + Sync {
+    /// Start receiving messages published on a topic
+    async fn subscribe(&self, topic: &str) -> Result<()>;
+
+    /// Publish an encrypted message on a topic
+    async fn publish(&self, topic: &str, message: &str, tag: u32, ttl: u64) -> Result<()>;
+
+    /// Next message delivered on any subscribed topic
+    async fn next_message(&self) -> Result<RelayMessage>;
+}
+
+/// A message delivered by the relay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayMessage {
+    /// Topic the message was published on
+    pub topic: String,
+    /// Base64 envelope
+    pub message: String,
+}
+
+/// A session proposal waiting for the wallet to scan its URI
+pub struct SessionProposal {
+    uri: PairingUri,
+    id: u64,
+    secret: StaticSecret,
+}
+
+impl SessionProposal {
+    /// Pairing URI to show the user, usually as a QR code
+    pub fn uri(&self) -> &PairingUri {
+        &self.uri
+    }
+}
+
+/// A session approved by a wallet
+#[derive(Clone)]
+pub struct Session {
+    /// Session topic
+    pub topic: String,
+    sym_key: [u8; 32],
+    /// Accounts shared by the wallet, as CAIP-10 ids (`polkadot:{chain}:{address}`)
+    pub accounts: Vec<String>,
+    /// Unix time the session expires at
+    pub expiry: u64,
+    /// Name of the wallet
+    pub wallet: Option<String>,
+}
+
+impl Session {
+    /// Session from the params of a `wc_sessionSettle` request
+    pub fn from_settle(topic: String, sym_key: [u8; 32], params: &JsonValue) -> Option<Self> {
+        let accounts = params
+            .get("namespaces")?
+            .get(POLKADOT_NAMESPACE)?
+            .get("accounts")?
+            .as_array()?
+            .iter()
+            .filter_map(|account| account.as_str().map(str::to_string))
+            .collect();
+        Some(Self {
+            topic,
+            sym_key,
+            accounts,
+            expiry: params.get("expiry")?.as_u64()?,
+            wallet: params
+                .pointer("/controller/metadata/name")
+                .and_then(JsonValue::as_str)
+                .map(str::to_string),
+        })
+    }
+
+    /// Addresses shared by the wallet, sorted and without duplicates across chains
+    pub fn addresses(&self) -> Vec<String> {
+        let mut addresses: Vec<String> = self
+            .accounts
+            .iter()
+            .filter_map(|account| account.rsplit(':').next().map(str::to_string))
+            .collect();
+        addresses.sort();
+        addresses.dedup();
+        addresses
+    }
+
+    /// Chain an address was shared on, as a CAIP-2 id
+    fn chain_of(&self, address: &str) -> Option<String> {
+        self.accounts.iter().find_map(|account| {
+            let (chain, shared) = account.rsplit_once(':')?;
+            (shared == address).then(|| chain.to_string())
+        })
+    }
+
+    /// Whether the session has expired
+    pub fn is_expired(&self) -> bool {
+        unix_time() >= self.expiry
+    }
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("topic", &self.topic)
+            .field("accounts", &self.accounts)
+            .field("expiry", &self.expiry)
+            .field("wallet", &self.wallet)
+            .finish_non_exhaustive()
+    }
+}
+
+/// WalletConnect v2 client for a dApp
+pub struct WalletConnect<T> {
+    transport: T,
+    metadata: AppMetadata,
+    timeout: Duration,
+}
+
+impl<T: RelayTransport> WalletConnect<T> {
+    /// Client publishing through `transport`
+    pub fn new(transport: T, metadata: AppMetadata) -> Self {
+        Self {
+            transport,
+            metadata,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Set how long to wait for each wallet response
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Propose a session for the given chains (see [`chain_id`])
+    pub async fn propose(&self, chains: &[String]) -> Result<SessionProposal> {
+        let uri = PairingUri::generate();
+        let mut secret_bytes = [0u8; 32];
+        rand::rng().fill_bytes(&mut secret_bytes);
+        let secret = StaticSecret::from(secret_bytes);
+        let public = PublicKey::from(&secret);
+
+        let id = message_id();
+        let request = json!({
+            "id": id,
+            "jsonrpc": "2.0",
+            "method": "wc_sessionPropose",
+            "params": {
+                "relays": [{"protocol": RELAY_PROTOCOL}],
+                "proposer": {
+                    "publicKey": hex::encode(public.as_bytes()),
+                    "metadata": self.metadata,
+                },
+                "requiredNamespaces": {
+                    POLKADOT_NAMESPACE: {
+                        "chains": chains,
+                        "methods": [SIGN_TRANSACTION, SIGN_MESSAGE],
+                        "events": ["chainChanged", "accountsChanged"],
+                    }
+                },
+            },
+        });
+
+        self.transport.subscribe(&uri.topic).await?;
+        self.send(&uri.topic, &uri.sym_key, &request, TAG_SESSION_PROPOSE)
+            .await?;
+        Ok(SessionProposal { uri, id, secret })
+    }
+
+    /// Wait for the wallet to approve a proposal and settle the session
+    pub async fn await_session(&self, proposal: SessionProposal) -> Result<Session> {
+        let response = self
+            .receive(&proposal.uri.topic, &proposal.uri.sym_key, |message| {
+                message.get("id").and_then(JsonValue::as_u64) == Some(proposal.id)
+            })
+            .await?;
+        let result = rpc_result(&response, "Session proposal")?;
+        let responder: [u8; 32] = result
+            .get("responderPublicKey")
+            .and_then(JsonValue::as_str)
+            .and_then(|key| hex::decode(key).ok())
+            .and_then(|key| key.try_into().ok())
+            .ok_or_else(|| Error::Encoding("Missing responder public key".to_string()))?;
+
+        let sym_key = derive_sym_key(&proposal.secret, &responder)?;
+        let topic = topic_of(&sym_key);
+        self.transport.subscribe(&topic).await?;
+
+        let settle = self
+            .receive(&topic, &sym_key, |message| {
+                message.get("method").and_then(JsonValue::as_str) == Some("wc_sessionSettle")
+            })
+            .await?;
+        let ack = json!({"id": settle.get("id"), "jsonrpc": "2.0", "result": true});
+        self.send(&topic, &sym_key, &ack, TAG_SESSION_SETTLE_RESPONSE)
+            .await?;
+
+        settle
+            .get("params")
+            .and_then(|params| Session::from_settle(topic, sym_key, params))
+            .ok_or_else(|| Error::Encoding("Malformed session settlement".to_string()))
+    }
+
+    /// Ask the wallet to sign a transaction; returns the hex signature
+    pub async fn sign_transaction(
+        &self,
+        session: &Session,
+        payload: &TransactionPayload,
+    ) -> Result<String> {
+        let params = json!({"address": payload.address, "transactionPayload": payload});
+        self.request(session, &payload.address, SIGN_TRANSACTION, params)
+            .await
+    }
+
+    /// Ask the wallet to sign a message; returns the hex signature
+    pub async fn sign_message(
+        &self,
+        session: &Session,
+        address: &str,
+        message: &[u8],
+    ) -> Result<String> {
+        let params = json!({"address": address, "message": format!("0x{}", hex::encode(message))});
+        self.request(session, address, SIGN_MESSAGE, params).await
+    }
+
+    /// Send a session request and wait for the signature in its response
+    async fn request(
+        &self,
+        session: &Session,
+        address: &str,
+        method: &str,
+        params: JsonValue,
+    ) -> Result<String> {
+        if session.is_expired() {
+            return Err(Error::Wallet("WalletConnect session expired".to_string()));
+        }
+        let chain = session
+            .chain_of(address)
+            .ok_or_else(|| Error::Wallet(format!("{} was not shared in this session", address)))?;
+
+        let id = message_id();
+        let request = json!({
+            "id": id,
+            "jsonrpc": "2.0",
+            "method": "wc_sessionRequest",
+            "params": {
+                "request": {"method": method, "params": params},
+                "chainId": chain,
+            },
+        });
+        self.send(
+            &session.topic,
+            &session.sym_key,
+            &request,
+            TAG_SESSION_REQUEST,
+        )
+        .await?;
+
+        let response = self
+            .receive(&session.topic, &session.sym_key, |message| {
+                message.get("id").and_then(JsonValue::as_u64) == Some(id)
+                    && message.get("method").is_none()
+            })
+            .await?;
+        rpc_result(&response, "Signing request")?
+            .get("signature")
+            .and_then(JsonValue::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| Error::Signature("Wallet response has no signature".to_string()))
+    }
+
+    /// Encrypt and publish a JSON-RPC message
+    async fn send(
+        &self,
+        topic: &str,
+        sym_key: &[u8; 32],
+        message: &JsonValue,
+        tag: u32,
+    ) -> Result<()> {
+        let envelope = seal(sym_key, message.to_string().as_bytes())?;
+        self.transport
+            .publish(topic, &envelope, tag, PAIRING_TTL)
+            .await
+    }
+
+    /// Wait for a message on `topic` satisfying `matches`, skipping others
+    async fn receive(
+        &self,
+        topic: &str,
+        sym_key: &[u8; 32],
+        matches: impl Fn(&JsonValue) -> bool,
+    ) -> Result<JsonValue> {
+        let wait = async {
+            loop {
+                let delivered = self.transport.next_message().await?;
+                if delivered.topic != topic {
+                    continue;
+                }
+                let Ok(plaintext) = open(sym_key, &delivered.message) else {
+                    continue;
+                };
+                if let Ok(message) = serde_json::from_slice::<JsonValue>(&plaintext) {
+                    if matches(&message) {
+                        return Ok(message);
+                    }
+                }
+            }
+        };
+        tokio::time::timeout(self.timeout, wait)
+            .await
+            .map_err(|_| Error::Connection("Timed out waiting for the wallet".to_string()))?
+    }
+}
+
+/// Result of a JSON-RPC response, or the wallet's rejection as an error
+fn rpc_result<'r>(response: &'r JsonValue, what: &str) -> Result<&'r JsonValue> {
+    if let Some(error) = response.get("error") {
+        let message = error
+            .get("message")
+            .and_then(JsonValue::as_str)
+            .unwrap_or("no reason given");
+        return Err(Error::Wallet(format!("{} rejected: {}", what, message)));
+    }
+    response
+        .get("result")
+        .ok_or_else(|| Error::Encoding(format!("{} response has no result", what)))
+}
+
+/// JSON-RPC id: milliseconds since the epoch with three random digits
+fn message_id() -> u64 {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default();
+    millis * 1000 + rand::rng().next_u64() % 1000
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairing_uri_and_envelopes() {
+        let uri = PairingUri::generate();
+        let parsed = PairingUri::parse(&uri.to_string()).unwrap();
+        assert_eq!(parsed, uri);
+        assert_eq!(parsed.topic, topic_of(&parsed.sym_key));
+        assert!(PairingUri::parse("wc:abc@1?relay-protocol=irn&symKey=00").is_err());
+
+        let sealed = seal(&uri.sym_key, b"{\"id\":1}").unwrap();
+        assert_eq!(open(&uri.sym_key, &sealed).unwrap(), b"{\"id\":1}");
+        assert!(open(&[9; 32], &sealed).is_err());
+
+        let dapp = StaticSecret::from([1; 32]);
+        let wallet = StaticSecret::from([2; 32]);
+        assert_eq!(
+            derive_sym_key(&dapp, PublicKey::from(&wallet).as_bytes()).unwrap(),
+            derive_sym_key(&wallet, PublicKey::from(&dapp).as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_session_settle() {
+        let genesis = [0x91; 32];
+        let chain = chain_id(&genesis);
+        assert_eq!(chain, format!("polkadot:{}", "91".repeat(16)));
+
+        let params = json!({
+            "relay": {"protocol": "irn"},
+            "namespaces": {"polkadot": {
+                "accounts": [format!("{}:5Grw", chain)],
+                "methods": [SIGN_TRANSACTION],
+                "events": [],
+            }},
+            "expiry": u64::MAX,
+            "controller": {"publicKey": "00", "metadata": {"name": "Nova Wallet"}},
+        });
+        let session = Session::from_settle("topic".to_string(), [0; 32], &params).unwrap();
+        assert_eq!(session.addresses(), vec!["5Grw".to_string()]);
+        assert_eq!(session.chain_of("5Grw"), Some(chain));
+        assert_eq!(session.wallet.as_deref(), Some("Nova Wallet"));
+        assert!(!session.is_expired());
+    }
+}
@@ -0,0 +1,701 @@
+//! Watch-only accounts signed by an air-gapped device
+//!
+//! A [`WatchOnlyAccount`] knows only an address. It builds transactions
+//! against the connected chain and exports what has to be signed as a
+//! [`SigningRequest`], in the Universal Offline Signatures (UOS) format read
+//! by Polkadot Vault, as hex or as JSON. The signature the device returns is
+//! checked against the payload before the transaction is broadcast.
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::watch_only::WatchOnlyAccount;
+//! use apex_sdk_substrate::{KeyPairType, SubstrateAdapter};
+//!
+//! # async fn example(adapter: &SubstrateAdapter) -> Result<(), apex_sdk_substrate::Error> {
+//! let account = WatchOnlyAccount::new(
+//!     "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+//!     KeyPairType::Sr25519,
+//! )?;
+//! let pending = account
+//!     .prepare_transfer(adapter, "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty", 1_000_000_000)
+//!     .await?;
+//!
+//! // show this as a QR code to the signing device
+//! println!("{}", pending.request().to_hex());
+//!
+//! // hex signature scanned back from the device
+//! let signature = "0x01...";
+//! let tx_hash = pending.submit_signature(signature).await?;
+//! println!("submitted {}", tx_hash);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::derivation::ss58;
+use crate::event_query::account_id;
+use crate::metadata_hash::{
+    enable_metadata_hash, requires_metadata_hash, ExtensionData, MetadataHasher,
+    METADATA_HASH_EXTENSION,
+};
+use crate::short_metadata::{short_metadata, ShortMetadata};
+use crate::transaction::wait_for_finalized;
+use crate::uos::encode_frames;
+use crate::wallet::KeyPairType;
+use crate::{Error, Result, SubstrateAdapter};
+use parity_scale_codec::{Compact, Decode, Encode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use sp_core::{ed25519, sr25519, Pair as _};
+use subxt::config::{DefaultExtrinsicParams, DefaultExtrinsicParamsBuilder, ExtrinsicParams};
+use subxt::dynamic::Value;
+use subxt::tx::{PartialTransaction, SubmittableTransaction};
+use subxt::utils::{AccountId32, MultiSignature};
+use subxt::{OnlineClient, PolkadotConfig};
+
+/// First byte of every UOS payload, marking a Substrate network
+const UOS_SUBSTRATE: u8 = 0x53;
+
+/// UOS payload type of a legacy mortal transaction
+const UOS_SIGN_MORTAL_TRANSACTION: u8 = 0x00;
+
+/// UOS payload type of a transaction carrying its genesis hash
+const UOS_SIGN_TRANSACTION: u8 = 0x02;
+
+/// Bytes before the call: prefix, crypto, payload type and public key
+const UOS_HEADER_LEN: usize = 3 + 32;
+
+/// Longest signer payload signed as is; longer payloads are signed as their hash
+const MAX_UNHASHED_PAYLOAD: usize = 256;
+
+/// SS58 prefix of addresses derived for decoded requests
+const GENERIC_SS58_PREFIX: u16 = 42;
+
+/// Blocks a prepared transaction stays valid for
+pub const MORTAL_PERIOD: u64 = 64;
+
+/// Extrinsic format version of signed transactions
+const EXTRINSIC_VERSION: u32 = 4;
+
+/// Signature scheme of an offline signer, as numbered by UOS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UosCrypto {
+    /// ED25519 keys
+    Ed25519 = 0x00,
+    /// SR25519 keys
+    Sr25519 = 0x01,
+}
+
+impl UosCrypto {
+    /// Scheme of a key pair type; Ethereum accounts cannot be signed over UOS
+    pub fn from_key_type(key_type: KeyPairType) -> Option<Self> {
+        match key_type {
+            KeyPairType::Sr25519 => Some(Self::Sr25519),
+            KeyPairType::Ed25519 => Some(Self::Ed25519),
+            KeyPairType::Ethereum => None,
+        }
+    }
+
+    /// Scheme of a UOS crypto byte; ECDSA (`0x02`) is not supported
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0x00 => Some(Self::Ed25519),
+            0x01 => Some(Self::Sr25519),
+            _ => None,
+        }
+    }
+}
+
+/// An account known only by its address, signed for elsewhere
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchOnlyAccount {
+    address: String,
+    account_id: [u8; 32],
+    crypto: UosCrypto,
+}
+
+impl WatchOnlyAccount {
+    /// Account from its address and the scheme of its key
+    pub fn new(address: &str, key_type: KeyPairType) -> Result<Self> {
+        let crypto = UosCrypto::from_key_type(key_type).ok_or_else(|| {
+            Error::Signature("Ethereum accounts cannot be signed over UOS".to_string())
+        })?;
+        Ok(Self {
+            address: address.to_string(),
+            account_id: account_id(address)?,
+            crypto,
+        })
+    }
+
+    /// Address the account was created from
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Public key, which is the account id for SR25519 and ED25519 accounts
+    pub fn public_key(&self) -> [u8; 32] {
+        self.account_id
+    }
+
+    /// Signature scheme of the account
+    pub fn crypto(&self) -> UosCrypto {
+        self.crypto
+    }
+
+    /// Build a call from this account, ready to be signed offline
+    ///
+    /// Nonce and mortality are taken from the chain now, so the signature has
+    /// to come back within [`MORTAL_PERIOD`] blocks.
+    pub async fn prepare(
+        &self,
+        adapter: &SubstrateAdapter,
+        pallet: &str,
+        call: &str,
+        args: Vec<Value>,
+    ) -> Result<PendingTransaction> {
+        let client = adapter.client();
+        let signer = AccountId32(self.account_id);
+        let nonce = client.tx().account_nonce(&signer).await?;
+        let block = client.blocks().at_latest().await?;
+        let (block_number, block_hash) = (u64::from(block.number()), block.hash());
+        let params = || {
+            DefaultExtrinsicParamsBuilder::<PolkadotConfig>::new()
+                .nonce(nonce)
+                .mortal_from_unchecked(MORTAL_PERIOD, block_number, block_hash)
+                .build()
+        };
+        let tx = subxt::dynamic::tx(pallet, call, args);
+        // subxt signs with the metadata hash disabled, which hardware signers refuse
+        let metadata = client.metadata();
+        let metadata_hash = if requires_metadata_hash(&metadata) {
+            Some(MetadataHasher::new(adapter).hash().await?)
+        } else {
+            None
+        };
+        let unsigned = unsigned_transaction(client, &signer, &tx, params, metadata_hash).await?;
+
+        let runtime = client.runtime_version();
+        let payload = TransactionPayload {
+            address: self.address.clone(),
+            block_hash: format!("0x{}", hex::encode(block_hash)),
+            block_number: format!("0x{:08x}", block_number),
+            era: format!("0x{}", hex::encode(mortal_era(MORTAL_PERIOD, block_number))),
+            genesis_hash: format!("0x{}", hex::encode(client.genesis_hash())),
+            method: format!("0x{}", hex::encode(&unsigned.call_data)),
+            nonce: format!("0x{:08x}", nonce),
+            spec_version: format!("0x{:08x}", runtime.spec_version),
+            tip: format!("0x{:032x}", 0u128),
+            transaction_version: format!("0x{:08x}", runtime.transaction_version),
+            signed_extensions: metadata
+                .extrinsic()
+                .transaction_extensions_to_use_for_encoding()
+                .map(|extension| extension.identifier().to_string())
+                .collect(),
+            version: EXTRINSIC_VERSION,
+            mode: u8::from(metadata_hash.is_some()),
+            metadata_hash: metadata_hash.map(|hash| format!("0x{}", hex::encode(hash))),
+        };
+        let request = SigningRequest {
+            address: self.address.clone(),
+            crypto: self.crypto,
+            public_key: self.account_id,
+            call_data: unsigned.call_data.clone(),
+            extensions: unsigned.extensions.clone(),
+            genesis_hash: client.genesis_hash().0,
+            signer_payload: unsigned.signer_payload.clone(),
+        };
+        Ok(PendingTransaction {
+            client: client.clone(),
+            unsigned,
+            request,
+            payload,
+            signer,
+        })
+    }
+
+    /// Build a `Balances::transfer_keep_alive` to `to`
+    pub async fn prepare_transfer(
+        &self,
+        adapter: &SubstrateAdapter,
+        to: &str,
+        amount: u128,
+    ) -> Result<PendingTransaction> {
+        let dest = Value::unnamed_variant("Id", vec![Value::from_bytes(account_id(to)?)]);
+        self.prepare(
+            adapter,
+            "Balances",
+            "transfer_keep_alive",
+            vec![dest, Value::u128(amount)],
+        )
+        .await
+    }
+}
+
+/// A transaction split into the parts that are signed
+pub(crate) struct UnsignedTransaction {
+    partial: PartialTransaction<PolkadotConfig, OnlineClient<PolkadotConfig>>,
+    /// Encoded call
+    pub(crate) call_data: Vec<u8>,
+    /// Encoded extensions: the extra data followed by the implicit data
+    pub(crate) extensions: Vec<u8>,
+    /// Bytes to sign
+    pub(crate) signer_payload: Vec<u8>,
+    /// Extra extension data when it differs from what subxt would encode
+    extra: Option<Vec<u8>>,
+}
+
+impl UnsignedTransaction {
+    /// Attach `signer`'s signature of the signer payload
+    pub(crate) fn into_signed(
+        mut self,
+        client: &OnlineClient<PolkadotConfig>,
+        signer: &AccountId32,
+        signature: &MultiSignature,
+    ) -> SubmittableTransaction<PolkadotConfig, OnlineClient<PolkadotConfig>> {
+        match &self.extra {
+            Some(extra) => SubmittableTransaction::from_bytes(
+                client.clone(),
+                signed_extrinsic(signer, signature, extra, &self.call_data),
+            ),
+            None => self
+                .partial
+                .sign_with_account_and_signature(signer, signature),
+        }
+    }
+}
+
+/// Build `call` from `signer` for signing, with `metadata_hash` enabled in
+/// the `CheckMetadataHash` extension when given
+pub(crate) async fn unsigned_transaction<Call: subxt::tx::Payload>(
+    client: &OnlineClient<PolkadotConfig>,
+    signer: &AccountId32,
+    call: &Call,
+    params: impl Fn() -> <DefaultExtrinsicParams<PolkadotConfig> as ExtrinsicParams<PolkadotConfig>>::Params,
+    metadata_hash: Option<[u8; 32]>,
+) -> Result<UnsignedTransaction> {
+    let unsigned = partial(client, signer, call, params()).await?;
+
+    let call_data = unsigned.call_data().to_vec();
+    let signer_payload = unsigned.signer_payload();
+    let extensions = if signer_payload.len() <= MAX_UNHASHED_PAYLOAD {
+        signer_payload[call_data.len()..].to_vec()
+    } else {
+        // extensions do not depend on the call, so read them from an
+        // empty remark built with the same parameters
+        let remark = subxt::dynamic::tx("System", "remark", vec![Value::from_bytes([])]);
+        let probe = partial(client, signer, &remark, params()).await?;
+        probe.signer_payload()[probe.call_data().len()..].to_vec()
+    };
+    let mut unhashed = call_data.clone();
+    unhashed.extend_from_slice(&extensions);
+    if unhashed != signer_payload && sp_core::blake2_256(&unhashed) != signer_payload[..] {
+        return Err(Error::Transaction(
+            "Could not recover the extensions of the signer payload".to_string(),
+        ));
+    }
+
+    let Some(hash) = metadata_hash else {
+        return Ok(UnsignedTransaction {
+            partial: unsigned,
+            call_data,
+            extensions,
+            signer_payload,
+            extra: None,
+        });
+    };
+    let data = enable_metadata_hash(&extensions, &client.metadata(), hash)?;
+    let extensions = data.to_bytes();
+    Ok(UnsignedTransaction {
+        partial: unsigned,
+        signer_payload: payload_to_sign(&call_data, &extensions),
+        call_data,
+        extensions,
+        extra: Some(data.extra),
+    })
+}
+
+/// Bytes signed for a call and its extensions, hashed when long
+fn payload_to_sign(call_data: &[u8], extensions: &[u8]) -> Vec<u8> {
+    let mut payload = call_data.to_vec();
+    payload.extend_from_slice(extensions);
+    if payload.len() > MAX_UNHASHED_PAYLOAD {
+        payload = sp_core::blake2_256(&payload).to_vec();
+    }
+    payload
+}
+
+/// Signed extrinsic from its parts, with a `MultiAddress::Id` address
+fn signed_extrinsic(
+    signer: &AccountId32,
+    signature: &MultiSignature,
+    extra: &[u8],
+    call_data: &[u8],
+) -> Vec<u8> {
+    let mut body = vec![0x80 | EXTRINSIC_VERSION as u8, 0x00];
+    body.extend_from_slice(&signer.0);
+    body.extend(signature.encode());
+    body.extend_from_slice(extra);
+    body.extend_from_slice(call_data);
+    let mut extrinsic = Compact(body.len() as u32).encode();
+    extrinsic.extend(body);
+    extrinsic
+}
+
+/// Build a partial transaction with explicit extension parameters
+async fn partial<Call: subxt::tx::Payload>(
+    client: &OnlineClient<PolkadotConfig>,
+    signer: &AccountId32,
+    call: &Call,
+    params: <DefaultExtrinsicParams<PolkadotConfig> as ExtrinsicParams<PolkadotConfig>>::Params,
+) -> Result<PartialTransaction<PolkadotConfig, OnlineClient<PolkadotConfig>>> {
+    client
+        .tx()
+        .create_partial(call, signer, params)
+        .await
+        .map_err(|e| Error::Transaction(format!("Failed to build transaction: {}", e)))
+}
+
+/// Encoded mortal era of `period` blocks starting at `block`, as subxt encodes it
+///
+/// The period is rounded up to a power of two between 4 and 65536, and the
+/// phase quantized for periods above 4096 blocks.
+pub fn mortal_era(period: u64, block: u64) -> [u8; 2] {
+    let period = period
+        .checked_next_power_of_two()
+        .unwrap_or(1 << 16)
+        .clamp(4, 1 << 16);
+    let quantize_factor = (period >> 12).max(1);
+    let phase = block % period / quantize_factor;
+    let encoded = (period.trailing_zeros() - 1).clamp(1, 15) as u16 | ((phase as u16) << 4);
+    encoded.to_le_bytes()
+}
+
+/// Transaction to sign, in the polkadot-js `SignerPayloadJSON` layout
+///
+/// Browser extensions and wallets rebuild the signing payload from these
+/// fields; they match the extensions the transaction is submitted with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionPayload {
+    /// Signing address
+    pub address: String,
+    /// Hash of the block the era starts at
+    pub block_hash: String,
+    /// Number of that block, as hex
+    pub block_number: String,
+    /// Encoded era
+    pub era: String,
+    /// Genesis hash of the chain
+    pub genesis_hash: String,
+    /// Encoded call
+    pub method: String,
+    /// Account nonce, as hex
+    pub nonce: String,
+    /// Runtime spec version, as hex
+    pub spec_version: String,
+    /// Tip, as hex
+    pub tip: String,
+    /// Runtime transaction version, as hex
+    pub transaction_version: String,
+    /// Names of the signed extensions of the runtime
+    pub signed_extensions: Vec<String>,
+    /// Extrinsic format version
+    pub version: u32,
+    /// `CheckMetadataHash` mode: 1 when a metadata hash is included
+    #[serde(default)]
+    pub mode: u8,
+    /// Metadata hash the signer commits to, for chains checking it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_hash: Option<String>,
+}
+
+/// What an offline signer needs to sign a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningRequest {
+    /// Address of the signing account
+    pub address: String,
+    /// Signature scheme of the account
+    pub crypto: UosCrypto,
+    /// Public key of the account
+    pub public_key: [u8; 32],
+    /// SCALE encoded call
+    pub call_data: Vec<u8>,
+    /// Encoded extensions: the extra data followed by the implicit data
+    pub extensions: Vec<u8>,
+    /// Genesis hash of the chain, identifying it to the signer
+    pub genesis_hash: [u8; 32],
+    /// Bytes the signature is over; hashed when longer than 256 bytes
+    pub signer_payload: Vec<u8>,
+}
+
+impl SigningRequest {
+    /// UOS payload for Polkadot Vault
+    ///
+    /// `0x53`, the crypto byte, `0x02`, the public key, the call prefixed with
+    /// its compact length, the extensions and the genesis hash.
+    pub fn to_uos(&self) -> Vec<u8> {
+        let mut uos = vec![UOS_SUBSTRATE, self.crypto as u8, UOS_SIGN_TRANSACTION];
+        uos.extend_from_slice(&self.public_key);
+        uos.extend(self.call_data.encode());
+        uos.extend_from_slice(&self.extensions);
+        uos.extend_from_slice(&self.genesis_hash);
+        uos
+    }
+
+    /// Request from a UOS payload, e.g. one reassembled from scanned frames
+    ///
+    /// The address is derived from the public key with the generic SS58 prefix.
+    pub fn from_uos(payload: &[u8]) -> Result<Self> {
+        let invalid = |reason: &str| Error::Encoding(format!("Invalid UOS payload: {}", reason));
+        if payload.len() < UOS_HEADER_LEN {
+            return Err(invalid("too short"));
+        }
+        let (header, mut input) = payload.split_at(UOS_HEADER_LEN);
+        if header[0] != UOS_SUBSTRATE {
+            return Err(invalid("not a Substrate payload"));
+        }
+        let crypto =
+            UosCrypto::from_byte(header[1]).ok_or_else(|| invalid("unsupported crypto"))?;
+        if !matches!(
+            header[2],
+            UOS_SIGN_MORTAL_TRANSACTION | UOS_SIGN_TRANSACTION
+        ) {
+            return Err(invalid("not a transaction"));
+        }
+        let public_key: [u8; 32] = header[3..].try_into().expect("header holds a 32 byte key");
+
+        let call_data = Vec::<u8>::decode(&mut input).map_err(|_| invalid("bad call length"))?;
+        if input.len() < 32 {
+            return Err(invalid("no genesis hash"));
+        }
+        let (extensions, genesis_hash) = input.split_at(input.len() - 32);
+
+        let signer_payload = payload_to_sign(&call_data, extensions);
+        Ok(Self {
+            address: ss58(&public_key, GENERIC_SS58_PREFIX),
+            crypto,
+            public_key,
+            call_data,
+            extensions: extensions.to_vec(),
+            genesis_hash: genesis_hash
+                .try_into()
+                .expect("split 32 bytes from the end"),
+            signer_payload,
+        })
+    }
+
+    /// UOS payload split into multipart QR frames
+    pub fn to_frames(&self, frame_size: usize) -> Result<Vec<Vec<u8>>> {
+        encode_frames(&self.to_uos(), frame_size)
+    }
+
+    /// UOS payload as hex, for QR encoders taking text
+    pub fn to_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.to_uos()))
+    }
+
+    /// Request as JSON with hex fields, for signers not speaking UOS
+    pub fn to_json(&self) -> JsonValue {
+        json!({
+            "address": self.address,
+            "crypto": match self.crypto {
+                UosCrypto::Ed25519 => "ed25519",
+                UosCrypto::Sr25519 => "sr25519",
+            },
+            "publicKey": format!("0x{}", hex::encode(self.public_key)),
+            "method": format!("0x{}", hex::encode(&self.call_data)),
+            "extensions": format!("0x{}", hex::encode(&self.extensions)),
+            "genesisHash": format!("0x{}", hex::encode(self.genesis_hash)),
+            "signerPayload": format!("0x{}", hex::encode(&self.signer_payload)),
+        })
+    }
+
+    /// Parse a hex signature returned for this request and check it
+    ///
+    /// Accepts a bare 64 byte signature or one prefixed with its
+    /// `MultiSignature` variant, as Polkadot Vault returns it.
+    pub fn parse_signature(&self, signature: &str) -> Result<MultiSignature> {
+        let bytes = hex::decode(signature.trim().trim_start_matches("0x"))
+            .map_err(|e| Error::Signature(format!("Invalid signature hex: {}", e)))?;
+        let raw: [u8; 64] = match bytes.len() {
+            64 => bytes[..].try_into().ok(),
+            65 if bytes[0] == self.crypto as u8 => bytes[1..].try_into().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            Error::Signature(format!(
+                "Expected a 64 byte {:?} signature, got {} bytes",
+                self.crypto,
+                bytes.len()
+            ))
+        })?;
+
+        let valid = match self.crypto {
+            UosCrypto::Sr25519 => sr25519::Pair::verify(
+                &sr25519::Signature::from_raw(raw),
+                &self.signer_payload,
+                &sr25519::Public::from_raw(self.public_key),
+            ),
+            UosCrypto::Ed25519 => ed25519::Pair::verify(
+                &ed25519::Signature::from_raw(raw),
+                &self.signer_payload,
+                &ed25519::Public::from_raw(self.public_key),
+            ),
+        };
+        if !valid {
+            return Err(Error::Signature(format!(
+                "Signature does not match the payload for {}",
+                self.address
+            )));
+        }
+
+        Ok(match self.crypto {
+            UosCrypto::Sr25519 => MultiSignature::Sr25519(raw),
+            UosCrypto::Ed25519 => MultiSignature::Ed25519(raw),
+        })
+    }
+}
+
+/// A transaction built for a watch-only account, waiting for its signature
+pub struct PendingTransaction {
+    client: OnlineClient<PolkadotConfig>,
+    unsigned: UnsignedTransaction,
+    request: SigningRequest,
+    payload: TransactionPayload,
+    signer: AccountId32,
+}
+
+impl PendingTransaction {
+    /// What to hand to the offline signer
+    pub fn request(&self) -> &SigningRequest {
+        &self.request
+    }
+
+    /// The same transaction as a polkadot-js signer payload
+    pub fn payload(&self) -> &TransactionPayload {
+        &self.payload
+    }
+
+    /// Shortened metadata for clear signing on a Ledger device
+    ///
+    /// Fails on chains without the `CheckMetadataHash` extension, which the
+    /// Ledger Generic App needs.
+    pub async fn short_metadata(&self, adapter: &SubstrateAdapter) -> Result<ShortMetadata> {
+        let extra = self.unsigned.extra.as_ref().ok_or_else(|| {
+            Error::Metadata(format!(
+                "Runtime has no {} extension",
+                METADATA_HASH_EXTENSION
+            ))
+        })?;
+        let extensions = ExtensionData {
+            extra: extra.clone(),
+            implicit: self.request.extensions[extra.len()..].to_vec(),
+        };
+        let hasher = MetadataHasher::new(adapter);
+        short_metadata(
+            &hasher.runtime_metadata().await?,
+            &hasher.info().await?,
+            &self.request.call_data,
+            &extensions,
+        )
+    }
+
+    /// Attach the signature from the offline signer, broadcast and wait for finality
+    ///
+    /// Returns the transaction hash.
+    pub async fn submit_signature(self, signature: &str) -> Result<String> {
+        let signature = self.request.parse_signature(signature)?;
+        let tx = self
+            .unsigned
+            .into_signed(&self.client, &self.signer, &signature);
+        let progress = tx
+            .submit_and_watch()
+            .await
+            .map_err(|e| Error::Transaction(format!("Failed to submit transaction: {}", e)))?;
+        wait_for_finalized(progress).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(crypto: UosCrypto, public_key: [u8; 32]) -> SigningRequest {
+        SigningRequest {
+            address: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string(),
+            crypto,
+            public_key,
+            call_data: vec![0x05, 0x03, 0xaa],
+            extensions: vec![0x00, 0x04, 0x00],
+            genesis_hash: [0x91; 32],
+            signer_payload: vec![0x05, 0x03, 0xaa, 0x00, 0x04, 0x00],
+        }
+    }
+
+    #[test]
+    fn test_uos_payload() {
+        let uos = request(UosCrypto::Sr25519, [0xd4; 32]).to_uos();
+        assert_eq!(&uos[..3], &[0x53, 0x01, 0x02]);
+        assert_eq!(&uos[3..35], &[0xd4; 32]);
+        // compact length 3 is 0x0c
+        assert_eq!(&uos[35..42], &[0x0c, 0x05, 0x03, 0xaa, 0x00, 0x04, 0x00]);
+        assert_eq!(&uos[42..], &[0x91; 32]);
+        assert_eq!(uos.len(), 3 + 32 + 7 + 32);
+
+        let decoded = SigningRequest::from_uos(&uos).unwrap();
+        assert_eq!(decoded.to_uos(), uos);
+        assert_eq!(decoded.extensions, vec![0x00, 0x04, 0x00]);
+        assert_eq!(
+            decoded.signer_payload,
+            request(UosCrypto::Sr25519, [0; 32]).signer_payload
+        );
+        assert!(SigningRequest::from_uos(&uos[..60]).is_err());
+
+        // period 64 at block 1000: phase 40, as polkadot-js encodes it
+        assert_eq!(mortal_era(64, 1000), [0x85, 0x02]);
+        assert_eq!(mortal_era(3, 5), [0x11, 0x00]);
+
+        assert!(UosCrypto::from_key_type(KeyPairType::Ethereum).is_none());
+        assert!(WatchOnlyAccount::new(
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+            KeyPairType::Ethereum
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_signature() {
+        let pair = ed25519::Pair::from_seed(&[7; 32]);
+        let request = request(UosCrypto::Ed25519, pair.public().0);
+        let signature = pair.sign(&request.signer_payload).0;
+
+        let bare = format!("0x{}", hex::encode(signature));
+        assert_eq!(
+            request.parse_signature(&bare).unwrap(),
+            MultiSignature::Ed25519(signature)
+        );
+        let prefixed = format!("00{}", hex::encode(signature));
+        assert!(request.parse_signature(&prefixed).is_ok());
+        let wrong_variant = format!("01{}", hex::encode(signature));
+        assert!(request.parse_signature(&wrong_variant).is_err());
+
+        let mut forged = signature;
+        forged[0] ^= 1;
+        assert!(request.parse_signature(&hex::encode(forged)).is_err());
+    }
+
+    #[test]
+    fn test_signed_extrinsic() {
+        let signature = MultiSignature::Sr25519([0x55; 64]);
+        let extrinsic = signed_extrinsic(
+            &AccountId32([0xd4; 32]),
+            &signature,
+            &[0x00, 0x01],
+            &[0x05, 0x03],
+        );
+        // 1 + 1 + 32 + 65 + 2 + 2 bytes, prefixed with compact 103
+        assert_eq!(&extrinsic[..4], &[0x9d, 0x01, 0x84, 0x00]);
+        assert_eq!(&extrinsic[4..36], &[0xd4; 32]);
+        assert_eq!(extrinsic[36], 0x01);
+        assert_eq!(&extrinsic[37..101], &[0x55; 64]);
+        assert_eq!(&extrinsic[101..], &[0x00, 0x01, 0x05, 0x03]);
+    }
+}
@@ -6,7 +6,8 @@
 //! ## Features
 //!
 //! - Reserve transfers (transfer assets via reserve chain)
-//! - Teleport transfers (burn and mint across chains)
+//! - Teleport transfers (burn and mint across chains), checked against a
+//!   [`TeleportRegistry`](crate::teleport::TeleportRegistry) first
 //! - Multi-location address handling
 //! - XCM v3/v4 support
 //! - Parachain-to-parachain transfers
@@ -30,6 +31,8 @@
 //!     .await?;
 //! ```
 
+use crate::event_query::value_to_json;
+use crate::teleport::TeleportRegistry;
 use crate::{Error, Result, Sr25519Signer, Wallet};
 use subxt::{OnlineClient, PolkadotConfig};
 use tracing::{debug, info};
@@ -140,6 +143,93 @@ impl MultiLocation {
             }
         })
     }
+
+    /// Dynamic value of this location
+    pub(crate) fn to_value(&self) -> Result<subxt::dynamic::Value> {
+        // Structure: { parents: u8, interior: Junctions }
+        let interior = encode_junctions(&self.interior)?;
+
+        Ok(subxt::dynamic::Value::named_composite([
+            ("parents", subxt::dynamic::Value::u128(self.parents as u128)),
+            ("interior", interior),
+        ]))
+    }
+
+    /// Location as runtime JSON, the inverse of [`MultiLocation::from_json`]
+    ///
+    /// A single junction is written as `X1`'s value rather than a one-item
+    /// array, which template encoding accepts for both XCM v3 and v4.
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        let interior = match self.interior.as_slice() {
+            [] => serde_json::Value::from("Here"),
+            [junction] => serde_json::json!({ "X1": junction.to_json() }),
+            junctions => {
+                let mut variant = serde_json::Map::new();
+                variant.insert(
+                    format!("X{}", junctions.len()),
+                    junctions.iter().map(Junction::to_json).collect(),
+                );
+                serde_json::Value::Object(variant)
+            }
+        };
+        serde_json::json!({ "parents": self.parents, "interior": interior })
+    }
+
+    /// Location decoded from runtime JSON such as `{"parents": 1, "interior": "Here"}`
+    ///
+    /// `None` for junction kinds this module does not model.
+    pub(crate) fn from_json(json: &serde_json::Value) -> Option<Self> {
+        let parents = u8::try_from(json["parents"].as_u64()?).ok()?;
+        let interior = match &json["interior"] {
+            serde_json::Value::String(here) if here == "Here" => Vec::new(),
+            serde_json::Value::Object(variant) => {
+                let (_, junctions) = variant.iter().next()?;
+                // X1 holds a single junction once decoded
+                let junctions = match junctions {
+                    serde_json::Value::Array(junctions) => junctions.clone(),
+                    junction => vec![junction.clone()],
+                };
+                junctions
+                    .iter()
+                    .map(Junction::from_json)
+                    .collect::<Option<Vec<_>>>()?
+            }
+            _ => return None,
+        };
+        Some(Self { parents, interior })
+    }
+}
+
+/// `Junctions` value: `Here` or `X1` to `X8`
+fn encode_junctions(junctions: &[Junction]) -> Result<subxt::dynamic::Value> {
+    if junctions.is_empty() {
+        // X0 (Here) variant
+        return Ok(subxt::dynamic::Value::unnamed_variant("Here", vec![]));
+    }
+
+    // Encode junctions as nested X1, X2, etc.
+    let encoded_junctions: Vec<subxt::dynamic::Value> = junctions
+        .iter()
+        .map(Junction::to_value)
+        .collect::<Result<Vec<_>>>()?;
+
+    // Use appropriate variant based on number of junctions
+    let variant_name = match junctions.len() {
+        1 => "X1",
+        2 => "X2",
+        3 => "X3",
+        4 => "X4",
+        5 => "X5",
+        6 => "X6",
+        7 => "X7",
+        8 => "X8",
+        _ => return Err(Error::Transaction("Too many junctions (max 8)".to_string())),
+    };
+
+    Ok(subxt::dynamic::Value::unnamed_variant(
+        variant_name,
+        encoded_junctions,
+    ))
 }
 
 /// Interior junction types for multi-location
@@ -170,6 +260,120 @@ pub enum Junction {
     PalletInstance(u8),
 }
 
+impl Junction {
+    /// Dynamic value of this junction
+    fn to_value(&self) -> Result<subxt::dynamic::Value> {
+        match self {
+            Junction::Parachain(id) => Ok(subxt::dynamic::Value::unnamed_variant(
+                "Parachain",
+                vec![subxt::dynamic::Value::u128(*id as u128)],
+            )),
+            Junction::AccountId32 { network, id } => {
+                let network_value = if let Some(_net) = network {
+                    // Encode network if present
+                    subxt::dynamic::Value::unnamed_variant("Some", vec![])
+                } else {
+                    subxt::dynamic::Value::unnamed_variant("None", vec![])
+                };
+
+                Ok(subxt::dynamic::Value::unnamed_variant(
+                    "AccountId32",
+                    vec![network_value, subxt::dynamic::Value::from_bytes(id)],
+                ))
+            }
+            Junction::AccountId20 { network, key } => {
+                let network_value = if let Some(_net) = network {
+                    subxt::dynamic::Value::unnamed_variant("Some", vec![])
+                } else {
+                    subxt::dynamic::Value::unnamed_variant("None", vec![])
+                };
+
+                Ok(subxt::dynamic::Value::unnamed_variant(
+                    "AccountId20",
+                    vec![network_value, subxt::dynamic::Value::from_bytes(key)],
+                ))
+            }
+            Junction::GeneralIndex(index) => Ok(subxt::dynamic::Value::unnamed_variant(
+                "GeneralIndex",
+                vec![subxt::dynamic::Value::u128(*index)],
+            )),
+            Junction::GeneralKey { data } => Ok(subxt::dynamic::Value::unnamed_variant(
+                "GeneralKey",
+                vec![subxt::dynamic::Value::from_bytes(data)],
+            )),
+            Junction::PalletInstance(instance) => Ok(subxt::dynamic::Value::unnamed_variant(
+                "PalletInstance",
+                vec![subxt::dynamic::Value::u128(*instance as u128)],
+            )),
+        }
+    }
+
+    /// Junction as runtime JSON
+    fn to_json(&self) -> serde_json::Value {
+        let network = |network: &Option<NetworkId>| match network {
+            Some(network) => serde_json::json!({ "Some": network.to_json() }),
+            None => serde_json::Value::from("None"),
+        };
+        match self {
+            Junction::Parachain(id) => serde_json::json!({ "Parachain": id }),
+            Junction::AccountId32 { network: n, id } => serde_json::json!({
+                "AccountId32": { "network": network(n), "id": format!("0x{}", hex::encode(id)) }
+            }),
+            Junction::AccountId20 { network: n, key } => serde_json::json!({
+                "AccountKey20": { "network": network(n), "key": format!("0x{}", hex::encode(key)) }
+            }),
+            Junction::GeneralIndex(index) => match u64::try_from(*index) {
+                Ok(index) => serde_json::json!({ "GeneralIndex": index }),
+                Err(_) => serde_json::json!({ "GeneralIndex": index.to_string() }),
+            },
+            Junction::GeneralKey { data } => {
+                let mut padded = [0u8; 32];
+                let length = data.len().min(32);
+                padded[..length].copy_from_slice(&data[..length]);
+                serde_json::json!({
+                    "GeneralKey": { "length": length, "data": format!("0x{}", hex::encode(padded)) }
+                })
+            }
+            Junction::PalletInstance(index) => serde_json::json!({ "PalletInstance": index }),
+        }
+    }
+
+    /// Junction decoded from runtime JSON such as `{"Parachain": 1000}`
+    fn from_json(json: &serde_json::Value) -> Option<Self> {
+        let (name, value) = json.as_object()?.iter().next()?;
+        let bytes =
+            |value: &serde_json::Value| hex::decode(value.as_str()?.strip_prefix("0x")?).ok();
+        let network = |fields: &serde_json::Value| match &fields["network"] {
+            serde_json::Value::Object(some) => some.get("Some").and_then(NetworkId::from_json),
+            _ => None,
+        };
+        Some(match name.as_str() {
+            "Parachain" => Junction::Parachain(u32::try_from(value.as_u64()?).ok()?),
+            "PalletInstance" => Junction::PalletInstance(u8::try_from(value.as_u64()?).ok()?),
+            "GeneralIndex" => Junction::GeneralIndex(match value {
+                serde_json::Value::String(index) => index.parse().ok()?,
+                index => index.as_u64()?.into(),
+            }),
+            "GeneralKey" => {
+                let mut data = bytes(&value["data"])?;
+                if let Some(length) = value["length"].as_u64() {
+                    data.truncate(length as usize);
+                }
+                Junction::GeneralKey { data }
+            }
+            "AccountId32" => Junction::AccountId32 {
+                network: network(value),
+                id: bytes(&value["id"])?.try_into().ok()?,
+            },
+            "AccountKey20" => Junction::AccountId20 {
+                network: network(value),
+                key: bytes(&value["key"])?.try_into().ok()?,
+            },
+            _ => return None,
+        })
+    }
+}
+
 /// Network identifier for cross-consensus messaging
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NetworkId {
@@ -185,6 +389,39 @@ pub enum NetworkId {
     ByGenesis([u8; 32]),
 }
 
+impl NetworkId {
+    fn to_json(self) -> serde_json::Value {
+        match self {
+            NetworkId::Polkadot => "Polkadot".into(),
+            NetworkId::Kusama => "Kusama".into(),
+            NetworkId::Westend => "Westend".into(),
+            NetworkId::Rococo => "Rococo".into(),
+            NetworkId::ByGenesis(genesis) => {
+                serde_json::json!({ "ByGenesis": format!("0x{}", hex::encode(genesis)) })
+            }
+        }
+    }
+
+    fn from_json(json: &serde_json::Value) -> Option<Self> {
+        match json {
+            serde_json::Value::String(name) => match name.as_str() {
+                "Polkadot" => Some(NetworkId::Polkadot),
+                "Kusama" => Some(NetworkId::Kusama),
+                "Westend" => Some(NetworkId::Westend),
+                "Rococo" => Some(NetworkId::Rococo),
+                _ => None,
+            },
+            serde_json::Value::Object(variant) => {
+                let genesis = variant.get("ByGenesis")?.as_str()?.strip_prefix("0x")?;
+                Some(NetworkId::ByGenesis(
+                    hex::decode(genesis).ok()?.try_into().ok()?,
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
 /// XCM asset representation
 #[derive(Debug, Clone)]
 pub struct XcmAsset {
@@ -266,20 +503,22 @@ pub struct XcmConfig {
 pub struct XcmExecutor {
     client: OnlineClient<PolkadotConfig>,
     config: XcmConfig,
+    teleports: Option<TeleportRegistry>,
 }
 
 impl XcmExecutor {
     /// Create a new XCM executor
     pub fn new(client: OnlineClient<PolkadotConfig>) -> Self {
-        Self {
-            client,
-            config: XcmConfig::default(),
-        }
+        Self::with_config(client, XcmConfig::default())
     }
 
     /// Create a new XCM executor with custom configuration
     pub fn with_config(client: OnlineClient<PolkadotConfig>, config: XcmConfig) -> Self {
-        Self { client, config }
+        Self {
+            client,
+            config,
+            teleports: Some(TeleportRegistry::system_chains()),
+        }
     }
 
     /// Set the XCM version
@@ -294,6 +533,19 @@ impl XcmExecutor {
         self
     }
 
+    /// Check teleports against `registry` instead of
+    /// [`TeleportRegistry::system_chains`]
+    pub fn with_teleport_registry(mut self, registry: TeleportRegistry) -> Self {
+        self.teleports = Some(registry);
+        self
+    }
+
+    /// Send teleports without checking that the chains trust each other
+    pub fn without_teleport_check(mut self) -> Self {
+        self.teleports = None;
+        self
+    }
+
     /// Execute a reserve transfer to another chain
     ///
     /// # Arguments
@@ -348,6 +600,9 @@ impl XcmExecutor {
     /// # Returns
     ///
     /// Transaction hash of the XCM transfer extrinsic
+    ///
+    /// Fails with [`Error::TeleportNotTrusted`] if the teleport registry does
+    /// not list both chains as teleporters of every asset.
     pub async fn teleport(
         &self,
         wallet: &Wallet,
@@ -356,6 +611,7 @@ impl XcmExecutor {
         assets: Vec<XcmAsset>,
     ) -> Result<String> {
         info!("Executing teleport to {:?} for beneficiary", dest);
+        self.check_teleport(&dest, &assets).await?;
 
         let dest_value = self.encode_multilocation(&dest)?;
         let beneficiary_value = self.encode_multilocation(&MultiLocation::account(beneficiary))?;
@@ -418,98 +674,60 @@ impl XcmExecutor {
         .await
     }
 
-    // Helper methods for encoding XCM types
-
-    fn encode_multilocation(&self, location: &MultiLocation) -> Result<subxt::dynamic::Value> {
-        // Encode MultiLocation as composite value
-        // Structure: { parents: u8, interior: Junctions }
-
-        let interior = self.encode_junctions(&location.interior)?;
-
-        Ok(subxt::dynamic::Value::named_composite([
-            (
-                "parents",
-                subxt::dynamic::Value::u128(location.parents as u128),
-            ),
-            ("interior", interior),
-        ]))
+    async fn check_teleport(&self, dest: &MultiLocation, assets: &[XcmAsset]) -> Result<()> {
+        let Some(registry) = &self.teleports else {
+            return Ok(());
+        };
+        let origin = self.para_id().await?;
+        for asset in assets {
+            match &asset.id {
+                AssetId::Concrete(location) => registry.check(origin, dest, location)?,
+                AssetId::Abstract(_) => {
+                    return Err(Error::TeleportNotTrusted {
+                        asset: format!("{:?}", asset.id),
+                        from: "this chain".to_string(),
+                        to: format!("{:?}", dest),
+                    })
+                }
+            }
+        }
+        Ok(())
     }
 
-    fn encode_junctions(&self, junctions: &[Junction]) -> Result<subxt::dynamic::Value> {
-        if junctions.is_empty() {
-            // X0 (Here) variant
-            return Ok(subxt::dynamic::Value::unnamed_variant("Here", vec![]));
+    /// Para id of the connected chain; `None` on a relay chain
+    async fn para_id(&self) -> Result<Option<u32>> {
+        if self
+            .client
+            .metadata()
+            .pallet_by_name("ParachainInfo")
+            .is_none()
+        {
+            return Ok(None);
         }
-
-        // Encode junctions as nested X1, X2, etc.
-        let encoded_junctions: Vec<subxt::dynamic::Value> = junctions
-            .iter()
-            .map(|j| self.encode_junction(j))
-            .collect::<Result<Vec<_>>>()?;
-
-        // Use appropriate variant based on number of junctions
-        let variant_name = match junctions.len() {
-            1 => "X1",
-            2 => "X2",
-            3 => "X3",
-            4 => "X4",
-            5 => "X5",
-            6 => "X6",
-            7 => "X7",
-            8 => "X8",
-            _ => return Err(Error::Transaction("Too many junctions (max 8)".to_string())),
-        };
-
-        Ok(subxt::dynamic::Value::unnamed_variant(
-            variant_name,
-            encoded_junctions,
-        ))
+        let query = subxt::dynamic::storage("ParachainInfo", "ParachainId", vec![]);
+        let para_id = self
+            .client
+            .storage()
+            .at_latest()
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to fetch latest block: {}", e)))?
+            .fetch(&query)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to query para id: {}", e)))?
+            .ok_or_else(|| Error::Storage("Chain has no para id".to_string()))?
+            .to_value()
+            .map_err(|e| Error::Storage(format!("Failed to decode para id: {}", e)))?;
+        value_to_json(&para_id)
+            .as_u64()
+            .and_then(|id| u32::try_from(id).ok())
+            .map(Some)
+            .ok_or_else(|| Error::Storage("Unexpected para id".to_string()))
     }
 
-    fn encode_junction(&self, junction: &Junction) -> Result<subxt::dynamic::Value> {
-        match junction {
-            Junction::Parachain(id) => Ok(subxt::dynamic::Value::unnamed_variant(
-                "Parachain",
-                vec![subxt::dynamic::Value::u128(*id as u128)],
-            )),
-            Junction::AccountId32 { network, id } => {
-                let network_value = if let Some(_net) = network {
-                    // Encode network if present
-                    subxt::dynamic::Value::unnamed_variant("Some", vec![])
-                } else {
-                    subxt::dynamic::Value::unnamed_variant("None", vec![])
-                };
-
-                Ok(subxt::dynamic::Value::unnamed_variant(
-                    "AccountId32",
-                    vec![network_value, subxt::dynamic::Value::from_bytes(id)],
-                ))
-            }
-            Junction::AccountId20 { network, key } => {
-                let network_value = if let Some(_net) = network {
-                    subxt::dynamic::Value::unnamed_variant("Some", vec![])
-                } else {
-                    subxt::dynamic::Value::unnamed_variant("None", vec![])
-                };
+    // Helper methods for encoding XCM types
 
-                Ok(subxt::dynamic::Value::unnamed_variant(
-                    "AccountId20",
-                    vec![network_value, subxt::dynamic::Value::from_bytes(key)],
-                ))
-            }
-            Junction::GeneralIndex(index) => Ok(subxt::dynamic::Value::unnamed_variant(
-                "GeneralIndex",
-                vec![subxt::dynamic::Value::u128(*index)],
-            )),
-            Junction::GeneralKey { data } => Ok(subxt::dynamic::Value::unnamed_variant(
-                "GeneralKey",
-                vec![subxt::dynamic::Value::from_bytes(data)],
-            )),
-            Junction::PalletInstance(instance) => Ok(subxt::dynamic::Value::unnamed_variant(
-                "PalletInstance",
-                vec![subxt::dynamic::Value::u128(*instance as u128)],
-            )),
-        }
+    fn encode_multilocation(&self, location: &MultiLocation) -> Result<subxt::dynamic::Value> {
+        location.to_value()
     }
 
     fn encode_assets(&self, assets: &[XcmAsset]) -> Result<subxt::dynamic::Value> {
@@ -0,0 +1,463 @@
+//! Decoding XCM messages into readable instruction trees
+//!
+//! [`XcmDecoder`] decodes `VersionedXcm` blobs with the runtime's own type
+//! registry, so any XCM version the runtime knows can be read: messages
+//! from `Dmp::DownwardMessageQueues`, upward messages, HRMP pages holding
+//! several concatenated messages, or the arguments of `PolkadotXcm` calls.
+//! Messages already decoded into events, such as `PolkadotXcm::Sent`, are
+//! read with [`XcmMessage::from_json`].
+//!
+//! Each [`XcmInstruction`] lists the assets it names with their amounts, the
+//! location it sends to or deposits at, and the nested messages it carries,
+//! and the whole message renders as an indented tree:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{SubstrateAdapter, XcmDecoder};
+//!
+//! # fn example(adapter: &SubstrateAdapter, blob: &[u8]) -> Result<(), apex_sdk_substrate::Error> {
+//! let decoder = XcmDecoder::new(&adapter.client().metadata())?;
+//! println!("{}", decoder.decode(blob)?);
+//! // XCM V4
+//! //   WithdrawAsset 10000000000 of ..
+//! //   BuyExecution 10000000000 of ..
+//! //   DepositAsset All -> AccountId32(0xd43593c7…)
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event_query::value_to_json;
+use crate::receipt::json_u128;
+use crate::{Error, Result};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::fmt;
+use subxt::ext::scale_value;
+use subxt::Metadata;
+
+/// Instructions whose whole value is the assets they name
+const ASSET_INSTRUCTIONS: [&str; 4] = [
+    "WithdrawAsset",
+    "ReserveAssetDeposited",
+    "ReceiveTeleportedAsset",
+    "BurnAsset",
+];
+
+/// Instructions whose whole value is a nested message
+const NESTED_INSTRUCTIONS: [&str; 2] = ["SetAppendix", "SetErrorHandler"];
+
+/// An asset named by an instruction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AssetAmount {
+    /// Asset location, or a wildcard such as `All`
+    pub asset: String,
+    /// Fungible amount; `None` for wildcards and non-fungible instances
+    pub amount: Option<u128>,
+}
+
+impl fmt::Display for AssetAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.amount {
+            Some(amount) => write!(f, "{} of {}", amount, self.asset),
+            None => f.write_str(&self.asset),
+        }
+    }
+}
+
+/// One decoded XCM instruction
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct XcmInstruction {
+    /// Instruction name, e.g. `DepositAsset`
+    pub name: String,
+    /// Assets the instruction names
+    pub assets: Vec<AssetAmount>,
+    /// Beneficiary, destination or reserve the instruction targets
+    pub target: Option<String>,
+    /// Instructions of the message it carries or sets
+    pub nested: Vec<XcmInstruction>,
+    /// Decoded fields
+    pub fields: JsonValue,
+}
+
+impl XcmInstruction {
+    fn from_json(json: &JsonValue) -> Self {
+        let (name, fields) = match json {
+            JsonValue::Object(variant) => match variant.iter().next() {
+                Some((name, fields)) => (name.clone(), fields.clone()),
+                None => (String::new(), JsonValue::Null),
+            },
+            JsonValue::String(name) => (name.clone(), JsonValue::Null),
+            other => (other.to_string(), JsonValue::Null),
+        };
+
+        let assets = if ASSET_INSTRUCTIONS.contains(&name.as_str()) {
+            assets(&fields)
+        } else {
+            ["assets", "fees", "asset"]
+                .iter()
+                .find_map(|field| fields.get(field))
+                .map(assets)
+                .unwrap_or_default()
+        };
+        let target = ["beneficiary", "dest", "destination", "reserve"]
+            .iter()
+            .find_map(|field| fields.get(field))
+            .map(location_text);
+        let nested = if NESTED_INSTRUCTIONS.contains(&name.as_str()) {
+            instructions(&fields)
+        } else {
+            ["xcm", "remote_xcm"]
+                .iter()
+                .find_map(|field| fields.get(field))
+                .map(instructions)
+                .unwrap_or_default()
+        };
+
+        Self {
+            name,
+            assets,
+            target,
+            nested,
+            fields,
+        }
+    }
+
+    fn render(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        write!(f, "{:indent$}{}", "", self.name, indent = depth * 2)?;
+        for (index, asset) in self.assets.iter().enumerate() {
+            f.write_str(if index == 0 { " " } else { ", " })?;
+            write!(f, "{}", asset)?;
+        }
+        if let Some(target) = &self.target {
+            write!(f, " -> {}", target)?;
+        }
+        for instruction in &self.nested {
+            writeln!(f)?;
+            instruction.render(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// A decoded XCM message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct XcmMessage {
+    /// Version variant, e.g. `V4`; `None` for unversioned messages
+    pub version: Option<String>,
+    /// Instructions in execution order
+    pub instructions: Vec<XcmInstruction>,
+}
+
+impl XcmMessage {
+    /// Message from its decoded JSON, versioned (`{"V4": [...]}`) or not
+    pub fn from_json(json: &JsonValue) -> Self {
+        if let JsonValue::Object(variant) = json {
+            if let Some((version, xcm)) = variant.iter().next() {
+                if variant.len() == 1 && is_version(version) {
+                    return Self {
+                        version: Some(version.clone()),
+                        instructions: instructions(xcm),
+                    };
+                }
+            }
+        }
+        Self {
+            version: None,
+            instructions: instructions(json),
+        }
+    }
+
+    /// Every asset named by the message and its nested messages
+    pub fn assets(&self) -> Vec<&AssetAmount> {
+        fn collect<'a>(instructions: &'a [XcmInstruction], out: &mut Vec<&'a AssetAmount>) {
+            for instruction in instructions {
+                out.extend(&instruction.assets);
+                collect(&instruction.nested, out);
+            }
+        }
+        let mut assets = Vec::new();
+        collect(&self.instructions, &mut assets);
+        assets
+    }
+}
+
+impl fmt::Display for XcmMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "XCM {}", version)?,
+            None => f.write_str("XCM")?,
+        }
+        for instruction in &self.instructions {
+            writeln!(f)?;
+            instruction.render(f, 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes SCALE-encoded `VersionedXcm` with a runtime's metadata
+#[derive(Clone)]
+pub struct XcmDecoder {
+    metadata: Metadata,
+    ty: u32,
+}
+
+impl XcmDecoder {
+    /// Decoder for the runtime described by `metadata`
+    pub fn new(metadata: &Metadata) -> Result<Self> {
+        let ty = metadata
+            .types()
+            .types
+            .iter()
+            .find(|ty| ty.ty.path.segments.last().map(String::as_str) == Some("VersionedXcm"))
+            .map(|ty| ty.id)
+            .ok_or_else(|| Error::Metadata("Runtime has no VersionedXcm type".to_string()))?;
+        Ok(Self {
+            metadata: metadata.clone(),
+            ty,
+        })
+    }
+
+    /// Decode one message, rejecting trailing bytes
+    pub fn decode(&self, bytes: &[u8]) -> Result<XcmMessage> {
+        let mut input = bytes;
+        let message = self.decode_next(&mut input)?;
+        if !input.is_empty() {
+            return Err(Error::Encoding(format!(
+                "Invalid XCM: {} trailing bytes",
+                input.len()
+            )));
+        }
+        Ok(message)
+    }
+
+    /// Decode back-to-back messages, as in an HRMP page
+    ///
+    /// A leading `XcmpMessageFormat::ConcatenatedVersionedXcm` byte is
+    /// skipped if `with_format` is set.
+    pub fn decode_concatenated(&self, bytes: &[u8], with_format: bool) -> Result<Vec<XcmMessage>> {
+        let mut input = bytes;
+        if with_format {
+            match input.split_first() {
+                Some((0, rest)) => input = rest,
+                _ => {
+                    return Err(Error::Encoding(
+                        "Not a page of concatenated versioned XCM".to_string(),
+                    ))
+                }
+            }
+        }
+        let mut messages = Vec::new();
+        while !input.is_empty() {
+            messages.push(self.decode_next(&mut input)?);
+        }
+        Ok(messages)
+    }
+
+    fn decode_next(&self, input: &mut &[u8]) -> Result<XcmMessage> {
+        let value = scale_value::scale::decode_as_type(input, self.ty, self.metadata.types())
+            .map_err(|e| Error::Encoding(format!("Invalid XCM: {}", e)))?;
+        Ok(XcmMessage::from_json(&value_to_json(&value)))
+    }
+}
+
+impl fmt::Debug for XcmDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("XcmDecoder").field("ty", &self.ty).finish()
+    }
+}
+
+fn is_version(name: &str) -> bool {
+    name.strip_prefix('V')
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Instructions of a decoded `Xcm`; one-instruction messages decode to the
+/// instruction itself
+fn instructions(json: &JsonValue) -> Vec<XcmInstruction> {
+    match json {
+        JsonValue::Array(instructions) => {
+            instructions.iter().map(XcmInstruction::from_json).collect()
+        }
+        JsonValue::Null => Vec::new(),
+        instruction => vec![XcmInstruction::from_json(instruction)],
+    }
+}
+
+/// Assets of a decoded `Assets`, `Asset` or `AssetFilter`
+pub(crate) fn assets(json: &JsonValue) -> Vec<AssetAmount> {
+    match json {
+        JsonValue::Array(assets) => assets.iter().map(asset).collect(),
+        JsonValue::Object(variant) if variant.contains_key("Definite") => {
+            assets(&variant["Definite"])
+        }
+        JsonValue::Object(variant) if variant.contains_key("Wild") => vec![AssetAmount {
+            asset: wildcard(&variant["Wild"]),
+            amount: None,
+        }],
+        JsonValue::Null => Vec::new(),
+        single => vec![asset(single)],
+    }
+}
+
+fn asset(json: &JsonValue) -> AssetAmount {
+    let id = asset_id_text(&json["id"]);
+    match &json["fun"] {
+        JsonValue::Object(fun) if fun.contains_key("Fungible") => AssetAmount {
+            asset: id,
+            amount: json_u128(&fun["Fungible"]),
+        },
+        JsonValue::Object(fun) if fun.contains_key("NonFungible") => AssetAmount {
+            asset: format!("{} #{}", id, compact(&fun["NonFungible"])),
+            amount: None,
+        },
+        _ => AssetAmount {
+            asset: compact(json),
+            amount: None,
+        },
+    }
+}
+
+fn wildcard(json: &JsonValue) -> String {
+    let JsonValue::Object(variant) = json else {
+        return compact(json);
+    };
+    match variant.iter().next() {
+        Some((name, of)) if name == "AllOf" => format!("All of {}", asset_id_text(&of["id"])),
+        Some((name, count)) if name == "AllCounted" => {
+            format!("All (up to {})", compact(count))
+        }
+        Some((name, of)) if name == "AllOfCounted" => format!(
+            "All of {} (up to {})",
+            asset_id_text(&of["id"]),
+            compact(&of["count"])
+        ),
+        _ => compact(json),
+    }
+}
+
+/// Asset id as a location; v3 ids are wrapped in `Concrete`
+fn asset_id_text(json: &JsonValue) -> String {
+    match json.get("Concrete") {
+        Some(location) => location_text(location),
+        None if json.get("parents").is_some() => location_text(json),
+        None => compact(json),
+    }
+}
+
+/// Location as `../Parachain(1000)/GeneralIndex(1984)`; `Here` when empty
+pub(crate) fn location_text(json: &JsonValue) -> String {
+    let Some(parents) = json["parents"].as_u64() else {
+        return compact(json);
+    };
+    let mut parts = vec!["..".to_string(); parents as usize];
+    if let JsonValue::Object(variant) = &json["interior"] {
+        if let Some((_, junctions)) = variant.iter().next() {
+            match junctions {
+                JsonValue::Array(junctions) => parts.extend(junctions.iter().map(junction_text)),
+                junction => parts.push(junction_text(junction)),
+            }
+        }
+    }
+    if parts.is_empty() {
+        "Here".to_string()
+    } else {
+        parts.join("/")
+    }
+}
+
+fn junction_text(json: &JsonValue) -> String {
+    let JsonValue::Object(variant) = json else {
+        return compact(json);
+    };
+    let Some((name, value)) = variant.iter().next() else {
+        return compact(json);
+    };
+    let inner = match value {
+        JsonValue::Object(fields) => ["id", "key", "data", "index"]
+            .iter()
+            .find_map(|field| fields.get(*field))
+            .map(compact)
+            .unwrap_or_else(|| compact(value)),
+        value => compact(value),
+    };
+    format!("{}({})", name, inner)
+}
+
+fn compact(json: &JsonValue) -> String {
+    match json {
+        JsonValue::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_message_tree_and_rendering() {
+        let alice = format!("0x{}", "d4".repeat(32));
+        let message = XcmMessage::from_json(&json!({
+            "V4": [
+                { "WithdrawAsset": { "id": { "parents": 1, "interior": "Here" }, "fun": { "Fungible": 10_000_000_000u64 } } },
+                "ClearOrigin",
+                { "BuyExecution": {
+                    "fees": { "id": { "parents": 1, "interior": "Here" }, "fun": { "Fungible": "10000000000" } },
+                    "weight_limit": "Unlimited"
+                } },
+                { "DepositReserveAsset": {
+                    "assets": { "Wild": { "AllCounted": 1 } },
+                    "dest": { "parents": 1, "interior": { "X1": { "Parachain": 2034 } } },
+                    "xcm": { "DepositAsset": {
+                        "assets": { "Wild": "All" },
+                        "beneficiary": { "parents": 0, "interior": { "X1": { "AccountId32": { "network": "None", "id": alice } } } }
+                    } }
+                } }
+            ]
+        }));
+
+        assert_eq!(message.version.as_deref(), Some("V4"));
+        assert_eq!(message.instructions.len(), 4);
+        assert_eq!(message.assets()[1].amount, Some(10_000_000_000));
+        let reserve = &message.instructions[3];
+        assert_eq!(reserve.target.as_deref(), Some("../Parachain(2034)"));
+        assert_eq!(reserve.nested.len(), 1);
+
+        assert_eq!(
+            message.to_string(),
+            format!(
+                "XCM V4\n  WithdrawAsset 10000000000 of ..\n  ClearOrigin\n  \
+                 BuyExecution 10000000000 of ..\n  \
+                 DepositReserveAsset All (up to 1) -> ../Parachain(2034)\n    \
+                 DepositAsset All -> AccountId32({})",
+                alice
+            )
+        );
+    }
+
+    #[test]
+    fn test_v3_assets_and_unversioned_messages() {
+        let message = XcmMessage::from_json(&json!({
+            "ReserveAssetDeposited": [
+                { "id": { "Concrete": { "parents": 0, "interior": { "X2": [{ "PalletInstance": 50 }, { "GeneralIndex": 1984 }] } } },
+                  "fun": { "Fungible": 5 } },
+                { "id": { "Concrete": { "parents": 1, "interior": "Here" } }, "fun": { "Fungible": 7 } }
+            ]
+        }));
+        assert_eq!(message.version, None);
+        assert_eq!(
+            message.instructions[0].assets,
+            vec![
+                AssetAmount {
+                    asset: "PalletInstance(50)/GeneralIndex(1984)".to_string(),
+                    amount: Some(5),
+                },
+                AssetAmount {
+                    asset: "..".to_string(),
+                    amount: Some(7),
+                },
+            ]
+        );
+    }
+}
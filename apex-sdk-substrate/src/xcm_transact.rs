@@ -0,0 +1,408 @@
+//! Remote execution of calls through XCM `Transact`
+//!
+//! [`RemoteCall`] wraps a call encoded for another chain into an XCM
+//! message: it withdraws and spends the fee asset, dispatches the call with
+//! the chosen [`OriginKind`] and weight, optionally refunds unused fees and
+//! ends with a `SetTopic`, so the message carries a known id.
+//! [`TransactSender`] sends it with the XCM pallet and then looks for that id
+//! in the destination chain's message queue events to report whether the
+//! call executed:
+//!
+//! ```rust,no_run
+//! use apex_sdk_substrate::{Junction, MultiLocation, OriginKind, RemoteCall, TransactSender, Weight};
+//! # use apex_sdk_substrate::{SubstrateAdapter, Wallet};
+//!
+//! # async fn example(relay: &SubstrateAdapter, asset_hub: &SubstrateAdapter, root: &Wallet, call: Vec<u8>) -> Result<(), apex_sdk_substrate::Error> {
+//! // from the relay chain, AssetHub is a child
+//! let remote = RemoteCall::new(MultiLocation::new(0, vec![Junction::Parachain(1000)]), call)
+//!     .with_origin_kind(OriginKind::Superuser)
+//!     .with_weight(Weight { ref_time: 2_000_000_000, proof_size: 100_000 });
+//!
+//! let from = asset_hub.spec_client().finalized_number().await?;
+//! TransactSender::new(relay).send(root, &remote).await?;
+//!
+//! if let Some(outcome) = TransactSender::track(asset_hub, &remote, from, from + 20).await? {
+//!     println!("executed: {} in block {}", outcome.success, outcome.block_number);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Locations are relative to the sending chain, except the fee asset and
+//! refund beneficiary, which are relative to the destination. Without fees
+//! the message asks for unpaid execution, which destinations only grant to
+//! trusted origins such as the relay chain.
+
+use crate::block_limits::Weight;
+use crate::event_query::{EventQuery, MatchedEvent};
+use crate::template::json_to_value;
+use crate::xcm::{MultiLocation, XcmVersion};
+use crate::{Error, Result, SubstrateAdapter, Wallet};
+use serde_json::{json, Value as JsonValue};
+use subxt::dynamic::Value;
+use tracing::info;
+
+/// Weight reserved for the call when none is set
+pub const DEFAULT_TRANSACT_WEIGHT: Weight = Weight {
+    ref_time: 1_000_000_000,
+    proof_size: 65_536,
+};
+
+/// Origin the destination dispatches the call with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OriginKind {
+    /// The destination's native interpretation of the origin
+    Native,
+    /// Signed by the sovereign account of the origin
+    #[default]
+    SovereignAccount,
+    /// Root; only trusted origins get it
+    Superuser,
+    /// The XCM origin itself, for pallets that accept it
+    Xcm,
+}
+
+impl OriginKind {
+    fn name(&self) -> &'static str {
+        match self {
+            OriginKind::Native => "Native",
+            OriginKind::SovereignAccount => "SovereignAccount",
+            OriginKind::Superuser => "Superuser",
+            OriginKind::Xcm => "Xcm",
+        }
+    }
+}
+
+/// A call to execute on another chain
+#[derive(Debug, Clone)]
+pub struct RemoteCall {
+    dest: MultiLocation,
+    call: Vec<u8>,
+    origin_kind: OriginKind,
+    weight: Weight,
+    fees: Option<(MultiLocation, u128)>,
+    refund_to: Option<MultiLocation>,
+    topic: [u8; 32],
+    version: XcmVersion,
+}
+
+impl RemoteCall {
+    /// `call`, encoded for the runtime at `dest`, with a random topic
+    pub fn new(dest: MultiLocation, call: Vec<u8>) -> Self {
+        Self {
+            dest,
+            call,
+            origin_kind: OriginKind::default(),
+            weight: DEFAULT_TRANSACT_WEIGHT,
+            fees: None,
+            refund_to: None,
+            topic: rand::random(),
+            version: XcmVersion::V4,
+        }
+    }
+
+    /// Dispatch origin on the destination
+    pub fn with_origin_kind(mut self, origin_kind: OriginKind) -> Self {
+        self.origin_kind = origin_kind;
+        self
+    }
+
+    /// Most weight the call may use
+    pub fn with_weight(mut self, weight: Weight) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Pay for execution with up to `amount` of `asset`, a location
+    /// relative to the destination
+    pub fn with_fees(mut self, asset: MultiLocation, amount: u128) -> Self {
+        self.fees = Some((asset, amount));
+        self
+    }
+
+    /// Deposit unused fees to `beneficiary`, a location relative to the
+    /// destination
+    pub fn with_refund_to(mut self, beneficiary: MultiLocation) -> Self {
+        self.refund_to = Some(beneficiary);
+        self
+    }
+
+    /// Message id to correlate the execution by, instead of a random one
+    pub fn with_topic(mut self, topic: [u8; 32]) -> Self {
+        self.topic = topic;
+        self
+    }
+
+    /// XCM version to build the message in; v3 or later
+    pub fn with_version(mut self, version: XcmVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Message id the destination reports the execution under
+    pub fn topic(&self) -> [u8; 32] {
+        self.topic
+    }
+
+    /// Fee amount withdrawn on the destination, if paid
+    pub(crate) fn fee_amount(&self) -> Option<u128> {
+        self.fees.as_ref().map(|(_, amount)| *amount)
+    }
+
+    /// Destination as a `VersionedLocation`
+    pub fn dest_json(&self) -> Result<JsonValue> {
+        Ok(json!({ self.version_name()?: self.dest.to_json() }))
+    }
+
+    /// The message as a `VersionedXcm`
+    pub fn message_json(&self) -> Result<JsonValue> {
+        let version = self.version_name()?;
+        let mut instructions = Vec::new();
+        match &self.fees {
+            Some((asset, amount)) => {
+                let fees = self.asset_json(asset, *amount);
+                instructions.push(json!({ "WithdrawAsset": [fees.clone()] }));
+                instructions.push(json!({
+                    "BuyExecution": { "fees": fees, "weight_limit": "Unlimited" }
+                }));
+            }
+            None => instructions.push(json!({
+                "UnpaidExecution": { "weight_limit": "Unlimited", "check_origin": "None" }
+            })),
+        }
+        instructions.push(json!({
+            "Transact": {
+                "origin_kind": self.origin_kind.name(),
+                "require_weight_at_most": {
+                    "ref_time": self.weight.ref_time,
+                    "proof_size": self.weight.proof_size,
+                },
+                "call": { "encoded": format!("0x{}", hex::encode(&self.call)) },
+            }
+        }));
+        if let (Some(beneficiary), Some(_)) = (&self.refund_to, &self.fees) {
+            instructions.push(json!("RefundSurplus"));
+            instructions.push(json!({
+                "DepositAsset": {
+                    "assets": { "Wild": { "AllCounted": 1 } },
+                    "beneficiary": beneficiary.to_json(),
+                }
+            }));
+        }
+        instructions.push(json!({ "SetTopic": format!("0x{}", hex::encode(self.topic)) }));
+        Ok(json!({ version: instructions }))
+    }
+
+    fn asset_json(&self, asset: &MultiLocation, amount: u128) -> JsonValue {
+        let id = match self.version {
+            XcmVersion::V3 => json!({ "Concrete": asset.to_json() }),
+            _ => asset.to_json(),
+        };
+        let amount = match u64::try_from(amount) {
+            Ok(amount) => json!(amount),
+            Err(_) => json!(amount.to_string()),
+        };
+        json!({ "id": id, "fun": { "Fungible": amount } })
+    }
+
+    fn version_name(&self) -> Result<&'static str> {
+        match self.version {
+            XcmVersion::V2 => Err(Error::Encoding(
+                "XCM Transact needs XCM v3 or later".to_string(),
+            )),
+            XcmVersion::V3 => Ok("V3"),
+            XcmVersion::V4 => Ok("V4"),
+        }
+    }
+}
+
+/// Result of a remote call on its destination
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteOutcome {
+    /// Destination block that processed the message
+    pub block_number: u64,
+    /// Whether the message executed without error
+    pub success: bool,
+    /// Weight the message used, if reported
+    pub weight_used: Option<Weight>,
+    /// The event reporting the outcome
+    pub event: MatchedEvent,
+}
+
+impl RemoteOutcome {
+    /// Outcome reported by a message queue event; `None` for other events
+    pub fn from_event(event: &MatchedEvent) -> Option<Self> {
+        let success = match (event.pallet.as_str(), event.variant.as_str()) {
+            ("MessageQueue", "Processed") => event.fields["success"].as_bool()?,
+            ("MessageQueue", "ProcessingFailed") => false,
+            ("XcmpQueue" | "DmpQueue", "Success") => true,
+            ("XcmpQueue" | "DmpQueue", "Fail") => false,
+            ("DmpQueue", "ExecutedDownward") => event.fields["outcome"].get("Complete").is_some(),
+            _ => return None,
+        };
+        let weight_used = ["weight_used", "weight"]
+            .iter()
+            .find_map(|field| event.fields.get(field))
+            .and_then(Weight::from_json);
+        Some(Self {
+            block_number: event.block_number,
+            success,
+            weight_used,
+            event: event.clone(),
+        })
+    }
+}
+
+/// Sends [`RemoteCall`]s from the adapter's chain
+pub struct TransactSender<'a> {
+    adapter: &'a SubstrateAdapter,
+}
+
+impl<'a> TransactSender<'a> {
+    /// Sender on the adapter's chain
+    pub fn new(adapter: &'a SubstrateAdapter) -> Self {
+        Self { adapter }
+    }
+
+    /// Arguments of the XCM pallet's `send` call for `remote`
+    pub fn send_args(&self, remote: &RemoteCall) -> Result<Vec<Value>> {
+        let pallet = self.xcm_pallet()?;
+        let metadata = self.adapter.client().metadata();
+        let fields = metadata
+            .pallet_by_name(pallet)
+            .and_then(|pallet| pallet.call_variant_by_name("send"))
+            .map(|call| {
+                call.fields
+                    .iter()
+                    .map(|field| field.ty.id)
+                    .collect::<Vec<_>>()
+            })
+            .ok_or_else(|| Error::Metadata(format!("Runtime has no {}::send", pallet)))?;
+        let [dest_ty, message_ty] = fields[..] else {
+            return Err(Error::Metadata(format!(
+                "Unexpected {}::send arguments",
+                pallet
+            )));
+        };
+        Ok(vec![
+            json_to_value(&remote.dest_json()?, dest_ty, metadata.types())?,
+            json_to_value(&remote.message_json()?, message_ty, metadata.types())?,
+        ])
+    }
+
+    /// Send `remote`, signed by `wallet`; returns the transaction hash
+    ///
+    /// Signed origins are descended into the message, so the destination
+    /// sees the wallet's account on this chain as origin.
+    pub async fn send(&self, wallet: &Wallet, remote: &RemoteCall) -> Result<String> {
+        info!(
+            "Sending XCM Transact to {:?} with topic 0x{}",
+            remote.dest,
+            hex::encode(remote.topic)
+        );
+        let args = self.send_args(remote)?;
+        self.adapter
+            .transaction_executor()
+            .submit_call(wallet, self.xcm_pallet()?, "send", args)
+            .await
+    }
+
+    /// Outcome of `remote` on its destination, searched in blocks
+    /// `from..=to` of `dest`; `None` if not processed yet
+    pub async fn track(
+        dest: &SubstrateAdapter,
+        remote: &RemoteCall,
+        from: u64,
+        to: u64,
+    ) -> Result<Option<RemoteOutcome>> {
+        // the topic is matched like an account id held by the event
+        let events = EventQuery::new()
+            .involving(format!("0x{}", hex::encode(remote.topic)))
+            .between(from, to)
+            .run(dest)
+            .await?;
+        Ok(events.iter().find_map(RemoteOutcome::from_event))
+    }
+
+    fn xcm_pallet(&self) -> Result<&'static str> {
+        let pallets = self.adapter.pallets();
+        match pallets.first_of(&["PolkadotXcm", "XcmPallet"]) {
+            Some(pallet) => Ok(pallet),
+            None => pallets.require("PolkadotXcm").map(|_| "PolkadotXcm"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xcm::Junction;
+    use crate::xcm_decode::XcmMessage;
+
+    #[test]
+    fn test_message_with_fees_refund_and_topic() {
+        let asset_hub = MultiLocation::new(1, vec![Junction::Parachain(1000)]);
+        let sovereign = MultiLocation::parachain(2034);
+        let remote = RemoteCall::new(asset_hub, vec![0x00, 0x07, 0x04, 0x2a])
+            .with_weight(Weight {
+                ref_time: 2_000_000_000,
+                proof_size: 100_000,
+            })
+            .with_fees(MultiLocation::parent(), 1_000_000_000)
+            .with_refund_to(sovereign)
+            .with_topic([7; 32]);
+
+        let message = remote.message_json().unwrap();
+        assert_eq!(
+            message["V4"][2]["Transact"]["call"]["encoded"],
+            json!("0x0007042a")
+        );
+        assert_eq!(
+            remote.dest_json().unwrap(),
+            json!({ "V4": { "parents": 1, "interior": { "X1": { "Parachain": 1000 } } } })
+        );
+
+        let rendered = XcmMessage::from_json(&message).to_string();
+        assert_eq!(
+            rendered,
+            "XCM V4\n  WithdrawAsset 1000000000 of ..\n  BuyExecution 1000000000 of ..\n  \
+             Transact\n  RefundSurplus\n  DepositAsset All (up to 1) -> ../Parachain(2034)\n  \
+             SetTopic"
+        );
+
+        let unpaid = RemoteCall::new(MultiLocation::parachain(1000), Vec::new())
+            .with_refund_to(MultiLocation::parent())
+            .with_version(XcmVersion::V3);
+        let message = unpaid.message_json().unwrap();
+        assert!(message["V3"][0].get("UnpaidExecution").is_some());
+        assert_eq!(message["V3"].as_array().unwrap().len(), 3);
+        assert!(unpaid.with_version(XcmVersion::V2).message_json().is_err());
+    }
+
+    #[test]
+    fn test_outcome_from_message_queue_event() {
+        let event = MatchedEvent {
+            block_number: 42,
+            block_hash: format!("0x{}", "00".repeat(32)),
+            event_index: 1,
+            extrinsic_index: None,
+            pallet: "MessageQueue".to_string(),
+            variant: "Processed".to_string(),
+            fields: json!({
+                "id": format!("0x{}", "07".repeat(32)),
+                "origin": { "Sibling": 2034 },
+                "weight_used": { "ref_time": 1_200_000, "proof_size": 3_500 },
+                "success": true,
+            }),
+        };
+        let outcome = RemoteOutcome::from_event(&event).unwrap();
+        assert!(outcome.success);
+        assert_eq!(
+            outcome.weight_used,
+            Some(Weight {
+                ref_time: 1_200_000,
+                proof_size: 3_500
+            })
+        );
+    }
+}
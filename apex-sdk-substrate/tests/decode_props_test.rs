@@ -0,0 +1,102 @@
+//! Property tests for decoding untrusted input
+//!
+//! Runs the [`apex_sdk_substrate::fuzzing`] hooks on generated input. For
+//! longer runs with coverage guidance use the targets in `fuzz/`:
+//! `make fuzz TARGET=uos_payload`.
+
+use apex_sdk_substrate::derivation::ss58;
+use apex_sdk_substrate::fuzzing;
+use apex_sdk_substrate::golden::load_metadata;
+use apex_sdk_substrate::SigningRequest;
+use parity_scale_codec::Encode;
+use proptest::prelude::*;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use subxt::Metadata;
+
+/// Metadata recorded next to the golden fixtures
+fn golden_metadata() -> &'static [Metadata] {
+    static METADATA: OnceLock<Vec<Metadata>> = OnceLock::new();
+    METADATA.get_or_init(|| {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+        std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "scale"))
+                    .filter_map(|path| load_metadata(&path).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    })
+}
+
+/// Well-formed UOS transaction payload
+fn uos_payload() -> impl Strategy<Value = Vec<u8>> {
+    (
+        0u8..2,
+        prop::array::uniform32(any::<u8>()),
+        prop::collection::vec(any::<u8>(), 0..400),
+        prop::collection::vec(any::<u8>(), 0..64),
+        prop::array::uniform32(any::<u8>()),
+    )
+        .prop_map(|(crypto, public_key, call, extensions, genesis)| {
+            let mut payload = vec![0x53, crypto, 0x02];
+            payload.extend_from_slice(&public_key);
+            payload.extend(call.encode());
+            payload.extend_from_slice(&extensions);
+            payload.extend_from_slice(&genesis);
+            payload
+        })
+}
+
+proptest! {
+    #[test]
+    fn test_uos_payload_arbitrary_bytes(data in prop::collection::vec(any::<u8>(), 0..512)) {
+        fuzzing::uos_payload(&data);
+    }
+
+    #[test]
+    fn test_uos_payload_roundtrip(payload in uos_payload()) {
+        let request = SigningRequest::from_uos(&payload).expect("well-formed payload");
+        prop_assert_eq!(request.to_uos(), payload.clone());
+        fuzzing::uos_payload(&payload);
+    }
+
+    #[test]
+    fn test_uos_frames_arbitrary_bytes(data in prop::collection::vec(any::<u8>(), 0..512)) {
+        fuzzing::uos_frames(&data);
+    }
+
+    #[test]
+    fn test_frames_roundtrip(
+        payload in prop::collection::vec(any::<u8>(), 0..2048),
+        frame_size in 1usize..600,
+    ) {
+        fuzzing::frames_roundtrip(&payload, frame_size);
+    }
+
+    #[test]
+    fn test_address_arbitrary_strings(address in "\\PC{0,64}") {
+        fuzzing::address(&address);
+    }
+
+    #[test]
+    fn test_address_roundtrip(account in prop::array::uniform32(any::<u8>()), prefix in 0u16..16384) {
+        fuzzing::address(&ss58(&account, prefix));
+        fuzzing::address(&format!("0x{}", hex::encode(account)));
+        fuzzing::address(&format!("0x{}", hex::encode(&account[..20])));
+    }
+
+    #[test]
+    fn test_storage_entry_arbitrary_bytes(data in prop::collection::vec(any::<u8>(), 0..256)) {
+        fuzzing::storage_entry(&data);
+    }
+
+    #[test]
+    fn test_extrinsic_arbitrary_bytes(data in prop::collection::vec(any::<u8>(), 0..512)) {
+        for metadata in golden_metadata() {
+            fuzzing::extrinsic(&data, metadata);
+        }
+    }
+}
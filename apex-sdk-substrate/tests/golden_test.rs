@@ -0,0 +1,45 @@
+//! Golden decoding tests
+//!
+//! Checks that every fixture in `tests/golden` still decodes to its recorded
+//! output. Record new fixtures from Westend with:
+//! `APEX_GOLDEN_BLOCKS=24000000,24000001 cargo test --test golden_test -- --ignored`
+//!
+//! After an intended change in decoding output, accept it with
+//! `APEX_UPDATE_GOLDEN=1 cargo test --test golden_test`.
+
+use apex_sdk_substrate::golden::{check_dir, GoldenRecorder};
+use apex_sdk_substrate::{ChainConfig, SubstrateAdapter};
+use std::path::PathBuf;
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+#[test]
+fn test_golden_fixtures_decode_as_recorded() {
+    let checked = check_dir(&golden_dir()).unwrap_or_else(|e| panic!("{}", e));
+    println!("{} golden fixtures checked", checked);
+}
+
+/// Record fixtures for the blocks listed in `APEX_GOLDEN_BLOCKS`
+#[tokio::test]
+#[ignore] // Requires network connection
+async fn record_westend_fixtures() {
+    let blocks = std::env::var("APEX_GOLDEN_BLOCKS").unwrap_or_default();
+    let adapter = SubstrateAdapter::connect_with_config(ChainConfig::westend())
+        .await
+        .expect("Failed to connect to Westend");
+    let recorder = GoldenRecorder::new(&adapter);
+
+    for block in blocks
+        .split(',')
+        .filter_map(|block| block.trim().parse().ok())
+    {
+        let path = recorder
+            .record_to(&golden_dir(), block)
+            .await
+            .expect("Failed to record block");
+        println!("recorded {}", path.display());
+    }
+    check_dir(&golden_dir()).expect("Recorded fixtures do not decode");
+}
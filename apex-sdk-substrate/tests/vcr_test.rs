@@ -0,0 +1,57 @@
+//! BlockQuery tests replayed from recorded RPC traffic
+//!
+//! Replays `tests/cassettes/westend_block_query.json` without a network.
+//! Re-record it against Westend with:
+//! `APEX_VCR=record cargo test --test vcr_test`
+
+use apex_sdk_substrate::{ChainConfig, Vcr, VcrMode};
+use std::path::PathBuf;
+
+/// Block the cassette was recorded for
+const BLOCK: u64 = 24_000_000;
+
+fn cassette(name: &str) -> Option<Vcr> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/cassettes")
+        .join(name);
+    let vcr = Vcr::new(path);
+    if vcr.mode() == VcrMode::Replay && !vcr.path().exists() {
+        println!("{} not recorded, skipping", vcr.path().display());
+        return None;
+    }
+    Some(vcr)
+}
+
+#[tokio::test]
+async fn test_block_query_replay() {
+    let Some(vcr) = cassette("westend_block_query.json") else {
+        return;
+    };
+    let adapter = vcr
+        .connect(ChainConfig::westend())
+        .await
+        .expect("Failed to connect");
+    let block_query = adapter.block_query();
+
+    let block = block_query
+        .get_block_by_number(BLOCK)
+        .await
+        .expect("Failed to get block");
+    assert_eq!(block.number, BLOCK);
+
+    let by_hash = block_query
+        .get_block_by_hash(&block.hash)
+        .await
+        .expect("Failed to get block by hash");
+    assert_eq!(by_hash.number, BLOCK);
+    assert_eq!(by_hash.parent_hash, block.parent_hash);
+
+    let detailed = adapter
+        .get_block_detailed(BLOCK)
+        .await
+        .expect("Failed to get detailed block");
+    assert_eq!(detailed.basic.number, BLOCK);
+    assert!(!detailed.events.is_empty());
+
+    vcr.save().expect("Failed to save cassette");
+}
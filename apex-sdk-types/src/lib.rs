@@ -533,6 +533,22 @@ pub fn extract_ss58_prefix(address: &str) -> Option<u16> {
     Some(network_id)
 }
 
+/// Decode the 32-byte account id of an SS58 address, whatever its network
+/// prefix
+///
+/// Returns None for invalid addresses and addresses of other lengths.
+pub fn ss58_account_id(address: &str) -> Option<[u8; 32]> {
+    if !validate_ss58_address(address) {
+        return None;
+    }
+    let decoded = bs58::decode(address).into_vec().ok()?;
+    let prefix_len = if decoded[0] & 0b01000000 == 0 { 1 } else { 2 };
+    if decoded.len() != prefix_len + 32 + 2 {
+        return None;
+    }
+    decoded[prefix_len..prefix_len + 32].try_into().ok()
+}
+
 /// Generic address type for different chains
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Address {
@@ -1187,6 +1203,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ss58_account_id() {
+        let alice = ss58_account_id("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").unwrap();
+        assert_eq!(alice[..4], [0xd4, 0x35, 0x93, 0xc7]);
+        // the same account with the Polkadot prefix
+        assert_eq!(
+            ss58_account_id("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5"),
+            Some(alice)
+        );
+        assert!(ss58_account_id("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQX").is_none());
+        assert!(ss58_account_id("0xd43593c715fdd31c61141abd04a99fd6822c8558").is_none());
+    }
+
     #[test]
     fn test_substrate_ss58_validation_invalid_addresses() {
         // Invalid SS58 addresses
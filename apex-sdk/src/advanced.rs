@@ -1,11 +1,15 @@
 //! Advanced features and utilities.
 
 use crate::sdk::ApexSDK;
+use crate::stream::{bounded_stream, ItemSource, LaggingReceiver, StreamConfig, StreamSender};
 use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::sync::broadcast;
 use tokio_util::sync::CancellationToken;
 
+/// Capacity of the channels behind the deprecated broadcast constructors
+const BROADCAST_CAPACITY: usize = 100;
+
 /// Block information
 #[derive(Debug, Clone)]
 pub struct BlockInfo {
@@ -16,18 +20,43 @@ pub struct BlockInfo {
 
 /// Block subscription for real-time updates
 pub struct BlockSubscription {
-    receiver: broadcast::Receiver<BlockInfo>,
+    receiver: Box<dyn ItemSource<BlockInfo>>,
     cancellation_token: CancellationToken,
 }
 
 impl BlockSubscription {
-    /// Create a new block subscription with cancellation support
+    /// Create a new block subscription fed by a broadcast channel
+    ///
+    /// Blocks the subscriber falls behind on are skipped and counted in
+    /// [`dropped_count`](Self::dropped_count).
+    #[deprecated(
+        since = "0.1.5",
+        note = "use `bounded` or `with_config`, which apply backpressure instead of skipping"
+    )]
     pub fn new() -> (broadcast::Sender<BlockInfo>, CancellationToken, Self) {
-        let (sender, receiver) = broadcast::channel(100);
+        let (sender, receiver) = broadcast::channel(BROADCAST_CAPACITY);
+        let (cancellation_token, subscription) =
+            Self::from_source(Box::new(LaggingReceiver::new(receiver)));
+        (sender, cancellation_token, subscription)
+    }
+
+    /// Create a new block subscription with cancellation support and the
+    /// default buffer configuration
+    pub fn bounded() -> (StreamSender<BlockInfo>, CancellationToken, Self) {
+        Self::with_config(StreamConfig::default())
+    }
+
+    /// Create a new block subscription with a custom buffer configuration
+    pub fn with_config(config: StreamConfig) -> (StreamSender<BlockInfo>, CancellationToken, Self) {
+        let (sender, receiver) = bounded_stream(config);
+        let (cancellation_token, subscription) = Self::from_source(Box::new(receiver));
+        (sender, cancellation_token, subscription)
+    }
+
+    fn from_source(receiver: Box<dyn ItemSource<BlockInfo>>) -> (CancellationToken, Self) {
         let cancellation_token = CancellationToken::new();
         let token_clone = cancellation_token.clone();
         (
-            sender,
             cancellation_token,
             Self {
                 receiver,
@@ -39,7 +68,7 @@ impl BlockSubscription {
     /// Get the next block from the subscription
     pub async fn next(&mut self) -> Option<BlockInfo> {
         tokio::select! {
-            result = self.receiver.recv() => result.ok(),
+            result = self.receiver.next_item() => result,
             _ = self.cancellation_token.cancelled() => None,
         }
     }
@@ -54,22 +83,57 @@ impl BlockSubscription {
     pub fn is_stopped(&self) -> bool {
         self.cancellation_token.is_cancelled()
     }
+
+    /// Number of blocks dropped because the consumer fell behind
+    pub fn dropped_count(&self) -> u64 {
+        self.receiver.dropped_count()
+    }
+
+    /// Buffered blocks and cancellation token, for [`crate::v2::Subscription`]
+    pub(crate) fn into_parts(self) -> (Box<dyn ItemSource<BlockInfo>>, CancellationToken) {
+        (self.receiver, self.cancellation_token)
+    }
 }
 
 /// Event subscription for blockchain events
 pub struct EventSubscription {
-    receiver: broadcast::Receiver<String>,
+    receiver: Box<dyn ItemSource<String>>,
     cancellation_token: CancellationToken,
 }
 
 impl EventSubscription {
-    /// Create a new event subscription with cancellation support
+    /// Create a new event subscription fed by a broadcast channel
+    ///
+    /// Events the subscriber falls behind on are skipped and counted in
+    /// [`dropped_count`](Self::dropped_count).
+    #[deprecated(
+        since = "0.1.5",
+        note = "use `bounded` or `with_config`, which apply backpressure instead of skipping"
+    )]
     pub fn new() -> (broadcast::Sender<String>, CancellationToken, Self) {
-        let (sender, receiver) = broadcast::channel(100);
+        let (sender, receiver) = broadcast::channel(BROADCAST_CAPACITY);
+        let (cancellation_token, subscription) =
+            Self::from_source(Box::new(LaggingReceiver::new(receiver)));
+        (sender, cancellation_token, subscription)
+    }
+
+    /// Create a new event subscription with cancellation support and the
+    /// default buffer configuration
+    pub fn bounded() -> (StreamSender<String>, CancellationToken, Self) {
+        Self::with_config(StreamConfig::default())
+    }
+
+    /// Create a new event subscription with a custom buffer configuration
+    pub fn with_config(config: StreamConfig) -> (StreamSender<String>, CancellationToken, Self) {
+        let (sender, receiver) = bounded_stream(config);
+        let (cancellation_token, subscription) = Self::from_source(Box::new(receiver));
+        (sender, cancellation_token, subscription)
+    }
+
+    fn from_source(receiver: Box<dyn ItemSource<String>>) -> (CancellationToken, Self) {
         let cancellation_token = CancellationToken::new();
         let token_clone = cancellation_token.clone();
         (
-            sender,
             cancellation_token,
             Self {
                 receiver,
@@ -81,7 +145,7 @@ impl EventSubscription {
     /// Get the next event from the subscription
     pub async fn next(&mut self) -> Option<String> {
         tokio::select! {
-            result = self.receiver.recv() => result.ok(),
+            result = self.receiver.next_item() => result,
             _ = self.cancellation_token.cancelled() => None,
         }
     }
@@ -96,6 +160,16 @@ impl EventSubscription {
     pub fn is_stopped(&self) -> bool {
         self.cancellation_token.is_cancelled()
     }
+
+    /// Number of events dropped because the consumer fell behind
+    pub fn dropped_count(&self) -> u64 {
+        self.receiver.dropped_count()
+    }
+
+    /// Buffered events and cancellation token, for [`crate::v2::Subscription`]
+    pub(crate) fn into_parts(self) -> (Box<dyn ItemSource<String>>, CancellationToken) {
+        (self.receiver, self.cancellation_token)
+    }
 }
 
 /// Transaction batch for executing multiple transactions
@@ -304,6 +378,7 @@ impl ParallelExecutor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::stream::{OverflowPolicy, StreamSendError};
     use crate::transaction::Transaction;
 
     #[test]
@@ -347,7 +422,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_block_subscription_stop() {
-        let (_sender, cancellation_token, mut subscription) = BlockSubscription::new();
+        let (_sender, cancellation_token, mut subscription) = BlockSubscription::bounded();
 
         assert!(!subscription.is_stopped());
 
@@ -363,15 +438,17 @@ mod tests {
 
     #[tokio::test]
     async fn test_block_subscription_receives_blocks() {
-        let (sender, _cancellation_token, mut subscription) = BlockSubscription::new();
+        let (sender, _cancellation_token, mut subscription) = BlockSubscription::bounded();
 
         let send_task = tokio::spawn(async move {
             tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-            let _ = sender.send(BlockInfo {
-                number: 100,
-                hash: "0xabc123".to_string(),
-                timestamp: 1234567890,
-            });
+            let _ = sender
+                .send(BlockInfo {
+                    number: 100,
+                    hash: "0xabc123".to_string(),
+                    timestamp: 1234567890,
+                })
+                .await;
         });
 
         let block = subscription.next().await;
@@ -385,7 +462,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_event_subscription_stop() {
-        let (_sender, cancellation_token, mut subscription) = EventSubscription::new();
+        let (_sender, cancellation_token, mut subscription) = EventSubscription::bounded();
 
         assert!(!subscription.is_stopped());
 
@@ -400,11 +477,11 @@ mod tests {
 
     #[tokio::test]
     async fn test_event_subscription_receives_events() {
-        let (sender, _cancellation_token, mut subscription) = EventSubscription::new();
+        let (sender, _cancellation_token, mut subscription) = EventSubscription::bounded();
 
         let send_task = tokio::spawn(async move {
             tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-            let _ = sender.send("TestEvent".to_string());
+            let _ = sender.send("TestEvent".to_string()).await;
         });
 
         let event = subscription.next().await;
@@ -416,16 +493,18 @@ mod tests {
 
     #[tokio::test]
     async fn test_subscription_multiple_events() {
-        let (sender, _cancellation_token, mut subscription) = BlockSubscription::new();
+        let (sender, _cancellation_token, mut subscription) = BlockSubscription::bounded();
 
         let send_task = tokio::spawn(async move {
             for i in 0..3 {
                 tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-                let _ = sender.send(BlockInfo {
-                    number: i,
-                    hash: format!("0x{:x}", i),
-                    timestamp: 1000000 + i,
-                });
+                let _ = sender
+                    .send(BlockInfo {
+                        number: i,
+                        hash: format!("0x{:x}", i),
+                        timestamp: 1000000 + i,
+                    })
+                    .await;
             }
         });
 
@@ -446,7 +525,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_subscription_cancellation_via_token() {
-        let (sender, cancellation_token, mut subscription) = BlockSubscription::new();
+        let (sender, cancellation_token, mut subscription) = BlockSubscription::bounded();
 
         let send_task = tokio::spawn(async move {
             for i in 0..10 {
@@ -457,6 +536,7 @@ mod tests {
                         hash: format!("0x{:x}", i),
                         timestamp: 1000000 + i,
                     })
+                    .await
                     .is_err()
                 {
                     break; // All receivers dropped
@@ -480,23 +560,27 @@ mod tests {
 
     #[tokio::test]
     async fn test_multiple_subscribers() {
-        let (sender, _token1, mut sub1) = BlockSubscription::new();
-        let (sender2, _token2, mut sub2) = BlockSubscription::new();
+        let (sender, _token1, mut sub1) = BlockSubscription::bounded();
+        let (sender2, _token2, mut sub2) = BlockSubscription::bounded();
 
         let task1 = tokio::spawn(async move {
-            let _ = sender.send(BlockInfo {
-                number: 100,
-                hash: "0xabc".to_string(),
-                timestamp: 2000000,
-            });
+            let _ = sender
+                .send(BlockInfo {
+                    number: 100,
+                    hash: "0xabc".to_string(),
+                    timestamp: 2000000,
+                })
+                .await;
         });
 
         let task2 = tokio::spawn(async move {
-            let _ = sender2.send(BlockInfo {
-                number: 200,
-                hash: "0xdef".to_string(),
-                timestamp: 3000000,
-            });
+            let _ = sender2
+                .send(BlockInfo {
+                    number: 200,
+                    hash: "0xdef".to_string(),
+                    timestamp: 3000000,
+                })
+                .await;
         });
 
         let block1 = sub1.next().await;
@@ -513,7 +597,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_subscription_timeout_behavior() {
-        let (_sender, _token, mut subscription) = EventSubscription::new();
+        let (_sender, _token, mut subscription) = EventSubscription::bounded();
 
         let result =
             tokio::time::timeout(tokio::time::Duration::from_millis(100), subscription.next())
@@ -524,12 +608,73 @@ mod tests {
 
     #[tokio::test]
     async fn test_subscription_drop_handling() {
-        let (sender, _token, subscription) = EventSubscription::new();
+        let (sender, _token, subscription) = EventSubscription::bounded();
 
         drop(subscription);
 
-        let send_result = sender.send("test".to_string());
-        assert_eq!(send_result.err().unwrap().0, "test");
+        let send_result = sender.send("test".to_string()).await;
+        assert_eq!(
+            send_result,
+            Err(StreamSendError::Closed("test".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscription_drops_oldest_when_full() {
+        let config = StreamConfig::new()
+            .with_capacity(2)
+            .with_overflow_policy(OverflowPolicy::DropOldest);
+        let (sender, _token, mut subscription) = EventSubscription::with_config(config);
+
+        for event in ["first", "second", "third"] {
+            sender.send(event.to_string()).await.unwrap();
+        }
+
+        assert_eq!(subscription.dropped_count(), 1);
+        assert_eq!(subscription.next().await.as_deref(), Some("second"));
+        assert_eq!(subscription.next().await.as_deref(), Some("third"));
+    }
+
+    #[tokio::test]
+    async fn test_subscription_error_policy_rejects_when_full() {
+        let config = StreamConfig::new()
+            .with_capacity(1)
+            .with_overflow_policy(OverflowPolicy::Error);
+        let (sender, _token, mut subscription) = BlockSubscription::with_config(config);
+
+        let block = |number| BlockInfo {
+            number,
+            hash: format!("0x{:x}", number),
+            timestamp: 0,
+        };
+
+        sender.send(block(1)).await.unwrap();
+        let rejected = sender.send(block(2)).await.unwrap_err();
+        assert!(matches!(rejected, StreamSendError::Full(_)));
+        assert_eq!(rejected.into_inner().number, 2);
+
+        assert_eq!(subscription.next().await.unwrap().number, 1);
+        assert_eq!(subscription.dropped_count(), 0);
+    }
+
+    #[tokio::test]
+    #[allow(deprecated)]
+    async fn test_broadcast_subscription_reports_skipped_blocks() {
+        let (sender, _token, mut subscription) = BlockSubscription::new();
+        let block = |number| BlockInfo {
+            number,
+            hash: format!("0x{:x}", number),
+            timestamp: 0,
+        };
+
+        // tokio rounds the capacity up to a power of two
+        let capacity = BROADCAST_CAPACITY.next_power_of_two() as u64;
+        for number in 0..capacity + 5 {
+            sender.send(block(number)).unwrap();
+        }
+
+        assert_eq!(subscription.next().await.unwrap().number, 5);
+        assert_eq!(subscription.dropped_count(), 5);
     }
 
     #[test]
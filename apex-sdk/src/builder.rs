@@ -4,10 +4,14 @@ use crate::{
     error::{Error, Result},
     sdk::ApexSDK,
 };
+use apex_sdk_core::screening::AddressScreener;
+use apex_sdk_core::telemetry::Telemetry;
+#[cfg(any(feature = "substrate", feature = "evm"))]
+use apex_sdk_core::ClientConfig;
 use std::time::Duration;
 
 #[cfg(feature = "substrate")]
-use apex_sdk_substrate::SubstrateAdapter;
+use apex_sdk_substrate::{ChainConfig, RpcLayer, RpcLayers, SubstrateAdapter};
 
 #[cfg(feature = "evm")]
 use apex_sdk_evm::EvmAdapter;
@@ -40,12 +44,21 @@ pub struct ApexSDKBuilder {
     #[cfg(feature = "substrate")]
     substrate_wallet: Option<apex_sdk_substrate::Wallet>,
 
+    #[cfg(feature = "substrate")]
+    substrate_client_config: Option<ClientConfig>,
+
+    #[cfg(feature = "substrate")]
+    substrate_rpc_layers: RpcLayers,
+
     #[cfg(feature = "evm")]
     evm_endpoint: Option<String>,
 
     #[cfg(feature = "evm")]
     evm_wallet: Option<apex_sdk_evm::wallet::Wallet>,
 
+    #[cfg(feature = "evm")]
+    evm_client_config: Option<ClientConfig>,
+
     timeout: Option<Duration>,
     config: Option<crate::sdk::SdkConfig>,
 }
@@ -96,6 +109,63 @@ impl ApexSDKBuilder {
         self
     }
 
+    /// Attach API keys or custom headers to the Substrate connection.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use apex_sdk::ApexSDKBuilder;
+    /// use apex_sdk::core::ClientConfig;
+    ///
+    /// let builder = ApexSDKBuilder::new()
+    ///     .with_substrate_endpoint("wss://polkadot.api.onfinality.io/public-ws")
+    ///     .with_substrate_client_config(ClientConfig::new().with_api_key("apikey", "YOUR_KEY"));
+    /// ```
+    #[cfg(feature = "substrate")]
+    pub fn with_substrate_client_config(mut self, client_config: ClientConfig) -> Self {
+        self.substrate_client_config = Some(client_config);
+        self
+    }
+
+    /// Add a middleware layer every Substrate RPC request goes through.
+    ///
+    /// Layers wrap the connection in the order they are added, as with
+    /// [`ChainConfig::with_rpc_layer`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use apex_sdk::ApexSDKBuilder;
+    /// use apex_sdk_substrate::TracingLayer;
+    ///
+    /// let builder = ApexSDKBuilder::new()
+    ///     .with_substrate_endpoint("wss://polkadot.api.onfinality.io/public-ws")
+    ///     .with_substrate_rpc_layer(TracingLayer);
+    /// ```
+    #[cfg(feature = "substrate")]
+    pub fn with_substrate_rpc_layer(mut self, layer: impl RpcLayer + 'static) -> Self {
+        self.substrate_rpc_layers.push(layer);
+        self
+    }
+
+    /// Attach API keys or custom headers to the EVM connection.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use apex_sdk::ApexSDKBuilder;
+    /// use apex_sdk::core::ClientConfig;
+    ///
+    /// let builder = ApexSDKBuilder::new()
+    ///     .with_evm_endpoint("https://rpc.example.com")
+    ///     .with_evm_client_config(ClientConfig::new().with_bearer_token("YOUR_TOKEN"));
+    /// ```
+    #[cfg(feature = "evm")]
+    pub fn with_evm_client_config(mut self, client_config: ClientConfig) -> Self {
+        self.evm_client_config = Some(client_config);
+        self
+    }
+
     /// Configure a Substrate wallet for signing transactions.
     ///
     /// # Example
@@ -169,6 +239,7 @@ impl ApexSDKBuilder {
     ///     confirmation_strategy: ConfirmationStrategy::WaitForFinality,
     ///     confirmation_blocks: 3,
     ///     timeout_seconds: 120,
+    ///     ..Default::default()
     /// };
     /// let builder = ApexSDKBuilder::new().with_config(config);
     /// ```
@@ -196,6 +267,74 @@ impl ApexSDKBuilder {
         self.config = Some(config);
         self
     }
+
+    /// Configure buffering and backpressure for subscription streams.
+    ///
+    /// Applied to the streams of the Substrate adapter, such as resumable
+    /// block subscriptions and chain monitor alerts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use apex_sdk::{ApexSDKBuilder, OverflowPolicy, StreamConfig};
+    ///
+    /// let builder = ApexSDKBuilder::new().with_stream_config(
+    ///     StreamConfig::new()
+    ///         .with_capacity(1_000)
+    ///         .with_overflow_policy(OverflowPolicy::Block),
+    /// );
+    /// ```
+    pub fn with_stream_config(mut self, stream_config: crate::stream::StreamConfig) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.stream_config = stream_config;
+        self.config = Some(config);
+        self
+    }
+
+    /// Opt in to anonymous usage statistics.
+    ///
+    /// Telemetry is off unless configured here. Reports hold the SDK version,
+    /// the genesis hashes or chain ids of the connected chains and feature
+    /// counters, and are only sent when [`Telemetry::flush`] is called.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use apex_sdk::core::telemetry::{HttpReporter, Telemetry};
+    /// use apex_sdk::ApexSDKBuilder;
+    ///
+    /// // requires the `telemetry` feature
+    /// let builder = ApexSDKBuilder::new().with_telemetry(Telemetry::new(HttpReporter::new(
+    ///     "https://telemetry.example.com/apex",
+    /// )));
+    /// ```
+    pub fn with_telemetry(mut self, telemetry: Telemetry) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.telemetry = telemetry;
+        self.config = Some(config);
+        self
+    }
+
+    /// Screen the addresses of every transaction before it is executed
+    ///
+    /// Transactions involving a blocked address, or whose addresses cannot
+    /// be screened, fail without being signed.
+    ///
+    /// ```rust
+    /// use apex_sdk::core::screening::StaticListScreener;
+    /// use apex_sdk::ApexSDKBuilder;
+    ///
+    /// let builder = ApexSDKBuilder::new().with_address_screener(StaticListScreener::new([
+    ///     "0x8589427373D6D84E98730D7795D8f6f8731FDA16",
+    /// ]));
+    /// ```
+    pub fn with_address_screener(mut self, screener: impl AddressScreener + 'static) -> Self {
+        let mut config = self.config.unwrap_or_default();
+        config.address_screener = Some(std::sync::Arc::new(screener));
+        self.config = Some(config);
+        self
+    }
+
     /// Build the ApexSDK instance.
     ///
     /// # Errors
@@ -222,11 +361,11 @@ impl ApexSDKBuilder {
         let timeout = self.timeout.unwrap_or(Duration::from_secs(30));
 
         #[cfg(feature = "substrate")]
-        let substrate_adapter = if let Some(endpoint) = self.substrate_endpoint {
+        let substrate_adapter = if let Some(chain_config) = self.substrate_chain_config() {
             Some(
-                SubstrateAdapter::connect(&endpoint)
+                SubstrateAdapter::connect_with_config(chain_config)
                     .await
-                    .map_err(|e| Error::Connection(e.to_string()))?,
+                    .map_err(|e| Error::Connection(e.to_string()).classify_rate_limit())?,
             )
         } else {
             None
@@ -235,9 +374,12 @@ impl ApexSDKBuilder {
         #[cfg(feature = "evm")]
         let evm_adapter = if let Some(endpoint) = self.evm_endpoint {
             Some(
-                EvmAdapter::connect(&endpoint)
-                    .await
-                    .map_err(|e| Error::Connection(e.to_string()))?,
+                EvmAdapter::connect_with_client_config(
+                    &endpoint,
+                    &self.evm_client_config.unwrap_or_default(),
+                )
+                .await
+                .map_err(|e| Error::Connection(e.to_string()).classify_rate_limit())?,
             )
         } else {
             None
@@ -270,6 +412,20 @@ impl ApexSDKBuilder {
             }
         }
 
+        let config = self.config.unwrap_or_default();
+        #[cfg(feature = "substrate")]
+        if let Some(adapter) = &substrate_adapter {
+            config
+                .telemetry
+                .record_chain(&format!("{:?}", adapter.client().genesis_hash()));
+        }
+        #[cfg(feature = "evm")]
+        if let Some(adapter) = &evm_adapter {
+            config
+                .telemetry
+                .record_chain(&format!("eip155:{}", adapter.chain_id()));
+        }
+
         ApexSDK::new(
             #[cfg(feature = "substrate")]
             substrate_adapter,
@@ -280,9 +436,22 @@ impl ApexSDKBuilder {
             #[cfg(feature = "evm")]
             self.evm_wallet,
             timeout,
-            self.config.unwrap_or_default(),
+            config,
         )
     }
+
+    /// Configuration of the Substrate connection, if an endpoint is set
+    #[cfg(feature = "substrate")]
+    fn substrate_chain_config(&self) -> Option<ChainConfig> {
+        let endpoint = self.substrate_endpoint.as_deref()?;
+        let mut chain_config = ChainConfig::custom("Substrate", endpoint, 42)
+            .with_client_config(self.substrate_client_config.clone().unwrap_or_default());
+        chain_config.rpc_layers = self.substrate_rpc_layers.clone();
+        if let Some(config) = &self.config {
+            chain_config.stream = config.stream_config.clone();
+        }
+        Some(chain_config)
+    }
 }
 
 #[cfg(test)]
@@ -323,6 +492,24 @@ mod tests {
         assert_eq!(builder.substrate_endpoint, Some(endpoint.to_string()));
     }
 
+    #[cfg(feature = "substrate")]
+    #[test]
+    fn test_builder_with_substrate_rpc_layer() {
+        use apex_sdk_substrate::TracingLayer;
+
+        let builder = ApexSDKBuilder::new()
+            .with_substrate_endpoint("ws://127.0.0.1:9944")
+            .with_substrate_rpc_layer(TracingLayer)
+            .with_substrate_rpc_layer(TracingLayer);
+        let chain_config = builder.substrate_chain_config().unwrap();
+        assert_eq!(chain_config.endpoint, "ws://127.0.0.1:9944");
+        assert_eq!(chain_config.rpc_layers.len(), 2);
+        assert_eq!(chain_config.stream, crate::stream::StreamConfig::default());
+
+        let without_endpoint = ApexSDKBuilder::new().with_substrate_rpc_layer(TracingLayer);
+        assert!(without_endpoint.substrate_chain_config().is_none());
+    }
+
     #[cfg(feature = "evm")]
     #[test]
     fn test_builder_with_evm_endpoint() {
@@ -340,6 +527,37 @@ mod tests {
         assert_eq!(builder.timeout, Some(timeout));
     }
 
+    #[test]
+    fn test_builder_with_stream_config() {
+        let stream_config = crate::stream::StreamConfig::new().with_capacity(10);
+        let builder = ApexSDKBuilder::new().with_stream_config(stream_config.clone());
+
+        assert_eq!(builder.config.unwrap().stream_config, stream_config);
+    }
+
+    #[cfg(feature = "substrate")]
+    #[test]
+    fn test_builder_stream_config_reaches_substrate_adapter() {
+        let stream_config = crate::stream::StreamConfig::new()
+            .with_capacity(10)
+            .with_overflow_policy(crate::stream::OverflowPolicy::DropOldest);
+        let builder = ApexSDKBuilder::new()
+            .with_substrate_endpoint("ws://127.0.0.1:9944")
+            .with_stream_config(stream_config.clone());
+
+        assert_eq!(
+            builder.substrate_chain_config().unwrap().stream,
+            stream_config
+        );
+    }
+
+    #[test]
+    fn test_builder_telemetry_is_opt_in() {
+        let builder = ApexSDKBuilder::new();
+        assert!(builder.config.is_none());
+        assert!(!crate::sdk::SdkConfig::default().telemetry.is_enabled());
+    }
+
     #[tokio::test]
     async fn test_builder_requires_at_least_one_adapter() {
         let result = ApexSDKBuilder::new().build().await;
@@ -1,5 +1,6 @@
 //! Error types for the Apex SDK.
 
+use std::time::Duration;
 use thiserror::Error;
 
 /// Result type alias for Apex SDK operations.
@@ -32,11 +33,168 @@ pub enum Error {
     #[error("Unsupported chain: {0}")]
     UnsupportedChain(String),
 
+    /// The RPC provider throttled the request (HTTP 429 or a provider-specific
+    /// limit error)
+    #[error("Rate limited by provider: {message}{}", format_retry_after(.retry_after))]
+    RateLimited {
+        /// How long the provider asked us to wait, if it said
+        retry_after: Option<Duration>,
+        /// The provider's error message
+        message: String,
+    },
+
     /// Generic error
     #[error("Error: {0}")]
     Other(String),
 }
 
+impl Error {
+    /// How long to wait before retrying, if the provider gave a hint
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Check whether this error was caused by provider throttling
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(self, Error::RateLimited { .. })
+    }
+
+    /// Reclassify a connection, transaction or generic error as
+    /// [`Error::RateLimited`] when its message reports throttling.
+    ///
+    /// Adapter errors reach the SDK as strings, so this is applied where they are
+    /// converted into [`Error`].
+    pub fn classify_rate_limit(self) -> Self {
+        match &self {
+            Error::Connection(message) | Error::Transaction(message) | Error::Other(message) => {
+                detect_rate_limit(message)
+                    .map(|retry_after| Error::RateLimited {
+                        retry_after,
+                        message: message.clone(),
+                    })
+                    .unwrap_or(self)
+            }
+            _ => self,
+        }
+    }
+}
+
+/// Markers used by RPC providers to report throttling
+const RATE_LIMIT_MARKERS: &[&str] = &[
+    "too many requests",
+    "rate limit",
+    "rate-limit",
+    "ratelimit",
+    "request rate exceeded",
+    "compute units per second",
+    "throttled",
+];
+
+/// JSON-RPC error code for exceeded request limits (EIP-1474), which some
+/// providers send with a bare "limit exceeded" message
+const LIMIT_EXCEEDED_CODE: &str = "-32005";
+
+/// Keys after which providers put a retry delay
+const RETRY_HINT_KEYS: &[&str] = &[
+    "retry-after",
+    "retry_after",
+    "retry after",
+    "retryafter",
+    "backoff_seconds",
+    "try again in",
+];
+
+/// Detect a rate-limit error message and extract its retry hint.
+///
+/// Returns `None` if the message does not look like throttling, and
+/// `Some(retry_after)` otherwise, where `retry_after` is the parsed hint if the
+/// provider included one.
+pub fn detect_rate_limit(message: &str) -> Option<Option<Duration>> {
+    let lower = message.to_ascii_lowercase();
+    let throttled = RATE_LIMIT_MARKERS
+        .iter()
+        .any(|marker| lower.contains(marker))
+        || contains_status_429(&lower)
+        || (lower.contains("limit exceeded") && lower.contains(LIMIT_EXCEEDED_CODE));
+    if !throttled {
+        return None;
+    }
+
+    let retry_after = RETRY_HINT_KEYS.iter().find_map(|key| {
+        let start = lower.find(key)? + key.len();
+        let rest = lower[start..]
+            .trim_start_matches(|c: char| c.is_whitespace() || matches!(c, ':' | '=' | '"' | '\''));
+        parse_retry_after(rest)
+    });
+
+    Some(retry_after)
+}
+
+/// Parse a `Retry-After` value given in seconds.
+///
+/// Accepts whole or fractional seconds with an optional unit suffix
+/// (`"5"`, `"1.5s"`, `"500ms"`, `"2 seconds"`). Anything after the value is
+/// ignored. HTTP-date values are not supported and, like values too large
+/// for a [`Duration`], yield `None`.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim_start();
+    let number_len = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let amount: f64 = value[..number_len].parse().ok()?;
+
+    let unit = value[number_len..].trim_start();
+    let unit = &unit[..unit
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(unit.len())];
+    let seconds = match unit {
+        "ms" | "msec" | "msecs" | "millisecond" | "milliseconds" => amount / 1000.0,
+        "m" | "min" | "mins" | "minute" | "minutes" => amount * 60.0,
+        _ => amount,
+    };
+
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Words that mark a number right after them as an HTTP status
+const STATUS_CONTEXT: &[&str] = &["http", "status", "code"];
+
+/// Match `429` as a standalone status code right after HTTP or status
+/// context, not as part of a hash or address or as a block number, nonce or
+/// amount
+fn contains_status_429(message: &str) -> bool {
+    let bytes = message.as_bytes();
+    message.match_indices("429").any(|(index, _)| {
+        let before = index.checked_sub(1).map(|i| bytes[i]);
+        let after = bytes.get(index + 3).copied();
+        if before.is_some_and(|b| b.is_ascii_alphanumeric())
+            || after.is_some_and(|b| b.is_ascii_alphanumeric())
+        {
+            return false;
+        }
+        let context = message[..index].trim_end_matches(|c: char| {
+            c.is_whitespace() || matches!(c, ':' | '=' | '"' | '\'' | '(')
+        });
+        // the last few words, e.g. "http error", "status code" or "http/1.1"
+        let mut start = context.len().saturating_sub(12);
+        while !context.is_char_boundary(start) {
+            start += 1;
+        }
+        let context = &context[start..];
+        STATUS_CONTEXT.iter().any(|word| context.contains(word))
+    })
+}
+
+fn format_retry_after(retry_after: &Option<Duration>) -> String {
+    match retry_after {
+        Some(delay) => format!(" (retry after {:?})", delay),
+        None => String::new(),
+    }
+}
+
 impl From<anyhow::Error> for Error {
     fn from(err: anyhow::Error) -> Self {
         Error::Other(err.to_string())
@@ -104,6 +262,102 @@ mod tests {
         assert_eq!(error.to_string(), "Error: test other error");
     }
 
+    #[test]
+    fn test_rate_limited_error_display() {
+        let error = Error::RateLimited {
+            retry_after: Some(Duration::from_secs(5)),
+            message: "HTTP 429".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Rate limited by provider: HTTP 429 (retry after 5s)"
+        );
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(5)));
+
+        let error = Error::RateLimited {
+            retry_after: None,
+            message: "HTTP 429".to_string(),
+        };
+        assert_eq!(error.to_string(), "Rate limited by provider: HTTP 429");
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("5"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_retry_after("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_retry_after("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(
+            parse_retry_after("250 msec"),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            parse_retry_after("2 minutes"),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(parse_retry_after("1.5min"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_retry_after(&"9".repeat(400)), None);
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+
+    #[test]
+    fn test_detect_rate_limit() {
+        assert_eq!(
+            detect_rate_limit("HTTP error 429 Too Many Requests, Retry-After: 12"),
+            Some(Some(Duration::from_secs(12)))
+        );
+        assert_eq!(
+            detect_rate_limit(
+                r#"{"code":-32005,"message":"project ID request rate exceeded","data":{"backoff_seconds":30}}"#
+            ),
+            Some(Some(Duration::from_secs(30)))
+        );
+        assert_eq!(
+            detect_rate_limit("Rate limit reached, try again in 250ms"),
+            Some(Some(Duration::from_millis(250)))
+        );
+        assert_eq!(detect_rate_limit("status code: 429"), Some(None));
+        assert_eq!(detect_rate_limit("Too many requests"), Some(None));
+        assert_eq!(
+            detect_rate_limit("invalid address 0xd1220a0cf47c7b9be7a2e6ba89f429762e7b9adb"),
+            None
+        );
+        assert_eq!(detect_rate_limit("connection refused"), None);
+        assert_eq!(detect_rate_limit("gas limit exceeded"), None);
+        assert_eq!(detect_rate_limit("HTTP/1.1 429"), Some(None));
+        assert_eq!(detect_rate_limit(r#"{"code":429}"#), Some(None));
+        assert_eq!(
+            detect_rate_limit(r#"{"code":-32005,"message":"limit exceeded"}"#),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn test_bare_429_is_not_throttling() {
+        for message in [
+            "block 429 not found",
+            "nonce 429 too low",
+            "need 429 more",
+            "error code -32000: nonce 429 too low",
+        ] {
+            assert_eq!(detect_rate_limit(message), None, "{}", message);
+            let error = Error::Transaction(message.to_string()).classify_rate_limit();
+            assert!(matches!(error, Error::Transaction(_)), "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_classify_rate_limit() {
+        let error = Error::Connection("429 Too Many Requests".to_string()).classify_rate_limit();
+        assert!(error.is_rate_limited());
+        assert!(error.to_string().contains("429 Too Many Requests"));
+
+        let error = Error::Transaction("nonce too low".to_string()).classify_rate_limit();
+        assert!(matches!(error, Error::Transaction(_)));
+
+        let error = Error::InvalidAddress("rate limit".to_string()).classify_rate_limit();
+        assert!(matches!(error, Error::InvalidAddress(_)));
+    }
+
     #[test]
     fn test_from_anyhow_error() {
         let anyhow_err = anyhow::anyhow!("test anyhow error");
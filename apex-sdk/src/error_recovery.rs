@@ -1,5 +1,7 @@
 //! Error recovery and retry mechanisms.
 
+use crate::error::detect_rate_limit;
+use std::any::Any;
 use std::time::Duration;
 use thiserror::Error;
 
@@ -10,6 +12,10 @@ pub struct RetryConfig {
     pub initial_delay: Duration,
     pub max_delay: Duration,
     pub multiplier: f64,
+    /// Wait for the provider's `Retry-After` hint instead of the backoff delay
+    pub honor_retry_after: bool,
+    /// Longest `Retry-After` hint to honor; longer hints fail immediately
+    pub max_retry_after: Duration,
 }
 
 impl Default for RetryConfig {
@@ -19,6 +25,8 @@ impl Default for RetryConfig {
             initial_delay: Duration::from_millis(100),
             max_delay: Duration::from_secs(30),
             multiplier: 2.0,
+            honor_retry_after: true,
+            max_retry_after: Duration::from_secs(60),
         }
     }
 }
@@ -36,6 +44,8 @@ pub struct RetryConfigBuilder {
     initial_delay: Option<Duration>,
     max_delay: Option<Duration>,
     multiplier: Option<f64>,
+    honor_retry_after: Option<bool>,
+    max_retry_after: Option<Duration>,
 }
 
 impl RetryConfigBuilder {
@@ -59,6 +69,16 @@ impl RetryConfigBuilder {
         self
     }
 
+    pub fn honor_retry_after(mut self, honor: bool) -> Self {
+        self.honor_retry_after = Some(honor);
+        self
+    }
+
+    pub fn max_retry_after(mut self, limit: Duration) -> Self {
+        self.max_retry_after = Some(limit);
+        self
+    }
+
     pub fn build(self) -> RetryConfig {
         let default = RetryConfig::default();
         RetryConfig {
@@ -66,16 +86,22 @@ impl RetryConfigBuilder {
             initial_delay: self.initial_delay.unwrap_or(default.initial_delay),
             max_delay: self.max_delay.unwrap_or(default.max_delay),
             multiplier: self.multiplier.unwrap_or(default.multiplier),
+            honor_retry_after: self.honor_retry_after.unwrap_or(default.honor_retry_after),
+            max_retry_after: self.max_retry_after.unwrap_or(default.max_retry_after),
         }
     }
 }
 
 /// Execute a function with retry logic
+///
+/// Between attempts this backs off exponentially, except when the error is a
+/// provider rate limit carrying a retry hint: then the hint is honored instead
+/// (see [`RetryConfig::honor_retry_after`]).
 pub async fn with_retry<F, Fut, T, E>(mut f: F, config: RetryConfig) -> Result<T, E>
 where
     F: FnMut() -> Fut,
     Fut: std::future::Future<Output = Result<T, E>>,
-    E: std::fmt::Display,
+    E: std::fmt::Display + 'static,
 {
     let mut last_error = None;
     let mut delay = config.initial_delay;
@@ -88,16 +114,36 @@ where
                     return Err(err);
                 }
 
-                last_error = Some(err);
-
                 if attempt < config.max_attempts {
-                    tokio::time::sleep(delay).await;
-                    delay = std::cmp::min(
-                        Duration::from_millis(
-                            (delay.as_millis() as f64 * config.multiplier) as u64,
-                        ),
-                        config.max_delay,
-                    );
+                    let wait = match retry_after_hint(&err) {
+                        Some(retry_after) if config.honor_retry_after => {
+                            if retry_after > config.max_retry_after {
+                                tracing::warn!(
+                                    "Provider asked to retry after {:?}, exceeding the {:?} limit",
+                                    retry_after,
+                                    config.max_retry_after
+                                );
+                                return Err(err);
+                            }
+                            tracing::debug!("Rate limited, retrying after {:?}", retry_after);
+                            retry_after
+                        }
+                        _ => {
+                            let backoff = delay;
+                            delay = std::cmp::min(
+                                Duration::from_millis(
+                                    (delay.as_millis() as f64 * config.multiplier) as u64,
+                                ),
+                                config.max_delay,
+                            );
+                            backoff
+                        }
+                    };
+
+                    last_error = Some(err);
+                    tokio::time::sleep(wait).await;
+                } else {
+                    last_error = Some(err);
                 }
             }
         }
@@ -106,6 +152,20 @@ where
     Err(last_error.unwrap())
 }
 
+/// Extract a provider retry hint from a rate-limit error
+///
+/// SDK errors are inspected directly; any other error type falls back to
+/// scanning its message for a throttling response.
+fn retry_after_hint<E: std::fmt::Display + 'static>(error: &E) -> Option<Duration> {
+    match (error as &dyn Any).downcast_ref::<crate::error::Error>() {
+        Some(error) => error.retry_after(),
+        None => match (error as &dyn Any).downcast_ref::<crate::v2::Error>() {
+            Some(error) => error.retry_after(),
+            None => detect_rate_limit(&error.to_string()).flatten(),
+        },
+    }
+}
+
 /// Check if an error is retryable
 fn is_retryable<E: std::fmt::Display>(_error: &E) -> bool {
     // Simple implementation - in practice this would check error types
@@ -212,6 +272,88 @@ mod tests {
         assert_eq!(call_count, 1);
     }
 
+    #[tokio::test]
+    async fn test_with_retry_honors_retry_after() {
+        let mut call_count = 0;
+        let config = RetryConfig::builder()
+            .initial_delay(Duration::from_millis(1))
+            .build();
+        let start = tokio::time::Instant::now();
+        let result = with_retry(
+            || {
+                call_count += 1;
+                let attempt = call_count;
+                async move {
+                    if attempt == 1 {
+                        Err(crate::error::Error::RateLimited {
+                            retry_after: Some(Duration::from_millis(50)),
+                            message: "throttled".to_string(),
+                        })
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            },
+            config,
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), 2);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_parses_hint_from_foreign_errors() {
+        let mut call_count = 0;
+        let config = RetryConfig::builder()
+            .initial_delay(Duration::from_millis(1))
+            .build();
+        let start = tokio::time::Instant::now();
+        let result = with_retry(
+            || {
+                call_count += 1;
+                let attempt = call_count;
+                async move {
+                    if attempt == 1 {
+                        Err("HTTP 429 Too Many Requests (retry-after: 0.05)".to_string())
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            },
+            config,
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), 2);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_gives_up_on_excessive_retry_after() {
+        let mut call_count = 0;
+        let config = RetryConfig::builder()
+            .max_retry_after(Duration::from_secs(10))
+            .build();
+
+        let result: Result<(), crate::error::Error> = with_retry(
+            || {
+                call_count += 1;
+                async {
+                    Err(crate::error::Error::RateLimited {
+                        retry_after: Some(Duration::from_secs(600)),
+                        message: "throttled".to_string(),
+                    })
+                }
+            },
+            config,
+        )
+        .await;
+
+        assert!(result.unwrap_err().is_rate_limited());
+        assert_eq!(call_count, 1);
+    }
+
     #[test]
     fn test_circuit_breaker_opens_after_failures() {
         let mut breaker = CircuitBreaker::new(3, Duration::from_secs(10));
@@ -32,7 +32,9 @@ pub mod error;
 pub mod error_recovery;
 pub mod performance;
 pub mod sdk;
+pub mod stream;
 pub mod transaction;
+pub mod v2;
 
 pub use apex_sdk_core as core;
 pub use apex_sdk_evm as evm;
@@ -43,24 +45,32 @@ pub use advanced::{
     BlockInfo, BlockSubscription, EventSubscription, ParallelExecutor, TransactionBatch,
 };
 pub use builder::ApexSDKBuilder;
+#[cfg(not(feature = "v2-default"))]
 pub use error::{Error, Result};
 pub use error_recovery::{with_retry, CircuitBreaker, RetryConfig};
 pub use performance::{
     batch_execute, parallel_execute, AsyncMemo, BatchConfig, ConnectionPool, RateLimiter,
 };
 pub use sdk::{ApexSDK, ConfirmationStrategy, SdkConfig};
+pub use stream::{OverflowPolicy, StreamConfig, StreamSendError};
 pub use transaction::{Transaction, TransactionBuilder, TransactionResult};
+#[cfg(feature = "v2-default")]
+pub use v2::{Error, Result};
 
 /// Prelude module for common imports
 pub mod prelude {
     pub use crate::{
         builder::ApexSDKBuilder,
-        error::{Error, Result},
         sdk::{ApexSDK, ConfirmationStrategy, SdkConfig},
         transaction::{Transaction, TransactionBuilder, TransactionResult},
         types::{Address, Chain, ChainType},
     };
 
+    #[cfg(not(feature = "v2-default"))]
+    pub use crate::error::{Error, Result};
+    #[cfg(feature = "v2-default")]
+    pub use crate::v2::{Error, Result};
+
     #[cfg(feature = "substrate")]
     pub use crate::substrate::{SubstrateAdapter, Wallet as SubstrateWallet};
 
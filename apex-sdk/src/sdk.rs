@@ -2,9 +2,12 @@
 
 use crate::{
     error::{Error, Result},
+    stream::StreamConfig,
     transaction::{Transaction, TransactionResult},
     types::{Address, Chain},
 };
+use apex_sdk_core::screening::{AddressScreener, ScreeningResult};
+use apex_sdk_core::telemetry::Telemetry;
 use apex_sdk_core::ChainAdapter;
 use apex_sdk_types::TxStatus;
 use std::{sync::Arc, time::Duration};
@@ -30,6 +33,13 @@ pub struct SdkConfig {
     pub confirmation_blocks: u32,
     /// Maximum time to wait for confirmations
     pub timeout_seconds: u64,
+    /// Buffer capacity and overflow policy for the subscription streams of
+    /// the Substrate adapter
+    pub stream_config: StreamConfig,
+    /// Opt-in anonymous usage statistics; disabled by default
+    pub telemetry: Telemetry,
+    /// Compliance screening of transfer addresses before signing
+    pub address_screener: Option<Arc<dyn AddressScreener>>,
 }
 
 impl Default for SdkConfig {
@@ -38,6 +48,9 @@ impl Default for SdkConfig {
             confirmation_strategy: ConfirmationStrategy::WaitForInclusion,
             confirmation_blocks: 1,
             timeout_seconds: 60,
+            stream_config: StreamConfig::default(),
+            telemetry: Telemetry::disabled(),
+            address_screener: None,
         }
     }
 }
@@ -71,6 +84,14 @@ use apex_sdk_evm::EvmAdapter;
 ///     Ok(())
 /// }
 /// ```
+///
+/// # Sharing across tasks
+///
+/// `ApexSDK` is cheap to clone: adapters, wallets and configuration are held in
+/// `Arc`s, so every clone talks through the same connections. The adapters
+/// synchronize internally, so clones can be moved into spawned tasks without
+/// wrapping the SDK in a `Mutex`.
+#[derive(Clone)]
 pub struct ApexSDK {
     #[cfg(feature = "substrate")]
     substrate_adapter: Option<Arc<SubstrateAdapter>>,
@@ -85,7 +106,7 @@ pub struct ApexSDK {
     evm_wallet: Option<Arc<apex_sdk_evm::wallet::Wallet>>,
 
     timeout: Duration,
-    config: SdkConfig,
+    config: Arc<SdkConfig>,
 }
 
 impl ApexSDK {
@@ -135,12 +156,13 @@ impl ApexSDK {
             evm_wallet: evm_wallet.map(Arc::new),
 
             timeout,
-            config,
+            config: Arc::new(config),
         })
     }
 
     /// Execute a transaction on the appropriate blockchain.
     pub async fn execute(&self, transaction: Transaction) -> Result<TransactionResult> {
+        self.screen(&transaction).await?;
         match transaction.destination_chain() {
             #[cfg(feature = "substrate")]
             chain if chain.chain_type() == apex_sdk_types::ChainType::Substrate => {
@@ -151,6 +173,7 @@ impl ApexSDK {
                     ))
                 })?;
 
+                self.config.telemetry.record_feature("execute.substrate");
                 self.execute_substrate_transaction(adapter, transaction)
                     .await
             }
@@ -164,6 +187,7 @@ impl ApexSDK {
                     ))
                 })?;
 
+                self.config.telemetry.record_feature("execute.evm");
                 self.execute_evm_transaction(adapter, transaction).await
             }
 
@@ -174,6 +198,27 @@ impl ApexSDK {
         }
     }
 
+    /// Reject transactions the address screener blocks, or cannot screen
+    async fn screen(&self, transaction: &Transaction) -> Result<()> {
+        let Some(screener) = &self.config.address_screener else {
+            return Ok(());
+        };
+        match screener
+            .screen(transaction.from.as_str(), transaction.to.as_str())
+            .await
+        {
+            Ok(ScreeningResult::Clear) => Ok(()),
+            Ok(ScreeningResult::Blocked { address, reason }) => Err(Error::Transaction(format!(
+                "Address {} blocked by screening: {}",
+                address, reason
+            ))),
+            Err(e) => Err(Error::Transaction(format!(
+                "Address screening failed: {}",
+                e
+            ))),
+        }
+    }
+
     /// Get the status of a transaction.
     pub async fn get_transaction_status(
         &self,
@@ -193,7 +238,7 @@ impl ApexSDK {
                 adapter
                     .get_transaction_status(tx_hash)
                     .await
-                    .map_err(|e| Error::Transaction(e.to_string()))
+                    .map_err(|e| Error::Transaction(e.to_string()).classify_rate_limit())
             }
 
             #[cfg(feature = "evm")]
@@ -208,7 +253,7 @@ impl ApexSDK {
                 adapter
                     .get_transaction_status(tx_hash)
                     .await
-                    .map_err(|e| Error::Transaction(e.to_string()))
+                    .map_err(|e| Error::Transaction(e.to_string()).classify_rate_limit())
             }
 
             #[cfg(feature = "substrate")]
@@ -221,7 +266,9 @@ impl ApexSDK {
                                 evm_adapter
                                     .get_transaction_status(tx_hash)
                                     .await
-                                    .map_err(|e| Error::Transaction(e.to_string()))
+                                    .map_err(|e| {
+                                        Error::Transaction(e.to_string()).classify_rate_limit()
+                                    })
                             } else {
                                 Err(Error::Transaction(
                                     "No EVM adapter available for hybrid chain".to_string(),
@@ -233,7 +280,7 @@ impl ApexSDK {
                     evm_adapter
                         .get_transaction_status(tx_hash)
                         .await
-                        .map_err(|e| Error::Transaction(e.to_string()))
+                        .map_err(|e| Error::Transaction(e.to_string()).classify_rate_limit())
                 } else {
                     Err(Error::UnsupportedChain(format!(
                         "No adapter configured for hybrid chain {}",
@@ -307,6 +354,21 @@ impl ApexSDK {
         self.timeout
     }
 
+    /// Get the SDK configuration.
+    pub fn config(&self) -> &SdkConfig {
+        &self.config
+    }
+
+    /// Get the stream configuration used for subscriptions.
+    pub fn stream_config(&self) -> &StreamConfig {
+        &self.config.stream_config
+    }
+
+    /// Get the usage statistics collector; flush it to send a report.
+    pub fn telemetry(&self) -> &Telemetry {
+        &self.config.telemetry
+    }
+
     /// Create a new transaction builder.
     pub fn transaction(&self) -> crate::transaction::TransactionBuilder {
         crate::transaction::TransactionBuilder::new()
@@ -464,7 +526,10 @@ impl ApexSDK {
         let tx_hash = executor
             .transfer(wallet.as_ref(), &to_address, amount)
             .await
-            .map_err(|e| Error::Transaction(format!("Substrate transaction failed: {}", e)))?;
+            .map_err(|e| {
+                Error::Transaction(format!("Substrate transaction failed: {}", e))
+                    .classify_rate_limit()
+            })?;
 
         tracing::info!(
             "Substrate transaction submitted: {} → {}, amount: {}, hash: {}",
@@ -583,10 +648,9 @@ impl ApexSDK {
         // Add amount (16 bytes for u128)
         tx_bytes.extend_from_slice(&transaction.amount.to_be_bytes());
 
-        let tx_result = executor
-            .execute_transaction(&tx_bytes)
-            .await
-            .map_err(|e| Error::Transaction(format!("EVM transaction failed: {}", e)))?;
+        let tx_result = executor.execute_transaction(&tx_bytes).await.map_err(|e| {
+            Error::Transaction(format!("EVM transaction failed: {}", e)).classify_rate_limit()
+        })?;
 
         let tx_hash_str = tx_result.hash;
 
@@ -753,7 +817,7 @@ mod tests {
     #[test]
     fn test_is_chain_supported_no_adapters() {
         let sdk = ApexSDK {
-            config: SdkConfig::default(),
+            config: Arc::new(SdkConfig::default()),
             #[cfg(feature = "substrate")]
             substrate_adapter: None,
             #[cfg(feature = "substrate")]
@@ -770,6 +834,30 @@ mod tests {
         assert!(!sdk.is_chain_supported(&Chain::Kusama));
     }
 
+    #[test]
+    fn test_sdk_clones_share_state() {
+        fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+        assert_shareable::<ApexSDK>();
+
+        let sdk = ApexSDK {
+            config: Arc::new(SdkConfig::default()),
+            #[cfg(feature = "substrate")]
+            substrate_adapter: None,
+            #[cfg(feature = "substrate")]
+            substrate_wallet: None,
+            #[cfg(feature = "evm")]
+            evm_adapter: None,
+            #[cfg(feature = "evm")]
+            evm_wallet: None,
+            timeout: Duration::from_secs(30),
+        };
+        let clone = sdk.clone();
+
+        assert!(Arc::ptr_eq(&sdk.config, &clone.config));
+        assert_eq!(clone.timeout(), sdk.timeout());
+        assert_eq!(clone.config().confirmation_blocks, 1);
+    }
+
     #[test]
     fn test_chain_type_detection() {
         assert_eq!(
@@ -871,7 +959,7 @@ mod tests {
 
     #[test]
     fn test_adapter_error_handling() {
-        use crate::Error;
+        use crate::error::Error;
 
         let config_error = Error::Config("Test configuration error".to_string());
         match config_error {
@@ -905,7 +993,7 @@ mod tests {
     #[cfg(feature = "evm")]
     fn test_evm_adapter_not_configured() {
         let sdk = ApexSDK {
-            config: SdkConfig::default(),
+            config: Arc::new(SdkConfig::default()),
             #[cfg(feature = "substrate")]
             substrate_adapter: None,
             #[cfg(feature = "substrate")]
@@ -926,7 +1014,7 @@ mod tests {
     #[cfg(feature = "substrate")]
     fn test_get_transaction_status_substrate_not_configured() {
         let sdk = ApexSDK {
-            config: SdkConfig::default(),
+            config: Arc::new(SdkConfig::default()),
             substrate_adapter: None,
             substrate_wallet: None,
             #[cfg(feature = "evm")]
@@ -948,7 +1036,7 @@ mod tests {
     #[cfg(feature = "evm")]
     fn test_get_transaction_status_evm_not_configured() {
         let sdk = ApexSDK {
-            config: SdkConfig::default(),
+            config: Arc::new(SdkConfig::default()),
             #[cfg(feature = "substrate")]
             substrate_adapter: None,
             #[cfg(feature = "substrate")]
@@ -971,7 +1059,7 @@ mod tests {
         use crate::transaction::TransactionBuilder;
 
         let sdk = ApexSDK {
-            config: SdkConfig::default(),
+            config: Arc::new(SdkConfig::default()),
             #[cfg(feature = "substrate")]
             substrate_adapter: None,
             #[cfg(feature = "substrate")]
@@ -998,6 +1086,47 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_execute_screens_addresses() {
+        use crate::transaction::TransactionBuilder;
+        use apex_sdk_core::screening::StaticListScreener;
+
+        let config = SdkConfig {
+            address_screener: Some(Arc::new(StaticListScreener::new([
+                "0xd8da6bf26964af9d7eed9e03e53415d37aa96045",
+            ]))),
+            ..SdkConfig::default()
+        };
+        let sdk = ApexSDK {
+            config: Arc::new(config),
+            #[cfg(feature = "substrate")]
+            substrate_adapter: None,
+            #[cfg(feature = "substrate")]
+            substrate_wallet: None,
+            #[cfg(feature = "evm")]
+            evm_adapter: None,
+            #[cfg(feature = "evm")]
+            evm_wallet: None,
+            timeout: Duration::from_secs(30),
+        };
+
+        let transaction = TransactionBuilder::new()
+            .from(Address::evm(
+                "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEbD".to_string(),
+            ))
+            .to(Address::evm(
+                "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045".to_string(),
+            ))
+            .amount(100)
+            .build()
+            .expect("Failed to build test transaction");
+
+        match sdk.execute(transaction).await {
+            Err(Error::Transaction(msg)) => assert!(msg.contains("blocked by screening")),
+            other => panic!("expected a screening error, got {:?}", other.map(|_| ())),
+        }
+    }
+
     #[test]
     fn test_chain_defaults() {
         let polkadot = Chain::Polkadot;
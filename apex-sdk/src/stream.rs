@@ -0,0 +1,67 @@
+//! Bounded stream channels with configurable backpressure.
+//!
+//! The channels live in [`apex_sdk_core::stream`] so the chain adapters can
+//! use them too, and are re-exported here.
+
+pub use apex_sdk_core::stream::*;
+
+use async_trait::async_trait;
+use tokio::sync::broadcast;
+
+/// Receiving end a subscription reads its items from
+#[async_trait]
+pub(crate) trait ItemSource<T>: Send {
+    /// Next item, or `None` once the source is exhausted
+    async fn next_item(&mut self) -> Option<T>;
+
+    /// Number of items the consumer missed
+    fn dropped_count(&self) -> u64;
+}
+
+#[async_trait]
+impl<T: Send> ItemSource<T> for StreamReceiver<T> {
+    async fn next_item(&mut self) -> Option<T> {
+        self.recv().await
+    }
+
+    fn dropped_count(&self) -> u64 {
+        StreamReceiver::dropped_count(self)
+    }
+}
+
+/// Broadcast receiver counting the items it lagged behind on as dropped
+///
+/// Backs the deprecated broadcast constructors of the subscriptions.
+pub(crate) struct LaggingReceiver<T> {
+    receiver: broadcast::Receiver<T>,
+    lagged: u64,
+}
+
+impl<T> LaggingReceiver<T> {
+    pub(crate) fn new(receiver: broadcast::Receiver<T>) -> Self {
+        Self {
+            receiver,
+            lagged: 0,
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Clone + Send> ItemSource<T> for LaggingReceiver<T> {
+    async fn next_item(&mut self) -> Option<T> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(item) => return Some(item),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    self.lagged += skipped;
+                    tracing::warn!("Subscription fell behind, skipped {} items", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    fn dropped_count(&self) -> u64 {
+        self.lagged
+    }
+}
@@ -0,0 +1,574 @@
+//! Redesigned APIs, next to the 1.x ones
+//!
+//! `apex_sdk::v2` holds the APIs that change shape in the next major version:
+//!
+//! - [`Error`] is structured: variants carry the fields callers match on and
+//!   [`Error::kind`] classifies them without string inspection
+//! - amounts are [`Balance`]s instead of bare `u128`s
+//! - chains are used through the [`ChainClient`] trait, implemented by every
+//!   adapter
+//! - [`Subscription`]s yield `Result`s, so a stream that fails says why
+//!
+//! Every 1.x type converts into its v2 counterpart, and v2 errors convert back,
+//! so code can migrate one call site at a time:
+//!
+//! ```rust,no_run
+//! use apex_sdk::prelude::*;
+//! use apex_sdk::v2::{self, ChainClient};
+//!
+//! # async fn example(sdk: ApexSDK) -> v2::Result<()> {
+//! let sdk = sdk.v2();
+//! let polkadot = sdk.client(&Chain::Polkadot)?;
+//! let balance = polkadot
+//!     .balance(&Address::substrate("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5"))
+//!     .await?;
+//! println!("{} planck at #{}", balance.planck(), polkadot.block_number().await?);
+//!
+//! // v1 code keeps working on the same connections
+//! let _v1: &ApexSDK = sdk.v1();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With the `v2-default` feature the crate root and the prelude export the v2
+//! [`Error`] and [`Result`] instead of the 1.x ones.
+
+use crate::advanced::{BlockInfo, BlockSubscription, EventSubscription};
+use crate::sdk::ApexSDK;
+use crate::stream::{bounded_stream, ItemSource, StreamConfig, StreamReceiver, StreamSender};
+use crate::transaction::{Transaction, TransactionResult};
+use crate::types::{Address, Chain};
+use apex_sdk_core::SdkError;
+use apex_sdk_types::TransactionStatus;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+pub use apex_sdk_core::balance::{Balance, Denomination};
+
+/// Result type alias for v2 operations
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Broad class of an [`Error`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// Invalid SDK or adapter configuration
+    Config,
+    /// The node could not be reached or failed to answer
+    Connection,
+    /// A transaction could not be built, submitted or tracked
+    Transaction,
+    /// Data could not be encoded or decoded
+    Serialization,
+    /// An address is malformed or for another chain
+    InvalidAddress,
+    /// No adapter is configured for the chain
+    UnsupportedChain,
+    /// The provider throttled the request
+    RateLimited,
+    /// Anything else
+    Other,
+}
+
+/// Structured error type of the v2 API
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Invalid SDK or adapter configuration
+    #[error("Configuration error: {message}")]
+    Config {
+        /// What is wrong
+        message: String,
+    },
+
+    /// The node could not be reached or failed to answer
+    #[error("Connection error: {message}")]
+    Connection {
+        /// What failed
+        message: String,
+    },
+
+    /// A transaction could not be built, submitted or tracked
+    #[error("Transaction error{}: {message}", format_tx_hash(.tx_hash))]
+    Transaction {
+        /// Hash of the transaction, once known
+        tx_hash: Option<String>,
+        /// What failed
+        message: String,
+    },
+
+    /// Data could not be encoded or decoded
+    #[error("Serialization error: {message}")]
+    Serialization {
+        /// What failed
+        message: String,
+    },
+
+    /// An address is malformed or for another chain
+    #[error("Invalid address: {message}")]
+    InvalidAddress {
+        /// Why the address was rejected
+        message: String,
+    },
+
+    /// No adapter is configured for the chain
+    #[error("Unsupported chain: {chain}")]
+    UnsupportedChain {
+        /// Name of the chain
+        chain: String,
+    },
+
+    /// The provider throttled the request
+    #[error("Rate limited by provider: {message}{}", format_retry_after(.retry_after))]
+    RateLimited {
+        /// How long the provider asked us to wait, if it said
+        retry_after: Option<Duration>,
+        /// The provider's error message
+        message: String,
+    },
+
+    /// Anything else
+    #[error("Error: {message}")]
+    Other {
+        /// What failed
+        message: String,
+    },
+}
+
+fn format_tx_hash(tx_hash: &Option<String>) -> String {
+    tx_hash
+        .as_ref()
+        .map(|hash| format!(" in {}", hash))
+        .unwrap_or_default()
+}
+
+fn format_retry_after(retry_after: &Option<Duration>) -> String {
+    retry_after
+        .map(|duration| format!(", retry after {:?}", duration))
+        .unwrap_or_default()
+}
+
+impl Error {
+    /// Broad class of the error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Config { .. } => ErrorKind::Config,
+            Error::Connection { .. } => ErrorKind::Connection,
+            Error::Transaction { .. } => ErrorKind::Transaction,
+            Error::Serialization { .. } => ErrorKind::Serialization,
+            Error::InvalidAddress { .. } => ErrorKind::InvalidAddress,
+            Error::UnsupportedChain { .. } => ErrorKind::UnsupportedChain,
+            Error::RateLimited { .. } => ErrorKind::RateLimited,
+            Error::Other { .. } => ErrorKind::Other,
+        }
+    }
+
+    /// Whether retrying the same request may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.kind(), ErrorKind::Connection | ErrorKind::RateLimited)
+    }
+
+    /// How long to wait before retrying, if the provider gave a hint
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    fn transaction(tx_hash: &str, message: impl Into<String>) -> Self {
+        Error::Transaction {
+            tx_hash: Some(tx_hash.to_string()),
+            message: message.into(),
+        }
+    }
+}
+
+impl From<crate::error::Error> for Error {
+    fn from(error: crate::error::Error) -> Self {
+        use crate::error::Error as V1;
+        match error.classify_rate_limit() {
+            V1::Config(message) => Error::Config { message },
+            V1::Connection(message) => Error::Connection { message },
+            V1::Transaction(message) => Error::Transaction {
+                tx_hash: None,
+                message,
+            },
+            V1::Serialization(message) => Error::Serialization { message },
+            V1::InvalidAddress(message) => Error::InvalidAddress { message },
+            V1::UnsupportedChain(chain) => Error::UnsupportedChain { chain },
+            V1::RateLimited {
+                retry_after,
+                message,
+            } => Error::RateLimited {
+                retry_after,
+                message,
+            },
+            V1::Other(message) => Error::Other { message },
+        }
+    }
+}
+
+impl From<Error> for crate::error::Error {
+    fn from(error: Error) -> Self {
+        use crate::error::Error as V1;
+        match error {
+            Error::Config { message } => V1::Config(message),
+            Error::Connection { message } => V1::Connection(message),
+            Error::Transaction { tx_hash, message } => V1::Transaction(match tx_hash {
+                Some(hash) => format!("{} ({})", message, hash),
+                None => message,
+            }),
+            Error::Serialization { message } => V1::Serialization(message),
+            Error::InvalidAddress { message } => V1::InvalidAddress(message),
+            Error::UnsupportedChain { chain } => V1::UnsupportedChain(chain),
+            Error::RateLimited {
+                retry_after,
+                message,
+            } => V1::RateLimited {
+                retry_after,
+                message,
+            },
+            Error::Other { message } => V1::Other(message),
+        }
+    }
+}
+
+impl From<SdkError> for Error {
+    fn from(error: SdkError) -> Self {
+        let error = match error {
+            SdkError::ProviderError(message) | SdkError::NetworkError(message) => {
+                Error::Connection { message }
+            }
+            SdkError::SignerError(message) | SdkError::TransactionError(message) => {
+                Error::Transaction {
+                    tx_hash: None,
+                    message,
+                }
+            }
+            SdkError::ConfigError(message) => Error::Config { message },
+            SdkError::NotImplemented(message) => Error::Other {
+                message: format!("Not implemented: {}", message),
+            },
+        };
+        match &error {
+            Error::Connection { message } | Error::Transaction { message, .. } => {
+                crate::error::detect_rate_limit(message)
+                    .map(|retry_after| Error::RateLimited {
+                        retry_after,
+                        message: message.clone(),
+                    })
+                    .unwrap_or(error)
+            }
+            _ => error,
+        }
+    }
+}
+
+/// A connected chain, independent of its adapter
+#[async_trait]
+pub trait ChainClient: Send + Sync {
+    /// Name of the chain
+    fn name(&self) -> &str;
+
+    /// Whether `address` is valid on this chain
+    fn is_valid_address(&self, address: &Address) -> bool;
+
+    /// Number of the latest block
+    async fn block_number(&self) -> Result<u64>;
+
+    /// Free balance of `address`
+    async fn balance(&self, address: &Address) -> Result<Balance>;
+
+    /// Next nonce of `address`
+    async fn nonce(&self, address: &Address) -> Result<u64>;
+
+    /// Status of a submitted transaction
+    async fn transaction_status(&self, tx_hash: &str) -> Result<TransactionStatus>;
+}
+
+#[cfg(feature = "substrate")]
+#[async_trait]
+impl ChainClient for apex_sdk_substrate::SubstrateAdapter {
+    fn name(&self) -> &str {
+        self.chain_name()
+    }
+
+    fn is_valid_address(&self, address: &Address) -> bool {
+        self.validate_address(address)
+    }
+
+    async fn block_number(&self) -> Result<u64> {
+        Ok(apex_sdk_core::Provider::get_block_number(self).await?)
+    }
+
+    async fn balance(&self, address: &Address) -> Result<Balance> {
+        let planck = apex_sdk_core::Provider::get_balance(self, address).await?;
+        Ok(Balance::from_planck(planck))
+    }
+
+    async fn nonce(&self, address: &Address) -> Result<u64> {
+        Ok(apex_sdk_core::Provider::get_transaction_count(self, address).await?)
+    }
+
+    async fn transaction_status(&self, tx_hash: &str) -> Result<TransactionStatus> {
+        apex_sdk_core::ChainAdapter::get_transaction_status(self, tx_hash)
+            .await
+            .map_err(|message| Error::transaction(tx_hash, message))
+    }
+}
+
+#[cfg(feature = "evm")]
+#[async_trait]
+impl ChainClient for apex_sdk_evm::EvmAdapter {
+    fn name(&self) -> &str {
+        apex_sdk_core::ChainAdapter::chain_name(self)
+    }
+
+    fn is_valid_address(&self, address: &Address) -> bool {
+        apex_sdk_core::ChainAdapter::validate_address(self, address)
+    }
+
+    async fn block_number(&self) -> Result<u64> {
+        Ok(apex_sdk_core::Provider::get_block_number(self.provider()).await?)
+    }
+
+    async fn balance(&self, address: &Address) -> Result<Balance> {
+        let wei = apex_sdk_core::Provider::get_balance(self.provider(), address).await?;
+        Ok(Balance::from_planck(wei))
+    }
+
+    async fn nonce(&self, address: &Address) -> Result<u64> {
+        Ok(apex_sdk_core::Provider::get_transaction_count(self.provider(), address).await?)
+    }
+
+    async fn transaction_status(&self, tx_hash: &str) -> Result<TransactionStatus> {
+        apex_sdk_core::ChainAdapter::get_transaction_status(self, tx_hash)
+            .await
+            .map_err(|message| Error::transaction(tx_hash, message))
+    }
+}
+
+/// v2 view of an [`ApexSDK`], sharing its connections
+#[derive(Clone)]
+pub struct Sdk {
+    inner: ApexSDK,
+}
+
+impl Sdk {
+    /// Client for `chain`, through the adapter configured for its chain type
+    pub fn client(&self, chain: &Chain) -> Result<Arc<dyn ChainClient>> {
+        let unsupported = || Error::UnsupportedChain {
+            chain: chain.name().to_string(),
+        };
+        match chain.chain_type() {
+            #[cfg(feature = "substrate")]
+            apex_sdk_types::ChainType::Substrate => {
+                let adapter = self.inner.substrate().map_err(|_| unsupported())?;
+                Ok(adapter as Arc<dyn ChainClient>)
+            }
+            #[cfg(feature = "evm")]
+            apex_sdk_types::ChainType::Evm => {
+                let adapter = self.inner.evm().map_err(|_| unsupported())?;
+                Ok(adapter as Arc<dyn ChainClient>)
+            }
+            _ => Err(unsupported()),
+        }
+    }
+
+    /// Execute a transaction on the appropriate chain
+    pub async fn execute(&self, transaction: Transaction) -> Result<TransactionResult> {
+        Ok(self.inner.execute(transaction).await?)
+    }
+
+    /// Status of a transaction on `chain`
+    pub async fn transaction_status(
+        &self,
+        tx_hash: &str,
+        chain: &Chain,
+    ) -> Result<TransactionStatus> {
+        self.inner
+            .get_transaction_status(tx_hash, chain)
+            .await
+            .map_err(|error| match Error::from(error) {
+                Error::Transaction { message, .. } => Error::transaction(tx_hash, message),
+                error => error,
+            })
+    }
+
+    /// The 1.x SDK behind this view
+    pub fn v1(&self) -> &ApexSDK {
+        &self.inner
+    }
+
+    /// Back to the 1.x SDK
+    pub fn into_v1(self) -> ApexSDK {
+        self.inner
+    }
+}
+
+impl From<ApexSDK> for Sdk {
+    fn from(inner: ApexSDK) -> Self {
+        Self { inner }
+    }
+}
+
+impl ApexSDK {
+    /// v2 view of this SDK, sharing its connections
+    pub fn v2(&self) -> Sdk {
+        Sdk::from(self.clone())
+    }
+}
+
+/// Items buffered by a [`Subscription`]
+enum Items<T> {
+    Results(StreamReceiver<Result<T>>),
+    Infallible(Box<dyn ItemSource<T>>),
+}
+
+/// Subscription yielding `Result` items
+///
+/// A producer reports why a stream ends by sending an `Err` before dropping
+/// its sender.
+pub struct Subscription<T> {
+    items: Items<T>,
+    cancellation_token: CancellationToken,
+}
+
+impl<T> Subscription<T> {
+    /// Create a new subscription with cancellation support
+    pub fn new() -> (StreamSender<Result<T>>, CancellationToken, Self) {
+        Self::with_config(StreamConfig::default())
+    }
+
+    /// Create a new subscription with a custom buffer configuration
+    pub fn with_config(config: StreamConfig) -> (StreamSender<Result<T>>, CancellationToken, Self) {
+        let (sender, receiver) = bounded_stream(config);
+        let cancellation_token = CancellationToken::new();
+        (
+            sender,
+            cancellation_token.clone(),
+            Self {
+                items: Items::Results(receiver),
+                cancellation_token,
+            },
+        )
+    }
+
+    /// Get the next item, or `None` once the subscription has ended
+    pub async fn next(&mut self) -> Option<Result<T>> {
+        tokio::select! {
+            result = async {
+                match &mut self.items {
+                    Items::Results(receiver) => receiver.recv().await,
+                    Items::Infallible(receiver) => receiver.next_item().await.map(Ok),
+                }
+            } => result,
+            _ = self.cancellation_token.cancelled() => None,
+        }
+    }
+
+    /// Stop the subscription by triggering cancellation
+    pub fn stop(&self) {
+        self.cancellation_token.cancel();
+        tracing::debug!("Subscription stopped");
+    }
+
+    /// Check if the subscription has been stopped
+    pub fn is_stopped(&self) -> bool {
+        self.cancellation_token.is_cancelled()
+    }
+
+    /// Number of items dropped because the consumer fell behind
+    pub fn dropped_count(&self) -> u64 {
+        match &self.items {
+            Items::Results(receiver) => receiver.dropped_count(),
+            Items::Infallible(receiver) => receiver.dropped_count(),
+        }
+    }
+}
+
+impl From<BlockSubscription> for Subscription<BlockInfo> {
+    fn from(subscription: BlockSubscription) -> Self {
+        let (receiver, cancellation_token) = subscription.into_parts();
+        Self {
+            items: Items::Infallible(receiver),
+            cancellation_token,
+        }
+    }
+}
+
+impl From<EventSubscription> for Subscription<String> {
+    fn from(subscription: EventSubscription) -> Self {
+        let (receiver, cancellation_token) = subscription.into_parts();
+        Self {
+            items: Items::Infallible(receiver),
+            cancellation_token,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_round_trip_through_v1() {
+        let v1 = crate::error::Error::Connection("HTTP error 429, Retry-After: 3".to_string());
+        let error = Error::from(v1);
+        assert_eq!(
+            error,
+            Error::RateLimited {
+                retry_after: Some(Duration::from_secs(3)),
+                message: "HTTP error 429, Retry-After: 3".to_string(),
+            }
+        );
+        assert!(error.is_retryable());
+
+        let error = Error::transaction("0xabc", "reverted");
+        assert_eq!(error.kind(), ErrorKind::Transaction);
+        assert_eq!(error.to_string(), "Transaction error in 0xabc: reverted");
+        assert!(!error.is_retryable());
+        let v1 = crate::error::Error::from(error);
+        assert_eq!(v1.to_string(), "Transaction error: reverted (0xabc)");
+
+        let error = Error::from(SdkError::ConfigError("no endpoint".to_string()));
+        assert_eq!(error.kind(), ErrorKind::Config);
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_yield_results() {
+        let (sender, _token, mut subscription) = Subscription::<u64>::new();
+        sender.send(Ok(1)).await.unwrap();
+        sender
+            .send(Err(Error::Connection {
+                message: "closed".to_string(),
+            }))
+            .await
+            .unwrap();
+        drop(sender);
+        assert_eq!(subscription.next().await, Some(Ok(1)));
+        assert!(matches!(
+            subscription.next().await,
+            Some(Err(Error::Connection { .. }))
+        ));
+        assert_eq!(subscription.next().await, None);
+
+        let (sender, token, v1) = BlockSubscription::bounded();
+        let mut subscription = Subscription::from(v1);
+        sender
+            .send(BlockInfo {
+                number: 7,
+                hash: "0x07".to_string(),
+                timestamp: 0,
+            })
+            .await
+            .unwrap();
+        assert_eq!(subscription.next().await.unwrap().unwrap().number, 7);
+        token.cancel();
+        assert!(subscription.is_stopped());
+        assert!(subscription.next().await.is_none());
+    }
+}
@@ -0,0 +1,286 @@
+//! Apex Explorer
+//!
+//! A small block explorer for Substrate chains, served as a JSON REST API and
+//! backed entirely by Apex SDK. It doubles as a demo of the SDK's query APIs
+//! and as a usable internal explorer for private chains and dev networks.
+//!
+//! **Endpoints:**
+//! - `GET /block/{number}` - block header, extrinsics and events
+//! - `GET /account/{address}` - nonce and balances of an account
+//! - `GET /tx/{hash}` - inclusion status of an extrinsic
+//! - `GET /events` - events filtered by `pallet`, `variant`, `involving`,
+//!   `from`, `to` and `limit`
+//!
+//! Blocks and decoded events are cached for an hour; account state for a few
+//! seconds so balances stay close to the chain head.
+//!
+//! **Usage:**
+//! ```text
+//! apex-explorer [endpoint] [listen-address]
+//! ```
+//! Both arguments fall back to `APEX_ENDPOINT` and `APEX_LISTEN`, then to
+//! `ws://127.0.0.1:9944` and `127.0.0.1:8080`.
+
+use apex_sdk::substrate::{
+    Cache, CacheConfig, Error as SubstrateError, EventQuery, SubstrateAdapter,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_ENDPOINT: &str = "ws://127.0.0.1:9944";
+const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
+
+/// Blocks searched by `/events` when no range is given
+const DEFAULT_EVENT_WINDOW: u64 = 100;
+
+/// Widest block range a single `/events` request may search
+const MAX_EVENT_WINDOW: u64 = 10_000;
+
+/// Matches returned by `/events` when no limit is given
+const DEFAULT_EVENT_LIMIT: usize = 100;
+
+/// Shared state of the explorer
+struct Explorer {
+    adapter: SubstrateAdapter,
+    /// Blocks, transaction lookups and decoded events
+    blocks: Arc<Cache>,
+    /// Account state, which changes every block
+    accounts: Cache,
+}
+
+impl Explorer {
+    /// Number of the best block known to the node
+    async fn best_block(&self) -> Result<u64, ApiError> {
+        let block = self
+            .adapter
+            .client()
+            .blocks()
+            .at_latest()
+            .await
+            .map_err(|e| ApiError::Upstream(format!("Failed to fetch latest block: {}", e)))?;
+        Ok(block.number() as u64)
+    }
+}
+
+/// Errors returned to API clients
+#[derive(Debug)]
+enum ApiError {
+    /// The request was malformed
+    BadRequest(String),
+    /// The requested item does not exist
+    NotFound(String),
+    /// The node failed to answer
+    Upstream(String),
+}
+
+impl From<SubstrateError> for ApiError {
+    fn from(error: SubstrateError) -> Self {
+        match error {
+            SubstrateError::Connection(message) => ApiError::Upstream(message),
+            other => ApiError::BadRequest(other.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            ApiError::Upstream(message) => (StatusCode::BAD_GATEWAY, message),
+        };
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
+
+type ApiResult = Result<Json<JsonValue>, ApiError>;
+
+/// Look up a cached JSON response
+fn cached(cache: &Cache, key: &str) -> Option<Json<JsonValue>> {
+    cache
+        .get_rpc(key)
+        .and_then(|body| serde_json::from_str(&body).ok())
+        .map(Json)
+}
+
+/// Store a JSON response and hand it back
+fn remember(cache: &Cache, key: String, value: JsonValue) -> Json<JsonValue> {
+    cache.put_rpc(key, value.to_string());
+    Json(value)
+}
+
+fn to_json(value: impl serde::Serialize) -> Result<JsonValue, ApiError> {
+    serde_json::to_value(value)
+        .map_err(|e| ApiError::Upstream(format!("Failed to encode response: {}", e)))
+}
+
+async fn block(State(explorer): State<Arc<Explorer>>, Path(number): Path<u64>) -> ApiResult {
+    let key = format!("explorer:block:{}", number);
+    if let Some(response) = cached(&explorer.blocks, &key) {
+        return Ok(response);
+    }
+
+    let best = explorer.best_block().await?;
+    if number > best {
+        return Err(ApiError::NotFound(format!(
+            "Block {} is past the best block {}",
+            number, best
+        )));
+    }
+
+    let block = explorer.adapter.get_block_detailed(number).await?;
+    Ok(remember(&explorer.blocks, key, to_json(block)?))
+}
+
+async fn account(State(explorer): State<Arc<Explorer>>, Path(address): Path<String>) -> ApiResult {
+    let key = format!("explorer:account:{}", address);
+    if let Some(response) = cached(&explorer.accounts, &key) {
+        return Ok(response);
+    }
+
+    let info = explorer.adapter.storage().get_account_info(&address).await?;
+    let response = json!({
+        "address": address,
+        "nonce": info.nonce,
+        "consumers": info.consumers,
+        "providers": info.providers,
+        "sufficients": info.sufficients,
+        // Balances exceed the range of JSON numbers, so they are strings
+        "free": info.free.to_string(),
+        "reserved": info.reserved.to_string(),
+        "frozen": info.frozen.to_string(),
+        "transferable": info.transferable().to_string(),
+        "total": info.total().to_string(),
+    });
+    Ok(remember(&explorer.accounts, key, response))
+}
+
+async fn transaction(State(explorer): State<Arc<Explorer>>, Path(hash): Path<String>) -> ApiResult {
+    let key = format!("explorer:tx:{}", hash.to_lowercase());
+    if let Some(response) = cached(&explorer.blocks, &key) {
+        return Ok(response);
+    }
+
+    let status = explorer.adapter.get_transaction_status(&hash).await?;
+    let response = to_json(&status)?;
+    // Only cache results that can no longer change
+    if status.block_number.is_some() {
+        return Ok(remember(&explorer.blocks, key, response));
+    }
+    Ok(Json(response))
+}
+
+/// Query parameters of `/events`
+#[derive(Debug, Deserialize)]
+struct EventParams {
+    pallet: Option<String>,
+    variant: Option<String>,
+    involving: Option<String>,
+    from: Option<u64>,
+    to: Option<u64>,
+    limit: Option<usize>,
+}
+
+async fn events(
+    State(explorer): State<Arc<Explorer>>,
+    Query(params): Query<EventParams>,
+) -> ApiResult {
+    let to = match params.to {
+        Some(to) => to,
+        None => explorer.best_block().await?,
+    };
+    let from = params
+        .from
+        .unwrap_or_else(|| to.saturating_sub(DEFAULT_EVENT_WINDOW - 1));
+    if from > to {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid block range: {} is after {}",
+            from, to
+        )));
+    }
+    if to - from >= MAX_EVENT_WINDOW {
+        return Err(ApiError::BadRequest(format!(
+            "Block range spans more than {} blocks",
+            MAX_EVENT_WINDOW
+        )));
+    }
+
+    let mut query = EventQuery::new()
+        .between(from, to)
+        .limit(params.limit.unwrap_or(DEFAULT_EVENT_LIMIT))
+        .with_cache(explorer.blocks.clone());
+    if let Some(pallet) = params.pallet {
+        query = query.pallet(pallet);
+    }
+    if let Some(variant) = params.variant {
+        query = query.variant(variant);
+    }
+    if let Some(involving) = params.involving {
+        query = query.involving(involving);
+    }
+
+    let events = query.run(&explorer.adapter).await?;
+    Ok(Json(json!({
+        "from": from,
+        "to": to,
+        "events": to_json(events)?,
+    })))
+}
+
+async fn not_found() -> ApiError {
+    ApiError::NotFound("No such endpoint".to_string())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let mut args = std::env::args().skip(1);
+    let endpoint = args
+        .next()
+        .or_else(|| std::env::var("APEX_ENDPOINT").ok())
+        .unwrap_or_else(|| DEFAULT_ENDPOINT.to_string());
+    let listen = args
+        .next()
+        .or_else(|| std::env::var("APEX_LISTEN").ok())
+        .unwrap_or_else(|| DEFAULT_LISTEN.to_string());
+
+    tracing::info!("Connecting to {}", endpoint);
+    let adapter = SubstrateAdapter::connect(&endpoint).await?;
+
+    let explorer = Arc::new(Explorer {
+        adapter,
+        blocks: Arc::new(Cache::with_config(CacheConfig {
+            max_entries: 10_000,
+            rpc_ttl: Duration::from_secs(3600),
+            ..CacheConfig::default()
+        })),
+        accounts: Cache::with_config(CacheConfig {
+            rpc_ttl: Duration::from_secs(6),
+            ..CacheConfig::default()
+        }),
+    });
+
+    let app = Router::new()
+        .route("/block/{number}", get(block))
+        .route("/account/{address}", get(account))
+        .route("/tx/{hash}", get(transaction))
+        .route("/events", get(events))
+        .fallback(not_found)
+        .with_state(explorer);
+
+    let listener = tokio::net::TcpListener::bind(&listen).await?;
+    tracing::info!("Explorer listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}